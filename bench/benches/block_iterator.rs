@@ -0,0 +1,101 @@
+//! Iterate over the blocks of synthetic archives with lots of entries in every format.
+//!
+//! Run with `cargo bench` from this directory.
+
+use std::io;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rawrxd::{compat::Archive, rar14, rar15, rar50, Signature};
+
+const ENTRIES: usize = 100_000;
+
+fn block_iterator(c: &mut Criterion) {
+    let mut group = c.benchmark_group("block_iterator");
+    group.throughput(Throughput::Elements(ENTRIES as u64));
+    group.sample_size(20);
+
+    let archive = rawrxd_bench::rar14(ENTRIES);
+    group.bench_function("rar14", |b| {
+        b.iter_batched(
+            || io::Cursor::new(archive.as_slice()),
+            |reader| {
+                let blocks = rar14::BlockIterator::new(reader, Signature::Rar14.size()).unwrap();
+                blocks.map(Result::unwrap).count()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    let archive = rawrxd_bench::rar15(ENTRIES);
+    group.bench_function("rar15", |b| {
+        b.iter_batched(
+            || io::Cursor::new(archive.as_slice()),
+            |reader| {
+                let blocks = rar15::BlockIterator::new(reader, Signature::Rar15.size()).unwrap();
+                blocks.map(Result::unwrap).count()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    let archive = rawrxd_bench::rar50(ENTRIES);
+    group.bench_function("rar50", |b| {
+        b.iter_batched(
+            || io::Cursor::new(archive.as_slice()),
+            |reader| {
+                let blocks = rar50::BlockIterator::new(reader, Signature::Rar50.size()).unwrap();
+                blocks.map(Result::unwrap).count()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+fn entries(c: &mut Criterion) {
+    let mut group = c.benchmark_group("entries");
+    group.throughput(Throughput::Elements(ENTRIES as u64));
+    group.sample_size(20);
+
+    for (format, archive) in [
+        ("rar14", rawrxd_bench::rar14(ENTRIES)),
+        ("rar15", rawrxd_bench::rar15(ENTRIES)),
+        ("rar50", rawrxd_bench::rar50(ENTRIES)),
+    ] {
+        group.bench_function(format, |b| {
+            b.iter_batched(
+                || Archive::new(io::Cursor::new(archive.as_slice())).unwrap(),
+                |mut archive| archive.entries().unwrap().len(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn entries_compact(c: &mut Criterion) {
+    let mut group = c.benchmark_group("entries_compact");
+    group.throughput(Throughput::Elements(ENTRIES as u64));
+    group.sample_size(20);
+
+    for (format, archive) in [
+        ("rar14", rawrxd_bench::rar14(ENTRIES)),
+        ("rar15", rawrxd_bench::rar15(ENTRIES)),
+        ("rar50", rawrxd_bench::rar50(ENTRIES)),
+    ] {
+        group.bench_function(format, |b| {
+            b.iter_batched(
+                || Archive::new(io::Cursor::new(archive.as_slice())).unwrap(),
+                |mut archive| archive.entries_compact().unwrap().len(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, block_iterator, entries, entries_compact);
+criterion_main!(benches);
@@ -0,0 +1,140 @@
+//! Synthetic archives of every format with lots of stored entries, to benchmark parsing the
+//! headers.
+//!
+//! The entries are 16 bytes long, so the archives are mostly headers.
+
+use rawrxd::{hash::Crc32, Signature};
+
+/// Data of each entry.
+const DATA: &[u8; 16] = b"0123456789abcdef";
+
+/// Name of the entry `i`, nested in a few directories like in real archives.
+fn name(i: usize) -> String {
+    format!("dir{:02}/subdir{:03}/file{i:06}.txt", i % 37, i % 101)
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(bytes);
+    crc.finalize()
+}
+
+/// RAR14 archive with `entries` stored files.
+pub fn rar14(entries: usize) -> Vec<u8> {
+    let mut archive = Signature::RAR14.to_vec();
+    // The size of the main header includes the signature.
+    archive.extend_from_slice(&7u16.to_le_bytes());
+    archive.push(0);
+
+    for i in 0..entries {
+        let name = name(i);
+        archive.extend_from_slice(&(DATA.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&(DATA.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&(crc32(DATA) as u16).to_le_bytes());
+        archive.extend_from_slice(&(21 + name.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&0x5a21_284au32.to_le_bytes());
+        // Attributes, flags, version 1.3, name size and stored method.
+        archive.extend_from_slice(&[0x20, 0, 2, name.len() as u8, 0]);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(DATA);
+    }
+
+    archive
+}
+
+/// RAR15 header of `header_type` with the CRC16 of its fields.
+fn rar15_header(header_type: u8, flags: u16, fields: &[u8]) -> Vec<u8> {
+    let mut header = vec![header_type];
+    header.extend_from_slice(&flags.to_le_bytes());
+    header.extend_from_slice(&(7 + fields.len() as u16).to_le_bytes());
+    header.extend_from_slice(fields);
+
+    let mut block = (crc32(&header) as u16).to_le_bytes().to_vec();
+    block.extend_from_slice(&header);
+    block
+}
+
+/// RAR15 archive with `entries` stored files.
+pub fn rar15(entries: usize) -> Vec<u8> {
+    let mut archive = Signature::RAR15.to_vec();
+    archive.extend_from_slice(&rar15_header(0x73, 0, &[0; 6]));
+
+    for i in 0..entries {
+        let name = name(i);
+        let mut fields = vec![];
+        fields.extend_from_slice(&(DATA.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(DATA.len() as u32).to_le_bytes());
+        // Windows.
+        fields.push(2);
+        fields.extend_from_slice(&crc32(DATA).to_le_bytes());
+        fields.extend_from_slice(&0x5a21_284au32.to_le_bytes());
+        // Version 2.9 and stored method.
+        fields.extend_from_slice(&[29, 0x30]);
+        fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        fields.extend_from_slice(&0x20u32.to_le_bytes());
+        fields.extend_from_slice(name.as_bytes());
+
+        // The file has a data area.
+        archive.extend_from_slice(&rar15_header(0x74, 0x8000, &fields));
+        archive.extend_from_slice(DATA);
+    }
+
+    archive.extend_from_slice(&rar15_header(0x7b, 0x4000, &[]));
+    archive
+}
+
+fn vint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// RAR50 header of `header_type` with the CRC32 of its fields, followed by a data area of
+/// `data_size` bytes if there is one.
+fn rar50_header(header_type: u64, data_size: Option<u64>, fields: &[u8]) -> Vec<u8> {
+    let mut header = vec![];
+    vint(header_type, &mut header);
+    vint(if data_size.is_some() { 0x02 } else { 0 }, &mut header);
+    if let Some(data_size) = data_size {
+        vint(data_size, &mut header);
+    }
+    header.extend_from_slice(fields);
+
+    let mut sized = vec![];
+    vint(header.len() as u64, &mut sized);
+    sized.extend_from_slice(&header);
+
+    let mut block = crc32(&sized).to_le_bytes().to_vec();
+    block.extend_from_slice(&sized);
+    block
+}
+
+/// RAR50 archive with `entries` stored files.
+pub fn rar50(entries: usize) -> Vec<u8> {
+    let mut archive = Signature::RAR50.to_vec();
+    archive.extend_from_slice(&rar50_header(1, None, &[0]));
+
+    for i in 0..entries {
+        let name = name(i);
+        let mut fields = vec![];
+        // Modification time and CRC32 present.
+        vint(0x06, &mut fields);
+        vint(DATA.len() as u64, &mut fields);
+        vint(0o644, &mut fields);
+        fields.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+        fields.extend_from_slice(&crc32(DATA).to_le_bytes());
+        // Stored, created on Unix.
+        vint(0, &mut fields);
+        vint(1, &mut fields);
+        vint(name.len() as u64, &mut fields);
+        fields.extend_from_slice(name.as_bytes());
+
+        archive.extend_from_slice(&rar50_header(2, Some(DATA.len() as u64), &fields));
+        archive.extend_from_slice(DATA);
+    }
+
+    archive.extend_from_slice(&rar50_header(5, None, &[0]));
+    archive
+}
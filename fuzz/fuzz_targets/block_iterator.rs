@@ -0,0 +1,72 @@
+//! Parse the blocks of arbitrary bytes as an archive of every format, checking that
+//! malformed archives return errors instead of panicking.
+//!
+//! Run with `cargo +nightly fuzz run block_iterator -- tests/fixtures/*` from the root of
+//! the repository to seed the corpus with the fixtures.
+
+#![no_main]
+
+use std::io;
+
+use libfuzzer_sys::fuzz_target;
+use rawrxd::{compat::Archive, parser::Event, rar14, rar15, rar50, RarResult, Signature};
+
+/// Maximum number of blocks read from each archive, since the headers of forged archives
+/// may all be tiny.
+const MAX_BLOCKS: usize = 1000;
+
+fn exhaust<B>(blocks: impl Iterator<Item = RarResult<B>>) {
+    for block in blocks.take(MAX_BLOCKS) {
+        if block.is_err() {
+            break;
+        }
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let reader = || io::Cursor::new(data);
+
+    if let Ok(blocks) = rar14::BlockIterator::new(reader(), Signature::Rar14.size()) {
+        exhaust(blocks);
+    }
+
+    let options = rar15::ParseOptions {
+        cross_check: true,
+        ..Default::default()
+    };
+    if let Ok(blocks) =
+        rar15::BlockIterator::with_options(reader(), Signature::Rar15.size(), options)
+    {
+        exhaust(blocks);
+    }
+
+    let options = rar50::ParseOptions {
+        cross_check: true,
+        quick_open: true,
+        ..Default::default()
+    };
+    if let Ok(blocks) =
+        rar50::BlockIterator::with_options(reader(), Signature::Rar50.size(), options)
+    {
+        exhaust(blocks);
+    }
+
+    // Feed the parser in small chunks, so that headers are split across several calls.
+    let mut parser = rar50::Parser::new(0);
+    for chunk in data.chunks(7) {
+        if parser
+            .feed(chunk)
+            .iter()
+            .any(|event| !matches!(event, Event::Block(_)))
+        {
+            break;
+        }
+    }
+    parser.finish();
+
+    if let Ok(mut archive) = Archive::new(reader()) {
+        if let Ok(blocks) = archive.blocks() {
+            exhaust(blocks);
+        }
+    }
+});
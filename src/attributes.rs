@@ -0,0 +1,299 @@
+//! File attributes of the entries, which depend on the OS used to add them to the archive.
+
+flags! {
+    /// MS-DOS file attributes.
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/fileio/file-attribute-constants>
+    pub struct DosFileAttributes(u8) {
+        /// File or directory is read-only.
+        pub is_read_only = 0x01;
+
+        /// File or directory is hidden.
+        pub is_hidden = 0x02;
+
+        /// File or directory that is used by the OS.
+        pub is_system_file = 0x04;
+
+        /// File stores the name of the disk.
+        pub is_volume_label = 0x08;
+
+        /// Entry is a directory.
+        pub is_directory = 0x10;
+
+        /// Entry is an archive file or directory.
+        pub is_archive = 0x20;
+    }
+}
+
+flags! {
+    /// Windows file attributes, which extend the [`DosFileAttributes`].
+    ///
+    /// <https://learn.microsoft.com/en-us/windows/win32/fileio/file-attribute-constants>
+    pub struct WindowsFileAttributes(u32) {
+        /// File or directory is read-only.
+        pub is_read_only = 0x0000_0001;
+
+        /// File or directory is hidden.
+        pub is_hidden = 0x0000_0002;
+
+        /// File or directory that is used by the OS.
+        pub is_system_file = 0x0000_0004;
+
+        /// Entry is a directory.
+        pub is_directory = 0x0000_0010;
+
+        /// Entry is an archive file or directory.
+        pub is_archive = 0x0000_0020;
+
+        /// Reserved for use by the system.
+        pub is_device = 0x0000_0040;
+
+        /// File doesn't have any other attributes set.
+        pub is_normal = 0x0000_0080;
+
+        /// File is used for temporary storage.
+        pub is_temporary = 0x0000_0100;
+
+        /// File is a sparse file.
+        pub is_sparse_file = 0x0000_0200;
+
+        /// File or directory has an associated reparse point, or is a symbolic link.
+        pub is_reparse_point = 0x0000_0400;
+
+        /// File or directory is compressed by the filesystem.
+        pub is_compressed = 0x0000_0800;
+
+        /// Data of the file is not available immediately.
+        pub is_offline = 0x0000_1000;
+
+        /// File or directory is not indexed by the content indexing service.
+        pub is_not_content_indexed = 0x0000_2000;
+
+        /// File or directory is encrypted by the filesystem.
+        pub is_encrypted = 0x0000_4000;
+
+        /// File or directory is configured with integrity, on ReFS.
+        pub is_integrity_stream = 0x0000_8000;
+
+        /// Reserved for use by the system.
+        pub is_virtual = 0x0001_0000;
+
+        /// File or directory is excluded from the data integrity scan, on ReFS.
+        pub is_no_scrub_data = 0x0002_0000;
+
+        /// File or directory has no physical representation on the local system.
+        pub is_recall_on_open = 0x0004_0000;
+
+        /// File or directory should be kept fully present locally.
+        pub is_pinned = 0x0008_0000;
+
+        /// File or directory should not be kept fully present locally.
+        pub is_unpinned = 0x0010_0000;
+
+        /// File or directory is not fully present locally.
+        pub is_recall_on_data_access = 0x0040_0000;
+
+        /// File is read sequentially from start to end, on ReFS.
+        pub is_strictly_sequential = 0x2000_0000;
+    }
+}
+
+int_enum! {
+    /// Type of a file on Unix, stored in the `S_IFMT` bits of its mode.
+    pub enum UnixFileType : u32 {
+        /// Named pipe.
+        Fifo = 0o010000,
+
+        /// Character device.
+        CharacterDevice = 0o020000,
+
+        Directory = 0o040000,
+
+        /// Block device.
+        BlockDevice = 0o060000,
+
+        /// Regular file.
+        Regular = 0o100000,
+
+        SymbolicLink = 0o120000,
+
+        Socket = 0o140000,
+    }
+}
+
+flags! {
+    /// Unix file mode, containing the type of the file and its permission bits.
+    pub struct UnixPermissions(u32) {
+        /// Execute the file with the privileges of its owner.
+        pub is_setuid = 0o4000;
+
+        /// Execute the file with the privileges of its group, or make new files in the
+        /// directory inherit its group.
+        pub is_setgid = 0o2000;
+
+        /// Only let the owners of the files in the directory rename or delete them.
+        pub is_sticky = 0o1000;
+
+        pub owner_read = 0o400;
+        pub owner_write = 0o200;
+        pub owner_execute = 0o100;
+        pub group_read = 0o040;
+        pub group_write = 0o020;
+        pub group_execute = 0o010;
+        pub others_read = 0o004;
+        pub others_write = 0o002;
+        pub others_execute = 0o001;
+    }
+}
+
+impl UnixPermissions {
+    /// Mask of the `S_IFMT` bits of the mode.
+    const FILE_TYPE_MASK: u32 = 0o170000;
+
+    /// Type of the file, or `None` if the mode doesn't store it.
+    pub fn file_type(&self) -> Option<UnixFileType> {
+        match self.0 & Self::FILE_TYPE_MASK {
+            0 => None,
+            file_type => Some(file_type.into()),
+        }
+    }
+
+    /// Permission bits of the mode, including the setuid, setgid and sticky bits.
+    pub fn permissions(&self) -> u32 {
+        self.0 & 0o7777
+    }
+}
+
+/// Formats the mode like `ls -l`, for example `drwxr-xr-x`.
+///
+/// Modes that don't store the type of the file are formatted as regular files.
+impl core::fmt::Display for UnixPermissions {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use core::fmt::Write;
+
+        let file_type = match self.file_type() {
+            Some(UnixFileType::Regular) | None => '-',
+            Some(UnixFileType::Directory) => 'd',
+            Some(UnixFileType::SymbolicLink) => 'l',
+            Some(UnixFileType::Fifo) => 'p',
+            Some(UnixFileType::CharacterDevice) => 'c',
+            Some(UnixFileType::BlockDevice) => 'b',
+            Some(UnixFileType::Socket) => 's',
+            Some(UnixFileType::Unknown(_)) => '?',
+        };
+
+        // The special bit replaces the execute bit of its triplet.
+        let triplet = |read, write, execute, special, letter: char| {
+            [
+                if read { 'r' } else { '-' },
+                if write { 'w' } else { '-' },
+                match (execute, special) {
+                    (true, true) => letter,
+                    (false, true) => letter.to_ascii_uppercase(),
+                    (true, false) => 'x',
+                    (false, false) => '-',
+                },
+            ]
+        };
+
+        f.write_char(file_type)?;
+        let triplets = [
+            triplet(
+                self.owner_read(),
+                self.owner_write(),
+                self.owner_execute(),
+                self.is_setuid(),
+                's',
+            ),
+            triplet(
+                self.group_read(),
+                self.group_write(),
+                self.group_execute(),
+                self.is_setgid(),
+                's',
+            ),
+            triplet(
+                self.others_read(),
+                self.others_write(),
+                self.others_execute(),
+                self.is_sticky(),
+                't',
+            ),
+        ];
+        for c in triplets.iter().flatten() {
+            f.write_char(*c)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// File attributes decoded according to the OS used to add the entry to the archive.
+pub enum FileAttributes {
+    /// Attributes of entries added on MS-DOS or OS/2.
+    Dos(DosFileAttributes),
+
+    /// Mode of entries added on Unix or BeOS.
+    Unix(UnixPermissions),
+
+    /// Attributes of entries added on Windows.
+    Windows(WindowsFileAttributes),
+
+    /// Attributes of entries added on other systems, whose meaning is not known.
+    Unknown(u64),
+}
+
+serialize_enum!(FileAttributes {
+    Dos(value),
+    Unix(value),
+    Windows(value),
+    Unknown(value),
+});
+
+/// Flags and enums defined in this module, listed in [`crate::schema::MODULES`].
+pub(crate) const SCHEMA: crate::schema::ModuleSchema = crate::schema::ModuleSchema {
+    name: "rawrxd",
+    flags: &[
+        DosFileAttributes::SCHEMA,
+        WindowsFileAttributes::SCHEMA,
+        UnixPermissions::SCHEMA,
+    ],
+    enums: &[UnixFileType::SCHEMA],
+};
+
+#[test]
+fn test_unix_permissions() {
+    let mode = UnixPermissions::new(0o104755);
+    assert_eq!(mode.file_type(), Some(UnixFileType::Regular));
+    assert_eq!(mode.permissions(), 0o4755);
+    assert!(mode.is_setuid());
+    assert!(!mode.is_sticky());
+    assert!(mode.group_execute());
+    assert!(!mode.others_write());
+
+    assert_eq!(mode.to_string(), "-rwsr-xr-x");
+    assert_eq!(UnixPermissions::new(0o041777).to_string(), "drwxrwxrwt");
+    assert_eq!(UnixPermissions::new(0o122644).to_string(), "lrw-r-Sr--");
+
+    assert_eq!(UnixPermissions::new(0o755).file_type(), None);
+    assert_eq!(
+        UnixPermissions::new(0o170755).file_type(),
+        Some(UnixFileType::Unknown(0o170000))
+    );
+}
+
+#[test]
+fn test_windows_attributes() {
+    let attributes = WindowsFileAttributes::new(0x0000_0421);
+    assert!(attributes.is_read_only());
+    assert!(attributes.is_archive());
+    assert!(attributes.is_reparse_point());
+    assert!(!attributes.is_directory());
+    assert_eq!(attributes.unknown_bits(), 0);
+
+    assert_eq!(
+        WindowsFileAttributes::new(0x8000_0010).unknown_bits(),
+        0x8000_0000
+    );
+}
@@ -0,0 +1,582 @@
+use std::{fmt, io};
+
+use crate::read::*;
+
+use super::SecurityParts;
+
+/// Control flags of a [`SecurityDescriptor`].
+const DACL_PRESENT: u16 = 0x0004;
+const SACL_PRESENT: u16 = 0x0010;
+const DACL_AUTO_INHERIT_REQ: u16 = 0x0100;
+const SACL_AUTO_INHERIT_REQ: u16 = 0x0200;
+const DACL_AUTO_INHERITED: u16 = 0x0400;
+const SACL_AUTO_INHERITED: u16 = 0x0800;
+const DACL_PROTECTED: u16 = 0x1000;
+const SACL_PROTECTED: u16 = 0x2000;
+const SELF_RELATIVE: u16 = 0x8000;
+
+/// Aliases of well-known SIDs used in SDDL strings.
+const SID_ALIASES: &[(&str, &str)] = &[
+    ("S-1-1-0", "WD"),
+    ("S-1-3-0", "CO"),
+    ("S-1-3-1", "CG"),
+    ("S-1-5-2", "NU"),
+    ("S-1-5-4", "IU"),
+    ("S-1-5-6", "SU"),
+    ("S-1-5-7", "AN"),
+    ("S-1-5-9", "ED"),
+    ("S-1-5-10", "PS"),
+    ("S-1-5-11", "AU"),
+    ("S-1-5-12", "RC"),
+    ("S-1-5-18", "SY"),
+    ("S-1-5-19", "LS"),
+    ("S-1-5-20", "NS"),
+    ("S-1-5-32-544", "BA"),
+    ("S-1-5-32-545", "BU"),
+    ("S-1-5-32-546", "BG"),
+    ("S-1-5-32-547", "PU"),
+    ("S-1-5-32-548", "AO"),
+    ("S-1-5-32-549", "SO"),
+    ("S-1-5-32-550", "PO"),
+    ("S-1-5-32-551", "BO"),
+    ("S-1-5-32-552", "RE"),
+    ("S-1-5-32-555", "RD"),
+    ("S-1-5-32-556", "NO"),
+    ("S-1-16-4096", "LW"),
+    ("S-1-16-8192", "ME"),
+    ("S-1-16-12288", "HI"),
+    ("S-1-16-16384", "SI"),
+];
+
+/// Access masks with an SDDL alias, which are used when the whole mask matches.
+const COMBINED_RIGHTS: &[(u32, &str)] = &[
+    (0x001f_01ff, "FA"),
+    (0x0012_0089, "FR"),
+    (0x0012_0116, "FW"),
+    (0x0012_00a0, "FX"),
+    (0x000f_003f, "KA"),
+    (0x0002_0019, "KR"),
+    (0x0002_0006, "KW"),
+];
+
+/// Single access rights with an SDDL alias, in the order Windows writes them.
+const RIGHTS: &[(u32, &str)] = &[
+    (0x1000_0000, "GA"),
+    (0x8000_0000, "GR"),
+    (0x4000_0000, "GW"),
+    (0x2000_0000, "GX"),
+    (0x0000_0001, "CC"),
+    (0x0000_0002, "DC"),
+    (0x0000_0004, "LC"),
+    (0x0000_0008, "SW"),
+    (0x0000_0010, "RP"),
+    (0x0000_0020, "WP"),
+    (0x0000_0040, "DT"),
+    (0x0000_0080, "LO"),
+    (0x0000_0100, "CR"),
+    (0x0001_0000, "SD"),
+    (0x0002_0000, "RC"),
+    (0x0004_0000, "WD"),
+    (0x0008_0000, "WO"),
+];
+
+/// Rights of mandatory label ACEs.
+const LABEL_RIGHTS: &[(u32, &str)] = &[(0x1, "NW"), (0x2, "NR"), (0x4, "NX")];
+
+/// ACE flags, in the order Windows writes them.
+const ACE_FLAGS: &[(u8, &str)] = &[
+    (0x01, "OI"),
+    (0x02, "CI"),
+    (0x04, "NP"),
+    (0x08, "IO"),
+    (0x10, "ID"),
+    (0x40, "SA"),
+    (0x80, "FA"),
+];
+
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Windows security identifier.
+pub struct Sid {
+    pub revision: u8,
+
+    /// 48-bit identifier authority.
+    pub authority: u64,
+
+    pub sub_authorities: Vec<u32>,
+}
+
+impl Sid {
+    fn read(data: &[u8]) -> io::Result<Self> {
+        let reader = &mut io::Cursor::new(data);
+
+        let revision = read_u8(reader)?;
+        let count = read_u8(reader)?;
+        let authority: [u8; 6] = read_const_bytes(reader)?;
+        let authority = authority.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+
+        let sub_authorities = (0..count)
+            .map(|_| read_u32(reader))
+            .collect::<io::Result<_>>()?;
+
+        Ok(Sid {
+            revision,
+            authority,
+            sub_authorities,
+        })
+    }
+
+    /// SDDL representation of the SID, which is an alias for well-known SIDs.
+    pub fn to_sddl(&self) -> String {
+        let sid = self.to_string();
+        match SID_ALIASES.iter().find(|(s, _)| *s == sid) {
+            Some((_, alias)) => alias.to_string(),
+            None => sid,
+        }
+    }
+}
+
+impl fmt::Display for Sid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "S-{}-", self.revision)?;
+
+        if self.authority >> 32 == 0 {
+            write!(f, "{}", self.authority)?;
+        } else {
+            write!(f, "0x{:012X}", self.authority)?;
+        }
+
+        for sub_authority in &self.sub_authorities {
+            write!(f, "-{sub_authority}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Type of an [`Ace`].
+pub enum AceType {
+    AccessAllowed,
+    AccessDenied,
+    SystemAudit,
+    SystemAlarm,
+    AccessAllowedObject,
+    AccessDeniedObject,
+    SystemAuditObject,
+    SystemMandatoryLabel,
+}
+
+impl AceType {
+    fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0x00 => Self::AccessAllowed,
+            0x01 => Self::AccessDenied,
+            0x02 => Self::SystemAudit,
+            0x03 => Self::SystemAlarm,
+            0x05 => Self::AccessAllowedObject,
+            0x06 => Self::AccessDeniedObject,
+            0x07 => Self::SystemAuditObject,
+            0x11 => Self::SystemMandatoryLabel,
+            _ => return None,
+        })
+    }
+
+    fn is_object(self) -> bool {
+        matches!(
+            self,
+            Self::AccessAllowedObject | Self::AccessDeniedObject | Self::SystemAuditObject
+        )
+    }
+
+    fn sddl(self) -> &'static str {
+        match self {
+            Self::AccessAllowed => "A",
+            Self::AccessDenied => "D",
+            Self::SystemAudit => "AU",
+            Self::SystemAlarm => "AL",
+            Self::AccessAllowedObject => "OA",
+            Self::AccessDeniedObject => "OD",
+            Self::SystemAuditObject => "OU",
+            Self::SystemMandatoryLabel => "ML",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Access control entry.
+pub struct Ace {
+    pub ace_type: AceType,
+    pub flags: u8,
+    pub mask: u32,
+
+    /// GUID of the object type, for object ACEs.
+    pub object_type: Option<[u8; 16]>,
+
+    /// GUID of the object type that can inherit the ACE, for object ACEs.
+    pub inherited_object_type: Option<[u8; 16]>,
+
+    pub sid: Sid,
+}
+
+impl Ace {
+    const OBJECT_TYPE_PRESENT: u32 = 0x1;
+    const INHERITED_OBJECT_TYPE_PRESENT: u32 = 0x2;
+
+    /// Read an ACE and return it with its size.
+    fn read(data: &[u8]) -> io::Result<(Self, usize)> {
+        let reader = &mut io::Cursor::new(data);
+
+        let ace_type = read_u8(reader)?;
+        let flags = read_u8(reader)?;
+        let size = read_u16(reader)? as usize;
+        if size < 8 || size > data.len() {
+            return Err(invalid_data("ACE size exceeds the ACL"));
+        }
+
+        let ace_type = AceType::from_u8(ace_type)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "unsupported ACE type"))?;
+        let mask = read_u32(reader)?;
+
+        let mut object_type = None;
+        let mut inherited_object_type = None;
+        if ace_type.is_object() {
+            let object_flags = read_u32(reader)?;
+            if object_flags & Self::OBJECT_TYPE_PRESENT != 0 {
+                object_type = Some(read_const_bytes(reader)?);
+            }
+            if object_flags & Self::INHERITED_OBJECT_TYPE_PRESENT != 0 {
+                inherited_object_type = Some(read_const_bytes(reader)?);
+            }
+        }
+
+        let sid_start = reader.position() as usize;
+        if sid_start > size {
+            return Err(invalid_data("ACE size is too small"));
+        }
+        let sid = Sid::read(&data[sid_start..size])?;
+
+        let ace = Ace {
+            ace_type,
+            flags,
+            mask,
+            object_type,
+            inherited_object_type,
+            sid,
+        };
+
+        Ok((ace, size))
+    }
+
+    /// SDDL representation of the ACE.
+    pub fn to_sddl(&self) -> String {
+        let flags: String = ACE_FLAGS
+            .iter()
+            .filter(|(flag, _)| self.flags & flag != 0)
+            .map(|(_, alias)| *alias)
+            .collect();
+
+        let rights = match self.ace_type {
+            AceType::SystemMandatoryLabel => rights_to_sddl(self.mask, &[], LABEL_RIGHTS),
+            _ => rights_to_sddl(self.mask, COMBINED_RIGHTS, RIGHTS),
+        };
+
+        format!(
+            "({};{flags};{rights};{};{};{})",
+            self.ace_type.sddl(),
+            self.object_type
+                .as_ref()
+                .map(format_guid)
+                .unwrap_or_default(),
+            self.inherited_object_type
+                .as_ref()
+                .map(format_guid)
+                .unwrap_or_default(),
+            self.sid.to_sddl(),
+        )
+    }
+}
+
+fn rights_to_sddl(mask: u32, combined: &[(u32, &str)], single: &[(u32, &str)]) -> String {
+    if let Some((_, alias)) = combined.iter().find(|(rights, _)| *rights == mask) {
+        return alias.to_string();
+    }
+
+    let known = single.iter().fold(0, |acc, (right, _)| acc | right);
+    if mask == 0 || mask & !known != 0 {
+        return format!("0x{mask:x}");
+    }
+
+    single
+        .iter()
+        .filter(|(right, _)| mask & right != 0)
+        .map(|(_, alias)| *alias)
+        .collect()
+}
+
+fn format_guid(guid: &[u8; 16]) -> String {
+    let data1 = u32::from_le_bytes(guid[0..4].try_into().unwrap());
+    let data2 = u16::from_le_bytes(guid[4..6].try_into().unwrap());
+    let data3 = u16::from_le_bytes(guid[6..8].try_into().unwrap());
+    let data4: String = guid[8..].iter().map(|b| format!("{b:02x}")).collect();
+    format!(
+        "{data1:08x}-{data2:04x}-{data3:04x}-{}-{}",
+        &data4[..4],
+        &data4[4..]
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Access control list.
+pub struct Acl {
+    pub revision: u8,
+    pub aces: Vec<Ace>,
+}
+
+impl Acl {
+    fn read(data: &[u8]) -> io::Result<Self> {
+        let reader = &mut io::Cursor::new(data);
+
+        let revision = read_u8(reader)?;
+        let _sbz1 = read_u8(reader)?;
+        let size = read_u16(reader)? as usize;
+        let count = read_u16(reader)?;
+        let _sbz2 = read_u16(reader)?;
+
+        if size > data.len() {
+            return Err(invalid_data("ACL size exceeds the security descriptor"));
+        }
+
+        let mut aces = vec![];
+        let mut offset = 8;
+        for _ in 0..count {
+            let data = data
+                .get(offset..size)
+                .ok_or_else(|| invalid_data("ACE exceeds the ACL"))?;
+            let (ace, ace_size) = Ace::read(data)?;
+            aces.push(ace);
+            offset += ace_size;
+        }
+
+        Ok(Acl { revision, aces })
+    }
+
+    fn to_sddl(&self) -> String {
+        self.aces.iter().map(Ace::to_sddl).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Windows security descriptor, which is how RAR stores the NTFS permissions of an entry.
+///
+/// This is the self-relative `SECURITY_DESCRIPTOR` found in the unpacked data of the
+/// NTFS ACL service blocks of RAR15 and RAR50 archives, which can be converted to an
+/// [SDDL](https://learn.microsoft.com/en-us/windows/win32/secauthz/security-descriptor-string-format)
+/// string to store it without depending on Windows APIs.
+pub struct SecurityDescriptor {
+    pub revision: u8,
+
+    /// `SECURITY_DESCRIPTOR_CONTROL` flags.
+    pub control: u16,
+
+    pub owner: Option<Sid>,
+    pub group: Option<Sid>,
+
+    /// Discretionary ACL, or `None` if there is none or it's a NULL ACL.
+    pub dacl: Option<Acl>,
+
+    /// System ACL, or `None` if there is none or it's a NULL ACL.
+    pub sacl: Option<Acl>,
+}
+
+impl SecurityDescriptor {
+    /// Parse a self-relative security descriptor.
+    pub fn parse(data: &[u8]) -> io::Result<Self> {
+        let reader = &mut io::Cursor::new(data);
+
+        let revision = read_u8(reader)?;
+        let _sbz1 = read_u8(reader)?;
+        let control = read_u16(reader)?;
+        let owner_offset = read_u32(reader)? as usize;
+        let group_offset = read_u32(reader)? as usize;
+        let sacl_offset = read_u32(reader)? as usize;
+        let dacl_offset = read_u32(reader)? as usize;
+
+        if control & SELF_RELATIVE == 0 {
+            return Err(invalid_data("security descriptor is not self-relative"));
+        }
+
+        // An offset of 0 means that the part is not present.
+        let part = |offset: usize| match offset {
+            0 => Ok(None),
+            offset => data
+                .get(offset..)
+                .map(Some)
+                .ok_or_else(|| invalid_data("offset exceeds the security descriptor")),
+        };
+
+        let owner = part(owner_offset)?.map(Sid::read).transpose()?;
+        let group = part(group_offset)?.map(Sid::read).transpose()?;
+        let sacl = match control & SACL_PRESENT {
+            0 => None,
+            _ => part(sacl_offset)?.map(Acl::read).transpose()?,
+        };
+        let dacl = match control & DACL_PRESENT {
+            0 => None,
+            _ => part(dacl_offset)?.map(Acl::read).transpose()?,
+        };
+
+        Ok(SecurityDescriptor {
+            revision,
+            control,
+            owner,
+            group,
+            dacl,
+            sacl,
+        })
+    }
+
+    /// Convert the security descriptor to an SDDL string.
+    pub fn to_sddl(&self) -> String {
+        let mut sddl = String::new();
+
+        if let Some(owner) = &self.owner {
+            sddl += &format!("O:{}", owner.to_sddl());
+        }
+
+        if let Some(group) = &self.group {
+            sddl += &format!("G:{}", group.to_sddl());
+        }
+
+        if self.control & DACL_PRESENT != 0 {
+            let flags = self.acl_flags(DACL_PROTECTED, DACL_AUTO_INHERIT_REQ, DACL_AUTO_INHERITED);
+            sddl += &format!("D:{flags}{}", acl_to_sddl(&self.dacl));
+        }
+
+        if self.control & SACL_PRESENT != 0 {
+            let flags = self.acl_flags(SACL_PROTECTED, SACL_AUTO_INHERIT_REQ, SACL_AUTO_INHERITED);
+            sddl += &format!("S:{flags}{}", acl_to_sddl(&self.sacl));
+        }
+
+        sddl
+    }
+
+    /// Parts present in the security descriptor.
+    ///
+    /// NULL ACLs count as present, since applying them changes the permissions of a file.
+    pub fn parts(&self) -> SecurityParts {
+        SecurityParts {
+            owner: self.owner.is_some(),
+            group: self.group.is_some(),
+            dacl: self.control & DACL_PRESENT != 0,
+            sacl: self.control & SACL_PRESENT != 0,
+        }
+    }
+
+    fn acl_flags(&self, protected: u16, auto_inherit_req: u16, auto_inherited: u16) -> String {
+        [
+            (protected, "P"),
+            (auto_inherit_req, "AR"),
+            (auto_inherited, "AI"),
+        ]
+        .iter()
+        .filter(|(flag, _)| self.control & flag != 0)
+        .map(|(_, alias)| *alias)
+        .collect()
+    }
+}
+
+fn acl_to_sddl(acl: &Option<Acl>) -> String {
+    match acl {
+        Some(acl) => acl.to_sddl(),
+        None => "NO_ACCESS_CONTROL".to_string(),
+    }
+}
+
+#[test]
+fn test_security_descriptor_to_sddl() {
+    fn sid(authority: u8, sub_authorities: &[u32]) -> Vec<u8> {
+        let mut sid = vec![1, sub_authorities.len() as u8, 0, 0, 0, 0, 0, authority];
+        for sub_authority in sub_authorities {
+            sid.extend_from_slice(&sub_authority.to_le_bytes());
+        }
+        sid
+    }
+
+    fn ace(ace_type: u8, flags: u8, mask: u32, sid: &[u8]) -> Vec<u8> {
+        let mut ace = vec![ace_type, flags];
+        ace.extend_from_slice(&(8 + sid.len() as u16).to_le_bytes());
+        ace.extend_from_slice(&mask.to_le_bytes());
+        ace.extend_from_slice(sid);
+        ace
+    }
+
+    let user = sid(5, &[21, 1_004_336_348, 1_177_238_915, 682_003_330, 1001]);
+    let aces = [
+        ace(0x00, 0x13, 0x001f_01ff, &sid(5, &[18])),
+        ace(0x00, 0x13, 0x001f_01ff, &sid(5, &[32, 544])),
+        ace(0x00, 0x10, 0x0012_00a9, &user),
+        ace(0x01, 0x00, 0x0001_0000, &sid(1, &[0])),
+    ]
+    .concat();
+
+    let mut dacl = vec![2, 0];
+    dacl.extend_from_slice(&(8 + aces.len() as u16).to_le_bytes());
+    dacl.extend_from_slice(&4u16.to_le_bytes());
+    dacl.extend_from_slice(&[0, 0]);
+    dacl.extend_from_slice(&aces);
+
+    let owner = sid(5, &[32, 544]);
+    let group = sid(5, &[18]);
+
+    // Owner, group and DACL follow the 20 bytes of the header.
+    let owner_offset = 20u32;
+    let group_offset = owner_offset + owner.len() as u32;
+    let dacl_offset = group_offset + group.len() as u32;
+
+    let control = SELF_RELATIVE | DACL_PRESENT | DACL_PROTECTED | DACL_AUTO_INHERITED;
+    let mut data = vec![1, 0];
+    data.extend_from_slice(&control.to_le_bytes());
+    for offset in [owner_offset, group_offset, 0, dacl_offset] {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(&owner);
+    data.extend_from_slice(&group);
+    data.extend_from_slice(&dacl);
+
+    let descriptor = SecurityDescriptor::parse(&data).unwrap();
+    assert_eq!(
+        descriptor.to_sddl(),
+        "O:BAG:SYD:PAI(A;OICIID;FA;;;SY)(A;OICIID;FA;;;BA)\
+         (A;ID;0x1200a9;;;S-1-5-21-1004336348-1177238915-682003330-1001)(D;;SD;;;WD)"
+    );
+    assert_eq!(
+        descriptor.parts(),
+        SecurityParts {
+            owner: true,
+            group: true,
+            dacl: true,
+            sacl: false,
+        }
+    );
+
+    // A NULL DACL grants everyone full access.
+    let mut data = data[..20].to_vec();
+    data[16..20].copy_from_slice(&[0; 4]);
+    data[4..12].copy_from_slice(&[0; 8]);
+    let descriptor = SecurityDescriptor::parse(&data).unwrap();
+    assert_eq!(descriptor.to_sddl(), "D:PAINO_ACCESS_CONTROL");
+    assert_eq!(
+        descriptor.parts(),
+        SecurityParts {
+            dacl: true,
+            ..SecurityParts::default()
+        }
+    );
+
+    // Truncated descriptors are rejected.
+    assert!(SecurityDescriptor::parse(&data[..10]).is_err());
+}
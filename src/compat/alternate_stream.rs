@@ -0,0 +1,264 @@
+use std::io::{self, Read, Seek};
+
+use crate::{error::RarResult, hash::Crc32, rar15, rar50, size::BlockSize};
+
+use super::{Archive, Block, FileEntry};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// NTFS alternate data stream of the file entry preceding it in the archive, independent
+/// of how each format stores it.
+///
+/// RAR50 and RAR15 (RAR 3.x and later) archives store streams in `STM` service blocks, and
+/// RAR 2.x archives in stream sub blocks.
+pub struct AlternateDataStream {
+    /// Offset of the block containing the stream.
+    pub offset: u64,
+
+    /// Size of the header of the block containing the stream.
+    pub header_size: u64,
+
+    /// Name of the stream, without the leading `:` and the `:$DATA` suffix.
+    ///
+    /// Names are returned as UTF-8 if they are valid, and as the raw bytes stored in the
+    /// archive otherwise.
+    pub name: Result<String, Vec<u8>>,
+
+    /// Size of the data area of the block containing the stream.
+    pub packed_size: u64,
+
+    /// Size of the stream after decompression, if known.
+    pub unpacked_size: Option<u64>,
+
+    /// CRC32 of the unpacked data.
+    pub crc32: Option<u32>,
+
+    /// Stream data is encrypted.
+    pub is_encrypted: bool,
+
+    /// Stream data is stored without compression.
+    pub is_stored: bool,
+
+    /// Version of the algorithm used to compress the stream data, for RAR 2.x sub blocks
+    /// whose data can be decompressed by [`Self::read_data`].
+    pub unpack_version: Option<u8>,
+}
+
+impl AlternateDataStream {
+    /// Extract the alternate data stream stored in `block`.
+    ///
+    /// Returns `None` if the block doesn't contain a stream.
+    pub fn from_block(block: &Block) -> Option<Self> {
+        match block {
+            Block::Rar15(block) => match &block.kind {
+                rar15::BlockKind::Service(
+                    service @ rar15::ServiceBlock {
+                        kind: rar15::ServiceBlockKind::NtfsAlternateDataStream(stream),
+                        ..
+                    },
+                ) => Some(Self {
+                    name: decode_utf16_name(&stream.stream_name),
+                    unpacked_size: Some(service.unpacked_data_size),
+                    crc32: Some(service.data_crc32),
+                    is_encrypted: service.salt.is_some(),
+                    is_stored: service.method == rar15::FileBlock::METHOD_STORE,
+                    ..Self::new(block)
+                }),
+
+                rar15::BlockKind::Sub(rar15::SubBlock {
+                    kind: rar15::SubBlockKind::NtfsStream(stream),
+                    ..
+                }) => Some(Self {
+                    name: decode_utf8_name(&stream.stream_name),
+                    unpacked_size: Some(stream.unpacked_data_size as u64),
+                    crc32: Some(stream.stream_crc32),
+                    is_encrypted: false,
+                    is_stored: stream.method == rar15::FileBlock::METHOD_STORE,
+                    unpack_version: Some(stream.unpack_version),
+                    ..Self::new(block)
+                }),
+
+                _ => None,
+            },
+
+            Block::Rar50(block) => match &block.kind {
+                rar50::BlockKind::Service(
+                    service @ rar50::ServiceBlock {
+                        kind:
+                            rar50::ServiceBlockKind::NtfsAlternateDataStream(
+                                rar50::NtfsStreamServiceBlock {
+                                    stream_name: Some(name),
+                                },
+                            ),
+                        ..
+                    },
+                ) => Some(Self {
+                    name: decode_utf8_name(name),
+                    unpacked_size: service.unpacked_size,
+                    crc32: service.data_crc32,
+                    is_encrypted: service.encryption.is_some(),
+                    is_stored: service.compression_info.method()
+                        == rar50::CompressionMethod::NoCompression,
+                    ..Self::new(&**block)
+                }),
+
+                _ => None,
+            },
+
+            Block::Rar14(_) => None,
+        }
+    }
+
+    fn new(block: &impl BlockSize) -> Self {
+        Self {
+            offset: block.offset(),
+            header_size: block.header_size(),
+            name: Ok(String::new()),
+            packed_size: block.data_size(),
+            unpacked_size: None,
+            crc32: None,
+            is_encrypted: false,
+            is_stored: false,
+            unpack_version: None,
+        }
+    }
+
+    /// Name of the stream, replacing invalid UTF-8 sequences.
+    pub fn name_lossy(&self) -> std::borrow::Cow<'_, str> {
+        match &self.name {
+            Ok(name) => name.into(),
+            Err(name) => String::from_utf8_lossy(name),
+        }
+    }
+
+    /// Read the data of a stream which is stored without compression.
+    ///
+    /// Seeks `reader` to the start of the data area and returns a reader that stops at its
+    /// end. The data is not checked against [`Self::crc32`].
+    ///
+    /// Returns an [`io::ErrorKind::Unsupported`] error if the stream is compressed or
+    /// encrypted.
+    pub fn data_reader<'a, R: Read + Seek>(
+        &self,
+        reader: &'a mut R,
+    ) -> io::Result<io::Take<&'a mut R>> {
+        let unsupported = |message| io::Error::new(io::ErrorKind::Unsupported, message);
+
+        if !self.is_stored {
+            return Err(unsupported("stream is compressed"));
+        }
+        if self.is_encrypted {
+            return Err(unsupported("encrypted streams are not supported"));
+        }
+
+        reader.seek(io::SeekFrom::Start(self.offset + self.header_size))?;
+        Ok(reader.take(self.packed_size))
+    }
+
+    /// Read the whole data of a stream which is stored without compression or stored in a
+    /// RAR 2.x sub block, and check it against [`Self::crc32`].
+    ///
+    /// Returns an [`io::ErrorKind::InvalidData`] error if the CRC32 doesn't match, and an
+    /// [`io::ErrorKind::Unsupported`] error if the stream is encrypted or compressed in a
+    /// service block.
+    pub fn read_data<R: Read + Seek>(&self, reader: &mut R) -> io::Result<Vec<u8>> {
+        let mut data = vec![];
+
+        if let (false, Some(unpack_version)) = (self.is_stored, self.unpack_version) {
+            reader.seek(io::SeekFrom::Start(self.offset + self.header_size))?;
+            rar15::Decoder::for_compressed_data(
+                reader.take(self.packed_size),
+                unpack_version,
+                self.unpacked_size.unwrap_or(0),
+                self.crc32.unwrap_or(0),
+            )?
+            .read_to_end(&mut data)?;
+            return Ok(data);
+        }
+
+        self.data_reader(reader)?.read_to_end(&mut data)?;
+
+        if let Some(expected) = self.crc32 {
+            let mut crc = Crc32::new();
+            crc.update(&data);
+            if crc.finalize() != expected {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "CRC32 of the stream doesn't match",
+                ));
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// File entry and the alternate data streams stored after it, returned by
+/// [`Archive::alternate_streams`].
+pub struct EntryStreams {
+    pub entry: FileEntry,
+    pub streams: Vec<AlternateDataStream>,
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Alternate data streams of the entries of the archive, grouped with the file entry
+    /// that precedes them.
+    ///
+    /// Entries without streams are not listed.
+    pub fn alternate_streams(&mut self) -> RarResult<Vec<EntryStreams>> {
+        let mut entries: Vec<EntryStreams> = vec![];
+
+        for block in self.blocks()? {
+            let block = block?;
+            if let Some(entry) = FileEntry::from_block(&block) {
+                entries.push(EntryStreams {
+                    entry,
+                    streams: vec![],
+                });
+            } else if let Some(stream) = AlternateDataStream::from_block(&block) {
+                if let Some(entry) = entries.last_mut() {
+                    entry.streams.push(stream);
+                }
+            }
+        }
+
+        entries.retain(|entry| !entry.streams.is_empty());
+        Ok(entries)
+    }
+}
+
+/// Remove the `:` prefix and the `:$DATA` suffix from a stream name.
+fn trim_name(name: &str) -> &str {
+    let name = name.strip_prefix(':').unwrap_or(name);
+    name.strip_suffix(":$DATA").unwrap_or(name)
+}
+
+fn decode_utf8_name(name: &[u8]) -> Result<String, Vec<u8>> {
+    match std::str::from_utf8(name) {
+        Ok(name) => Ok(trim_name(name).to_string()),
+        Err(_) => Err(name.strip_prefix(b":").unwrap_or(name).to_vec()),
+    }
+}
+
+/// RAR15 service blocks store the name as UTF-16, which is returned as UTF-16LE bytes if
+/// it's not valid.
+fn decode_utf16_name(name: &[u16]) -> Result<String, Vec<u8>> {
+    match char::decode_utf16(name.iter().copied()).collect::<Result<String, _>>() {
+        Ok(name) => Ok(trim_name(&name).to_string()),
+        Err(_) => Err(name.iter().flat_map(|unit| unit.to_le_bytes()).collect()),
+    }
+}
+
+#[test]
+fn test_decode_stream_names() {
+    assert_eq!(
+        decode_utf8_name(b":Zone.Identifier"),
+        Ok("Zone.Identifier".into())
+    );
+    assert_eq!(decode_utf8_name(b":notes:$DATA"), Ok("notes".into()));
+    assert_eq!(decode_utf8_name(b":\xff"), Err(vec![0xff]));
+
+    let name: Vec<u16> = ":n\u{f6}tes".encode_utf16().collect();
+    assert_eq!(decode_utf16_name(&name), Ok("n\u{f6}tes".into()));
+    assert_eq!(decode_utf16_name(&[0xd800]), Err(vec![0x00, 0xd8]));
+}
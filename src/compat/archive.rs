@@ -0,0 +1,214 @@
+use std::{io, ops::Range};
+
+use crate::{
+    error::{Error, RarResult},
+    rar14, rar15, rar50,
+    raw_header::RawHeaderReader,
+    signature::{SearchOptions, Signature},
+    warning::Warning,
+};
+
+use super::Block;
+
+#[derive(Debug)]
+/// A RAR archive of any format.
+///
+/// Wraps an [`io::Read`] with an [`io::Seek`] impl and provides access to the archive
+/// without handling the specifics of each format.
+pub struct Archive<R: io::Read + io::Seek> {
+    reader: R,
+    format: Signature,
+    signature_offset: u64,
+
+    /// Offsets of the blocks of the file entries, once they have been indexed.
+    pub(super) entry_offsets: Option<Vec<u64>>,
+}
+
+impl<R: io::Read + io::Seek> Archive<R> {
+    /// Search for the RAR signature at the start of `reader` and open the archive.
+    pub fn new(reader: R) -> RarResult<Self> {
+        Self::with_search_options(reader, SearchOptions::default())
+    }
+
+    /// Search for the RAR signature at the start of `reader` within the limits of
+    /// `options` and open the archive.
+    pub fn with_search_options(mut reader: R, options: SearchOptions) -> RarResult<Self> {
+        reader.rewind()?;
+
+        let Some((format, signature_offset)) = Signature::search_stream_with(&mut reader, options)?
+        else {
+            return Err(Error::SignatureNotFound);
+        };
+
+        Ok(Self {
+            reader,
+            format,
+            signature_offset,
+            entry_offsets: None,
+        })
+    }
+
+    /// Format of the archive.
+    pub fn format(&self) -> Signature {
+        self.format
+    }
+
+    /// Offset of the RAR signature in the file.
+    ///
+    /// This is greater than 0 if the archive is a self-extracting archive.
+    pub fn signature_offset(&self) -> u64 {
+        self.signature_offset
+    }
+
+    /// Offset of the first block of the archive.
+    pub fn first_block_offset(&self) -> u64 {
+        self.signature_offset + self.format.size()
+    }
+
+    /// Iterate over the blocks of the archive.
+    pub fn blocks(&mut self) -> RarResult<BlockIterator<&mut R>> {
+        let offset = self.first_block_offset();
+        BlockIterator::new(&mut self.reader, self.format, offset)
+    }
+
+    /// Get a mutable reference to the underlying reader.
+    pub fn reader(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwrap the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl Archive<io::Cursor<memmap2::Mmap>> {
+    /// Open the archive at `path` by mapping the file in memory.
+    ///
+    /// The headers are parsed directly from the mapped bytes, which avoids the system calls
+    /// of the many small reads needed to scan the headers of archives with lots of entries.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it's mapped, for example by another
+    /// process. See [`memmap2::Mmap::map`].
+    pub unsafe fn open_mmap<P: AsRef<std::path::Path>>(path: P) -> RarResult<Self> {
+        let file = std::fs::File::open(path)?;
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        Self::new(io::Cursor::new(mmap))
+    }
+}
+
+#[derive(Debug)]
+/// Iterator over the blocks of an archive of any format.
+pub enum BlockIterator<R: io::Read + io::Seek> {
+    Rar14(rar14::BlockIterator<R>),
+    Rar15(rar15::BlockIterator<R>),
+    Rar50(rar50::BlockIterator<R>),
+}
+
+impl<R: io::Read + io::Seek> BlockIterator<R> {
+    /// Create a [`BlockIterator`] for `format` starting at `offset`.
+    ///
+    /// `offset` must be the offset in the file right after the signature.
+    pub fn new(reader: R, format: Signature, offset: u64) -> RarResult<Self> {
+        Ok(match format {
+            Signature::Rar14 => Self::Rar14(rar14::BlockIterator::new(reader, offset)?),
+            Signature::Rar15 => Self::Rar15(rar15::BlockIterator::new(reader, offset)?),
+            Signature::Rar50 => Self::Rar50(rar50::BlockIterator::new(reader, offset)?),
+        })
+    }
+
+    /// Warnings about the blocks read so far.
+    pub fn warnings(&self) -> &[Warning] {
+        match self {
+            Self::Rar14(iter) => iter.warnings(),
+            Self::Rar15(iter) => iter.warnings(),
+            Self::Rar50(iter) => iter.warnings(),
+        }
+    }
+
+    /// Take the warnings about the blocks read so far, leaving the list empty.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        match self {
+            Self::Rar14(iter) => iter.take_warnings(),
+            Self::Rar15(iter) => iter.take_warnings(),
+            Self::Rar50(iter) => iter.take_warnings(),
+        }
+    }
+
+    /// Whether the archive continues in the next volume, once the end of archive block
+    /// was read.
+    ///
+    /// Always returns `None` for RAR14 archives, which don't have one.
+    pub fn has_next_volume(&self) -> Option<bool> {
+        match self {
+            Self::Rar14(_) => None,
+            Self::Rar15(iter) => iter.has_next_volume(),
+            Self::Rar50(iter) => iter.has_next_volume(),
+        }
+    }
+
+    /// Offset of the block which will be read next.
+    pub fn next_offset(&self) -> u64 {
+        match self {
+            Self::Rar14(iter) => iter.next_offset(),
+            Self::Rar15(iter) => iter.next_offset(),
+            Self::Rar50(iter) => iter.next_offset(),
+        }
+    }
+
+    /// Resume the iteration at `offset` of a file which grew to `file_size`, without
+    /// reading the blocks before it again.
+    ///
+    /// See [`rar15::BlockIterator::resume_at`].
+    pub fn resume_at(&mut self, offset: u64, file_size: u64) {
+        match self {
+            Self::Rar14(iter) => iter.resume_at(offset, file_size),
+            Self::Rar15(iter) => iter.resume_at(offset, file_size),
+            Self::Rar50(iter) => iter.resume_at(offset, file_size),
+        }
+    }
+
+    /// Range of the bytes between the end of archive block and the end of the file, once
+    /// the end of archive block was read.
+    ///
+    /// Always returns `None` for RAR14 archives, which don't have one.
+    pub fn trailing_data(&self) -> Option<Range<u64>> {
+        match self {
+            Self::Rar14(_) => None,
+            Self::Rar15(iter) => iter.trailing_data(),
+            Self::Rar50(iter) => iter.trailing_data(),
+        }
+    }
+}
+
+impl<R: io::Read + io::Seek> Iterator for BlockIterator<R> {
+    type Item = RarResult<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Rar14(iter) => Some(iter.next()?.map(Block::Rar14)),
+            Self::Rar15(iter) => Some(iter.next()?.map(Block::Rar15)),
+            Self::Rar50(iter) => Some(iter.next()?.map(|b| Block::Rar50(Box::new(b)))),
+        }
+    }
+}
+
+impl<R: io::Read + io::Seek> RawHeaderReader for BlockIterator<R> {
+    type Block = Block;
+
+    fn raw_header(&mut self, block: &Block) -> RarResult<Vec<u8>> {
+        match (self, block) {
+            (Self::Rar14(iter), Block::Rar14(block)) => iter.raw_header(block),
+            (Self::Rar15(iter), Block::Rar15(block)) => iter.raw_header(block),
+            (Self::Rar50(iter), Block::Rar50(block)) => iter.raw_header(block),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "block is from an archive of a different format",
+            )
+            .into()),
+        }
+    }
+}
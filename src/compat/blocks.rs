@@ -7,7 +7,7 @@ pub enum Block {
     Rar50(Box<rar50::Block>),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HashKind {
     Crc16(u16),
     Crc32(u32),
@@ -0,0 +1,303 @@
+use std::{
+    borrow::Cow,
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    io,
+    ops::Deref,
+};
+
+use crate::error::RarResult;
+
+use super::{Archive, FileEntry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Identifier of a name stored in a [`CompactListing`] or a [`NameInterner`].
+pub struct NameId(u32);
+
+impl NameId {
+    /// Create an identifier from its index, for use by [`NameInterner`] implementations.
+    pub fn new(index: u32) -> Self {
+        Self(index)
+    }
+
+    /// Index of the identifier.
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+/// Storage for the names of [`CompactEntry`]s.
+///
+/// An interner can be passed to [`Archive::entries_compact_with`] to share the storage of
+/// the names across the listings of many archives.
+pub trait NameInterner {
+    /// Store `name` if it wasn't already, and return its identifier.
+    fn intern(&mut self, name: &[u8]) -> NameId;
+
+    /// Name identified by `id`.
+    ///
+    /// May panic if `id` wasn't returned by this interner.
+    fn get(&self, id: NameId) -> &[u8];
+}
+
+#[derive(Debug, Default)]
+/// Stores each distinct name only once in a single contiguous buffer.
+///
+/// This is the [`NameInterner`] used by [`CompactListing`].
+pub struct NameArena {
+    bytes: Vec<u8>,
+    spans: Vec<(usize, usize)>,
+
+    /// Maps the hash of a name to the first name with that hash.
+    index: HashMap<u64, NameId>,
+}
+
+impl NameArena {
+    /// Number of distinct names stored.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// No names are stored.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Total size of the names stored.
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.bytes.shrink_to_fit();
+        self.spans.shrink_to_fit();
+        self.index = HashMap::new();
+    }
+}
+
+impl NameInterner for NameArena {
+    fn intern(&mut self, name: &[u8]) -> NameId {
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if let Some(&id) = self.index.get(&hash) {
+            if self.get(id) == name {
+                return id;
+            }
+        }
+
+        let id = NameId(self.spans.len() as u32);
+        self.spans.push((self.bytes.len(), name.len()));
+        self.bytes.extend_from_slice(name);
+        self.index.entry(hash).or_insert(id);
+
+        id
+    }
+
+    fn get(&self, id: NameId) -> &[u8] {
+        let (start, len) = self.spans[id.0 as usize];
+        &self.bytes[start..start + len]
+    }
+}
+
+flags! {
+    /// Flags of a [`CompactEntry`].
+    pub struct CompactEntryFlags(u8) {
+        /// Entry is a directory.
+        pub is_directory = 0x01;
+
+        /// Entry data is encrypted.
+        pub is_encrypted = 0x02;
+
+        /// Entry data is continuing from the previous volume.
+        pub split_before = 0x04;
+
+        /// Entry data is continuing in the next volume.
+        pub split_after = 0x08;
+
+        /// The unpacked size of the entry is not known.
+        unknown_unpacked_size = 0x10;
+
+        /// The modification time of the entry is not known.
+        unknown_modification_time = 0x20;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Memory-efficient representation of a file entry, used to list archives with a very large
+/// number of entries.
+///
+/// The name is stored in the arena of the [`CompactListing`] the entry belongs to, or in the
+/// [`NameInterner`] passed to [`Archive::entries_compact_with`].
+pub struct CompactEntry {
+    name: NameId,
+    offset: u64,
+    packed_size: u64,
+    unpacked_size: u64,
+    modification_time: i64,
+    flags: CompactEntryFlags,
+}
+
+impl CompactEntry {
+    /// Identifier of the entry name in the [`CompactListing`] or [`NameInterner`].
+    pub fn name_id(&self) -> NameId {
+        self.name
+    }
+
+    /// Offset of the block containing the entry.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Size of the data area of the block containing the entry.
+    pub fn packed_size(&self) -> u64 {
+        self.packed_size
+    }
+
+    /// Size of the entry after decompression.
+    pub fn unpacked_size(&self) -> Option<u64> {
+        if self.flags.unknown_unpacked_size() {
+            None
+        } else {
+            Some(self.unpacked_size)
+        }
+    }
+
+    /// Modification time of the entry as a Unix timestamp in seconds.
+    ///
+    /// RAR14 and RAR15 store timestamps in the local time of the system that created the
+    /// archive; these are converted to a timestamp as if they were in UTC.
+    pub fn modification_time(&self) -> Option<i64> {
+        if self.flags.unknown_modification_time() {
+            None
+        } else {
+            Some(self.modification_time)
+        }
+    }
+
+    /// Flags of the entry.
+    pub fn flags(&self) -> CompactEntryFlags {
+        self.flags
+    }
+
+    fn from_entry(entry: &FileEntry, names: &mut impl NameInterner) -> Self {
+        let name = match &entry.name {
+            Ok(name) => name.as_bytes(),
+            Err(name) => name,
+        };
+
+        let modification_time = entry.modification_time();
+
+        let flags = (entry.is_directory as u8)
+            | (entry.is_encrypted as u8) << 1
+            | (entry.split_before as u8) << 2
+            | (entry.split_after as u8) << 3
+            | (entry.unpacked_size.is_none() as u8) << 4
+            | (modification_time.is_none() as u8) << 5;
+
+        CompactEntry {
+            name: names.intern(name),
+            offset: entry.offset,
+            packed_size: entry.packed_size,
+            unpacked_size: entry.unpacked_size.unwrap_or(0),
+            modification_time: modification_time.map_or(0, |t| t.unix_timestamp()),
+            flags: CompactEntryFlags::new(flags),
+        }
+    }
+}
+
+impl Deref for CompactEntry {
+    type Target = CompactEntryFlags;
+
+    fn deref(&self) -> &Self::Target {
+        &self.flags
+    }
+}
+
+#[derive(Debug, Default)]
+/// Memory-efficient listing of the file entries of an archive.
+pub struct CompactListing {
+    names: NameArena,
+    entries: Vec<CompactEntry>,
+}
+
+impl CompactListing {
+    /// Entries of the archive, in the order they appear in the archive.
+    pub fn entries(&self) -> &[CompactEntry] {
+        &self.entries
+    }
+
+    /// Name of an entry, as it was stored in the archive.
+    ///
+    /// Names are decoded to UTF-8 if the format specifies an encoding; RAR14 and RAR15
+    /// names which were not stored as Unicode are returned in their original OEM encoding.
+    pub fn name(&self, entry: &CompactEntry) -> &[u8] {
+        self.names.get(entry.name)
+    }
+
+    /// Name of an entry, replacing invalid UTF-8 sequences.
+    pub fn name_lossy(&self, entry: &CompactEntry) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.name(entry))
+    }
+
+    /// Iterate over the entries along with their names.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &CompactEntry)> {
+        self.entries.iter().map(|e| (self.name(e), e))
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The listing doesn't contain any entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<R: io::Read + io::Seek> Archive<R> {
+    /// List the file entries of the archive using a memory-efficient representation.
+    pub fn entries_compact(&mut self) -> RarResult<CompactListing> {
+        let mut listing = CompactListing::default();
+        listing.entries = self.entries_compact_with(&mut listing.names)?;
+
+        listing.entries.shrink_to_fit();
+        listing.names.shrink_to_fit();
+
+        Ok(listing)
+    }
+
+    /// List the file entries of the archive like [`Self::entries_compact`], storing the
+    /// names in `names`.
+    ///
+    /// The names of the entries can be retrieved from `names` with [`NameInterner::get`].
+    pub fn entries_compact_with(
+        &mut self,
+        names: &mut impl NameInterner,
+    ) -> RarResult<Vec<CompactEntry>> {
+        let mut entries = vec![];
+
+        for block in self.blocks()? {
+            if let Some(entry) = FileEntry::from_owned_block(block?) {
+                entries.push(CompactEntry::from_entry(&entry, names));
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+#[test]
+fn test_name_arena_interns_names() {
+    let mut arena = NameArena::default();
+    let a = arena.intern(b"dir/a.txt");
+    let b = arena.intern(b"dir/b.txt");
+
+    assert_eq!(arena.intern(b"dir/a.txt"), a);
+    assert_ne!(a, b);
+    assert_eq!(arena.get(b), b"dir/b.txt");
+    assert_eq!(arena.size(), 18);
+    assert_eq!(arena.len(), 2);
+}
@@ -0,0 +1,174 @@
+use std::io::{Read, Seek};
+
+use crate::{
+    error::{Error, RarResult},
+    rar14, rar15, rar50,
+    size::BlockSize as _,
+};
+
+use super::{features::rar15_encryption_scheme, Archive, Block, EncryptionScheme, FileEntry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Parameters of the PBKDF2 key derivation of RAR50 encryption.
+pub struct KdfParameters {
+    /// Binary logarithm of the number of iterations.
+    pub kdf_count: u8,
+
+    /// Salt mixed with the password.
+    pub salt: [u8; 16],
+}
+
+impl KdfParameters {
+    /// Number of iterations of the key derivation, which is what makes deriving the key
+    /// from a password slow.
+    pub fn iterations(&self) -> u64 {
+        1 << self.kdf_count
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Encryption of the block headers of an archive.
+pub struct HeaderEncryption {
+    /// Scheme used to encrypt the headers.
+    pub scheme: EncryptionScheme,
+
+    /// Offset of the first encrypted block.
+    pub offset: u64,
+
+    /// Parameters of the key derivation, which are only stored in the headers of RAR50.
+    /// RAR15 derives the key of each header from its own salt.
+    pub kdf: Option<KdfParameters>,
+
+    /// A password check value is stored, so a wrong password is detected before
+    /// decrypting any header. Otherwise it's only detected when the first header fails its
+    /// checksum.
+    pub has_password_check: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// File entry whose data is encrypted.
+pub struct EncryptedEntry {
+    /// Offset of the block containing the entry.
+    pub offset: u64,
+
+    /// Name of the entry, like [`FileEntry::name`].
+    pub name: Result<String, Vec<u8>>,
+
+    /// Scheme used to encrypt the data of the entry.
+    pub scheme: EncryptionScheme,
+
+    /// Parameters of the key derivation of RAR50 entries.
+    pub kdf: Option<KdfParameters>,
+
+    /// A password check value is stored, so a wrong password is detected before
+    /// decrypting the data. Otherwise it's only detected by the checksums of the data once
+    /// it's decrypted, or not at all for some older schemes.
+    pub has_password_check: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// What is encrypted in an archive, to decide whether to ask for a password.
+///
+/// Returned by [`Archive::encryption`].
+pub struct EncryptionStatus {
+    /// Encryption of the block headers, if they are encrypted.
+    ///
+    /// The blocks after the first encrypted one can't be read without a password, so the
+    /// encrypted entries among them are not in [`Self::encrypted_entries`].
+    pub headers: Option<HeaderEncryption>,
+
+    /// Entries whose data is encrypted, in the order of the archive.
+    pub encrypted_entries: Vec<EncryptedEntry>,
+}
+
+impl EncryptionStatus {
+    /// Something in the archive can't be read without a password.
+    pub fn needs_password(&self) -> bool {
+        self.headers.is_some() || !self.encrypted_entries.is_empty()
+    }
+
+    fn add_block(&mut self, block: &Block) {
+        let (scheme, kdf, has_password_check) = match block {
+            Block::Rar14(rar14::Block::File(file)) if file.is_encrypted() => {
+                (EncryptionScheme::Rar13, None, false)
+            }
+
+            Block::Rar15(block) => match &block.kind {
+                rar15::BlockKind::Main(main) if main.has_password() => {
+                    self.headers = Some(HeaderEncryption {
+                        scheme: EncryptionScheme::Aes128,
+                        offset: block.offset() + block.size(),
+                        kdf: None,
+                        has_password_check: false,
+                    });
+                    return;
+                }
+                rar15::BlockKind::File(file) if file.is_encrypted() => {
+                    (rar15_encryption_scheme(file.unpack_version), None, false)
+                }
+                _ => return,
+            },
+
+            Block::Rar50(block) => match &block.kind {
+                rar50::BlockKind::Crypt(crypt) => {
+                    self.headers = Some(HeaderEncryption {
+                        scheme: EncryptionScheme::Aes256,
+                        offset: block.offset() + block.size(),
+                        kdf: Some(KdfParameters {
+                            kdf_count: crypt.kdf_count,
+                            salt: crypt.salt,
+                        }),
+                        has_password_check: crypt.check_value.is_some(),
+                    });
+                    return;
+                }
+                rar50::BlockKind::File(rar50::FileBlock {
+                    encryption: Some(encryption),
+                    ..
+                }) => (
+                    EncryptionScheme::Aes256,
+                    Some(KdfParameters {
+                        kdf_count: encryption.kdf_count,
+                        salt: encryption.salt,
+                    }),
+                    encryption.check_value.is_some(),
+                ),
+                _ => return,
+            },
+
+            _ => return,
+        };
+
+        let Some(entry) = FileEntry::from_block(block) else {
+            return;
+        };
+        self.encrypted_entries.push(EncryptedEntry {
+            offset: entry.offset,
+            name: entry.name,
+            scheme,
+            kdf,
+            has_password_check,
+        });
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Find out what is encrypted in the archive and how, without a password.
+    ///
+    /// Only the block headers are read, up to the first encrypted one.
+    pub fn encryption(&mut self) -> RarResult<EncryptionStatus> {
+        let mut status = EncryptionStatus::default();
+
+        for block in self.blocks()? {
+            match block {
+                Ok(block) => status.add_block(&block),
+
+                // The rest of the blocks can't be read without a password.
+                Err(Error::PasswordRequired) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(status)
+    }
+}
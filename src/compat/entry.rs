@@ -0,0 +1,479 @@
+use std::{
+    io::{self, Read, Seek},
+    mem,
+    ops::Range,
+};
+
+use crate::{
+    attributes::{DosFileAttributes, FileAttributes, UnixPermissions, WindowsFileAttributes},
+    error::RarResult,
+    path::sanitize_path,
+    rar14, rar15, rar50,
+    size::BlockSize,
+};
+
+use super::{Archive, Block, HashKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Timestamp of an entry.
+pub enum Timestamp {
+    /// MS-DOS timestamp used by RAR14 and RAR15, which is stored in the local time of the
+    /// system that created the archive and is not timezone aware.
+    Local(time::PrimitiveDateTime),
+
+    /// Timezone-aware timestamp used by RAR50.
+    Utc(time::OffsetDateTime),
+}
+
+impl Timestamp {
+    /// Convert the timestamp to a timezone-aware one.
+    ///
+    /// [`Timestamp::Local`] timestamps are in the local time of the system that created the
+    /// archive, whose timezone is not stored, so they are assumed to be in UTC. They may be
+    /// off by the UTC offset of that system.
+    pub fn assume_utc(self) -> time::OffsetDateTime {
+        self.assume_offset(time::UtcOffset::UTC)
+    }
+
+    /// Convert the timestamp to a timezone-aware one, interpreting [`Timestamp::Local`]
+    /// timestamps as being at `offset` from UTC.
+    ///
+    /// To use the offset of the system reading the archive, which is usually the same as
+    /// the one of the system that created it, pass the one returned by
+    /// `time::UtcOffset::current_local_offset` with the `local-offset` feature of `time`.
+    /// [`Timestamp::Utc`] timestamps are returned as they are.
+    pub fn assume_offset(self, offset: time::UtcOffset) -> time::OffsetDateTime {
+        match self {
+            Timestamp::Local(time) => time.assume_offset(offset),
+            Timestamp::Utc(time) => time,
+        }
+    }
+}
+
+int_enum! {
+    /// OS of the host system used to add the file to the archive.
+    pub enum HostOs : u8 {
+        /// MS-DOS
+        MsDos = 0,
+
+        /// OS/2
+        Os2 = 1,
+
+        /// Windows
+        Windows = 2,
+
+        /// Unix-like (Linux, OS X/macOS)
+        Unix = 3,
+
+        /// Classic Mac OS (not to be confused with OS X/macOS)
+        MacOs = 4,
+
+        /// BeOS
+        BeOs = 5,
+    }
+}
+
+impl From<rar15::HostOs> for HostOs {
+    fn from(host_os: rar15::HostOs) -> Self {
+        match host_os {
+            rar15::HostOs::MsDos => HostOs::MsDos,
+            rar15::HostOs::Os2 => HostOs::Os2,
+            rar15::HostOs::Win32 => HostOs::Windows,
+            rar15::HostOs::Unix => HostOs::Unix,
+            rar15::HostOs::MacOs => HostOs::MacOs,
+            rar15::HostOs::BeOs => HostOs::BeOs,
+            rar15::HostOs::Unknown(n) => HostOs::Unknown(n),
+        }
+    }
+}
+
+impl From<rar50::HostOs> for HostOs {
+    fn from(host_os: rar50::HostOs) -> Self {
+        match host_os {
+            rar50::HostOs::Windows => HostOs::Windows,
+            rar50::HostOs::Unix => HostOs::Unix,
+            rar50::HostOs::Unknown(n) => HostOs::Unknown(n),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Metadata of a file or directory stored in an archive of any format.
+pub struct FileEntry {
+    /// Offset of the block containing the entry.
+    pub offset: u64,
+
+    /// Size of the header of the block containing the entry.
+    pub header_size: u64,
+
+    /// Name of the entry.
+    ///
+    /// RAR14 and RAR15 names that were not stored as Unicode and contain characters outside
+    /// the ASCII range are returned in their original OEM encoding.
+    pub name: Result<String, Vec<u8>>,
+
+    /// Size of the data area of the block containing the entry.
+    pub packed_size: u64,
+
+    /// Size of the entry after decompression, if known.
+    pub unpacked_size: Option<u64>,
+
+    /// Modification time of the entry.
+    pub modification_time: Option<Timestamp>,
+
+    /// Creation time of the entry, if stored in the archive.
+    pub creation_time: Option<Timestamp>,
+
+    /// Last access time of the entry, if stored in the archive.
+    pub access_time: Option<Timestamp>,
+
+    /// Time at which the entry was added to the archive. Only stored by RAR15.
+    pub archive_time: Option<Timestamp>,
+
+    /// CRC of the unpacked data.
+    pub checksum: Option<HashKind>,
+
+    /// BLAKE2sp hash of the unpacked data.
+    pub blake2sp: Option<[u8; 32]>,
+
+    /// OS-specific file attributes.
+    pub attributes: u64,
+
+    /// OS used to add the entry to the archive.
+    pub host_os: HostOs,
+
+    /// Entry is a directory.
+    pub is_directory: bool,
+
+    /// Entry data is encrypted.
+    pub is_encrypted: bool,
+
+    /// Entry data is stored without compression.
+    pub is_stored: bool,
+
+    /// Entry data is compressed with the dictionary of the entries before it, so they must
+    /// be decompressed first. See [`Archive::solid_plan`].
+    ///
+    /// Always `false` for RAR14 entries, whose archives are either entirely solid or not,
+    /// as stored in their main block.
+    pub is_solid: bool,
+
+    /// Entry data is continuing from the previous volume.
+    pub split_before: bool,
+
+    /// Entry data is continuing in the next volume.
+    pub split_after: bool,
+
+    /// Version number of an older version of a file kept by RAR's file version control.
+    ///
+    /// RAR15 stores the version number at the end of the name after a `;`, which is
+    /// removed from [`Self::name`] so that all the versions of a file have the same name.
+    /// See [`Archive::entry_versions`].
+    pub version: Option<u64>,
+}
+
+impl FileEntry {
+    /// Normalize the metadata of a file block.
+    ///
+    /// Returns `None` if the block does not contain a file entry.
+    pub fn from_block(block: &Block) -> Option<Self> {
+        let name = match block {
+            Block::Rar14(rar14::Block::File(file)) => match &file.name {
+                rar14::OemString::Ascii(name) => Ok(name.clone()),
+                rar14::OemString::Oem(name) => Err(name.clone()),
+            },
+            Block::Rar15(block) => match &block.kind {
+                rar15::BlockKind::File(file) => match &file.file_name {
+                    rar15::Filename::Unicode(name) => name.clone(),
+                    rar15::Filename::Ascii(name) => Ok(name.clone()),
+                    rar15::Filename::Oem(name) => Err(name.clone()),
+                },
+                _ => return None,
+            },
+            Block::Rar50(block) => match &block.kind {
+                rar50::BlockKind::File(file) => file.name.clone(),
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        Self::from_block_with_name(block, name)
+    }
+
+    /// Normalize the metadata of a file block like [`Self::from_block`], moving the name
+    /// out of the block instead of copying it.
+    ///
+    /// Listing the entries of an archive this way only allocates the name of each entry
+    /// once, when its block is read.
+    pub fn from_owned_block(mut block: Block) -> Option<Self> {
+        let name = match &mut block {
+            Block::Rar14(rar14::Block::File(file)) => {
+                match mem::replace(&mut file.name, rar14::OemString::Oem(vec![])) {
+                    rar14::OemString::Ascii(name) => Ok(name),
+                    rar14::OemString::Oem(name) => Err(name),
+                }
+            }
+            Block::Rar15(block) => match &mut block.kind {
+                rar15::BlockKind::File(file) => {
+                    match mem::replace(&mut file.file_name, rar15::Filename::Oem(vec![])) {
+                        rar15::Filename::Unicode(name) => name,
+                        rar15::Filename::Ascii(name) => Ok(name),
+                        rar15::Filename::Oem(name) => Err(name),
+                    }
+                }
+                _ => return None,
+            },
+            Block::Rar50(block) => match &mut block.kind {
+                rar50::BlockKind::File(file) => mem::replace(&mut file.name, Err(vec![])),
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        Self::from_block_with_name(&block, name)
+    }
+
+    fn from_block_with_name(block: &Block, name: Result<String, Vec<u8>>) -> Option<Self> {
+        match block {
+            Block::Rar14(rar14::Block::File(file)) => Some(Self::from_rar14(file, name)),
+            Block::Rar15(block) => match &block.kind {
+                rar15::BlockKind::File(file) => Some(Self::from_rar15(block, file, name)),
+                _ => None,
+            },
+            Block::Rar50(block) => match &block.kind {
+                rar50::BlockKind::File(file) => Some(Self::from_rar50(block, file, name)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn from_rar14(file: &rar14::FileBlock, name: Result<String, Vec<u8>>) -> Self {
+        FileEntry {
+            offset: file.offset(),
+            header_size: file.header_size(),
+            name,
+            packed_size: file.data_size(),
+            unpacked_size: Some(file.unpacked_data_size as u64),
+            modification_time: file.modification_time.ok().map(Timestamp::Local),
+            creation_time: None,
+            access_time: None,
+            archive_time: None,
+            checksum: Some(HashKind::Crc16(file.crc16)),
+            blake2sp: None,
+            attributes: file.attributes.bits() as u64,
+            host_os: HostOs::MsDos,
+            is_directory: file.is_directory(),
+            is_encrypted: file.is_encrypted(),
+            is_stored: file.method == rar14::FileBlock::METHOD_STORE,
+            is_solid: false,
+            split_before: file.split_before(),
+            split_after: file.split_after(),
+            version: None,
+        }
+    }
+
+    fn from_rar15(
+        block: &rar15::Block,
+        file: &rar15::FileBlock,
+        mut name: Result<String, Vec<u8>>,
+    ) -> Self {
+        let version = if file.flags.has_version() {
+            strip_version(&mut name)
+        } else {
+            None
+        };
+
+        FileEntry {
+            offset: block.offset(),
+            header_size: block.header_size(),
+            name,
+            packed_size: block.data_size(),
+            unpacked_size: Some(file.unpacked_data_size),
+            modification_time: file.modification_time.ok().map(Timestamp::Local),
+            creation_time: file
+                .creation_time
+                .and_then(Result::ok)
+                .map(Timestamp::Local),
+            access_time: file.access_time.and_then(Result::ok).map(Timestamp::Local),
+            archive_time: file.archive_time.and_then(Result::ok).map(Timestamp::Local),
+            checksum: Some(HashKind::Crc32(file.file_crc32)),
+            blake2sp: None,
+            attributes: file.attributes as u64,
+            host_os: file.host_os.into(),
+            is_directory: file.is_directory(),
+            is_encrypted: file.is_encrypted(),
+            is_stored: file.method == rar15::FileBlock::METHOD_STORE,
+            is_solid: file.is_solid(),
+            split_before: file.split_before(),
+            split_after: file.split_after(),
+            version,
+        }
+    }
+
+    fn from_rar50(
+        block: &rar50::Block,
+        file: &rar50::FileBlock,
+        name: Result<String, Vec<u8>>,
+    ) -> Self {
+        FileEntry {
+            offset: block.offset(),
+            header_size: block.header_size(),
+            name,
+            packed_size: block.data_size(),
+            unpacked_size: file.unpacked_size,
+            modification_time: file
+                .modification_time()
+                .and_then(Result::ok)
+                .map(Timestamp::Utc),
+            creation_time: file
+                .extended_time
+                .as_ref()
+                .and_then(|t| t.creation_time?.ok())
+                .map(Timestamp::Utc),
+            access_time: file
+                .extended_time
+                .as_ref()
+                .and_then(|t| t.access_time?.ok())
+                .map(Timestamp::Utc),
+            archive_time: None,
+            checksum: file.unpacked_data_crc32.map(HashKind::Crc32),
+            blake2sp: match &file.hash {
+                Some(rar50::FileHashRecord {
+                    hash: rar50::FileHash::Blake2Sp(hash),
+                }) => Some(*hash),
+                _ => None,
+            },
+            attributes: file.attributes,
+            host_os: file.host_os.into(),
+            is_directory: file.is_directory(),
+            is_encrypted: file.encryption.is_some(),
+            is_stored: file.compression_info.method() == rar50::CompressionMethod::NoCompression,
+            is_solid: file.compression_info.is_solid(),
+            split_before: block.split_before(),
+            split_after: block.split_after(),
+            // Version 0 is the current version of the file.
+            version: file
+                .version
+                .as_ref()
+                .map(|version| version.version_number)
+                .filter(|&number| number != 0),
+        }
+    }
+
+    /// Modification time of the entry as a timezone-aware timestamp, converted with
+    /// [`Timestamp::assume_utc`].
+    ///
+    /// [`Self::modification_time`] is already taken from the most precise field stored in
+    /// the archive: the extended time of RAR15 and the time record of RAR50 take precedence
+    /// over the MS-DOS timestamp, which only has a precision of two seconds.
+    pub fn modification_time(&self) -> Option<time::OffsetDateTime> {
+        self.modification_time.map(Timestamp::assume_utc)
+    }
+
+    /// Name of the entry, replacing invalid UTF-8 sequences.
+    pub fn name_lossy(&self) -> std::borrow::Cow<'_, str> {
+        match &self.name {
+            Ok(name) => name.into(),
+            Err(name) => String::from_utf8_lossy(name),
+        }
+    }
+
+    /// Name of the entry as a relative path which can't escape the directory it's extracted
+    /// to, with `..` components, leading separators and drive prefixes removed.
+    ///
+    /// Both `/` and `\` are treated as separators, and names which are not valid UTF-8 are
+    /// converted with [`Self::name_lossy`]. The [`Self::version`] of older versions of a
+    /// file is appended after a `;`, like RAR does, so they don't replace the latest one.
+    pub fn sanitized_path(&self) -> std::path::PathBuf {
+        match self.version {
+            Some(version) => sanitize_path(&format!("{};{version}", self.name_lossy())),
+            None => sanitize_path(&self.name_lossy()),
+        }
+    }
+
+    /// Attributes of the entry, decoded according to [`Self::host_os`].
+    pub fn attributes(&self) -> FileAttributes {
+        match self.host_os {
+            HostOs::MsDos | HostOs::Os2 => {
+                FileAttributes::Dos(DosFileAttributes::new(self.attributes as u8))
+            }
+            HostOs::Windows => {
+                FileAttributes::Windows(WindowsFileAttributes::new(self.attributes as u32))
+            }
+            HostOs::Unix | HostOs::BeOs => {
+                FileAttributes::Unix(UnixPermissions::new(self.attributes as u32))
+            }
+            HostOs::MacOs | HostOs::Unknown(_) => FileAttributes::Unknown(self.attributes),
+        }
+    }
+
+    /// Absolute offsets of the packed data of the entry in the file, which is the data area
+    /// of the block containing it.
+    ///
+    /// For entries split across volumes this is only the part stored in this volume.
+    pub fn data_range(&self) -> Range<u64> {
+        let start = self.offset.saturating_add(self.header_size);
+        start..start.saturating_add(self.packed_size)
+    }
+
+    /// Read the data of an entry which is stored without compression.
+    ///
+    /// Seeks `reader` to the start of the data area and returns a reader that stops at its
+    /// end. The data is not checked against [`Self::checksum`].
+    ///
+    /// Returns an [`io::ErrorKind::Unsupported`] error if the entry is compressed,
+    /// encrypted, or split across volumes.
+    pub fn data_reader<'a, R: Read + Seek>(
+        &self,
+        reader: &'a mut R,
+    ) -> io::Result<io::Take<&'a mut R>> {
+        let unsupported = |message| io::Error::new(io::ErrorKind::Unsupported, message);
+
+        if !self.is_stored {
+            return Err(unsupported("entry is compressed"));
+        }
+        if self.is_encrypted {
+            return Err(unsupported("encrypted entries are not supported"));
+        }
+        if self.split_before || self.split_after {
+            return Err(unsupported(
+                "entries split across volumes are not supported",
+            ));
+        }
+
+        reader.seek(io::SeekFrom::Start(self.data_range().start))?;
+        Ok(reader.take(self.packed_size))
+    }
+}
+
+/// Remove the `;` and the version number at the end of a versioned RAR15 `name`, returning
+/// the version number.
+fn strip_version(name: &mut Result<String, Vec<u8>>) -> Option<u64> {
+    let bytes = match name {
+        Ok(name) => name.as_bytes(),
+        Err(name) => name.as_slice(),
+    };
+    let separator = bytes.iter().rposition(|&b| b == b';')?;
+    let digits = &bytes[separator + 1..];
+    if !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let version = std::str::from_utf8(digits).ok()?.parse().ok()?;
+
+    match name {
+        Ok(name) => name.truncate(separator),
+        Err(name) => name.truncate(separator),
+    }
+
+    Some(version)
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// List the file entries of the archive.
+    ///
+    /// Use [`Self::entry_iter`] to read them one at a time instead.
+    pub fn entries(&mut self) -> RarResult<Vec<FileEntry>> {
+        self.entry_iter()?.collect()
+    }
+}
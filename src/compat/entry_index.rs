@@ -0,0 +1,177 @@
+use std::{
+    io::{self, Read, Seek},
+    ops::Range,
+};
+
+use crate::{
+    error::RarResult, limits::Limits, rar14, rar15, rar50, signature::Signature, size::BlockSize,
+};
+
+use super::{Archive, Block, FileEntry};
+
+impl<R: Read + Seek> Archive<R> {
+    /// Offsets of the blocks of the file entries, indexing them on the first call.
+    ///
+    /// Indexing reads the headers of all the blocks of the archive, but only the offsets of
+    /// the entries are kept.
+    pub(super) fn entry_offsets(&mut self) -> RarResult<&[u64]> {
+        if self.entry_offsets.is_none() {
+            let mut offsets = vec![];
+            for block in self.blocks()? {
+                if let Some(entry) = FileEntry::from_owned_block(block?) {
+                    offsets.push(entry.offset);
+                }
+            }
+            self.entry_offsets = Some(offsets);
+        }
+
+        Ok(self.entry_offsets.as_deref().unwrap_or_default())
+    }
+
+    /// Number of file entries in the archive.
+    pub fn entry_count(&mut self) -> RarResult<usize> {
+        Ok(self.entry_offsets()?.len())
+    }
+
+    /// Metadata of the entry at `index` in the list returned by [`Self::entries`].
+    ///
+    /// The offsets of the entries are indexed on the first call, so that only the header of
+    /// the entry is read afterwards.
+    ///
+    /// Returns `None` if there is no entry at `index`.
+    pub fn entry_at(&mut self, index: usize) -> RarResult<Option<FileEntry>> {
+        let Some(&offset) = self.entry_offsets()?.get(index) else {
+            return Ok(None);
+        };
+
+        let block = self.block_at(offset)?;
+        Ok(FileEntry::from_owned_block(block))
+    }
+
+    /// Metadata of the entries in `range` of the list returned by [`Self::entries`], to
+    /// list a page of the entries of a large archive without reading the other ones.
+    ///
+    /// The range is clamped to the number of entries.
+    pub fn entries_range(&mut self, range: Range<usize>) -> RarResult<Vec<FileEntry>> {
+        let offsets = self.entry_offsets()?;
+        let end = range.end.min(offsets.len());
+        let offsets = offsets.get(range.start..end).unwrap_or_default().to_vec();
+
+        let mut entries = Vec::with_capacity(offsets.len());
+        for offset in offsets {
+            if let Some(entry) = FileEntry::from_owned_block(self.block_at(offset)?) {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Read the QuickOpen cache referenced by the locator record of the main block of a
+    /// RAR50 archive, seeking directly to it without reading the blocks in between.
+    ///
+    /// Returns `None` if the archive is not a RAR50 archive, if its headers are encrypted,
+    /// or if the main block has no QuickOpen offset.
+    pub fn seek_to_quick_open(&mut self) -> RarResult<Option<rar50::QuickOpenCache>> {
+        let Some(block) = self.locator_target(|locator| locator.quick_open_record_offset)? else {
+            return Ok(None);
+        };
+
+        Ok(Some(rar50::QuickOpenCache::read(self.reader(), &block)?))
+    }
+
+    /// Read the recovery record referenced by the locator record of the main block of a
+    /// RAR50 archive, seeking directly to it without reading the blocks in between.
+    ///
+    /// Returns `None` under the same conditions as [`Self::seek_to_quick_open`].
+    pub fn seek_to_recovery_record(&mut self) -> RarResult<Option<rar50::RecoveryRecord>> {
+        let Some(block) = self.locator_target(|locator| locator.recovery_record_offset)? else {
+            return Ok(None);
+        };
+
+        Ok(Some(rar50::RecoveryRecord::read(self.reader(), &block)?))
+    }
+
+    /// Metadata of the first entry named `name`.
+    ///
+    /// If the archive has a QuickOpen cache, only the main block and the cache are read.
+    /// The cache may not contain the headers of all the entries, so the archive is scanned
+    /// as usual if the entry is not in the cache or if the cache can't be read.
+    ///
+    /// Entries whose name is not valid UTF-8 are never found.
+    pub fn find_entry(&mut self, name: &str) -> RarResult<Option<FileEntry>> {
+        let is_match = |entry: &FileEntry| entry.name.as_deref() == Ok(name);
+
+        if let Ok(Some(cache)) = self.seek_to_quick_open() {
+            let options = rar50::ParseOptions::default();
+            let found = cache
+                .headers
+                .iter()
+                .filter_map(|cached| cached.block(&options).ok())
+                .filter_map(|block| FileEntry::from_block(&Block::Rar50(Box::new(block))))
+                .find(is_match);
+
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+
+        for block in self.blocks()? {
+            if let Some(entry) = FileEntry::from_block(&block?).filter(is_match) {
+                return Ok(Some(entry));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Read the block at the offset selected by `target` from the locator record of the
+    /// main block of a RAR50 archive.
+    fn locator_target(
+        &mut self,
+        target: impl FnOnce(&rar50::LocatorRecord) -> Option<u64>,
+    ) -> RarResult<Option<rar50::Block>> {
+        if self.format() != Signature::Rar50 {
+            return Ok(None);
+        }
+
+        let offset = self.first_block_offset();
+        let reader = self.reader();
+        reader.seek(io::SeekFrom::Start(offset))?;
+        let main = rar50::Block::read(reader)?;
+
+        let rar50::BlockKind::Main(rar50::MainBlock {
+            locator: Some(locator),
+            ..
+        }) = &main.kind
+        else {
+            return Ok(None);
+        };
+        let Some(target) = target(locator) else {
+            return Ok(None);
+        };
+
+        // Locator offsets are relative to the start of the main block.
+        let Some(offset) = main.offset().checked_add(target) else {
+            return Ok(None);
+        };
+        reader.seek(io::SeekFrom::Start(offset))?;
+        Ok(Some(rar50::Block::read(reader)?))
+    }
+
+    /// Read the block at `offset`, which must be the offset of a file block.
+    pub(super) fn block_at(&mut self, offset: u64) -> RarResult<Block> {
+        let format = self.format();
+        let reader = self.reader();
+        reader.seek(io::SeekFrom::Start(offset))?;
+
+        Ok(match format {
+            Signature::Rar14 => Block::Rar14(rar14::Block::File(rar14::FileBlock::read(
+                reader,
+                &Limits::default(),
+            )?)),
+            Signature::Rar15 => Block::Rar15(rar15::Block::read(reader)?),
+            Signature::Rar50 => Block::Rar50(Box::new(rar50::Block::read(reader)?)),
+        })
+    }
+}
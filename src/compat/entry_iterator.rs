@@ -0,0 +1,72 @@
+use std::io::{Read, Seek};
+
+use crate::{error::RarResult, warning::Warning};
+
+use super::{Archive, BlockIterator, FileEntry};
+
+/// Iterator over the file entries of an archive of any format.
+///
+/// The blocks are read one at a time and only the [`FileEntry`] of each file block is
+/// kept, so the memory used doesn't grow with the number of entries like with
+/// [`Archive::entries`]. Blocks which are not file blocks are skipped, and errors returned
+/// by the [`BlockIterator`] are passed through.
+pub struct EntryIterator<R: Read + Seek> {
+    blocks: BlockIterator<R>,
+}
+
+impl<R: Read + Seek> EntryIterator<R> {
+    pub fn new(blocks: BlockIterator<R>) -> Self {
+        Self { blocks }
+    }
+
+    /// Warnings about the blocks read so far.
+    pub fn warnings(&self) -> &[Warning] {
+        self.blocks.warnings()
+    }
+
+    /// Take the warnings about the blocks read so far, leaving the list empty.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        self.blocks.take_warnings()
+    }
+
+    /// Unwrap the iterator over the blocks.
+    pub fn into_inner(self) -> BlockIterator<R> {
+        self.blocks
+    }
+}
+
+impl<R: Read + Seek> Iterator for EntryIterator<R> {
+    type Item = RarResult<FileEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.blocks.next()? {
+                Ok(block) => {
+                    if let Some(entry) = FileEntry::from_owned_block(block) {
+                        return Some(Ok(entry));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Iterate over the file entries of the archive, reading them lazily.
+    pub fn entry_iter(&mut self) -> RarResult<EntryIterator<&mut R>> {
+        Ok(EntryIterator::new(self.blocks()?))
+    }
+
+    /// Iterate over the file entries of the archive like [`Self::entry_iter`], taking
+    /// ownership of the reader.
+    pub fn into_entry_iter(self) -> RarResult<EntryIterator<R>> {
+        let format = self.format();
+        let offset = self.first_block_offset();
+        Ok(EntryIterator::new(BlockIterator::new(
+            self.into_inner(),
+            format,
+            offset,
+        )?))
+    }
+}
@@ -0,0 +1,287 @@
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::Progress;
+
+use super::{FileEntry, HostOs};
+
+#[derive(Debug, Clone, Default)]
+/// Options controlling how entries are written out when extracting an archive.
+pub struct ExtractOptions {
+    /// How to choose the Unix permissions of extracted entries.
+    pub permissions: PermissionPolicy,
+
+    /// Apply the NTFS permissions stored in the archive to the extracted entries with
+    /// [`apply_security_descriptor`](super::apply_security_descriptor), like the "keep NTFS
+    /// permissions" option of WinRAR.
+    ///
+    /// Only has an effect on Windows. Defaults to `false`.
+    pub keep_ntfs_permissions: bool,
+
+    /// How to extract the NTFS alternate data streams of entries.
+    pub streams: StreamPolicy,
+
+    /// Restore the Mac OS type and creator codes of entries as their Finder info with
+    /// [`set_extended_attribute`](super::set_extended_attribute).
+    ///
+    /// Only has an effect on macOS and BSD with the `xattr` feature. Defaults to `false`.
+    pub keep_mac_info: bool,
+
+    /// How to extract the symbolic links, junctions, hard links and file copies stored in
+    /// the archive.
+    pub links: LinkPolicy,
+
+    /// Replace the files which already exist in the destination directory.
+    ///
+    /// Defaults to `false`, which leaves them untouched.
+    pub overwrite: bool,
+
+    /// UTC offset of the MS-DOS timestamps of RAR14 and RAR15 entries, which are stored in
+    /// the local time of the system that created the archive. It's used to set the
+    /// modification time of the extracted files, as in
+    /// [`Timestamp::assume_offset`](super::Timestamp::assume_offset).
+    ///
+    /// Defaults to `None`, which treats them as UTC.
+    pub local_offset: Option<time::UtcOffset>,
+
+    /// Counters of the total bytes read from the archive and written out, updated while
+    /// the entries are extracted so that they can be polled from another thread.
+    ///
+    /// The counters of each entry are children of these, and are returned in
+    /// [`ExtractedEntry::progress`](super::ExtractedEntry::progress).
+    pub progress: Option<Arc<Progress>>,
+
+    /// Maximum number of bytes per second read from the data areas of the archive, enforced
+    /// with a [`RateLimiter`](crate::RateLimiter) shared by all the entries.
+    ///
+    /// Defaults to `None`, which doesn't limit the throughput. A custom hook can be set with
+    /// [`Extractor::with_throttle`](super::Extractor::with_throttle) instead.
+    pub max_bytes_per_second: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How to extract the symbolic links, junctions, hard links and file copies stored in the
+/// archive.
+///
+/// Links whose target is outside of the destination directory are rejected, and so are
+/// symbolic links whose target goes through another symbolic link and entries whose path
+/// goes through a symbolic link extracted before them, unless
+/// [`Self::allow_outside_destination`] is enabled.
+pub struct LinkPolicy {
+    /// How to extract symbolic links and junctions. [`LinkAction::Copy`] copies the file
+    /// they point to.
+    ///
+    /// Defaults to [`LinkAction::Link`].
+    pub symbolic_links: LinkAction,
+
+    /// How to extract hard links.
+    ///
+    /// Defaults to [`LinkAction::Link`].
+    pub hard_links: LinkAction,
+
+    /// How to extract file copies, which RAR50 archives store as references to an
+    /// identical file. [`LinkAction::Link`] creates a hard link to the file.
+    ///
+    /// Defaults to [`LinkAction::Copy`].
+    pub file_copies: LinkAction,
+
+    /// Extract links pointing outside of the destination directory.
+    ///
+    /// Defaults to `false`. Only enable this for trusted archives.
+    pub allow_outside_destination: bool,
+}
+
+impl Default for LinkPolicy {
+    fn default() -> Self {
+        Self {
+            symbolic_links: LinkAction::Link,
+            hard_links: LinkAction::Link,
+            file_copies: LinkAction::Copy,
+            allow_outside_destination: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How to extract a kind of link, used by [`LinkPolicy`].
+pub enum LinkAction {
+    /// Don't extract the link.
+    Skip,
+
+    /// Recreate the link.
+    Link,
+
+    /// Write a copy of the file the link points to.
+    Copy,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How to extract the NTFS alternate data streams of entries, which are stored in the
+/// [`AlternateDataStream`](super::AlternateDataStream)s following them.
+pub enum StreamPolicy {
+    /// Don't extract the streams.
+    Skip,
+
+    /// Write the streams as alternate data streams named `file:stream` on Windows if the
+    /// filesystem supports them, or as separate files named `file__stream` otherwise.
+    #[default]
+    AlternateDataStream,
+
+    /// Always write the streams as separate files named `file__stream`.
+    SeparateFile,
+}
+
+impl StreamPolicy {
+    /// Create the file that the stream called `name` of the file at `path` is extracted
+    /// to, returning its path, or `None` if the streams are skipped.
+    ///
+    /// Characters which can't appear in a file or stream name are replaced with `_`.
+    pub fn create(&self, path: &Path, name: &str) -> io::Result<Option<(PathBuf, fs::File)>> {
+        let name = sanitize_stream_name(name);
+        if name.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream name is empty",
+            ));
+        }
+
+        match self {
+            Self::Skip => return Ok(None),
+
+            #[cfg(windows)]
+            Self::AlternateDataStream => {
+                // Filesystems without streams, like FAT, reject names containing a `:`.
+                const ERROR_NOT_SUPPORTED: i32 = 50;
+                const ERROR_INVALID_PARAMETER: i32 = 87;
+                const ERROR_INVALID_NAME: i32 = 123;
+
+                let stream_path = stream_path(path, ":", &name);
+                match fs::File::create(&stream_path) {
+                    Ok(file) => return Ok(Some((stream_path, file))),
+                    Err(e)
+                        if matches!(
+                            e.raw_os_error(),
+                            Some(
+                                ERROR_NOT_SUPPORTED | ERROR_INVALID_PARAMETER | ERROR_INVALID_NAME
+                            )
+                        ) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            #[cfg(not(windows))]
+            Self::AlternateDataStream => {}
+
+            Self::SeparateFile => {}
+        }
+
+        let stream_path = stream_path(path, "__", &name);
+        let file = fs::File::create(&stream_path)?;
+        Ok(Some((stream_path, file)))
+    }
+}
+
+fn sanitize_stream_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '\0'..='\x1f' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+fn stream_path(path: &Path, separator: &str, name: &str) -> PathBuf {
+    let mut stream_path = OsString::from(path);
+    stream_path.push(separator);
+    stream_path.push(name);
+    stream_path.into()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How to choose the Unix permissions of extracted entries.
+///
+/// Entries added on Unix store their mode, which is used as is unless
+/// [`Self::preserve_unix_mode`] is disabled. Entries added on other systems only store
+/// DOS attributes, so they get [`Self::file_mode`] or [`Self::directory_mode`] instead,
+/// without the write bits if they are read-only.
+///
+/// The setuid, setgid and sticky bits are never set.
+pub struct PermissionPolicy {
+    /// Bits removed from every mode, like the umask of a process.
+    ///
+    /// Defaults to `0o022`.
+    pub umask: u32,
+
+    /// Mode of files that don't store a Unix mode, before the umask is applied.
+    ///
+    /// Defaults to `0o666`.
+    pub file_mode: u32,
+
+    /// Mode of directories that don't store a Unix mode, before the umask is applied.
+    ///
+    /// Defaults to `0o777`.
+    pub directory_mode: u32,
+
+    /// Use the mode stored in entries added on Unix.
+    ///
+    /// Defaults to `true`.
+    pub preserve_unix_mode: bool,
+}
+
+impl Default for PermissionPolicy {
+    fn default() -> Self {
+        Self {
+            umask: 0o022,
+            file_mode: 0o666,
+            directory_mode: 0o777,
+            preserve_unix_mode: true,
+        }
+    }
+}
+
+impl PermissionPolicy {
+    /// DOS attribute of read-only files.
+    const READ_ONLY: u64 = 0x01;
+
+    /// Permission bits of a Unix mode.
+    const PERMISSION_MASK: u32 = 0o777;
+
+    /// Bits removed from the mode of read-only entries.
+    const WRITE_BITS: u32 = 0o222;
+
+    /// Unix permission bits to give to `entry` once extracted.
+    pub fn mode(&self, entry: &FileEntry) -> u32 {
+        let mode = match entry.host_os {
+            HostOs::Unix if self.preserve_unix_mode => entry.attributes as u32,
+
+            _ => {
+                let mode = if entry.is_directory {
+                    self.directory_mode
+                } else {
+                    self.file_mode
+                };
+
+                let has_dos_attributes =
+                    matches!(entry.host_os, HostOs::MsDos | HostOs::Os2 | HostOs::Windows);
+
+                if has_dos_attributes && entry.attributes & Self::READ_ONLY != 0 {
+                    mode & !Self::WRITE_BITS
+                } else {
+                    mode
+                }
+            }
+        };
+
+        mode & Self::PERMISSION_MASK & !self.umask
+    }
+}
+
+#[test]
+fn test_sanitize_stream_name() {
+    assert_eq!(sanitize_stream_name("Zone.Identifier"), "Zone.Identifier");
+    assert_eq!(sanitize_stream_name("../a:b\\c"), ".._a_b_c");
+}
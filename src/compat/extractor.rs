@@ -0,0 +1,754 @@
+use std::{
+    fs,
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use crate::{
+    attributes::{FileAttributes, UnixFileType},
+    error::RarResult,
+    hash::Crc32,
+    path::sanitize_path,
+    rar50, Progress, RateLimiter, Throttle, ThrottledReader,
+};
+
+use super::{
+    apply_security_descriptor, ntfs_acl::entry_acl, peek::unpacked_reader, set_extended_attribute,
+    AlternateDataStream, Archive, Block, ExtractOptions, FileEntry, LinkAction, MacFileInfo,
+    NtfsAcl, StreamPolicy,
+};
+
+/// Longest target of a symbolic link stored as the data of its entry.
+const MAX_LINK_TARGET_SIZE: u64 = 0x10000;
+
+#[derive(Debug)]
+/// Result of extracting an entry with an [`Extractor`].
+pub enum ExtractStatus {
+    /// The entry was written out.
+    Extracted,
+
+    /// A file already exists at the path of the entry and
+    /// [`ExtractOptions::overwrite`] is disabled.
+    AlreadyExists,
+
+    /// The entry is a link which was not recreated because of [`ExtractOptions::links`].
+    LinkRejected,
+
+    /// The entry can't be extracted because it's encrypted, part of a solid stream, split
+    /// across volumes, compressed with an unsupported method, or needs a window larger
+    /// than the default [`Limits::max_window_size`](crate::Limits::max_window_size).
+    NotExtractable,
+
+    /// The entry couldn't be written out.
+    Failed(io::Error),
+}
+
+#[derive(Debug)]
+/// Result of extracting an entry with an [`Extractor`].
+pub struct ExtractedEntry {
+    /// Metadata of the entry.
+    pub entry: FileEntry,
+
+    /// Path the entry was extracted to.
+    pub path: PathBuf,
+
+    pub status: ExtractStatus,
+
+    /// Bytes of the entry read from the archive and unpacked. Only the data of files and
+    /// of the targets of RAR 3.x symbolic links is counted.
+    pub progress: Arc<Progress>,
+
+    /// Errors raised while restoring the modification time, permissions, streams, NTFS
+    /// permissions or Mac OS info of an entry which was otherwise extracted.
+    pub metadata_errors: Vec<io::Error>,
+}
+
+#[derive(Debug, Default)]
+/// Result of extracting every entry of an archive with [`Extractor::extract_all`].
+pub struct ExtractReport {
+    /// Results of the entries, in the order they appear in the archive.
+    pub entries: Vec<ExtractedEntry>,
+}
+
+impl ExtractReport {
+    /// No entry failed to be written out.
+    ///
+    /// Entries which were skipped or couldn't be extracted are not counted as failures,
+    /// and neither are errors restoring their metadata.
+    pub fn is_ok(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Entries which failed to be written out.
+    pub fn failures(&self) -> impl Iterator<Item = &ExtractedEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.status, ExtractStatus::Failed(_)))
+    }
+}
+
+/// Writes out the entries of an [`Archive`] to a destination directory, like `unrar x`.
+///
+/// Files and directories are created at the [`FileEntry::sanitized_path`] of their entry,
+/// so that they can't be written outside of the destination directory, and get the
+/// modification time and permissions stored in the archive. Links are extracted after all
+/// the other entries, according to [`ExtractOptions::links`].
+///
+/// MS-DOS timestamps are not timezone aware and are applied as if they were UTC.
+pub struct Extractor<'a, R: Read + Seek> {
+    archive: &'a mut Archive<R>,
+    destination: PathBuf,
+    options: ExtractOptions,
+    throttle: Option<Box<dyn Throttle + 'a>>,
+}
+
+/// File entry and the blocks following it that store its metadata.
+struct Item {
+    entry: FileEntry,
+    block: Block,
+    streams: Vec<AlternateDataStream>,
+    acl: Option<NtfsAcl>,
+    mac_info: Option<MacFileInfo>,
+}
+
+/// Link stored in a file entry.
+enum Link {
+    /// Symbolic link or junction, whose target is relative to the directory containing it
+    /// unless it's absolute.
+    Symbolic { target: String, is_directory: bool },
+
+    /// Hard link to the file extracted at this path.
+    Hard(PathBuf),
+
+    /// Copy of the file extracted at this path.
+    Copy(PathBuf),
+}
+
+impl<'a, R: Read + Seek> Extractor<'a, R> {
+    /// Extract the entries of `archive` to `destination` with the default options.
+    pub fn new(archive: &'a mut Archive<R>, destination: impl Into<PathBuf>) -> Self {
+        Self::with_options(archive, destination, ExtractOptions::default())
+    }
+
+    pub fn with_options(
+        archive: &'a mut Archive<R>,
+        destination: impl Into<PathBuf>,
+        options: ExtractOptions,
+    ) -> Self {
+        let throttle = options
+            .max_bytes_per_second
+            .map(|rate| Box::new(RateLimiter::new(rate)) as Box<dyn Throttle>);
+
+        Self {
+            archive,
+            destination: destination.into(),
+            options,
+            throttle,
+        }
+    }
+
+    /// Call `throttle` with the number of bytes read from the data areas of the archive
+    /// after every read, instead of the limit set by [`ExtractOptions::max_bytes_per_second`].
+    pub fn with_throttle(mut self, throttle: impl Throttle + 'a) -> Self {
+        self.throttle = Some(Box::new(throttle));
+        self
+    }
+
+    /// Extract every entry of the archive.
+    ///
+    /// The destination directory is created if it doesn't exist. Errors writing out an
+    /// entry are reported in its [`ExtractedEntry`] and don't stop the extraction.
+    pub fn extract_all(&mut self) -> RarResult<ExtractReport> {
+        let items = self.items()?;
+        fs::create_dir_all(&self.destination)?;
+
+        let mut report = ExtractReport::default();
+        let mut links = vec![];
+        for (index, item) in items.iter().enumerate() {
+            let path = self.destination.join(item.entry.sanitized_path());
+            let mut metadata_errors = vec![];
+            let progress = match &self.options.progress {
+                Some(total) => total.child(),
+                None => Progress::new(),
+            };
+
+            let status = match self.link(item, &progress) {
+                // Links are extracted once all the files are, so that their targets exist.
+                Ok(Some(link)) => {
+                    links.push((index, link));
+                    ExtractStatus::Extracted
+                }
+                Ok(None) => self
+                    .extract(item, &path, &progress, &mut metadata_errors)
+                    .unwrap_or_else(ExtractStatus::Failed),
+                Err(e) => ExtractStatus::Failed(e),
+            };
+
+            report.entries.push(ExtractedEntry {
+                entry: item.entry.clone(),
+                path,
+                status,
+                progress,
+                metadata_errors,
+            });
+        }
+
+        for (index, link) in links {
+            let extracted = &mut report.entries[index];
+            extracted.status = self
+                .extract_link(link, &extracted.path)
+                .unwrap_or_else(ExtractStatus::Failed);
+        }
+
+        // The metadata of directories is restored once their contents are extracted, as
+        // extracting them changes their modification time and they could be read-only.
+        for (item, extracted) in items.iter().zip(&mut report.entries).rev() {
+            if item.entry.is_directory && matches!(extracted.status, ExtractStatus::Extracted) {
+                self.restore_metadata(item, &extracted.path, &mut extracted.metadata_errors);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Read the file entries of the archive and the blocks following them.
+    fn items(&mut self) -> RarResult<Vec<Item>> {
+        let mut items: Vec<Item> = vec![];
+        for block in self.archive.blocks()? {
+            let block = block?;
+            if let Some(entry) = FileEntry::from_block(&block) {
+                items.push(Item {
+                    entry,
+                    block,
+                    streams: vec![],
+                    acl: None,
+                    mac_info: None,
+                });
+            } else if let Some(item) = items.last_mut() {
+                if let Some(stream) = AlternateDataStream::from_block(&block) {
+                    item.streams.push(stream);
+                } else if let Some(info) = MacFileInfo::from_block(&block) {
+                    item.mac_info = Some(info);
+                } else if let Some(acl) = entry_acl(&block) {
+                    item.acl = Some(acl);
+                }
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Check that an entry can be written at `path` and create its parent directories.
+    fn prepare(&self, path: &Path) -> io::Result<()> {
+        if path == self.destination {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "entry name is empty",
+            ));
+        }
+
+        if !self.options.links.allow_outside_destination {
+            check_no_symlink(&self.destination, path)?;
+        }
+
+        match path.parent() {
+            Some(parent) => fs::create_dir_all(parent),
+            None => Ok(()),
+        }
+    }
+
+    fn extract(
+        &mut self,
+        item: &Item,
+        path: &Path,
+        progress: &Arc<Progress>,
+        metadata_errors: &mut Vec<io::Error>,
+    ) -> io::Result<ExtractStatus> {
+        self.prepare(path)?;
+
+        if item.entry.is_directory {
+            if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_symlink()) {
+                return Ok(ExtractStatus::AlreadyExists);
+            }
+            fs::create_dir_all(path)?;
+            return Ok(ExtractStatus::Extracted);
+        }
+
+        let status = self.extract_file(item, path, progress)?;
+        if matches!(status, ExtractStatus::Extracted) {
+            self.restore_metadata(item, path, metadata_errors);
+        }
+
+        Ok(status)
+    }
+
+    /// Link stored in the entry of `item`, reading the target of RAR 3.x symbolic links
+    /// from the data of the entry.
+    fn link(&mut self, item: &Item, progress: &Arc<Progress>) -> io::Result<Option<Link>> {
+        if item.entry.is_directory {
+            return Ok(None);
+        }
+
+        if let Block::Rar50(block) = &item.block {
+            if let rar50::BlockKind::File(rar50::FileBlock {
+                filesystem_redirection: Some(redirection),
+                ..
+            }) = &block.kind
+            {
+                let target = redirection.name.clone();
+                let is_directory = redirection.flags.is_directory();
+
+                return Ok(match redirection.redirection_type {
+                    rar50::FileSystemRedirectionType::UnixSymlink => Some(Link::Symbolic {
+                        target,
+                        is_directory,
+                    }),
+                    rar50::FileSystemRedirectionType::WindowsSymlink
+                    | rar50::FileSystemRedirectionType::WindowsJunction => Some(Link::Symbolic {
+                        target: target.replace('\\', "/"),
+                        is_directory,
+                    }),
+                    rar50::FileSystemRedirectionType::HardLink => {
+                        Some(Link::Hard(self.destination.join(sanitize_path(&target))))
+                    }
+                    rar50::FileSystemRedirectionType::FileCopy => {
+                        Some(Link::Copy(self.destination.join(sanitize_path(&target))))
+                    }
+                    rar50::FileSystemRedirectionType::Unknown(_) => None,
+                });
+            }
+        }
+
+        let FileAttributes::Unix(mode) = item.entry.attributes() else {
+            return Ok(None);
+        };
+        if mode.file_type() != Some(UnixFileType::SymbolicLink) {
+            return Ok(None);
+        }
+
+        let reader = self.archive.reader();
+        reader.seek(io::SeekFrom::Start(
+            item.entry.offset + item.entry.header_size,
+        ))?;
+        let packed = throttled(reader.take(item.entry.packed_size), &mut self.throttle);
+        let packed = progress.packed_reader(packed);
+
+        let Some((unpacked, _)) = unpacked_reader(packed, &item.entry, &item.block) else {
+            return Ok(None);
+        };
+        let unpacked = progress.unpacked_reader(unpacked);
+
+        let mut target = vec![];
+        unpacked
+            .take(MAX_LINK_TARGET_SIZE)
+            .read_to_end(&mut target)?;
+        let target =
+            String::from_utf8(target).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(Some(Link::Symbolic {
+            target,
+            is_directory: false,
+        }))
+    }
+
+    fn extract_file(
+        &mut self,
+        item: &Item,
+        path: &Path,
+        progress: &Arc<Progress>,
+    ) -> io::Result<ExtractStatus> {
+        let entry = &item.entry;
+        let reader = self.archive.reader();
+        reader.seek(io::SeekFrom::Start(entry.offset + entry.header_size))?;
+        let packed = throttled(reader.take(entry.packed_size), &mut self.throttle);
+        let packed = progress.packed_reader(packed);
+
+        let Some((unpacked, _)) = unpacked_reader(packed, entry, &item.block) else {
+            return Ok(ExtractStatus::NotExtractable);
+        };
+        let mut unpacked = progress.unpacked_reader(unpacked);
+
+        let Some(mut file) = create_file(path, self.options.overwrite)? else {
+            return Ok(ExtractStatus::AlreadyExists);
+        };
+
+        // Decoders check the hashes of the data once they reach its end.
+        if let Err(e) = io::copy(&mut unpacked, &mut file) {
+            drop(file);
+            let _ = fs::remove_file(path);
+            return Err(e);
+        }
+
+        Ok(ExtractStatus::Extracted)
+    }
+
+    fn extract_link(&mut self, link: Link, path: &Path) -> io::Result<ExtractStatus> {
+        let policy = self.options.links;
+        let (action, source) = match &link {
+            Link::Symbolic { target, .. } => {
+                let parent = path.parent().unwrap_or(&self.destination);
+                (policy.symbolic_links, parent.join(target))
+            }
+            Link::Hard(source) => (policy.hard_links, source.clone()),
+            Link::Copy(source) => (policy.file_copies, source.clone()),
+        };
+
+        if action == LinkAction::Skip {
+            return Ok(ExtractStatus::LinkRejected);
+        }
+
+        self.prepare(path)?;
+
+        if let Link::Symbolic { target, .. } = &link {
+            let relative = path.strip_prefix(&self.destination).unwrap_or(path);
+            if !policy.allow_outside_destination
+                && (!is_contained_link(relative, target)
+                    || goes_through_unresolved_path(path.parent().unwrap_or(path), target))
+            {
+                return Ok(ExtractStatus::LinkRejected);
+            }
+        }
+
+        match (&link, action) {
+            (
+                Link::Symbolic {
+                    target,
+                    is_directory,
+                },
+                LinkAction::Link,
+            ) => {
+                if !self.remove_existing(path)? {
+                    return Ok(ExtractStatus::AlreadyExists);
+                }
+                create_symlink(target, path, *is_directory)?;
+            }
+
+            // Hard links and copies point to the file itself, so its real path is checked
+            // after resolving any symbolic link.
+            _ => {
+                let source = fs::canonicalize(source)?;
+                if !policy.allow_outside_destination
+                    && !source.starts_with(fs::canonicalize(&self.destination)?)
+                {
+                    return Ok(ExtractStatus::LinkRejected);
+                }
+                if !fs::metadata(&source)?.is_file() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        "only links to files can be extracted as hard links or copies",
+                    ));
+                }
+
+                if !self.remove_existing(path)? {
+                    return Ok(ExtractStatus::AlreadyExists);
+                }
+                if action == LinkAction::Link {
+                    fs::hard_link(source, path)?;
+                } else {
+                    fs::copy(source, path)?;
+                }
+            }
+        }
+
+        Ok(ExtractStatus::Extracted)
+    }
+
+    /// Remove the file at `path` if it exists and can be overwritten, returning whether
+    /// `path` is free.
+    fn remove_existing(&self, path: &Path) -> io::Result<bool> {
+        if fs::symlink_metadata(path).is_err() {
+            return Ok(true);
+        }
+        if !self.options.overwrite {
+            return Ok(false);
+        }
+
+        fs::remove_file(path)?;
+        Ok(true)
+    }
+
+    /// Restore the streams, modification time, Mac OS info, permissions and NTFS
+    /// permissions of the entry extracted to `path`, in this order so that read-only
+    /// entries can still be modified.
+    fn restore_metadata(&mut self, item: &Item, path: &Path, errors: &mut Vec<io::Error>) {
+        if self.options.streams != StreamPolicy::Skip {
+            for stream in &item.streams {
+                if let Err(e) = self.extract_stream(path, stream) {
+                    errors.push(e);
+                }
+            }
+        }
+
+        if let Some(time) = item.entry.modification_time {
+            let offset = self.options.local_offset.unwrap_or(time::UtcOffset::UTC);
+            if let Err(e) = set_modified(path, time.assume_offset(offset).into()) {
+                errors.push(e);
+            }
+        }
+
+        if let (true, Some(info)) = (self.options.keep_mac_info, &item.mac_info) {
+            match set_extended_attribute(path, MacFileInfo::FINDER_INFO, &info.finder_info()) {
+                Err(e) if e.kind() != io::ErrorKind::Unsupported => errors.push(e),
+                _ => {}
+            }
+        }
+
+        if let Err(e) = set_permissions(path, self.options.permissions.mode(&item.entry)) {
+            errors.push(e);
+        }
+
+        if let (true, Some(acl)) = (
+            self.options.keep_ntfs_permissions && cfg!(windows),
+            &item.acl,
+        ) {
+            let applied = acl
+                .read_data(self.archive.reader())
+                .and_then(|descriptor| apply_security_descriptor(path, &descriptor));
+            if let Err(e) = applied {
+                errors.push(e);
+            }
+        }
+    }
+
+    fn extract_stream(&mut self, path: &Path, stream: &AlternateDataStream) -> io::Result<()> {
+        // Compressed RAR 2.x streams are small enough to be unpacked in memory.
+        let data: Box<dyn Read> = match (stream.is_stored, stream.unpack_version) {
+            (false, Some(_)) => Box::new(io::Cursor::new(stream.read_data(self.archive.reader())?)),
+            _ => Box::new(stream.data_reader(self.archive.reader())?),
+        };
+
+        let Some((_, mut file)) = self.options.streams.create(path, &stream.name_lossy())? else {
+            return Ok(());
+        };
+
+        copy_checked(data, &mut file, stream.crc32)
+    }
+}
+
+/// Wrap the reader of a data area so that the bytes read from it are reported to
+/// `throttle`, if any.
+fn throttled<'a: 'b, 'b>(
+    reader: impl Read + 'b,
+    throttle: &'b mut Option<Box<dyn Throttle + 'a>>,
+) -> Box<dyn Read + 'b> {
+    Box::new(ThrottledReader::new(reader, move |bytes| {
+        if let Some(throttle) = throttle {
+            throttle.consume(bytes);
+        }
+    }))
+}
+
+/// Copy `reader` to `writer`, checking the CRC32 of the data if there is one.
+fn copy_checked(
+    mut reader: impl Read,
+    writer: &mut impl Write,
+    crc32: Option<u32>,
+) -> io::Result<()> {
+    let mut hasher = Crc32::new();
+    let mut buf = vec![0; 0x10000];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                hasher.update(&buf[..n]);
+                writer.write_all(&buf[..n])?;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    match crc32 {
+        Some(expected) if hasher.finalize() != expected => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "CRC32 of the data doesn't match",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Fail if a component of `path` below `destination`, other than the last one, is a
+/// symbolic link, so that entries can't be written outside of `destination` through a
+/// link extracted before them.
+fn check_no_symlink(destination: &Path, path: &Path) -> io::Result<()> {
+    let Ok(relative) = path.strip_prefix(destination) else {
+        return Ok(());
+    };
+
+    let mut components = relative.components();
+    components.next_back();
+
+    let mut current = destination.to_path_buf();
+    for component in components {
+        current.push(component);
+        if fs::symlink_metadata(&current).is_ok_and(|metadata| metadata.is_symlink()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "path of the entry goes through a symbolic link",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the symbolic link at the relative path `link` and pointing to `target` stays
+/// inside the directory `link` is relative to.
+///
+/// Both `/` and `\` are treated as separators in `target`, and absolute targets are never
+/// contained.
+fn is_contained_link(link: &Path, target: &str) -> bool {
+    if target.starts_with(['/', '\\']) || target.get(1..2) == Some(":") {
+        return false;
+    }
+
+    let mut depth = link.components().count().saturating_sub(1);
+    for component in target.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => match depth.checked_sub(1) {
+                Some(parent) => depth = parent,
+                None => return false,
+            },
+            _ => depth += 1,
+        }
+    }
+
+    true
+}
+
+/// Whether resolving `target` from the directory `parent` goes through a symbolic link or
+/// through a path which doesn't exist, and so could be a link extracted later.
+///
+/// [`is_contained_link`] only counts the components of the target, which is only enough if
+/// every directory the target goes through is a real one: a link `d` pointing to `.` makes
+/// `d/..` point to the parent of the destination. The last component of the target can be
+/// a link, because links are checked relative to their own location.
+fn goes_through_unresolved_path(parent: &Path, target: &str) -> bool {
+    let mut components: Vec<_> = target
+        .split(['/', '\\'])
+        .filter(|component| !matches!(*component, "" | "."))
+        .collect();
+    components.pop();
+
+    let mut current = parent.to_path_buf();
+    for component in components {
+        if component == ".." {
+            current.pop();
+            continue;
+        }
+
+        current.push(component);
+        match fs::symlink_metadata(&current) {
+            Ok(metadata) if !metadata.is_symlink() => {}
+            _ => return true,
+        }
+    }
+
+    false
+}
+
+/// Create a file at `path`, or return `None` if it already exists and can't be
+/// overwritten.
+///
+/// Existing files are removed before being replaced, so that a symbolic link at `path` is
+/// replaced instead of having its target written to.
+fn create_file(path: &Path, overwrite: bool) -> io::Result<Option<fs::File>> {
+    let create = || {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+    };
+
+    match create() {
+        Ok(file) => Ok(Some(file)),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            if !overwrite {
+                return Ok(None);
+            }
+            fs::remove_file(path)?;
+            create().map(Some)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn create_symlink(target: &str, path: &Path, is_directory: bool) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let _ = is_directory;
+        std::os::unix::fs::symlink(target, path)
+    }
+
+    #[cfg(windows)]
+    {
+        let target = target.replace('/', "\\");
+        if is_directory {
+            std::os::windows::fs::symlink_dir(target, path)
+        } else {
+            std::os::windows::fs::symlink_file(target, path)
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (target, path, is_directory);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "symbolic links are not supported on this platform",
+        ))
+    }
+}
+
+fn set_modified(path: &Path, time: SystemTime) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt as _;
+
+        // Directories can only be opened with backup semantics.
+        const FILE_WRITE_ATTRIBUTES: u32 = 0x0100;
+        const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+        options
+            .access_mode(FILE_WRITE_ATTRIBUTES)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS);
+    }
+
+    #[cfg(not(windows))]
+    options.read(true);
+
+    options.open(path)?.set_modified(time)
+}
+
+/// Set the Unix permissions of the file at `path` to `mode`, or only make it read-only if
+/// `mode` has no write bits on other platforms.
+fn set_permissions(path: &Path, mode: u32) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+    }
+
+    #[cfg(not(unix))]
+    {
+        let mut permissions = fs::metadata(path)?.permissions();
+        permissions.set_readonly(mode & 0o222 == 0);
+        fs::set_permissions(path, permissions)
+    }
+}
+
+#[test]
+fn test_is_contained_link() {
+    assert!(is_contained_link(Path::new("link"), "dir/file.txt"));
+    assert!(is_contained_link(Path::new("a/b/link"), "../../c"));
+    assert!(is_contained_link(Path::new("a/link"), "./b/../../c"));
+    assert!(!is_contained_link(Path::new("a/link"), "../../c"));
+    assert!(!is_contained_link(Path::new("link"), "../../etc/passwd"));
+    assert!(!is_contained_link(Path::new("link"), "/etc/passwd"));
+    assert!(!is_contained_link(Path::new("link"), "C:/Windows"));
+    assert!(!is_contained_link(Path::new("a/link"), "..\\..\\c"));
+}
@@ -0,0 +1,281 @@
+use std::{
+    collections::BTreeSet,
+    io::{Read, Seek},
+};
+
+use crate::{
+    error::{Error, RarResult},
+    rar14, rar15, rar50,
+    signature::Signature,
+};
+
+use super::{Archive, Block, FileEntry, HashKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// Encryption scheme used by an archive.
+pub enum EncryptionScheme {
+    /// Proprietary stream cipher of RAR 1.3.
+    Rar13,
+
+    /// Proprietary stream cipher of RAR 1.5.
+    Rar15,
+
+    /// Proprietary block cipher of RAR 2.0.
+    Rar20,
+
+    /// AES-128 with the SHA-1 based key derivation of RAR 3.0.
+    Aes128,
+
+    /// AES-256 with the PBKDF2-HMAC-SHA256 key derivation of RAR 5.0.
+    Aes256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// Type of the hash used to check the data of the entries.
+pub enum HashType {
+    Crc16,
+    Crc32,
+    Blake2sp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Change to a feature of an archive that would happen by re-creating it in another
+/// format, as reported by [`FormatFeatures::changes_for`].
+pub enum FeatureChange {
+    /// Files encrypted with `from` would be encrypted with `to`.
+    FileEncryption {
+        from: EncryptionScheme,
+        to: EncryptionScheme,
+    },
+
+    /// Headers encrypted with `from` would be encrypted with `to`, or couldn't be encrypted
+    /// if `to` is `None`.
+    HeaderEncryption {
+        from: EncryptionScheme,
+        to: Option<EncryptionScheme>,
+    },
+
+    /// Files compressed with a dictionary of `from` bytes would be compressed with the
+    /// largest dictionary supported by the format, which is `to` bytes.
+    DictionarySize { from: u64, to: u64 },
+
+    /// Entries checked with `from` would be checked with `to`.
+    Hash { from: HashType, to: HashType },
+
+    /// The recovery record would be rebuilt in the format of the new archive, or lost if
+    /// the format doesn't support it.
+    RecoveryRecord { lost: bool },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Features used by an archive whose support depends on the format.
+///
+/// Returned by [`Archive::format_features`].
+pub struct FormatFeatures {
+    /// Format of the archive.
+    pub format: Signature,
+
+    /// Schemes used to encrypt the data of the entries.
+    pub file_encryption: BTreeSet<EncryptionScheme>,
+
+    /// Scheme used to encrypt the block headers, if they are encrypted.
+    ///
+    /// The other features of archives with encrypted headers are only collected from the
+    /// blocks before the first encrypted one.
+    pub header_encryption: Option<EncryptionScheme>,
+
+    /// Largest dictionary size needed to decompress an entry, if known.
+    pub max_dictionary_size: Option<u64>,
+
+    /// Types of the hashes stored for the entries.
+    pub hashes: BTreeSet<HashType>,
+
+    /// The archive contains a recovery record.
+    pub recovery_record: bool,
+}
+
+impl FormatFeatures {
+    fn new(format: Signature) -> Self {
+        Self {
+            format,
+            file_encryption: BTreeSet::new(),
+            header_encryption: None,
+            max_dictionary_size: None,
+            hashes: BTreeSet::new(),
+            recovery_record: false,
+        }
+    }
+
+    fn add_block(&mut self, block: &Block) {
+        match block {
+            Block::Rar14(rar14::Block::File(file)) if file.is_encrypted() => {
+                self.file_encryption.insert(EncryptionScheme::Rar13);
+            }
+
+            Block::Rar15(block) => match &block.kind {
+                rar15::BlockKind::Main(main) => {
+                    if main.has_password() {
+                        self.header_encryption = Some(EncryptionScheme::Aes128);
+                    }
+                    self.recovery_record |= main.has_recovery_record();
+                }
+                rar15::BlockKind::File(file) => {
+                    if file.is_encrypted() {
+                        self.file_encryption
+                            .insert(rar15_encryption_scheme(file.unpack_version));
+                    }
+                    if !file.is_directory() && file.method != rar15::FileBlock::METHOD_STORE {
+                        self.add_dictionary_size(file.dictionary_size());
+                    }
+                }
+                rar15::BlockKind::Protect(_) => self.recovery_record = true,
+                _ => {}
+            },
+
+            Block::Rar50(block) => match &block.kind {
+                rar50::BlockKind::Main(main) => {
+                    self.recovery_record |= main.has_recovery_record();
+                }
+                rar50::BlockKind::Crypt(_) => {
+                    self.header_encryption = Some(EncryptionScheme::Aes256);
+                }
+                rar50::BlockKind::File(file) => {
+                    if file.encryption.is_some() {
+                        self.file_encryption.insert(EncryptionScheme::Aes256);
+                    }
+                    let info = &file.compression_info;
+                    if info.method() != rar50::CompressionMethod::NoCompression {
+                        self.add_dictionary_size(
+                            info.min_dictionary_size().unwrap_or_else(|size| size),
+                        );
+                    }
+                }
+                rar50::BlockKind::Service(service) => {
+                    if matches!(service.kind, rar50::ServiceBlockKind::RecoveryRecord(_)) {
+                        self.recovery_record = true;
+                    }
+                }
+                _ => {}
+            },
+
+            _ => {}
+        }
+
+        if let Some(entry) = FileEntry::from_block(block) {
+            match entry.checksum {
+                Some(HashKind::Crc16(_)) => self.hashes.insert(HashType::Crc16),
+                Some(HashKind::Crc32(_)) => self.hashes.insert(HashType::Crc32),
+                None => false,
+            };
+            if entry.blake2sp.is_some() {
+                self.hashes.insert(HashType::Blake2sp);
+            }
+        }
+    }
+
+    fn add_dictionary_size(&mut self, size: u64) {
+        self.max_dictionary_size = Some(self.max_dictionary_size.map_or(size, |max| max.max(size)));
+    }
+
+    /// Features that would be lost or changed by re-creating the archive in the `target`
+    /// format with the most recent version of RAR supporting it.
+    ///
+    /// Files encrypted with older schemes are reported as changed even if the format stays
+    /// the same, since RAR only encrypts new archives with the latest scheme of the format.
+    pub fn changes_for(&self, target: Signature) -> Vec<FeatureChange> {
+        let mut changes = vec![];
+
+        let encryption = match target {
+            Signature::Rar14 => EncryptionScheme::Rar13,
+            Signature::Rar15 => EncryptionScheme::Aes128,
+            Signature::Rar50 => EncryptionScheme::Aes256,
+        };
+
+        for &from in &self.file_encryption {
+            if from != encryption {
+                changes.push(FeatureChange::FileEncryption {
+                    from,
+                    to: encryption,
+                });
+            }
+        }
+
+        if let Some(from) = self.header_encryption {
+            let to = match target {
+                Signature::Rar14 => None,
+                _ => Some(encryption),
+            };
+            if to != Some(from) {
+                changes.push(FeatureChange::HeaderEncryption { from, to });
+            }
+        }
+
+        let max_dictionary_size = match target {
+            Signature::Rar14 => 0x1_0000,
+            Signature::Rar15 => 0x40_0000,
+            Signature::Rar50 => rar50::CompressionInfo::MAX_DICT_SIZE,
+        };
+        if let Some(from) = self.max_dictionary_size {
+            if from > max_dictionary_size {
+                changes.push(FeatureChange::DictionarySize {
+                    from,
+                    to: max_dictionary_size,
+                });
+            }
+        }
+
+        let hash = match target {
+            Signature::Rar14 => HashType::Crc16,
+            _ => HashType::Crc32,
+        };
+        for &from in &self.hashes {
+            let supported =
+                from == hash || (from == HashType::Blake2sp && target == Signature::Rar50);
+            if !supported {
+                changes.push(FeatureChange::Hash { from, to: hash });
+            }
+        }
+
+        if self.recovery_record && target != self.format {
+            changes.push(FeatureChange::RecoveryRecord {
+                lost: target == Signature::Rar14,
+            });
+        }
+
+        changes
+    }
+}
+
+/// Encryption scheme of a RAR15 file, which depends on the version of the algorithm used
+/// to compress it.
+pub(super) fn rar15_encryption_scheme(unpack_version: u8) -> EncryptionScheme {
+    match unpack_version {
+        29.. => EncryptionScheme::Aes128,
+        20.. => EncryptionScheme::Rar20,
+        15.. => EncryptionScheme::Rar15,
+        _ => EncryptionScheme::Rar13,
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Collect the features used by the archive whose support depends on the format, to
+    /// find out what would change by re-creating it in another format with
+    /// [`FormatFeatures::changes_for`].
+    ///
+    /// Only the block headers are read.
+    pub fn format_features(&mut self) -> RarResult<FormatFeatures> {
+        let mut features = FormatFeatures::new(self.format());
+
+        for block in self.blocks()? {
+            match block {
+                Ok(block) => features.add_block(&block),
+
+                // The rest of the blocks can't be read without a password.
+                Err(Error::PasswordRequired) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(features)
+    }
+}
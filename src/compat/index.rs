@@ -0,0 +1,315 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read, Seek},
+};
+
+use crate::{
+    error::RarResult,
+    read::{read_const_bytes, read_u32, read_u64, read_u8, read_vec},
+};
+
+use super::{Archive, FileEntry, HashKind, MultiVolumeReader};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Location and metadata of an entry stored in an [`ArchiveIndex`].
+pub struct IndexEntry {
+    /// Name of the entry, with invalid UTF-8 sequences replaced.
+    pub name: String,
+
+    /// Index of the volume containing the block of the entry, or of its first segment if
+    /// it spans multiple volumes.
+    pub volume: usize,
+
+    /// Offset of the block of the entry in its volume.
+    pub offset: u64,
+
+    /// Size of the header of the block of the entry.
+    pub header_size: u64,
+
+    /// Size of the packed data of the entry, including all its segments.
+    pub packed_size: u64,
+
+    /// Size of the entry after decompression, if known.
+    pub unpacked_size: Option<u64>,
+
+    /// CRC of the unpacked data.
+    pub checksum: Option<HashKind>,
+
+    /// BLAKE2sp hash of the unpacked data.
+    pub blake2sp: Option<[u8; 32]>,
+
+    /// Entry is a directory.
+    pub is_directory: bool,
+}
+
+impl IndexEntry {
+    fn from_entry(entry: &FileEntry, volume: usize) -> Self {
+        Self {
+            name: entry.name_lossy().into_owned(),
+            volume,
+            offset: entry.offset,
+            header_size: entry.header_size,
+            packed_size: entry.packed_size,
+            unpacked_size: entry.unpacked_size,
+            checksum: entry.checksum,
+            blake2sp: entry.blake2sp,
+            is_directory: entry.is_directory,
+        }
+    }
+}
+
+flags! {
+    /// Flags of an [`IndexEntry`] in the serialized index.
+    pub(crate) struct IndexEntryFlags(u8) {
+        /// Entry is a directory.
+        is_directory = 0x01;
+
+        /// The unpacked size of the entry is known.
+        has_unpacked_size = 0x02;
+
+        /// The checksum field is a CRC16.
+        has_crc16 = 0x04;
+
+        /// The checksum field is a CRC32.
+        has_crc32 = 0x08;
+
+        /// The BLAKE2sp hash field is present.
+        has_blake2sp = 0x10;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Index of the entries of an archive by path, built from a single pass over its blocks.
+///
+/// The index can be saved with [`Self::write_to`] and loaded with [`Self::read_from`], so
+/// that huge archives don't need to be scanned again each time they're opened. It's not
+/// updated when the archive changes, so it must be built again if it's modified.
+///
+/// Both `/` and `\` are treated as path separators, so paths can be looked up with either.
+pub struct ArchiveIndex {
+    entries: Vec<IndexEntry>,
+
+    /// Index of the first entry with each normalized path.
+    by_path: HashMap<String, usize>,
+
+    /// Index of the first entry with each lowercase normalized path.
+    by_lowercase_path: HashMap<String, usize>,
+}
+
+impl ArchiveIndex {
+    /// Magic bytes at the start of a serialized index.
+    const MAGIC: &'static [u8; 4] = b"RXDI";
+
+    /// Version of the serialization format.
+    const VERSION: u8 = 1;
+
+    /// Build an index of `entries`, which must be in the order they appear in the archive.
+    pub fn from_entries(entries: impl IntoIterator<Item = IndexEntry>) -> Self {
+        let mut index = Self::default();
+
+        for entry in entries {
+            let path = normalize_path(&entry.name);
+            let position = index.entries.len();
+
+            index
+                .by_lowercase_path
+                .entry(path.to_lowercase())
+                .or_insert(position);
+            index.by_path.entry(path).or_insert(position);
+            index.entries.push(entry);
+        }
+
+        index
+    }
+
+    /// Entries of the archive, in the order they appear in the archive.
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The index doesn't contain any entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// First entry whose path is `path`.
+    pub fn get(&self, path: &str) -> Option<&IndexEntry> {
+        let &position = self.by_path.get(&normalize_path(path))?;
+        self.entries.get(position)
+    }
+
+    /// First entry whose path is `path`, ignoring case.
+    pub fn get_ignore_case(&self, path: &str) -> Option<&IndexEntry> {
+        let &position = self
+            .by_lowercase_path
+            .get(&normalize_path(path).to_lowercase())?;
+        self.entries.get(position)
+    }
+
+    /// Entries whose path matches the glob `pattern`, in order.
+    ///
+    /// `?` matches any character and `*` matches any sequence of characters except for
+    /// path separators, while `**` also matches path separators.
+    pub fn glob<'a>(&'a self, pattern: &str) -> impl Iterator<Item = &'a IndexEntry> {
+        let pattern: Vec<char> = normalize_path(pattern).chars().collect();
+
+        self.entries.iter().filter(move |entry| {
+            let path: Vec<char> = normalize_path(&entry.name).chars().collect();
+            glob_match(&pattern, &path)
+        })
+    }
+
+    /// Serialize the index to `writer`.
+    pub fn write_to<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(Self::MAGIC)?;
+        writer.write_all(&[Self::VERSION])?;
+        writer.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+
+        for entry in &self.entries {
+            let flags = (entry.is_directory as u8)
+                | (entry.unpacked_size.is_some() as u8) << 1
+                | (matches!(entry.checksum, Some(HashKind::Crc16(_))) as u8) << 2
+                | (matches!(entry.checksum, Some(HashKind::Crc32(_))) as u8) << 3
+                | (entry.blake2sp.is_some() as u8) << 4;
+            let checksum = match entry.checksum {
+                Some(HashKind::Crc16(crc)) => crc as u32,
+                Some(HashKind::Crc32(crc)) => crc,
+                None => 0,
+            };
+
+            writer.write_all(&[flags])?;
+            writer.write_all(&(entry.name.len() as u32).to_le_bytes())?;
+            writer.write_all(entry.name.as_bytes())?;
+            writer.write_all(&(entry.volume as u32).to_le_bytes())?;
+            writer.write_all(&entry.offset.to_le_bytes())?;
+            writer.write_all(&entry.header_size.to_le_bytes())?;
+            writer.write_all(&entry.packed_size.to_le_bytes())?;
+            writer.write_all(&entry.unpacked_size.unwrap_or(0).to_le_bytes())?;
+            writer.write_all(&checksum.to_le_bytes())?;
+            writer.write_all(&entry.blake2sp.unwrap_or_default())?;
+        }
+
+        Ok(())
+    }
+
+    /// Deserialize an index written by [`Self::write_to`].
+    ///
+    /// Returns an [`io::ErrorKind::InvalidData`] error if the data was not written by a
+    /// compatible version of this library.
+    pub fn read_from<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let invalid_data = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        if &read_const_bytes::<4, _>(&mut reader)? != Self::MAGIC {
+            return Err(invalid_data("not a serialized archive index"));
+        }
+        if read_u8(&mut reader)? != Self::VERSION {
+            return Err(invalid_data("unsupported archive index version"));
+        }
+
+        let count = read_u64(&mut reader)?;
+        let mut entries = vec![];
+
+        for _ in 0..count {
+            let flags = IndexEntryFlags::new(read_u8(&mut reader)?);
+            let name_size = read_u32(&mut reader)?;
+            let name = String::from_utf8(read_vec(&mut reader, name_size as usize)?)
+                .map_err(|_| invalid_data("archive index name is not valid UTF-8"))?;
+            let volume = read_u32(&mut reader)? as usize;
+            let offset = read_u64(&mut reader)?;
+            let header_size = read_u64(&mut reader)?;
+            let packed_size = read_u64(&mut reader)?;
+            let unpacked_size = read_u64(&mut reader)?;
+            let checksum = read_u32(&mut reader)?;
+            let blake2sp = read_const_bytes(&mut reader)?;
+
+            entries.push(IndexEntry {
+                name,
+                volume,
+                offset,
+                header_size,
+                packed_size,
+                unpacked_size: flags.has_unpacked_size().then_some(unpacked_size),
+                checksum: if flags.has_crc16() {
+                    Some(HashKind::Crc16(checksum as u16))
+                } else if flags.has_crc32() {
+                    Some(HashKind::Crc32(checksum))
+                } else {
+                    None
+                },
+                blake2sp: flags.has_blake2sp().then_some(blake2sp),
+                is_directory: flags.is_directory(),
+            });
+        }
+
+        Ok(Self::from_entries(entries))
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Build an [`ArchiveIndex`] of the file entries of the archive, which is treated as
+    /// volume 0.
+    pub fn index(&mut self) -> RarResult<ArchiveIndex> {
+        let mut entries = vec![];
+
+        for block in self.blocks()? {
+            if let Some(entry) = FileEntry::from_owned_block(block?) {
+                entries.push(IndexEntry::from_entry(&entry, 0));
+            }
+        }
+
+        Ok(ArchiveIndex::from_entries(entries))
+    }
+}
+
+impl<R: Read + Seek> MultiVolumeReader<R> {
+    /// Build an [`ArchiveIndex`] of the entries of all the volumes.
+    pub fn index(&self) -> ArchiveIndex {
+        ArchiveIndex::from_entries(
+            self.entries()
+                .iter()
+                .map(|spanned| IndexEntry::from_entry(&spanned.entry, *spanned.volumes.start())),
+        )
+    }
+}
+
+/// Path with `\` separators replaced by `/`.
+fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+fn glob_match(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        ['*', '*', rest @ ..] => (0..=path.len()).any(|i| glob_match(rest, &path[i..])),
+        ['*', rest @ ..] => {
+            let segment_end = path.iter().position(|&c| c == '/').unwrap_or(path.len());
+            (0..=segment_end).any(|i| glob_match(rest, &path[i..]))
+        }
+        ['?', rest @ ..] => matches!(path, [c, ..] if *c != '/') && glob_match(rest, &path[1..]),
+        [c, rest @ ..] => path.first() == Some(c) && glob_match(rest, &path[1..]),
+    }
+}
+
+#[test]
+fn test_glob_match() {
+    let matches = |pattern: &str, path: &str| {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let path: Vec<char> = path.chars().collect();
+        glob_match(&pattern, &path)
+    };
+
+    assert!(matches("*.txt", "hello.txt"));
+    assert!(!matches("*.txt", "docs/hello.txt"));
+    assert!(matches("*/*.txt", "docs/hello.txt"));
+    assert!(matches("**.txt", "docs/hello.txt"));
+    assert!(matches("**/hello.txt", "a/b/hello.txt"));
+    assert!(matches("h?llo.txt", "hello.txt"));
+    assert!(!matches("docs?hello.txt", "docs/hello.txt"));
+    assert!(!matches("*.txt", "hello.txt.bak"));
+    assert!(matches("*", ""));
+}
@@ -0,0 +1,168 @@
+use std::{io, path::Path};
+
+use crate::rar15;
+
+use super::Block;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Classic Mac OS type and creator codes of a file, stored by RAR 2.x in the sub block
+/// which follows the file block.
+pub struct MacFileInfo {
+    /// Four-character type code, such as `TEXT`.
+    pub file_type: [u8; 4],
+
+    /// Four-character creator code of the application that created the file.
+    pub creator: [u8; 4],
+}
+
+impl MacFileInfo {
+    /// Name of the extended attribute which stores the Finder info of a file on macOS.
+    pub const FINDER_INFO: &'static str = "com.apple.FinderInfo";
+
+    /// Extract the type and creator codes stored in `block`.
+    ///
+    /// Returns `None` if the block doesn't contain them.
+    pub fn from_block(block: &Block) -> Option<Self> {
+        match block {
+            Block::Rar15(rar15::Block {
+                kind:
+                    rar15::BlockKind::Sub(rar15::SubBlock {
+                        kind: rar15::SubBlockKind::MacOsInfo(info),
+                        ..
+                    }),
+                ..
+            }) => Some(Self {
+                file_type: info.file_type.to_be_bytes(),
+                creator: info.file_creator.to_be_bytes(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Value of the [`Self::FINDER_INFO`] extended attribute with these codes.
+    ///
+    /// The type and creator are the first two fields of the 32 bytes of Finder info, and
+    /// the other fields are left empty.
+    pub fn finder_info(&self) -> [u8; 32] {
+        let mut info = [0; 32];
+        info[..4].copy_from_slice(&self.file_type);
+        info[4..8].copy_from_slice(&self.creator);
+        info
+    }
+}
+
+/// Set the extended attribute `name` of the file at `path` to `value`.
+///
+/// Attributes are set in the user namespace on BSD. Only supported on macOS, FreeBSD and
+/// NetBSD with the `xattr` feature; returns an [`io::ErrorKind::Unsupported`] error
+/// elsewhere.
+pub fn set_extended_attribute(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+    #[cfg(all(
+        feature = "xattr",
+        any(target_os = "macos", target_os = "freebsd", target_os = "netbsd")
+    ))]
+    {
+        xattr::set(path, name, value)
+    }
+
+    #[cfg(not(all(
+        feature = "xattr",
+        any(target_os = "macos", target_os = "freebsd", target_os = "netbsd")
+    )))]
+    {
+        let _ = (path, name, value);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "extended attributes are not supported on this platform",
+        ))
+    }
+}
+
+#[cfg(all(
+    feature = "xattr",
+    any(target_os = "macos", target_os = "freebsd", target_os = "netbsd")
+))]
+mod xattr {
+    use std::{
+        ffi::{c_char, c_int, c_void, CString},
+        io,
+        os::unix::ffi::OsStrExt as _,
+        path::Path,
+    };
+
+    #[cfg(target_os = "macos")]
+    extern "C" {
+        fn setxattr(
+            path: *const c_char,
+            name: *const c_char,
+            value: *const c_void,
+            size: usize,
+            position: u32,
+            options: c_int,
+        ) -> c_int;
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    extern "C" {
+        fn extattr_set_file(
+            path: *const c_char,
+            attr_namespace: c_int,
+            attr_name: *const c_char,
+            data: *const c_void,
+            nbytes: usize,
+        ) -> isize;
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    const EXTATTR_NAMESPACE_USER: c_int = 1;
+
+    fn c_string(bytes: &[u8]) -> io::Result<CString> {
+        CString::new(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    pub(super) fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        let path = c_string(path.as_os_str().as_bytes())?;
+        let name = c_string(name.as_bytes())?;
+
+        #[cfg(target_os = "macos")]
+        let failed = unsafe {
+            setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+                0,
+                0,
+            ) != 0
+        };
+
+        #[cfg(not(target_os = "macos"))]
+        let failed = unsafe {
+            extattr_set_file(
+                path.as_ptr(),
+                EXTATTR_NAMESPACE_USER,
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+            ) < 0
+        };
+
+        if failed {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[test]
+fn test_finder_info() {
+    let info = MacFileInfo {
+        file_type: *b"TEXT",
+        creator: *b"ttxt",
+    };
+
+    let finder_info = info.finder_info();
+    assert_eq!(&finder_info[..8], b"TEXTttxt");
+    assert!(finder_info[8..].iter().all(|&b| b == 0));
+}
@@ -0,0 +1,106 @@
+use std::io::{Read, Seek};
+
+use crate::{
+    error::{Error, RarResult},
+    rar15, rar50,
+};
+
+use super::{Archive, Block};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Memory needed to decompress the entries of an archive.
+///
+/// Returned by [`Archive::memory_requirements`].
+pub struct MemoryRequirements {
+    /// Largest dictionary size needed to decompress an entry, or `None` if no entry is
+    /// compressed.
+    ///
+    /// RAR 7 archives can use dictionaries of up to 64 GiB, and larger sizes are reported
+    /// as they are stored even though they can't be decompressed.
+    pub max_dictionary_size: Option<u64>,
+
+    /// Largest window the decoders of this library would allocate to decompress an entry,
+    /// or `None` if no entry is compressed.
+    ///
+    /// The window is only as large as the entry if it's smaller than the dictionary, except
+    /// for the entries of solid archives which need the whole dictionary.
+    pub max_window_size: Option<u64>,
+
+    /// Memory budget passed to [`Archive::memory_requirements`].
+    pub budget: u64,
+}
+
+impl MemoryRequirements {
+    fn add_entry(&mut self, dictionary_size: u64, unpacked_size: Option<u64>, is_solid: bool) {
+        let window_size = match unpacked_size {
+            Some(size) if !is_solid => size.min(dictionary_size),
+            _ => dictionary_size,
+        };
+
+        self.max_dictionary_size = self.max_dictionary_size.max(Some(dictionary_size));
+        self.max_window_size = self.max_window_size.max(Some(window_size));
+    }
+
+    fn add_block(&mut self, block: &Block) {
+        match block {
+            Block::Rar15(rar15::Block {
+                kind: rar15::BlockKind::File(file),
+                ..
+            }) if !file.is_directory() && file.method != rar15::FileBlock::METHOD_STORE => {
+                self.add_entry(
+                    file.dictionary_size(),
+                    Some(file.unpacked_data_size),
+                    file.is_solid(),
+                );
+            }
+
+            Block::Rar50(block) => {
+                if let rar50::BlockKind::File(file) = &block.kind {
+                    let info = &file.compression_info;
+                    if info.method() != rar50::CompressionMethod::NoCompression {
+                        self.add_entry(
+                            info.min_dictionary_size().unwrap_or_else(|size| size),
+                            file.unpacked_size,
+                            info.is_solid(),
+                        );
+                    }
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    /// Decompressing the entries of the archive needs more memory than the budget.
+    pub fn exceeds_budget(&self) -> bool {
+        self.max_window_size.is_some_and(|size| size > self.budget)
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Find out how much memory is needed to decompress the entries of the archive, and
+    /// whether it fits within `budget` bytes, before attempting to decompress them.
+    ///
+    /// Only the block headers are read. Entries of archives with encrypted headers are only
+    /// taken into account up to the first encrypted block, and RAR14 entries, which can't
+    /// be decompressed, are ignored.
+    pub fn memory_requirements(&mut self, budget: u64) -> RarResult<MemoryRequirements> {
+        let mut requirements = MemoryRequirements {
+            max_dictionary_size: None,
+            max_window_size: None,
+            budget,
+        };
+
+        for block in self.blocks()? {
+            match block {
+                Ok(block) => requirements.add_block(&block),
+
+                // The rest of the blocks can't be read without a password.
+                Err(Error::PasswordRequired) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(requirements)
+    }
+}
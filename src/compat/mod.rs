@@ -1,3 +1,65 @@
+mod acl;
+mod alternate_stream;
+mod archive;
 mod blocks;
+mod compact;
+mod encryption;
+mod entry;
+mod entry_index;
+mod entry_iterator;
+mod extract;
+mod extractor;
+mod features;
+mod index;
+mod mac_info;
+mod memory;
+mod multi_volume;
+mod nested;
+mod ntfs_acl;
+mod ntfs_security;
+mod owner;
+mod peek;
+mod segment_cache;
+mod sfx;
+mod solid;
+mod summary;
+mod trailing_data;
+mod unpacked_entry_reader;
+mod verify;
+mod versions;
+mod volume_set;
 
+pub use acl::*;
+pub use alternate_stream::*;
+pub use archive::*;
 pub use blocks::*;
+pub use compact::*;
+pub use encryption::*;
+pub use entry::*;
+pub use entry_iterator::*;
+pub use extract::*;
+pub use extractor::*;
+pub use features::*;
+pub use index::*;
+pub use mac_info::*;
+pub use memory::*;
+pub use multi_volume::*;
+pub use ntfs_acl::*;
+pub use ntfs_security::*;
+pub use owner::*;
+pub use peek::*;
+pub use segment_cache::*;
+pub use sfx::*;
+pub use solid::*;
+pub use summary::*;
+pub use unpacked_entry_reader::*;
+pub use verify::*;
+pub use versions::*;
+pub use volume_set::*;
+
+/// Flags and enums defined in this module, listed in [`crate::schema::MODULES`].
+pub(crate) const SCHEMA: crate::schema::ModuleSchema = crate::schema::ModuleSchema {
+    name: "rawrxd::compat",
+    flags: &[CompactEntryFlags::SCHEMA, IndexEntryFlags::SCHEMA],
+    enums: &[HostOs::SCHEMA],
+};
@@ -0,0 +1,505 @@
+use std::{
+    fs,
+    io::{self, Read, Seek},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{Error, RarResult},
+    rar14, rar15, rar50,
+};
+
+use super::{Archive, Block, FileEntry, Segment, SegmentCache};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Naming scheme of the volumes of a multi-volume archive.
+pub enum VolumeNaming {
+    /// `name.rar`, `name.r00`, `name.r01`, ..., `name.r99`, `name.s00`, ...
+    ///
+    /// Used by RAR 2.x and earlier, and by later versions with the `-vn` switch.
+    Old,
+
+    /// `name.part1.rar`, `name.part2.rar`, ..., with the volume number padded with zeros
+    /// to `digits` digits.
+    New { digits: usize },
+}
+
+impl VolumeNaming {
+    /// Detect the naming scheme from the path of the first volume.
+    ///
+    /// Paths whose file name ends with `.partN.rar` use the new scheme, and any other path
+    /// uses the old one.
+    pub fn detect(first: &Path) -> Self {
+        match new_naming_parts(first) {
+            Some((_, number, _)) => Self::New {
+                digits: number.len(),
+            },
+            None => Self::Old,
+        }
+    }
+
+    /// Path of the volume at `index`, given the path of the first volume.
+    ///
+    /// Returns `None` if the scheme can't name the volume, which happens past `name.z99`
+    /// with the old scheme, or if `first` doesn't follow the scheme.
+    pub fn volume_path(&self, first: &Path, index: usize) -> Option<PathBuf> {
+        match *self {
+            Self::Old => {
+                if index == 0 {
+                    return Some(first.to_path_buf());
+                }
+
+                let letter = b'r' + u8::try_from((index - 1) / 100).ok()?;
+                if letter > b'z' {
+                    return None;
+                }
+
+                // Follow the case of the extension of the first volume.
+                let is_upper = first
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| {
+                        !ext.is_empty() && ext.chars().all(|c| c.is_ascii_uppercase())
+                    });
+                let letter = if is_upper {
+                    letter.to_ascii_uppercase()
+                } else {
+                    letter
+                } as char;
+
+                Some(first.with_extension(format!("{letter}{:02}", (index - 1) % 100)))
+            }
+
+            Self::New { digits } => {
+                let (prefix, _, suffix) = new_naming_parts(first)?;
+                let file_name = format!("{prefix}{:0digits$}{suffix}", index + 1);
+                Some(first.with_file_name(file_name))
+            }
+        }
+    }
+}
+
+/// Split the file name of a volume using the new naming scheme into the part before the
+/// volume number, the volume number and the part after it.
+fn new_naming_parts(path: &Path) -> Option<(&str, &str, &str)> {
+    let file_name = path.file_name()?.to_str()?;
+
+    let dot = file_name.rfind('.')?;
+    let (rest, suffix) = file_name.split_at(dot);
+    if !suffix.eq_ignore_ascii_case(".rar") {
+        return None;
+    }
+
+    let digits_start = rest.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let (prefix, number) = rest.split_at(digits_start);
+    if number.is_empty() || !prefix.to_ascii_lowercase().ends_with(".part") {
+        return None;
+    }
+
+    Some((prefix, number, suffix))
+}
+
+/// Find the paths of the volumes of the archive whose first volume is at `first`.
+///
+/// The naming scheme is chosen from the main block of the first volume, and each volume is
+/// read to find out whether the archive continues in the next one. RAR50 archives and RAR15
+/// archives which use the new numbering only use the new scheme if `first` follows it.
+///
+/// Stops at the first volume that doesn't exist, whose path can be found with
+/// [`VolumeNaming::volume_path`]. Returns an error if one of the volumes can't be read.
+pub fn find_volumes(first: impl AsRef<Path>) -> RarResult<Vec<PathBuf>> {
+    let first = first.as_ref();
+    let mut naming = VolumeNaming::Old;
+    let mut paths = vec![];
+
+    loop {
+        let index = paths.len();
+        let Some(path) = naming.volume_path(first, index) else {
+            break;
+        };
+
+        let file = match fs::File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && index > 0 => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut archive = Archive::new(io::BufReader::new(file))?;
+        let mut blocks = archive.blocks()?;
+        let mut split_after = false;
+
+        for block in blocks.by_ref() {
+            let block = block?;
+
+            if index == 0 && uses_new_numbering(&block) == Some(true) {
+                naming = VolumeNaming::detect(first);
+            }
+
+            if let Some(entry) = FileEntry::from_block(&block) {
+                split_after = entry.split_after;
+            }
+        }
+
+        let has_next_volume = blocks.has_next_volume();
+        paths.push(path);
+
+        if !has_next_volume.unwrap_or(split_after) {
+            break;
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Whether the volumes use the new naming scheme, if `block` is a main block.
+fn uses_new_numbering(block: &Block) -> Option<bool> {
+    match block {
+        Block::Rar14(rar14::Block::Main(_)) => Some(false),
+        Block::Rar15(rar15::Block {
+            kind: rar15::BlockKind::Main(main),
+            ..
+        }) => Some(main.uses_new_numbering()),
+        Block::Rar50(block) => match &block.kind {
+            rar50::BlockKind::Main(_) => Some(true),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+/// Entry of a [`MultiVolumeReader`], whose data may span multiple volumes.
+pub struct MultiVolumeEntry {
+    /// Metadata of the whole entry.
+    ///
+    /// The offset and header size are those of the first segment, while the packed size is
+    /// the sum of the sizes of all the segments, and the checksums are taken from the last
+    /// segment, which stores the ones of the whole unpacked data.
+    pub entry: FileEntry,
+
+    /// Block of the last segment of the entry, which can be passed to the decoders.
+    pub block: Block,
+
+    /// Indices of the first and last volumes containing the data of the entry.
+    pub volumes: RangeInclusive<usize>,
+
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug)]
+/// Reader for the entries of a multi-volume archive.
+///
+/// All the volumes are opened and their headers read upfront, and the segments of the
+/// entries that span multiple volumes are joined so that their data can be read as a
+/// single stream with [`Self::entry_reader`]. The data read can be kept in memory to be
+/// read again with [`Self::set_cache_capacity`].
+///
+/// Archives with a single volume are read like any other archive.
+pub struct MultiVolumeReader<R: io::Read + io::Seek> {
+    volumes: Vec<Archive<R>>,
+    entries: Vec<MultiVolumeEntry>,
+    cache: SegmentCache,
+}
+
+impl MultiVolumeReader<io::BufReader<fs::File>> {
+    /// Open the multi-volume archive whose first volume is at `first`.
+    ///
+    /// The paths of the other volumes are found with [`find_volumes`].
+    pub fn open(first: impl AsRef<Path>) -> RarResult<Self> {
+        let mut paths = find_volumes(first)?.into_iter();
+
+        Self::with_opener(|_| match paths.next() {
+            Some(path) => Ok(Some(io::BufReader::new(fs::File::open(path)?))),
+            None => Ok(None),
+        })
+    }
+}
+
+impl<R: io::Read + io::Seek> MultiVolumeReader<R> {
+    /// Read the archive from `volumes`, which must be in order.
+    ///
+    /// Volumes after the last one of the archive are ignored.
+    pub fn new(volumes: impl IntoIterator<Item = R>) -> RarResult<Self> {
+        let mut volumes = volumes.into_iter();
+        Self::with_opener(|_| Ok(volumes.next()))
+    }
+
+    /// Read the archive from the volumes returned by `open_volume`, which is called with
+    /// the index of each volume starting from 0, and returns `None` if there is no such
+    /// volume.
+    ///
+    /// Volumes are opened until one reports that it's the last, or until one ends without
+    /// an entry continuing in the next volume if it doesn't say. Returns an
+    /// [`io::ErrorKind::NotFound`] error if `open_volume` returns `None` for a volume that
+    /// is needed.
+    pub fn with_opener(
+        mut open_volume: impl FnMut(usize) -> io::Result<Option<R>>,
+    ) -> RarResult<Self> {
+        let mut volumes = vec![];
+        let mut entries: Vec<MultiVolumeEntry> = vec![];
+
+        // Entry continuing in the next volume, if any.
+        let mut open: Option<usize> = None;
+
+        loop {
+            let index = volumes.len();
+            let reader = open_volume(index)?.ok_or_else(|| missing_volume(index))?;
+
+            let mut archive = Archive::new(reader)?;
+            let mut blocks = archive.blocks()?;
+
+            for block in blocks.by_ref() {
+                let block = block?;
+
+                let Some(entry) = FileEntry::from_block(&block) else {
+                    continue;
+                };
+
+                let segment = Segment {
+                    volume: index,
+                    offset: entry.offset + entry.header_size,
+                    size: entry.packed_size,
+                };
+                let split_after = entry.split_after;
+
+                let entry_index = if entry.split_before {
+                    let entry_index = open.take().ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "entry continues from a missing volume",
+                        )
+                    })?;
+
+                    let spanned = &mut entries[entry_index];
+                    if spanned.entry.name != entry.name {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "entry continues with a different name",
+                        )
+                        .into());
+                    }
+
+                    spanned.entry.packed_size += entry.packed_size;
+                    spanned.entry.unpacked_size = entry.unpacked_size;
+                    spanned.entry.checksum = entry.checksum;
+                    spanned.entry.blake2sp = entry.blake2sp;
+                    spanned.block = block;
+                    spanned.volumes = *spanned.volumes.start()..=index;
+                    spanned.segments.push(segment);
+                    entry_index
+                } else {
+                    if open.is_some() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "entry doesn't continue in the next volume",
+                        )
+                        .into());
+                    }
+
+                    entries.push(MultiVolumeEntry {
+                        entry,
+                        block,
+                        volumes: index..=index,
+                        segments: vec![segment],
+                    });
+                    entries.len() - 1
+                };
+
+                if split_after {
+                    open = Some(entry_index);
+                }
+            }
+
+            let has_next_volume = blocks.has_next_volume();
+            volumes.push(archive);
+
+            if !has_next_volume.unwrap_or(open.is_some()) {
+                break;
+            }
+        }
+
+        if open.is_some() {
+            return Err(missing_volume(volumes.len()));
+        }
+
+        for spanned in &mut entries {
+            spanned.entry.split_before = false;
+            spanned.entry.split_after = false;
+        }
+
+        Ok(Self {
+            volumes,
+            entries,
+            cache: SegmentCache::default(),
+        })
+    }
+
+    /// Number of volumes of the archive.
+    pub fn volume_count(&self) -> usize {
+        self.volumes.len()
+    }
+
+    /// Entries of the archive, in order.
+    pub fn entries(&self) -> &[MultiVolumeEntry] {
+        &self.entries
+    }
+
+    /// Keep the packed data of the most recently read segments of the entries in memory, up
+    /// to `capacity` bytes in total, so that reading them again doesn't read their volumes
+    /// again. See [`SegmentCache`].
+    ///
+    /// Defaults to 0, which disables the cache.
+    pub fn set_cache_capacity(&mut self, capacity: u64) {
+        self.cache.set_capacity(capacity);
+    }
+
+    /// Read the packed data of the entry at `index` across all its volumes.
+    ///
+    /// The data is not decompressed nor checked, which can be done by passing the reader
+    /// to the decoder of the format along with the [`MultiVolumeEntry::block`] returned by
+    /// [`EntryReader::entry`].
+    ///
+    /// Returns `None` if there is no entry at `index`.
+    pub fn entry_reader(&mut self, index: usize) -> Option<EntryReader<'_, R>> {
+        let entry = self.entries.get(index)?;
+
+        Some(EntryReader {
+            volumes: &mut self.volumes,
+            cache: &mut self.cache,
+            entry,
+            segment: 0,
+            segment_pos: 0,
+            seek_pending: true,
+        })
+    }
+
+    /// Unwrap the readers of the volumes.
+    pub fn into_inner(self) -> Vec<R> {
+        self.volumes.into_iter().map(Archive::into_inner).collect()
+    }
+}
+
+fn missing_volume(index: usize) -> Error {
+    Error::Io(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("volume {index} is missing"),
+    ))
+}
+
+#[derive(Debug)]
+/// Reader for the packed data of an entry of a [`MultiVolumeReader`].
+pub struct EntryReader<'a, R: io::Read + io::Seek> {
+    volumes: &'a mut [Archive<R>],
+    cache: &'a mut SegmentCache,
+    entry: &'a MultiVolumeEntry,
+
+    /// Index of the current segment.
+    segment: usize,
+
+    /// Position in the current segment.
+    segment_pos: u64,
+
+    /// The reader of the volume needs to be moved to the current position.
+    seek_pending: bool,
+}
+
+impl<'a, R: io::Read + io::Seek> EntryReader<'a, R> {
+    /// Entry whose data is being read.
+    pub fn entry(&self) -> &'a MultiVolumeEntry {
+        self.entry
+    }
+
+    /// Total size of the packed data of the entry.
+    pub fn len(&self) -> u64 {
+        self.entry.entry.packed_size
+    }
+
+    /// The entry has no packed data.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn position(&self) -> u64 {
+        let before: u64 = self.entry.segments[..self.segment]
+            .iter()
+            .map(|segment| segment.size)
+            .sum();
+        before + self.segment_pos
+    }
+}
+
+impl<R: io::Read + io::Seek> Read for EntryReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let Some(segment) = self.entry.segments.get(self.segment) else {
+                return Ok(0);
+            };
+
+            let remaining = segment.size - self.segment_pos;
+            if remaining == 0 {
+                self.segment += 1;
+                self.segment_pos = 0;
+                self.seek_pending = true;
+                continue;
+            }
+
+            let reader = self.volumes[segment.volume].reader();
+            if let Some(data) = self.cache.data(reader, segment)? {
+                let start = self.segment_pos as usize;
+                let n = buf.len().min(data.len() - start);
+                buf[..n].copy_from_slice(&data[start..start + n]);
+
+                self.segment_pos += n as u64;
+                self.seek_pending = true;
+                return Ok(n);
+            }
+
+            if self.seek_pending {
+                reader.seek(io::SeekFrom::Start(segment.offset + self.segment_pos))?;
+                self.seek_pending = false;
+            }
+
+            let max = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+            let n = reader.read(&mut buf[..max])?;
+            if n == 0 && max > 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+
+            self.segment_pos += n as u64;
+            return Ok(n);
+        }
+    }
+}
+
+impl<R: io::Read + io::Seek> Seek for EntryReader<'_, R> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            io::SeekFrom::Start(offset) => Some(offset),
+            io::SeekFrom::End(offset) => self.len().checked_add_signed(offset),
+            io::SeekFrom::Current(offset) => self.position().checked_add_signed(offset),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        // Find the segment containing the target, or place the reader past the last one.
+        let mut start = 0;
+        self.segment = self.entry.segments.len();
+        self.segment_pos = 0;
+        for (i, segment) in self.entry.segments.iter().enumerate() {
+            if target < start + segment.size {
+                self.segment = i;
+                self.segment_pos = target - start;
+                break;
+            }
+            start += segment.size;
+        }
+        self.seek_pending = true;
+
+        Ok(target)
+    }
+}
@@ -0,0 +1,28 @@
+use std::io::{Read, Seek};
+
+use crate::error::RarResult;
+
+use super::{Archive, UnpackedEntryReader};
+
+impl<R: Read + Seek> Archive<R> {
+    /// Open the entry at `index` in the list returned by [`Self::entries`] as an archive,
+    /// reading its unpacked data through an [`UnpackedEntryReader`] without extracting it.
+    ///
+    /// Stored entries are read directly from the archive. Compressed entries are decoded
+    /// again from their start every time the nested archive seeks backward, so reading
+    /// them into memory first is faster if they are read more than once.
+    ///
+    /// Returns `None` if there is no entry at `index`, and fails with
+    /// [`Error::SignatureNotFound`](crate::Error::SignatureNotFound) if the entry is not an
+    /// archive or with an [`std::io::ErrorKind::Unsupported`] error if it can't be read.
+    pub fn open_nested(
+        &mut self,
+        index: usize,
+    ) -> RarResult<Option<Archive<UnpackedEntryReader<'_, R>>>> {
+        let Some(reader) = self.open_entry(index)? else {
+            return Ok(None);
+        };
+
+        Ok(Some(Archive::new(reader)?))
+    }
+}
@@ -0,0 +1,223 @@
+use std::io::{self, Read, Seek};
+
+use crate::{error::RarResult, hash::Crc32, rar15, rar50, size::BlockSize};
+
+use super::{Archive, Block, FileEntry, SecurityDescriptor};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// NTFS permissions of the file entry preceding them in the archive, independent of how
+/// each format stores them.
+///
+/// RAR50 and RAR15 (RAR 3.x and later) archives store the permissions in `ACL` service
+/// blocks, and RAR 2.x archives in NTFS extended attributes sub blocks. The unpacked data
+/// is a self-relative `SECURITY_DESCRIPTOR`, which can be parsed with
+/// [`Self::read_descriptor`].
+pub struct NtfsAcl {
+    /// Offset of the block containing the permissions.
+    pub offset: u64,
+
+    /// Size of the header of the block containing the permissions.
+    pub header_size: u64,
+
+    /// Size of the data area of the block containing the permissions.
+    pub packed_size: u64,
+
+    /// Size of the security descriptor after decompression, if known.
+    pub unpacked_size: Option<u64>,
+
+    /// CRC32 of the unpacked data.
+    pub crc32: Option<u32>,
+
+    /// Security descriptor is encrypted.
+    pub is_encrypted: bool,
+
+    /// Security descriptor is stored without compression.
+    pub is_stored: bool,
+
+    /// Version of the algorithm used to compress the security descriptor, for RAR 2.x
+    /// sub blocks whose data can be decompressed by [`Self::read_data`].
+    pub unpack_version: Option<u8>,
+}
+
+impl NtfsAcl {
+    /// Extract the NTFS permissions stored in `block`.
+    ///
+    /// Returns `None` if the block doesn't contain them.
+    pub fn from_block(block: &Block) -> Option<Self> {
+        match block {
+            Block::Rar15(block) => match &block.kind {
+                rar15::BlockKind::Service(
+                    service @ rar15::ServiceBlock {
+                        kind: rar15::ServiceBlockKind::NtfsFilePermissions,
+                        ..
+                    },
+                ) => Some(Self {
+                    unpacked_size: Some(service.unpacked_data_size),
+                    crc32: Some(service.data_crc32),
+                    is_encrypted: service.salt.is_some(),
+                    is_stored: service.method == rar15::FileBlock::METHOD_STORE,
+                    ..Self::new(block)
+                }),
+
+                rar15::BlockKind::Sub(rar15::SubBlock {
+                    kind: rar15::SubBlockKind::ExtendedAttributes(attributes),
+                    ..
+                }) if attributes.filesystem == rar15::ExtendedAttributesFs::Ntfs => Some(Self {
+                    unpacked_size: Some(attributes.unpacked_data_size as u64),
+                    crc32: Some(attributes.extended_attributes_crc32),
+                    is_encrypted: false,
+                    is_stored: attributes.method == rar15::FileBlock::METHOD_STORE,
+                    unpack_version: Some(attributes.unpack_version),
+                    ..Self::new(block)
+                }),
+
+                _ => None,
+            },
+
+            Block::Rar50(block) => match &block.kind {
+                rar50::BlockKind::Service(
+                    service @ rar50::ServiceBlock {
+                        kind: rar50::ServiceBlockKind::NtfsFilePermissions,
+                        ..
+                    },
+                ) => Some(Self {
+                    unpacked_size: service.unpacked_size,
+                    crc32: service.data_crc32,
+                    is_encrypted: service.encryption.is_some(),
+                    is_stored: service.compression_info.method()
+                        == rar50::CompressionMethod::NoCompression,
+                    ..Self::new(&**block)
+                }),
+
+                _ => None,
+            },
+
+            Block::Rar14(_) => None,
+        }
+    }
+
+    fn new(block: &impl BlockSize) -> Self {
+        Self {
+            offset: block.offset(),
+            header_size: block.header_size(),
+            packed_size: block.data_size(),
+            unpacked_size: None,
+            crc32: None,
+            is_encrypted: false,
+            is_stored: false,
+            unpack_version: None,
+        }
+    }
+
+    /// Read the data of permissions which are stored without compression.
+    ///
+    /// Seeks `reader` to the start of the data area and returns a reader that stops at its
+    /// end. The data is not checked against [`Self::crc32`].
+    ///
+    /// Returns an [`io::ErrorKind::Unsupported`] error if the permissions are compressed or
+    /// encrypted, because the decoders can only unpack the data of file blocks.
+    pub fn data_reader<'a, R: Read + Seek>(
+        &self,
+        reader: &'a mut R,
+    ) -> io::Result<io::Take<&'a mut R>> {
+        let unsupported = |message| io::Error::new(io::ErrorKind::Unsupported, message);
+
+        if !self.is_stored {
+            return Err(unsupported("permissions are compressed"));
+        }
+        if self.is_encrypted {
+            return Err(unsupported("encrypted permissions are not supported"));
+        }
+
+        reader.seek(io::SeekFrom::Start(self.offset + self.header_size))?;
+        Ok(reader.take(self.packed_size))
+    }
+
+    /// Read the raw `SECURITY_DESCRIPTOR` of permissions which are stored without
+    /// compression or stored in a RAR 2.x sub block, and check it against [`Self::crc32`].
+    ///
+    /// Returns an [`io::ErrorKind::InvalidData`] error if the CRC32 doesn't match, and an
+    /// [`io::ErrorKind::Unsupported`] error if the permissions are encrypted or compressed
+    /// in a service block.
+    pub fn read_data<R: Read + Seek>(&self, reader: &mut R) -> io::Result<Vec<u8>> {
+        let mut data = vec![];
+
+        if let (false, Some(unpack_version)) = (self.is_stored, self.unpack_version) {
+            reader.seek(io::SeekFrom::Start(self.offset + self.header_size))?;
+            rar15::Decoder::for_compressed_data(
+                reader.take(self.packed_size),
+                unpack_version,
+                self.unpacked_size.unwrap_or(0),
+                self.crc32.unwrap_or(0),
+            )?
+            .read_to_end(&mut data)?;
+            return Ok(data);
+        }
+
+        self.data_reader(reader)?.read_to_end(&mut data)?;
+
+        if let Some(expected) = self.crc32 {
+            let mut crc = Crc32::new();
+            crc.update(&data);
+            if crc.finalize() != expected {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "CRC32 of the permissions doesn't match",
+                ));
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Read and parse the security descriptor, like [`Self::read_data`].
+    pub fn read_descriptor<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+    ) -> io::Result<SecurityDescriptor> {
+        SecurityDescriptor::parse(&self.read_data(reader)?)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// File entry and the NTFS permissions stored after it, returned by [`Archive::ntfs_acls`].
+pub struct EntryAcl {
+    pub entry: FileEntry,
+    pub acl: NtfsAcl,
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// NTFS permissions of the entries of the archive, grouped with the file entry that
+    /// precedes them.
+    ///
+    /// RAR50 service blocks are only grouped with the entry if they are marked as its
+    /// children. Entries without permissions are not listed.
+    pub fn ntfs_acls(&mut self) -> RarResult<Vec<EntryAcl>> {
+        let mut acls = vec![];
+        let mut entry = None;
+
+        for block in self.blocks()? {
+            let block = block?;
+            if let Some(file) = FileEntry::from_block(&block) {
+                entry = Some(file);
+            } else if let Some(acl) = entry_acl(&block) {
+                if let Some(entry) = entry.take() {
+                    acls.push(EntryAcl { entry, acl });
+                }
+            }
+        }
+
+        Ok(acls)
+    }
+}
+
+/// NTFS permissions stored in `block` if it belongs to the file entry preceding it.
+pub(super) fn entry_acl(block: &Block) -> Option<NtfsAcl> {
+    if let Block::Rar50(block) = block {
+        if !block.flags.is_child() {
+            return None;
+        }
+    }
+
+    NtfsAcl::from_block(block)
+}
@@ -0,0 +1,293 @@
+use std::{io, path::Path};
+
+use super::SecurityDescriptor;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Parts of a [`SecurityDescriptor`] that can be applied to a file.
+pub struct SecurityParts {
+    pub owner: bool,
+    pub group: bool,
+
+    /// Discretionary ACL, which grants or denies access to the file.
+    pub dacl: bool,
+
+    /// System ACL, which controls auditing.
+    pub sacl: bool,
+}
+
+impl SecurityParts {
+    /// None of the parts are set.
+    pub fn is_empty(&self) -> bool {
+        !(self.owner || self.group || self.dacl || self.sacl)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Outcome of [`apply_security_descriptor`].
+pub struct AppliedSecurity {
+    /// Parts of the security descriptor that were applied to the file.
+    pub applied: SecurityParts,
+
+    /// Parts of the security descriptor that were left out because the process doesn't
+    /// have the privileges needed to set them.
+    pub skipped: SecurityParts,
+}
+
+/// Apply the self-relative security descriptor `descriptor`, as stored in the NTFS ACL
+/// blocks of an archive, to the file or directory at `path`.
+///
+/// Setting the owner to a different user requires `SeRestorePrivilege`, and setting the
+/// system ACL requires `SeSecurityPrivilege`, which are enabled the first time this is
+/// called if the process holds them, like WinRAR does when keeping NTFS permissions.
+/// Without them, the parts that can't be set are reported in
+/// [`AppliedSecurity::skipped`] and the rest is still applied; an error is only returned
+/// if not even the discretionary ACL can be set.
+///
+/// Only supported on Windows; returns an [`io::ErrorKind::Unsupported`] error elsewhere.
+pub fn apply_security_descriptor(path: &Path, descriptor: &[u8]) -> io::Result<AppliedSecurity> {
+    let parts = SecurityDescriptor::parse(descriptor)?.parts();
+
+    #[cfg(windows)]
+    {
+        windows::apply(path, descriptor, parts)
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = (path, parts);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "NTFS permissions can only be applied on Windows",
+        ))
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::{ffi::c_void, io, os::windows::ffi::OsStrExt as _, path::Path, ptr, sync::OnceLock};
+
+    use super::{AppliedSecurity, SecurityParts};
+
+    type Handle = *mut c_void;
+
+    const OWNER_SECURITY_INFORMATION: u32 = 0x1;
+    const GROUP_SECURITY_INFORMATION: u32 = 0x2;
+    const DACL_SECURITY_INFORMATION: u32 = 0x4;
+    const SACL_SECURITY_INFORMATION: u32 = 0x8;
+
+    const TOKEN_ADJUST_PRIVILEGES: u32 = 0x20;
+    const TOKEN_QUERY: u32 = 0x8;
+    const SE_PRIVILEGE_ENABLED: u32 = 0x2;
+
+    const ERROR_ACCESS_DENIED: i32 = 5;
+    const ERROR_NOT_ALL_ASSIGNED: i32 = 1300;
+    const ERROR_INVALID_OWNER: i32 = 1307;
+    const ERROR_INVALID_PRIMARY_GROUP: i32 = 1308;
+    const ERROR_PRIVILEGE_NOT_HELD: i32 = 1314;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    struct Luid {
+        low_part: u32,
+        high_part: i32,
+    }
+
+    #[repr(C)]
+    struct TokenPrivileges {
+        privilege_count: u32,
+        luid: Luid,
+        attributes: u32,
+    }
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn SetFileSecurityW(
+            file_name: *const u16,
+            security_information: u32,
+            security_descriptor: *const c_void,
+        ) -> i32;
+
+        fn OpenProcessToken(process: Handle, desired_access: u32, token: *mut Handle) -> i32;
+
+        fn LookupPrivilegeValueW(system_name: *const u16, name: *const u16, luid: *mut Luid)
+            -> i32;
+
+        fn AdjustTokenPrivileges(
+            token: Handle,
+            disable_all_privileges: i32,
+            new_state: *const TokenPrivileges,
+            buffer_length: u32,
+            previous_state: *mut c_void,
+            return_length: *mut u32,
+        ) -> i32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentProcess() -> Handle;
+        fn CloseHandle(handle: Handle) -> i32;
+    }
+
+    fn wide(s: impl AsRef<std::ffi::OsStr>) -> Vec<u16> {
+        s.as_ref().encode_wide().chain([0]).collect()
+    }
+
+    /// Enable the privilege called `name`, returning whether the process holds it.
+    fn enable_privilege(name: &str) -> bool {
+        let name = wide(name);
+
+        unsafe {
+            let mut token = ptr::null_mut();
+            if OpenProcessToken(
+                GetCurrentProcess(),
+                TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY,
+                &mut token,
+            ) == 0
+            {
+                return false;
+            }
+
+            let mut luid = Luid::default();
+            let enabled = if LookupPrivilegeValueW(ptr::null(), name.as_ptr(), &mut luid) == 0 {
+                false
+            } else {
+                let privileges = TokenPrivileges {
+                    privilege_count: 1,
+                    luid,
+                    attributes: SE_PRIVILEGE_ENABLED,
+                };
+
+                // Succeeds even if the privilege isn't held, which is reported as an error.
+                AdjustTokenPrivileges(token, 0, &privileges, 0, ptr::null_mut(), ptr::null_mut())
+                    != 0
+                    && io::Error::last_os_error().raw_os_error() != Some(ERROR_NOT_ALL_ASSIGNED)
+            };
+
+            CloseHandle(token);
+            enabled
+        }
+    }
+
+    /// Enable the privileges needed to set the owner and the system ACL, returning whether
+    /// the process can set the system ACL.
+    ///
+    /// Without `SeRestorePrivilege` the owner can still be set to the current user, so it's
+    /// only found out whether it can be set by trying.
+    fn enable_privileges() -> bool {
+        static CAN_SET_SACL: OnceLock<bool> = OnceLock::new();
+        *CAN_SET_SACL.get_or_init(|| {
+            enable_privilege("SeRestorePrivilege");
+            enable_privilege("SeSecurityPrivilege")
+        })
+    }
+
+    fn set_file_security(path: &[u16], parts: SecurityParts, descriptor: &[u8]) -> io::Result<()> {
+        let information = [
+            (parts.owner, OWNER_SECURITY_INFORMATION),
+            (parts.group, GROUP_SECURITY_INFORMATION),
+            (parts.dacl, DACL_SECURITY_INFORMATION),
+            (parts.sacl, SACL_SECURITY_INFORMATION),
+        ]
+        .into_iter()
+        .filter(|(present, _)| *present)
+        .fold(0, |acc, (_, flag)| acc | flag);
+
+        let ok =
+            unsafe { SetFileSecurityW(path.as_ptr(), information, descriptor.as_ptr().cast()) };
+        if ok == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn is_privilege_error(e: &io::Error) -> bool {
+        matches!(
+            e.raw_os_error(),
+            Some(
+                ERROR_ACCESS_DENIED
+                    | ERROR_INVALID_OWNER
+                    | ERROR_INVALID_PRIMARY_GROUP
+                    | ERROR_PRIVILEGE_NOT_HELD
+            )
+        )
+    }
+
+    pub(super) fn apply(
+        path: &Path,
+        descriptor: &[u8],
+        parts: SecurityParts,
+    ) -> io::Result<AppliedSecurity> {
+        let path = wide(path);
+        let can_set_sacl = enable_privileges();
+
+        let mut applied = parts;
+        let mut skipped = SecurityParts::default();
+
+        if applied.sacl && !can_set_sacl {
+            applied.sacl = false;
+            skipped.sacl = true;
+        }
+
+        // Give up the system ACL first, then the owner and group, and retry each time.
+        loop {
+            match set_file_security(&path, applied, descriptor) {
+                Ok(()) => return Ok(AppliedSecurity { applied, skipped }),
+
+                Err(e) if is_privilege_error(&e) && applied.sacl => {
+                    applied.sacl = false;
+                    skipped.sacl = true;
+                }
+
+                Err(e) if is_privilege_error(&e) && (applied.owner || applied.group) => {
+                    skipped.owner |= applied.owner;
+                    skipped.group |= applied.group;
+                    applied.owner = false;
+                    applied.group = false;
+                }
+
+                Err(e) => return Err(e),
+            }
+
+            if applied.is_empty() {
+                return Ok(AppliedSecurity { applied, skipped });
+            }
+        }
+    }
+}
+
+#[cfg(not(windows))]
+#[test]
+fn test_apply_security_descriptor_unsupported() {
+    // Self-relative descriptor without any parts.
+    let mut descriptor = vec![1, 0, 0x00, 0x80];
+    descriptor.extend_from_slice(&[0; 16]);
+
+    let err = apply_security_descriptor(Path::new("file"), &descriptor).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+    // Invalid descriptors are rejected on every platform.
+    let err = apply_security_descriptor(Path::new("file"), &descriptor[..10]).unwrap_err();
+    assert_ne!(err.kind(), io::ErrorKind::Unsupported);
+}
+
+#[cfg(windows)]
+#[test]
+fn test_apply_security_descriptor() {
+    // Self-relative descriptor with a discretionary ACL granting full access to Everyone.
+    let mut descriptor = vec![1, 0, 0x04, 0x80];
+    descriptor.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 20, 0, 0, 0]);
+    descriptor.extend_from_slice(&[2, 0, 28, 0, 1, 0, 0, 0]);
+    descriptor.extend_from_slice(&[0, 0, 20, 0, 0xff, 0x01, 0x1f, 0x00]);
+    descriptor.extend_from_slice(&[1, 1, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0]);
+
+    let path = std::env::temp_dir().join("rawrxd_apply_security_descriptor");
+    std::fs::write(&path, b"data").unwrap();
+
+    let result = apply_security_descriptor(&path, &descriptor);
+    let _ = std::fs::remove_file(&path);
+
+    let result = result.unwrap();
+    assert!(result.applied.dacl);
+    assert!(result.skipped.is_empty());
+}
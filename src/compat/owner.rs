@@ -0,0 +1,110 @@
+use crate::{rar15, rar50};
+
+use super::Block;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Unix owner and mode of an entry, independent of how each format stores them.
+///
+/// Names are returned as UTF-8 if they are valid, and as the raw bytes stored in the
+/// archive otherwise.
+pub struct UnixOwner {
+    /// Name of the user owning the entry.
+    pub user_name: Option<Result<String, Vec<u8>>>,
+
+    /// Name of the group owning the entry.
+    pub group_name: Option<Result<String, Vec<u8>>>,
+
+    /// Numeric ID of the user owning the entry.
+    pub uid: Option<u64>,
+
+    /// Numeric ID of the group owning the entry.
+    pub gid: Option<u64>,
+
+    /// Permission bits of the entry, including the setuid, setgid and sticky bits.
+    pub mode: Option<u32>,
+}
+
+impl UnixOwner {
+    /// Extract the Unix owner and mode stored in `block`.
+    ///
+    /// RAR50 file blocks store both the owner and the mode. RAR15 file blocks only store
+    /// the mode, and the owner is stored in the service block (RAR 3.x and later) or sub
+    /// block (RAR 2.x) which follows the file block; these can be combined with
+    /// [`Self::merge`].
+    ///
+    /// Returns `None` if the block doesn't contain any of this metadata.
+    pub fn from_block(block: &Block) -> Option<Self> {
+        let owner = match block {
+            Block::Rar15(block) => Self::from_rar15(block)?,
+            Block::Rar50(block) => match &block.kind {
+                rar50::BlockKind::File(file) => Self::from_rar50(file),
+                _ => return None,
+            },
+            Block::Rar14(_) => return None,
+        };
+
+        if owner == Self::default() {
+            None
+        } else {
+            Some(owner)
+        }
+    }
+
+    fn from_rar15(block: &rar15::Block) -> Option<Self> {
+        match &block.kind {
+            rar15::BlockKind::File(file) => Some(Self {
+                mode: (file.host_os == rar15::HostOs::Unix).then_some(file.attributes & 0o7777),
+                ..Self::default()
+            }),
+
+            rar15::BlockKind::Service(rar15::ServiceBlock {
+                kind: rar15::ServiceBlockKind::UnixOwner(owner),
+                ..
+            }) => Some(Self {
+                user_name: Some(decode_name(&owner.user)),
+                group_name: Some(decode_name(&owner.group)),
+                ..Self::default()
+            }),
+
+            rar15::BlockKind::Sub(rar15::SubBlock {
+                kind: rar15::SubBlockKind::UnixOwner(owner),
+                ..
+            }) => Some(Self {
+                user_name: Some(decode_name(&owner.user)),
+                group_name: Some(decode_name(&owner.group)),
+                ..Self::default()
+            }),
+
+            _ => None,
+        }
+    }
+
+    fn from_rar50(file: &rar50::FileBlock) -> Self {
+        let mut owner = Self {
+            mode: (file.host_os == rar50::HostOs::Unix).then_some(file.attributes as u32 & 0o7777),
+            ..Self::default()
+        };
+
+        if let Some(record) = &file.unix_owner {
+            owner.user_name = record.user_name.clone();
+            owner.group_name = record.group_name.clone();
+            owner.uid = record.user_id;
+            owner.gid = record.group_id;
+        }
+
+        owner
+    }
+
+    /// Fill the fields which are not known with the ones of `other`.
+    pub fn merge(&mut self, other: Self) {
+        self.user_name = self.user_name.take().or(other.user_name);
+        self.group_name = self.group_name.take().or(other.group_name);
+        self.uid = self.uid.or(other.uid);
+        self.gid = self.gid.or(other.gid);
+        self.mode = self.mode.or(other.mode);
+    }
+}
+
+fn decode_name(name: &[u8]) -> Result<String, Vec<u8>> {
+    String::from_utf8(name.to_vec()).map_err(|e| e.into_bytes())
+}
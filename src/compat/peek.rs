@@ -0,0 +1,159 @@
+use std::io::{self, Read, Seek};
+
+use crate::{error::RarResult, rar15, rar50};
+
+use super::{Archive, Block, FileEntry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Integrity of the data returned by [`Archive::peek`].
+pub enum PeekStatus {
+    /// The whole entry was read and its checksum matches.
+    Verified,
+
+    /// The data was read without errors, but the checksum couldn't be checked because only
+    /// part of the entry was read, or because the format has no decoder that checks it.
+    Unverified,
+
+    /// The data is corrupt: it couldn't be decoded, or the checksum doesn't match.
+    Corrupt,
+
+    /// The entry can't be extracted because it's encrypted, part of a solid stream, split
+    /// across volumes, or compressed with an unsupported method.
+    NotExtractable,
+}
+
+#[derive(Debug, Clone)]
+/// Preview of the start of an entry returned by [`Archive::peek`].
+pub struct Peek {
+    /// Metadata of the entry.
+    pub entry: FileEntry,
+
+    /// Start of the unpacked data of the entry.
+    ///
+    /// If the entry is corrupt, this contains the data read before the error, and if it's
+    /// not extractable, it's empty.
+    pub data: Vec<u8>,
+
+    /// Integrity of [`Self::data`].
+    pub status: PeekStatus,
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Read the metadata and the first `n` bytes of unpacked data of the entry at `index`
+    /// in the list returned by [`Self::entries`].
+    ///
+    /// Only the headers of the entries before it are read, and only as much of the data of
+    /// the entry as needed to unpack `n` bytes is decoded.
+    ///
+    /// Returns `None` if there is no entry at `index`.
+    pub fn peek(&mut self, index: usize, n: usize) -> RarResult<Option<Peek>> {
+        let mut found = None;
+        let mut entries = 0;
+        for block in self.blocks()? {
+            let block = block?;
+            if let Some(entry) = FileEntry::from_block(&block) {
+                if entries == index {
+                    found = Some((entry, block));
+                    break;
+                }
+                entries += 1;
+            }
+        }
+
+        let Some((entry, block)) = found else {
+            return Ok(None);
+        };
+
+        let reader = self.reader();
+        reader.seek(io::SeekFrom::Start(entry.offset + entry.header_size))?;
+        let packed = reader.take(entry.packed_size);
+
+        let (data, status) = match unpacked_reader(packed, &entry, &block) {
+            Some((reader, checked)) => read_prefix(reader, n, checked, entry.unpacked_size),
+            None => (vec![], PeekStatus::NotExtractable),
+        };
+
+        Ok(Some(Peek {
+            entry,
+            data,
+            status,
+        }))
+    }
+}
+
+/// Reader of the unpacked data of `entry` from its `packed` data, and whether the reader
+/// checks the data once it reaches its end.
+///
+/// Returns `None` if the entry can't be extracted.
+pub(super) fn unpacked_reader<'a>(
+    packed: impl Read + 'a,
+    entry: &FileEntry,
+    block: &Block,
+) -> Option<(Box<dyn Read + 'a>, bool)> {
+    if entry.split_before || entry.split_after {
+        return None;
+    }
+
+    match block {
+        // There is no decoder for RAR14, so only stored entries can be read.
+        Block::Rar14(_) if entry.is_stored && !entry.is_encrypted => {
+            Some((Box::new(packed), false))
+        }
+        Block::Rar15(rar15::Block {
+            kind: rar15::BlockKind::File(file),
+            ..
+        }) => {
+            let decoder = rar15::Decoder::new(packed, file).ok()?;
+            Some((Box::new(decoder), true))
+        }
+        Block::Rar50(block) => match &block.kind {
+            rar50::BlockKind::File(file) => {
+                let decoder = rar50::Decoder::new(packed, file).ok()?;
+                Some((Box::new(decoder), true))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Read up to `n` bytes from `reader`.
+///
+/// `checked` is whether `reader` checks the data when reaching its end, which is only
+/// done if the whole entry fits in `n` bytes.
+fn read_prefix(
+    mut reader: impl Read,
+    n: usize,
+    checked: bool,
+    unpacked_size: Option<u64>,
+) -> (Vec<u8>, PeekStatus) {
+    let mut data = vec![];
+    if reader
+        .by_ref()
+        .take(n as u64)
+        .read_to_end(&mut data)
+        .is_err()
+    {
+        return (data, PeekStatus::Corrupt);
+    }
+
+    if !checked {
+        return (data, PeekStatus::Unverified);
+    }
+
+    if data.len() < n {
+        return (data, PeekStatus::Verified);
+    }
+
+    // Only read past the first `n` bytes if that's the end of the entry.
+    if unpacked_size.is_none_or(|size| size > n as u64) {
+        return (data, PeekStatus::Unverified);
+    }
+
+    let status = match reader.read(&mut [0]) {
+        Ok(0) => PeekStatus::Verified,
+        Ok(_) | Err(_) => PeekStatus::Corrupt,
+    };
+
+    (data, status)
+}
@@ -0,0 +1,103 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Seek},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Location of a segment of data in one of the volumes of an archive.
+pub struct Segment {
+    /// Index of the volume.
+    pub volume: usize,
+
+    /// Offset of the data in the volume.
+    pub offset: u64,
+
+    /// Size of the data.
+    pub size: u64,
+}
+
+#[derive(Debug, Default)]
+/// Keeps the data of the most recently read segments of the volumes of an archive in
+/// memory, up to a capacity in bytes.
+///
+/// Extracting an entry of a solid group needs the data of all the entries before it, so
+/// extracting the entries of the group one at a time reads the first ones again and again.
+/// Reading the segments through the cache only reads each one from its volume once, as long
+/// as it fits.
+///
+/// The default capacity is 0, which disables the cache.
+pub struct SegmentCache {
+    capacity: u64,
+    size: u64,
+
+    /// Cached segments, from the least to the most recently used.
+    segments: VecDeque<(Segment, Vec<u8>)>,
+}
+
+impl SegmentCache {
+    /// Create a cache holding up to `capacity` bytes.
+    pub fn new(capacity: u64) -> Self {
+        Self {
+            capacity,
+            ..Self::default()
+        }
+    }
+
+    /// Maximum number of bytes kept in the cache.
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Change the capacity of the cache, dropping the least recently used segments which
+    /// don't fit anymore.
+    pub fn set_capacity(&mut self, capacity: u64) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    /// Number of bytes currently in the cache.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Data of `segment`, which is read from `reader` if it's not cached yet. `reader` must
+    /// be the volume the segment belongs to.
+    ///
+    /// Returns `None` without reading anything if the segment is larger than the capacity,
+    /// in which case it should be read from the volume directly.
+    pub fn data<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        segment: &Segment,
+    ) -> io::Result<Option<&[u8]>> {
+        match self.segments.iter().position(|(s, _)| s == segment) {
+            // Mark the segment as the most recently used.
+            Some(index) => {
+                let cached = self.segments.remove(index);
+                self.segments.extend(cached);
+            }
+            None if segment.size > self.capacity => return Ok(None),
+            None => {
+                reader.seek(io::SeekFrom::Start(segment.offset))?;
+                let mut data = vec![0; segment.size as usize];
+                reader.read_exact(&mut data)?;
+
+                self.size += segment.size;
+                self.segments.push_back((*segment, data));
+                self.evict();
+            }
+        }
+
+        Ok(self.segments.back().map(|(_, data)| data.as_slice()))
+    }
+
+    /// Remove the least recently used segments until the cache fits in its capacity.
+    fn evict(&mut self) {
+        while self.size > self.capacity {
+            let Some((segment, _)) = self.segments.pop_front() else {
+                break;
+            };
+            self.size -= segment.size;
+        }
+    }
+}
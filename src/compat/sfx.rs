@@ -0,0 +1,108 @@
+use std::{
+    io::{self, Read, Seek},
+    ops::Range,
+};
+
+use crate::error::RarResult;
+
+use super::Archive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Executable format of the SFX module of a self-extracting archive.
+pub enum SfxExecutableKind {
+    /// Windows Portable Executable.
+    Pe,
+
+    /// Executable and Linkable Format, used by Linux and other Unix-like systems.
+    Elf,
+
+    /// The format of the module was not recognized.
+    Unknown,
+}
+
+impl SfxExecutableKind {
+    /// Identify the format of an executable from its first bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        if bytes.starts_with(b"\x7fELF") {
+            return Self::Elf;
+        }
+
+        // The MS-DOS header stores the offset of the PE header at 0x3c.
+        if bytes.starts_with(b"MZ") {
+            let pe_offset = bytes
+                .get(0x3c..0x40)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+            let has_pe_header = pe_offset
+                .and_then(|offset| bytes.get(offset..offset.checked_add(4)?))
+                .is_some_and(|magic| magic == b"PE\0\0");
+
+            if has_pe_header {
+                return Self::Pe;
+            }
+        }
+
+        Self::Unknown
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// SFX module embedded before the archive in a self-extracting archive.
+///
+/// Returned by [`Archive::sfx_module`].
+pub struct SfxModule {
+    /// Range of the module in the file, which always starts at 0 and ends at the RAR
+    /// signature.
+    pub range: Range<u64>,
+
+    /// Executable format of the module.
+    pub kind: SfxExecutableKind,
+}
+
+impl SfxModule {
+    /// Size of the module.
+    pub fn size(&self) -> u64 {
+        self.range.end - self.range.start
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Find the SFX module stored before the RAR signature and identify its executable
+    /// format.
+    ///
+    /// Returns `None` if the archive is not self-extracting.
+    pub fn sfx_module(&mut self) -> RarResult<Option<SfxModule>> {
+        let end = self.signature_offset();
+        if end == 0 {
+            return Ok(None);
+        }
+
+        // The PE header may be anywhere in the module, but it's usually within the first
+        // kilobyte.
+        let mut header = vec![];
+        let reader = self.reader();
+        reader.rewind()?;
+        reader.take(end.min(0x1000)).read_to_end(&mut header)?;
+
+        Ok(Some(SfxModule {
+            range: 0..end,
+            kind: SfxExecutableKind::from_bytes(&header),
+        }))
+    }
+
+    /// Copy the SFX module stored before the RAR signature to `writer`, for example to
+    /// inspect it or to strip it from the archive.
+    ///
+    /// Returns the number of bytes copied, which is 0 if the archive is not self-extracting.
+    pub fn copy_sfx_module<W: io::Write>(&mut self, mut writer: W) -> RarResult<u64> {
+        let end = self.signature_offset();
+        let reader = self.reader();
+        reader.rewind()?;
+
+        let copied = io::copy(&mut reader.take(end), &mut writer)?;
+        if copied != end {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        Ok(copied)
+    }
+}
@@ -0,0 +1,90 @@
+use std::io::{Read, Seek};
+
+use crate::{error::RarResult, rar14};
+
+use super::{Archive, Block, FileEntry};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Entries that must be decompressed, in order, to decompress an entry of a solid archive.
+///
+/// Returned by [`Archive::solid_plan`]. Entries are identified by their index in the list
+/// returned by [`Archive::entries`], which can be read with [`Archive::entry_at`].
+///
+/// [`Archive::open_entry`] decodes the prerequisites before solid RAR15 and RAR50
+/// entries, passing each entry to `continue_solid` on the decoder of the stream.
+pub struct SolidPlan {
+    entries: Vec<usize>,
+}
+
+impl SolidPlan {
+    /// All the entries to decompress, ending with the target entry.
+    pub fn entries(&self) -> &[usize] {
+        &self.entries
+    }
+
+    /// Entries to decompress before the target entry, in order.
+    pub fn prerequisites(&self) -> &[usize] {
+        &self.entries[..self.entries.len() - 1]
+    }
+
+    /// The entry the plan was made for.
+    pub fn target(&self) -> usize {
+        self.entries[self.entries.len() - 1]
+    }
+
+    /// The target entry can be decompressed on its own.
+    pub fn is_independent(&self) -> bool {
+        self.entries.len() == 1
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Find the entries that must be decompressed before the entry at `index` in the list
+    /// returned by [`Self::entries`], in order.
+    ///
+    /// Solid entries are compressed with the dictionary left by the entries before them,
+    /// up to the first entry of the solid stream which starts with an empty dictionary.
+    /// Directories and entries stored without compression don't go through the dictionary
+    /// and are never needed.
+    ///
+    /// Returns `None` if there is no entry at `index`.
+    pub fn solid_plan(&mut self, index: usize) -> RarResult<Option<SolidPlan>> {
+        // Compressed entries of the solid stream up to the current entry.
+        let mut stream = vec![];
+
+        // RAR14 only stores whether the whole archive is solid.
+        let mut rar14_solid = false;
+
+        let mut entries = 0;
+        for block in self.blocks()? {
+            let block = block?;
+
+            if let Block::Rar14(rar14::Block::Main(main)) = &block {
+                rar14_solid = main.is_solid();
+            }
+
+            let Some(entry) = FileEntry::from_block(&block) else {
+                continue;
+            };
+            let i = entries;
+            entries += 1;
+
+            let is_compressed = !entry.is_directory && !entry.is_stored;
+            if is_compressed {
+                if !entry.is_solid && !rar14_solid {
+                    stream.clear();
+                }
+                stream.push(i);
+            }
+
+            if i == index {
+                if !is_compressed {
+                    stream = vec![i];
+                }
+                return Ok(Some(SolidPlan { entries: stream }));
+            }
+        }
+
+        Ok(None)
+    }
+}
@@ -0,0 +1,180 @@
+use std::io::{Read, Seek};
+
+use crate::{
+    error::{Error, RarResult},
+    rar14, rar15, rar50,
+    signature::Signature,
+};
+
+use super::{Archive, Block, FileEntry, HostOs};
+
+#[derive(Debug, Clone, PartialEq)]
+/// Totals of the entries of an archive, like the ones printed by `unrar l -v`.
+///
+/// Returned by [`Archive::summary`].
+pub struct ArchiveSummary {
+    /// Format of the archive.
+    pub format: Signature,
+
+    /// Number of file entries, including directories.
+    pub entries: usize,
+
+    /// Number of directory entries.
+    pub directories: usize,
+
+    /// Total size of the packed data of the entries.
+    pub packed_size: u64,
+
+    /// Total size of the entries after decompression, not counting the entries whose
+    /// unpacked size is not known.
+    pub unpacked_size: u64,
+
+    /// The headers or the data of some entries are encrypted.
+    pub is_encrypted: bool,
+
+    /// The headers are encrypted, so only the blocks before the first encrypted one were
+    /// read.
+    pub has_encrypted_headers: bool,
+
+    /// The archive is solid.
+    pub is_solid: bool,
+
+    /// The archive is locked and can't be modified by RAR.
+    pub is_locked: bool,
+
+    /// The archive contains a recovery record.
+    pub has_recovery_record: bool,
+
+    /// Number of entries added on each host OS, in the order the OSes first appear in the
+    /// archive.
+    pub host_os: Vec<(HostOs, usize)>,
+
+    /// Earliest modification time of an entry, as returned by
+    /// [`FileEntry::modification_time`].
+    pub earliest_modification_time: Option<time::OffsetDateTime>,
+
+    /// Latest modification time of an entry, as returned by
+    /// [`FileEntry::modification_time`].
+    pub latest_modification_time: Option<time::OffsetDateTime>,
+}
+
+impl ArchiveSummary {
+    fn new(format: Signature) -> Self {
+        Self {
+            format,
+            entries: 0,
+            directories: 0,
+            packed_size: 0,
+            unpacked_size: 0,
+            is_encrypted: false,
+            has_encrypted_headers: false,
+            is_solid: false,
+            is_locked: false,
+            has_recovery_record: false,
+            host_os: vec![],
+            earliest_modification_time: None,
+            latest_modification_time: None,
+        }
+    }
+
+    fn add_block(&mut self, block: &Block) {
+        match block {
+            Block::Rar14(rar14::Block::Main(main)) => {
+                self.is_solid = main.is_solid();
+                self.is_locked = main.is_locked();
+            }
+
+            Block::Rar15(block) => match &block.kind {
+                rar15::BlockKind::Main(main) => {
+                    self.is_solid = main.is_solid();
+                    self.is_locked = main.is_locked();
+                    self.has_recovery_record |= main.has_recovery_record();
+                }
+                rar15::BlockKind::Protect(_) => self.has_recovery_record = true,
+                _ => {}
+            },
+
+            Block::Rar50(block) => match &block.kind {
+                rar50::BlockKind::Main(main) => {
+                    self.is_solid = main.is_solid();
+                    self.is_locked = main.is_locked();
+                    self.has_recovery_record |= main.has_recovery_record();
+                }
+                rar50::BlockKind::Service(service) => {
+                    if matches!(service.kind, rar50::ServiceBlockKind::RecoveryRecord(_)) {
+                        self.has_recovery_record = true;
+                    }
+                }
+                _ => {}
+            },
+
+            _ => {}
+        }
+
+        if let Some(entry) = FileEntry::from_block(block) {
+            self.add_entry(&entry);
+        }
+    }
+
+    fn add_entry(&mut self, entry: &FileEntry) {
+        self.entries += 1;
+        self.directories += entry.is_directory as usize;
+        self.packed_size += entry.packed_size;
+        self.unpacked_size += entry.unpacked_size.unwrap_or(0);
+        self.is_encrypted |= entry.is_encrypted;
+
+        match self.host_os.iter_mut().find(|(os, _)| *os == entry.host_os) {
+            Some((_, count)) => *count += 1,
+            None => self.host_os.push((entry.host_os, 1)),
+        }
+
+        if let Some(time) = entry.modification_time() {
+            self.earliest_modification_time = Some(
+                self.earliest_modification_time
+                    .map_or(time, |earliest| earliest.min(time)),
+            );
+            self.latest_modification_time = Some(
+                self.latest_modification_time
+                    .map_or(time, |latest| latest.max(time)),
+            );
+        }
+    }
+
+    /// Ratio between the packed and the unpacked size of the entries, or `None` if the
+    /// unpacked size is 0.
+    ///
+    /// `unrar` prints this as a percentage.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        if self.unpacked_size == 0 {
+            None
+        } else {
+            Some(self.packed_size as f64 / self.unpacked_size as f64)
+        }
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Compute the totals of the entries of the archive.
+    ///
+    /// Only the block headers are read. The entries of archives with encrypted headers are
+    /// only counted up to the first encrypted block.
+    pub fn summary(&mut self) -> RarResult<ArchiveSummary> {
+        let mut summary = ArchiveSummary::new(self.format());
+
+        for block in self.blocks()? {
+            match block {
+                Ok(block) => summary.add_block(&block),
+
+                // The rest of the blocks can't be read without a password.
+                Err(Error::PasswordRequired) => {
+                    summary.is_encrypted = true;
+                    summary.has_encrypted_headers = true;
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(summary)
+    }
+}
@@ -0,0 +1,46 @@
+use std::{
+    io::{self, Read, Seek},
+    ops::Range,
+};
+
+use crate::error::RarResult;
+
+use super::Archive;
+
+impl<R: Read + Seek> Archive<R> {
+    /// Find the bytes appended after the end of archive block, which are ignored by RAR
+    /// and may be used to smuggle a payload in an otherwise valid archive.
+    ///
+    /// All the block headers are read to find the end of the archive. Returns `None` if
+    /// the file ends with the end of archive block, or if there is no end of archive
+    /// block, like in RAR14 archives and in archives truncated before it.
+    pub fn trailing_data(&mut self) -> RarResult<Option<Range<u64>>> {
+        let mut blocks = self.blocks()?;
+        for block in blocks.by_ref() {
+            block?;
+        }
+
+        Ok(blocks.trailing_data().filter(|range| !range.is_empty()))
+    }
+
+    /// Copy the bytes appended after the end of archive block to `writer`, for example to
+    /// inspect them or to strip them from the archive.
+    ///
+    /// Returns the number of bytes copied, which is 0 if there are none.
+    pub fn copy_trailing_data<W: io::Write>(&mut self, mut writer: W) -> RarResult<u64> {
+        let Some(range) = self.trailing_data()? else {
+            return Ok(0);
+        };
+
+        let reader = self.reader();
+        reader.seek(io::SeekFrom::Start(range.start))?;
+
+        let size = range.end - range.start;
+        let copied = io::copy(&mut reader.take(size), &mut writer)?;
+        if copied != size {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        Ok(copied)
+    }
+}
@@ -0,0 +1,305 @@
+use std::io::{self, Read, Seek};
+
+use crate::{error::RarResult, rar15, rar50};
+
+use super::{Archive, Block, FileEntry};
+
+/// Reader of the packed data of an entry, stopping at its end.
+type Packed<'a, R> = io::Take<&'a mut R>;
+
+enum Inner<'a, R> {
+    /// Data of a stored RAR14 entry, which has no decoder.
+    Stored(Packed<'a, R>),
+    Rar15(Box<rar15::Decoder<Packed<'a, R>>>),
+    Rar50(Box<rar50::Decoder<Packed<'a, R>>>),
+}
+
+/// Reader of the unpacked data of an entry returned by [`Archive::open_entry`], which can
+/// seek anywhere in it.
+///
+/// Seeking in stored entries moves the underlying reader directly. Compressed data can only
+/// be decoded from its start, so seeking forward in a compressed entry decodes and discards
+/// the data in between, and seeking backward starts decoding again from the start of the
+/// entry.
+///
+/// Like the decoders, the checksum of the entry is checked once its end is reached. It
+/// can't be checked after seeking over part of the data of a stored entry.
+///
+/// Solid entries are decoded after the entries before them in their solid stream, which
+/// are decoded again each time the reader seeks backward.
+///
+/// Unlike [`EntryReader`](super::EntryReader), which reads the packed data of an entry of a
+/// multi-volume archive, this reads the unpacked data of an entry stored in a single volume.
+pub struct UnpackedEntryReader<'a, R: Read + Seek> {
+    /// Only `None` while the decoder is being restarted.
+    inner: Option<Inner<'a, R>>,
+    entry: FileEntry,
+    block: Block,
+
+    /// Entries to decode before this one, from the start of its solid stream.
+    prerequisites: Vec<(FileEntry, Block)>,
+
+    /// Position in the unpacked data.
+    pos: u64,
+}
+
+impl<'a, R: Read + Seek> UnpackedEntryReader<'a, R> {
+    /// Create a reader for the data of `entry`, whose file block is `block`.
+    ///
+    /// Returns an [`io::ErrorKind::Unsupported`] error if the entry is encrypted, part of
+    /// a solid stream, split across volumes, or compressed with an unsupported method.
+    pub fn new(reader: &'a mut R, entry: FileEntry, block: Block) -> io::Result<Self> {
+        Self::with_prerequisites(reader, entry, block, vec![])
+    }
+
+    /// Create a reader for the data of the solid `entry` like [`Self::new`], decoding the
+    /// `prerequisites` listed by [`Archive::solid_plan`] first.
+    fn with_prerequisites(
+        reader: &'a mut R,
+        entry: FileEntry,
+        block: Block,
+        prerequisites: Vec<(FileEntry, Block)>,
+    ) -> io::Result<Self> {
+        let entries = prerequisites.iter().map(|(entry, _)| entry);
+        if entries
+            .chain([&entry])
+            .any(|e| e.split_before || e.split_after)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "entries split across volumes are not supported",
+            ));
+        }
+
+        let inner = Self::start(reader, &entry, &block, &prerequisites)?;
+
+        Ok(Self {
+            inner: Some(inner),
+            entry,
+            block,
+            prerequisites,
+            pos: 0,
+        })
+    }
+
+    /// Create the reader of the data of the entry from its start, decoding the
+    /// `prerequisites` first.
+    fn start(
+        reader: &'a mut R,
+        entry: &FileEntry,
+        block: &Block,
+        prerequisites: &[(FileEntry, Block)],
+    ) -> io::Result<Inner<'a, R>> {
+        let Some(((first_entry, first_block), rest)) = prerequisites.split_first() else {
+            return Self::start_entry(reader, entry, block);
+        };
+
+        let mut inner = Self::start_entry(reader, first_entry, first_block)?;
+
+        let next = rest.iter().map(|(entry, block)| (entry, block));
+        for (entry, block) in next.chain([(entry, block)]) {
+            let seek = |packed: &mut Packed<'a, R>| {
+                packed
+                    .get_mut()
+                    .seek(io::SeekFrom::Start(entry.data_range().start))?;
+                packed.set_limit(entry.packed_size);
+                Ok(())
+            };
+
+            match (&mut inner, block) {
+                (
+                    Inner::Rar15(decoder),
+                    Block::Rar15(rar15::Block {
+                        kind: rar15::BlockKind::File(file),
+                        ..
+                    }),
+                ) => decoder.continue_solid(file, seek)?,
+                (Inner::Rar50(decoder), Block::Rar50(block)) => match &block.kind {
+                    rar50::BlockKind::File(file) => decoder.continue_solid(file, seek)?,
+                    _ => return Err(io::ErrorKind::InvalidInput.into()),
+                },
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        "solid RAR14 entries are not supported",
+                    ))
+                }
+            }
+        }
+
+        Ok(inner)
+    }
+
+    /// Create the reader of the data of a single entry from its start.
+    fn start_entry(
+        reader: &'a mut R,
+        entry: &FileEntry,
+        block: &Block,
+    ) -> io::Result<Inner<'a, R>> {
+        reader.seek(io::SeekFrom::Start(entry.data_range().start))?;
+        let packed = reader.take(entry.packed_size);
+
+        Ok(match block {
+            Block::Rar15(rar15::Block {
+                kind: rar15::BlockKind::File(file),
+                ..
+            }) => Inner::Rar15(Box::new(rar15::Decoder::new(packed, file)?)),
+            Block::Rar50(block) => match &block.kind {
+                rar50::BlockKind::File(file) => {
+                    Inner::Rar50(Box::new(rar50::Decoder::new(packed, file)?))
+                }
+                _ => return Err(io::ErrorKind::InvalidInput.into()),
+            },
+            // There is no decoder for RAR14, so only stored entries can be read.
+            Block::Rar14(_) if entry.is_stored && !entry.is_encrypted => Inner::Stored(packed),
+            Block::Rar14(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "compressed and encrypted RAR14 entries are not supported",
+                ))
+            }
+            _ => return Err(io::ErrorKind::InvalidInput.into()),
+        })
+    }
+
+    /// Metadata of the entry.
+    pub fn entry(&self) -> &FileEntry {
+        &self.entry
+    }
+
+    /// Size of the unpacked data, if known.
+    pub fn unpacked_size(&self) -> Option<u64> {
+        self.entry.unpacked_size
+    }
+
+    /// Start decoding the entry again from the start of its data.
+    fn restart(&mut self) -> io::Result<()> {
+        let reader = match self.inner.take() {
+            Some(Inner::Stored(packed)) => packed.into_inner(),
+            Some(Inner::Rar15(decoder)) => decoder.into_inner().into_inner(),
+            Some(Inner::Rar50(decoder)) => decoder.into_inner().into_inner(),
+            None => return Err(io::Error::other("the reader failed to restart")),
+        };
+
+        self.inner = Some(Self::start(
+            reader,
+            &self.entry,
+            &self.block,
+            &self.prerequisites,
+        )?);
+        self.pos = 0;
+        Ok(())
+    }
+
+    /// Move forward by `n` bytes from the current position, seeking over stored data.
+    fn forward(&mut self, n: u64) -> io::Result<u64> {
+        match &mut self.inner {
+            Some(Inner::Stored(packed)) => {
+                let n = n.min(packed.limit());
+                packed.get_mut().seek(io::SeekFrom::Current(n as i64))?;
+                packed.set_limit(packed.limit() - n);
+                Ok(n)
+            }
+            Some(Inner::Rar15(decoder)) => decoder.seek_forward(n),
+            Some(Inner::Rar50(decoder)) => decoder.seek_forward(n),
+            None => Err(io::Error::other("the reader failed to restart")),
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for UnpackedEntryReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = match &mut self.inner {
+            Some(Inner::Stored(packed)) => packed.read(buf)?,
+            Some(Inner::Rar15(decoder)) => decoder.read(buf)?,
+            Some(Inner::Rar50(decoder)) => decoder.read(buf)?,
+            None => return Err(io::Error::other("the reader failed to restart")),
+        };
+
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for UnpackedEntryReader<'_, R> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            io::SeekFrom::Start(offset) => Some(offset),
+            io::SeekFrom::End(offset) => {
+                let Some(size) = self.unpacked_size() else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        "the unpacked size of the entry is unknown",
+                    ));
+                };
+                size.checked_add_signed(offset)
+            }
+            io::SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        if target < self.pos {
+            self.restart()?;
+        }
+
+        // Seeking past the end leaves the decoder at the end of the data.
+        self.forward(target - self.pos)?;
+        self.pos = target;
+
+        Ok(target)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.pos)
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Open the unpacked data of the entry at `index` in the list returned by
+    /// [`Self::entries`] for reading and seeking.
+    ///
+    /// The offsets of the entries are indexed on the first call like with
+    /// [`Self::entry_at`].
+    ///
+    /// Entries of a solid stream are decoded after the entries before them, which are listed
+    /// by [`Self::solid_plan`].
+    ///
+    /// Returns `None` if there is no entry at `index`, and an [`io::ErrorKind::Unsupported`]
+    /// error under the same conditions as [`UnpackedEntryReader::new`], except for entries
+    /// of RAR15 and RAR50 solid streams.
+    pub fn open_entry(&mut self, index: usize) -> RarResult<Option<UnpackedEntryReader<'_, R>>> {
+        let Some((entry, block)) = self.entry_block(index)? else {
+            return Ok(None);
+        };
+
+        let mut prerequisites = vec![];
+        if entry.is_solid {
+            if let Some(plan) = self.solid_plan(index)? {
+                for &i in plan.prerequisites() {
+                    prerequisites.extend(self.entry_block(i)?);
+                }
+            }
+        }
+
+        Ok(Some(UnpackedEntryReader::with_prerequisites(
+            self.reader(),
+            entry,
+            block,
+            prerequisites,
+        )?))
+    }
+
+    fn entry_block(&mut self, index: usize) -> RarResult<Option<(FileEntry, Block)>> {
+        let Some(&offset) = self.entry_offsets()?.get(index) else {
+            return Ok(None);
+        };
+
+        let block = self.block_at(offset)?;
+        Ok(FileEntry::from_block(&block).map(|entry| (entry, block)))
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    io::{self, Read, Seek},
+    sync::Arc,
+};
+
+use crate::{
+    error::RarResult,
+    hash::{Blake2sp, Crc32},
+    Progress,
+};
+
+use super::{peek::unpacked_reader, Archive, Block, FileEntry, HashKind, HashType};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Result of checking the data of an entry with [`Archive::verify`].
+pub enum VerifyStatus {
+    /// The data was unpacked and all of its hashes match.
+    Ok,
+
+    /// The data was unpacked without errors, but there is no hash that can be checked.
+    Unverified,
+
+    /// The data was unpacked, but the hash of type `hash` doesn't match.
+    HashMismatch {
+        hash: HashType,
+        expected: Vec<u8>,
+        actual: Vec<u8>,
+    },
+
+    /// The data couldn't be unpacked.
+    Corrupt,
+
+    /// The entry can't be extracted because it's encrypted, part of a solid stream, split
+    /// across volumes, compressed with an unsupported method, or needs a window larger
+    /// than the default [`Limits::max_window_size`](crate::Limits::max_window_size).
+    NotExtractable,
+}
+
+#[derive(Debug, Clone)]
+/// Result of checking the data of an entry, returned by [`Archive::verify`] and
+/// [`Archive::test`].
+pub struct EntryReport {
+    /// Metadata of the entry.
+    pub entry: FileEntry,
+
+    /// Number of bytes unpacked before the end of the entry or the first error.
+    pub unpacked: u64,
+
+    pub status: VerifyStatus,
+
+    /// Bytes of the entry read from the archive and unpacked.
+    pub progress: Arc<Progress>,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Result of checking the data of every entry of an archive with [`Archive::test`].
+pub struct TestReport {
+    /// Reports of the entries, in the order they appear in the archive.
+    pub entries: Vec<EntryReport>,
+}
+
+impl TestReport {
+    /// No entry is corrupt or has a hash that doesn't match.
+    ///
+    /// Entries which can't be extracted or checked are not counted as failures.
+    pub fn is_ok(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Entries which are corrupt or have a hash that doesn't match.
+    pub fn failures(&self) -> impl Iterator<Item = &EntryReport> {
+        self.entries.iter().filter(|report| {
+            matches!(
+                report.status,
+                VerifyStatus::Corrupt | VerifyStatus::HashMismatch { .. }
+            )
+        })
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Unpack the data of every entry and check it against the hashes stored in the
+    /// archive, like `unrar t`.
+    pub fn test(&mut self) -> RarResult<TestReport> {
+        self.test_with_progress(&Progress::new())
+    }
+
+    /// Same as [`Self::test`], adding the bytes read from the archive and unpacked to
+    /// `progress` while the entries are checked.
+    ///
+    /// The counters of each entry are children of `progress`, and are returned in
+    /// [`EntryReport::progress`].
+    pub fn test_with_progress(&mut self, progress: &Arc<Progress>) -> RarResult<TestReport> {
+        let mut entries = vec![];
+        for block in self.blocks()? {
+            let block = block?;
+            if let Some(entry) = FileEntry::from_block(&block) {
+                entries.push((entry, block));
+            }
+        }
+
+        let mut report = TestReport::default();
+        for (entry, block) in entries {
+            report
+                .entries
+                .push(self.verify_entry(entry, &block, progress.child())?);
+        }
+
+        Ok(report)
+    }
+
+    /// Unpack the data of the entry at `index` in the list returned by [`Self::entries`]
+    /// and check it against the hashes stored in the archive.
+    ///
+    /// Returns `None` if there is no entry at `index`.
+    pub fn verify(&mut self, index: usize) -> RarResult<Option<EntryReport>> {
+        let mut found = None;
+        let mut entries = 0;
+        for block in self.blocks()? {
+            let block = block?;
+            if let Some(entry) = FileEntry::from_block(&block) {
+                if entries == index {
+                    found = Some((entry, block));
+                    break;
+                }
+                entries += 1;
+            }
+        }
+
+        let Some((entry, block)) = found else {
+            return Ok(None);
+        };
+
+        self.verify_entry(entry, &block, Progress::new()).map(Some)
+    }
+
+    fn verify_entry(
+        &mut self,
+        entry: FileEntry,
+        block: &Block,
+        progress: Arc<Progress>,
+    ) -> RarResult<EntryReport> {
+        let reader = self.reader();
+        reader.seek(io::SeekFrom::Start(entry.offset + entry.header_size))?;
+        let packed = progress.packed_reader(reader.take(entry.packed_size));
+
+        let Some((unpacked, _)) = unpacked_reader(packed, &entry, block) else {
+            return Ok(EntryReport {
+                entry,
+                unpacked: 0,
+                status: VerifyStatus::NotExtractable,
+                progress,
+            });
+        };
+        let mut unpacked = progress.unpacked_reader(unpacked);
+
+        let mut crc32 = match entry.checksum {
+            Some(HashKind::Crc32(expected)) => Some((Crc32::new(), expected)),
+            _ => None,
+        };
+        let mut blake2sp = entry.blake2sp.map(|expected| (Blake2sp::new(), expected));
+
+        let mut size = 0;
+        let mut buf = vec![0; 0x10000];
+        let error = loop {
+            match unpacked.read(&mut buf) {
+                Ok(0) => break false,
+                Ok(n) => {
+                    size += n as u64;
+                    if let Some((hasher, _)) = &mut crc32 {
+                        hasher.update(&buf[..n]);
+                    }
+                    if let Some((hasher, _)) = &mut blake2sp {
+                        hasher.update(&buf[..n]);
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+
+                // Decoders report hash mismatches as errors once they reach the end of
+                // the data, so these are told apart from corrupt data below.
+                Err(_) => break true,
+            }
+        };
+
+        let complete = entry.unpacked_size.is_none_or(|expected| expected == size);
+
+        let mut status = if error || !complete {
+            VerifyStatus::Corrupt
+        } else if crc32.is_none() && blake2sp.is_none() {
+            VerifyStatus::Unverified
+        } else {
+            VerifyStatus::Ok
+        };
+
+        if complete {
+            if let Some((hasher, expected)) = crc32 {
+                let actual = hasher.finalize();
+                if actual != expected {
+                    status = VerifyStatus::HashMismatch {
+                        hash: HashType::Crc32,
+                        expected: expected.to_be_bytes().to_vec(),
+                        actual: actual.to_be_bytes().to_vec(),
+                    };
+                }
+            }
+        }
+
+        if complete && !matches!(status, VerifyStatus::HashMismatch { .. }) {
+            if let Some((hasher, expected)) = blake2sp {
+                let actual = hasher.finalize();
+                if actual != expected {
+                    status = VerifyStatus::HashMismatch {
+                        hash: HashType::Blake2sp,
+                        expected: expected.to_vec(),
+                        actual: actual.to_vec(),
+                    };
+                }
+            }
+        }
+
+        Ok(EntryReport {
+            entry,
+            unpacked: size,
+            status,
+            progress,
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    io::{Read, Seek},
+};
+
+use crate::error::RarResult;
+
+use super::{Archive, FileEntry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Which versions of each file are returned by [`Archive::entry_versions`].
+pub enum VersionSelection {
+    /// Only the latest version of each file.
+    #[default]
+    Latest,
+
+    /// All the versions of each file.
+    All,
+}
+
+#[derive(Debug, Clone)]
+/// Versions of a file stored in an archive, which are added by RAR's file version control
+/// when a file is updated.
+///
+/// Returned by [`Archive::entry_versions`].
+pub struct EntryVersions {
+    /// Name of the file, shared by all its versions.
+    pub name: Result<String, Vec<u8>>,
+
+    /// Entries of the file from the latest to the oldest version, which is never empty.
+    ///
+    /// The latest version is the entry without a [`FileEntry::version`], followed by the
+    /// older versions from the highest version number to the lowest.
+    pub versions: Vec<FileEntry>,
+}
+
+impl EntryVersions {
+    /// Entry of the latest version of the file.
+    pub fn latest(&self) -> &FileEntry {
+        &self.versions[0]
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// List the file entries of the archive grouped by name, in the order in which the
+    /// first version of each file appears in the archive.
+    ///
+    /// Only the latest version of each file is kept unless `selection` is
+    /// [`VersionSelection::All`].
+    pub fn entry_versions(&mut self, selection: VersionSelection) -> RarResult<Vec<EntryVersions>> {
+        let mut files: Vec<EntryVersions> = vec![];
+        let mut indices = HashMap::new();
+
+        for entry in self.entry_iter()? {
+            let entry = entry?;
+            let index = *indices.entry(entry.name.clone()).or_insert_with(|| {
+                files.push(EntryVersions {
+                    name: entry.name.clone(),
+                    versions: vec![],
+                });
+                files.len() - 1
+            });
+            files[index].versions.push(entry);
+        }
+
+        for file in &mut files {
+            file.versions
+                .sort_by_key(|entry| Reverse((entry.version.is_none(), entry.version)));
+            if selection == VersionSelection::Latest {
+                file.versions.truncate(1);
+            }
+        }
+
+        Ok(files)
+    }
+}
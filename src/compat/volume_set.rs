@@ -0,0 +1,367 @@
+use std::{
+    fs,
+    io::{self, Read as _},
+    ops::RangeInclusive,
+    path::PathBuf,
+};
+
+use crate::{error::Error, hash::Crc32, rar14, rar15, rar50};
+
+use super::{Archive, Block, FileEntry, HashKind};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Status of a single volume of a [`VolumeSet`].
+pub struct VolumeStatus {
+    /// Path of the volume.
+    pub path: PathBuf,
+
+    /// The volume file exists and can be opened.
+    pub present: bool,
+
+    /// The volume starts with a RAR signature.
+    pub signature_ok: bool,
+
+    /// All the block headers of the volume could be read up to its end.
+    ///
+    /// This is always `false` for archives with encrypted headers, which can't be read
+    /// without a password.
+    pub header_chain_ok: bool,
+
+    /// Whether the data of the entries whose CRC32 can be checked without decompressing
+    /// them matches, or `None` if the volume doesn't contain any.
+    ///
+    /// These are the segments of entries that continue in the next volume, which store the
+    /// CRC32 of their packed data, and the entries that are stored without compression
+    /// in a single segment. Encrypted entries are never checked.
+    pub crc_sampled_ok: Option<bool>,
+}
+
+impl VolumeStatus {
+    /// The volume passed all the checks.
+    pub fn is_ok(&self) -> bool {
+        self.present
+            && self.signature_ok
+            && self.header_chain_ok
+            && self.crc_sampled_ok != Some(false)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Volumes spanned by the data of an entry of a [`VolumeSet`].
+pub struct EntrySpan {
+    /// Name of the entry, with invalid UTF-8 sequences replaced.
+    pub name: String,
+
+    /// Indices of the first and last volumes containing the data of the entry.
+    ///
+    /// If some of the segments of the entry are in volumes that couldn't be read, the
+    /// range conservatively includes all the unreadable volumes they could be in.
+    pub volumes: RangeInclusive<usize>,
+}
+
+#[derive(Debug, Clone)]
+/// Integrity report of the volumes of a multi-volume archive.
+///
+/// Each volume is checked on its own, so the report can be produced with any number of
+/// volumes missing or corrupt, and can be used to find out which volumes need to be
+/// acquired again to extract some of the entries.
+pub struct VolumeSet {
+    volumes: Vec<VolumeStatus>,
+    entries: Vec<EntrySpan>,
+    is_solid: bool,
+}
+
+/// Entry segments read from a single volume.
+struct Segment {
+    name: String,
+    split_before: bool,
+    split_after: bool,
+}
+
+impl VolumeSet {
+    /// Check the volumes at `paths`, which must be all the volumes of the archive in order.
+    pub fn check<P: Into<PathBuf>>(paths: impl IntoIterator<Item = P>) -> Self {
+        Self::from_checked(paths.into_iter().map(|path| check_volume(path.into())))
+    }
+
+    #[cfg(feature = "parallel")]
+    /// Check the volumes at `paths` like [`Self::check`], reading up to `threads` volumes
+    /// at the same time.
+    ///
+    /// Each volume is read by its own thread, so this is faster than [`Self::check`] when
+    /// the volumes are large or on a network file system. The result is the same.
+    pub fn check_parallel<P: Into<PathBuf>>(
+        paths: impl IntoIterator<Item = P>,
+        threads: std::num::NonZeroUsize,
+    ) -> Self {
+        use std::sync::{atomic, Mutex};
+
+        let paths: Vec<PathBuf> = paths.into_iter().map(Into::into).collect();
+        let checked: Vec<_> = paths.iter().map(|_| Mutex::new(None)).collect();
+        let next = atomic::AtomicUsize::new(0);
+
+        std::thread::scope(|scope| {
+            for _ in 0..threads.get().min(paths.len()) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, atomic::Ordering::Relaxed);
+                    let Some(path) = paths.get(index) else {
+                        break;
+                    };
+
+                    let volume = check_volume(path.clone());
+                    *checked[index].lock().unwrap() = Some(volume);
+                });
+            }
+        });
+
+        Self::from_checked(checked.into_iter().map(|volume| {
+            let volume = volume.into_inner().unwrap();
+            volume.expect("all the volumes are checked when the threads end")
+        }))
+    }
+
+    /// Merge the results of [`check_volume`] for all the volumes, in order.
+    fn from_checked(checked: impl Iterator<Item = (VolumeStatus, Vec<Segment>, bool)>) -> Self {
+        let mut volumes = vec![];
+        let mut segments = vec![];
+        let mut is_solid = false;
+
+        for (status, volume_segments, volume_is_solid) in checked {
+            volumes.push(status);
+            segments.push(volume_segments);
+            is_solid |= volume_is_solid;
+        }
+
+        let entries = entry_spans(&volumes, &segments);
+
+        Self {
+            volumes,
+            entries,
+            is_solid,
+        }
+    }
+
+    /// Status of each volume, in order.
+    pub fn volumes(&self) -> &[VolumeStatus] {
+        &self.volumes
+    }
+
+    /// Entries found in the readable parts of the volumes, in order.
+    ///
+    /// Entries whose segments are all in unreadable volumes can't be listed.
+    pub fn entries(&self) -> &[EntrySpan] {
+        &self.entries
+    }
+
+    /// The volumes passed all the checks.
+    pub fn is_ok(&self) -> bool {
+        self.volumes.iter().all(VolumeStatus::is_ok)
+    }
+
+    /// Indices of the volumes needed to extract the entry called `name`, or `None` if the
+    /// entry wasn't found.
+    ///
+    /// Extracting an entry of a solid archive requires decompressing all the entries
+    /// before it, so all the volumes up to its last one are needed.
+    pub fn volumes_needed(&self, name: &str) -> Option<RangeInclusive<usize>> {
+        let span = self.entries.iter().find(|entry| entry.name == name)?;
+
+        if self.is_solid {
+            Some(0..=*span.volumes.end())
+        } else {
+            Some(span.volumes.clone())
+        }
+    }
+
+    /// Indices of the volumes that failed the checks and are needed to extract the entries
+    /// called `names`, in order.
+    ///
+    /// Names of entries that weren't found are ignored.
+    pub fn volumes_to_reacquire<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+        let mut indices: Vec<_> = names
+            .into_iter()
+            .filter_map(|name| self.volumes_needed(name))
+            .flatten()
+            .filter(|&i| !self.volumes[i].is_ok())
+            .collect();
+
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+}
+
+fn check_volume(path: PathBuf) -> (VolumeStatus, Vec<Segment>, bool) {
+    let mut status = VolumeStatus {
+        path,
+        present: false,
+        signature_ok: false,
+        header_chain_ok: false,
+        crc_sampled_ok: None,
+    };
+    let mut segments = vec![];
+    let mut is_solid = false;
+
+    let Ok(file) = fs::File::open(&status.path) else {
+        return (status, segments, is_solid);
+    };
+    status.present = true;
+
+    let mut archive = match Archive::new(io::BufReader::new(file)) {
+        Ok(archive) => archive,
+        Err(_) => return (status, segments, is_solid),
+    };
+    status.signature_ok = true;
+
+    let mut samples = vec![];
+    let blocks = match archive.blocks() {
+        Ok(blocks) => blocks,
+        Err(_) => return (status, segments, is_solid),
+    };
+
+    let mut chain_ok = true;
+    for block in blocks {
+        let block = match block {
+            Ok(block) => block,
+            Err(_) => {
+                chain_ok = false;
+                break;
+            }
+        };
+
+        is_solid |= is_solid_main_block(&block);
+
+        let Some(entry) = FileEntry::from_block(&block) else {
+            continue;
+        };
+
+        // Segments that continue in the next volume store the CRC32 of their packed data.
+        let checksum_is_packed = entry.split_after || (entry.is_stored && !entry.split_before);
+        if let Some(HashKind::Crc32(crc32)) = entry.checksum {
+            if checksum_is_packed && !entry.is_encrypted {
+                samples.push((entry.offset + entry.header_size, entry.packed_size, crc32));
+            }
+        }
+
+        segments.push(Segment {
+            name: entry.name_lossy().into_owned(),
+            split_before: entry.split_before,
+            split_after: entry.split_after,
+        });
+    }
+    status.header_chain_ok = chain_ok;
+
+    if !samples.is_empty() {
+        let reader = archive.reader();
+        let sampled_ok = samples.into_iter().all(|(offset, size, expected)| {
+            matches!(packed_crc32(reader, offset, size), Ok(crc32) if crc32 == expected)
+        });
+        status.crc_sampled_ok = Some(sampled_ok);
+    }
+
+    (status, segments, is_solid)
+}
+
+fn is_solid_main_block(block: &Block) -> bool {
+    match block {
+        Block::Rar14(rar14::Block::Main(main)) => main.is_solid(),
+        Block::Rar15(rar15::Block {
+            kind: rar15::BlockKind::Main(main),
+            ..
+        }) => main.is_solid(),
+        Block::Rar50(block) => match &block.kind {
+            rar50::BlockKind::Main(main) => main.is_solid(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn packed_crc32<R: io::Read + io::Seek>(
+    reader: &mut R,
+    offset: u64,
+    size: u64,
+) -> Result<u32, Error> {
+    reader.seek(io::SeekFrom::Start(offset))?;
+
+    let mut crc = Crc32::new();
+    let mut reader = reader.take(size);
+    let mut buf = [0; 0x4000];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        crc.update(&buf[..n]);
+    }
+
+    if reader.limit() > 0 {
+        return Err(Error::UnexpectedEof);
+    }
+
+    Ok(crc.finalize())
+}
+
+/// Find the volumes spanned by each entry from the segments of each volume.
+fn entry_spans(volumes: &[VolumeStatus], segments: &[Vec<Segment>]) -> Vec<EntrySpan> {
+    let mut entries: Vec<EntrySpan> = vec![];
+
+    // Entry continuing after the last segment that was read, if any.
+    let mut open: Option<usize> = None;
+
+    for (i, volume_segments) in segments.iter().enumerate() {
+        for segment in volume_segments {
+            let continued = match open.take() {
+                Some(index) if segment.split_before && entries[index].name == segment.name => {
+                    Some(index)
+                }
+                _ => None,
+            };
+
+            let index = match continued {
+                Some(index) => {
+                    let start = *entries[index].volumes.start();
+                    entries[index].volumes = start..=i;
+                    index
+                }
+                None => {
+                    // The first segments are in the unreadable volumes before this one.
+                    let start = if segment.split_before {
+                        unreadable_run_start(volumes, i)
+                    } else {
+                        i
+                    };
+
+                    entries.push(EntrySpan {
+                        name: segment.name.clone(),
+                        volumes: start..=i,
+                    });
+                    entries.len() - 1
+                }
+            };
+
+            if segment.split_after {
+                open = Some(index);
+            }
+        }
+
+        // If the volume couldn't be read entirely, the entry may continue in it.
+        if !volumes[i].is_ok() {
+            if let Some(index) = open {
+                let start = *entries[index].volumes.start();
+                entries[index].volumes = start..=i;
+            }
+        }
+    }
+
+    entries
+}
+
+/// Index of the first of the unreadable volumes right before the volume at `index`.
+fn unreadable_run_start(volumes: &[VolumeStatus], index: usize) -> usize {
+    let mut start = index.saturating_sub(1);
+    while start > 0 && !volumes[start - 1].is_ok() && !volumes[start].is_ok() {
+        start -= 1;
+    }
+    start
+}
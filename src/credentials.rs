@@ -0,0 +1,85 @@
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+};
+use core::fmt;
+
+/// What a [`CredentialProvider`] is asked a password for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PasswordRequest<'a> {
+    /// The headers of the archive, which are encrypted starting from the block at `offset`.
+    ///
+    /// Asked at most once by each block iterator, so each volume of a multi-volume archive
+    /// is asked separately.
+    Headers { offset: u64 },
+
+    /// The data of the encrypted file named `name`, decoded lossily if it isn't valid
+    /// UTF-8.
+    File { name: &'a str },
+}
+
+/// Source of the passwords of encrypted archives, which is only asked for a password when
+/// an encrypted header or file is read.
+///
+/// It's implemented for strings, which return the same password for every request, and
+/// for closures, which can be used to prompt for the password or to pick a different one
+/// for each file or volume.
+pub trait CredentialProvider {
+    /// Password for the `request`, or `None` to stop with
+    /// [`Error::PasswordRequired`](crate::Error::PasswordRequired).
+    fn password(&mut self, request: PasswordRequest) -> Option<String>;
+}
+
+impl CredentialProvider for &str {
+    fn password(&mut self, _: PasswordRequest) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl CredentialProvider for String {
+    fn password(&mut self, _: PasswordRequest) -> Option<String> {
+        Some(self.clone())
+    }
+}
+
+impl<F: FnMut(PasswordRequest) -> Option<String>> CredentialProvider for F {
+    fn password(&mut self, request: PasswordRequest) -> Option<String> {
+        self(request)
+    }
+}
+
+/// Password of the headers of an archive, which is asked to the provider the first time
+/// it's needed.
+#[derive(Default)]
+pub(crate) struct HeaderCredentials {
+    provider: Option<Box<dyn CredentialProvider + Send>>,
+    password: Option<String>,
+}
+
+impl HeaderCredentials {
+    pub fn new(provider: impl CredentialProvider + Send + 'static) -> Self {
+        Self {
+            provider: Some(Box::new(provider)),
+            password: None,
+        }
+    }
+
+    /// Password of the headers encrypted starting from the block at `offset`.
+    pub fn password(&mut self, offset: u64) -> Option<&str> {
+        if self.password.is_none() {
+            let request = PasswordRequest::Headers { offset };
+            self.password = self.provider.as_mut()?.password(request);
+        }
+
+        self.password.as_deref()
+    }
+}
+
+impl fmt::Debug for HeaderCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeaderCredentials")
+            .field("has_provider", &self.provider.is_some())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,92 @@
+//! Re-parse headers from their raw bytes with strict checks, to detect headers that
+//! different parsers could interpret in different ways.
+
+use alloc::{string::ToString, vec::Vec};
+use core::fmt::Debug;
+
+use crate::{
+    io::{self, Read as _},
+    warning::{Divergence, Warning, WarningKind},
+};
+
+/// Reader over the raw bytes of a header which reports positions relative to the start
+/// of the archive, so that the offsets of the re-parsed block match the original ones.
+pub(crate) struct HeaderCursor<'a> {
+    inner: io::Cursor<&'a [u8]>,
+    offset: u64,
+}
+
+impl<'a> HeaderCursor<'a> {
+    pub fn new(header: &'a [u8], offset: u64) -> Self {
+        Self {
+            inner: io::Cursor::new(header),
+            offset,
+        }
+    }
+
+    /// Number of bytes of the header that were read.
+    pub fn consumed(&self) -> u64 {
+        self.inner.position()
+    }
+}
+
+impl io::Read for HeaderCursor<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl io::Seek for HeaderCursor<'_> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            io::SeekFrom::Start(pos) => io::SeekFrom::Start(pos.checked_sub(self.offset).ok_or(
+                io::Error::new(io::ErrorKind::InvalidInput, "seek before the header"),
+            )?),
+            pos => pos,
+        };
+
+        Ok(self.inner.seek(pos)? + self.offset)
+    }
+}
+
+/// Read the raw bytes of the header of the block at `offset`.
+pub(crate) fn read_header<R: io::Read + io::Seek>(
+    reader: &mut R,
+    offset: u64,
+    header_size: u64,
+) -> io::Result<Vec<u8>> {
+    reader.seek(io::SeekFrom::Start(offset))?;
+
+    let mut header = vec![];
+    reader.by_ref().take(header_size).read_to_end(&mut header)?;
+    Ok(header)
+}
+
+/// Compare the result of the strict parse of a header with the lenient one, pushing
+/// a warning if they disagree.
+pub(crate) fn compare<B: Debug>(
+    offset: u64,
+    lenient: &B,
+    strict: Result<B, Divergence>,
+    warnings: &mut Vec<Warning>,
+) {
+    let divergence = match strict {
+        Ok(strict) if format!("{strict:?}") == format!("{lenient:?}") => return,
+        Ok(_) => Divergence::FieldsDiffer,
+        Err(divergence) => divergence,
+    };
+
+    warnings.push(Warning {
+        offset,
+        kind: WarningKind::ParserDivergence(divergence),
+    });
+}
+
+impl From<io::Error> for Divergence {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::UnexpectedEof => Self::Truncated,
+            _ => Self::Invalid(error.to_string()),
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! [AES](https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.197-upd1.pdf) decryption in CBC
+//! mode, which RAR uses with 128-bit keys since RAR 2.9 and with 256-bit keys in RAR5.
+
+use alloc::vec::Vec;
+
+pub const BLOCK_SIZE: usize = 16;
+
+const SBOX: [u8; 256] = {
+    // Multiplicative inverse in GF(2^8) followed by the affine transformation.
+    let mut sbox = [0; 256];
+    let mut p: u8 = 1;
+    let mut q: u8 = 1;
+
+    loop {
+        // Multiply p by 3 and divide q by 3, so that q is always the inverse of p.
+        p = p ^ (p << 1) ^ if p & 0x80 != 0 { 0x1b } else { 0 };
+        q ^= q << 1;
+        q ^= q << 2;
+        q ^= q << 4;
+        if q & 0x80 != 0 {
+            q ^= 0x09;
+        }
+
+        let x = q ^ q.rotate_left(1) ^ q.rotate_left(2) ^ q.rotate_left(3) ^ q.rotate_left(4);
+        sbox[p as usize] = x ^ 0x63;
+
+        if p == 1 {
+            break;
+        }
+    }
+
+    sbox[0] = 0x63;
+    sbox
+};
+
+const INV_SBOX: [u8; 256] = {
+    let mut inv = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        inv[SBOX[i] as usize] = i as u8;
+        i += 1;
+    }
+    inv
+};
+
+/// Multiply by x in GF(2^8).
+fn xtime(b: u8) -> u8 {
+    (b << 1) ^ if b & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = xtime(a);
+        b >>= 1;
+    }
+    product
+}
+
+#[derive(Clone)]
+/// AES block cipher with an expanded 128-bit or 256-bit key.
+pub struct Aes {
+    round_keys: Vec<[u8; BLOCK_SIZE]>,
+}
+
+impl Aes {
+    /// Expand a 16 or 32 byte key.
+    pub fn new(key: &[u8]) -> Self {
+        assert!(key.len() == 16 || key.len() == 32, "invalid AES key size");
+
+        let nk = key.len() / 4;
+        let rounds = nk + 6;
+
+        let mut words: Vec<[u8; 4]> = key.chunks_exact(4).map(|w| w.try_into().unwrap()).collect();
+        let mut rcon = 1;
+
+        for i in nk..4 * (rounds + 1) {
+            let mut word = words[i - 1];
+            if i % nk == 0 {
+                word.rotate_left(1);
+                word = word.map(|b| SBOX[b as usize]);
+                word[0] ^= rcon;
+                rcon = xtime(rcon);
+            } else if nk > 6 && i % nk == 4 {
+                word = word.map(|b| SBOX[b as usize]);
+            }
+
+            let previous = words[i - nk];
+            words.push(core::array::from_fn(|j| word[j] ^ previous[j]));
+        }
+
+        let round_keys = words
+            .chunks_exact(4)
+            .map(|round| core::array::from_fn(|i| round[i / 4][i % 4]))
+            .collect();
+
+        Self { round_keys }
+    }
+
+    /// Decrypt a single block in place.
+    pub fn decrypt_block(&self, block: &mut [u8; BLOCK_SIZE]) {
+        let rounds = self.round_keys.len() - 1;
+        add_round_key(block, &self.round_keys[rounds]);
+
+        for round in (0..rounds).rev() {
+            inv_shift_rows(block);
+            for b in block.iter_mut() {
+                *b = INV_SBOX[*b as usize];
+            }
+            add_round_key(block, &self.round_keys[round]);
+            if round > 0 {
+                inv_mix_columns(block);
+            }
+        }
+    }
+}
+
+impl core::fmt::Debug for Aes {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Aes").finish_non_exhaustive()
+    }
+}
+
+fn add_round_key(block: &mut [u8; BLOCK_SIZE], key: &[u8; BLOCK_SIZE]) {
+    for (b, k) in block.iter_mut().zip(key) {
+        *b ^= k;
+    }
+}
+
+fn inv_shift_rows(block: &mut [u8; BLOCK_SIZE]) {
+    let state = *block;
+    for column in 0..4 {
+        for row in 0..4 {
+            block[column * 4 + row] = state[((column + 4 - row) % 4) * 4 + row];
+        }
+    }
+}
+
+fn inv_mix_columns(block: &mut [u8; BLOCK_SIZE]) {
+    for column in block.chunks_exact_mut(4) {
+        let [a, b, c, d] = [column[0], column[1], column[2], column[3]];
+        column[0] = mul(a, 14) ^ mul(b, 11) ^ mul(c, 13) ^ mul(d, 9);
+        column[1] = mul(a, 9) ^ mul(b, 14) ^ mul(c, 11) ^ mul(d, 13);
+        column[2] = mul(a, 13) ^ mul(b, 9) ^ mul(c, 14) ^ mul(d, 11);
+        column[3] = mul(a, 11) ^ mul(b, 13) ^ mul(c, 9) ^ mul(d, 14);
+    }
+}
+
+#[derive(Debug, Clone)]
+/// AES decryption in [CBC](https://en.wikipedia.org/wiki/Block_cipher_mode_of_operation#CBC)
+/// mode.
+pub struct CbcDecryptor {
+    aes: Aes,
+    iv: [u8; BLOCK_SIZE],
+}
+
+impl CbcDecryptor {
+    pub fn new(key: &[u8], iv: [u8; BLOCK_SIZE]) -> Self {
+        Self {
+            aes: Aes::new(key),
+            iv,
+        }
+    }
+
+    /// Decrypt `data` in place, continuing from the previous call.
+    ///
+    /// The length of `data` must be a multiple of [`BLOCK_SIZE`].
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        assert!(
+            data.len().is_multiple_of(BLOCK_SIZE),
+            "data is not aligned to blocks"
+        );
+
+        for chunk in data.chunks_exact_mut(BLOCK_SIZE) {
+            let block: &mut [u8; BLOCK_SIZE] = chunk.try_into().unwrap();
+            let ciphertext = *block;
+            self.aes.decrypt_block(block);
+            add_round_key(block, &self.iv);
+            self.iv = ciphertext;
+        }
+    }
+}
+
+#[test]
+fn test_aes() {
+    fn bytes<const N: usize>(hex: &str) -> [u8; N] {
+        core::array::from_fn(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap())
+    }
+
+    // Examples from appendix C of FIPS 197.
+    let plaintext: [u8; 16] = bytes("00112233445566778899aabbccddeeff");
+
+    let mut block = bytes("69c4e0d86a7b0430d8cdb78070b4c55a");
+    Aes::new(&bytes::<16>("000102030405060708090a0b0c0d0e0f")).decrypt_block(&mut block);
+    assert_eq!(block, plaintext);
+
+    let key: [u8; 32] = bytes("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f");
+    let mut block = bytes("8ea2b7ca516745bfeafc49904b496089");
+    Aes::new(&key).decrypt_block(&mut block);
+    assert_eq!(block, plaintext);
+
+    // CBC-AES256 example F.2.2 from NIST SP 800-38A.
+    let key: [u8; 32] = bytes("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4");
+    let mut data: [u8; 32] =
+        bytes("f58c4c04d6e5f1ba779eabfb5f7bfbd69cfc4e967edb808d679f777bc6702c7d");
+    let mut decryptor = CbcDecryptor::new(&key, bytes("000102030405060708090a0b0c0d0e0f"));
+    decryptor.decrypt(&mut data[..16]);
+    decryptor.decrypt(&mut data[16..]);
+    assert_eq!(
+        data,
+        bytes::<32>("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51")
+    );
+}
@@ -0,0 +1,9 @@
+//! Ciphers and key derivation used to decrypt archives.
+
+mod aes;
+mod rar30;
+mod rar50;
+
+pub use aes::{CbcDecryptor, BLOCK_SIZE};
+pub use rar30::{Rar30Keys, SALT_SIZE as RAR30_SALT_SIZE};
+pub use rar50::Rar50Keys;
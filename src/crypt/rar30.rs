@@ -0,0 +1,58 @@
+//! Key derivation of the AES encryption introduced in RAR 2.9, which hashes the password
+//! and the salt many times with SHA-1.
+
+use alloc::vec::Vec;
+
+use crate::hash::Sha1;
+
+/// Number of times the password and the salt are hashed.
+const ROUNDS: u32 = 0x40000;
+
+pub const SALT_SIZE: usize = 8;
+
+/// Key and IV derived from a password and the salt of a header or file.
+pub struct Rar30Keys {
+    /// AES-128 key.
+    pub key: [u8; 16],
+
+    /// Initialization vector for CBC mode.
+    pub iv: [u8; 16],
+}
+
+impl Rar30Keys {
+    pub fn derive(password: &str, salt: &[u8; SALT_SIZE]) -> Self {
+        // The password is hashed as UTF-16, which is how RAR stores it on Windows.
+        let mut data: Vec<u8> = password.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        data.extend_from_slice(salt);
+
+        let mut hasher = Sha1::new();
+        let mut iv = [0; 16];
+
+        for i in 0..ROUNDS {
+            // The rounds hash the data as modified by the previous ones.
+            hasher.update_rar29(&mut data);
+            hasher.update(&i.to_le_bytes()[..3]);
+
+            // Each byte of the IV is sampled from the hash at regular intervals.
+            if i % (ROUNDS / 16) == 0 {
+                iv[(i / (ROUNDS / 16)) as usize] = hasher.clone().finalize()[19];
+            }
+        }
+
+        // The key is made of the first 4 words of the hash, in little endian.
+        let hash = hasher.finalize();
+        let mut key = [0; 16];
+        for (key, word) in key.chunks_exact_mut(4).zip(hash.chunks_exact(4)) {
+            key.copy_from_slice(word);
+            key.reverse();
+        }
+
+        Self { key, iv }
+    }
+}
+
+impl core::fmt::Debug for Rar30Keys {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Rar30Keys").finish_non_exhaustive()
+    }
+}
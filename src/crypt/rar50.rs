@@ -0,0 +1,203 @@
+//! Key derivation of RAR5 encryption, which uses
+//! [PBKDF2](https://www.rfc-editor.org/rfc/rfc8018#section-5.2) with HMAC-SHA256.
+
+use crate::hash::{HmacSha256, Sha256};
+
+/// Largest base-2 logarithm of the number of PBKDF2 iterations accepted by UnRAR.
+const MAX_KDF_COUNT: u8 = 24;
+
+/// Number of extra iterations between the key and each of the supplementary values.
+const EXTRA_ITERATIONS: u32 = 16;
+
+const PASSWORD_CHECK_SIZE: usize = 8;
+
+/// Number of derived keys kept in the cache, like UnRAR.
+#[cfg(feature = "std")]
+const CACHE_SIZE: usize = 4;
+
+/// Keys derived most recently, so that checking a password and then decrypting with it
+/// doesn't run the KDF twice. The passwords are kept as their SHA-256.
+#[cfg(feature = "std")]
+static CACHE: std::sync::Mutex<std::collections::VecDeque<CacheEntry>> =
+    std::sync::Mutex::new(std::collections::VecDeque::new());
+
+#[cfg(feature = "std")]
+struct CacheEntry {
+    password_hash: [u8; 32],
+    salt: [u8; 16],
+    kdf_count: u8,
+    keys: Rar50Keys,
+}
+
+#[derive(Clone)]
+/// Keys derived from a password and the salt of an archive or file.
+pub struct Rar50Keys {
+    /// AES-256 key.
+    pub key: [u8; 32],
+
+    /// HMAC key used to turn the checksums of encrypted files into MACs.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub hash_key: [u8; 32],
+
+    /// Value stored in the archive to detect wrong passwords.
+    pub password_check: [u8; PASSWORD_CHECK_SIZE],
+}
+
+impl Rar50Keys {
+    /// Derive the keys with 2^`kdf_count` iterations of PBKDF2, or take them from the
+    /// cache if they were derived recently.
+    ///
+    /// Returns `None` if `kdf_count` is larger than what UnRAR supports.
+    pub fn derive(password: &str, salt: &[u8; 16], kdf_count: u8) -> Option<Self> {
+        if kdf_count > MAX_KDF_COUNT {
+            return None;
+        }
+
+        #[cfg(feature = "std")]
+        {
+            let mut hasher = Sha256::new();
+            hasher.update(password.as_bytes());
+            let password_hash = hasher.finalize();
+
+            let lock = || {
+                CACHE
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+            };
+            let cached = lock()
+                .iter()
+                .find(|entry| {
+                    entry.password_hash == password_hash
+                        && entry.salt == *salt
+                        && entry.kdf_count == kdf_count
+                })
+                .map(|entry| entry.keys.clone());
+            if cached.is_some() {
+                return cached;
+            }
+
+            let keys = Self::derive_uncached(password, salt, kdf_count);
+            let mut cache = lock();
+            cache.push_front(CacheEntry {
+                password_hash,
+                salt: *salt,
+                kdf_count,
+                keys: keys.clone(),
+            });
+            cache.truncate(CACHE_SIZE);
+            Some(keys)
+        }
+
+        #[cfg(not(feature = "std"))]
+        Some(Self::derive_uncached(password, salt, kdf_count))
+    }
+
+    fn derive_uncached(password: &str, salt: &[u8; 16], kdf_count: u8) -> Self {
+        let hmac = HmacSha256::new(password.as_bytes());
+
+        // Only one block of output is needed, so the block index is always 1.
+        let mut first = salt.to_vec();
+        first.extend_from_slice(&1u32.to_be_bytes());
+
+        let mut u = hmac.mac(&first);
+        let mut value = u;
+        let mut values = [[0; 32]; 3];
+        let counts = [(1 << kdf_count) - 1, EXTRA_ITERATIONS, EXTRA_ITERATIONS];
+
+        // The supplementary values are the outputs of PBKDF2 with 16 and 32 more
+        // iterations than the key.
+        for (output, count) in values.iter_mut().zip(counts) {
+            for _ in 0..count {
+                u = hmac.mac(&u);
+                for (v, u) in value.iter_mut().zip(u) {
+                    *v ^= u;
+                }
+            }
+            *output = value;
+        }
+
+        let [key, hash_key, check] = values;
+        let mut password_check = [0; PASSWORD_CHECK_SIZE];
+        for (i, byte) in check.into_iter().enumerate() {
+            password_check[i % PASSWORD_CHECK_SIZE] ^= byte;
+        }
+
+        Self {
+            key,
+            hash_key,
+            password_check,
+        }
+    }
+
+    /// MAC of the CRC32 of an encrypted file, which is stored in place of the CRC32 so
+    /// that it can't be used to guess the contents of the file.
+    #[cfg(feature = "std")]
+    pub fn crc32_mac(&self, crc32: u32) -> u32 {
+        let mac = HmacSha256::new(&self.hash_key).mac(&crc32.to_le_bytes());
+        mac.chunks_exact(4).fold(0, |crc, bytes| {
+            crc ^ u32::from_le_bytes(bytes.try_into().unwrap())
+        })
+    }
+
+    /// MAC of the BLAKE2sp hash of an encrypted file.
+    #[cfg(feature = "std")]
+    pub fn blake2sp_mac(&self, hash: &[u8; 32]) -> [u8; 32] {
+        HmacSha256::new(&self.hash_key).mac(hash)
+    }
+
+    /// Compare the password check value stored in an archive with the one derived from
+    /// the password.
+    ///
+    /// The stored value is followed by 4 bytes of its SHA-256, and like UnRAR, the check
+    /// is skipped if they don't match because the value itself might be corrupt.
+    pub fn is_password_correct(&self, check_value: &[u8; 12]) -> bool {
+        let (check, checksum) = check_value.split_at(PASSWORD_CHECK_SIZE);
+
+        let mut hasher = Sha256::new();
+        hasher.update(check);
+        if hasher.finalize()[..4] != *checksum {
+            return true;
+        }
+
+        check == self.password_check
+    }
+}
+
+impl core::fmt::Debug for Rar50Keys {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Rar50Keys").finish_non_exhaustive()
+    }
+}
+
+#[test]
+fn test_derive_keys() {
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    let salt = core::array::from_fn(|i| i as u8);
+    let keys = Rar50Keys::derive("password", &salt, 4).unwrap();
+    assert_eq!(
+        hex(&keys.key),
+        "42972fbb08bb7d5c0c05b3d261eb85106b36f702c5b02f4c3605213180f63792"
+    );
+    assert_eq!(hex(&keys.password_check), "feec6fdc3ff25cb6");
+
+    assert!(Rar50Keys::derive("password", &salt, 25).is_none());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_derive_cached() {
+    let salt = [0xca; 16];
+    let keys = Rar50Keys::derive("cached", &salt, 4).unwrap();
+    assert!(CACHE
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|entry| entry.salt == salt && entry.keys.key == keys.key));
+
+    let cached = Rar50Keys::derive("cached", &salt, 4).unwrap();
+    assert_eq!(cached.key, keys.key);
+    assert_ne!(Rar50Keys::derive("other", &salt, 4).unwrap().key, keys.key);
+}
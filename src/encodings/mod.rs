@@ -0,0 +1,277 @@
+//! Decoding of the strings encoded with the OEM code page of the system that created the
+//! archive.
+
+use alloc::string::String;
+
+mod single_byte;
+
+/// Characters of the double-byte sequences of CP932, for each lead byte indexed by
+/// [`cp932_lead_index`] and trail byte in [`CP932_TRAIL_BYTES`], as little-endian UTF-16
+/// code units. Sequences which are not mapped are stored as 0.
+///
+/// Generated from the `cp932` codec of Python's standard library, which follows
+/// Microsoft's mapping table.
+static CP932: &[u8; 22680] = include_bytes!("cp932.bin");
+
+const CP932_TRAIL_BYTES: core::ops::RangeInclusive<u8> = 0x40..=0xfc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// An [OEM code page](https://en.wikipedia.org/wiki/Windows_code_page#OEM) which can be
+/// used to decode the names stored by MS-DOS and Windows versions of RAR.
+pub enum CodePage {
+    /// United States, the default OEM code page of English versions of Windows.
+    Cp437,
+
+    /// Western Europe.
+    Cp850,
+
+    /// Central and Eastern Europe.
+    Cp852,
+
+    /// Cyrillic.
+    Cp866,
+
+    /// Japanese, also known as Shift JIS.
+    Cp932,
+}
+
+/// Which characters are expected in a string decoded with a [`CodePage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Ascii,
+    Latin,
+    Cyrillic,
+    Japanese,
+    HalfwidthKatakana,
+    Other,
+}
+
+impl Script {
+    fn of(c: char) -> Self {
+        match c {
+            '\0'..='\x7f' => Script::Ascii,
+            '\u{3005}' | '\u{3040}'..='\u{30ff}' | '\u{4e00}'..='\u{9fff}' => Script::Japanese,
+            '\u{ff61}'..='\u{ff9f}' => Script::HalfwidthKatakana,
+            '\u{0400}'..='\u{04ff}' => Script::Cyrillic,
+            '\u{0080}'..='\u{024f}' if c.is_alphabetic() => Script::Latin,
+            _ => Script::Other,
+        }
+    }
+}
+
+/// Scripts of the letters of a word.
+#[derive(Debug, Default)]
+struct Word {
+    latin: bool,
+    cyrillic: bool,
+    japanese: bool,
+    katakana: bool,
+}
+
+impl Word {
+    fn push(&mut self, script: Script) {
+        match script {
+            Script::Ascii | Script::Latin => self.latin = true,
+            Script::Cyrillic => self.cyrillic = true,
+            Script::Japanese => self.japanese = true,
+            Script::HalfwidthKatakana => self.katakana = true,
+            Script::Other => {}
+        }
+    }
+
+    /// End the word, returning the penalty for mixing scripts which aren't used together.
+    fn end(&mut self) -> u32 {
+        let mixed = (self.latin && self.cyrillic) || (self.japanese && self.katakana);
+        *self = Self::default();
+        mixed as u32 * 2
+    }
+}
+
+impl CodePage {
+    /// All the supported code pages, in the order in which [`Self::guess`] prefers them.
+    pub const ALL: [CodePage; 5] = [
+        CodePage::Cp932,
+        CodePage::Cp866,
+        CodePage::Cp850,
+        CodePage::Cp852,
+        CodePage::Cp437,
+    ];
+
+    /// The code page with the Windows code page identifier `id`, like 437 or 932.
+    pub fn from_id(id: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|page| page.id() == id)
+    }
+
+    /// Windows code page identifier.
+    pub fn id(self) -> u16 {
+        match self {
+            CodePage::Cp437 => 437,
+            CodePage::Cp850 => 850,
+            CodePage::Cp852 => 852,
+            CodePage::Cp866 => 866,
+            CodePage::Cp932 => 932,
+        }
+    }
+
+    /// Decode `bytes`, replacing the invalid sequences with U+FFFD.
+    pub fn decode(self, bytes: &[u8]) -> String {
+        let mut string = String::with_capacity(bytes.len());
+        self.for_each_char(bytes, |c, _| string.push(c.unwrap_or('\u{fffd}')));
+        string
+    }
+
+    /// Guess the code page of `bytes` from the characters they decode to.
+    ///
+    /// Each code page is scored by how many of the decoded characters are unusual for the
+    /// script of the code page, like box-drawing characters or words mixing Latin and
+    /// Cyrillic letters. This is only a heuristic, and short strings can decode to
+    /// plausible characters in several code pages.
+    pub fn guess(bytes: &[u8]) -> Self {
+        Self::ALL
+            .into_iter()
+            .filter_map(|page| Some((page.penalty(bytes)?, page)))
+            .min_by_key(|&(penalty, _)| penalty)
+            .map_or(CodePage::Cp437, |(_, page)| page)
+    }
+
+    fn expected_script(self) -> Script {
+        match self {
+            CodePage::Cp437 | CodePage::Cp850 | CodePage::Cp852 => Script::Latin,
+            CodePage::Cp866 => Script::Cyrillic,
+            CodePage::Cp932 => Script::Japanese,
+        }
+    }
+
+    /// How unlikely it is for `bytes` to be encoded with this code page, or `None` if they
+    /// contain invalid sequences.
+    fn penalty(self, bytes: &[u8]) -> Option<u32> {
+        let expected = self.expected_script();
+        let mut penalty = 0;
+        let mut valid = true;
+        let mut word = Word::default();
+
+        self.for_each_char(bytes, |c, code| {
+            let Some(c) = c else {
+                valid = false;
+                return;
+            };
+
+            let script = Script::of(c);
+            penalty += match script {
+                Script::Ascii => 0,
+                Script::HalfwidthKatakana if self == CodePage::Cp932 => 1,
+                script if script == expected => 0,
+                _ => 2,
+            };
+
+            // JIS level 2 kanji are rarer than the ones of level 1.
+            if script == Script::Japanese && code >= 0x989f {
+                penalty += 1;
+            }
+
+            if c.is_alphabetic() {
+                word.push(script);
+            } else {
+                penalty += word.end();
+            }
+        });
+        penalty += word.end();
+
+        valid.then_some(penalty)
+    }
+
+    /// Decode `bytes`, calling `f` with each character, or `None` for invalid sequences,
+    /// and the bytes it was decoded from.
+    fn for_each_char(self, bytes: &[u8], mut f: impl FnMut(Option<char>, u16)) {
+        let table = match self {
+            CodePage::Cp437 => &single_byte::CP437,
+            CodePage::Cp850 => &single_byte::CP850,
+            CodePage::Cp852 => &single_byte::CP852,
+            CodePage::Cp866 => &single_byte::CP866,
+            CodePage::Cp932 => return decode_cp932(bytes, f),
+        };
+
+        for &byte in bytes {
+            let c = match byte {
+                0..=0x7f => byte as char,
+                _ => table[byte as usize - 0x80],
+            };
+            f(Some(c), byte as u16);
+        }
+    }
+}
+
+/// Index of the lead byte of a CP932 double-byte sequence in [`CP932`].
+fn cp932_lead_index(byte: u8) -> Option<usize> {
+    match byte {
+        0x81..=0x9f => Some(byte as usize - 0x81),
+        0xe0..=0xfc => Some(byte as usize - 0xe0 + 0x1f),
+        _ => None,
+    }
+}
+
+fn decode_cp932(bytes: &[u8], mut f: impl FnMut(Option<char>, u16)) {
+    let trail_count = CP932_TRAIL_BYTES.len();
+    let mut bytes = bytes.iter().copied();
+
+    while let Some(byte) = bytes.next() {
+        let Some(lead) = cp932_lead_index(byte) else {
+            let c = match byte {
+                0..=0x80 => Some(byte as char),
+                0xa1..=0xdf => char::from_u32(0xff61 + (byte - 0xa1) as u32),
+                _ => None,
+            };
+            f(c, byte as u16);
+            continue;
+        };
+
+        let Some(trail) = bytes.next() else {
+            f(None, byte as u16);
+            break;
+        };
+
+        let code = u16::from_be_bytes([byte, trail]);
+        let c = CP932_TRAIL_BYTES
+            .contains(&trail)
+            .then(|| (lead * trail_count + (trail - CP932_TRAIL_BYTES.start()) as usize) * 2)
+            .map(|index| u16::from_le_bytes([CP932[index], CP932[index + 1]]))
+            .filter(|&unit| unit != 0)
+            .and_then(|unit| char::from_u32(unit as u32));
+        f(c, code);
+    }
+}
+
+#[test]
+fn test_decode() {
+    assert_eq!(
+        CodePage::Cp866.decode(b"\x8f\xe0\xa8\xa2\xa5\xe2.txt"),
+        "Привет.txt"
+    );
+    assert_eq!(CodePage::Cp850.decode(b"Caf\x82"), "Café");
+    assert_eq!(
+        CodePage::Cp932.decode(b"\x83\x65\x83\x58\x83\x67\xb6\x80\xa0\x82"),
+        "テストｶ\u{80}\u{fffd}\u{fffd}"
+    );
+}
+
+#[test]
+fn test_guess() {
+    let cases: [(&[u8], CodePage); 6] = [
+        (
+            b"\x8f\xe0\xa8\xa2\xa5\xe2 \xac\xa8\xe0.txt",
+            CodePage::Cp866,
+        ),
+        (b"\x94\xae\xe2\xae", CodePage::Cp866),
+        (
+            b"\x93\xfa\x96\x7b\x8c\xea\x83\x74\x83\x40\x83\x43\x83\x8b.txt",
+            CodePage::Cp932,
+        ),
+        (b"\xb6\xc0\xb6\xc5.TXT", CodePage::Cp932),
+        (b"M\x81ller.doc", CodePage::Cp850),
+        (b"\x9d\xa2d\xab.txt", CodePage::Cp852),
+    ];
+
+    for (bytes, page) in cases {
+        assert_eq!(CodePage::guess(bytes), page, "{bytes:x?}");
+    }
+}
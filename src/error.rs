@@ -1,7 +1,9 @@
-use std::io;
+use alloc::{boxed::Box, format, string::String};
 
 use thiserror::Error;
 
+use crate::{io, limits::LimitKind};
+
 /// Specialized [`Result`] type for decoding RAR archives.
 pub type RarResult<T> = core::result::Result<T, Error>;
 
@@ -12,20 +14,169 @@ pub enum Error {
     #[error("expected more data")]
     UnexpectedEof,
 
+    /// The stream does not contain a RAR signature.
+    #[error("RAR signature not found")]
+    SignatureNotFound,
+
     /// A block header reported a size equal to 0 or exceeding the EOF.
     #[error("header reported sizes are 0 or exceed the EOF")]
     CorruptHeader,
 
+    /// The block headers of the archive are encrypted and no password was given.
+    #[error("block headers are encrypted and no password was given")]
+    PasswordRequired,
+
+    /// The password doesn't match the one the archive was encrypted with.
+    #[error("incorrect password")]
+    IncorrectPassword,
+
+    /// A size field of a header exceeds its maximum in the [`Limits`](crate::Limits) of
+    /// the parser.
+    #[error("{kind} of {size} exceeds the limit of {limit}")]
+    LimitExceeded {
+        /// Which of the limits was exceeded.
+        kind: LimitKind,
+
+        /// Size read from the header.
+        size: u64,
+
+        /// Maximum size allowed by the limit.
+        limit: u64,
+    },
+
+    /// A text field of a header which must be UTF-8 is not.
+    #[error("{field} is not valid UTF-8")]
+    InvalidUtf8 {
+        /// Name of the field.
+        field: &'static str,
+    },
+
+    /// A field of a header has a value too large for the enum it's parsed into.
+    #[error("invalid {name} value {value}")]
+    InvalidEnumValue {
+        /// Name of the enum.
+        name: &'static str,
+
+        /// Value read from the header.
+        value: u64,
+    },
+
     /// Unknown I/O error.
     #[error(transparent)]
     Io(io::Error),
+
+    /// The archive is corrupt at the block starting at `offset`.
+    ///
+    /// The block iterators wrap the errors caused by corrupt data with the location of the
+    /// block where they happened. [`Self::root`] returns the wrapped error, whose fields
+    /// tell which field of the header couldn't be read.
+    #[error("block at offset {offset}{}: {source}", block_type_suffix(*block_type))]
+    Block {
+        /// Offset of the block in the file.
+        offset: u64,
+
+        /// Type of the block read from its header, unless the header is encrypted or
+        /// couldn't be read that far.
+        block_type: Option<u64>,
+
+        /// Error returned while reading the block.
+        source: Box<Error>,
+    },
+}
+
+fn block_type_suffix(block_type: Option<u64>) -> String {
+    block_type
+        .map(|block_type| format!(" of type {block_type:#x}"))
+        .unwrap_or_default()
+}
+
+impl Error {
+    /// The error without the location of the block where it happened.
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Block { source, .. } => source.root(),
+            error => error,
+        }
+    }
+
+    /// Take the error without the location of the block where it happened.
+    pub fn into_root(self) -> Error {
+        match self {
+            Error::Block { source, .. } => source.into_root(),
+            error => error,
+        }
+    }
+
+    /// Offset of the block where the error happened, if known.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Error::Block { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// Wrap the error with the location of the block where it happened if it's caused by
+    /// corrupt data. Other errors are returned unchanged.
+    pub(crate) fn at_block(self, offset: u64, block_type: Option<u64>) -> Self {
+        if !self.is_corrupt_data() || matches!(self, Error::Block { .. }) {
+            return self;
+        }
+
+        Error::Block {
+            offset,
+            block_type,
+            source: Box::new(self),
+        }
+    }
+
+    /// The error is caused by corrupt data in the archive rather than by the reader or
+    /// the password.
+    pub(crate) fn is_corrupt_data(&self) -> bool {
+        match self {
+            Error::UnexpectedEof
+            | Error::CorruptHeader
+            | Error::LimitExceeded { .. }
+            | Error::InvalidUtf8 { .. }
+            | Error::InvalidEnumValue { .. } => true,
+            Error::Io(error) => error.kind() == io::ErrorKind::InvalidData,
+            Error::Block { source, .. } => source.is_corrupt_data(),
+            Error::SignatureNotFound | Error::PasswordRequired | Error::IncorrectPassword => false,
+        }
+    }
 }
 
 impl From<io::Error> for Error {
     fn from(value: io::Error) -> Self {
+        // The parsers return errors about the archive wrapped in an io::Error.
+        if value.get_ref().is_some_and(|error| error.is::<Error>()) {
+            return match value.into_inner().map(|error| error.downcast::<Error>()) {
+                Some(Ok(error)) => *error,
+                Some(Err(error)) => Self::Io(io::Error::other(error)),
+                None => Self::Io(io::ErrorKind::Other.into()),
+            };
+        }
+
         match value.kind() {
             io::ErrorKind::UnexpectedEof => Self::UnexpectedEof,
             _ => Self::Io(value),
         }
     }
 }
+
+impl From<Error> for io::Error {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::Io(error) => error,
+            Error::UnexpectedEof => io::ErrorKind::UnexpectedEof.into(),
+            Error::Block { .. } => {
+                let kind = match value.root() {
+                    Error::Io(error) => error.kind(),
+                    Error::UnexpectedEof => io::ErrorKind::UnexpectedEof,
+                    _ => io::ErrorKind::InvalidData,
+                };
+                io::Error::new(kind, value)
+            }
+            error => io::Error::new(io::ErrorKind::InvalidData, error),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! [BLAKE2sp](https://www.blake2.net/blake2.pdf), the 8-way parallel variant of BLAKE2s
+//! used by RAR5 for the optional file hash.
+
+const BLOCK_SIZE: usize = 64;
+const OUT_SIZE: usize = 32;
+const PARALLELISM: usize = 8;
+
+const IV: [u32; 8] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+#[derive(Clone)]
+/// A single BLAKE2s instance, used both for the leaves and for the root of the tree.
+struct Blake2s {
+    h: [u32; 8],
+    t: u64,
+    buf: [u8; BLOCK_SIZE],
+    buf_len: usize,
+    last_node: bool,
+}
+
+impl Blake2s {
+    fn new(node_offset: u32, node_depth: u8, last_node: bool) -> Self {
+        let mut h = IV;
+        // Digest length, no key, fanout and depth of the tree.
+        h[0] ^= OUT_SIZE as u32 | (PARALLELISM as u32) << 16 | 2 << 24;
+        h[2] ^= node_offset;
+        h[3] ^= (node_depth as u32) << 16 | (OUT_SIZE as u32) << 24;
+
+        Self {
+            h,
+            t: 0,
+            buf: [0; BLOCK_SIZE],
+            buf_len: 0,
+            last_node,
+        }
+    }
+
+    fn compress(&mut self, block: &[u8; BLOCK_SIZE], last: bool) {
+        let mut m = [0u32; 16];
+        for (word, bytes) in m.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+
+        let mut v = [0u32; 16];
+        v[..8].copy_from_slice(&self.h);
+        v[8..].copy_from_slice(&IV);
+        v[12] ^= self.t as u32;
+        v[13] ^= (self.t >> 32) as u32;
+        if last {
+            v[14] = !v[14];
+            if self.last_node {
+                v[15] = !v[15];
+            }
+        }
+
+        fn g(v: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32, y: u32) {
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+            v[d] = (v[d] ^ v[a]).rotate_right(16);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(12);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+            v[d] = (v[d] ^ v[a]).rotate_right(8);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(7);
+        }
+
+        for s in &SIGMA {
+            g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+            g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+            g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+            g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+            g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+            g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+            g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+            g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+        }
+
+        for i in 0..8 {
+            self.h[i] ^= v[i] ^ v[i + 8];
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // The last block is only compressed on finalization, so it can be flagged.
+            if self.buf_len == BLOCK_SIZE {
+                self.t += BLOCK_SIZE as u64;
+                let block = self.buf;
+                self.compress(&block, false);
+                self.buf_len = 0;
+            }
+
+            let n = data.len().min(BLOCK_SIZE - self.buf_len);
+            self.buf[self.buf_len..self.buf_len + n].copy_from_slice(&data[..n]);
+            self.buf_len += n;
+            data = &data[n..];
+        }
+    }
+
+    fn finalize(mut self) -> [u8; OUT_SIZE] {
+        self.t += self.buf_len as u64;
+        self.buf[self.buf_len..].fill(0);
+        let block = self.buf;
+        self.compress(&block, true);
+
+        let mut out = [0; OUT_SIZE];
+        for (bytes, word) in out.chunks_exact_mut(4).zip(self.h) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        out
+    }
+}
+
+#[derive(Clone)]
+/// Incremental BLAKE2sp hasher.
+pub struct Blake2sp {
+    leaves: [Blake2s; PARALLELISM],
+    buf: [u8; BLOCK_SIZE * PARALLELISM],
+    buf_len: usize,
+}
+
+impl Blake2sp {
+    pub fn new() -> Self {
+        Self {
+            leaves: core::array::from_fn(|i| Blake2s::new(i as u32, 0, i == PARALLELISM - 1)),
+            buf: [0; BLOCK_SIZE * PARALLELISM],
+            buf_len: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // Blocks are distributed round-robin among the leaves. Like for the leaves, the
+            // last round is kept in the buffer until finalization.
+            if self.buf_len == self.buf.len() {
+                for (leaf, block) in self
+                    .leaves
+                    .iter_mut()
+                    .zip(self.buf.chunks_exact(BLOCK_SIZE))
+                {
+                    leaf.update(block);
+                }
+                self.buf_len = 0;
+            }
+
+            let n = data.len().min(self.buf.len() - self.buf_len);
+            self.buf[self.buf_len..self.buf_len + n].copy_from_slice(&data[..n]);
+            self.buf_len += n;
+            data = &data[n..];
+        }
+    }
+
+    pub fn finalize(self) -> [u8; OUT_SIZE] {
+        let mut root = Blake2s::new(0, 1, true);
+
+        for (i, mut leaf) in self.leaves.into_iter().enumerate() {
+            let start = (i * BLOCK_SIZE).min(self.buf_len);
+            let end = ((i + 1) * BLOCK_SIZE).min(self.buf_len);
+            leaf.update(&self.buf[start..end]);
+            root.update(&leaf.finalize());
+        }
+
+        root.finalize()
+    }
+}
+
+impl Default for Blake2sp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::fmt::Debug for Blake2sp {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Blake2sp").finish_non_exhaustive()
+    }
+}
+
+#[test]
+fn test_blake2sp() {
+    fn hex(hash: [u8; OUT_SIZE]) -> String {
+        hash.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    assert_eq!(
+        hex(Blake2sp::new().finalize()),
+        "dd0e891776933f43c7d032b08a917e25741f8aa9a12c12e1cac8801500f2ca4f"
+    );
+
+    let data = (0..=255).cycle().take(256 * 5).collect::<Vec<u8>>();
+
+    let mut hasher = Blake2sp::new();
+    hasher.update(&data);
+    assert_eq!(
+        hex(hasher.finalize()),
+        "f8643dbeacfcc98c1de662e35a800fd8dfc8a369ce62ed514e813e65728ed379"
+    );
+
+    // Exactly one round of blocks, fed in uneven chunks.
+    let mut hasher = Blake2sp::new();
+    for chunk in data[..512].chunks(100) {
+        hasher.update(chunk);
+    }
+    assert_eq!(
+        hex(hasher.finalize()),
+        "322ce06cc141a0b3d89bcdcfcb385975dbca56e5719a78c34000fcec2e15b55d"
+    );
+}
@@ -0,0 +1,30 @@
+//! CRC16 used by RAR 1.5 to 4.x for header and comment checksums, which is the low 16 bits
+//! of the [CRC32](super::Crc32) of the same data.
+
+use super::Crc32;
+
+#[derive(Debug, Clone, Default)]
+/// Incremental CRC16 hasher.
+pub struct Crc16(Crc32);
+
+impl Crc16 {
+    pub fn new() -> Self {
+        Self(Crc32::new())
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    pub fn finalize(&self) -> u16 {
+        self.0.finalize() as u16
+    }
+}
+
+#[test]
+fn test_crc16() {
+    let mut crc = Crc16::new();
+    crc.update(b"1234");
+    crc.update(b"56789");
+    assert_eq!(crc.finalize(), 0x3926);
+}
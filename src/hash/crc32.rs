@@ -0,0 +1,62 @@
+//! CRC32 with the IEEE 802.3 polynomial, as used by RAR for file and header checksums.
+
+const POLYNOMIAL: u32 = 0xedb8_8320;
+
+const TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+};
+
+#[derive(Debug, Clone)]
+/// Incremental CRC32 hasher.
+pub struct Crc32(u32);
+
+impl Crc32 {
+    pub fn new() -> Self {
+        Self(!0)
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let mut crc = self.0;
+        for &byte in data {
+            crc = TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+        }
+        self.0 = crc;
+    }
+
+    pub fn finalize(&self) -> u32 {
+        !self.0
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_crc32() {
+    let mut crc = Crc32::new();
+    crc.update(b"1234");
+    crc.update(b"56789");
+    assert_eq!(crc.finalize(), 0xcbf4_3926);
+    assert_eq!(Crc32::new().finalize(), 0);
+}
@@ -0,0 +1,78 @@
+//! CRC64 with the ECMA-182 polynomial, as used by the recovery records of RAR50.
+
+const POLYNOMIAL: u64 = 0xc96c_5795_d787_0f42;
+
+const TABLE: [u64; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u64;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+};
+
+#[derive(Debug, Clone)]
+/// Incremental CRC64 hasher.
+pub struct Crc64 {
+    crc: u64,
+    xor_out: u64,
+}
+
+impl Crc64 {
+    /// CRC64 which inverts the register before and after hashing, the same as the one of XZ.
+    pub fn new() -> Self {
+        Self {
+            crc: !0,
+            xor_out: !0,
+        }
+    }
+
+    /// CRC64 which doesn't invert the register, used for the shards of recovery records.
+    pub fn uninverted() -> Self {
+        Self { crc: 0, xor_out: 0 }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let mut crc = self.crc;
+        for &byte in data {
+            crc = TABLE[((crc ^ byte as u64) & 0xff) as usize] ^ (crc >> 8);
+        }
+        self.crc = crc;
+    }
+
+    pub fn finalize(&self) -> u64 {
+        self.crc ^ self.xor_out
+    }
+}
+
+impl Default for Crc64 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_crc64() {
+    let mut crc = Crc64::new();
+    crc.update(b"1234");
+    crc.update(b"56789");
+    assert_eq!(crc.finalize(), 0x995d_c9bb_df19_39fa);
+    assert_eq!(Crc64::new().finalize(), 0);
+
+    let mut crc = Crc64::uninverted();
+    crc.update(b"123456789");
+    assert_eq!(crc.finalize(), 0x2b9c_7ee4_e278_0c8a);
+}
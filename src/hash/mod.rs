@@ -0,0 +1,18 @@
+//! Checksums used to verify the contents of archives.
+//!
+//! The CRCs are exported so that the data of archives can be verified with the same
+//! parameters RAR uses.
+
+mod blake2sp;
+mod crc16;
+mod crc32;
+mod crc64;
+mod sha1;
+mod sha256;
+
+pub(crate) use blake2sp::Blake2sp;
+pub use crc16::Crc16;
+pub use crc32::Crc32;
+pub(crate) use crc64::Crc64;
+pub(crate) use sha1::Sha1;
+pub(crate) use sha256::{HmacSha256, Sha256};
@@ -0,0 +1,184 @@
+//! [SHA-1](https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf), used by RAR 3.x to
+//! derive encryption keys from passwords.
+
+const BLOCK_SIZE: usize = 64;
+const OUT_SIZE: usize = 20;
+
+const IV: [u32; 5] = [
+    0x6745_2301,
+    0xefcd_ab89,
+    0x98ba_dcfe,
+    0x1032_5476,
+    0xc3d2_e1f0,
+];
+
+#[derive(Clone)]
+/// Incremental SHA-1 hasher.
+pub struct Sha1 {
+    h: [u32; 5],
+    len: u64,
+    buf: [u8; BLOCK_SIZE],
+    buf_len: usize,
+}
+
+impl Sha1 {
+    pub fn new() -> Self {
+        Self {
+            h: IV,
+            len: 0,
+            buf: [0; BLOCK_SIZE],
+            buf_len: 0,
+        }
+    }
+
+    /// Compress a block and return the last 16 words of the message schedule.
+    fn compress(&mut self, block: &[u8; BLOCK_SIZE]) -> [u32; 16] {
+        // The schedule is computed in place in a ring of 16 words, like the reference
+        // implementation that RAR 2.9 is based on.
+        let mut w = [0u32; 16];
+        for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = self.h;
+        for i in 0..80 {
+            if i >= 16 {
+                w[i % 16] = (w[(i + 13) % 16] ^ w[(i + 8) % 16] ^ w[(i + 2) % 16] ^ w[i % 16])
+                    .rotate_left(1);
+            }
+
+            let (f, k) = match i {
+                0..20 => ((b & c) | (!b & d), 0x5a82_7999),
+                20..40 => (b ^ c ^ d, 0x6ed9_eba1),
+                40..60 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+
+            let t = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(w[i % 16]);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = t;
+        }
+
+        for (h, v) in self.h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(v);
+        }
+
+        w
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+
+        while !data.is_empty() {
+            let n = data.len().min(BLOCK_SIZE - self.buf_len);
+            self.buf[self.buf_len..self.buf_len + n].copy_from_slice(&data[..n]);
+            self.buf_len += n;
+            data = &data[n..];
+
+            if self.buf_len == BLOCK_SIZE {
+                let block = self.buf;
+                self.compress(&block);
+                self.buf_len = 0;
+            }
+        }
+    }
+
+    /// Update the hash like the SHA-1 implementation of RAR 2.9, which overwrites each
+    /// full block that it hashes directly from `data` with the end of its message
+    /// schedule.
+    ///
+    /// The hash itself is the same as [`Self::update`], but the changes to `data` affect
+    /// the key derivation of RAR 3.x with long passwords.
+    pub fn update_rar29(&mut self, data: &mut [u8]) {
+        if self.buf_len + data.len() < BLOCK_SIZE {
+            self.update(data);
+            return;
+        }
+
+        // The first block is hashed from the internal buffer and isn't overwritten.
+        let head = BLOCK_SIZE - self.buf_len;
+        self.update(&data[..head]);
+
+        let mut blocks = data[head..].chunks_exact_mut(BLOCK_SIZE);
+        for chunk in &mut blocks {
+            self.len += BLOCK_SIZE as u64;
+            let schedule = self.compress(&(*chunk).try_into().unwrap());
+            for (bytes, word) in chunk.chunks_exact_mut(4).zip(schedule) {
+                bytes.copy_from_slice(&word.to_le_bytes());
+            }
+        }
+
+        self.update(blocks.into_remainder());
+    }
+
+    pub fn finalize(mut self) -> [u8; OUT_SIZE] {
+        let bit_len = self.len * 8;
+
+        // Pad with a 1 bit and zeros up to the length, which takes the last 8 bytes.
+        let padding = (BLOCK_SIZE * 2 - 8 - 1 - self.buf_len) % BLOCK_SIZE;
+        self.update(&[0x80]);
+        self.update(&[0; BLOCK_SIZE][..padding]);
+        self.update(&bit_len.to_be_bytes());
+
+        let mut out = [0; OUT_SIZE];
+        for (bytes, word) in out.chunks_exact_mut(4).zip(self.h) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+}
+
+impl Default for Sha1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::fmt::Debug for Sha1 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Sha1").finish_non_exhaustive()
+    }
+}
+
+#[test]
+fn test_sha1() {
+    fn hex(hash: [u8; OUT_SIZE]) -> String {
+        hash.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    assert_eq!(
+        hex(Sha1::new().finalize()),
+        "da39a3ee5e6b4b0d3255bfef95601890afd80709"
+    );
+
+    // Crosses a block boundary, fed in uneven chunks.
+    let message = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+    let mut hasher = Sha1::new();
+    for chunk in message.chunks(7) {
+        hasher.update(chunk);
+    }
+    assert_eq!(
+        hex(hasher.finalize()),
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+    );
+
+    // The RAR 2.9 variant produces the same hash, but overwrites the second block.
+    let mut data = [message.as_slice(); 3].concat();
+    let mut hasher = Sha1::new();
+    hasher.update(&[0; 10]);
+    hasher.update_rar29(&mut data);
+    let mut reference = Sha1::new();
+    reference.update(&[0; 10]);
+    reference.update(&[message.as_slice(); 3].concat());
+    assert_eq!(hasher.finalize(), reference.finalize());
+    assert_eq!(data[..54], [message.as_slice(); 3].concat()[..54]);
+    assert_ne!(data[54..118], [message.as_slice(); 3].concat()[54..118]);
+}
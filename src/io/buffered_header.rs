@@ -0,0 +1,185 @@
+//! Reader over a header which was read into memory with a single call.
+
+use alloc::vec::Vec;
+
+use crate::io;
+
+/// Reader over the raw bytes of the header of a block, read from the archive in one go so
+/// that parsing its fields doesn't need a read call for each of them.
+///
+/// Reads after the end of the header fall back to the underlying reader, so malformed
+/// headers are parsed past their declared size like they would be without the buffer.
+pub(crate) struct BufferedHeader<'a, R> {
+    reader: &'a mut R,
+    header: &'a mut Vec<u8>,
+    offset: u64,
+    pos: u64,
+}
+
+impl<'a, R: io::Read + io::Seek> BufferedHeader<'a, R> {
+    /// Read the header at `offset`, which is at most `max_size` bytes long, into `buffer`.
+    ///
+    /// First `prefix_size` bytes are read and passed to `header_size` to get the full size
+    /// of the header, then the rest of the header is read. Errors returned by `header_size`
+    /// are returned before anything is allocated for the rest of the header.
+    ///
+    /// The contents of `buffer` are replaced, so the same buffer can be reused for each
+    /// header to avoid allocating one every time.
+    pub fn read(
+        reader: &'a mut R,
+        buffer: &'a mut Vec<u8>,
+        offset: u64,
+        max_size: u64,
+        prefix_size: usize,
+        header_size: impl FnOnce(&[u8]) -> io::Result<u64>,
+    ) -> io::Result<Self> {
+        reader.seek(io::SeekFrom::Start(offset))?;
+
+        let header = buffer;
+        header.clear();
+        header.resize(prefix_size.min(max_size as usize), 0);
+        let len = read_up_to(reader, header)?;
+        header.truncate(len);
+
+        if len == prefix_size {
+            let size = header_size(header)?.clamp(len as u64, max_size);
+            header.resize(size as usize, 0);
+            let len = len + read_up_to(reader, &mut header[len..])?;
+            header.truncate(len);
+        }
+
+        Ok(Self {
+            reader,
+            header,
+            offset,
+            pos: offset,
+        })
+    }
+}
+
+/// Fill as much of `buf` as possible, stopping early only at the end of the stream.
+fn read_up_to<R: io::Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+    Ok(len)
+}
+
+impl<R: io::Read + io::Seek> io::Read for BufferedHeader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Empty reads of fields at the end of the header don't reach the underlying reader.
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let end = self.offset + self.header.len() as u64;
+
+        let n = if (self.offset..end).contains(&self.pos) {
+            let available = &self.header[(self.pos - self.offset) as usize..];
+            let n = buf.len().min(available.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        } else {
+            self.reader.seek(io::SeekFrom::Start(self.pos))?;
+            self.reader.read(buf)?
+        };
+
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: io::Read + io::Seek> io::Seek for BufferedHeader<'_, R> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            io::SeekFrom::Start(pos) => (pos, 0),
+            io::SeekFrom::Current(offset) => (self.pos, offset),
+            io::SeekFrom::End(_) => {
+                self.pos = self.reader.seek(pos)?;
+                return Ok(self.pos);
+            }
+        };
+
+        self.pos = base.checked_add_signed(offset).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
+
+#[cfg(test)]
+struct CountingReader<R> {
+    inner: R,
+    reads: usize,
+}
+
+#[cfg(test)]
+impl<R: io::Read> io::Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reads += 1;
+        self.inner.read(buf)
+    }
+}
+
+#[cfg(test)]
+impl<R: io::Seek> io::Seek for CountingReader<R> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn test_at_most_two_reads_per_header() {
+    use crate::{rar15, rar50, Signature};
+
+    let file = std::fs::read("tests/fixtures/rar15/stored.rar").unwrap();
+    let mut reader = CountingReader {
+        inner: io::Cursor::new(file),
+        reads: 0,
+    };
+    let blocks = rar15::BlockIterator::new(&mut reader, Signature::Rar15.size())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert!(reader.reads <= blocks.len() * 2);
+
+    let file = std::fs::read("tests/fixtures/rar50/unix_owner.rar").unwrap();
+    let mut reader = CountingReader {
+        inner: io::Cursor::new(file),
+        reads: 0,
+    };
+    let blocks = rar50::BlockIterator::new(&mut reader, Signature::Rar50.size())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert!(reader.reads <= blocks.len() * 2);
+}
+
+#[test]
+fn test_read_past_header() {
+    use crate::io::{Read as _, Seek as _};
+
+    let mut reader = io::Cursor::new((0..16).collect::<Vec<u8>>());
+    let mut buffer = vec![0xff; 8];
+    let mut header = BufferedHeader::read(&mut reader, &mut buffer, 2, 14, 1, |prefix| {
+        Ok(prefix[0] as u64)
+    })
+    .unwrap();
+    assert_eq!(*header.header, [2, 3]);
+
+    let mut buf = [0; 4];
+    header.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, [2, 3, 4, 5]);
+
+    header.seek(io::SeekFrom::Current(-3)).unwrap();
+    header.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, [3, 4, 5, 6]);
+    assert_eq!(header.stream_position().unwrap(), 7);
+}
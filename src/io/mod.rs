@@ -0,0 +1,18 @@
+//! I/O traits used to read the blocks of an archive.
+//!
+//! With the `std` feature this module re-exports the types of [`std::io`], so any
+//! [`std::io::Read`] and [`std::io::Seek`] implementation can be passed to the block
+//! iterators. Without it the crate is `no_std`, and this module provides minimal
+//! replacements for the types the parsers need: readers of `no_std` environments only have
+//! to implement [`Read::read`] and [`Seek::seek`].
+
+#[cfg(feature = "std")]
+pub use std::io::{Cursor, Error, ErrorKind, Read, Result, Seek, SeekFrom, Take};
+
+pub(crate) mod buffered_header;
+#[cfg(not(feature = "std"))]
+mod no_std;
+pub(crate) mod window;
+
+#[cfg(not(feature = "std"))]
+pub use no_std::*;
@@ -0,0 +1,280 @@
+//! Minimal replacements for the types of `std::io`, with the same names and semantics.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt;
+
+/// Specialized [`Result`](core::result::Result) type for I/O operations.
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+/// Category of an [`Error`].
+pub enum ErrorKind {
+    /// The data read was not valid.
+    InvalidData,
+
+    /// A parameter was incorrect.
+    InvalidInput,
+
+    /// The operation is not supported.
+    Unsupported,
+
+    /// The end of the stream was reached before the operation could complete.
+    UnexpectedEof,
+
+    /// The operation needs to block to complete.
+    WouldBlock,
+
+    /// Any other error.
+    Other,
+}
+
+impl ErrorKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::InvalidData => "invalid data",
+            ErrorKind::InvalidInput => "invalid input parameter",
+            ErrorKind::Unsupported => "unsupported",
+            ErrorKind::UnexpectedEof => "unexpected end of file",
+            ErrorKind::WouldBlock => "operation would block",
+            ErrorKind::Other => "other error",
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Error returned by the methods of [`Read`] and [`Seek`].
+pub struct Error {
+    kind: ErrorKind,
+    error: Option<Box<dyn core::error::Error + Send + Sync>>,
+}
+
+impl Error {
+    pub fn new<E>(kind: ErrorKind, error: E) -> Self
+    where
+        E: Into<Box<dyn core::error::Error + Send + Sync>>,
+    {
+        Self {
+            kind,
+            error: Some(error.into()),
+        }
+    }
+
+    pub fn other<E>(error: E) -> Self
+    where
+        E: Into<Box<dyn core::error::Error + Send + Sync>>,
+    {
+        Self::new(ErrorKind::Other, error)
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The inner error passed to [`Error::new`], if any.
+    pub fn get_ref(&self) -> Option<&(dyn core::error::Error + Send + Sync + 'static)> {
+        self.error.as_deref()
+    }
+
+    /// Consume the error, returning the inner error passed to [`Error::new`], if any.
+    pub fn into_inner(self) -> Option<Box<dyn core::error::Error + Send + Sync>> {
+        self.error
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self { kind, error: None }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            Some(error) => error.fmt(f),
+            None => f.write_str(self.kind.as_str()),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Position to seek to, relative to the start, the end, or the current position of the
+/// stream.
+pub enum SeekFrom {
+    Start(u64),
+    End(i64),
+    Current(i64),
+}
+
+/// Source of bytes.
+pub trait Read {
+    /// Read some bytes into `buf` and return how many were read.
+    ///
+    /// Returns 0 at the end of the stream.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+
+    /// Read exactly enough bytes to fill `buf`.
+    ///
+    /// Returns an [`ErrorKind::UnexpectedEof`] error if the stream ends before.
+    fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<()> {
+        while !buf.is_empty() {
+            match self.read(buf)? {
+                0 => return Err(ErrorKind::UnexpectedEof.into()),
+                n => buf = &mut buf[n..],
+            }
+        }
+        Ok(())
+    }
+
+    /// Read all the bytes until the end of the stream and append them to `buf`.
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
+        let start = buf.len();
+        let mut chunk = [0; 512];
+        loop {
+            match self.read(&mut chunk)? {
+                0 => return Ok(buf.len() - start),
+                n => buf.extend_from_slice(&chunk[..n]),
+            }
+        }
+    }
+
+    /// Adapter which reads at most `limit` bytes.
+    fn take(self, limit: u64) -> Take<Self>
+    where
+        Self: Sized,
+    {
+        Take { inner: self, limit }
+    }
+
+    fn by_ref(&mut self) -> &mut Self
+    where
+        Self: Sized,
+    {
+        self
+    }
+}
+
+/// Stream with a cursor which can be moved.
+pub trait Seek {
+    /// Move the cursor and return its new position from the start of the stream.
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64>;
+
+    /// Current position from the start of the stream.
+    fn stream_position(&mut self) -> Result<u64> {
+        self.seek(SeekFrom::Current(0))
+    }
+}
+
+impl<R: Read + ?Sized> Read for &mut R {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        (**self).read(buf)
+    }
+}
+
+impl<S: Seek + ?Sized> Seek for &mut S {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        (**self).seek(pos)
+    }
+}
+
+impl Read for &[u8] {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = buf.len().min(self.len());
+        let (read, rest) = self.split_at(n);
+        buf[..n].copy_from_slice(read);
+        *self = rest;
+        Ok(n)
+    }
+}
+
+#[derive(Debug)]
+/// Reader which stops after a number of bytes, returned by [`Read::take`].
+pub struct Take<R> {
+    inner: R,
+    limit: u64,
+}
+
+impl<R> Take<R> {
+    /// Number of bytes that can still be read.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for Take<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let max = buf.len().min(self.limit.try_into().unwrap_or(usize::MAX));
+        let n = self.inner.read(&mut buf[..max])?;
+        self.limit -= n as u64;
+        Ok(n)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Seekable reader over an in-memory buffer.
+pub struct Cursor<T> {
+    inner: T,
+    pos: u64,
+}
+
+impl<T> Cursor<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner, pos: 0 }
+    }
+
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: AsRef<[u8]>> Read for Cursor<T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let data = self.inner.as_ref();
+        let start = self.pos.min(data.len() as u64) as usize;
+        let n = (&data[start..]).read(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<T: AsRef<[u8]>> Seek for Cursor<T> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(pos) => {
+                self.pos = pos;
+                return Ok(pos);
+            }
+            SeekFrom::End(offset) => (self.inner.as_ref().len() as u64, offset),
+            SeekFrom::Current(offset) => (self.pos, offset),
+        };
+
+        match base.checked_add_signed(offset) {
+            Some(pos) => {
+                self.pos = pos;
+                Ok(pos)
+            }
+            None => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
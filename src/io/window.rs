@@ -0,0 +1,315 @@
+//! Reader over the bytes of the archive received so far, which lets the synchronous block
+//! iterators parse archives that aren't read through [`io::Read`] and [`io::Seek`].
+//!
+//! The block iterators read their headers from a [`Window`], an in-memory copy of a range
+//! of the archive. When a parser reads past the bytes of the window the read fails and the
+//! position is recorded, so that the caller can add the missing bytes and parse the block
+//! again. Parsers only change the state of the iterator after they read the whole header,
+//! so parsing a block again is safe.
+//!
+//! The bytes are either fetched from an asynchronous reader by [`next_block`], or fed in
+//! order by the caller of a [`Parser`](crate::parser).
+
+use alloc::vec::Vec;
+
+use crate::{error::RarResult, io};
+
+#[cfg(feature = "async")]
+use core::{future::poll_fn, pin::Pin};
+
+#[cfg(feature = "async")]
+use futures_io::{AsyncRead, AsyncSeek};
+
+#[cfg(feature = "async")]
+use crate::{error::Error, size::BlockSize};
+
+/// Number of bytes fetched after the position of a failed read, so that several small
+/// headers can be read with a single request.
+#[cfg(feature = "async")]
+pub(crate) const READ_AHEAD: usize = 0x10000;
+
+/// Maximum size of the window. Only a single block is parsed at a time, and headers are
+/// never larger than 2 MiB.
+#[cfg(feature = "async")]
+const MAX_SIZE: u64 = 0x400000;
+
+/// File size reported while the end of a fed stream is not known.
+const UNKNOWN_SIZE: u64 = i64::MAX as u64;
+
+#[derive(Debug)]
+/// Reader over the bytes of the archive received so far.
+pub(crate) struct Window {
+    file_size: u64,
+    start: u64,
+    buf: Vec<u8>,
+    pos: u64,
+
+    /// Position of the last read which fell outside of the window.
+    missing: Option<u64>,
+}
+
+impl Window {
+    /// Create an empty window over a file of `file_size` bytes.
+    #[cfg(feature = "async")]
+    pub fn new(file_size: u64) -> Self {
+        Self {
+            file_size,
+            start: 0,
+            buf: vec![],
+            pos: 0,
+            missing: None,
+        }
+    }
+
+    /// Create an empty window over a stream whose size is not known yet, which will be
+    /// [fed](Self::push) from `offset`.
+    pub fn streaming(offset: u64) -> Self {
+        Self {
+            file_size: UNKNOWN_SIZE,
+            start: offset,
+            buf: vec![],
+            pos: 0,
+            missing: None,
+        }
+    }
+
+    /// Offset of the first byte of the window.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    fn end(&self) -> u64 {
+        self.start + self.buf.len() as u64
+    }
+
+    /// Take the position of the last read which fell outside of the window.
+    pub fn take_missing(&mut self) -> Option<u64> {
+        self.missing.take()
+    }
+
+    /// Add the bytes of the stream at `pos`, skipping the ones before the window.
+    ///
+    /// The stream must be fed in order.
+    pub fn push(&mut self, pos: u64, data: &[u8]) {
+        let skip = self.end().saturating_sub(pos);
+        if let Some(data) = data.get(skip as usize..) {
+            self.buf.extend_from_slice(data);
+        }
+    }
+
+    /// Set the size of the stream once its end is reached.
+    pub fn set_file_size(&mut self, file_size: u64) {
+        self.file_size = file_size;
+    }
+
+    /// Drop the bytes before `offset`, which won't be read again.
+    pub fn discard_before(&mut self, offset: u64) {
+        if offset >= self.end() {
+            self.buf.clear();
+            self.start = offset;
+        } else if offset > self.start {
+            self.buf.drain(..(offset - self.start) as usize);
+            self.start = offset;
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Window {
+    /// Fetch the bytes from `pos` to the end of the read-ahead, along with the bytes between
+    /// the window and `pos`.
+    async fn fill<R: AsyncRead + AsyncSeek + Unpin>(
+        &mut self,
+        reader: &mut R,
+        pos: u64,
+        read_ahead: usize,
+    ) -> RarResult<()> {
+        if self.buf.is_empty() {
+            self.start = pos;
+        }
+
+        let start = self.start.min(pos);
+        let end = self.end().max(pos + read_ahead as u64).min(self.file_size);
+        if end - start > MAX_SIZE {
+            return Err(Error::CorruptHeader);
+        }
+
+        let mut buf = vec![0; (end - start) as usize];
+        let (before, rest) = buf.split_at_mut((self.start - start) as usize);
+        let (current, after) = rest.split_at_mut(self.buf.len());
+        read_exact_at(reader, start, before).await?;
+        current.copy_from_slice(&self.buf);
+        read_exact_at(reader, self.end(), after).await?;
+
+        self.start = start;
+        self.buf = buf;
+        Ok(())
+    }
+
+    /// Fetch more bytes if fewer than the read-ahead are left in the window.
+    async fn top_up<R: AsyncRead + AsyncSeek + Unpin>(
+        &mut self,
+        reader: &mut R,
+        read_ahead: usize,
+    ) -> RarResult<()> {
+        if self.buf.is_empty() || self.buf.len() >= read_ahead || self.end() == self.file_size {
+            return Ok(());
+        }
+
+        self.fill(reader, self.end(), read_ahead).await
+    }
+}
+
+impl io::Read for Window {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.file_size {
+            return Ok(0);
+        }
+
+        if self.pos < self.start || self.pos >= self.end() {
+            self.missing = Some(self.pos);
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "bytes not received yet",
+            ));
+        }
+
+        let available = &self.buf[(self.pos - self.start) as usize..];
+        let n = buf.len().min(available.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl io::Seek for Window {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            io::SeekFrom::Start(pos) => (pos, 0),
+            io::SeekFrom::End(offset) => (self.file_size, offset),
+            io::SeekFrom::Current(offset) => (self.pos, offset),
+        };
+
+        self.pos = base.checked_add_signed(offset).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
+
+/// Block iterator reading from a [`Window`].
+pub(crate) trait WindowedIterator<B>: Iterator<Item = RarResult<B>> {
+    fn window(&mut self) -> &mut Window;
+}
+
+/// Read the next block of `blocks`, fetching the bytes of its header from `reader`
+/// `read_ahead` bytes at a time.
+#[cfg(feature = "async")]
+pub(crate) async fn next_block<R, I, B>(
+    reader: &mut R,
+    blocks: &mut I,
+    read_ahead: usize,
+) -> Option<RarResult<B>>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+    I: WindowedIterator<B>,
+    B: BlockSize,
+{
+    if let Err(e) = blocks.window().top_up(reader, read_ahead).await {
+        return Some(Err(e));
+    }
+
+    loop {
+        let result = blocks.next();
+
+        // Reads outside of the window are ignored if the block was parsed anyway, like
+        // the QuickOpen cache which is only loaded if it's available.
+        match (result, blocks.window().take_missing()) {
+            (Some(Err(_)), Some(pos)) => {
+                if let Err(e) = blocks.window().fill(reader, pos, read_ahead).await {
+                    return Some(Err(e));
+                }
+            }
+            (Some(Ok(block)), _) => {
+                blocks
+                    .window()
+                    .discard_before(block.offset() + block.size());
+                return Some(Ok(block));
+            }
+            (result, _) => return result,
+        }
+    }
+}
+
+/// Size of the stream read by `reader`.
+#[cfg(feature = "async")]
+pub(crate) async fn stream_len<R: AsyncSeek + Unpin>(reader: &mut R) -> io::Result<u64> {
+    poll_fn(|cx| Pin::new(&mut *reader).poll_seek(cx, io::SeekFrom::End(0))).await
+}
+
+#[cfg(feature = "async")]
+async fn read_exact_at<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    offset: u64,
+    mut buf: &mut [u8],
+) -> io::Result<()> {
+    if buf.is_empty() {
+        return Ok(());
+    }
+
+    poll_fn(|cx| Pin::new(&mut *reader).poll_seek(cx, io::SeekFrom::Start(offset))).await?;
+
+    while !buf.is_empty() {
+        match poll_fn(|cx| Pin::new(&mut *reader).poll_read(cx, buf)).await {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => buf = &mut buf[n..],
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_small_read_ahead() {
+    use futures::{executor::block_on, io::AllowStdIo};
+
+    use crate::{rar15, rar50, Signature};
+
+    fn debug<T: core::fmt::Debug>(items: impl IntoIterator<Item = T>) -> Vec<String> {
+        items.into_iter().map(|item| format!("{item:?}")).collect()
+    }
+
+    for read_ahead in [1, 7, 64] {
+        let path = "tests/fixtures/rar15/file_comment.rar";
+        let blocks = rar15::BlockIterator::new(std::fs::File::open(path).unwrap(), 7).unwrap();
+        let expected = debug(blocks);
+
+        let mut reader = AllowStdIo::new(std::fs::File::open(path).unwrap());
+        let file_size = block_on(stream_len(&mut reader)).unwrap();
+        let mut blocks = rar15::BlockIterator::new(Window::new(file_size), 7).unwrap();
+        let actual = debug(core::iter::from_fn(|| {
+            block_on(next_block(&mut reader, &mut blocks, read_ahead))
+        }));
+        assert_eq!(actual, expected);
+
+        let path = "tests/fixtures/rar50/quick_open.rar";
+        let offset = Signature::Rar50.size();
+        let blocks = rar50::BlockIterator::new(std::fs::File::open(path).unwrap(), offset).unwrap();
+        let expected = debug(blocks);
+
+        let mut reader = AllowStdIo::new(std::fs::File::open(path).unwrap());
+        let file_size = block_on(stream_len(&mut reader)).unwrap();
+        let window = Window::new(file_size);
+        let mut blocks = rar50::BlockIterator::new(window, offset).unwrap();
+        let actual = debug(core::iter::from_fn(|| {
+            block_on(next_block(&mut reader, &mut blocks, read_ahead))
+        }));
+        assert_eq!(actual, expected);
+    }
+}
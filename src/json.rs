@@ -0,0 +1,394 @@
+//! Minimal JSON serializer for the types implementing `serde::Serialize`.
+//!
+//! Only available with the `serde` feature. Enums are externally tagged like in
+//! `serde_json`: unit variants are serialized as strings, and variants holding values as
+//! objects with a single key. Byte strings are serialized as arrays of numbers, and
+//! non-finite floats as `null`.
+
+use std::fmt::{self, Write as _};
+
+use serde::ser::{self, Serialize};
+
+use crate::schema::string;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Error returned by [`to_string`] when a value can't be serialized.
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+/// Serialize `value` as compact JSON.
+pub fn to_string<T: ?Sized + Serialize>(value: &T) -> Result<String, Error> {
+    let mut out = String::new();
+    value.serialize(Serializer { out: &mut out })?;
+    Ok(out)
+}
+
+struct Serializer<'a> {
+    out: &'a mut String,
+}
+
+/// Serializer of the elements of an array or the fields of an object.
+struct Compound<'a> {
+    out: &'a mut String,
+    first: bool,
+
+    /// The object is the value of a variant, and needs a closing brace of its own.
+    variant: bool,
+}
+
+impl<'a> Compound<'a> {
+    fn new(out: &'a mut String, open: &str, variant: bool) -> Self {
+        out.push_str(open);
+        Self {
+            out,
+            first: true,
+            variant,
+        }
+    }
+
+    fn separator(&mut self) {
+        if !self.first {
+            self.out.push(',');
+        }
+        self.first = false;
+    }
+
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.separator();
+        value.serialize(Serializer { out: self.out })
+    }
+
+    fn field<T: ?Sized + Serialize>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+        self.separator();
+        self.out.push_str(&string(key));
+        self.out.push(':');
+        value.serialize(Serializer { out: self.out })
+    }
+
+    fn end(self, close: char) -> Result<(), Error> {
+        self.out.push(close);
+        if self.variant {
+            self.out.push('}');
+        }
+        Ok(())
+    }
+}
+
+/// Open the object wrapping the value of a variant.
+fn open_variant(out: &mut String, variant: &str) {
+    out.push('{');
+    out.push_str(&string(variant));
+    out.push(':');
+}
+
+macro_rules! serialize_display {
+    ($($method:ident($type:ty)),* $(,)?) => {
+        $(
+            fn $method(self, v: $type) -> Result<(), Error> {
+                write!(self.out, "{v}").unwrap();
+                Ok(())
+            }
+        )*
+    };
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    serialize_display! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        if v.is_finite() {
+            write!(self.out, "{v}").unwrap();
+        } else {
+            self.out.push_str("null");
+        }
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.out.push_str(&string(v.encode_utf8(&mut [0; 4])));
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.out.push_str(&string(v));
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        let mut seq = Compound::new(self.out, "[", false);
+        for byte in v {
+            seq.element(byte)?;
+        }
+        seq.end(']')
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.out.push_str("null");
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        open_variant(self.out, variant);
+        value.serialize(Serializer { out: self.out })?;
+        self.out.push('}');
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>, Error> {
+        Ok(Compound::new(self.out, "[", false))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        open_variant(self.out, variant);
+        Ok(Compound::new(self.out, "[", true))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>, Error> {
+        Ok(Compound::new(self.out, "{", false))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Compound<'a>, Error> {
+        Ok(Compound::new(self.out, "{", false))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        open_variant(self.out, variant);
+        Ok(Compound::new(self.out, "{", true))
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self, ']')
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self, ']')
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self, ']')
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self, ']')
+    }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    /// Keys which are not strings are converted to strings, since JSON only supports those.
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        self.separator();
+
+        let mut json = String::new();
+        key.serialize(Serializer { out: &mut json })?;
+        if json.starts_with('"') {
+            self.out.push_str(&json);
+        } else {
+            self.out.push_str(&string(&json));
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.out.push(':');
+        value.serialize(Serializer { out: self.out })
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self, '}')
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self, '}')
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self, '}')
+    }
+}
+
+#[test]
+fn test_to_string() {
+    use crate::rar50::{CompressionInfo, FileHash, FileHashRecord};
+
+    assert_eq!(
+        to_string(&(1u8, "a\"\n", None::<u8>)).unwrap(),
+        r#"[1,"a\"\n",null]"#
+    );
+    assert_eq!(
+        to_string(&Ok::<_, u32>("name")).unwrap(),
+        r#"{"Ok":"name"}"#
+    );
+
+    let record = FileHashRecord {
+        hash: FileHash::Unknown(7),
+    };
+    assert_eq!(to_string(&record).unwrap(), r#"{"hash":{"Unknown":7}}"#);
+
+    let json = to_string(&CompressionInfo::new(0x0040)).unwrap();
+    assert!(json.starts_with(r#"{"version":"Pack5","is_rar5_compatible":false,"#));
+    assert!(json.contains(r#""is_solid":true,"method":"NoCompression","#));
+}
@@ -0,0 +1,165 @@
+//! Offsets and sizes of the fields of the raw headers, for tools which display them.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write as _;
+
+use crate::read::read_vint;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A field of a raw block header, as returned by the `header_layout` function of the
+/// module of each format.
+pub struct HeaderField {
+    /// Name of the field.
+    pub name: &'static str,
+
+    /// Offset of the field in the file.
+    pub offset: u64,
+
+    /// Size of the field in bytes.
+    pub size: u64,
+
+    /// Value of the field decoded as text. Flags and checksums are printed in hexadecimal,
+    /// and fields which are not decoded as a hex dump of their bytes.
+    pub value: String,
+}
+
+serialize_struct!(HeaderField {
+    name,
+    offset,
+    size,
+    value,
+});
+
+/// Splits the raw bytes of a header into fields, stopping at the first field which doesn't
+/// fit in the header.
+pub(crate) struct Layout<'a> {
+    header: &'a [u8],
+    pos: usize,
+    offset: u64,
+    fields: Vec<HeaderField>,
+}
+
+impl<'a> Layout<'a> {
+    /// Start splitting the bytes of the `header` found at `offset` in the file.
+    pub fn new(header: &'a [u8], offset: u64) -> Self {
+        Self {
+            header,
+            pos: 0,
+            offset,
+            fields: vec![],
+        }
+    }
+
+    /// Position in the header of the next field.
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Number of bytes after the last field.
+    pub fn remaining(&self) -> usize {
+        self.header.len() - self.pos
+    }
+
+    /// Record a field of `size` bytes whose value is formatted by `value`.
+    pub fn field(
+        &mut self,
+        name: &'static str,
+        size: usize,
+        value: impl FnOnce(&[u8]) -> String,
+    ) -> Option<&'a [u8]> {
+        let header = self.header;
+        let bytes = header.get(self.pos..self.pos.checked_add(size)?)?;
+        self.fields.push(HeaderField {
+            name,
+            offset: self.offset + self.pos as u64,
+            size: size as u64,
+            value: value(bytes),
+        });
+        self.pos += size;
+        Some(bytes)
+    }
+
+    pub fn u8(&mut self, name: &'static str) -> Option<u8> {
+        self.field(name, 1, |b| b[0].to_string()).map(|b| b[0])
+    }
+
+    pub fn u16(&mut self, name: &'static str) -> Option<u16> {
+        let bytes = self.field(name, 2, |b| u16::from_le_bytes([b[0], b[1]]).to_string())?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn u32(&mut self, name: &'static str) -> Option<u32> {
+        let bytes = self.field(name, 4, |b| le_u32(b).to_string())?;
+        Some(le_u32(bytes))
+    }
+
+    /// Record a 16-bit field of flags or a checksum.
+    pub fn hex_u16(&mut self, name: &'static str) -> Option<u16> {
+        let bytes = self.field(name, 2, |b| {
+            format!("{:#06x}", u16::from_le_bytes([b[0], b[1]]))
+        })?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Record a 32-bit field of flags or a checksum.
+    pub fn hex_u32(&mut self, name: &'static str) -> Option<u32> {
+        let bytes = self.field(name, 4, |b| format!("{:#010x}", le_u32(b)))?;
+        Some(le_u32(bytes))
+    }
+
+    /// Record a variable-size integer.
+    pub fn vint(&mut self, name: &'static str) -> Option<u64> {
+        self.vint_with(name, |value| value.to_string())
+    }
+
+    /// Record a variable-size integer holding flags.
+    pub fn hex_vint(&mut self, name: &'static str) -> Option<u64> {
+        self.vint_with(name, |value| format!("{value:#x}"))
+    }
+
+    fn vint_with(&mut self, name: &'static str, format: fn(u64) -> String) -> Option<u64> {
+        let (value, size) = read_vint(&mut &self.header[self.pos..]).ok()?;
+        self.field(name, size as usize, |_| format(value))?;
+        Some(value)
+    }
+
+    /// Record a field of `size` bytes shown as a hex dump.
+    pub fn bytes(&mut self, name: &'static str, size: usize) -> Option<&'a [u8]> {
+        self.field(name, size, hex)
+    }
+
+    /// Record a field of `size` bytes of text, replacing invalid UTF-8 sequences.
+    pub fn text(&mut self, name: &'static str, size: usize) -> Option<&'a [u8]> {
+        self.field(name, size, |b| String::from_utf8_lossy(b).into_owned())
+    }
+
+    /// Record the bytes up to `end` as a single field if there are any.
+    pub fn until(&mut self, name: &'static str, end: usize) -> Option<&'a [u8]> {
+        let size = end.checked_sub(self.pos).filter(|size| *size > 0)?;
+        self.bytes(name, size)
+    }
+
+    /// Record the bytes after the last field as a single field if there are any.
+    pub fn rest(&mut self, name: &'static str) -> Option<&'a [u8]> {
+        self.until(name, self.header.len())
+    }
+
+    pub fn into_fields(self) -> Vec<HeaderField> {
+        self.fields
+    }
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    hex
+}
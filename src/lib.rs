@@ -17,39 +17,107 @@
 //!
 //! At the moment it supports:
 //!
-//! - [ ] RAR13
-//!   - Currently not supported due to lack of information on this format. If you have any
-//!     RAR files this old lying around please get in touch!
+//! - RAR13:
+//!   - UnRAR reads archives created by RAR 1.3x like RAR14 archives, with the same signature
+//!     and blocks, so they are listed by the [`rar14`] module, which like UnRAR reports 13 or
+//!     10 as the [unpack version](rar14::FileBlock::unpack_version) of their files. There is
+//!     no documentation of any other RAR13 format, so there is no separate module for it, and
+//!     it has only been tested with hand-built archives. If you have any RAR files this old
+//!     lying around please get in touch!
 //! - [ ] RAR14:
 //!   - [x] Metadata
 //!   - [ ] Decompression
 //!   - [ ] Decryption
 //! - RAR15:
 //!   - [x] Metadata
-//!   - [ ] Decompression
+//!   - [x] Decompression
 //!   - [ ] Decryption
 //! - RAR50:
 //!   - [x] Metadata
-//!   - [ ] Decompression
-//!   - [ ] Decryption
+//!   - [x] Decompression
+//!   - [x] Decryption
 //!
 //! We aim for 100% compatibility with all files generated by RARLAB products.
 //! If you have a RAR file that you can extract with any version of WinRAR/UnRAR but not with
 //! this library, please raise a bug!
+//!
+//! # `no_std`
+//!
+//! Disabling the default `std` feature makes the crate `no_std` (it still needs `alloc`).
+//! The block iterators of every format and [`Signature::search_stream`] then read the
+//! archive through the [`io::Read`] and [`io::Seek`] traits of this crate instead of the
+//! ones of the standard library, so the metadata can be parsed in environments like WASM.
+//! The parsers of the [`parser`] module don't need a reader at all.
+//! Decompression, decryption of file data, extraction and the `compat` layer require
+//! `std`.
+//!
+//! # Async
+//!
+//! The `async` feature adds an `AsyncBlockIterator` to the module of each format and
+//! `Signature::search_stream_async`, which read the archive through the `AsyncRead` and
+//! `AsyncSeek` traits of `futures-io` and fetch the headers in chunks of 64 KiB, so that
+//! archives behind network-backed readers can be listed without blocking a thread.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[macro_use]
+extern crate alloc;
 
 #[macro_use]
 mod macros;
+mod attributes;
+#[cfg(feature = "std")]
 pub mod compat;
+mod credentials;
+mod cross_check;
+mod crypt;
+#[cfg(feature = "encodings")]
+mod encodings;
 mod error;
+pub mod hash;
+pub mod io;
+#[cfg(feature = "serde")]
+pub mod json;
+mod layout;
+mod limits;
+pub mod parser;
+#[cfg(feature = "std")]
+mod path;
+#[cfg(feature = "std")]
+mod progress;
 pub mod rar14;
 pub mod rar15;
 pub mod rar50;
+mod raw_header;
 mod read;
+mod round_trip;
+mod salvage;
+pub mod schema;
 mod signature;
 mod size;
+#[cfg(feature = "std")]
+mod throttle;
 mod time_conv;
+#[cfg(feature = "std")]
 mod unpack;
+mod warning;
 
+pub use attributes::{
+    DosFileAttributes, FileAttributes, UnixFileType, UnixPermissions, WindowsFileAttributes,
+};
+pub use credentials::{CredentialProvider, PasswordRequest};
+#[cfg(feature = "encodings")]
+pub use encodings::CodePage;
 pub use error::{Error, RarResult};
-pub use signature::Signature;
+pub use layout::HeaderField;
+pub use limits::{LimitKind, Limits};
+#[cfg(feature = "std")]
+pub use progress::{Progress, ProgressReader};
+pub use raw_header::{RawHeaderReader, RawHeaders};
+pub use signature::{Detection, SearchOptions, Signature, SignatureMatch};
 pub use size::BlockSize;
+#[cfg(feature = "std")]
+pub use throttle::{RateLimiter, Throttle, ThrottledReader};
+#[cfg(feature = "std")]
+pub use unpack::{self_test, SelfTestError};
+pub use warning::{Divergence, Severity, Warning, WarningKind};
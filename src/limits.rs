@@ -0,0 +1,104 @@
+//! Limits on the sizes read from headers, to bound the memory used to parse forged
+//! archives.
+
+use core::fmt;
+
+use crate::{error::Error, io};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Maximum sizes accepted for the fields of the headers whose size is read from the archive.
+///
+/// The parsers fail with [`Error::LimitExceeded`](crate::Error::LimitExceeded) instead of
+/// allocating a buffer for a field which is larger than its limit. The defaults of the
+/// header sizes are larger than anything RAR itself writes, so only forged or corrupt
+/// archives exceed them. The block iterators wrap the error in an
+/// [`Error::Block`](crate::Error::Block) with the offset of the block.
+pub struct Limits {
+    /// Maximum size of the header of a block, in bytes.
+    ///
+    /// RAR50 headers can't be larger than 2 MiB, and the headers of the older formats are
+    /// at most 64 KiB.
+    pub max_header_size: u64,
+
+    /// Maximum size of the name of a file, a service block, a stream or an owner, in bytes.
+    pub max_name_size: u64,
+
+    /// Maximum size of the extra records of a RAR50 header and of the data of a RAR15
+    /// service block, in bytes. Names stored inside of records are bounded by it.
+    pub max_record_size: u64,
+
+    /// Maximum size of the window allocated by the RAR50 decoder, in bytes.
+    ///
+    /// The window is as large as the dictionary of the file, or as the file itself if it's
+    /// smaller and its size is known. RAR 7 creates dictionaries of up to 64 GiB, but like
+    /// UnRAR only dictionaries of up to 4 GiB are accepted by default.
+    pub max_window_size: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_header_size: 0x200000,
+            max_name_size: 0x10000,
+            max_record_size: 0x200000,
+            max_window_size: 0x1_0000_0000,
+        }
+    }
+}
+
+impl Limits {
+    /// Check the size of the header of a block.
+    pub(crate) fn header_size(&self, size: u64) -> io::Result<u64> {
+        check(LimitKind::HeaderSize, size, self.max_header_size).map(|_| size)
+    }
+
+    /// Check the size of a name, returning it as the size of the buffer to read.
+    pub(crate) fn name_size(&self, size: u64) -> io::Result<usize> {
+        check(LimitKind::NameSize, size, self.max_name_size)
+    }
+
+    /// Check the size of a record, returning it as the size of the buffer to read.
+    pub(crate) fn record_size(&self, size: u64) -> io::Result<usize> {
+        check(LimitKind::RecordSize, size, self.max_record_size)
+    }
+
+    /// Check the size of a decompression window, returning it as the size to allocate.
+    #[cfg(feature = "std")]
+    pub(crate) fn window_size(&self, size: u64) -> io::Result<usize> {
+        check(LimitKind::WindowSize, size, self.max_window_size)
+    }
+}
+
+fn check(kind: LimitKind, size: u64, limit: u64) -> io::Result<usize> {
+    match usize::try_from(size) {
+        Ok(size) if size as u64 <= limit => Ok(size),
+        _ => Err(Error::LimitExceeded { kind, size, limit }.into()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Which of the [`Limits`] was exceeded.
+pub enum LimitKind {
+    /// [`Limits::max_header_size`]
+    HeaderSize,
+
+    /// [`Limits::max_name_size`]
+    NameSize,
+
+    /// [`Limits::max_record_size`]
+    RecordSize,
+
+    /// [`Limits::max_window_size`]
+    WindowSize,
+}
+
+impl fmt::Display for LimitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LimitKind::HeaderSize => "header size",
+            LimitKind::NameSize => "name size",
+            LimitKind::RecordSize => "record size",
+            LimitKind::WindowSize => "window size",
+        })
+    }
+}
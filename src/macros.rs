@@ -1,4 +1,4 @@
-/// Automatically implement From<T> for the type of the enum
+/// Automatically implement From<T> for the type of the enum and back
 /// and add an Unknown case for when we fail parsing.
 macro_rules! int_enum {
     {
@@ -20,6 +20,61 @@ macro_rules! int_enum {
             Unknown($type),
         }
 
+        impl $name {
+            /// Description of the enum and its variants.
+            #[allow(dead_code)]
+            pub const SCHEMA: $crate::schema::EnumSchema = $crate::schema::EnumSchema {
+                name: stringify!($name),
+                size: core::mem::size_of::<$type>(),
+                doc: &[$($struct_doc),*],
+                variants: &[
+                    $(
+                        $crate::schema::VariantSchema {
+                            name: stringify!($field_name),
+                            value: $field_value as u64,
+                            doc: &[$($field_doc),*],
+                        },
+                    )*
+                ],
+            };
+
+            /// Convert a value read as a vint, failing if it doesn't fit in the type of
+            /// the enum.
+            #[allow(dead_code)]
+            pub(crate) fn from_vint(value: u64) -> Result<Self, $crate::Error> {
+                <$type>::try_from(value)
+                    .map(Self::from)
+                    .map_err(|_| $crate::Error::InvalidEnumValue {
+                        name: stringify!($name),
+                        value,
+                    })
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                const VARIANTS: &[&str] = &[$(stringify!($field_name),)* "Unknown"];
+                let index = |variant| VARIANTS.iter().position(|v| *v == variant).unwrap_or(0) as u32;
+
+                match self {
+                    $(
+                        $name::$field_name => serializer.serialize_unit_variant(
+                            stringify!($name),
+                            index(stringify!($field_name)),
+                            stringify!($field_name),
+                        ),
+                    )*
+                    $name::Unknown(value) => serializer.serialize_newtype_variant(
+                        stringify!($name),
+                        index("Unknown"),
+                        "Unknown",
+                        value,
+                    ),
+                }
+            }
+        }
+
         impl From<$type> for $name {
             fn from(value: $type) -> Self {
                 match value {
@@ -31,6 +86,17 @@ macro_rules! int_enum {
 
             }
         }
+
+        impl From<$name> for $type {
+            fn from(value: $name) -> Self {
+                match value {
+                    $(
+                        $name::$field_name => $field_value,
+                    )*
+                    $name::Unknown(value) => value,
+                }
+            }
+        }
     };
 }
 
@@ -53,6 +119,39 @@ macro_rules! flags {
                 Self(flags)
             }
 
+            /// Raw value of the flags.
+            #[allow(dead_code)]
+            pub fn bits(&self) -> $type {
+                self.0
+            }
+
+            /// Bits which are known to this library.
+            #[allow(dead_code)]
+            pub const KNOWN_BITS: $type = 0 $(| $field_value)*;
+
+            /// Bits that are set but are not known to this library.
+            #[allow(dead_code)]
+            pub fn unknown_bits(&self) -> $type {
+                self.0 & !Self::KNOWN_BITS
+            }
+
+            /// Description of the flags and the meaning of their bits.
+            #[allow(dead_code)]
+            pub const SCHEMA: $crate::schema::FlagsSchema = $crate::schema::FlagsSchema {
+                name: stringify!($struct_name),
+                size: core::mem::size_of::<$type>(),
+                doc: &[$($struct_doc),*],
+                flags: &[
+                    $(
+                        $crate::schema::FlagSchema {
+                            name: stringify!($field_name),
+                            mask: $field_value as u64,
+                            doc: &[$($field_doc),*],
+                        },
+                    )*
+                ],
+            };
+
             $(
                 $(#[doc = $field_doc])*
                 $field_vis fn $field_name(&self) -> bool {
@@ -61,8 +160,8 @@ macro_rules! flags {
             )*
         }
 
-        impl std::fmt::Debug for $struct_name {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        impl core::fmt::Debug for $struct_name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
                 f.debug_struct(stringify!($struct_name))
                     $(
                         .field(stringify!($field_name), &self.$field_name())
@@ -70,9 +169,84 @@ macro_rules! flags {
                     .finish()
             }
         }
+
+        // Flags are serialized as a struct of named booleans.
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $struct_name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeStruct as _;
+
+                let fields: &[&str] = &[$(stringify!($field_name)),*];
+                let mut state = serializer.serialize_struct(stringify!($struct_name), fields.len())?;
+                $(
+                    state.serialize_field(stringify!($field_name), &self.$field_name())?;
+                )*
+                state.end()
+            }
+        }
     }
 }
 
+/// Implement `serde::Serialize` for a struct when the `serde` feature is enabled, as a struct
+/// with the listed fields. Fields followed by `()` are serialized by calling the method with
+/// the same name.
+macro_rules! serialize_struct {
+    ($name:ident {}) => {
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_unit_struct(stringify!($name))
+            }
+        }
+    };
+
+    ($name:ident { $($field:ident $(($($call:tt)*))?),* $(,)? }) => {
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeStruct as _;
+
+                let fields: &[&str] = &[$(stringify!($field)),*];
+                let mut state = serializer.serialize_struct(stringify!($name), fields.len())?;
+                $(
+                    state.serialize_field(stringify!($field), &self.$field $(($($call)*))?)?;
+                )*
+                state.end()
+            }
+        }
+    };
+}
+
+/// Implement `serde::Serialize` for an enum when the `serde` feature is enabled. Variants
+/// must either have no fields, or a single field listed in parentheses.
+macro_rules! serialize_enum {
+    ($name:ident { $($variant:ident $(($value:ident))?),* $(,)? }) => {
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                const VARIANTS: &[&str] = &[$(stringify!($variant)),*];
+                let index = |variant| VARIANTS.iter().position(|v| *v == variant).unwrap_or(0) as u32;
+
+                match self {
+                    $(
+                        $name::$variant $(($value))? => serialize_enum!(
+                            @variant serializer, $name, index(stringify!($variant)), $variant $(, $value)?
+                        ),
+                    )*
+                }
+            }
+        }
+    };
+
+    (@variant $serializer:ident, $name:ident, $index:expr, $variant:ident) => {
+        $serializer.serialize_unit_variant(stringify!($name), $index, stringify!($variant))
+    };
+
+    (@variant $serializer:ident, $name:ident, $index:expr, $variant:ident, $value:ident) => {
+        $serializer.serialize_newtype_variant(stringify!($name), $index, stringify!($variant), $value)
+    };
+}
+
 /// Helper macro to parse RAR50 records.
 macro_rules! parse_records {
     {
@@ -99,7 +273,8 @@ macro_rules! parse_records {
         let mut $unknown = vec![];
 
         if let Some(extra_area_size) = $common_header.extra_area_size {
-            for record in RecordIterator::new($reader, extra_area_size)? {
+            let records = RecordIterator::new($reader, extra_area_size, &$common_header.limits)?;
+            for record in records {
                 let mut record = record?;
 
                 match record.record_type {
@@ -116,7 +291,7 @@ macro_rules! parse_records {
                             }
                         )*
                     )?
-                    _ => $unknown.push(UnknownRecord::new(record.record_type)),
+                    _ => $unknown.push(UnknownRecord::from_record(record, $common_header)),
                 }
             }
         }
@@ -1,58 +1,369 @@
-use std::{fs, io};
+use std::{
+    fmt::Debug,
+    fs, io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
-use rawrxd::{rar14, rar15, rar50, RarResult, Signature};
+use rawrxd::{
+    compat::{Archive, ExtractOptions, ExtractStatus, Extractor, Timestamp, VerifyStatus},
+    rar14, rar15, rar50, BlockSize, RarResult, Signature,
+};
+
+const USAGE: &str = "\
+usage: rawrxd <command> [options] FILE...
+
+commands:
+    list                    list the entries of the archives
+    blocks [--json]         dump the blocks of the archives
+    test                    unpack the entries and check their CRCs
+    extract [-o] [-d DIR]   extract the entries to DIR, or the current directory
+
+options:
+    --json                  print one JSON object describing the blocks of each archive
+    -d, --dest DIR          directory to extract the entries to
+    -o, --overwrite         replace files which already exist when extracting";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    /// Debug representation of the blocks.
+    Debug,
+
+    /// One JSON object per archive, describing its format and blocks.
+    Json,
+}
+
+#[derive(Debug)]
+enum Command {
+    List,
+    Blocks(Output),
+    Test,
+    Extract {
+        destination: PathBuf,
+        overwrite: bool,
+    },
+}
+
+/// Error in the command line arguments.
+#[derive(Debug)]
+struct UsageError(String);
+
+fn parse_args(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(Command, Vec<String>), UsageError> {
+    let command = args
+        .next()
+        .ok_or_else(|| UsageError("missing command".to_string()))?;
+    let mut command = match command.as_str() {
+        "list" | "l" => Command::List,
+        "blocks" => Command::Blocks(Output::Debug),
+        "test" | "t" => Command::Test,
+        "extract" | "x" => Command::Extract {
+            destination: PathBuf::from("."),
+            overwrite: false,
+        },
+        command => return Err(UsageError(format!("unknown command {command}"))),
+    };
+
+    let mut filenames = vec![];
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), &mut command) {
+            ("--json", Command::Blocks(output)) => *output = Output::Json,
+            ("-d" | "--dest", Command::Extract { destination, .. }) => {
+                *destination = args
+                    .next()
+                    .ok_or_else(|| UsageError(format!("missing directory after {arg}")))?
+                    .into();
+            }
+            ("-o" | "--overwrite", Command::Extract { overwrite, .. }) => *overwrite = true,
+            ("--", _) => filenames.extend(args.by_ref()),
+            (flag, _) if flag.starts_with('-') => {
+                return Err(UsageError(format!("unknown option {flag}")));
+            }
+            _ => filenames.push(arg),
+        }
+    }
+
+    if filenames.is_empty() {
+        return Err(UsageError("missing archive".to_string()));
+    }
+
+    if cfg!(not(feature = "serde")) && matches!(command, Command::Blocks(Output::Json)) {
+        return Err(UsageError(
+            "--json requires building with `--features serde`".to_string(),
+        ));
+    }
+
+    Ok((command, filenames))
+}
+
+fn open(filename: &str) -> RarResult<Archive<io::BufReader<fs::File>>> {
+    Archive::new(io::BufReader::new(fs::File::open(filename)?))
+}
+
+/// Print a table of the entries of the archive.
+fn list(filename: &str) -> RarResult<bool> {
+    let entries = open(filename)?.entries()?;
 
-fn dump_headers(filename: &str) -> RarResult<()> {
     println!("{filename}");
+    println!("{:>12} {:>12}  {:<16}  Name", "Size", "Packed", "Modified");
+
+    for entry in &entries {
+        let size = match (entry.is_directory, entry.unpacked_size) {
+            (true, _) => "<DIR>".to_string(),
+            (false, Some(size)) => size.to_string(),
+            (false, None) => "?".to_string(),
+        };
+
+        println!(
+            "{size:>12} {:>12}  {:<16}  {}",
+            entry.packed_size,
+            entry.modification_time.map(format_time).unwrap_or_default(),
+            entry.name_lossy()
+        );
+    }
+
+    let unpacked: u64 = entries.iter().filter_map(|e| e.unpacked_size).sum();
+    let packed: u64 = entries.iter().map(|e| e.packed_size).sum();
+    println!("{unpacked:>12} {packed:>12}  {} entries", entries.len());
+
+    Ok(true)
+}
+
+/// Format a timestamp like `2024-01-31 12:00`.
+///
+/// MS-DOS timestamps are printed as they are stored, and RAR50 timestamps in UTC.
+fn format_time(time: Timestamp) -> String {
+    let time = match time {
+        Timestamp::Local(time) => time,
+        Timestamp::Utc(time) => {
+            let time = time.to_offset(time::UtcOffset::UTC);
+            time::PrimitiveDateTime::new(time.date(), time.time())
+        }
+    };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        time.year(),
+        time.month() as u8,
+        time.day(),
+        time.hour(),
+        time.minute()
+    )
+}
+
+/// Unpack every entry and check its hashes, printing the result of each.
+///
+/// Returns `false` if an entry is corrupt or a hash doesn't match.
+fn test(filename: &str) -> RarResult<bool> {
+    let report = open(filename)?.test()?;
+
+    println!("{filename}");
+    for entry in &report.entries {
+        let status = match &entry.status {
+            VerifyStatus::Ok => "OK".to_string(),
+            VerifyStatus::Unverified => "OK (no hash)".to_string(),
+            VerifyStatus::HashMismatch { hash, .. } => format!("FAILED ({hash:?} mismatch)"),
+            VerifyStatus::Corrupt => "FAILED (corrupt data)".to_string(),
+            VerifyStatus::NotExtractable => "SKIPPED (not extractable)".to_string(),
+        };
+        println!("    {status:<24} {}", entry.entry.name_lossy());
+    }
+
+    let failures = report.failures().count();
+    if failures == 0 {
+        println!("All OK");
+    } else {
+        println!("{failures} entries failed");
+    }
 
+    Ok(report.is_ok())
+}
+
+/// Extract the entries of the archive, printing the ones that couldn't be extracted.
+///
+/// Returns `false` if an entry couldn't be written out.
+fn extract(filename: &str, destination: &Path, overwrite: bool) -> RarResult<bool> {
+    let mut archive = open(filename)?;
+    let options = ExtractOptions {
+        overwrite,
+        ..Default::default()
+    };
+    let report = Extractor::with_options(&mut archive, destination, options).extract_all()?;
+
+    println!("{filename}");
+    for extracted in &report.entries {
+        let status = match &extracted.status {
+            ExtractStatus::Extracted => "OK".to_string(),
+            ExtractStatus::AlreadyExists => "SKIPPED (already exists)".to_string(),
+            ExtractStatus::LinkRejected => "SKIPPED (link rejected)".to_string(),
+            ExtractStatus::NotExtractable => "SKIPPED (not extractable)".to_string(),
+            ExtractStatus::Failed(e) => format!("FAILED ({e})"),
+        };
+        println!("    {status:<24} {}", extracted.path.display());
+
+        for e in &extracted.metadata_errors {
+            println!("        metadata not restored: {e}");
+        }
+    }
+
+    Ok(report.is_ok())
+}
+
+/// Blocks which can be printed in every [`Output`] format.
+#[cfg(feature = "serde")]
+trait DumpBlock: Debug + BlockSize + serde::Serialize {}
+
+#[cfg(feature = "serde")]
+impl<B: Debug + BlockSize + serde::Serialize> DumpBlock for B {}
+
+#[cfg(not(feature = "serde"))]
+trait DumpBlock: Debug + BlockSize {}
+
+#[cfg(not(feature = "serde"))]
+impl<B: Debug + BlockSize> DumpBlock for B {}
+
+fn dump_headers(filename: &str, output: Output) -> RarResult<bool> {
     let f = fs::File::open(filename)?;
     let mut f = io::BufReader::new(f);
 
     let Some((format, offset)) = Signature::search_stream(&mut f)? else {
-        eprintln!("RAR signature not found");
-        return Ok(());
+        eprintln!("{filename}: RAR signature not found");
+        return Ok(false);
     };
 
-    println!("{:?}", (format, offset));
-
+    let dump = Dump {
+        filename,
+        format,
+        offset,
+    };
     let offset = offset + format.size();
 
     match format {
-        Signature::Rar14 => {
-            let block_reader = rar14::BlockIterator::new(f, offset)?;
+        Signature::Rar14 => dump.blocks(rar14::BlockIterator::new(f, offset)?, output)?,
+        Signature::Rar15 => dump.blocks(rar15::BlockIterator::new(f, offset)?, output)?,
+        Signature::Rar50 => dump.blocks(rar50::BlockIterator::new(f, offset)?, output)?,
+    }
 
-            for block in block_reader {
-                let block = block?;
-                println!("{block:#?}");
-            }
-        }
-        Signature::Rar15 => {
-            let block_reader = rar15::BlockIterator::new(f, offset)?;
+    Ok(true)
+}
 
-            for block in block_reader {
-                let block = block?;
-                println!("{block:#?}");
+struct Dump<'a> {
+    filename: &'a str,
+    format: Signature,
+
+    /// Offset of the signature.
+    offset: u64,
+}
+
+impl Dump<'_> {
+    fn blocks<B: DumpBlock>(
+        &self,
+        blocks: impl Iterator<Item = RarResult<B>>,
+        output: Output,
+    ) -> RarResult<()> {
+        match output {
+            Output::Debug => {
+                println!("{}", self.filename);
+                println!("{:?}", (self.format, self.offset));
+
+                for block in blocks {
+                    println!("{:#?}", block?);
+                }
+
+                Ok(())
             }
-        }
-        Signature::Rar50 => {
-            let block_reader = rar50::BlockIterator::new(f, offset)?;
 
-            for block in block_reader {
-                let block = block?;
-                println!("{block:#?}");
+            #[cfg(feature = "serde")]
+            Output::Json => {
+                println!("{}", self.to_json(blocks)?);
+                Ok(())
             }
+
+            #[cfg(not(feature = "serde"))]
+            Output::Json => unreachable!("JSON output requires the serde feature"),
         }
     }
 
-    Ok(())
+    /// Describe the archive and its blocks as a single line of JSON.
+    ///
+    /// The blocks read before an error are still listed, and the error is stored in the
+    /// `error` field.
+    #[cfg(feature = "serde")]
+    fn to_json<B: DumpBlock>(
+        &self,
+        blocks: impl Iterator<Item = RarResult<B>>,
+    ) -> io::Result<String> {
+        use rawrxd::json::to_string;
+
+        let mut json_blocks = vec![];
+        let mut error = None;
+
+        for block in blocks {
+            let block = match block {
+                Ok(block) => block,
+                Err(e) => {
+                    error = Some(e.to_string());
+                    break;
+                }
+            };
+
+            json_blocks.push(format!(
+                r#"{{"offset":{},"header_size":{},"data_size":{},"block":{}}}"#,
+                block.offset(),
+                block.header_size(),
+                block.data_size(),
+                to_string(&block).map_err(io::Error::other)?,
+            ));
+        }
+
+        Ok(format!(
+            r#"{{"file":{},"format":"{:?}","offset":{},"blocks":[{}],"error":{}}}"#,
+            to_string(self.filename).map_err(io::Error::other)?,
+            self.format,
+            self.offset,
+            json_blocks.join(","),
+            to_string(&error).map_err(io::Error::other)?,
+        ))
+    }
 }
 
-fn main() {
-    let args = std::env::args();
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1).peekable();
+    if matches!(args.peek().map(String::as_str), Some("-h" | "--help")) {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let (command, filenames) = match parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(UsageError(message)) => {
+            eprintln!("{message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut status = ExitCode::SUCCESS;
+    for filename in &filenames {
+        let result = match &command {
+            Command::List => list(filename),
+            Command::Blocks(output) => dump_headers(filename, *output),
+            Command::Test => test(filename),
+            Command::Extract {
+                destination,
+                overwrite,
+            } => extract(filename, destination, *overwrite),
+        };
 
-    for filename in args.skip(1) {
-        if let Err(e) = dump_headers(&filename) {
-            eprintln!("{e}");
+        match result {
+            Ok(true) => {}
+            Ok(false) => status = ExitCode::FAILURE,
+            Err(e) => {
+                eprintln!("{filename}: {e}");
+                status = ExitCode::FAILURE;
+            }
         }
     }
+
+    status
 }
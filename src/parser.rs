@@ -0,0 +1,186 @@
+//! Sans-io parsing of the blocks of an archive.
+//!
+//! The `Parser` of each format doesn't own a reader: the bytes of the archive are passed to
+//! its `feed` method in order as they arrive, and each block is returned as soon as its
+//! header is complete. The data areas of the blocks are skipped without being buffered,
+//! so archives can be parsed incrementally from streams which can't be seeked, in
+//! `no_std` environments, or with any I/O framework.
+//!
+//! Each format has its own parser: [`rar14::Parser`], [`rar15::Parser`] and
+//! [`rar50::Parser`]. The `StreamBlockIterator` of each format feeds its parser with the
+//! bytes of an [`io::Read`], so that archives can be listed from pipes and sockets.
+//!
+//! [`rar14::Parser`]: crate::rar14::Parser
+//! [`rar15::Parser`]: crate::rar15::Parser
+//! [`rar50::Parser`]: crate::rar50::Parser
+
+use alloc::{collections::VecDeque, vec::Vec};
+
+use crate::{
+    error::{Error, RarResult},
+    io::{self, window::WindowedIterator},
+    size::BlockSize,
+};
+
+#[derive(Debug)]
+/// Event returned by a parser after it's fed some bytes.
+pub enum Event<B> {
+    /// The header of a block was parsed.
+    Block(B),
+
+    /// The archive can't be parsed any further. No more events are returned.
+    Error(Error),
+
+    /// The end of the archive was reached. No more events are returned.
+    End,
+}
+
+#[derive(Debug)]
+/// State shared by the parsers of every format, which runs a block iterator over the
+/// bytes fed so far.
+pub(crate) struct Core<I> {
+    pub blocks: I,
+
+    /// Number of bytes of the stream fed so far.
+    position: u64,
+    pub finished: bool,
+    done: bool,
+}
+
+impl<I> Core<I> {
+    /// Create a parser of the stream fed from `offset`.
+    pub fn new(blocks: I, offset: u64) -> Self {
+        Self {
+            blocks,
+            position: offset,
+            finished: false,
+            done: false,
+        }
+    }
+
+    /// Offset in the stream of the next byte to feed.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub fn feed<B: BlockSize>(&mut self, data: &[u8]) -> Vec<Event<B>>
+    where
+        I: WindowedIterator<B>,
+    {
+        if self.finished {
+            return vec![];
+        }
+
+        self.blocks.window().push(self.position, data);
+        self.position += data.len() as u64;
+        self.parse()
+    }
+
+    pub fn finish<B: BlockSize>(&mut self) -> Vec<Event<B>>
+    where
+        I: WindowedIterator<B>,
+    {
+        if self.finished {
+            return vec![];
+        }
+
+        self.finished = true;
+        self.blocks.window().set_file_size(self.position);
+        self.parse()
+    }
+
+    fn parse<B: BlockSize>(&mut self) -> Vec<Event<B>>
+    where
+        I: WindowedIterator<B>,
+    {
+        let mut events = vec![];
+
+        while !self.done {
+            // The iterator only knows it's at the end of the file if the size was known
+            // when it was created.
+            let next_offset = self.blocks.window().start();
+            if self.finished && next_offset >= self.position {
+                self.done = true;
+                events.push(if next_offset == self.position {
+                    Event::End
+                } else {
+                    Event::Error(Error::CorruptHeader)
+                });
+                break;
+            }
+
+            let result = self.blocks.next();
+
+            // Reads past the end of the fed bytes are ignored if the block was parsed
+            // anyway, like the QuickOpen cache which is only loaded if it's available.
+            match (result, self.blocks.window().take_missing()) {
+                (Some(Err(_)), Some(_)) => break,
+                (Some(Ok(block)), _) => {
+                    self.blocks
+                        .window()
+                        .discard_before(block.offset() + block.size());
+                    events.push(Event::Block(block));
+                }
+                (Some(Err(e)), None) => {
+                    self.done = true;
+                    events.push(Event::Error(e));
+                }
+                (None, _) => {
+                    self.done = true;
+                    events.push(Event::End);
+                }
+            }
+        }
+
+        events
+    }
+}
+
+/// Parser fed with the bytes of a reader, for the block iterators which read the stream
+/// forward only.
+#[derive(Debug)]
+pub(crate) struct Streamed<R, I, B> {
+    reader: R,
+    pub core: Core<I>,
+    events: VecDeque<Event<B>>,
+    buf: Vec<u8>,
+}
+
+impl<R: io::Read, I: WindowedIterator<B>, B: BlockSize> Streamed<R, I, B> {
+    /// Size of the chunks read from the reader. Data areas are read and discarded one chunk
+    /// at a time.
+    const CHUNK_SIZE: usize = 0x10000;
+
+    pub fn new(reader: R, core: Core<I>) -> Self {
+        Self {
+            reader,
+            core,
+            events: VecDeque::new(),
+            buf: vec![0; Self::CHUNK_SIZE],
+        }
+    }
+
+    pub fn next(&mut self) -> Option<RarResult<B>> {
+        loop {
+            match self.events.pop_front() {
+                Some(Event::Block(block)) => return Some(Ok(block)),
+                Some(Event::Error(e)) => return Some(Err(e)),
+                Some(Event::End) => return None,
+                None if self.core.finished => return None,
+                None => {}
+            }
+
+            let events = match self.reader.read(&mut self.buf) {
+                Ok(0) => self.core.finish(),
+                Ok(n) => self.core.feed(&self.buf[..n]),
+                #[cfg(feature = "std")]
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.core.finished = true;
+                    return Some(Err(e.into()));
+                }
+            };
+            self.events.extend(events);
+        }
+    }
+}
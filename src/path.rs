@@ -0,0 +1,92 @@
+use std::{
+    borrow::Cow,
+    path::{Component, Path, PathBuf},
+};
+
+/// Convert the name of an entry to a relative path which can't escape the directory it's
+/// extracted to.
+///
+/// Both `/` and `\` are treated as separators. Empty, `.` and `..` components are removed,
+/// along with leading separators and drive prefixes like `C:` at the start of any
+/// component, so that the path is always relative. On Windows the remaining `:` are
+/// replaced with `_`, because a component like `C:foo` would replace the whole path when
+/// joined to it.
+pub(crate) fn sanitize_path(name: &str) -> PathBuf {
+    let mut path = PathBuf::new();
+
+    for component in name.split(['/', '\\']) {
+        let component = strip_drive_prefix(component);
+        if matches!(component, "" | "." | "..") {
+            continue;
+        }
+
+        let component = if cfg!(windows) {
+            Cow::Owned(component.replace(':', "_"))
+        } else {
+            Cow::Borrowed(component)
+        };
+
+        // Anything that isn't a plain name on this platform could change the root of the
+        // path.
+        let component = Path::new(component.as_ref());
+        if component
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            path.push(component);
+        }
+    }
+
+    path
+}
+
+fn strip_drive_prefix(mut component: &str) -> &str {
+    while let [letter, b':', ..] = component.as_bytes() {
+        if !letter.is_ascii_alphabetic() {
+            break;
+        }
+        component = &component[2..];
+    }
+    component
+}
+
+#[test]
+fn test_sanitize_path() {
+    let path = |components: &[&str]| components.iter().collect::<PathBuf>();
+
+    assert_eq!(
+        sanitize_path("docs/readme.txt"),
+        path(&["docs", "readme.txt"])
+    );
+    assert_eq!(
+        sanitize_path("docs\\readme.txt"),
+        path(&["docs", "readme.txt"])
+    );
+    assert_eq!(sanitize_path("/etc/passwd"), path(&["etc", "passwd"]));
+    assert_eq!(sanitize_path("../../etc/passwd"), path(&["etc", "passwd"]));
+    assert_eq!(sanitize_path("a/./b/../c"), path(&["a", "b", "c"]));
+    assert_eq!(
+        sanitize_path("C:\\Windows\\win.ini"),
+        path(&["Windows", "win.ini"])
+    );
+    assert_eq!(sanitize_path("C:win.ini"), path(&["win.ini"]));
+    assert_eq!(
+        sanitize_path("\\\\server\\share\\x"),
+        path(&["server", "share", "x"])
+    );
+    assert_eq!(sanitize_path(".."), PathBuf::new());
+    assert_eq!(sanitize_path("a/C:foo"), path(&["a", "foo"]));
+    assert_eq!(sanitize_path("a\\C:\\b"), path(&["a", "b"]));
+    assert_eq!(sanitize_path("a/C:D:foo/b"), path(&["a", "foo", "b"]));
+    assert_eq!(sanitize_path("a/C:/../b"), path(&["a", "b"]));
+    assert_eq!(sanitize_path("a/C:"), path(&["a"]));
+}
+
+#[test]
+#[cfg(windows)]
+fn test_sanitize_path_colons() {
+    let path = |components: &[&str]| components.iter().collect::<PathBuf>();
+
+    assert_eq!(sanitize_path("a/b:c"), path(&["a", "b_c"]));
+    assert_eq!(sanitize_path("a/:C:x"), path(&["a", "_C_x"]));
+}
@@ -0,0 +1,144 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+#[derive(Debug, Default)]
+/// Byte counters of an extraction.
+///
+/// The data pipeline reports both the packed bytes read from the archive and the unpacked
+/// bytes produced by the decoder: UIs usually show the percentage of the unpacked size,
+/// while I/O planning cares about the packed size.
+///
+/// Counters are atomic so they can be polled from another thread while the extraction is
+/// running. A counter can have a parent, which is updated along with it; this is used to
+/// keep per-entry counters along with the totals of an extraction session.
+pub struct Progress {
+    packed_bytes: AtomicU64,
+    unpacked_bytes: AtomicU64,
+    parent: Option<Arc<Progress>>,
+}
+
+impl Progress {
+    /// Create a new counter starting at 0.
+    pub fn new() -> Arc<Self> {
+        Arc::default()
+    }
+
+    /// Create a counter whose updates are also added to `self`.
+    pub fn child(self: &Arc<Self>) -> Arc<Self> {
+        Arc::new(Progress {
+            parent: Some(self.clone()),
+            ..Default::default()
+        })
+    }
+
+    /// Number of bytes read from the data area of the archive.
+    pub fn packed_bytes(&self) -> u64 {
+        self.packed_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Number of bytes produced after decryption and decompression.
+    pub fn unpacked_bytes(&self) -> u64 {
+        self.unpacked_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Add `bytes` to the packed counter of this counter and its parents.
+    pub fn add_packed(&self, bytes: u64) {
+        self.packed_bytes.fetch_add(bytes, Ordering::Relaxed);
+
+        if let Some(parent) = &self.parent {
+            parent.add_packed(bytes);
+        }
+    }
+
+    /// Add `bytes` to the unpacked counter of this counter and its parents.
+    pub fn add_unpacked(&self, bytes: u64) {
+        self.unpacked_bytes.fetch_add(bytes, Ordering::Relaxed);
+
+        if let Some(parent) = &self.parent {
+            parent.add_unpacked(bytes);
+        }
+    }
+
+    /// Wrap the reader of a data area so that the bytes read from it are added to the packed
+    /// counter.
+    pub fn packed_reader<R: io::Read>(self: &Arc<Self>, reader: R) -> ProgressReader<R> {
+        ProgressReader {
+            reader,
+            progress: self.clone(),
+            side: Side::Packed,
+        }
+    }
+
+    /// Wrap a decoder so that the bytes read from it are added to the unpacked counter.
+    pub fn unpacked_reader<R: io::Read>(self: &Arc<Self>, reader: R) -> ProgressReader<R> {
+        ProgressReader {
+            reader,
+            progress: self.clone(),
+            side: Side::Unpacked,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Side {
+    Packed,
+    Unpacked,
+}
+
+#[derive(Debug)]
+/// Wraps an [`io::Read`] and adds the bytes read to a [`Progress`] counter.
+pub struct ProgressReader<R> {
+    reader: R,
+    progress: Arc<Progress>,
+    side: Side,
+}
+
+impl<R> ProgressReader<R> {
+    /// The counter updated by this reader.
+    pub fn progress(&self) -> &Arc<Progress> {
+        &self.progress
+    }
+
+    /// Unwrap the inner reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: io::Read> io::Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+
+        match self.side {
+            Side::Packed => self.progress.add_packed(n as u64),
+            Side::Unpacked => self.progress.add_unpacked(n as u64),
+        }
+
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_progress_updates_parent() {
+    let total = Progress::new();
+    let entry = total.child();
+
+    let packed = entry.packed_reader(&b"packed"[..]);
+    let mut unpacked = entry.unpacked_reader(packed);
+    io::copy(&mut unpacked, &mut io::sink()).unwrap();
+
+    assert_eq!(entry.packed_bytes(), 6);
+    assert_eq!(entry.unpacked_bytes(), 6);
+
+    let other_entry = total.child();
+    other_entry.add_packed(10);
+    other_entry.add_unpacked(20);
+
+    assert_eq!(total.packed_bytes(), 16);
+    assert_eq!(total.unpacked_bytes(), 26);
+}
@@ -0,0 +1,45 @@
+use futures_io::{AsyncRead, AsyncSeek};
+
+use crate::{
+    error::RarResult,
+    io::window::{self, Window},
+    warning::Warning,
+};
+
+use super::{Block, BlockIterator};
+
+#[derive(Debug)]
+/// Asynchronous version of [`BlockIterator`].
+///
+/// Wraps a reader implementing [`AsyncRead`] and [`AsyncSeek`] and fetches the headers of
+/// the blocks in chunks, skipping over their data areas.
+pub struct AsyncBlockIterator<R> {
+    reader: R,
+    blocks: BlockIterator<Window>,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncBlockIterator<R> {
+    /// Create an [`AsyncBlockIterator`] starting at `offset`.
+    ///
+    /// `offset` must be the offset in the file right after the RAR14 signature.
+    pub async fn new(mut reader: R, offset: u64) -> RarResult<Self> {
+        let file_size = window::stream_len(&mut reader).await?;
+        let blocks = BlockIterator::new(Window::new(file_size), offset)?;
+        Ok(Self { reader, blocks })
+    }
+
+    /// Read the next block, or return `None` at the end of the file.
+    pub async fn next(&mut self) -> Option<RarResult<Block>> {
+        window::next_block(&mut self.reader, &mut self.blocks, window::READ_AHEAD).await
+    }
+
+    /// Warnings about the blocks read so far.
+    pub fn warnings(&self) -> &[Warning] {
+        self.blocks.warnings()
+    }
+
+    /// Take the warnings about the blocks read so far, leaving the list empty.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        self.blocks.take_warnings()
+    }
+}
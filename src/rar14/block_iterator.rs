@@ -1,8 +1,18 @@
-use std::io;
+use alloc::vec::Vec;
 
 use crate::{
+    cross_check,
     error::{Error, RarResult},
+    io::{
+        self,
+        buffered_header::BufferedHeader,
+        window::{Window, WindowedIterator},
+        Seek as _,
+    },
+    limits::Limits,
+    raw_header::RawHeaderReader,
     size::BlockSize as _,
+    warning::{self, BlockRanges, Warning},
 };
 
 use super::{Block, FileBlock, MainBlock};
@@ -15,14 +25,25 @@ pub struct BlockIterator<R: io::Read + io::Seek> {
     reader: R,
     file_size: u64,
     next_offset: u64,
+    /// Buffer reused to read each header, so that reading a block doesn't allocate one.
+    header_buffer: Vec<u8>,
+    ranges: BlockRanges,
+    warnings: Vec<Warning>,
     has_read_main_block: bool,
+    limits: Limits,
 }
 
 impl<R: io::Read + io::Seek> BlockIterator<R> {
     /// Create a [`BlockIterator`] starting at `offset`.
     ///
     /// `offset` must be the offset in the file right after the RAR14 signature.
-    pub fn new(mut reader: R, offset: u64) -> RarResult<Self> {
+    pub fn new(reader: R, offset: u64) -> RarResult<Self> {
+        Self::with_limits(reader, offset, Limits::default())
+    }
+
+    /// Create a [`BlockIterator`] starting at `offset` which fails with
+    /// [`Error::LimitExceeded`] on headers exceeding `limits`.
+    pub fn with_limits(mut reader: R, offset: u64, limits: Limits) -> RarResult<Self> {
         let file_size = reader.seek(io::SeekFrom::End(0))?;
 
         Ok(Self {
@@ -30,27 +51,89 @@ impl<R: io::Read + io::Seek> BlockIterator<R> {
             file_size,
             has_read_main_block: false,
             next_offset: offset,
+            header_buffer: vec![],
+            ranges: BlockRanges::default(),
+            warnings: vec![],
+            limits,
         })
     }
 
+    /// Warnings about the blocks read so far.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Take the warnings about the blocks read so far, leaving the list empty.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        core::mem::take(&mut self.warnings)
+    }
+
+    /// Offset of the block which will be read next.
+    ///
+    /// After an error, this is still the offset of the block which failed, unless it was
+    /// skipped by salvaging.
+    pub fn next_offset(&self) -> u64 {
+        self.next_offset
+    }
+
+    /// Resume the iteration at `offset` of a file which grew to `file_size`, like a file
+    /// which is still being downloaded, without reading the blocks before it again.
+    ///
+    /// `offset` is usually the [`next_offset`](Self::next_offset) of the iterator once it
+    /// ended or failed to read a block truncated by the end of the file. The state of the
+    /// iterator is kept, so it must be an offset in the same archive. Blocks which were already read are reported as overlapping
+    /// if they are read again.
+    pub fn resume_at(&mut self, offset: u64, file_size: u64) {
+        self.next_offset = offset;
+        self.file_size = file_size;
+    }
+
     fn read_block(&mut self) -> RarResult<Block> {
-        self.reader.seek(io::SeekFrom::Start(self.next_offset))?;
+        let max_size = self.file_size.saturating_sub(self.next_offset);
+        let limits = self.limits;
 
-        let block = if !self.has_read_main_block {
-            let main_block = MainBlock::read(&mut self.reader)?;
+        // The size of the main header includes the signature before it.
+        let (block, parsed_end) = if !self.has_read_main_block {
+            let mut header = BufferedHeader::read(
+                &mut self.reader,
+                &mut self.header_buffer,
+                self.next_offset,
+                max_size,
+                2,
+                |prefix| {
+                    limits.header_size(
+                        (u16::from_le_bytes([prefix[0], prefix[1]]) as u64).saturating_sub(4),
+                    )
+                },
+            )?;
+            let main_block = MainBlock::read(&mut header)?;
             self.has_read_main_block = true;
-            Block::Main(main_block)
+            (Block::Main(main_block), header.stream_position()?)
         } else {
-            Block::File(FileBlock::read(&mut self.reader)?)
+            let mut header = BufferedHeader::read(
+                &mut self.reader,
+                &mut self.header_buffer,
+                self.next_offset,
+                max_size,
+                12,
+                |prefix| limits.header_size(u16::from_le_bytes([prefix[10], prefix[11]]) as u64),
+            )?;
+            let file_block = FileBlock::read(&mut header, &limits)?;
+            (Block::File(file_block), header.stream_position()?)
         };
 
         if block.size() == 0
-            || block.offset() + block.header_size() > self.file_size
-            || block.offset() + block.size() > self.file_size
+            || block.offset().saturating_add(block.header_size()) > self.file_size
+            || block.offset().saturating_add(block.size()) > self.file_size
         {
             return Err(Error::CorruptHeader);
         }
 
+        self.ranges
+            .record_block(&block, parsed_end, &mut self.warnings);
+        warning::check_header_end(&block, parsed_end, &mut self.warnings);
+        block.check_flags(&mut self.warnings);
+
         self.next_offset = block.offset() + block.size();
 
         Ok(block)
@@ -65,6 +148,46 @@ impl<R: io::Read + io::Seek> Iterator for BlockIterator<R> {
             return None;
         }
 
-        Some(self.read_block())
+        let offset = self.next_offset;
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("block", format = "rar14", offset).entered();
+
+        // The headers of RAR14 have no type field.
+        let result = self.read_block().map_err(|e| e.at_block(offset, None));
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(block) => tracing::debug!(
+                offset = block.offset(),
+                kind = match block {
+                    Block::Main(_) => "Main",
+                    Block::File(_) => "File",
+                },
+                header_size = block.header_size(),
+                size = block.size(),
+                "read block"
+            ),
+            Err(error) => tracing::debug!(%error, "failed to read block"),
+        }
+
+        Some(result)
+    }
+}
+
+impl<R: io::Read + io::Seek> RawHeaderReader for BlockIterator<R> {
+    type Block = Block;
+
+    fn raw_header(&mut self, block: &Block) -> RarResult<Vec<u8>> {
+        Ok(cross_check::read_header(
+            &mut self.reader,
+            block.offset(),
+            block.header_size(),
+        )?)
+    }
+}
+
+impl WindowedIterator<Block> for BlockIterator<Window> {
+    fn window(&mut self) -> &mut Window {
+        &mut self.reader
     }
 }
@@ -1,6 +1,23 @@
-use std::{io, ops::Deref};
-
-use crate::{read::*, size::BlockSize, time_conv};
+#[cfg(feature = "encodings")]
+use alloc::borrow::Cow;
+use alloc::{string::String, vec::Vec};
+use core::ops::Deref;
+
+#[cfg(feature = "encodings")]
+use crate::encodings::CodePage;
+
+use crate::{
+    attributes::FileAttributes,
+    error::Error,
+    io,
+    limits::Limits,
+    read::*,
+    size::BlockSize,
+    time_conv,
+    warning::{check_flags, Warning},
+};
+
+pub use crate::attributes::DosFileAttributes;
 
 #[derive(Debug)]
 /// A generic RAR14 block.
@@ -9,6 +26,11 @@ pub enum Block {
     File(FileBlock),
 }
 
+serialize_enum!(Block {
+    Main(value),
+    File(value),
+});
+
 impl BlockSize for Block {
     fn offset(&self) -> u64 {
         match self {
@@ -32,6 +54,26 @@ impl BlockSize for Block {
     }
 }
 
+impl Block {
+    /// Push a warning for each flags of the block which have bits set that are not known.
+    pub(crate) fn check_flags(&self, warnings: &mut Vec<Warning>) {
+        match self {
+            Block::Main(b) => check_flags(
+                b.offset,
+                MainBlockFlags::SCHEMA.name,
+                b.flags.unknown_bits() as u64,
+                warnings,
+            ),
+            Block::File(b) => check_flags(
+                b.offset,
+                FileBlockFlags::SCHEMA.name,
+                b.flags.unknown_bits() as u64,
+                warnings,
+            ),
+        }
+    }
+}
+
 #[derive(Debug)]
 /// Block containing archive metadata.
 pub struct MainBlock {
@@ -48,6 +90,13 @@ pub struct MainBlock {
     pub comment: Option<OemString>,
 }
 
+serialize_struct!(MainBlock {
+    offset,
+    header_size,
+    flags,
+    comment,
+});
+
 flags! {
     /// Flags containing archive metadata.
     pub struct MainBlockFlags(u8) {
@@ -81,7 +130,9 @@ impl MainBlock {
     pub(super) fn read<R: io::Read + io::Seek>(reader: &mut R) -> io::Result<Self> {
         let offset = reader.stream_position()?;
 
-        let header_size = read_u16(reader)? - Self::SIGNATURE_SIZE;
+        let header_size = read_u16(reader)?
+            .checked_sub(Self::SIGNATURE_SIZE)
+            .ok_or(Error::CorruptHeader)?;
         let flags = read_u8(reader)?;
         let flags = MainBlockFlags::new(flags);
 
@@ -126,14 +177,8 @@ impl MainBlock {
 
         let _unpacked_size = unpacked_size - 2;
 
-        let crypt_method = 1;
-        let unpack_version = 15;
-        let mut key13 = [0, 7, 77];
-
-        let mut old_dist = [!0, !0, !0, !0];
-        let mut old_dist_ptr = 0;
-
-        // TODO unpack comment
+        // TODO unpack comment. It's encrypted with the RAR 1.3 cipher and the key
+        // [0, 7, 77], then packed with the RAR 1.5 algorithm (unpack version 15).
 
         Ok(None)
     }
@@ -205,6 +250,21 @@ pub struct FileBlock {
     pub name: OemString,
 }
 
+serialize_struct!(FileBlock {
+    offset,
+    header_size,
+    flags,
+    packed_data_size,
+    unpacked_data_size,
+    crc16,
+    modification_time,
+    attributes,
+    unpack_version,
+    method,
+    comment,
+    name,
+});
+
 flags! {
     /// Flags containing metadata for the file.
     pub struct FileBlockFlags(u8) {
@@ -223,7 +283,13 @@ flags! {
 }
 
 impl FileBlock {
-    pub(super) fn read<R: io::Read + io::Seek>(reader: &mut R) -> io::Result<FileBlock> {
+    /// Compression method of files which are stored without compression.
+    pub const METHOD_STORE: u8 = 0;
+
+    pub(crate) fn read<R: io::Read + io::Seek>(
+        reader: &mut R,
+        limits: &Limits,
+    ) -> io::Result<FileBlock> {
         let offset = reader.stream_position()?;
 
         let packed_data_size = read_u32(reader)?;
@@ -255,7 +321,7 @@ impl FileBlock {
             None
         };
 
-        let name = read_vec(reader, name_size)?;
+        let name = read_vec(reader, limits.name_size(name_size as u64)?)?;
         let name = OemString::parse(name);
 
         Ok(FileBlock {
@@ -278,6 +344,11 @@ impl FileBlock {
     pub fn is_directory(&self) -> bool {
         self.attributes.is_directory()
     }
+
+    /// Attributes of the file, which are always MS-DOS attributes.
+    pub fn attributes(&self) -> FileAttributes {
+        FileAttributes::Dos(self.attributes)
+    }
 }
 
 #[derive(Debug)]
@@ -287,12 +358,18 @@ pub enum OemString {
     Ascii(String),
 
     /// The string was encoded using the host system's OEM code page and cannot be decoded
-    /// correctly on its own. The user must select an encoding and use
+    /// correctly on its own. The user must select an encoding and decode it with
+    /// `OemString::decode` of the `encodings` feature,
     /// [`encoding_rs`](https://crates.io/crates/encoding_rs) or
-    /// [`oem_cp`](https://crates.io/crates/oem_cp) to decode it correctly.
+    /// [`oem_cp`](https://crates.io/crates/oem_cp).
     Oem(Vec<u8>),
 }
 
+serialize_enum!(OemString {
+    Ascii(value),
+    Oem(value),
+});
+
 impl OemString {
     pub(crate) fn parse(buf: Vec<u8>) -> Self {
         if buf.is_ascii() {
@@ -305,30 +382,28 @@ impl OemString {
             OemString::Oem(buf)
         }
     }
-}
-
-flags! {
-    /// MS-DOS file attributes.
-    ///
-    /// <https://learn.microsoft.com/en-us/windows/win32/fileio/file-attribute-constants>
-    pub struct DosFileAttributes(u8) {
-        /// File or directory is read-only.
-        pub is_read_only = 0x01;
-
-        /// File or directory is hidden.
-        pub is_hidden = 0x02;
-
-        /// File or directory that is used by the OS.
-        pub is_system_file = 0x04;
 
-        /// File stores the name of the disk.
-        pub is_volume_label = 0x08;
+    /// The bytes of the string, as they were stored in the archive.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            OemString::Ascii(string) => string.as_bytes(),
+            OemString::Oem(bytes) => bytes,
+        }
+    }
 
-        /// Entry is a directory.
-        pub is_directory = 0x10;
+    #[cfg(feature = "encodings")]
+    /// Decode the string with `code_page`, replacing invalid sequences with U+FFFD.
+    pub fn decode(&self, code_page: CodePage) -> Cow<'_, str> {
+        match self {
+            OemString::Ascii(string) => Cow::Borrowed(string),
+            OemString::Oem(bytes) => Cow::Owned(code_page.decode(bytes)),
+        }
+    }
 
-        /// Entry is an archive file or directory.
-        pub is_archive = 0x20;
+    #[cfg(feature = "encodings")]
+    /// Decode the string with the code page guessed by [`CodePage::guess`].
+    pub fn guess_decode(&self) -> Cow<'_, str> {
+        self.decode(CodePage::guess(self.as_bytes()))
     }
 }
 
@@ -353,3 +428,10 @@ impl BlockSize for FileBlock {
         self.packed_data_size as u64
     }
 }
+
+/// Flags and enums defined in this module, listed in [`crate::schema::MODULES`].
+pub(crate) const SCHEMA: crate::schema::ModuleSchema = crate::schema::ModuleSchema {
+    name: "rawrxd::rar14",
+    flags: &[MainBlockFlags::SCHEMA, FileBlockFlags::SCHEMA],
+    enums: &[],
+};
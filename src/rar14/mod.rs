@@ -2,14 +2,24 @@
 //!
 //! The RAR14 archive format was introduced in 1994 with RAR version 1.40
 //! (or was it RAR 1.39?) and only supported MS-DOS. RAR14 archives contain the signature,
-//! followed by the main block, followed by one or more file blocks.
+//! followed by the main block, followed by one or more file blocks. Archives created by
+//! RAR 1.3x share the same format, and the [unpack version](FileBlock::unpack_version) of
+//! their files is read like UnRAR does, as 13 if its byte is 2 and as 10 otherwise.
 //!
 //! Since RAR14 only supported MS-DOS, some care must be taken to correctly handle filenames
 //! and comments, which are encoded using an ANSI/OEM code page and may contain characters not
 //! in the ASCII range.
 
+#[cfg(feature = "async")]
+mod async_block_iterator;
 mod block_iterator;
 mod blocks;
+mod parser;
+mod stream_block_iterator;
 
+#[cfg(feature = "async")]
+pub use async_block_iterator::AsyncBlockIterator;
 pub use block_iterator::*;
 pub use blocks::*;
+pub use parser::{Event, Parser};
+pub use stream_block_iterator::StreamBlockIterator;
@@ -0,0 +1,47 @@
+use alloc::vec::Vec;
+
+use crate::{error::RarResult, io, io::window::Window, parser::Streamed, warning::Warning};
+
+use super::{Block, BlockIterator, Parser};
+
+#[derive(Debug)]
+/// Iterator over the blocks of a RAR14 file which reads the stream forward only.
+///
+/// Unlike [`BlockIterator`], the reader doesn't need to implement [`io::Seek`]: the data
+/// areas of the blocks are read and discarded, so archives can be listed while they are
+/// received from a pipe or a socket.
+pub struct StreamBlockIterator<R: io::Read> {
+    inner: Streamed<R, BlockIterator<Window>, Block>,
+}
+
+impl<R: io::Read> StreamBlockIterator<R> {
+    /// Create a [`StreamBlockIterator`] over a stream whose next byte is at `offset` in the
+    /// archive, which must be the offset right after the RAR14 signature.
+    pub fn new(reader: R, offset: u64) -> Self {
+        Self::from_parser(reader, Parser::new(offset))
+    }
+
+    fn from_parser(reader: R, parser: Parser) -> Self {
+        Self {
+            inner: Streamed::new(reader, parser.core),
+        }
+    }
+
+    /// Warnings about the blocks read so far.
+    pub fn warnings(&self) -> &[Warning] {
+        self.inner.core.blocks.warnings()
+    }
+
+    /// Take the warnings about the blocks read so far, leaving the list empty.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        self.inner.core.blocks.take_warnings()
+    }
+}
+
+impl<R: io::Read> Iterator for StreamBlockIterator<R> {
+    type Item = RarResult<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
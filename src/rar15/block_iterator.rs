@@ -1,11 +1,90 @@
-use std::io;
+use alloc::{string::ToString, vec::Vec};
+use core::ops::Range;
 
 use crate::{
+    credentials::{CredentialProvider, HeaderCredentials},
+    cross_check::{self, HeaderCursor},
+    crypt::{self, CbcDecryptor, Rar30Keys},
     error::{Error, RarResult},
+    hash::Crc16,
+    io::{
+        self,
+        buffered_header::BufferedHeader,
+        window::{Window, WindowedIterator},
+        Seek as _,
+    },
+    limits::Limits,
+    raw_header::RawHeaderReader,
+    read::{read_const_bytes, read_u16, read_u8},
+    round_trip, salvage,
     size::BlockSize as _,
+    warning::{self, BlockRanges, Divergence, Warning, WarningKind},
 };
 
-use super::{Block, BlockKind};
+use super::{Block, BlockKind, FileBlock};
+
+#[derive(Debug, Clone, Default)]
+/// Options controlling how blocks are parsed by a [`BlockIterator`].
+pub struct ParseOptions {
+    /// Parse each header again from its raw bytes with strict checks and report any
+    /// disagreement with the normal parse as a [`WarningKind::ParserDivergence`].
+    ///
+    /// The strict parse fails if the header checksum doesn't match, if the header can't
+    /// be parsed without reading past its declared size, or if some bytes at the end
+    /// of the header are not part of any field. Old-style comments embedded in headers
+    /// are not parsed, so for those headers only the parsed fields are checksummed,
+    /// like UnRAR does.
+    ///
+    /// [`WarningKind::ParserDivergence`]: crate::WarningKind::ParserDivergence
+    pub cross_check: bool,
+
+    /// Maximum sizes of the headers and of their fields. Blocks exceeding them fail with
+    /// [`Error::LimitExceeded`].
+    pub limits: Limits,
+
+    /// When a block header is corrupt, scan forward for the next header with a matching
+    /// checksum and resume from it instead of failing, reporting the skipped bytes as a
+    /// [`WarningKind::CorruptDataSkipped`].
+    ///
+    /// Headers whose checksum doesn't match are also treated as corrupt. Encrypted headers
+    /// can't be scanned for, so the iterator still fails after a corrupt encrypted header.
+    ///
+    /// [`WarningKind::CorruptDataSkipped`]: crate::WarningKind::CorruptDataSkipped
+    pub salvage: bool,
+
+    /// Serialize each header back into bytes from the parsed block and report the first
+    /// byte which differs from the bytes it was parsed from as a
+    /// [`WarningKind::RoundTripMismatch`], to check that no byte was skipped or misread.
+    ///
+    /// Fields whose encoding isn't kept by the parsed block, like the precision of the
+    /// extended times, are written in their shortest encoding. Blocks which don't keep
+    /// enough of their fields to be serialized, like the blocks of unknown types and the
+    /// files with a Unicode name or an embedded comment, and encrypted headers are not
+    /// checked.
+    ///
+    /// [`WarningKind::RoundTripMismatch`]: crate::WarningKind::RoundTripMismatch
+    pub round_trip: bool,
+}
+
+/// State of the encryption of the block headers.
+#[derive(Debug)]
+enum HeaderEncryption {
+    /// The main block doesn't have the password flag, so the headers are in plaintext.
+    None,
+
+    /// The headers following the main block are encrypted, but no key was derived yet.
+    Pending,
+
+    /// The key derived from the salt of the last encrypted header.
+    Key {
+        salt: [u8; crypt::RAR30_SALT_SIZE],
+        key: [u8; 16],
+        iv: [u8; 16],
+
+        /// A header was decrypted successfully with the password.
+        password_checked: bool,
+    },
+}
 
 #[derive(Debug)]
 /// Iterator over the blocks of a RAR15 file.
@@ -16,46 +95,434 @@ pub struct BlockIterator<R: io::Read + io::Seek> {
     reader: R,
     file_size: u64,
     next_offset: u64,
+    /// Buffer reused to read each header, so that reading a block doesn't allocate one.
+    header_buffer: Vec<u8>,
+    ranges: BlockRanges,
+    warnings: Vec<Warning>,
     end_of_archive_reached: bool,
+    /// Flag of the end of archive block, once it was read.
+    has_next_volume: Option<bool>,
+    options: ParseOptions,
+
+    credentials: HeaderCredentials,
+    encryption: HeaderEncryption,
 }
 
 impl<R: io::Read + io::Seek> BlockIterator<R> {
     /// Create a [`BlockIterator`] starting at `offset`.
     ///
     /// `offset` must be the offset of the beginning of a block.
-    pub fn new(mut reader: R, offset: u64) -> RarResult<Self> {
+    pub fn new(reader: R, offset: u64) -> RarResult<Self> {
+        Self::with_options(reader, offset, ParseOptions::default())
+    }
+
+    /// Create a [`BlockIterator`] starting at `offset` which parses blocks according
+    /// to `options`.
+    pub fn with_options(mut reader: R, offset: u64, options: ParseOptions) -> RarResult<Self> {
         let file_size = reader.seek(io::SeekFrom::End(0))?;
 
         Ok(Self {
             reader,
             file_size,
             next_offset: offset,
+            header_buffer: vec![],
+            ranges: BlockRanges::default(),
+            warnings: vec![],
             end_of_archive_reached: false,
+            has_next_volume: None,
+            options,
+            credentials: HeaderCredentials::default(),
+            encryption: HeaderEncryption::None,
         })
     }
 
+    /// Create a [`BlockIterator`] starting at `offset` which decrypts the block headers
+    /// with `password` if the main block has the [`MainBlockFlags::has_password`] flag.
+    ///
+    /// Without a password, the iterator stops at the first encrypted block with
+    /// [`Error::PasswordRequired`]. RAR15 archives don't store a password check value,
+    /// so a wrong password is only detected when the first encrypted header fails its
+    /// checksum, and the iterator stops with [`Error::IncorrectPassword`].
+    ///
+    /// [`MainBlockFlags::has_password`]: super::MainBlockFlags::has_password
+    pub fn with_password(reader: R, offset: u64, password: &str) -> RarResult<Self> {
+        Self::with_credentials(reader, offset, password.to_string())
+    }
+
+    /// Create a [`BlockIterator`] starting at `offset` like
+    /// [`with_password`](Self::with_password), which asks `provider` for the password
+    /// when it reads the first encrypted block.
+    pub fn with_credentials(
+        reader: R,
+        offset: u64,
+        provider: impl CredentialProvider + Send + 'static,
+    ) -> RarResult<Self> {
+        let mut iter = Self::new(reader, offset)?;
+        iter.credentials = HeaderCredentials::new(provider);
+        Ok(iter)
+    }
+
+    /// Warnings about the blocks read so far.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Take the warnings about the blocks read so far, leaving the list empty.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        core::mem::take(&mut self.warnings)
+    }
+
+    /// Whether the archive continues in the next volume, once the [`EndArchiveBlock`](super::EndArchiveBlock) was
+    /// read.
+    ///
+    /// Returns `None` before that, so an iterator which ended while this returns `None`
+    /// reached the end of the file or an error without finding the end of the archive.
+    pub fn has_next_volume(&self) -> Option<bool> {
+        self.has_next_volume
+    }
+
+    /// Range of the bytes between the end of the [`EndArchiveBlock`](super::EndArchiveBlock)
+    /// and the end of the file, once it was read.
+    ///
+    /// The range is empty if the file ends with the end of archive block. Returns `None`
+    /// before that, like [`has_next_volume`](Self::has_next_volume).
+    pub fn trailing_data(&self) -> Option<Range<u64>> {
+        self.has_next_volume?;
+        Some(self.next_offset..self.file_size)
+    }
+
+    /// Offset of the block which will be read next.
+    ///
+    /// After an error, this is still the offset of the block which failed, unless it was
+    /// skipped by salvaging.
+    pub fn next_offset(&self) -> u64 {
+        self.next_offset
+    }
+
+    /// Resume the iteration at `offset` of a file which grew to `file_size`, like a file
+    /// which is still being downloaded, without reading the blocks before it again.
+    ///
+    /// `offset` is usually the [`next_offset`](Self::next_offset) of the iterator once it
+    /// ended or failed to read a block truncated by the end of the file. The state of the
+    /// iterator is kept, like the key of the encrypted headers, so it must be an offset
+    /// in the same archive. Blocks which were already read are reported as overlapping
+    /// if they are read again.
+    pub fn resume_at(&mut self, offset: u64, file_size: u64) {
+        self.next_offset = offset;
+        self.file_size = file_size;
+        self.end_of_archive_reached = false;
+        self.has_next_volume = None;
+    }
+
+    /// Parse `header` again with strict checks.
+    fn strict_parse(header: &[u8], offset: u64, limits: &Limits) -> Result<Block, Divergence> {
+        let mut cursor = HeaderCursor::new(header, offset);
+        let block = Block::read_with_limits(&mut cursor, limits)?;
+
+        let has_embedded_comment = has_embedded_comment(&block);
+
+        // The checksum doesn't cover embedded comments, which have their own.
+        let checksummed = match &block.kind {
+            BlockKind::File(FileBlock {
+                comment: Some(comment),
+                ..
+            }) => cursor.consumed() as usize - comment.size(),
+            _ if has_embedded_comment => cursor.consumed() as usize,
+            _ => header.len(),
+        };
+
+        let mut crc = Crc16::new();
+        crc.update(&header[2..checksummed]);
+        let computed = crc.finalize() as u32;
+        let stored = block.header_crc16 as u32;
+        if stored != computed {
+            return Err(Divergence::ChecksumMismatch { stored, computed });
+        }
+
+        if has_embedded_comment || matches!(block.kind, BlockKind::Unknown(_)) {
+            return Ok(block);
+        }
+
+        let count = header.len() as u64 - cursor.consumed();
+        if count > 0 {
+            return Err(Divergence::TrailingBytes { count });
+        }
+
+        Ok(block)
+    }
+
+    /// Find the offset of the first block header after `start` which passes the strict
+    /// parse.
+    fn find_next_header(&mut self, start: u64) -> io::Result<Option<u64>> {
+        let limits = &self.options.limits;
+        salvage::find_header(
+            &mut self.reader,
+            start,
+            self.file_size,
+            7,
+            |prefix| {
+                let header_size = u16::from_le_bytes([prefix[5], prefix[6]]);
+                limits.header_size(header_size as u64).ok()
+            },
+            |header, offset| {
+                Self::strict_parse(header, offset, limits)
+                    .is_ok_and(|block| !matches!(block.kind, BlockKind::Unknown(_)))
+            },
+        )
+    }
+
+    /// Skip the corrupt header at the next offset and read the first block after it which
+    /// can be read, returning `error` if there is none.
+    fn salvage(&mut self, error: Error) -> RarResult<Block> {
+        let corrupt_offset = self.next_offset;
+        let mut start = corrupt_offset + 1;
+
+        while let Some(offset) = self.find_next_header(start)? {
+            self.next_offset = offset;
+            match self.read_block() {
+                Ok(block) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(corrupt_offset, next_offset = offset, "skipped corrupt data");
+
+                    self.warnings.push(Warning {
+                        offset: corrupt_offset,
+                        kind: WarningKind::CorruptDataSkipped {
+                            range: corrupt_offset..offset,
+                        },
+                    });
+                    return Ok(block);
+                }
+                Err(e) if e.is_corrupt_data() => start = offset + 1,
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.end_of_archive_reached = true;
+        Err(error)
+    }
+
+    /// Derive the key of the header encrypted with `salt`, unless it's the same salt as
+    /// the previous header.
+    fn derive_key(&mut self, salt: [u8; crypt::RAR30_SALT_SIZE]) -> RarResult<()> {
+        let offset = self.next_offset;
+        let password_checked = match self.encryption {
+            HeaderEncryption::Key { salt: previous, .. } if previous == salt => return Ok(()),
+            HeaderEncryption::Key {
+                password_checked, ..
+            } => password_checked,
+            _ => false,
+        };
+
+        let Some(password) = self.credentials.password(offset) else {
+            return Err(Error::PasswordRequired);
+        };
+
+        let keys = Rar30Keys::derive(password, &salt);
+        self.encryption = HeaderEncryption::Key {
+            salt,
+            key: keys.key,
+            iv: keys.iv,
+            password_checked,
+        };
+
+        Ok(())
+    }
+
+    /// Read a block whose header is encrypted.
+    ///
+    /// The header is preceded by the salt and padded to the AES block size, and both are
+    /// counted in the [`BlockSize::header_size`] of the returned block.
+    ///
+    /// [`BlockSize::header_size`]: crate::BlockSize::header_size
+    fn read_encrypted_block(&mut self) -> RarResult<Block> {
+        let offset = self.next_offset;
+        let salt = read_const_bytes(&mut self.reader)?;
+        self.derive_key(salt)?;
+
+        let HeaderEncryption::Key {
+            key,
+            iv,
+            password_checked,
+            ..
+        } = self.encryption
+        else {
+            unreachable!("the key was just derived");
+        };
+
+        // Until a header was decrypted correctly, garbage means a wrong password.
+        let corrupt = || {
+            if password_checked {
+                Error::CorruptHeader
+            } else {
+                Error::IncorrectPassword
+            }
+        };
+
+        // The first AES block contains the whole common part of the header.
+        let mut decryptor = CbcDecryptor::new(&key, iv);
+        let mut header = vec![0; crypt::BLOCK_SIZE];
+        self.reader.read_exact(&mut header)?;
+        decryptor.decrypt(&mut header);
+
+        let header_size = self
+            .options
+            .limits
+            .header_size(read_u16(&mut &header[5..7])? as u64)?;
+        let padded_size = header_size.next_multiple_of(crypt::BLOCK_SIZE as u64);
+        if header_size < 7 || offset + salt.len() as u64 + padded_size > self.file_size {
+            return Err(corrupt());
+        }
+
+        header.resize(padded_size as usize, 0);
+        self.reader.read_exact(&mut header[crypt::BLOCK_SIZE..])?;
+        decryptor.decrypt(&mut header[crypt::BLOCK_SIZE..]);
+        header.truncate(header_size as usize);
+
+        let mut crc = Crc16::new();
+        crc.update(&header[2..]);
+        if read_u16(&mut &header[..2])? != crc.finalize() {
+            return Err(corrupt());
+        }
+
+        if let HeaderEncryption::Key {
+            password_checked, ..
+        } = &mut self.encryption
+        {
+            *password_checked = true;
+        }
+
+        let mut block = Block::read_with_limits(
+            &mut HeaderCursor::new(&header, offset),
+            &self.options.limits,
+        )?;
+        block.header_salt = Some(salt);
+
+        Ok(block)
+    }
+
+    /// Read the type of the unencrypted header at `offset`, to tell where an error happened.
+    fn block_type_at(&mut self, offset: u64) -> Option<u64> {
+        if !matches!(self.encryption, HeaderEncryption::None) {
+            return None;
+        }
+
+        self.reader.seek(io::SeekFrom::Start(offset + 2)).ok()?;
+        read_u8(&mut self.reader).ok().map(u64::from)
+    }
+
     fn read_block(&mut self) -> RarResult<Block> {
-        self.reader.seek(io::SeekFrom::Start(self.next_offset))?;
+        let (block, parsed_end, raw_header) = match self.encryption {
+            HeaderEncryption::None => {
+                let max_size = self.file_size.saturating_sub(self.next_offset);
+                let mut header = BufferedHeader::read(
+                    &mut self.reader,
+                    &mut self.header_buffer,
+                    self.next_offset,
+                    max_size,
+                    7,
+                    |prefix| {
+                        let header_size = u16::from_le_bytes([prefix[5], prefix[6]]);
+                        self.options.limits.header_size(header_size as u64)
+                    },
+                )?;
+                let block = Block::read_with_limits(&mut header, &self.options.limits)?;
+                let parsed_end = header.stream_position()?;
+
+                // The raw header is read before the block is recorded, so that a failed read
+                // leaves the iterator unchanged.
+                let raw_header = if self.options.cross_check
+                    || self.options.salvage
+                    || self.options.round_trip
+                {
+                    Some(cross_check::read_header(
+                        &mut header,
+                        block.offset(),
+                        block.header_size(),
+                    )?)
+                } else {
+                    None
+                };
 
-        let block = Block::read(&mut self.reader)?;
+                (block, parsed_end, raw_header)
+            }
+            _ => {
+                self.reader.seek(io::SeekFrom::Start(self.next_offset))?;
+
+                // The raw bytes of encrypted headers can't be parsed again.
+                let block = self.read_encrypted_block().inspect_err(|e| {
+                    if matches!(e, Error::PasswordRequired | Error::IncorrectPassword) {
+                        self.end_of_archive_reached = true;
+                    }
+                })?;
+                (block, self.reader.stream_position()?, None)
+            }
+        };
+
+        let strict = raw_header
+            .as_deref()
+            .map(|header| Self::strict_parse(header, block.offset(), &self.options.limits));
+        if self.options.salvage && matches!(strict, Some(Err(Divergence::ChecksumMismatch { .. })))
+        {
+            return Err(Error::CorruptHeader);
+        }
 
         if block.size() == 0
-            || block.offset() + block.header_size() > self.file_size
-            || block.offset() + block.size() > self.file_size
+            || block.offset().saturating_add(block.header_size()) > self.file_size
+            || block.offset().saturating_add(block.size()) > self.file_size
         {
             return Err(Error::CorruptHeader);
         }
 
+        self.ranges
+            .record_block(&block, parsed_end, &mut self.warnings);
+        block.check_flags(&mut self.warnings);
+
+        // Embedded comments and the contents of unknown blocks are never parsed, and the
+        // end of encrypted headers is padded.
+        if block.header_salt.is_none()
+            && !has_embedded_comment(&block)
+            && !matches!(block.kind, BlockKind::Unknown(_))
+        {
+            warning::check_header_end(&block, parsed_end, &mut self.warnings);
+        }
+
+        if let Some(strict) = strict.filter(|_| self.options.cross_check) {
+            cross_check::compare(block.offset(), &block, strict, &mut self.warnings);
+        }
+
+        if let Some(header) = raw_header.filter(|_| self.options.round_trip) {
+            if let Some(serialized) = block.serialize_header() {
+                round_trip::compare(block.offset(), &header, &serialized, &mut self.warnings);
+            }
+        }
+
+        if let BlockKind::Main(main) = &block.kind {
+            if main.has_password() {
+                self.encryption = HeaderEncryption::Pending;
+            }
+        }
+
         self.next_offset = block.offset() + block.size();
 
-        if let BlockKind::EndArchive(_) = block.kind {
+        if let BlockKind::EndArchive(end) = &block.kind {
             self.end_of_archive_reached = true;
+            self.has_next_volume = Some(end.has_next_volume());
         }
 
         Ok(block)
     }
 }
 
+/// The header of `block` contains an old-style comment after its fields.
+fn has_embedded_comment(block: &Block) -> bool {
+    match &block.kind {
+        BlockKind::Main(main) => main.has_comment(),
+        BlockKind::File(file) => file.has_comment(),
+        BlockKind::Comment(_) => true,
+        _ => false,
+    }
+}
+
 impl<R: io::Read + io::Seek> Iterator for BlockIterator<R> {
     type Item = RarResult<Block>;
 
@@ -68,6 +535,55 @@ impl<R: io::Read + io::Seek> Iterator for BlockIterator<R> {
             return None;
         }
 
-        Some(self.read_block())
+        let offset = self.next_offset;
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("block", format = "rar15", offset).entered();
+
+        let result = match self.read_block() {
+            Err(e)
+                if self.options.salvage
+                    && e.is_corrupt_data()
+                    && matches!(self.encryption, HeaderEncryption::None) =>
+            {
+                self.salvage(e)
+            }
+            result => result,
+        }
+        .map_err(|e| {
+            let block_type = self.block_type_at(offset);
+            e.at_block(offset, block_type)
+        });
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(block) => tracing::debug!(
+                offset = block.offset(),
+                kind = block.kind.name(),
+                header_size = block.header_size(),
+                size = block.size(),
+                "read block"
+            ),
+            Err(error) => tracing::debug!(%error, "failed to read block"),
+        }
+
+        Some(result)
+    }
+}
+
+impl<R: io::Read + io::Seek> RawHeaderReader for BlockIterator<R> {
+    type Block = Block;
+
+    fn raw_header(&mut self, block: &Block) -> RarResult<Vec<u8>> {
+        Ok(cross_check::read_header(
+            &mut self.reader,
+            block.offset(),
+            block.header_size(),
+        )?)
+    }
+}
+
+impl WindowedIterator<Block> for BlockIterator<Window> {
+    fn window(&mut self) -> &mut Window {
+        &mut self.reader
     }
 }
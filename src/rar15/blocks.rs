@@ -1,6 +1,22 @@
-use std::{io, ops::Deref};
-
-use crate::{read::*, size::BlockSize, time_conv};
+#[cfg(feature = "encodings")]
+use alloc::borrow::Cow;
+use alloc::{string::String, vec::Vec};
+use core::ops::Deref;
+
+#[cfg(feature = "encodings")]
+use crate::encodings::CodePage;
+
+use crate::{
+    attributes::{DosFileAttributes, FileAttributes, UnixPermissions, WindowsFileAttributes},
+    crypt,
+    error::Error,
+    io,
+    limits::Limits,
+    read::*,
+    size::BlockSize,
+    time_conv,
+    warning::{check_flags, Warning},
+};
 
 use super::{decode_file_name::decode_file_name, extended_time::ExtendedTime, NAME_MAX_SIZE};
 
@@ -14,12 +30,27 @@ pub struct Block {
     pub header_crc16: u16,
 
     /// Size of the header.
+    ///
+    /// For encrypted headers this is the size of the plaintext header, which doesn't
+    /// include the salt and the padding.
     pub header_size: u16,
 
+    /// Salt of the key the header was encrypted with, if the archive has encrypted
+    /// headers.
+    pub header_salt: Option<[u8; crypt::RAR30_SALT_SIZE]>,
+
     /// Specific type of this block.
     pub kind: BlockKind,
 }
 
+serialize_struct!(Block {
+    offset,
+    header_crc16,
+    header_size,
+    header_salt,
+    kind,
+});
+
 flags! {
     /// Flags that are common to all blocks.
     pub struct CommonFlags(u16) {
@@ -33,33 +64,45 @@ flags! {
 }
 
 impl Block {
-    const MAIN: u8 = 0x73;
-    const FILE: u8 = 0x74;
-    const COMMENT: u8 = 0x75;
-    const AV: u8 = 0x76;
-    const SUB: u8 = 0x77;
-    const PROTECT: u8 = 0x78;
-    const SIGN: u8 = 0x79;
-    const SERVICE: u8 = 0x7a;
-    const ENDARC: u8 = 0x7b;
+    pub(super) const MAIN: u8 = 0x73;
+    pub(super) const FILE: u8 = 0x74;
+    pub(super) const COMMENT: u8 = 0x75;
+    pub(super) const AV: u8 = 0x76;
+    pub(super) const SUB: u8 = 0x77;
+    pub(super) const PROTECT: u8 = 0x78;
+    pub(super) const SIGN: u8 = 0x79;
+    pub(super) const SERVICE: u8 = 0x7a;
+    pub(super) const ENDARC: u8 = 0x7b;
 
     pub fn read<R: io::Read + io::Seek>(reader: &mut R) -> io::Result<Self> {
+        Self::read_with_limits(reader, &Limits::default())
+    }
+
+    /// Read a block, failing if its sizes exceed `limits`.
+    pub fn read_with_limits<R: io::Read + io::Seek>(
+        reader: &mut R,
+        limits: &Limits,
+    ) -> io::Result<Self> {
         let offset = reader.stream_position()?;
 
         let header_crc16 = read_u16(reader)?;
         let block_type = read_u8(reader)?;
         let flags = read_u16(reader)?;
         let header_size = read_u16(reader)?;
+        limits.header_size(header_size as u64)?;
+        let header_end = offset + header_size as u64;
 
         let kind = match block_type {
             Self::MAIN => BlockKind::Main(MainBlock::read(reader, flags)?),
-            Self::FILE => BlockKind::File(FileBlock::read(reader, flags)?),
-            Self::SERVICE => BlockKind::Service(ServiceBlock::read(reader, flags, header_size)?),
+            Self::FILE => BlockKind::File(FileBlock::read(reader, flags, header_end, limits)?),
+            Self::SERVICE => {
+                BlockKind::Service(ServiceBlock::read(reader, flags, header_size, limits)?)
+            }
             Self::COMMENT => BlockKind::Comment(CommentBlock::read(reader, flags)?),
-            Self::AV => BlockKind::Av(AvBlock::read(reader, flags)?),
-            Self::SUB => BlockKind::Sub(SubBlock::read(reader, flags)?),
+            Self::AV => BlockKind::Av(AvBlock::read(reader, flags, header_end, limits)?),
+            Self::SUB => BlockKind::Sub(SubBlock::read(reader, flags, limits)?),
             Self::PROTECT => BlockKind::Protect(ProtectBlock::read(reader, flags)?),
-            Self::SIGN => BlockKind::Sign(SignBlock::read(reader, flags)?),
+            Self::SIGN => BlockKind::Sign(SignBlock::read(reader, flags, header_end, limits)?),
             Self::ENDARC => BlockKind::EndArchive(EndArchiveBlock::read(reader, flags)?),
             _ => BlockKind::Unknown(UnknownBlock::read(reader, flags, block_type)?),
         };
@@ -68,18 +111,69 @@ impl Block {
             offset,
             header_crc16,
             header_size,
+            header_salt: None,
             kind,
         })
     }
 }
 
+impl Block {
+    /// Push a warning for each flags of the block which have bits set that are not known.
+    ///
+    /// The flags of the header are shared by the [`CommonFlags`] and the flags of the
+    /// kind of the block, and the dictionary size of files is stored in some of their bits.
+    /// Blocks whose kind doesn't parse the flags are not checked.
+    pub(crate) fn check_flags(&self, warnings: &mut Vec<Warning>) {
+        let (name, bits, known) = match &self.kind {
+            BlockKind::Main(b) => (
+                MainBlockFlags::SCHEMA.name,
+                b.flags.bits(),
+                MainBlockFlags::KNOWN_BITS,
+            ),
+            BlockKind::File(b) => (
+                FileBlockFlags::SCHEMA.name,
+                b.flags.bits(),
+                FileBlockFlags::KNOWN_BITS | FileBlock::DIRECTORY_MASK,
+            ),
+            BlockKind::Service(b) => {
+                check_flags(
+                    self.offset,
+                    SubHeadFlags::SCHEMA.name,
+                    b.sub_flags.unknown_bits() as u64,
+                    warnings,
+                );
+                (
+                    ServiceBlockFlags::SCHEMA.name,
+                    b.flags.bits(),
+                    ServiceBlockFlags::KNOWN_BITS | FileBlock::DIRECTORY_MASK,
+                )
+            }
+            BlockKind::EndArchive(b) => (
+                EndArchiveBlockFlags::SCHEMA.name,
+                b.flags.bits(),
+                EndArchiveBlockFlags::KNOWN_BITS,
+            ),
+            _ => return,
+        };
+
+        let unknown = bits & !(known | CommonFlags::KNOWN_BITS);
+        check_flags(self.offset, name, unknown as u64, warnings);
+    }
+}
+
 impl BlockSize for Block {
     fn offset(&self) -> u64 {
         self.offset
     }
 
     fn header_size(&self) -> u64 {
-        self.header_size as u64
+        match self.header_salt {
+            Some(salt) => {
+                salt.len() as u64
+                    + (self.header_size as u64).next_multiple_of(crypt::BLOCK_SIZE as u64)
+            }
+            None => self.header_size as u64,
+        }
     }
 
     fn data_size(&self) -> u64 {
@@ -113,6 +207,38 @@ pub enum BlockKind {
     Unknown(UnknownBlock),
 }
 
+serialize_enum!(BlockKind {
+    Main(value),
+    File(value),
+    Service(value),
+    EndArchive(value),
+    Comment(value),
+    Av(value),
+    Sub(value),
+    Protect(value),
+    Sign(value),
+    Unknown(value),
+});
+
+impl BlockKind {
+    /// Name of the variant, for the events of the `tracing` feature.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            BlockKind::Main(_) => "Main",
+            BlockKind::File(_) => "File",
+            BlockKind::Service(_) => "Service",
+            BlockKind::EndArchive(_) => "EndArchive",
+            BlockKind::Comment(_) => "Comment",
+            BlockKind::Av(_) => "Av",
+            BlockKind::Sub(_) => "Sub",
+            BlockKind::Protect(_) => "Protect",
+            BlockKind::Sign(_) => "Sign",
+            BlockKind::Unknown(_) => "Unknown",
+        }
+    }
+}
+
 #[derive(Debug)]
 /// Main block containing archive metadata.
 ///
@@ -128,6 +254,12 @@ pub struct MainBlock {
     pub encrypt_version: Option<u8>,
 }
 
+serialize_struct!(MainBlock {
+    flags,
+    av_block_offset,
+    encrypt_version,
+});
+
 flags! {
     /// [`MainBlock`] flags.
     pub struct MainBlockFlags(u16) {
@@ -243,6 +375,13 @@ pub enum EncryptionMethod {
     Rar30,
 }
 
+serialize_enum!(EncryptionMethod {
+    Rar13,
+    Rar15,
+    Rar20,
+    Rar30,
+});
+
 impl From<u8> for EncryptionMethod {
     fn from(value: u8) -> Self {
         match value {
@@ -292,7 +431,7 @@ pub struct FileBlock {
     // TODO enumerate these
     pub method: u8,
 
-    /// File attributes, dependent on the OS.
+    /// File attributes, dependent on the OS. They can be decoded with [`Self::attributes`].
     pub attributes: u32,
 
     /// Filename of the file.
@@ -300,13 +439,46 @@ pub struct FileBlock {
 
     // TODO document this
     pub salt: Option<[u8; Self::SALT_SIZE]>,
+
+    /// Old-style comment embedded in the header by RAR 2.x and earlier.
+    pub comment: Option<EmbeddedComment>,
 }
 
+serialize_struct!(FileBlock {
+    flags,
+    packed_data_size,
+    unpacked_data_size,
+    host_os,
+    file_crc32,
+    modification_time,
+    creation_time,
+    access_time,
+    archive_time,
+    unpack_version,
+    method,
+    attributes,
+    file_name,
+    salt,
+    comment,
+});
+
 flags! {
     /// [`FileBlock`] flags.
     pub struct FileBlockFlags(u16) {
+        /// File is continuing from previous volume.
+        pub split_before = 0x0001;
+
+        /// File is continuing in next volume.
+        pub split_after = 0x0002;
+
+        /// File is encrypted with a password.
+        pub is_encrypted = 0x0004;
+
         /// File block contains a comment in the header.
-        pub has_comment = 0x0002;
+        pub has_comment = 0x0008;
+
+        /// Information from previous files is used to decompress this file.
+        pub is_solid = 0x0010;
 
         /// The file size is larger than u32::MAX.
         pub(self) has_large_size = 0x0100;
@@ -317,7 +489,8 @@ flags! {
         /// File is encrypted with salt.
         pub(self) has_salt = 0x0400;
 
-        // TODO document this
+        /// File is an older version of a file kept by RAR's file version control, and its
+        /// name ends with a `;` followed by the version number.
         pub has_version = 0x0800;
 
         /// File may contain modification time, ctime and atime info in the header.
@@ -341,16 +514,80 @@ pub enum Filename {
     Ascii(String),
 
     /// Filename was encoded using the current OEM code page and cannot be decoded
-    /// on its own. The user must select an encoding and use
+    /// on its own. The user must select an encoding and decode it with
+    /// `Filename::decode` of the `encodings` feature,
     /// [`encoding_rs`](https://crates.io/crates/encoding_rs) or
-    /// [`oem_cp`](https://crates.io/crates/oem_cp) to decode it correctly.
+    /// [`oem_cp`](https://crates.io/crates/oem_cp).
     Oem(Vec<u8>),
 }
 
+serialize_enum!(Filename {
+    Unicode(value),
+    Ascii(value),
+    Oem(value),
+});
+
+impl Filename {
+    /// The bytes of the filename, either decoded into UTF-8 or as they were stored
+    /// in the archive if they couldn't be decoded.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Filename::Unicode(Ok(string)) | Filename::Ascii(string) => string.as_bytes(),
+            Filename::Unicode(Err(bytes)) | Filename::Oem(bytes) => bytes,
+        }
+    }
+
+    #[cfg(feature = "encodings")]
+    /// The filename in the OEM code page, which is stored before the encoded Unicode
+    /// filename when it couldn't be decoded.
+    fn oem_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Filename::Unicode(Ok(_)) | Filename::Ascii(_) => None,
+            Filename::Unicode(Err(bytes)) => bytes.split(|&b| b == 0).next(),
+            Filename::Oem(bytes) => Some(bytes),
+        }
+    }
+
+    #[cfg(feature = "encodings")]
+    /// Decode the filename, using `code_page` if it was encoded with the OEM code page.
+    /// Invalid sequences are replaced with U+FFFD.
+    ///
+    /// If the Unicode filename couldn't be decoded, the OEM filename stored before it is
+    /// decoded instead.
+    pub fn decode(&self, code_page: CodePage) -> Cow<'_, str> {
+        match (self, self.oem_bytes()) {
+            (Filename::Unicode(Ok(string)) | Filename::Ascii(string), _) => Cow::Borrowed(string),
+            (_, bytes) => Cow::Owned(code_page.decode(bytes.unwrap_or_default())),
+        }
+    }
+
+    #[cfg(feature = "encodings")]
+    /// Decode the filename like [`Self::decode`], with the code page guessed by
+    /// [`CodePage::guess`].
+    pub fn guess_decode(&self) -> Cow<'_, str> {
+        let code_page = CodePage::guess(self.oem_bytes().unwrap_or_default());
+        self.decode(code_page)
+    }
+}
+
 impl FileBlock {
+    /// Compression method of files which are stored without compression.
+    pub const METHOD_STORE: u8 = 0x30;
+
+    /// Largest [`Self::dictionary_size`] of the formats before RAR50, 4 MiB.
+    pub const MAX_DICTIONARY_SIZE: u64 = 0x400000;
+
     const SALT_SIZE: usize = 8;
 
-    fn read<R: io::Read + io::Seek>(reader: &mut R, flags: u16) -> io::Result<Self> {
+    /// The dictionary size bits are all set for directories.
+    const DIRECTORY_MASK: u16 = 0x00e0;
+
+    fn read<R: io::Read + io::Seek>(
+        reader: &mut R,
+        flags: u16,
+        header_end: u64,
+        limits: &Limits,
+    ) -> io::Result<Self> {
         let flags = FileBlockFlags::new(flags);
 
         let low_packed_data_size = read_u32(reader)? as u64;
@@ -369,7 +606,6 @@ impl FileBlock {
 
         let name_size = read_u16(reader)? as usize;
 
-        // TODO map the attributes
         let attributes = read_u32(reader)?;
 
         let (packed_data_size, unpacked_data_size) = if flags.has_large_size() {
@@ -384,7 +620,7 @@ impl FileBlock {
             (low_packed_data_size, low_unpacked_data_size)
         };
 
-        let file_name = read_vec(reader, name_size)?;
+        let file_name = read_vec(reader, limits.name_size(name_size as u64)?)?;
 
         let file_name = if flags.has_unicode_filename() {
             Filename::Unicode(decode_file_name(file_name))
@@ -398,6 +634,12 @@ impl FileBlock {
             Filename::Oem(file_name)
         };
 
+        let comment = if flags.has_comment() {
+            Some(EmbeddedComment::read(reader)?)
+        } else {
+            None
+        };
+
         let salt = if flags.has_salt() {
             Some(read_const_bytes(reader)?)
         } else {
@@ -409,7 +651,11 @@ impl FileBlock {
         let mut archive_time = None;
 
         if flags.has_extended_time() {
-            let ext = ExtendedTime::read(reader, modification_time)?;
+            let remaining = header_end.saturating_sub(reader.stream_position()?);
+            let ext = ExtendedTime::read(
+                &mut io::Read::take(&mut *reader, remaining),
+                modification_time,
+            )?;
 
             modification_time = ext.modification_time;
             creation_time = ext.creation_time;
@@ -432,12 +678,52 @@ impl FileBlock {
             attributes,
             file_name,
             salt,
+            comment,
         })
     }
+
+    /// Entry is a directory.
+    pub fn is_directory(&self) -> bool {
+        self.flags.0 & Self::DIRECTORY_MASK == Self::DIRECTORY_MASK
+    }
+
+    /// Size of the dictionary needed to decompress the file.
+    pub fn dictionary_size(&self) -> u64 {
+        0x1_0000 << ((self.flags.0 & Self::DIRECTORY_MASK) >> 5)
+    }
+
+    /// Name of the file as a relative path which can't escape the directory it's extracted
+    /// to, with `..` components, leading separators and drive prefixes removed.
+    ///
+    /// OEM names are converted to UTF-8, replacing the characters outside the ASCII range.
+    #[cfg(feature = "std")]
+    pub fn sanitized_path(&self) -> std::path::PathBuf {
+        crate::path::sanitize_path(&String::from_utf8_lossy(self.file_name.as_bytes()))
+    }
+
+    /// Attributes of the file, decoded according to [`Self::host_os`].
+    pub fn attributes(&self) -> FileAttributes {
+        match self.host_os {
+            HostOs::MsDos | HostOs::Os2 => {
+                FileAttributes::Dos(DosFileAttributes::new(self.attributes as u8))
+            }
+            HostOs::Win32 => FileAttributes::Windows(WindowsFileAttributes::new(self.attributes)),
+            HostOs::Unix | HostOs::BeOs => {
+                FileAttributes::Unix(UnixPermissions::new(self.attributes))
+            }
+            HostOs::MacOs | HostOs::Unknown(_) => FileAttributes::Unknown(self.attributes as u64),
+        }
+    }
+}
+
+impl Deref for FileBlock {
+    type Target = FileBlockFlags;
+
+    fn deref(&self) -> &Self::Target {
+        &self.flags
+    }
 }
 
-// TODO the service block has basically the same subheads
-// found in SubBlock, so we should parse them accordingly.
 #[derive(Debug)]
 /// Block containing metadata for the previons file block.
 pub struct ServiceBlock {
@@ -480,13 +766,32 @@ pub struct ServiceBlock {
     /// Concrete type of this service block.
     pub kind: ServiceBlockKind,
 
-    // TODO parse
+    /// Data specific to the type of the block stored in the header, which is parsed into
+    /// [`Self::kind`] for the Unix owner and NTFS stream blocks.
     pub sub_data: Option<Vec<u8>>,
 
     // TODO document this
     pub salt: Option<[u8; 8]>,
 }
 
+serialize_struct!(ServiceBlock {
+    flags,
+    packed_data_size,
+    unpacked_data_size,
+    host_os,
+    data_crc32,
+    modification_time,
+    creation_time,
+    access_time,
+    archive_time,
+    unpack_version,
+    method,
+    sub_flags,
+    kind,
+    sub_data,
+    salt,
+});
+
 flags! {
     /// [`ServiceBlock`] flags.
     pub struct ServiceBlockFlags(u16) {
@@ -542,11 +847,16 @@ impl ServiceBlockType {
 
 #[derive(Debug)]
 /// Concrete service block type.
+///
+/// The NTFS permissions and the extended attributes are only stored in the data area, and
+/// don't store anything in [`ServiceBlock::sub_data`].
 pub enum ServiceBlockKind {
     Comment,
     NtfsFilePermissions,
-    NtfsAlternateDataStream,
-    UnixOwner,
+    NtfsAlternateDataStream(NtfsStreamServiceBlock),
+    UnixOwner(UnixOwnerServiceBlock),
+    /// Authenticity verification information of RAR 3.x, which is stored encrypted in the
+    /// data area like in the [`AvBlock`] of RAR 2.x.
     AuthenticationVerification,
     RecoveryRecord,
     Os2ExtendedAttributes,
@@ -554,6 +864,84 @@ pub enum ServiceBlockKind {
     Unknown(Vec<u8>),
 }
 
+serialize_enum!(ServiceBlockKind {
+    Comment,
+    NtfsFilePermissions,
+    NtfsAlternateDataStream(value),
+    UnixOwner(value),
+    AuthenticationVerification,
+    RecoveryRecord,
+    Os2ExtendedAttributes,
+    BeOsExtendedAttributes,
+    Unknown(value),
+});
+
+impl ServiceBlockKind {
+    fn new(kind: Vec<u8>, sub_data: &[u8]) -> Self {
+        match ServiceBlockType::from_bytes(&kind) {
+            Some(ServiceBlockType::Comment) => Self::Comment,
+            Some(ServiceBlockType::NtfsFilePermissions) => Self::NtfsFilePermissions,
+            Some(ServiceBlockType::NtfsAlternateDataStream) => {
+                Self::NtfsAlternateDataStream(NtfsStreamServiceBlock::parse(sub_data))
+            }
+            Some(ServiceBlockType::UnixOwner) => {
+                Self::UnixOwner(UnixOwnerServiceBlock::parse(sub_data))
+            }
+            Some(ServiceBlockType::AuthenticationVerification) => Self::AuthenticationVerification,
+            Some(ServiceBlockType::RecoveryRecord) => Self::RecoveryRecord,
+            Some(ServiceBlockType::Os2ExtendedAttributes) => Self::Os2ExtendedAttributes,
+            Some(ServiceBlockType::BeOsExtendedAttributes) => Self::BeOsExtendedAttributes,
+            None => Self::Unknown(kind),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Owner of the previous file block, stored by RAR 3.x and later.
+pub struct UnixOwnerServiceBlock {
+    /// Name of the user owning the file.
+    pub user: Vec<u8>,
+
+    /// Name of the group owning the file.
+    pub group: Vec<u8>,
+}
+
+serialize_struct!(UnixOwnerServiceBlock { user, group });
+
+impl UnixOwnerServiceBlock {
+    /// The names are stored as NUL-terminated strings, but the NUL after the group may be
+    /// missing.
+    fn parse(sub_data: &[u8]) -> Self {
+        let mut names = sub_data.splitn(3, |&b| b == 0);
+        let user = names.next().unwrap_or_default().to_vec();
+        let group = names.next().unwrap_or_default().to_vec();
+
+        UnixOwnerServiceBlock { user, group }
+    }
+}
+
+#[derive(Debug)]
+/// NTFS alternate data stream of the previous file block, stored by RAR 3.x and later.
+pub struct NtfsStreamServiceBlock {
+    /// UTF-16 name of the stream, starting with a `:`.
+    pub stream_name: Vec<u16>,
+}
+
+serialize_struct!(NtfsStreamServiceBlock { stream_name });
+
+impl NtfsStreamServiceBlock {
+    /// The name is stored as UTF-16LE and may be terminated by a NUL.
+    fn parse(sub_data: &[u8]) -> Self {
+        let stream_name = sub_data
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .take_while(|&unit| unit != 0)
+            .collect();
+
+        NtfsStreamServiceBlock { stream_name }
+    }
+}
+
 impl ServiceBlock {
     const SIZE: usize = 32;
     const SALT_SIZE: usize = 8;
@@ -562,9 +950,13 @@ impl ServiceBlock {
         reader: &mut R,
         flags: u16,
         header_size: u16,
+        limits: &Limits,
     ) -> io::Result<Self> {
         let flags = ServiceBlockFlags::new(flags);
 
+        // The 7 bytes of the common fields of the block have already been read.
+        let header_end = reader.stream_position()?.saturating_sub(7) + header_size as u64;
+
         let low_packed_data_size = read_u32(reader)? as u64;
         let low_unpacked_data_size = read_u32(reader)? as u64;
         let host_os = read_u8(reader)?.into();
@@ -591,37 +983,19 @@ impl ServiceBlock {
             (low_packed_data_size, low_unpacked_data_size)
         };
 
-        let kind = read_vec(reader, name_size)?;
-        let kind = match ServiceBlockType::from_bytes(&kind) {
-            Some(ServiceBlockType::Comment) => ServiceBlockKind::Comment,
-            Some(ServiceBlockType::NtfsFilePermissions) => ServiceBlockKind::NtfsFilePermissions,
-            Some(ServiceBlockType::NtfsAlternateDataStream) => {
-                ServiceBlockKind::NtfsAlternateDataStream
-            }
-            Some(ServiceBlockType::UnixOwner) => ServiceBlockKind::UnixOwner,
-            Some(ServiceBlockType::AuthenticationVerification) => {
-                ServiceBlockKind::AuthenticationVerification
-            }
-            Some(ServiceBlockType::RecoveryRecord) => ServiceBlockKind::RecoveryRecord,
-            Some(ServiceBlockType::Os2ExtendedAttributes) => {
-                ServiceBlockKind::Os2ExtendedAttributes
-            }
-            Some(ServiceBlockType::BeOsExtendedAttributes) => {
-                ServiceBlockKind::BeOsExtendedAttributes
-            }
-            None => ServiceBlockKind::Unknown(kind),
-        };
+        let kind = read_vec(reader, limits.name_size(name_size as u64)?)?;
 
         let sub_data_size = (header_size as usize)
-            - name_size
-            - Self::SIZE
-            - if flags.has_salt() { Self::SALT_SIZE } else { 0 };
+            .checked_sub(name_size + Self::SIZE)
+            .and_then(|size| size.checked_sub(if flags.has_salt() { Self::SALT_SIZE } else { 0 }))
+            .ok_or(Error::CorruptHeader)?;
 
         let sub_data = if sub_data_size > 0 {
-            Some(read_vec(reader, sub_data_size)?)
+            Some(read_vec(reader, limits.record_size(sub_data_size as u64)?)?)
         } else {
             None
         };
+        let kind = ServiceBlockKind::new(kind, sub_data.as_deref().unwrap_or_default());
 
         let salt = if flags.has_salt() {
             Some(read_const_bytes(reader)?)
@@ -634,7 +1008,11 @@ impl ServiceBlock {
         let mut archive_time = None;
 
         if flags.has_extended_time() {
-            let ext = ExtendedTime::read(reader, modification_time)?;
+            let remaining = header_end.saturating_sub(reader.stream_position()?);
+            let ext = ExtendedTime::read(
+                &mut io::Read::take(&mut *reader, remaining),
+                modification_time,
+            )?;
 
             modification_time = ext.modification_time;
             creation_time = ext.creation_time;
@@ -693,12 +1071,22 @@ pub struct CommentBlock {
     // TODO enumerate these
     pub method: u8,
 
-    /// CRC15t6 hash of the comment.
-    // TODO before or after decompression?
+    /// Low 16 bits of the CRC32 of the comment after decompression.
     pub crc16: u16,
 }
 
+serialize_struct!(CommentBlock {
+    unpacked_data_size,
+    unpack_version,
+    method,
+    crc16,
+});
+
 impl CommentBlock {
+    /// Size of the window used to compress comments.
+    #[cfg(feature = "std")]
+    pub(super) const WINDOW_SIZE: usize = 0x1_0000;
+
     fn read<R: io::Read + io::Seek>(reader: &mut R, _flags: u16) -> io::Result<Self> {
         let unpacked_data_size = read_u16(reader)?;
         let unpack_version = read_u8(reader)?;
@@ -714,6 +1102,76 @@ impl CommentBlock {
     }
 }
 
+#[derive(Debug)]
+/// Old-style comment embedded in the header of a file block, which is stored as a whole
+/// comment block followed by its compressed data.
+pub struct EmbeddedComment {
+    pub header: CommentBlock,
+
+    /// Data of the comment before decompression.
+    pub packed_data: Vec<u8>,
+}
+
+serialize_struct!(EmbeddedComment {
+    header,
+    packed_data,
+});
+
+impl EmbeddedComment {
+    /// Size of the header of the comment block, including the common fields.
+    const HEADER_SIZE: u16 = 13;
+
+    fn read<R: io::Read + io::Seek>(reader: &mut R) -> io::Result<Self> {
+        let _header_crc16 = read_u16(reader)?;
+        let _block_type = read_u8(reader)?;
+        let flags = read_u16(reader)?;
+        let header_size = read_u16(reader)?;
+        let header = CommentBlock::read(reader, flags)?;
+
+        let data_size = header_size.checked_sub(Self::HEADER_SIZE).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "comment header is too small")
+        })?;
+        let packed_data = read_vec(reader, data_size as usize)?;
+
+        Ok(EmbeddedComment {
+            header,
+            packed_data,
+        })
+    }
+
+    /// Size of the comment in the header of the block it's embedded in.
+    pub(super) fn size(&self) -> usize {
+        Self::HEADER_SIZE as usize + self.packed_data.len()
+    }
+
+    /// Decompress the comment and check it against [`CommentBlock::crc16`].
+    ///
+    /// The text of the comment is encoded with the OEM code page.
+    ///
+    /// Returns an [`io::ErrorKind::InvalidData`] error if the CRC doesn't match, and an
+    /// [`io::ErrorKind::Unsupported`] error if the comment was compressed with an unknown
+    /// version of the algorithm.
+    #[cfg(feature = "std")]
+    pub fn unpack(&self) -> io::Result<Vec<u8>> {
+        use crate::{hash::Crc16, io::Read as _};
+
+        let mut data = vec![];
+        super::Decoder::for_comment(self.packed_data.as_slice(), &self.header)?
+            .read_to_end(&mut data)?;
+
+        let mut crc = Crc16::new();
+        crc.update(&data);
+        if crc.finalize() != self.header.crc16 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "CRC of the comment doesn't match",
+            ));
+        }
+
+        Ok(data)
+    }
+}
+
 #[derive(Debug)]
 pub struct ProtectBlock {
     // TODO do we need flags?
@@ -724,6 +1182,14 @@ pub struct ProtectBlock {
     pub mark: [u8; Self::MARK_SIZE],
 }
 
+serialize_struct!(ProtectBlock {
+    data_size,
+    version,
+    recovery_sectors,
+    total_blocks,
+    mark,
+});
+
 impl ProtectBlock {
     const MARK_SIZE: usize = 8;
 
@@ -767,27 +1233,38 @@ pub struct UnixOwnerSubBlock {
     pub group: Vec<u8>,
 }
 
+serialize_struct!(UnixOwnerSubBlock { user, group });
+
 impl UnixOwnerSubBlock {
-    pub fn read<R: io::Read + io::Seek>(reader: &mut R) -> io::Result<Self> {
-        let user_size = read_u16(reader)?.clamp(0, NAME_MAX_SIZE - 1) as usize;
-        let group_size = read_u16(reader)?.clamp(0, NAME_MAX_SIZE - 1) as usize;
-        let user = read_vec(reader, user_size)?;
-        let group = read_vec(reader, group_size)?;
+    pub fn read<R: io::Read + io::Seek>(reader: &mut R, limits: &Limits) -> io::Result<Self> {
+        let user_size = read_u16(reader)?.clamp(0, NAME_MAX_SIZE - 1);
+        let group_size = read_u16(reader)?.clamp(0, NAME_MAX_SIZE - 1);
+        let user = read_vec(reader, limits.name_size(user_size as u64)?)?;
+        let group = read_vec(reader, limits.name_size(group_size as u64)?)?;
 
         Ok(UnixOwnerSubBlock { user, group })
     }
 }
 
 #[derive(Debug)]
+/// Classic Mac OS type and creator codes of the previous file block.
 pub struct MacOsInfoSubBlock {
-    pub file_type: u16,
-    pub file_creator: u16,
+    /// Four-character type code, with the first character in the most significant byte.
+    pub file_type: u32,
+
+    /// Four-character creator code, with the first character in the most significant byte.
+    pub file_creator: u32,
 }
 
+serialize_struct!(MacOsInfoSubBlock {
+    file_type,
+    file_creator,
+});
+
 impl MacOsInfoSubBlock {
     pub fn read<R: io::Read + io::Seek>(reader: &mut R) -> io::Result<Self> {
-        let file_type = read_u16(reader)?;
-        let file_creator = read_u16(reader)?;
+        let file_type = read_u32(reader)?;
+        let file_creator = read_u32(reader)?;
 
         Ok(MacOsInfoSubBlock {
             file_type,
@@ -805,6 +1282,14 @@ pub struct ExtendedAttributesSubBlock {
     pub extended_attributes_crc32: u32,
 }
 
+serialize_struct!(ExtendedAttributesSubBlock {
+    filesystem,
+    unpacked_data_size,
+    unpack_version,
+    method,
+    extended_attributes_crc32,
+});
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum ExtendedAttributesFs {
@@ -813,6 +1298,8 @@ pub enum ExtendedAttributesFs {
     Ntfs,
 }
 
+serialize_enum!(ExtendedAttributesFs { Os2, BeOs, Ntfs });
+
 impl ExtendedAttributesSubBlock {
     pub fn read<R: io::Read + io::Seek>(
         reader: &mut R,
@@ -842,14 +1329,22 @@ pub struct NtfsStreamSubBlock {
     pub stream_name: Vec<u8>,
 }
 
+serialize_struct!(NtfsStreamSubBlock {
+    unpacked_data_size,
+    unpack_version,
+    method,
+    stream_crc32,
+    stream_name,
+});
+
 impl NtfsStreamSubBlock {
-    pub fn read<R: io::Read + io::Seek>(reader: &mut R) -> io::Result<Self> {
+    pub fn read<R: io::Read + io::Seek>(reader: &mut R, limits: &Limits) -> io::Result<Self> {
         let unpacked_data_size = read_u32(reader)?;
         let unpack_version = read_u8(reader)?;
         let method = read_u8(reader)?;
         let stream_crc32 = read_u32(reader)?;
-        let stream_name_size = read_u16(reader)?.clamp(0, NAME_MAX_SIZE - 1) as usize;
-        let stream_name = read_vec(reader, stream_name_size)?;
+        let stream_name_size = read_u16(reader)?.clamp(0, NAME_MAX_SIZE - 1);
+        let stream_name = read_vec(reader, limits.name_size(stream_name_size as u64)?)?;
 
         Ok(NtfsStreamSubBlock {
             unpacked_data_size,
@@ -870,6 +1365,14 @@ pub enum SubBlockKind {
     Unknown(u16),
 }
 
+serialize_enum!(SubBlockKind {
+    UnixOwner(value),
+    MacOsInfo(value),
+    ExtendedAttributes(value),
+    NtfsStream(value),
+    Unknown(value),
+});
+
 #[derive(Debug)]
 pub struct SubBlock {
     pub data_size: u32,
@@ -877,15 +1380,25 @@ pub struct SubBlock {
     pub kind: SubBlockKind,
 }
 
+serialize_struct!(SubBlock {
+    data_size,
+    level,
+    kind,
+});
+
 impl SubBlock {
-    fn read<R: io::Read + io::Seek>(reader: &mut R, _flags: u16) -> io::Result<Self> {
+    fn read<R: io::Read + io::Seek>(
+        reader: &mut R,
+        _flags: u16,
+        limits: &Limits,
+    ) -> io::Result<Self> {
         let data_size = read_u32(reader)?;
         let sub_type = read_u16(reader)?;
         let level = read_u8(reader)?;
 
         let sub_block = match sub_type.into() {
             SubBlockType::UnixOwner => {
-                let sub_block = UnixOwnerSubBlock::read(reader)?;
+                let sub_block = UnixOwnerSubBlock::read(reader, limits)?;
                 SubBlockKind::UnixOwner(sub_block)
             }
             SubBlockType::MacOsInfo => {
@@ -908,7 +1421,7 @@ impl SubBlock {
                 SubBlockKind::ExtendedAttributes(sub_block)
             }
             SubBlockType::NtfsAlternateDataStream => {
-                let sub_block = NtfsStreamSubBlock::read(reader)?;
+                let sub_block = NtfsStreamSubBlock::read(reader, limits)?;
                 SubBlockKind::NtfsStream(sub_block)
             }
             SubBlockType::Unknown(_) => SubBlockKind::Unknown(sub_type),
@@ -922,51 +1435,109 @@ impl SubBlock {
     }
 }
 
-// TODO supposedly the creation_time is in DOS format
-// and the archive and user name sizes are used to read the archive and user name
-// later in the header, but we don't have much information about this block right now.
 #[derive(Debug)]
+/// Signature added by RAR 2.x to the archives created by registered copies, recording
+/// when and by whom the archive was created.
 pub struct SignBlock {
-    pub creation_time: u32,
+    /// DOS time of the creation of the archive.
+    pub creation_time: Result<time::PrimitiveDateTime, u32>,
     pub archive_name_size: u16,
     pub user_name_size: u16,
+
+    /// Name of the archive, or `None` if the names don't fit in the header.
+    pub archive_name: Option<Vec<u8>>,
+
+    /// Name of the user whose license created the archive, or `None` if the names don't
+    /// fit in the header.
+    pub user_name: Option<Vec<u8>>,
 }
 
+serialize_struct!(SignBlock {
+    creation_time,
+    archive_name_size,
+    user_name_size,
+    archive_name,
+    user_name,
+});
+
 impl SignBlock {
-    fn read<R: io::Read + io::Seek>(reader: &mut R, _flags: u16) -> io::Result<Self> {
+    fn read<R: io::Read + io::Seek>(
+        reader: &mut R,
+        _flags: u16,
+        header_end: u64,
+        limits: &Limits,
+    ) -> io::Result<Self> {
         let creation_time = read_u32(reader)?;
+        let creation_time = time_conv::parse_dos_datetime(creation_time).map_err(|_| creation_time);
         let archive_name_size = read_u16(reader)?;
         let user_name_size = read_u16(reader)?;
 
+        // The names follow the fixed fields.
+        let names_size = archive_name_size as u64 + user_name_size as u64;
+        let (archive_name, user_name) = if reader.stream_position()? + names_size <= header_end {
+            let archive_name = read_vec(reader, limits.name_size(archive_name_size as u64)?)?;
+            let user_name = read_vec(reader, limits.name_size(user_name_size as u64)?)?;
+            (Some(archive_name), Some(user_name))
+        } else {
+            (None, None)
+        };
+
         Ok(SignBlock {
             creation_time,
             archive_name_size,
             user_name_size,
+            archive_name,
+            user_name,
         })
     }
 }
 
 #[derive(Debug)]
+/// Authenticity verification information added by RAR 2.x, which was replaced by the
+/// [`ServiceBlockKind::AuthenticationVerification`] service block in RAR 3.x.
+///
+/// The information identifies the creator of the archive, but it's encrypted with a
+/// proprietary scheme of RARLAB, so it's kept as raw bytes.
 pub struct AvBlock {
     // TODO flags?
     pub unpack_version: u8,
     pub method: u8,
     pub av_version: u8,
     pub av_info_crc32: u32,
+
+    /// Encrypted information, which takes the rest of the header.
+    pub av_info: Vec<u8>,
 }
 
+serialize_struct!(AvBlock {
+    unpack_version,
+    method,
+    av_version,
+    av_info_crc32,
+    av_info,
+});
+
 impl AvBlock {
-    fn read<R: io::Read + io::Seek>(reader: &mut R, _flags: u16) -> io::Result<Self> {
+    fn read<R: io::Read + io::Seek>(
+        reader: &mut R,
+        _flags: u16,
+        header_end: u64,
+        limits: &Limits,
+    ) -> io::Result<Self> {
         let unpack_version = read_u8(reader)?;
         let method = read_u8(reader)?;
         let av_version = read_u8(reader)?;
         let av_info_crc32 = read_u32(reader)?;
 
+        let av_info_size = header_end.saturating_sub(reader.stream_position()?);
+        let av_info = read_vec(reader, limits.record_size(av_info_size)?)?;
+
         Ok(AvBlock {
             unpack_version,
             method,
             av_version,
             av_info_crc32,
+            av_info,
         })
     }
 }
@@ -987,6 +1558,12 @@ pub struct EndArchiveBlock {
     pub volume_number: Option<u16>,
 }
 
+serialize_struct!(EndArchiveBlock {
+    flags,
+    archive_data_crc32,
+    volume_number,
+});
+
 flags! {
     /// [`EndArchiveBlock`] flags.
     pub struct EndArchiveBlockFlags(u16) {
@@ -1053,6 +1630,12 @@ pub struct UnknownBlock {
     pub data_size: Option<u32>,
 }
 
+serialize_struct!(UnknownBlock {
+    tag,
+    flags,
+    data_size,
+});
+
 impl UnknownBlock {
     fn read<R: io::Read + io::Seek>(reader: &mut R, flags: u16, tag: u8) -> io::Result<Self> {
         let flags = CommonFlags::new(flags);
@@ -1079,3 +1662,17 @@ impl Deref for UnknownBlock {
         &self.flags
     }
 }
+
+/// Flags and enums defined in this module, listed in [`crate::schema::MODULES`].
+pub(crate) const SCHEMA: crate::schema::ModuleSchema = crate::schema::ModuleSchema {
+    name: "rawrxd::rar15",
+    flags: &[
+        CommonFlags::SCHEMA,
+        MainBlockFlags::SCHEMA,
+        FileBlockFlags::SCHEMA,
+        ServiceBlockFlags::SCHEMA,
+        SubHeadFlags::SCHEMA,
+        EndArchiveBlockFlags::SCHEMA,
+    ],
+    enums: &[HostOs::SCHEMA, SubBlockType::SCHEMA],
+};
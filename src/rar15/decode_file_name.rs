@@ -1,3 +1,5 @@
+use alloc::{string::String, vec::Vec};
+
 pub fn decode_file_name(mut file_name: Vec<u8>) -> Result<String, Vec<u8>> {
     let split_off_index = match file_name.iter().position(|c| c == &0) {
         // Nothing after the 0 byte
@@ -39,6 +41,10 @@ pub fn decode_file_name(mut file_name: Vec<u8>) -> Result<String, Vec<u8>> {
 ///
 /// This structure repeats until the end of the bytecode section, which signals the end
 /// of the string.
+///
+/// Each instruction outputs a UTF-16 code unit, so characters outside of the Basic
+/// Multilingual Plane take two instructions, one for each surrogate. The name section is
+/// indexed by the number of code units output so far.
 fn decode_rar_encoded_string(file_name: &[u8], split_off_index: usize) -> Option<String> {
     let (original_filename, bytecode) = file_name.split_at(split_off_index);
     let mut bytecode = bytecode[1..].iter().copied().peekable();
@@ -178,3 +184,20 @@ fn test_decode_file_name_with_0_byte_end() {
 
     assert_eq!(decode_file_name(file_name).unwrap(), "test.rar");
 }
+
+#[test]
+fn test_decode_file_name_surrogate_pair() {
+    // "a😀.txt", with both surrogates of U+1F600 stored as two bytes.
+    let file_name = b"a?.txt\x00\x00\x28a\x3d\xd8\x00\xde.\x00txt".to_vec();
+    assert_eq!(decode_file_name(file_name).unwrap(), "a\u{1f600}.txt");
+
+    // The high surrogate stored with the high byte.
+    let file_name = b"?\x00\xd8\x60\x3d\x00\xde".to_vec();
+    assert_eq!(decode_file_name(file_name).unwrap(), "\u{1f600}");
+}
+
+#[test]
+fn test_decode_file_name_unpaired_surrogate() {
+    let file_name = b"a?\x00\xd8\x10a\x3d".to_vec();
+    assert_eq!(decode_file_name(file_name.clone()), Err(file_name));
+}
@@ -0,0 +1,374 @@
+use std::io;
+
+use crate::{
+    hash::Crc32,
+    unpack::{Unpack15, Unpack20, Unpack29},
+};
+
+use super::{CommentBlock, FileBlock, SubBlock, SubBlockKind};
+
+enum Inner<R> {
+    Store(R),
+    Unpack15(Box<Unpack15<R>>),
+    Unpack20(Box<Unpack20<R>>),
+    Unpack29(Box<Unpack29<R>>),
+}
+
+/// Decompresses the data area of a [`FileBlock`] or of a [`SubBlock`].
+///
+/// The reader must be positioned at the start of the data area and should stop at its end,
+/// for example by wrapping it with [`io::Read::take`].
+///
+/// Once all the data has been read, the CRC32 stored in the header is checked against the
+/// decompressed data, and a mismatch is reported as an [`io::ErrorKind::InvalidData`] error.
+pub struct Decoder<R> {
+    inner: Inner<R>,
+
+    /// Number of bytes left to read.
+    remaining: u64,
+
+    /// Hasher and expected CRC32, unless the check was given up by seeking.
+    crc32: Option<(Crc32, u32)>,
+
+    buf: Vec<u8>,
+    buf_pos: usize,
+    done: bool,
+}
+
+impl<R: io::Read> Inner<R> {
+    fn new(
+        reader: R,
+        method: u8,
+        unpack_version: u8,
+        window_size: usize,
+        size: u64,
+    ) -> io::Result<Self> {
+        if method == FileBlock::METHOD_STORE {
+            return Ok(Inner::Store(reader));
+        }
+
+        Self::unpack(reader, unpack_version, window_size, size)
+    }
+
+    /// Decompress data which is not stored.
+    fn unpack(reader: R, unpack_version: u8, window_size: usize, size: u64) -> io::Result<Self> {
+        Ok(match unpack_version {
+            15 => Inner::Unpack15(Box::new(Unpack15::new(reader, window_size, size))),
+            20 | 26 => Inner::Unpack20(Box::new(Unpack20::new(reader, window_size, size))),
+            29 => Inner::Unpack29(Box::new(Unpack29::new(reader, window_size, size))),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "unknown compression algorithm version",
+                ))
+            }
+        })
+    }
+}
+
+impl<R: io::Read> Decoder<R> {
+    /// Create a decoder for the data of `file` read from `reader`.
+    ///
+    /// Returns an [`io::ErrorKind::Unsupported`] error if the file is encrypted, is part of
+    /// a solid stream, or was compressed with an unknown version of the algorithm. Solid
+    /// files can be decoded with [`Decoder::continue_solid`].
+    pub fn new(reader: R, file: &FileBlock) -> io::Result<Self> {
+        let unsupported = |message| io::Error::new(io::ErrorKind::Unsupported, message);
+
+        if file.flags.is_encrypted() {
+            return Err(unsupported("encrypted files are not supported"));
+        }
+
+        if file.flags.is_solid() && file.method != FileBlock::METHOD_STORE {
+            return Err(unsupported(
+                "solid files must be decoded after the previous files of the stream",
+            ));
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            method = file.method,
+            unpack_version = file.unpack_version,
+            unpacked_size = file.unpacked_data_size,
+            "decoding file data"
+        );
+
+        // The window never needs to be larger than the file.
+        let window_size = file.unpacked_data_size.min(file.dictionary_size()) as usize;
+        let inner = Inner::new(
+            reader,
+            file.method,
+            file.unpack_version,
+            window_size,
+            file.unpacked_data_size,
+        )?;
+
+        Ok(Self::with_inner(
+            inner,
+            file.unpacked_data_size,
+            file.file_crc32,
+        ))
+    }
+
+    /// Create a decoder for the data of an old-style comment read from `reader`.
+    ///
+    /// Comments are always compressed with a 64 KiB window. Their CRC is only 16 bits long,
+    /// so it's not checked by the decoder.
+    pub(super) fn for_comment(reader: R, comment: &CommentBlock) -> io::Result<Self> {
+        let size = comment.unpacked_data_size as u64;
+        let inner = Inner::new(
+            reader,
+            comment.method,
+            comment.unpack_version,
+            CommentBlock::WINDOW_SIZE,
+            size,
+        )?;
+
+        Ok(Self {
+            inner,
+            remaining: size,
+            crc32: None,
+            buf: vec![],
+            buf_pos: 0,
+            done: false,
+        })
+    }
+
+    /// Create a decoder for the data area of a RAR 2.x extended attributes or NTFS stream
+    /// sub block read from `reader`.
+    ///
+    /// The CRC32 stored in the header of the sub block is checked like the one of files.
+    /// Returns an [`io::ErrorKind::Unsupported`] error for the other kinds of sub blocks,
+    /// whose data is entirely stored in the header, or if the data was compressed with an
+    /// unknown version of the algorithm.
+    pub fn for_sub_block(reader: R, sub_block: &SubBlock) -> io::Result<Self> {
+        let (method, unpack_version, size, crc32) = match &sub_block.kind {
+            SubBlockKind::ExtendedAttributes(attributes) => (
+                attributes.method,
+                attributes.unpack_version,
+                attributes.unpacked_data_size,
+                attributes.extended_attributes_crc32,
+            ),
+            SubBlockKind::NtfsStream(stream) => (
+                stream.method,
+                stream.unpack_version,
+                stream.unpacked_data_size,
+                stream.stream_crc32,
+            ),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "sub block has no data area",
+                ))
+            }
+        };
+
+        if method == FileBlock::METHOD_STORE {
+            Ok(Self::with_inner(Inner::Store(reader), size as u64, crc32))
+        } else {
+            Self::for_compressed_data(reader, unpack_version, size as u64, crc32)
+        }
+    }
+
+    /// Create a decoder for `size` bytes of non-solid data compressed with the algorithm of
+    /// `unpack_version`, checked against `crc32`.
+    pub(crate) fn for_compressed_data(
+        reader: R,
+        unpack_version: u8,
+        size: u64,
+        crc32: u32,
+    ) -> io::Result<Self> {
+        // The data is not solid, so the window only needs to fit it, and it can't be larger
+        // than the largest dictionary.
+        let window_size = size.min(FileBlock::MAX_DICTIONARY_SIZE) as usize;
+        let inner = Inner::unpack(reader, unpack_version, window_size, size)?;
+        Ok(Self::with_inner(inner, size, crc32))
+    }
+
+    fn with_inner(inner: Inner<R>, size: u64, crc32: u32) -> Self {
+        Self {
+            inner,
+            remaining: size,
+            crc32: Some((Crc32::new(), crc32)),
+            buf: vec![],
+            buf_pos: 0,
+            done: false,
+        }
+    }
+
+    /// Continue decoding the solid stream with the next `file`.
+    ///
+    /// Solid files are compressed with the window left by the files before them, so they
+    /// must be decoded in order with the same decoder, starting from the first file of the
+    /// stream, which is not solid. [`Archive::solid_plan`](crate::compat::Archive::solid_plan)
+    /// lists the files to decode.
+    ///
+    /// The rest of the current file is decoded and checked first, then `seek` is called
+    /// with the reader to move it to the start of the data area of `file`.
+    ///
+    /// Returns an [`io::ErrorKind::InvalidInput`] error if `file` is not solid or if the
+    /// current file is stored, and an [`io::ErrorKind::Unsupported`] error if `file` is
+    /// encrypted or was compressed with a different version of the algorithm.
+    pub fn continue_solid(
+        &mut self,
+        file: &FileBlock,
+        seek: impl FnOnce(&mut R) -> io::Result<()>,
+    ) -> io::Result<()> {
+        self.skip(u64::MAX)?;
+
+        let invalid_input = |message| io::Error::new(io::ErrorKind::InvalidInput, message);
+        let unsupported = |message| io::Error::new(io::ErrorKind::Unsupported, message);
+
+        if !file.flags.is_solid() || file.method == FileBlock::METHOD_STORE {
+            return Err(invalid_input("file doesn't continue a solid stream"));
+        }
+
+        if file.flags.is_encrypted() {
+            return Err(unsupported("encrypted files are not supported"));
+        }
+
+        let window_size = file.dictionary_size().min(FileBlock::MAX_DICTIONARY_SIZE) as usize;
+        let size = file.unpacked_data_size;
+
+        match (&mut self.inner, file.unpack_version) {
+            (Inner::Store(_), _) => {
+                return Err(invalid_input("stored files aren't part of a solid stream"));
+            }
+            (Inner::Unpack15(unpack), 15) => {
+                seek(unpack.get_mut())?;
+                unpack.next_file(window_size, size);
+            }
+            (Inner::Unpack20(unpack), 20 | 26) => {
+                unpack.end_file()?;
+                seek(unpack.get_mut())?;
+                unpack.next_file(window_size, size);
+            }
+            (Inner::Unpack29(unpack), 29) => {
+                unpack.end_file()?;
+                seek(unpack.get_mut())?;
+                unpack.next_file(window_size, size);
+            }
+            _ => {
+                return Err(unsupported(
+                    "solid stream changes the version of the compression algorithm",
+                ));
+            }
+        }
+
+        self.remaining = size;
+        self.crc32 = Some((Crc32::new(), file.file_crc32));
+        self.buf.clear();
+        self.buf_pos = 0;
+        self.done = false;
+
+        Ok(())
+    }
+
+    /// Unwrap the reader of the data area.
+    pub fn into_inner(self) -> R {
+        match self.inner {
+            Inner::Store(reader) => reader,
+            Inner::Unpack15(unpack) => unpack.into_inner(),
+            Inner::Unpack20(unpack) => unpack.into_inner(),
+            Inner::Unpack29(unpack) => unpack.into_inner(),
+        }
+    }
+
+    fn read_data(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let max_len = buf
+            .len()
+            .min(self.remaining.try_into().unwrap_or(usize::MAX));
+        let buf = &mut buf[..max_len];
+
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.buf_pos == self.buf.len() {
+            self.buf.clear();
+            self.buf_pos = 0;
+
+            match &mut self.inner {
+                Inner::Store(reader) => return reader.read(buf),
+                Inner::Unpack15(unpack) => unpack.decode(&mut self.buf)?,
+                Inner::Unpack20(unpack) => unpack.decode(&mut self.buf)?,
+                Inner::Unpack29(unpack) => unpack.decode(&mut self.buf)?,
+            }
+        }
+
+        let n = buf.len().min(self.buf.len() - self.buf_pos);
+        buf[..n].copy_from_slice(&self.buf[self.buf_pos..self.buf_pos + n]);
+        self.buf_pos += n;
+        Ok(n)
+    }
+
+    fn verify(&mut self) -> io::Result<()> {
+        if self.remaining > 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        if let Some((hasher, expected)) = self.crc32.take() {
+            if hasher.finalize() != expected {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "CRC32 of the unpacked data doesn't match",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Skip the next `n` bytes of unpacked data and return the number of bytes skipped,
+    /// which is less than `n` only if the end of the data was reached.
+    ///
+    /// The data is decoded and discarded without allocating, so the CRC32 is still checked
+    /// when the end of the data is reached.
+    pub fn skip(&mut self, n: u64) -> io::Result<u64> {
+        io::copy(&mut io::Read::take(self, n), &mut io::sink())
+    }
+}
+
+impl<R: io::Read + io::Seek> Decoder<R> {
+    /// Skip the next `n` bytes of unpacked data like [`Self::skip`], but seek over the data
+    /// of stored files instead of reading it.
+    ///
+    /// The CRC32 of stored files can't be checked after seeking over some of the data.
+    pub fn seek_forward(&mut self, n: u64) -> io::Result<u64> {
+        let Inner::Store(reader) = &mut self.inner else {
+            return self.skip(n);
+        };
+
+        let n = n.min(self.remaining);
+        if n > 0 {
+            reader.seek(io::SeekFrom::Current(n as i64))?;
+            self.remaining -= n;
+            self.crc32 = None;
+        }
+
+        Ok(n)
+    }
+}
+
+impl<R: io::Read> io::Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let n = self.read_data(buf)?;
+
+        if n == 0 {
+            self.done = true;
+            self.verify()?;
+            return Ok(0);
+        }
+
+        self.remaining -= n as u64;
+        if let Some((hasher, _)) = &mut self.crc32 {
+            hasher.update(&buf[..n]);
+        }
+
+        Ok(n)
+    }
+}
@@ -34,12 +34,11 @@
 //! is the precision field from the bitfield.
 //! This increment is then left shifted by (3 - N) * 8 and added to
 //! the resulting timestamp.
+//!
+//! Like unrar, we stop at the end of the header if it is truncated before
+//! the fields declared by the flags, keeping the timestamps read so far.
 
-// TODO need to test this part on a real archive with this field.
-
-use std::io;
-
-use crate::{read::*, time_conv};
+use crate::{io, read::*, time_conv};
 
 #[derive(Debug)]
 pub struct ExtendedTime {
@@ -51,6 +50,13 @@ pub struct ExtendedTime {
     pub archive_time: Option<Result<time::PrimitiveDateTime, u32>>,
 }
 
+serialize_struct!(ExtendedTime {
+    modification_time,
+    creation_time,
+    access_time,
+    archive_time,
+});
+
 #[derive(Debug)]
 struct ExtendedTimeFlags(u8);
 
@@ -79,29 +85,41 @@ impl ExtendedTimeFlags {
 }
 
 impl ExtendedTime {
+    /// Read the extended time from `reader`, which must end at the end of the header.
+    ///
+    /// The timestamps whose fields are cut off by the end of the header are left as they
+    /// were before the extended time was read.
     pub fn read<R: io::Read>(
         reader: &mut R,
         modification_time: Result<time::PrimitiveDateTime, u32>,
     ) -> io::Result<Self> {
+        let mut ext = ExtendedTime {
+            modification_time,
+            creation_time: None,
+            access_time: None,
+            archive_time: None,
+        };
+
+        match ext.read_fields(reader) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(ext),
+            result => result.map(|_| ext),
+        }
+    }
+
+    fn read_fields<R: io::Read>(&mut self, reader: &mut R) -> io::Result<()> {
         let all_flags = read_u16(reader)?;
 
         // We don't need to read mtime because it's already been read before.
         let flags = ExtendedTimeFlags::shifted(all_flags, 3);
-        let modification_time = match (modification_time, flags.exists()) {
-            (Ok(t), true) => Ok(read_extended_time_increments(reader, t, flags)?),
-            (t, _) => t,
-        };
+        if let (Ok(t), true) = (self.modification_time, flags.exists()) {
+            self.modification_time = Ok(read_extended_time_increments(reader, t, flags)?);
+        }
 
-        let creation_time = read_extended_time(reader, ExtendedTimeFlags::shifted(all_flags, 2))?;
-        let access_time = read_extended_time(reader, ExtendedTimeFlags::shifted(all_flags, 1))?;
-        let archive_time = read_extended_time(reader, ExtendedTimeFlags::shifted(all_flags, 0))?;
+        self.creation_time = read_extended_time(reader, ExtendedTimeFlags::shifted(all_flags, 2))?;
+        self.access_time = read_extended_time(reader, ExtendedTimeFlags::shifted(all_flags, 1))?;
+        self.archive_time = read_extended_time(reader, ExtendedTimeFlags::shifted(all_flags, 0))?;
 
-        Ok(ExtendedTime {
-            modification_time,
-            creation_time,
-            access_time,
-            archive_time,
-        })
+        Ok(())
     }
 }
 
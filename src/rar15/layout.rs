@@ -0,0 +1,112 @@
+use alloc::vec::Vec;
+
+use crate::layout::{HeaderField, Layout};
+
+use super::{Block, CommonFlags};
+
+/// Split the raw bytes of an unencrypted block `header` found at `offset` into its fields,
+/// like the ones returned by [`RawHeaderReader::raw_header`](crate::RawHeaderReader::raw_header).
+///
+/// The fields of the common header, of the main, file, service, authenticity verification,
+/// signature and end of archive blocks are listed separately. The optional fields of files
+/// whose layout depends on their content, like the extended times and the embedded
+/// comments, and the bodies of the other blocks are listed as a single field. Bytes after the known fields are listed as
+/// `"unknown"`, and the list stops at the first field which doesn't fit in the header.
+pub fn header_layout(header: &[u8], offset: u64) -> Vec<HeaderField> {
+    let mut layout = Layout::new(header, offset);
+    read_fields(&mut layout);
+    layout.into_fields()
+}
+
+fn read_fields(layout: &mut Layout) -> Option<()> {
+    layout.hex_u16("header crc16")?;
+    let block_type = layout.field("header type", 1, |b| format!("{:#04x}", b[0]))?[0];
+    let flags = layout.hex_u16("flags")?;
+    layout.u16("header size")?;
+
+    match block_type {
+        Block::MAIN => {
+            layout.u16("high av offset")?;
+            layout.u32("low av offset")?;
+            if flags & 0x0200 != 0 {
+                layout.u8("encrypt version")?;
+            }
+        }
+        Block::FILE | Block::SERVICE => read_file_fields(layout, block_type, flags)?,
+        Block::AV => {
+            layout.u8("unpack version")?;
+            layout.field("method", 1, |b| format!("{:#04x}", b[0]))?;
+            layout.u8("av version")?;
+            layout.hex_u32("av info crc32")?;
+            layout.rest("av info")?;
+        }
+        Block::SIGN => {
+            layout.hex_u32("creation time")?;
+            let archive_name_size = layout.u16("archive name size")?;
+            let user_name_size = layout.u16("user name size")?;
+            layout.text("archive name", archive_name_size as usize)?;
+            layout.text("user name", user_name_size as usize)?;
+        }
+        Block::ENDARC => {
+            if flags & 0x0002 != 0 {
+                layout.hex_u32("archive data crc32")?;
+            }
+            if flags & 0x0008 != 0 {
+                layout.u16("volume number")?;
+            }
+        }
+        _ => {
+            if CommonFlags::new(flags).contains_data() {
+                layout.u32("data size")?;
+            }
+            layout.rest("body")?;
+        }
+    }
+
+    layout.rest("unknown");
+    Some(())
+}
+
+/// Fields of file and service blocks, which share the same layout.
+fn read_file_fields(layout: &mut Layout, block_type: u8, flags: u16) -> Option<()> {
+    layout.u32("low packed data size")?;
+    layout.u32("low unpacked data size")?;
+    layout.u8("host os")?;
+    layout.hex_u32("data crc32")?;
+    layout.hex_u32("modification time")?;
+    layout.u8("unpack version")?;
+    layout.field("method", 1, |b| format!("{:#04x}", b[0]))?;
+    let name_size = layout.u16("name size")?;
+    if block_type == Block::FILE {
+        layout.hex_u32("attributes")?;
+    } else {
+        layout.hex_u32("sub flags")?;
+    }
+    if flags & 0x0100 != 0 {
+        layout.u32("high packed data size")?;
+        layout.u32("high unpacked data size")?;
+    }
+    layout.text("name", name_size as usize)?;
+
+    let salt_size = if flags & 0x0400 != 0 { 8 } else { 0 };
+    if block_type == Block::SERVICE {
+        // Like the parser, the size of the data of the service doesn't account for the
+        // high sizes.
+        let sub_data_size =
+            (layout.pos() + layout.remaining()).saturating_sub(name_size as usize + 32 + salt_size);
+        if sub_data_size > 0 {
+            layout.bytes("sub data", sub_data_size)?;
+        }
+    } else if flags & 0x0008 != 0 {
+        layout.rest("embedded comment and following fields")?;
+    }
+
+    if salt_size > 0 {
+        layout.bytes("salt", salt_size)?;
+    }
+    if flags & 0x1000 != 0 {
+        layout.rest("extended time")?;
+    }
+
+    Some(())
+}
@@ -3,12 +3,33 @@
 //! RAR15 was introduced with RAR 1.50 in 1994 and was used up to version 4.20 in 2012.
 //! This version of the format has many revisions and "deprecated" fields and block types.
 
+#[cfg(feature = "async")]
+mod async_block_iterator;
 mod block_iterator;
 mod blocks;
 mod decode_file_name;
+#[cfg(feature = "std")]
+mod decoder;
+mod entry_iterator;
 mod extended_time;
+mod layout;
+mod parser;
+#[cfg(feature = "experimental-protect-record")]
+mod protect;
+mod round_trip;
+mod stream_block_iterator;
 
+#[cfg(feature = "async")]
+pub use async_block_iterator::AsyncBlockIterator;
 pub use block_iterator::*;
 pub use blocks::*;
+#[cfg(feature = "std")]
+pub use decoder::Decoder;
+pub use entry_iterator::*;
+pub use layout::header_layout;
+pub use parser::{Event, Parser};
+#[cfg(feature = "experimental-protect-record")]
+pub use protect::*;
+pub use stream_block_iterator::StreamBlockIterator;
 
 const NAME_MAX_SIZE: u16 = 1000;
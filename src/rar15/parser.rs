@@ -0,0 +1,73 @@
+use alloc::vec::Vec;
+
+use crate::{
+    io::window::Window,
+    parser::{self, Core},
+    warning::Warning,
+};
+
+use super::{Block, BlockIterator, ParseOptions};
+
+/// Event returned by a [`Parser`].
+pub type Event = parser::Event<Block>;
+
+#[derive(Debug)]
+/// Sans-io parser of the blocks of a RAR15 archive, see the [`parser`](crate::parser)
+/// module.
+pub struct Parser {
+    pub(super) core: Core<BlockIterator<Window>>,
+}
+
+impl Parser {
+    /// Create a [`Parser`] of the stream fed from `offset`, which must be the offset of the
+    /// beginning of a block.
+    pub fn new(offset: u64) -> Self {
+        Self::with_options(offset, ParseOptions::default())
+    }
+
+    /// Create a [`Parser`] of the stream fed from `offset` which parses blocks according
+    /// to `options`.
+    pub fn with_options(offset: u64, options: ParseOptions) -> Self {
+        let blocks = BlockIterator::with_options(Window::streaming(offset), offset, options)
+            .expect("a window can always be seeked");
+        Self {
+            core: Core::new(blocks, offset),
+        }
+    }
+
+    /// Create a [`Parser`] of the stream fed from `offset` which decrypts the block headers
+    /// with `password`, like [`BlockIterator::with_password`].
+    pub fn with_password(offset: u64, password: &str) -> Self {
+        let blocks = BlockIterator::with_password(Window::streaming(offset), offset, password)
+            .expect("a window can always be seeked");
+        Self {
+            core: Core::new(blocks, offset),
+        }
+    }
+
+    /// Feed the next bytes of the stream and return the events of the blocks whose header
+    /// is complete.
+    pub fn feed(&mut self, data: &[u8]) -> Vec<Event> {
+        self.core.feed(data)
+    }
+
+    /// Signal the end of the stream and return the remaining events.
+    pub fn finish(&mut self) -> Vec<Event> {
+        self.core.finish()
+    }
+
+    /// Offset in the stream of the next byte to feed.
+    pub fn position(&self) -> u64 {
+        self.core.position()
+    }
+
+    /// Warnings about the blocks parsed so far.
+    pub fn warnings(&self) -> &[Warning] {
+        self.core.blocks.warnings()
+    }
+
+    /// Take the warnings about the blocks parsed so far, leaving the list empty.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        self.core.blocks.take_warnings()
+    }
+}
@@ -0,0 +1,204 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{
+    hash::Crc16,
+    io::{self, Read as _},
+    read::read_u16,
+    size::BlockSize as _,
+};
+
+use super::{Block, BlockKind, ProtectBlock};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Data area of a [`ProtectBlock`], the recovery record of RAR 2.x.
+///
+/// The archive before the block is assumed to be split in sectors of [`Self::SECTOR_SIZE`]
+/// bytes, and the data area to store the CRC16 of each sector followed by the parity
+/// sectors, where each sector is XORed into the parity sector of its index modulo the
+/// number of parity sectors.
+///
+/// **Experimental**: the layout of the data area is undocumented and was guessed, without
+/// checking it against archives created by RAR, so the damage found by [`Self::validate`]
+/// can't be relied upon. The data area is only parsed if its size matches the sizes stored
+/// in the block.
+pub struct ProtectRecord {
+    /// Offset of the protect block in the file, where the protected sectors end.
+    pub block_offset: u64,
+
+    /// Offset of the data area in the file.
+    pub data_offset: u64,
+
+    /// CRC16 of each sector of the archive.
+    pub sector_crcs: Vec<u16>,
+
+    /// Parity sectors.
+    pub parity_sectors: Vec<[u8; Self::SECTOR_SIZE]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Damaged bytes found by [`ProtectRecord::validate`].
+pub struct DamagedRange {
+    /// Offsets of the damaged bytes in the file.
+    ///
+    /// Damaged sectors of the archive are reported whole, and so are the damaged entries
+    /// of the recovery record itself.
+    pub range: Range<u64>,
+
+    /// The bytes can be rebuilt from the rest of the archive and the recovery record,
+    /// because they are the only damaged sector of their parity sector.
+    pub recoverable: bool,
+}
+
+impl ProtectRecord {
+    /// Size of the sectors of the archive and of the parity sectors.
+    pub const SECTOR_SIZE: usize = 512;
+
+    /// Parse the `data` area of the protect `block`.
+    ///
+    /// Returns an [`io::ErrorKind::Unsupported`] error if `block` is not a protect block,
+    /// or an [`io::ErrorKind::InvalidData`] error if the size of the data area doesn't
+    /// match the number of sectors of the block.
+    pub fn parse(block: &Block, data: &[u8]) -> io::Result<Self> {
+        let protect = protect_block(block)?;
+        let crcs_size = protect.total_blocks as usize * 2;
+        let parity_size = protect.recovery_sectors as usize * Self::SECTOR_SIZE;
+        if protect.recovery_sectors == 0 || data.len() != crcs_size + parity_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "protect data doesn't match the number of sectors",
+            ));
+        }
+
+        let (mut crcs, parity) = data.split_at(crcs_size);
+        let sector_crcs = (0..protect.total_blocks)
+            .map(|_| read_u16(&mut crcs))
+            .collect::<io::Result<_>>()?;
+        let parity_sectors = parity
+            .chunks_exact(Self::SECTOR_SIZE)
+            .map(|sector| sector.try_into().unwrap())
+            .collect();
+
+        Ok(Self {
+            block_offset: block.offset(),
+            data_offset: block.offset() + block.header_size(),
+            sector_crcs,
+            parity_sectors,
+        })
+    }
+
+    /// Read the data area of the protect `block` from `reader` and parse it.
+    ///
+    /// Returns an [`io::ErrorKind::Unsupported`] error if `block` is not a protect block.
+    pub fn read<R: io::Read + io::Seek>(reader: &mut R, block: &Block) -> io::Result<Self> {
+        protect_block(block)?;
+
+        reader.seek(io::SeekFrom::Start(block.offset() + block.header_size()))?;
+        let mut data = vec![];
+        reader.take(block.data_size()).read_to_end(&mut data)?;
+        if (data.len() as u64) < block.data_size() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Self::parse(block, &data)
+    }
+
+    /// Check the sectors of the archive starting at `archive_offset`, the offset of its
+    /// signature, against the recovery record, and return the damaged ranges in order.
+    ///
+    /// Sectors whose CRC16 doesn't match are only reported as damaged if their parity
+    /// doesn't match either, otherwise it's their CRC16 in the data area which is damaged.
+    /// Parity sectors which don't match sectors whose CRC16 match are reported as damaged.
+    /// The bytes of the last sector from the protect block onwards, and the ones past the
+    /// end of the file, are read as zeros.
+    pub fn validate<R: io::Read + io::Seek>(
+        &self,
+        reader: &mut R,
+        archive_offset: u64,
+    ) -> io::Result<Vec<DamagedRange>> {
+        let sector_size = Self::SECTOR_SIZE as u64;
+        let mut parity = vec![[0; Self::SECTOR_SIZE]; self.parity_sectors.len()];
+        let mut crc_mismatches = vec![];
+
+        reader.seek(io::SeekFrom::Start(archive_offset))?;
+        let mut reader = reader.take(self.block_offset.saturating_sub(archive_offset));
+        for (index, &stored_crc) in self.sector_crcs.iter().enumerate() {
+            let mut sector = [0; Self::SECTOR_SIZE];
+            let mut filled = 0;
+            while filled < Self::SECTOR_SIZE {
+                match reader.read(&mut sector[filled..])? {
+                    0 => break,
+                    size => filled += size,
+                }
+            }
+
+            let mut crc = Crc16::new();
+            crc.update(&sector);
+            if crc.finalize() != stored_crc {
+                crc_mismatches.push(index);
+            }
+
+            for (p, s) in parity[index % self.parity_sectors.len()]
+                .iter_mut()
+                .zip(sector)
+            {
+                *p ^= s;
+            }
+        }
+
+        let parity_matches: Vec<_> = parity
+            .iter()
+            .zip(&self.parity_sectors)
+            .map(|(computed, stored)| computed == stored)
+            .collect();
+
+        let mut damaged = vec![];
+        for &index in &crc_mismatches {
+            let group = index % self.parity_sectors.len();
+            damaged.push(if parity_matches[group] {
+                let offset = self.data_offset + index as u64 * 2;
+                DamagedRange {
+                    range: offset..offset + 2,
+                    recoverable: true,
+                }
+            } else {
+                let offset = archive_offset + index as u64 * sector_size;
+                let group_damage = crc_mismatches
+                    .iter()
+                    .filter(|&&other| other % self.parity_sectors.len() == group)
+                    .count();
+                DamagedRange {
+                    range: offset..offset + sector_size,
+                    recoverable: group_damage == 1,
+                }
+            });
+        }
+
+        let parity_offset = self.data_offset + self.sector_crcs.len() as u64 * 2;
+        for (group, _) in parity_matches.iter().enumerate().filter(|(_, m)| !**m) {
+            if crc_mismatches
+                .iter()
+                .all(|&index| index % self.parity_sectors.len() != group)
+            {
+                let offset = parity_offset + group as u64 * sector_size;
+                damaged.push(DamagedRange {
+                    range: offset..offset + sector_size,
+                    recoverable: true,
+                });
+            }
+        }
+
+        damaged.sort_by_key(|damaged| damaged.range.start);
+        Ok(damaged)
+    }
+}
+
+fn protect_block(block: &Block) -> io::Result<&ProtectBlock> {
+    match &block.kind {
+        BlockKind::Protect(protect) => Ok(protect),
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "not a protect block",
+        )),
+    }
+}
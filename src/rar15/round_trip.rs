@@ -0,0 +1,227 @@
+use alloc::vec::Vec;
+
+use crate::{round_trip::HeaderWriter, time_conv};
+
+use super::{
+    Block, BlockKind, EndArchiveBlock, FileBlock, Filename, MainBlock, ServiceBlock,
+    ServiceBlockKind,
+};
+
+type Time = Result<time::PrimitiveDateTime, u32>;
+
+impl Block {
+    /// Serialize the header of the block back into the fields it was parsed from.
+    ///
+    /// Returns `None` for the blocks whose parsed fields don't keep enough to write the
+    /// header again: the blocks which don't keep their flags, the unknown blocks, the files
+    /// with an embedded comment and the files with a name in RAR's Unicode encoding.
+    pub(crate) fn serialize_header(&self) -> Option<HeaderWriter> {
+        let (block_type, flags) = match &self.kind {
+            BlockKind::Main(main) => (Self::MAIN, main.flags.bits()),
+            BlockKind::File(file) => (Self::FILE, file.flags.bits()),
+            BlockKind::Service(service) => (Self::SERVICE, service.flags.bits()),
+            BlockKind::EndArchive(end) => (Self::ENDARC, end.flags.bits()),
+            _ => return None,
+        };
+
+        let mut w = HeaderWriter::new();
+        w.u16(self.header_crc16);
+        w.u8(block_type);
+        w.u16(flags);
+        w.u16(self.header_size);
+
+        match &self.kind {
+            BlockKind::Main(main) => write_main(&mut w, main),
+            BlockKind::File(file) => write_file(&mut w, file)?,
+            BlockKind::Service(service) => write_service(&mut w, service),
+            BlockKind::EndArchive(end) => write_end_archive(&mut w, end),
+            _ => unreachable!("the other kinds of blocks returned early"),
+        }
+
+        Some(w)
+    }
+}
+
+fn write_main(w: &mut HeaderWriter, main: &MainBlock) {
+    let av_block_offset = main.av_block_offset.unwrap_or(0);
+    w.u16((av_block_offset >> 32) as u16);
+    w.u32(av_block_offset as u32);
+    if let Some(encrypt_version) = main.encrypt_version {
+        w.u8(encrypt_version);
+    }
+}
+
+fn write_file(w: &mut HeaderWriter, file: &FileBlock) -> Option<()> {
+    let name = match &file.file_name {
+        Filename::Ascii(name) => name.as_bytes(),
+        Filename::Unicode(Err(name)) | Filename::Oem(name) => name,
+        // The encoding of Unicode names can't be recovered from the decoded name.
+        Filename::Unicode(Ok(_)) => return None,
+    };
+    if file.comment.is_some() {
+        return None;
+    }
+
+    let times = [
+        Some(file.modification_time),
+        file.creation_time,
+        file.access_time,
+        file.archive_time,
+    ];
+    w.u32(file.packed_data_size as u32);
+    w.u32(file.unpacked_data_size as u32);
+    w.u8(file.host_os.into());
+    w.u32(file.file_crc32);
+    w.u32(dos_time(file.modification_time));
+    w.u8(file.unpack_version);
+    w.u8(file.method);
+    w.u16(name.len() as u16);
+    w.u32(file.attributes);
+    write_tail(
+        w,
+        file.flags.bits(),
+        [file.packed_data_size, file.unpacked_data_size],
+        name,
+        None,
+        file.salt,
+        times,
+    );
+
+    Some(())
+}
+
+fn write_service(w: &mut HeaderWriter, service: &ServiceBlock) {
+    let name: &[u8] = match &service.kind {
+        ServiceBlockKind::Comment => b"CMT",
+        ServiceBlockKind::NtfsFilePermissions => b"ACL",
+        ServiceBlockKind::NtfsAlternateDataStream(_) => b"STM",
+        ServiceBlockKind::UnixOwner(_) => b"UOW",
+        ServiceBlockKind::AuthenticationVerification => b"AV",
+        ServiceBlockKind::RecoveryRecord => b"RR",
+        ServiceBlockKind::Os2ExtendedAttributes => b"EA2",
+        ServiceBlockKind::BeOsExtendedAttributes => b"EABE",
+        ServiceBlockKind::Unknown(name) => name,
+    };
+
+    let times = [
+        Some(service.modification_time),
+        service.creation_time,
+        service.access_time,
+        service.archive_time,
+    ];
+    w.u32(service.packed_data_size as u32);
+    w.u32(service.unpacked_data_size as u32);
+    w.u8(service.host_os.into());
+    w.u32(service.data_crc32);
+    w.u32(dos_time(service.modification_time));
+    w.u8(service.unpack_version);
+    w.u8(service.method);
+    w.u16(name.len() as u16);
+    w.u32(service.sub_flags.bits());
+    write_tail(
+        w,
+        service.flags.bits(),
+        [service.packed_data_size, service.unpacked_data_size],
+        name,
+        service.sub_data.as_deref(),
+        service.salt,
+        times,
+    );
+}
+
+/// Write the fields after the attributes of file and service blocks, which share the same
+/// flags for them.
+fn write_tail(
+    w: &mut HeaderWriter,
+    flags: u16,
+    sizes: [u64; 2],
+    name: &[u8],
+    sub_data: Option<&[u8]>,
+    salt: Option<[u8; 8]>,
+    times: [Option<Time>; 4],
+) {
+    // The high 32 bits of the sizes.
+    if flags & 0x0100 != 0 {
+        w.u32((sizes[0] >> 32) as u32);
+        w.u32((sizes[1] >> 32) as u32);
+    }
+    w.bytes(name);
+    if let Some(sub_data) = sub_data {
+        w.bytes(sub_data);
+    }
+    if let Some(salt) = salt {
+        w.bytes(&salt);
+    }
+    // The extended time.
+    if flags & 0x1000 != 0 {
+        write_extended_time(w, times);
+    }
+}
+
+/// DOS time stored in the fixed fields, without the precision added by the extended time.
+fn dos_time(time: Time) -> u32 {
+    time.map_or_else(|raw| raw, time_conv::to_dos_datetime)
+}
+
+/// Write the extended time in the shortest encoding of each timestamp.
+///
+/// The modification time is always marked as present, because its flags can't be told
+/// apart from missing ones when it has no increments.
+fn write_extended_time(w: &mut HeaderWriter, times: [Option<Time>; 4]) {
+    let fields = times.map(|time| time.map(extended_time_field));
+
+    let mut all_flags = 0;
+    for (i, field) in fields.iter().enumerate() {
+        let flags = field.as_ref().map_or(0, |(flags, _, _)| *flags);
+        all_flags |= (flags as u16) << ((3 - i) * 4);
+    }
+    w.u16(all_flags);
+
+    for (i, (_, dos_time, increment)) in fields.into_iter().flatten().enumerate() {
+        // The DOS time of the modification time is stored in the fixed fields.
+        if i > 0 {
+            w.u32(dos_time);
+        }
+        w.bytes(&increment);
+    }
+}
+
+/// Flags, DOS time and increment in 100ns of the precise `time`.
+fn extended_time_field(time: Time) -> (u8, u32, Vec<u8>) {
+    const EXISTS: u8 = 0x8;
+    const ADD_SECOND: u8 = 0x4;
+
+    let Ok(time) = time else {
+        // The increments of invalid timestamps are not read.
+        return (EXISTS, dos_time(time), vec![]);
+    };
+
+    let dos_time = time_conv::to_dos_datetime(time);
+    let Ok(base) = time_conv::parse_dos_datetime(dos_time) else {
+        return (EXISTS, dos_time, vec![]);
+    };
+
+    let mut flags = EXISTS;
+    let mut remainder = time - base;
+    if remainder >= time::Duration::SECOND {
+        flags |= ADD_SECOND;
+        remainder -= time::Duration::SECOND;
+    }
+
+    // The increment is stored in its most significant bytes, dropping the zero ones.
+    let hundred_nanos = (remainder.whole_nanoseconds() / 100) as u32;
+    let bytes = hundred_nanos.to_le_bytes();
+    let size = 3 - bytes[..3].iter().take_while(|&&b| b == 0).count();
+    flags |= size as u8;
+
+    (flags, dos_time, bytes[3 - size..3].to_vec())
+}
+
+fn write_end_archive(w: &mut HeaderWriter, end: &EndArchiveBlock) {
+    if let Some(crc32) = end.archive_data_crc32 {
+        w.u32(crc32);
+    }
+    if let Some(volume_number) = end.volume_number {
+        w.u16(volume_number);
+    }
+}
@@ -0,0 +1,58 @@
+use alloc::vec::Vec;
+
+use crate::{error::RarResult, io, io::window::Window, parser::Streamed, warning::Warning};
+
+use super::{Block, BlockIterator, ParseOptions, Parser};
+
+#[derive(Debug)]
+/// Iterator over the blocks of a RAR15 file which reads the stream forward only.
+///
+/// Unlike [`BlockIterator`], the reader doesn't need to implement [`io::Seek`]: the data
+/// areas of the blocks are read and discarded, so archives can be listed while they are
+/// received from a pipe or a socket.
+pub struct StreamBlockIterator<R: io::Read> {
+    inner: Streamed<R, BlockIterator<Window>, Block>,
+}
+
+impl<R: io::Read> StreamBlockIterator<R> {
+    /// Create a [`StreamBlockIterator`] over a stream whose next byte is at `offset` in the
+    /// archive, which must be the offset of the beginning of a block.
+    pub fn new(reader: R, offset: u64) -> Self {
+        Self::from_parser(reader, Parser::new(offset))
+    }
+
+    /// Create a [`StreamBlockIterator`] which parses blocks according to `options`.
+    pub fn with_options(reader: R, offset: u64, options: ParseOptions) -> Self {
+        Self::from_parser(reader, Parser::with_options(offset, options))
+    }
+
+    /// Create a [`StreamBlockIterator`] which decrypts the block headers with `password`,
+    /// like [`BlockIterator::with_password`].
+    pub fn with_password(reader: R, offset: u64, password: &str) -> Self {
+        Self::from_parser(reader, Parser::with_password(offset, password))
+    }
+
+    fn from_parser(reader: R, parser: Parser) -> Self {
+        Self {
+            inner: Streamed::new(reader, parser.core),
+        }
+    }
+
+    /// Warnings about the blocks read so far.
+    pub fn warnings(&self) -> &[Warning] {
+        self.inner.core.blocks.warnings()
+    }
+
+    /// Take the warnings about the blocks read so far, leaving the list empty.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        self.inner.core.blocks.take_warnings()
+    }
+}
+
+impl<R: io::Read> Iterator for StreamBlockIterator<R> {
+    type Item = RarResult<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
@@ -0,0 +1,63 @@
+use futures_io::{AsyncRead, AsyncSeek};
+
+use crate::{
+    error::RarResult,
+    io::window::{self, Window},
+    warning::Warning,
+};
+
+use super::{Block, BlockIterator, ParseOptions};
+
+#[derive(Debug)]
+/// Asynchronous version of [`BlockIterator`].
+///
+/// Wraps a reader implementing [`AsyncRead`] and [`AsyncSeek`] and fetches the headers of
+/// the blocks in chunks, skipping over their data areas. The QuickOpen cache is only used
+/// if it was fetched along with the main block.
+pub struct AsyncBlockIterator<R> {
+    reader: R,
+    blocks: BlockIterator<Window>,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncBlockIterator<R> {
+    /// Create an [`AsyncBlockIterator`] starting at `offset`.
+    pub async fn new(reader: R, offset: u64) -> RarResult<Self> {
+        Self::with_options(reader, offset, ParseOptions::default()).await
+    }
+
+    /// Create an [`AsyncBlockIterator`] starting at `offset` which parses blocks according
+    /// to `options`.
+    pub async fn with_options(
+        mut reader: R,
+        offset: u64,
+        options: ParseOptions,
+    ) -> RarResult<Self> {
+        let file_size = window::stream_len(&mut reader).await?;
+        let blocks = BlockIterator::with_options(Window::new(file_size), offset, options)?;
+        Ok(Self { reader, blocks })
+    }
+
+    /// Create an [`AsyncBlockIterator`] starting at `offset` which decrypts the block headers
+    /// with `password`, like [`BlockIterator::with_password`].
+    pub async fn with_password(mut reader: R, offset: u64, password: &str) -> RarResult<Self> {
+        let file_size = window::stream_len(&mut reader).await?;
+        let blocks = BlockIterator::with_password(Window::new(file_size), offset, password)?;
+        Ok(Self { reader, blocks })
+    }
+
+    /// Read the next block, or return `None` after the EndOfArchive block or at the end of
+    /// the file.
+    pub async fn next(&mut self) -> Option<RarResult<Block>> {
+        window::next_block(&mut self.reader, &mut self.blocks, window::READ_AHEAD).await
+    }
+
+    /// Warnings about the blocks read so far.
+    pub fn warnings(&self) -> &[Warning] {
+        self.blocks.warnings()
+    }
+
+    /// Take the warnings about the blocks read so far, leaving the list empty.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        self.blocks.take_warnings()
+    }
+}
@@ -1,54 +1,603 @@
-use std::io;
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::ToString,
+    vec::Vec,
+};
+use core::ops::Range;
 
 use crate::{
+    credentials::{CredentialProvider, HeaderCredentials},
+    cross_check::{self, HeaderCursor},
+    crypt::{self, CbcDecryptor, Rar50Keys},
     error::{Error, RarResult},
+    hash::Crc32,
+    io::{
+        self,
+        buffered_header::BufferedHeader,
+        window::{Window, WindowedIterator},
+        Seek as _,
+    },
+    raw_header::RawHeaderReader,
+    read::{read_const_bytes, read_u32, read_vint},
+    round_trip, salvage,
     size::BlockSize as _,
+    warning::{self, BlockRanges, Divergence, Warning, WarningKind},
 };
 
-use super::{Block, BlockKind};
+use super::{Block, BlockKind, CryptBlock, LocatorRecord, MainBlock, ParseOptions, QuickOpenCache};
+
+/// State of the encryption of the block headers.
+#[derive(Debug)]
+enum HeaderEncryption {
+    /// No CryptBlock was found, so the headers are in plaintext.
+    None,
+
+    /// A CryptBlock was found, but the key is derived only once the next block is read.
+    Pending {
+        kdf_count: u8,
+        salt: [u8; 16],
+        check_value: Option<[u8; 12]>,
+    },
+
+    /// The headers following the CryptBlock are encrypted with this key.
+    Key {
+        key: [u8; 32],
+
+        /// The password was verified with the check value of the CryptBlock.
+        password_checked: bool,
+    },
+}
 
 #[derive(Debug)]
 pub struct BlockIterator<R: io::Read + io::Seek> {
     reader: R,
     file_size: u64,
     next_offset: u64,
+    /// Buffer reused to read each header, so that reading a block doesn't allocate one.
+    header_buffer: Vec<u8>,
+    ranges: BlockRanges,
+    warnings: Vec<Warning>,
+
+    /// Absolute offsets of the blocks referenced by the locator record and by the
+    /// QuickOpen cache.
+    locator_targets: BTreeSet<u64>,
     end_of_archive_reached: bool,
+    /// Flag of the end of archive block, once it was read.
+    has_next_volume: Option<bool>,
+    options: ParseOptions,
+
+    credentials: HeaderCredentials,
+    encryption: HeaderEncryption,
+
+    /// Headers read from the QuickOpen cache, by offset.
+    quick_open: BTreeMap<u64, Vec<u8>>,
 }
 
 impl<R: io::Read + io::Seek> BlockIterator<R> {
-    pub fn new(mut reader: R, offset: u64) -> RarResult<Self> {
+    pub fn new(reader: R, offset: u64) -> RarResult<Self> {
+        Self::with_options(reader, offset, ParseOptions::default())
+    }
+
+    /// Create a [`BlockIterator`] starting at `offset` which parses blocks according
+    /// to `options`.
+    pub fn with_options(mut reader: R, offset: u64, options: ParseOptions) -> RarResult<Self> {
         let file_size = reader.seek(io::SeekFrom::End(0))?;
 
         Ok(Self {
             reader,
             file_size,
             next_offset: offset,
+            header_buffer: vec![],
+            ranges: BlockRanges::default(),
+            warnings: vec![],
+            locator_targets: BTreeSet::new(),
             end_of_archive_reached: false,
+            has_next_volume: None,
+            options,
+            credentials: HeaderCredentials::default(),
+            encryption: HeaderEncryption::None,
+            quick_open: BTreeMap::new(),
         })
     }
 
+    /// Create a [`BlockIterator`] starting at `offset` which decrypts the block headers
+    /// with `password` if the archive starts with a [`CryptBlock`].
+    ///
+    /// Without a password, the iterator stops at the first encrypted block with
+    /// [`Error::PasswordRequired`]. If the password doesn't match, it stops with
+    /// [`Error::IncorrectPassword`].
+    pub fn with_password(reader: R, offset: u64, password: &str) -> RarResult<Self> {
+        Self::with_credentials(reader, offset, password.to_string())
+    }
+
+    /// Create a [`BlockIterator`] starting at `offset` like
+    /// [`with_password`](Self::with_password), which asks `provider` for the password
+    /// when it reads the [`CryptBlock`].
+    pub fn with_credentials(
+        reader: R,
+        offset: u64,
+        provider: impl CredentialProvider + Send + 'static,
+    ) -> RarResult<Self> {
+        let mut iter = Self::new(reader, offset)?;
+        iter.credentials = HeaderCredentials::new(provider);
+        Ok(iter)
+    }
+
+    /// Warnings about the blocks read so far.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Take the warnings about the blocks read so far, leaving the list empty.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        core::mem::take(&mut self.warnings)
+    }
+
+    /// Whether the archive continues in the next volume, once the [`EndArchiveBlock`](super::EndArchiveBlock) was
+    /// read.
+    ///
+    /// Returns `None` before that, so an iterator which ended while this returns `None`
+    /// reached the end of the file or an error without finding the end of the archive.
+    pub fn has_next_volume(&self) -> Option<bool> {
+        self.has_next_volume
+    }
+
+    /// Range of the bytes between the end of the [`EndArchiveBlock`](super::EndArchiveBlock)
+    /// and the end of the file, once it was read.
+    ///
+    /// The range is empty if the file ends with the end of archive block. Returns `None`
+    /// before that, like [`has_next_volume`](Self::has_next_volume).
+    pub fn trailing_data(&self) -> Option<Range<u64>> {
+        self.has_next_volume?;
+        Some(self.next_offset..self.file_size)
+    }
+
+    /// Offset of the block which will be read next.
+    ///
+    /// After an error, this is still the offset of the block which failed, unless it was
+    /// skipped by salvaging.
+    pub fn next_offset(&self) -> u64 {
+        self.next_offset
+    }
+
+    /// Resume the iteration at `offset` of a file which grew to `file_size`, like a file
+    /// which is still being downloaded, without reading the blocks before it again.
+    ///
+    /// `offset` is usually the [`next_offset`](Self::next_offset) of the iterator once it
+    /// ended or failed to read a block truncated by the end of the file. The state of the
+    /// iterator is kept, like the key of the encrypted headers, so it must be an offset
+    /// in the same archive. Blocks which were already read are reported as overlapping
+    /// if they are read again.
+    pub fn resume_at(&mut self, offset: u64, file_size: u64) {
+        self.next_offset = offset;
+        self.file_size = file_size;
+        self.end_of_archive_reached = false;
+        self.has_next_volume = None;
+    }
+
+    /// Check that the offsets in the locator record and in the QuickOpen cache point to the
+    /// start of a block.
+    fn check_locator_targets(&mut self, block: &Block) {
+        if let BlockKind::Main(MainBlock {
+            locator: Some(locator),
+            ..
+        }) = &block.kind
+        {
+            // Locator offsets are relative to the start of the main block.
+            let targets = [
+                locator.quick_open_record_offset,
+                locator.recovery_record_offset,
+            ];
+            self.locator_targets
+                .extend(targets.into_iter().flatten().map(|o| block.offset + o));
+        }
+
+        let range = block.offset()..block.offset() + block.size();
+
+        for &target in self.locator_targets.range(range.start + 1..range.end) {
+            self.warnings.push(Warning {
+                offset: block.offset(),
+                kind: WarningKind::OffsetInsideBlock {
+                    target,
+                    block: range.clone(),
+                },
+            });
+        }
+    }
+
+    /// Parse `header` again with strict checks.
+    fn strict_parse(
+        header: &[u8],
+        offset: u64,
+        options: &ParseOptions,
+    ) -> Result<Block, Divergence> {
+        let mut cursor = HeaderCursor::new(header, offset);
+        let stored = read_u32(&mut cursor)?;
+
+        let mut crc = Crc32::new();
+        crc.update(&header[4..]);
+        let computed = crc.finalize();
+        if stored != computed {
+            return Err(Divergence::ChecksumMismatch { stored, computed });
+        }
+
+        cursor.seek(io::SeekFrom::Start(offset))?;
+        let block = Block::read_with_options(&mut cursor, options)?;
+
+        if !parses_whole_header(&block) {
+            return Ok(block);
+        }
+
+        let count = (header.len() as u64).saturating_sub(cursor.consumed());
+        if count > 0 {
+            return Err(Divergence::TrailingBytes { count });
+        }
+
+        Ok(block)
+    }
+
+    /// Find the offset of the first block header after `start` which passes the strict
+    /// parse.
+    fn find_next_header(&mut self, start: u64) -> io::Result<Option<u64>> {
+        let options = &self.options;
+        salvage::find_header(
+            &mut self.reader,
+            start,
+            self.file_size,
+            7,
+            |prefix| {
+                let (size, vint_size) = read_vint(&mut &prefix[4..]).ok()?;
+                let size = size.checked_add(4 + vint_size as u64)?;
+                options.limits.header_size(size).ok()
+            },
+            |header, offset| {
+                Self::strict_parse(header, offset, options)
+                    .is_ok_and(|block| !matches!(block.kind, BlockKind::Unknown(_)))
+            },
+        )
+    }
+
+    /// Skip the corrupt header at the next offset and read the first block after it which
+    /// can be read, returning `error` if there is none.
+    fn salvage(&mut self, error: Error) -> RarResult<Block> {
+        let corrupt_offset = self.next_offset;
+        let mut start = corrupt_offset + 1;
+
+        while let Some(offset) = self.find_next_header(start)? {
+            self.next_offset = offset;
+            match self.read_block() {
+                Ok(block) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(corrupt_offset, next_offset = offset, "skipped corrupt data");
+
+                    self.warnings.push(Warning {
+                        offset: corrupt_offset,
+                        kind: WarningKind::CorruptDataSkipped {
+                            range: corrupt_offset..offset,
+                        },
+                    });
+                    return Ok(block);
+                }
+                Err(e) if e.is_corrupt_data() => start = offset + 1,
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.end_of_archive_reached = true;
+        Err(error)
+    }
+
+    /// Derive the key of the encrypted headers from the password.
+    fn derive_key(&mut self) -> RarResult<()> {
+        let HeaderEncryption::Pending {
+            kdf_count,
+            salt,
+            check_value,
+        } = self.encryption
+        else {
+            return Ok(());
+        };
+
+        let Some(password) = self.credentials.password(self.next_offset) else {
+            return Err(Error::PasswordRequired);
+        };
+
+        let keys = Rar50Keys::derive(password, &salt, kdf_count).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "KDF iteration count is too large",
+            )
+        })?;
+
+        if let Some(check_value) = &check_value {
+            if !keys.is_password_correct(check_value) {
+                return Err(Error::IncorrectPassword);
+            }
+        }
+
+        self.encryption = HeaderEncryption::Key {
+            key: keys.key,
+            password_checked: check_value.is_some(),
+        };
+
+        Ok(())
+    }
+
+    /// Read a block whose header is encrypted.
+    ///
+    /// The header is preceded by the IV and padded to the AES block size, and both are
+    /// counted in the header size of the returned block.
+    fn read_encrypted_block(&mut self, key: &[u8; 32], password_checked: bool) -> RarResult<Block> {
+        let offset = self.next_offset;
+        let iv = read_const_bytes(&mut self.reader)?;
+        let mut decryptor = CbcDecryptor::new(key, iv);
+
+        // The first AES block contains the CRC32 and the size of the header.
+        let mut header = vec![0; crypt::BLOCK_SIZE];
+        self.reader.read_exact(&mut header)?;
+        decryptor.decrypt(&mut header);
+
+        let (size, vint_size) = read_vint(&mut &header[4..])?;
+        let full_header_size = self
+            .options
+            .limits
+            .header_size(size.saturating_add(vint_size as u64 + 4))?;
+        let padded_size = full_header_size
+            .checked_next_multiple_of(crypt::BLOCK_SIZE as u64)
+            .ok_or(Error::CorruptHeader)?;
+
+        if (offset + iv.len() as u64).saturating_add(padded_size) > self.file_size {
+            return Err(Error::CorruptHeader);
+        }
+
+        header.resize(padded_size as usize, 0);
+        self.reader.read_exact(&mut header[crypt::BLOCK_SIZE..])?;
+        decryptor.decrypt(&mut header[crypt::BLOCK_SIZE..]);
+        header.truncate(full_header_size as usize);
+
+        // Unless the password was verified, a wrong one is only noticed here.
+        let mut crc = Crc32::new();
+        crc.update(&header[4..]);
+        if read_u32(&mut &header[..4])? != crc.finalize() {
+            return Err(if password_checked {
+                Error::CorruptHeader
+            } else {
+                Error::IncorrectPassword
+            });
+        }
+
+        let mut block =
+            Block::read_with_options(&mut HeaderCursor::new(&header, offset), &self.options)?;
+        block.header_size = iv.len() as u64 + padded_size;
+
+        Ok(block)
+    }
+
+    /// Load the headers of the QuickOpen cache whose block is at `offset`, ignoring it if it
+    /// can't be read.
+    fn load_quick_open(&mut self, offset: u64) {
+        let cache = self
+            .reader
+            .seek(io::SeekFrom::Start(offset))
+            .and_then(|_| Block::read(&mut self.reader))
+            .and_then(|block| QuickOpenCache::read(&mut self.reader, &block));
+
+        let Ok(cache) = cache else {
+            return;
+        };
+
+        // The cached blocks are checked against each other here, and their offsets against
+        // the blocks in the archive as they are read.
+        let mut ranges = BlockRanges::default();
+        for cached in &cache.headers {
+            let Ok(block) = cached.block(&self.options) else {
+                continue;
+            };
+            let range = block.offset()..block.offset().saturating_add(block.size());
+
+            if let Some(previous) = ranges.insert(range.clone()) {
+                self.warnings.push(Warning {
+                    offset: block.offset(),
+                    kind: WarningKind::OverlappingBlock { range, previous },
+                });
+            }
+        }
+
+        self.locator_targets
+            .extend(cache.headers.iter().map(|cached| cached.offset));
+        self.quick_open = cache
+            .headers
+            .into_iter()
+            .map(|cached| (cached.offset, cached.header))
+            .collect();
+    }
+
+    /// Compare the `cached` copy of the header of `block` with the header in the archive,
+    /// pushing a warning at the first byte that differs.
+    fn compare_cached_header(&mut self, cached: &[u8], block: &Block) -> RarResult<()> {
+        let header =
+            cross_check::read_header(&mut self.reader, block.offset(), block.header_size())?;
+
+        if let Some(position) = header
+            .iter()
+            .zip(cached)
+            .position(|(a, b)| a != b)
+            .or((header.len() != cached.len()).then(|| header.len().min(cached.len())))
+        {
+            self.warnings.push(Warning {
+                offset: block.offset(),
+                kind: WarningKind::QuickOpenMismatch {
+                    position: block.offset() + position as u64,
+                },
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Read the unencrypted header at the next offset in one go, returning the block along
+    /// with the position where its parsing ended.
+    fn read_plain_block(&mut self) -> RarResult<(Block, u64)> {
+        let max_size = self.file_size.saturating_sub(self.next_offset);
+        let mut header = BufferedHeader::read(
+            &mut self.reader,
+            &mut self.header_buffer,
+            self.next_offset,
+            max_size,
+            7,
+            |prefix| match read_vint(&mut &prefix[4..]) {
+                Ok((size, vint_size)) => self
+                    .options
+                    .limits
+                    .header_size(size.saturating_add(4 + vint_size as u64)),
+                Err(_) => Ok(0),
+            },
+        )?;
+        let block = Block::read_with_options(&mut header, &self.options)?;
+        let parsed_end = header.stream_position()?;
+
+        if self.options.cross_check || self.options.salvage || self.options.round_trip {
+            let raw_header =
+                cross_check::read_header(&mut header, block.offset(), block.header_size())?;
+            let strict = Self::strict_parse(&raw_header, block.offset(), &self.options);
+
+            if self.options.salvage && matches!(strict, Err(Divergence::ChecksumMismatch { .. })) {
+                return Err(Error::CorruptHeader);
+            }
+            if self.options.cross_check {
+                cross_check::compare(block.offset(), &block, strict, &mut self.warnings);
+            }
+            if self.options.round_trip {
+                if let Some(serialized) = block.serialize_header(&self.options) {
+                    round_trip::compare(
+                        block.offset(),
+                        &raw_header,
+                        &serialized,
+                        &mut self.warnings,
+                    );
+                }
+            }
+        }
+
+        Ok((block, parsed_end))
+    }
+
+    /// Read the type of the unencrypted header at `offset`, to tell where an error happened.
+    fn block_type_at(&mut self, offset: u64) -> Option<u64> {
+        if !matches!(self.encryption, HeaderEncryption::None) {
+            return None;
+        }
+
+        // The type follows the CRC32 and the size of the header.
+        self.reader.seek(io::SeekFrom::Start(offset + 4)).ok()?;
+        read_vint(&mut self.reader).ok()?;
+        read_vint(&mut self.reader)
+            .ok()
+            .map(|(block_type, _)| block_type)
+    }
+
     fn read_block(&mut self) -> RarResult<Block> {
-        self.reader.seek(io::SeekFrom::Start(self.next_offset))?;
+        let cached = match self.encryption {
+            HeaderEncryption::None => self.quick_open.remove(&self.next_offset),
+            _ => None,
+        };
+
+        // The checks need the header in the archive, so the cached one is only compared
+        // with it.
+        let checked = self.options.cross_check || self.options.salvage || self.options.round_trip;
 
-        let block = Block::read(&mut self.reader)?;
+        let (block, parsed_end) = if let Some(header) = cached.as_ref().filter(|_| !checked) {
+            let mut cursor = HeaderCursor::new(header, self.next_offset);
+            let block = Block::read_with_options(&mut cursor, &self.options)?;
+            (block, cursor.stream_position()?)
+        } else {
+            self.reader.seek(io::SeekFrom::Start(self.next_offset))?;
+
+            if let Err(e) = self.derive_key() {
+                self.end_of_archive_reached = true;
+                return Err(e);
+            }
+
+            match self.encryption {
+                HeaderEncryption::Key {
+                    key,
+                    password_checked,
+                } => {
+                    let block = self.read_encrypted_block(&key, password_checked)?;
+                    (block, self.reader.stream_position()?)
+                }
+                _ => self.read_plain_block()?,
+            }
+        };
+
+        if let Some(cached) = cached.filter(|_| checked) {
+            self.compare_cached_header(&cached, &block)?;
+        }
 
         if block.size() == 0
-            || block.offset() + block.header_size() > self.file_size
-            || block.offset() + block.size() > self.file_size
+            || block.offset().saturating_add(block.header_size()) > self.file_size
+            || block.offset().saturating_add(block.size()) > self.file_size
         {
             return Err(Error::CorruptHeader);
         }
 
+        self.ranges
+            .record_block(&block, parsed_end, &mut self.warnings);
+        block.check_flags(&mut self.warnings);
+
+        // The end of encrypted headers is padded.
+        if matches!(self.encryption, HeaderEncryption::None) && parses_whole_header(&block) {
+            warning::check_header_end(&block, parsed_end, &mut self.warnings);
+        }
+        self.check_locator_targets(&block);
+
+        if let BlockKind::Main(MainBlock {
+            locator:
+                Some(LocatorRecord {
+                    quick_open_record_offset: Some(quick_open_offset),
+                    ..
+                }),
+            ..
+        }) = &block.kind
+        {
+            if self.options.quick_open {
+                self.load_quick_open(block.offset() + quick_open_offset);
+            }
+        }
+
+        if let BlockKind::Crypt(CryptBlock {
+            kdf_count,
+            salt,
+            check_value,
+            ..
+        }) = &block.kind
+        {
+            self.encryption = HeaderEncryption::Pending {
+                kdf_count: *kdf_count,
+                salt: *salt,
+                check_value: *check_value,
+            };
+        }
+
         self.next_offset = block.offset() + block.size();
 
-        if let BlockKind::EndArchive(_) = block.kind {
+        if let BlockKind::EndArchive(end) = &block.kind {
             self.end_of_archive_reached = true;
+            self.has_next_volume = Some(end.has_next_volume());
         }
 
         Ok(block)
     }
 }
 
+/// The parser reads the header of `block` up to its end.
+///
+/// The contents of unknown blocks are never parsed, while the records of skipped service
+/// blocks are stepped over.
+fn parses_whole_header(block: &Block) -> bool {
+    !matches!(block.kind, BlockKind::Unknown(_))
+}
+
 impl<R: io::Read + io::Seek> Iterator for BlockIterator<R> {
     type Item = RarResult<Block>;
 
@@ -61,6 +610,55 @@ impl<R: io::Read + io::Seek> Iterator for BlockIterator<R> {
             return None;
         }
 
-        Some(self.read_block())
+        let offset = self.next_offset;
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("block", format = "rar50", offset).entered();
+
+        let result = match self.read_block() {
+            Err(e)
+                if self.options.salvage
+                    && e.is_corrupt_data()
+                    && matches!(self.encryption, HeaderEncryption::None) =>
+            {
+                self.salvage(e)
+            }
+            result => result,
+        }
+        .map_err(|e| {
+            let block_type = self.block_type_at(offset);
+            e.at_block(offset, block_type)
+        });
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(block) => tracing::debug!(
+                offset = block.offset(),
+                kind = block.kind.name(),
+                header_size = block.header_size(),
+                size = block.size(),
+                "read block"
+            ),
+            Err(error) => tracing::debug!(%error, "failed to read block"),
+        }
+
+        Some(result)
+    }
+}
+
+impl<R: io::Read + io::Seek> RawHeaderReader for BlockIterator<R> {
+    type Block = Block;
+
+    fn raw_header(&mut self, block: &Block) -> RarResult<Vec<u8>> {
+        Ok(cross_check::read_header(
+            &mut self.reader,
+            block.offset(),
+            block.header_size(),
+        )?)
+    }
+}
+
+impl WindowedIterator<Block> for BlockIterator<Window> {
+    fn window(&mut self) -> &mut Window {
+        &mut self.reader
     }
 }
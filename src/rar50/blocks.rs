@@ -1,6 +1,17 @@
-use std::{io, ops::Deref};
-
-use crate::{read::*, size::BlockSize};
+use alloc::{string::String, vec::Vec};
+use core::ops::Deref;
+
+use crate::{
+    attributes::{FileAttributes, UnixPermissions, WindowsFileAttributes},
+    crypt::Rar50Keys,
+    error::Error,
+    hash::Blake2sp,
+    io,
+    limits::Limits,
+    read::*,
+    size::BlockSize,
+    warning::{check_flags, check_ignored_flags, Warning},
+};
 
 use super::{helpers::*, record_iterator::*, MAX_PATH_SIZE};
 
@@ -15,6 +26,16 @@ pub struct Block {
     pub kind: BlockKind,
 }
 
+serialize_struct!(Block {
+    offset,
+    flags,
+    header_crc32,
+    header_size,
+    extra_area_size,
+    data_size,
+    kind,
+});
+
 flags! {
     pub struct CommonFlags(u16) {
         /// Additional extra area is present at the end of the block header.
@@ -50,26 +71,235 @@ pub enum BlockKind {
     Unknown(UnknownBlock),
 }
 
+serialize_enum!(BlockKind {
+    Main(value),
+    File(value),
+    Service(value),
+    Crypt(value),
+    EndArchive(value),
+    Unknown(value),
+});
+
+impl BlockKind {
+    /// Name of the variant, for the events of the `tracing` feature.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            BlockKind::Main(_) => "Main",
+            BlockKind::File(_) => "File",
+            BlockKind::Service(_) => "Service",
+            BlockKind::Crypt(_) => "Crypt",
+            BlockKind::EndArchive(_) => "EndArchive",
+            BlockKind::Unknown(_) => "Unknown",
+        }
+    }
+}
+
 #[derive(Debug)]
 struct CommonHeader {
     pub extra_area_size: Option<u64>,
+    pub limits: Limits,
+    pub skip_unknown_record_data: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Options controlling how much of each block is parsed.
+pub struct ParseOptions {
+    /// Don't parse the records of QuickOpen service blocks.
+    ///
+    /// The block is still returned so its position in the archive is known.
+    pub skip_quick_open: bool,
+
+    /// Don't parse the records of RecoveryRecord service blocks.
+    ///
+    /// The block is still returned so its position in the archive is known, but its
+    /// [`RecoveryRecordServiceBlock::info`] will be `None`.
+    pub skip_recovery_record: bool,
+
+    /// Don't keep the data of the records of unknown types, leaving
+    /// [`UnknownRecord::data`] empty.
+    ///
+    /// The data is kept by default so that new record types can be inspected, but it can
+    /// take up to [`Limits::max_record_size`] for each record.
+    pub skip_unknown_record_data: bool,
+
+    /// Parse each header again from its raw bytes with strict checks and report any
+    /// disagreement with the normal parse as a [`WarningKind::ParserDivergence`].
+    ///
+    /// The strict parse fails if the header checksum doesn't match, if the header can't
+    /// be parsed without reading past its declared size, or if some bytes at the end
+    /// of the header are not part of any field or record.
+    ///
+    /// [`WarningKind::ParserDivergence`]: crate::WarningKind::ParserDivergence
+    pub cross_check: bool,
+
+    /// Read the headers of the blocks from the [`QuickOpenCache`] when the archive has one,
+    /// instead of reading them from their position in the archive.
+    ///
+    /// The cache is read when the main block is reached. Blocks missing from the cache are
+    /// read from the archive as usual, and the cache is ignored if it's corrupt or if the
+    /// headers are encrypted. Cached headers which overlap each other or start inside
+    /// another block are reported as a [`WarningKind::OverlappingBlock`] or
+    /// [`WarningKind::OffsetInsideBlock`].
+    ///
+    /// The headers in the archive are not read at all, so the checks which need their bytes
+    /// are skipped: a [`WarningKind::HeaderOverrun`] can't be found. When [`Self::cross_check`],
+    /// [`Self::round_trip`] or [`Self::salvage`] is set, the headers are read from the archive
+    /// anyway and the cached ones are only compared with them, reporting any difference as
+    /// a [`WarningKind::QuickOpenMismatch`].
+    ///
+    /// [`QuickOpenCache`]: super::QuickOpenCache
+    /// [`WarningKind::HeaderOverrun`]: crate::WarningKind::HeaderOverrun
+    /// [`WarningKind::QuickOpenMismatch`]: crate::WarningKind::QuickOpenMismatch
+    /// [`WarningKind::OverlappingBlock`]: crate::WarningKind::OverlappingBlock
+    /// [`WarningKind::OffsetInsideBlock`]: crate::WarningKind::OffsetInsideBlock
+    pub quick_open: bool,
+
+    /// Maximum sizes of the headers and of their fields. Blocks exceeding them fail with
+    /// [`Error::LimitExceeded`](crate::Error::LimitExceeded).
+    pub limits: Limits,
+
+    /// When a block header is corrupt, scan forward for the next header with a matching
+    /// checksum and resume from it instead of failing, reporting the skipped bytes as a
+    /// [`WarningKind::CorruptDataSkipped`].
+    ///
+    /// Headers whose checksum doesn't match are also treated as corrupt. Encrypted headers
+    /// can't be scanned for, so the iterator still fails after the [`CryptBlock`].
+    ///
+    /// [`WarningKind::CorruptDataSkipped`]: crate::WarningKind::CorruptDataSkipped
+    pub salvage: bool,
+
+    /// Serialize each header back into bytes from the parsed block and report the first
+    /// byte which differs from the bytes it was parsed from as a
+    /// [`WarningKind::RoundTripMismatch`], to check that no byte was skipped or misread.
+    ///
+    /// Variable-size integers are compared by value, because RAR pads the ones it fills in
+    /// after writing the header. Fields whose encoding isn't kept by the parsed block are
+    /// written the way RAR writes them: records in the order of their types followed by
+    /// the unknown records, and the attributes of service blocks as 0. Names with high
+    /// ASCII characters are reported, because they are decoded lossily. Blocks whose
+    /// records were skipped by the other options and encrypted headers are not checked.
+    ///
+    /// [`WarningKind::RoundTripMismatch`]: crate::WarningKind::RoundTripMismatch
+    pub round_trip: bool,
 }
 
 impl Block {
+    /// Push a warning for each flags of the block and of its records which have bits set
+    /// that are not known or that are ignored.
+    pub(crate) fn check_flags(&self, warnings: &mut Vec<Warning>) {
+        let mut check = |name, bits| check_flags(self.offset, name, bits, warnings);
+
+        check(CommonFlags::SCHEMA.name, self.flags.unknown_bits() as u64);
+
+        match &self.kind {
+            BlockKind::Main(b) => {
+                check(MainBlockFlags::SCHEMA.name, b.flags.unknown_bits() as u64);
+                if let Some(metadata) = &b.metadata {
+                    check(
+                        MetadataRecordFlags::SCHEMA.name,
+                        metadata.flags.unknown_bits() as u64,
+                    );
+                }
+            }
+            BlockKind::File(b) => {
+                check(FileBlockFlags::SCHEMA.name, b.flags.unknown_bits() as u64);
+                check("CompressionInfo", b.compression_info.unknown_bits());
+                if let Some(time) = &b.extended_time {
+                    check(
+                        FileTimeRecordFlags::SCHEMA.name,
+                        time.flags.unknown_bits() as u64,
+                    );
+                }
+                if let Some(encryption) = &b.encryption {
+                    check(
+                        FileEncryptionRecordFlags::SCHEMA.name,
+                        encryption.flags.unknown_bits() as u64,
+                    );
+                }
+                if let Some(redirection) = &b.filesystem_redirection {
+                    check(
+                        FileSystemRedirectionRecordFlags::SCHEMA.name,
+                        redirection.flags.unknown_bits() as u64,
+                    );
+                }
+            }
+            BlockKind::Service(b) => {
+                check(
+                    ServiceBlockFlags::SCHEMA.name,
+                    b.flags.unknown_bits() as u64,
+                );
+                check("CompressionInfo", b.compression_info.unknown_bits());
+                if let Some(encryption) = &b.encryption {
+                    check(
+                        FileEncryptionRecordFlags::SCHEMA.name,
+                        encryption.flags.unknown_bits() as u64,
+                    );
+                }
+                if let Some(time) = &b.extended_time {
+                    check(
+                        FileTimeRecordFlags::SCHEMA.name,
+                        time.flags.unknown_bits() as u64,
+                    );
+                }
+            }
+            BlockKind::EndArchive(b) => check(
+                EndArchiveBlockFlags::SCHEMA.name,
+                b.flags.unknown_bits() as u64,
+            ),
+            BlockKind::Crypt(_) | BlockKind::Unknown(_) => {}
+        }
+
+        self.check_ignored_flags(warnings);
+    }
+
+    /// Push a warning for each flags of the records of the block which have bits set that
+    /// are ignored because the flags they depend on are not set.
+    fn check_ignored_flags(&self, warnings: &mut Vec<Warning>) {
+        let (name, bits) = match &self.kind {
+            BlockKind::Main(MainBlock {
+                metadata: Some(metadata),
+                ..
+            }) => (MetadataRecordFlags::SCHEMA.name, metadata.ignored_bits()),
+            BlockKind::File(FileBlock {
+                extended_time: Some(time),
+                ..
+            })
+            | BlockKind::Service(ServiceBlock {
+                extended_time: Some(time),
+                ..
+            }) => (FileTimeRecordFlags::SCHEMA.name, time.ignored_bits()),
+            _ => return,
+        };
+
+        check_ignored_flags(self.offset, name, bits, warnings);
+    }
+
     // const MARKER: u64 = 0x00;
-    const MAIN: u64 = 0x01;
-    const FILE: u64 = 0x02;
-    const SERVICE: u64 = 0x03;
-    const CRYPT: u64 = 0x04;
-    const ENDARC: u64 = 0x05;
+    pub(super) const MAIN: u64 = 0x01;
+    pub(super) const FILE: u64 = 0x02;
+    pub(super) const SERVICE: u64 = 0x03;
+    pub(super) const CRYPT: u64 = 0x04;
+    pub(super) const ENDARC: u64 = 0x05;
 
     pub fn read<R: io::Read + io::Seek>(reader: &mut R) -> io::Result<Self> {
+        Self::read_with_options(reader, &ParseOptions::default())
+    }
+
+    /// Read a block, parsing only the parts allowed by `options`.
+    pub fn read_with_options<R: io::Read + io::Seek>(
+        reader: &mut R,
+        options: &ParseOptions,
+    ) -> io::Result<Self> {
         let offset = reader.stream_position()?;
 
         let header_crc32 = read_u32(reader)?;
 
         let (header_size, vint_size) = read_vint(reader)?;
-        let full_header_size = header_size + vint_size as u64 + 4;
+        let full_header_size = header_size
+            .checked_add(vint_size as u64 + 4)
+            .ok_or(Error::CorruptHeader)?;
+        options.limits.header_size(full_header_size)?;
 
         let (header_type, _) = read_vint(reader)?;
 
@@ -88,15 +318,26 @@ impl Block {
             None
         };
 
-        let common_header = CommonHeader { extra_area_size };
+        let common_header = CommonHeader {
+            extra_area_size,
+            limits: options.limits,
+            skip_unknown_record_data: options.skip_unknown_record_data,
+        };
 
         let kind = match header_type {
             Self::MAIN => BlockKind::Main(MainBlock::read(reader, &common_header)?),
             Self::FILE => BlockKind::File(FileBlock::read(reader, &common_header)?),
-            Self::SERVICE => BlockKind::Service(ServiceBlock::read(reader, &common_header)?),
+            Self::SERVICE => {
+                BlockKind::Service(ServiceBlock::read(reader, &common_header, options)?)
+            }
             Self::CRYPT => BlockKind::Crypt(CryptBlock::read(reader)?),
             Self::ENDARC => BlockKind::EndArchive(EndArchiveBlock::read(reader)?),
-            _ => BlockKind::Unknown(UnknownBlock::read(reader, header_type)?),
+            _ => BlockKind::Unknown(UnknownBlock::read(
+                reader,
+                header_type,
+                &common_header,
+                offset.saturating_add(full_header_size),
+            )?),
         };
 
         Ok(Block {
@@ -142,6 +383,14 @@ pub struct MainBlock {
     pub unknown_records: Vec<UnknownRecord>,
 }
 
+serialize_struct!(MainBlock {
+    flags,
+    volume_number,
+    locator,
+    metadata,
+    unknown_records,
+});
+
 flags! {
     pub struct MainBlockFlags(u16) {
         /// Archive is part of a multi-volume archive.
@@ -163,8 +412,8 @@ flags! {
 }
 
 impl MainBlock {
-    const LOCATOR: u64 = 0x0001;
-    const METADATA: u64 = 0x0002;
+    pub(super) const LOCATOR: u64 = 0x0001;
+    pub(super) const METADATA: u64 = 0x0002;
 
     pub(self) fn read<R: io::Read + io::Seek>(
         reader: &mut R,
@@ -210,14 +459,21 @@ impl Deref for MainBlock {
 
 #[derive(Debug)]
 pub struct LocatorRecord {
+    pub flags: LocatorRecordFlags,
     pub quick_open_record_offset: Option<u64>,
     pub recovery_record_offset: Option<u64>,
 }
 
+serialize_struct!(LocatorRecord {
+    flags,
+    quick_open_record_offset,
+    recovery_record_offset,
+});
+
 flags! {
-    struct LocatorRecordFlags(u8) {
-        has_quick_open_record_offset = 0x01;
-        has_recovery_record_offset = 0x02;
+    pub struct LocatorRecordFlags(u8) {
+        pub has_quick_open_record_offset = 0x01;
+        pub has_recovery_record_offset = 0x02;
     }
 }
 
@@ -249,6 +505,7 @@ impl LocatorRecord {
         };
 
         Ok(LocatorRecord {
+            flags,
             quick_open_record_offset,
             recovery_record_offset,
         })
@@ -257,16 +514,23 @@ impl LocatorRecord {
 
 #[derive(Debug)]
 pub struct MetadataRecord {
+    pub flags: MetadataRecordFlags,
     pub name: Option<String>,
     pub creation_time: Option<Result<time::OffsetDateTime, u64>>,
 }
 
+serialize_struct!(MetadataRecord {
+    flags,
+    name,
+    creation_time,
+});
+
 flags! {
-    struct MetadataRecordFlags(u8) {
-        has_archive_name = 0x01;
-        has_creation_time = 0x02;
-        uses_unix_time = 0x04;
-        is_unix_time_nanoseconds = 0x08;
+    pub struct MetadataRecordFlags(u8) {
+        pub has_archive_name = 0x01;
+        pub has_creation_time = 0x02;
+        pub uses_unix_time = 0x04;
+        pub is_unix_time_nanoseconds = 0x08;
     }
 }
 
@@ -282,7 +546,9 @@ impl MetadataRecord {
             if name.is_empty() {
                 None
             } else {
-                Some(String::from_utf8(name).unwrap())
+                Some(String::from_utf8(name).map_err(|_| Error::InvalidUtf8 {
+                    field: "archive name",
+                })?)
             }
         } else {
             None
@@ -296,27 +562,35 @@ impl MetadataRecord {
                     read_unix_time_sec(reader)?.map_err(|s| s as u64)
                 }
             } else {
-                if flags.is_unix_time_nanoseconds() {
-                    // TODO log warning?
-                }
-
                 read_windows_time(reader)?
             };
 
             Some(time)
         } else {
-            if flags.uses_unix_time() || flags.is_unix_time_nanoseconds() {
-                // TODO log warning?
-            }
-
             None
         };
 
         Ok(MetadataRecord {
+            flags,
             name,
             creation_time,
         })
     }
+
+    /// Bits of the flags which are ignored because the flags they depend on are not set.
+    fn ignored_bits(&self) -> u64 {
+        let flags = self.flags;
+        let mut bits = 0;
+        if flags.uses_unix_time() && !flags.has_creation_time() {
+            bits |= 0x04;
+        }
+        if flags.is_unix_time_nanoseconds()
+            && !(flags.has_creation_time() && flags.uses_unix_time())
+        {
+            bits |= 0x08;
+        }
+        bits
+    }
 }
 
 #[derive(Debug)]
@@ -329,7 +603,7 @@ pub struct FileBlock {
     /// from stdin to multi-volume archive.
     pub unpacked_size: Option<u64>,
 
-    /// OS-specific file attributes.
+    /// OS-specific file attributes. They can be decoded with [`Self::attributes`].
     pub attributes: u64,
 
     /// File modification time.
@@ -364,6 +638,24 @@ pub struct FileBlock {
     pub unknown_records: Vec<UnknownRecord>,
 }
 
+serialize_struct!(FileBlock {
+    flags,
+    unpacked_size,
+    attributes,
+    modification_time,
+    unpacked_data_crc32,
+    compression_info,
+    host_os,
+    name,
+    encryption,
+    hash,
+    extended_time,
+    version,
+    filesystem_redirection,
+    unix_owner,
+    unknown_records,
+});
+
 flags! {
     pub struct FileBlockFlags(u16) {
         pub is_directory = 0x0001;
@@ -382,13 +674,24 @@ int_enum! {
 
 pub struct CompressionInfo(u64);
 
+serialize_struct!(CompressionInfo {
+    version(),
+    is_rar5_compatible(),
+    algorithm(),
+    is_solid(),
+    method(),
+    dictionary_size_fraction(),
+    min_dictionary_size(),
+});
+
 impl CompressionInfo {
     const ALGORITHM_MASK: u64 = 0x003f;
     const SOLID_MASK: u64 = 0x0040;
     const METHOD_MASK: u64 = 0x0380;
     const MIN_DICT_SIZE_MASK: u64 = 0x7c00;
     const DICT_SIZE_FACTOR_MASK: u64 = 0xf8000;
-    const USES_PACK_5_ALGORITHM_MASK: u64 = 0x100_000;
+    const RAR5_COMPATIBLE_MASK: u64 = 0x100_000;
+    const KNOWN_BITS_MASK: u64 = 0x1f_ffff;
 
     pub const MIN_DICT_SIZE: u64 = 0x20_000;
     pub const MAX_DICT_SIZE: u64 = 0x1_000_000_000;
@@ -397,21 +700,29 @@ impl CompressionInfo {
         Self(info)
     }
 
-    /// Version of WinRAR required to unpack the file.
-    fn version(&self) -> CompressionAlgorithm {
+    /// Raw value of the field.
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Version of the compression algorithm reported by the archiver.
+    ///
+    /// This may differ from [`Self::algorithm`] for files compressed by RAR 7 which are
+    /// marked as [compatible with RAR5][Self::is_rar5_compatible].
+    pub fn version(&self) -> CompressionAlgorithm {
         ((self.0 & Self::ALGORITHM_MASK) as u8).into()
     }
 
-    fn uses_pack_5_algorithm(&self) -> bool {
-        self.0 & Self::USES_PACK_5_ALGORITHM_MASK != 0
+    /// The file was compressed by RAR 7 but can be decompressed with the RAR5 algorithm,
+    /// because it doesn't use any of the features introduced with RAR 7.
+    pub fn is_rar5_compatible(&self) -> bool {
+        self.0 & Self::RAR5_COMPATIBLE_MASK != 0
     }
 
     /// Actual version compression algorithm used to compress the file.
     pub fn algorithm(&self) -> CompressionAlgorithm {
         match self.version() {
-            CompressionAlgorithm::Pack7 if self.uses_pack_5_algorithm() => {
-                CompressionAlgorithm::Pack5
-            }
+            CompressionAlgorithm::Pack7 if self.is_rar5_compatible() => CompressionAlgorithm::Pack5,
             algo => algo,
         }
     }
@@ -425,6 +736,18 @@ impl CompressionInfo {
         (((self.0 & Self::METHOD_MASK) >> 7) as u8).into()
     }
 
+    /// Number of 1/32 fractions of the base dictionary size that should be added to obtain
+    /// the real dictionary size.
+    ///
+    /// Only used by RAR 7, which supports dictionary sizes that are not a power of two.
+    pub fn dictionary_size_fraction(&self) -> u8 {
+        if self.version() == CompressionAlgorithm::Pack7 {
+            ((self.0 & Self::DICT_SIZE_FACTOR_MASK) >> 15) as u8
+        } else {
+            0
+        }
+    }
+
     /// Minimum dictionary size required to extract data.
     /// UnRAR seems to have a maximum dict size of 64GiB, so if we get more than that
     /// we return an error with the reported size.
@@ -432,10 +755,8 @@ impl CompressionInfo {
         let factor = (self.0 & Self::MIN_DICT_SIZE_MASK) >> 10;
 
         let size = if self.version() == CompressionAlgorithm::Pack7 {
-            let extra_factor = (self.0 & Self::DICT_SIZE_FACTOR_MASK) >> 15;
-
             let size = Self::MIN_DICT_SIZE << (factor & 0x1f);
-            size + size / 32 * extra_factor
+            size + size / 32 * self.dictionary_size_fraction() as u64
         } else {
             Self::MIN_DICT_SIZE << (factor & 0x0f)
         };
@@ -446,11 +767,17 @@ impl CompressionInfo {
             Err(size)
         }
     }
+
+    /// Bits that are set in the compression info but are not known to this library.
+    pub fn unknown_bits(&self) -> u64 {
+        self.0 & !Self::KNOWN_BITS_MASK
+    }
 }
 
-impl std::fmt::Debug for CompressionInfo {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Debug for CompressionInfo {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("CompressionInfo")
+            .field("version", &self.version())
             .field("algorithm", &self.algorithm())
             .field("is_solid", &self.is_solid())
             .field("method", &self.method())
@@ -478,12 +805,12 @@ int_enum! {
 }
 
 impl FileBlock {
-    const CRYPT: u64 = 0x01;
-    const HASH: u64 = 0x02;
-    const HTIME: u64 = 0x03;
-    const VERSION: u64 = 0x04;
-    const REDIR: u64 = 0x05;
-    const UOWNER: u64 = 0x06;
+    pub(super) const CRYPT: u64 = 0x01;
+    pub(super) const HASH: u64 = 0x02;
+    pub(super) const HTIME: u64 = 0x03;
+    pub(super) const VERSION: u64 = 0x04;
+    pub(super) const REDIR: u64 = 0x05;
+    pub(super) const UOWNER: u64 = 0x06;
 
     pub(self) fn read<R: io::Read + io::Seek>(
         reader: &mut R,
@@ -519,7 +846,8 @@ impl FileBlock {
         let (host_os, _) = read_vint(reader)?;
         let (name_length, _) = read_vint(reader)?;
 
-        let name = read_vec(reader, name_length.clamp(0, MAX_PATH_SIZE) as usize)?;
+        let name_length = name_length.clamp(0, MAX_PATH_SIZE);
+        let name = read_vec(reader, common_header.limits.name_size(name_length)?)?;
         let name = unmap_high_ascii_chars(name);
 
         parse_records! {
@@ -544,7 +872,7 @@ impl FileBlock {
             modification_time,
             unpacked_data_crc32,
             compression_info,
-            host_os: (host_os as u8).into(),
+            host_os: HostOs::from_vint(host_os)?,
             name,
             encryption,
             hash,
@@ -565,6 +893,29 @@ impl FileBlock {
 
         self.modification_time.map(|r| r.map_err(|t| t as u64))
     }
+
+    /// Name of the file as a relative path which can't escape the directory it's extracted
+    /// to, with `..` components, leading separators and drive prefixes removed.
+    ///
+    /// Invalid UTF-8 sequences in the name are replaced.
+    #[cfg(feature = "std")]
+    pub fn sanitized_path(&self) -> std::path::PathBuf {
+        match &self.name {
+            Ok(name) => crate::path::sanitize_path(name),
+            Err(name) => crate::path::sanitize_path(&String::from_utf8_lossy(name)),
+        }
+    }
+
+    /// Attributes of the file, decoded according to [`Self::host_os`].
+    pub fn attributes(&self) -> FileAttributes {
+        match self.host_os {
+            HostOs::Windows => {
+                FileAttributes::Windows(WindowsFileAttributes::new(self.attributes as u32))
+            }
+            HostOs::Unix => FileAttributes::Unix(UnixPermissions::new(self.attributes as u32)),
+            HostOs::Unknown(_) => FileAttributes::Unknown(self.attributes),
+        }
+    }
 }
 
 impl Deref for FileBlock {
@@ -600,6 +951,23 @@ pub struct ServiceBlock {
     pub kind: ServiceBlockKind,
 }
 
+serialize_struct!(ServiceBlock {
+    flags,
+    unpacked_size,
+    modification_time,
+    data_crc32,
+    compression_info,
+    host_os,
+    encryption,
+    hash,
+    extended_time,
+    version,
+    filesystem_redirection,
+    unix_owner,
+    unknown_records,
+    kind,
+});
+
 flags! {
     pub struct ServiceBlockFlags(u16) {
         pub has_modification_time = 0x0002;
@@ -636,25 +1004,49 @@ pub enum ServiceBlockKind {
     Comment(CommentServiceBlock),
     QuickOpen(QuickOpenServiceBlock),
     NtfsFilePermissions,
-    NtfsAlternateDataStream,
+    NtfsAlternateDataStream(NtfsStreamServiceBlock),
     RecoveryRecord(RecoveryRecordServiceBlock),
     Unknown(Vec<u8>),
 }
 
+serialize_enum!(ServiceBlockKind {
+    Comment(value),
+    QuickOpen(value),
+    NtfsFilePermissions,
+    NtfsAlternateDataStream(value),
+    RecoveryRecord(value),
+    Unknown(value),
+});
+
 #[derive(Debug)]
 // Does not contain any records.
 pub struct QuickOpenServiceBlock;
 
+serialize_struct!(QuickOpenServiceBlock {});
+
 #[derive(Debug)]
 // Does not contain any records.
 pub struct CommentServiceBlock;
 
+serialize_struct!(CommentServiceBlock {});
+
+#[derive(Debug)]
+pub struct NtfsStreamServiceBlock {
+    /// UTF-8 name of the stream, starting with a `:`.
+    // It is probably illegal for this to be missing.
+    pub stream_name: Option<Vec<u8>>,
+}
+
+serialize_struct!(NtfsStreamServiceBlock { stream_name });
+
 #[derive(Debug)]
 pub struct RecoveryRecordServiceBlock {
     // It is probably illegal for this to be missing.
     pub info: Option<RecoveryRecordInfo>,
 }
 
+serialize_struct!(RecoveryRecordServiceBlock { info });
+
 #[derive(Debug)]
 /// The recovery record is not used in WinRAR.
 /// Here is more information about it.
@@ -667,6 +1059,11 @@ pub struct RecoveryRecordInfo {
     pub unknown: Vec<u8>,
 }
 
+serialize_struct!(RecoveryRecordInfo {
+    percentage,
+    unknown,
+});
+
 impl RecoveryRecordInfo {
     fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
         let percentage = read_u8(reader)?;
@@ -682,17 +1079,18 @@ impl RecoveryRecordInfo {
 }
 
 impl ServiceBlock {
-    const CRYPT: u64 = 0x01;
-    const HASH: u64 = 0x02;
-    const HTIME: u64 = 0x03;
-    const VERSION: u64 = 0x04;
-    const REDIR: u64 = 0x05;
-    const UOWNER: u64 = 0x06;
-    const SERVICE_DATA: u64 = 0x07;
+    pub(super) const CRYPT: u64 = 0x01;
+    pub(super) const HASH: u64 = 0x02;
+    pub(super) const HTIME: u64 = 0x03;
+    pub(super) const VERSION: u64 = 0x04;
+    pub(super) const REDIR: u64 = 0x05;
+    pub(super) const UOWNER: u64 = 0x06;
+    pub(super) const SERVICE_DATA: u64 = 0x07;
 
     fn read<R: io::Read + io::Seek>(
         reader: &mut R,
         common_header: &CommonHeader,
+        options: &ParseOptions,
     ) -> io::Result<Self> {
         let (flags, _) = read_vint(reader)?;
         let flags = ServiceBlockFlags::new(flags as u16);
@@ -727,45 +1125,86 @@ impl ServiceBlock {
         let (host_os, _) = read_vint(reader)?;
 
         let (name_length, _) = read_vint(reader)?;
-        let name = read_vec(reader, name_length as usize)?;
+        let name = read_vec(reader, options.limits.name_size(name_length)?)?;
         let name = ServiceBlockType::from_bytes(&name).ok_or(name);
 
-        let mut recovery_record = None;
+        let skip_records = match name {
+            Ok(ServiceBlockType::QuickOpen) => options.skip_quick_open,
+            Ok(ServiceBlockType::RecoveryRecord) => options.skip_recovery_record,
+            _ => false,
+        };
 
-        parse_records! {
-            reader,
-            common_header,
+        let (
+            encryption,
+            hash,
+            extended_time,
+            version,
+            filesystem_redirection,
+            unix_owner,
             unknown_records,
-
-            let {
-                encryption: FileEncryptionRecord = Self::CRYPT,
-                hash: FileHashRecord = Self::HASH,
-                extended_time: FileTimeRecord = Self::HTIME,
-                version: FileVersionRecord = Self::VERSION,
-                filesystem_redirection: FileSystemRedirectionRecord = Self::REDIR,
-                unix_owner: UnixOwnerRecord = Self::UOWNER,
+            recovery_record,
+            stream_name,
+        ) = if skip_records {
+            // Step over the extra area without reading the records.
+            if let Some(extra_area_size) = common_header.extra_area_size {
+                let extra_area_size =
+                    i64::try_from(extra_area_size).map_err(|_| Error::CorruptHeader)?;
+                reader.seek(io::SeekFrom::Current(extra_area_size))?;
             }
 
-            match record {
-                Self::SERVICE_DATA => {
-                    match name {
-                        Ok(ServiceBlockType::RecoveryRecord) => {
-                            recovery_record = Some(RecoveryRecordInfo::read(&mut record.data)?);
-                        }
-                        _ => {
-                            unknown_records.push(UnknownRecord::new(Self::SERVICE_DATA))
+            Default::default()
+        } else {
+            let mut recovery_record = None;
+            let mut stream_name = None;
+
+            parse_records! {
+                reader,
+                common_header,
+                unknown_records,
+
+                let {
+                    encryption: FileEncryptionRecord = Self::CRYPT,
+                    hash: FileHashRecord = Self::HASH,
+                    extended_time: FileTimeRecord = Self::HTIME,
+                    version: FileVersionRecord = Self::VERSION,
+                    filesystem_redirection: FileSystemRedirectionRecord = Self::REDIR,
+                    unix_owner: UnixOwnerRecord = Self::UOWNER,
+                }
+
+                match record {
+                    Self::SERVICE_DATA => {
+                        match name {
+                            Ok(ServiceBlockType::RecoveryRecord) => {
+                                recovery_record = Some(RecoveryRecordInfo::read(&mut record.data)?);
+                            }
+                            Ok(ServiceBlockType::NtfsAlternateDataStream) => {
+                                stream_name = Some(record.data.into_inner());
+                            }
+                            _ => unknown_records.push(UnknownRecord::from_record(record, common_header)),
                         }
                     }
                 }
             }
-        }
+
+            (
+                encryption,
+                hash,
+                extended_time,
+                version,
+                filesystem_redirection,
+                unix_owner,
+                unknown_records,
+                recovery_record,
+                stream_name,
+            )
+        };
 
         let kind = match name {
             Ok(ServiceBlockType::Comment) => ServiceBlockKind::Comment(CommentServiceBlock),
             Ok(ServiceBlockType::QuickOpen) => ServiceBlockKind::QuickOpen(QuickOpenServiceBlock),
             Ok(ServiceBlockType::NtfsFilePermissions) => ServiceBlockKind::NtfsFilePermissions,
             Ok(ServiceBlockType::NtfsAlternateDataStream) => {
-                ServiceBlockKind::NtfsAlternateDataStream
+                ServiceBlockKind::NtfsAlternateDataStream(NtfsStreamServiceBlock { stream_name })
             }
             Ok(ServiceBlockType::RecoveryRecord) => {
                 ServiceBlockKind::RecoveryRecord(RecoveryRecordServiceBlock {
@@ -781,7 +1220,7 @@ impl ServiceBlock {
             modification_time,
             data_crc32,
             compression_info,
-            host_os: (host_os as u8).into(),
+            host_os: HostOs::from_vint(host_os)?,
             encryption,
             hash,
             extended_time,
@@ -804,6 +1243,7 @@ impl Deref for ServiceBlock {
 
 #[derive(Debug)]
 pub struct FileEncryptionRecord {
+    pub encryption_version: EncryptionVersion,
     pub flags: FileEncryptionRecordFlags,
     pub kdf_count: u8,
     pub salt: [u8; 16],
@@ -811,6 +1251,15 @@ pub struct FileEncryptionRecord {
     pub check_value: Option<[u8; 12]>,
 }
 
+serialize_struct!(FileEncryptionRecord {
+    encryption_version,
+    flags,
+    kdf_count,
+    salt,
+    iv,
+    check_value,
+});
+
 flags! {
     pub struct FileEncryptionRecordFlags(u8) {
         pub has_password_check = 0x01;
@@ -820,6 +1269,9 @@ flags! {
 
 impl FileEncryptionRecord {
     pub fn read<R: io::Read + io::Seek>(reader: &mut R) -> io::Result<Self> {
+        let (encryption_version, _) = read_vint(reader)?;
+        let encryption_version = EncryptionVersion::from_vint(encryption_version)?;
+
         let (flags, _) = read_vint(reader)?;
         let flags = FileEncryptionRecordFlags::new(flags as u8);
 
@@ -834,6 +1286,7 @@ impl FileEncryptionRecord {
         };
 
         Ok(FileEncryptionRecord {
+            encryption_version,
             flags,
             kdf_count,
             salt,
@@ -841,6 +1294,42 @@ impl FileEncryptionRecord {
             check_value,
         })
     }
+
+    /// Check `password` against the check value of the record without decrypting the
+    /// data.
+    ///
+    /// Returns `true` if the record has no check value, because a wrong password can only
+    /// be noticed by the checksums of the decrypted data then, and `false` if the
+    /// encryption is not supported. The keys derived from the password are cached, so
+    /// decrypting the data with the same password afterwards doesn't derive them again.
+    pub fn verify_password(&self, password: &str) -> bool {
+        verify_password(
+            password,
+            self.encryption_version,
+            &self.salt,
+            self.kdf_count,
+            self.check_value.as_ref(),
+        )
+    }
+}
+
+/// Check `password` against the `check_value` stored with the salt of the encryption.
+fn verify_password(
+    password: &str,
+    encryption_version: EncryptionVersion,
+    salt: &[u8; 16],
+    kdf_count: u8,
+    check_value: Option<&[u8; 12]>,
+) -> bool {
+    if let EncryptionVersion::Unknown(_) = encryption_version {
+        return false;
+    }
+    let Some(check_value) = check_value else {
+        return true;
+    };
+
+    Rar50Keys::derive(password, salt, kdf_count)
+        .is_some_and(|keys| keys.is_password_correct(check_value))
 }
 
 #[derive(Debug)]
@@ -848,14 +1337,21 @@ pub struct FileHashRecord {
     pub hash: FileHash,
 }
 
+serialize_struct!(FileHashRecord { hash });
+
 #[derive(Debug)]
 pub enum FileHash {
     Blake2Sp([u8; 32]),
     Unknown(u64),
 }
 
+serialize_enum!(FileHash {
+    Blake2Sp(value),
+    Unknown(value),
+});
+
 impl FileHash {
-    pub(self) const BLAKE2SP: u64 = 0x00;
+    pub(super) const BLAKE2SP: u64 = 0x00;
 }
 
 impl FileHashRecord {
@@ -869,17 +1365,40 @@ impl FileHashRecord {
 
         Ok(FileHashRecord { hash })
     }
+
+    /// Check that `data`, the unpacked data of the file, matches the hash.
+    ///
+    /// The hashes of encrypted files are converted to a MAC using the key of the file, so
+    /// they never match the hash of the data. Returns `false` for unknown hash types.
+    pub fn verify(&self, data: &[u8]) -> bool {
+        match &self.hash {
+            FileHash::Blake2Sp(expected) => {
+                let mut hasher = Blake2sp::new();
+                hasher.update(data);
+                hasher.finalize() == *expected
+            }
+            FileHash::Unknown(_) => false,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct FileTimeRecord {
+    pub flags: FileTimeRecordFlags,
     pub modification_time: Option<Result<time::OffsetDateTime, u64>>,
     pub creation_time: Option<Result<time::OffsetDateTime, u64>>,
     pub access_time: Option<Result<time::OffsetDateTime, u64>>,
 }
 
+serialize_struct!(FileTimeRecord {
+    flags,
+    modification_time,
+    creation_time,
+    access_time,
+});
+
 flags! {
-    struct FileTimeRecordFlags(u8) {
+    pub struct FileTimeRecordFlags(u8) {
         pub uses_unix_time = 0x01;
         pub has_modification_time = 0x02;
         pub has_creation_time = 0x04;
@@ -912,6 +1431,7 @@ impl FileTimeRecord {
 
             if !flags.has_unix_time_nanoseconds() {
                 return Ok(FileTimeRecord {
+                    flags,
                     modification_time,
                     creation_time,
                     access_time,
@@ -936,15 +1456,12 @@ impl FileTimeRecord {
             }
 
             Ok(FileTimeRecord {
+                flags,
                 modification_time,
                 creation_time,
                 access_time,
             })
         } else {
-            if flags.has_unix_time_nanoseconds() {
-                // TODO log warning
-            }
-
             if flags.has_modification_time() {
                 modification_time = Some(read_windows_time(reader)?)
             }
@@ -958,12 +1475,22 @@ impl FileTimeRecord {
             }
 
             Ok(FileTimeRecord {
+                flags,
                 modification_time,
                 creation_time,
                 access_time,
             })
         }
     }
+
+    /// Bits of the flags which are ignored because the flags they depend on are not set.
+    fn ignored_bits(&self) -> u64 {
+        if self.flags.has_unix_time_nanoseconds() && !self.flags.uses_unix_time() {
+            0x10
+        } else {
+            0
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -971,6 +1498,8 @@ pub struct FileVersionRecord {
     pub version_number: u64,
 }
 
+serialize_struct!(FileVersionRecord { version_number });
+
 impl FileVersionRecord {
     pub fn read<R: io::Read + io::Seek>(reader: &mut R) -> io::Result<Self> {
         // Unused as of now
@@ -988,6 +1517,12 @@ pub struct FileSystemRedirectionRecord {
     pub name: String,
 }
 
+serialize_struct!(FileSystemRedirectionRecord {
+    redirection_type,
+    flags,
+    name,
+});
+
 int_enum! {
     pub enum FileSystemRedirectionType : u16 {
         UnixSymlink = 0x0001,
@@ -1007,14 +1542,16 @@ flags! {
 impl FileSystemRedirectionRecord {
     pub fn read<R: io::Read + io::Seek>(reader: &mut R) -> io::Result<Self> {
         let (redirection_type, _) = read_vint(reader)?;
-        let redirection_type = (redirection_type as u16).into();
+        let redirection_type = FileSystemRedirectionType::from_vint(redirection_type)?;
 
         let (flags, _) = read_vint(reader)?;
         let flags = FileSystemRedirectionRecordFlags::new(flags as u16);
 
         let (name_length, _) = read_vint(reader)?;
         let name = read_vec(reader, name_length as usize)?;
-        let name = String::from_utf8(name).unwrap();
+        let name = String::from_utf8(name).map_err(|_| Error::InvalidUtf8 {
+            field: "redirection target",
+        })?;
 
         Ok(FileSystemRedirectionRecord {
             redirection_type,
@@ -1032,6 +1569,13 @@ pub struct UnixOwnerRecord {
     pub group_id: Option<u64>,
 }
 
+serialize_struct!(UnixOwnerRecord {
+    user_name,
+    group_name,
+    user_id,
+    group_id,
+});
+
 flags! {
     struct UnixOwnerRecordFlags(u8) {
         pub has_user_name = 0x01;
@@ -1086,6 +1630,13 @@ pub struct CryptBlock {
     pub check_value: Option<[u8; 12]>,
 }
 
+serialize_struct!(CryptBlock {
+    encryption_version,
+    kdf_count,
+    salt,
+    check_value,
+});
+
 flags! {
     struct CryptBlockFlags(u16) {
         has_password_check = 0x0001;
@@ -1101,7 +1652,7 @@ int_enum! {
 impl CryptBlock {
     pub fn read<R: io::Read + io::Seek>(reader: &mut R) -> io::Result<Self> {
         let (encryption_version, _) = read_vint(reader)?;
-        let encryption_version = (encryption_version as u8).into();
+        let encryption_version = EncryptionVersion::from_vint(encryption_version)?;
 
         let (flags, _) = read_vint(reader)?;
         let flags = CryptBlockFlags::new(flags as u16);
@@ -1122,15 +1673,34 @@ impl CryptBlock {
             check_value,
         })
     }
+
+    /// Check `password` against the check value of the block without decrypting any
+    /// header, like [`FileEncryptionRecord::verify_password`].
+    pub fn verify_password(&self, password: &str) -> bool {
+        verify_password(
+            password,
+            self.encryption_version,
+            &self.salt,
+            self.kdf_count,
+            self.check_value.as_ref(),
+        )
+    }
 }
 
 #[derive(Debug)]
+/// Block signaling the end of the archive, or of the current volume.
+///
+/// Unlike in RAR15, its flags are the only field: the volume number is stored in the
+/// [`MainBlock`].
 pub struct EndArchiveBlock {
     pub flags: EndArchiveBlockFlags,
 }
 
+serialize_struct!(EndArchiveBlock { flags });
+
 flags! {
     pub struct EndArchiveBlockFlags(u16) {
+        /// Archive continues in the next volume.
         pub has_next_volume = 0x0001;
     }
 }
@@ -1142,6 +1712,11 @@ impl EndArchiveBlock {
 
         Ok(EndArchiveBlock { flags })
     }
+
+    /// Whether this is the last volume of the archive, or the only one.
+    pub fn is_last_volume(&self) -> bool {
+        !self.has_next_volume()
+    }
 }
 
 impl Deref for EndArchiveBlock {
@@ -1153,23 +1728,107 @@ impl Deref for EndArchiveBlock {
 }
 
 #[derive(Debug)]
+/// Block of a type which is not known to this library.
+///
+/// The fields specific to the block type can't be parsed, so they are kept as raw bytes,
+/// but the extra area has the same layout for all blocks and its records are read.
 pub struct UnknownBlock {
+    /// Type of the block.
     pub tag: u64,
+
+    /// Raw bytes of the header between the common fields and the extra area.
+    pub fields: Vec<u8>,
+
+    /// Records of the extra area.
+    pub records: Vec<UnknownRecord>,
 }
 
+serialize_struct!(UnknownBlock {
+    tag,
+    fields,
+    records,
+});
+
 impl UnknownBlock {
-    pub fn read<R: io::Read + io::Seek>(_reader: &mut R, tag: u64) -> io::Result<Self> {
-        Ok(UnknownBlock { tag })
+    fn read<R: io::Read + io::Seek>(
+        reader: &mut R,
+        tag: u64,
+        common_header: &CommonHeader,
+        header_end: u64,
+    ) -> io::Result<Self> {
+        // The extra area is at the end of the header.
+        let fields_size = header_end
+            .saturating_sub(common_header.extra_area_size.unwrap_or(0))
+            .saturating_sub(reader.stream_position()?);
+        let fields = read_vec(reader, fields_size as usize)?;
+
+        let mut records = vec![];
+        if let Some(extra_area_size) = common_header.extra_area_size {
+            for record in RecordIterator::new(reader, extra_area_size, &common_header.limits)? {
+                records.push(UnknownRecord::from_record(record?, common_header));
+            }
+        }
+
+        Ok(UnknownBlock {
+            tag,
+            fields,
+            records,
+        })
     }
 }
 
 #[derive(Debug)]
+/// Record of a type which is not known to this library, or which is not expected in the
+/// block it was found in.
 pub struct UnknownRecord {
+    /// Type of the record.
     pub tag: u64,
+
+    /// Raw bytes of the record after its type, which are left empty if
+    /// [`ParseOptions::skip_unknown_record_data`] is set.
+    pub data: Vec<u8>,
 }
 
+serialize_struct!(UnknownRecord { tag, data });
+
 impl UnknownRecord {
-    pub fn new(tag: u64) -> Self {
-        Self { tag }
+    pub fn new(tag: u64, data: Vec<u8>) -> Self {
+        Self { tag, data }
+    }
+
+    /// Keep the data of `record` unless the options of the block skip it.
+    fn from_record(record: CommonRecord, common_header: &CommonHeader) -> Self {
+        let data = if common_header.skip_unknown_record_data {
+            vec![]
+        } else {
+            record.data.into_inner()
+        };
+        Self::new(record.record_type, data)
     }
 }
+
+/// Flags and enums defined in this module, listed in [`crate::schema::MODULES`].
+pub(crate) const SCHEMA: crate::schema::ModuleSchema = crate::schema::ModuleSchema {
+    name: "rawrxd::rar50",
+    flags: &[
+        CommonFlags::SCHEMA,
+        MainBlockFlags::SCHEMA,
+        LocatorRecordFlags::SCHEMA,
+        MetadataRecordFlags::SCHEMA,
+        FileBlockFlags::SCHEMA,
+        ServiceBlockFlags::SCHEMA,
+        FileEncryptionRecordFlags::SCHEMA,
+        FileTimeRecordFlags::SCHEMA,
+        FileSystemRedirectionRecordFlags::SCHEMA,
+        UnixOwnerRecordFlags::SCHEMA,
+        CryptBlockFlags::SCHEMA,
+        EndArchiveBlockFlags::SCHEMA,
+    ],
+    enums: &[
+        HostOs::SCHEMA,
+        CompressionAlgorithm::SCHEMA,
+        CompressionMethod::SCHEMA,
+        FileSystemRedirectionType::SCHEMA,
+        EncryptionVersion::SCHEMA,
+    ],
+};
@@ -0,0 +1,410 @@
+use std::{borrow::Cow, io};
+
+use crate::{
+    credentials::{CredentialProvider, PasswordRequest},
+    crypt::Rar50Keys,
+    error::{Error, RarResult},
+    hash::{Blake2sp, Crc32},
+    limits::Limits,
+    unpack::Unpack50,
+};
+
+use super::{CompressionAlgorithm, CompressionMethod, DecryptReader, FileBlock, FileHash};
+
+enum Inner<R> {
+    Store(R),
+    Unpack(Box<Unpack50<R>>),
+}
+
+/// Decompresses the data area of a [`FileBlock`].
+///
+/// The reader must be positioned at the start of the data area and should stop at its end,
+/// for example by wrapping it with [`io::Read::take`].
+///
+/// Once all the data has been read, the CRC32 and BLAKE2sp hashes stored in the header
+/// are checked against the decompressed data, and a mismatch is reported as an
+/// [`io::ErrorKind::InvalidData`] error.
+pub struct Decoder<R> {
+    inner: Inner<R>,
+
+    /// Number of bytes left to read, if the unpacked size is known.
+    remaining: Option<u64>,
+
+    crc32: Option<(Crc32, u32)>,
+    blake2sp: Option<(Blake2sp, [u8; 32])>,
+
+    /// Keys used to compute the MACs stored in place of the hashes of encrypted files.
+    mac_keys: Option<Rar50Keys>,
+
+    /// Limits applied to the window when it grows for the next file of a solid stream.
+    limits: Limits,
+
+    buf: Vec<u8>,
+    buf_pos: usize,
+    done: bool,
+}
+
+impl<R: io::Read> Decoder<R> {
+    /// Create a decoder for the data of `file` read from `reader`.
+    ///
+    /// Returns an [`io::ErrorKind::Unsupported`] error if the file is encrypted, is part of
+    /// a solid stream, or uses an unknown compression algorithm. Encrypted files can be
+    /// decoded with [`Decoder::with_password`], and solid files with
+    /// [`Decoder::continue_solid`].
+    ///
+    /// The window is limited to the default [`Limits::max_window_size`].
+    pub fn new(reader: R, file: &FileBlock) -> io::Result<Self> {
+        Self::with_limits(reader, file, &Limits::default())
+    }
+
+    /// Create a decoder like [`Self::new`], failing with
+    /// [`Error::LimitExceeded`](crate::Error::LimitExceeded) instead of allocating a window
+    /// larger than [`Limits::max_window_size`].
+    pub fn with_limits(reader: R, file: &FileBlock, limits: &Limits) -> io::Result<Self> {
+        if file.encryption.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "encrypted files need a password",
+            ));
+        }
+
+        Self::with_reader(reader, file, limits)
+    }
+
+    fn with_reader(reader: R, file: &FileBlock, limits: &Limits) -> io::Result<Self> {
+        let unsupported = |message| io::Error::new(io::ErrorKind::Unsupported, message);
+
+        let info = &file.compression_info;
+
+        let inner = match info.method() {
+            CompressionMethod::NoCompression => Inner::Store(reader),
+            CompressionMethod::Unknown(_) => {
+                return Err(unsupported("unknown compression method"));
+            }
+            _ => {
+                let extended_distances = extended_distances(file)?;
+
+                if info.is_solid() {
+                    return Err(unsupported(
+                        "solid files must be decoded after the previous files of the stream",
+                    ));
+                }
+
+                let dictionary_size = dictionary_size(file)?;
+
+                // The window never needs to be larger than the file, unless it's followed
+                // by solid files, in which case it grows in Self::continue_solid.
+                let window_size = match file.unpacked_size {
+                    Some(size) => size.min(dictionary_size),
+                    None => dictionary_size,
+                };
+                let window_size = limits.window_size(window_size)?;
+
+                Inner::Unpack(Box::new(Unpack50::new(
+                    reader,
+                    window_size,
+                    extended_distances,
+                )))
+            }
+        };
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            method = ?info.method(),
+            algorithm = ?info.algorithm(),
+            unpacked_size = file.unpacked_size,
+            "decoding file data"
+        );
+
+        Ok(Self::with_inner(inner, file, *limits))
+    }
+
+    fn with_inner(inner: Inner<R>, file: &FileBlock, limits: Limits) -> Self {
+        let mut decoder = Self {
+            inner,
+            remaining: None,
+            crc32: None,
+            blake2sp: None,
+            mac_keys: None,
+            limits,
+            buf: vec![],
+            buf_pos: 0,
+            done: false,
+        };
+        decoder.start_file(file);
+        decoder
+    }
+
+    /// Reset the state of the decoder for the data of `file`.
+    fn start_file(&mut self, file: &FileBlock) {
+        self.remaining = file.unpacked_size;
+        self.crc32 = file.unpacked_data_crc32.map(|crc| (Crc32::new(), crc));
+        self.blake2sp = match &file.hash {
+            Some(record) => match record.hash {
+                FileHash::Blake2Sp(hash) => Some((Blake2sp::new(), hash)),
+                FileHash::Unknown(_) => None,
+            },
+            None => None,
+        };
+        self.buf.clear();
+        self.buf_pos = 0;
+        self.done = false;
+    }
+
+    /// Continue decoding the solid stream with the next `file`.
+    ///
+    /// Solid files are compressed with the window left by the files before them, so they
+    /// must be decoded in order with the same decoder, starting from the first file of the
+    /// stream, which is not solid. [`Archive::solid_plan`](crate::compat::Archive::solid_plan)
+    /// lists the files to decode.
+    ///
+    /// The rest of the current file is decoded and checked first, then `seek` is called
+    /// with the reader to move it to the start of the data area of `file`.
+    ///
+    /// Returns an [`io::ErrorKind::InvalidInput`] error if `file` is not solid or if the
+    /// current file is stored, and an [`io::ErrorKind::Unsupported`] error if `file` is
+    /// encrypted or uses an unknown compression algorithm.
+    pub fn continue_solid(
+        &mut self,
+        file: &FileBlock,
+        seek: impl FnOnce(&mut R) -> io::Result<()>,
+    ) -> io::Result<()> {
+        self.skip(u64::MAX)?;
+
+        let invalid_input = |message| io::Error::new(io::ErrorKind::InvalidInput, message);
+
+        let info = &file.compression_info;
+        if !info.is_solid() || matches!(info.method(), CompressionMethod::NoCompression) {
+            return Err(invalid_input("file doesn't continue a solid stream"));
+        }
+
+        if file.encryption.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "encrypted solid files are not supported",
+            ));
+        }
+
+        let Inner::Unpack(unpack) = &mut self.inner else {
+            return Err(invalid_input("stored files aren't part of a solid stream"));
+        };
+
+        let extended_distances = extended_distances(file)?;
+        let window_size = self.limits.window_size(dictionary_size(file)?)?;
+
+        seek(unpack.get_mut())?;
+        unpack.next_file(window_size, extended_distances);
+
+        self.start_file(file);
+
+        Ok(())
+    }
+
+    /// Unwrap the reader of the data area.
+    pub fn into_inner(self) -> R {
+        match self.inner {
+            Inner::Store(reader) => reader,
+            Inner::Unpack(unpack) => unpack.into_inner(),
+        }
+    }
+
+    fn read_data(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let max_len = match self.remaining {
+            Some(remaining) => buf.len().min(remaining.try_into().unwrap_or(usize::MAX)),
+            None => buf.len(),
+        };
+        let buf = &mut buf[..max_len];
+
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        match &mut self.inner {
+            Inner::Store(reader) => reader.read(buf),
+            Inner::Unpack(unpack) => {
+                if self.buf_pos == self.buf.len() {
+                    self.buf.clear();
+                    self.buf_pos = 0;
+                    unpack.decode(&mut self.buf)?;
+                }
+
+                let n = buf.len().min(self.buf.len() - self.buf_pos);
+                buf[..n].copy_from_slice(&self.buf[self.buf_pos..self.buf_pos + n]);
+                self.buf_pos += n;
+                Ok(n)
+            }
+        }
+    }
+
+    fn verify(&mut self) -> io::Result<()> {
+        if matches!(self.remaining, Some(remaining) if remaining > 0) {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        if let Some((hasher, expected)) = self.crc32.take() {
+            let mut crc32 = hasher.finalize();
+            if let Some(keys) = &self.mac_keys {
+                crc32 = keys.crc32_mac(crc32);
+            }
+
+            if crc32 != expected {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "CRC32 of the unpacked data doesn't match",
+                ));
+            }
+        }
+
+        if let Some((hasher, expected)) = self.blake2sp.take() {
+            let mut hash = hasher.finalize();
+            if let Some(keys) = &self.mac_keys {
+                hash = keys.blake2sp_mac(&hash);
+            }
+
+            if hash != expected {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "BLAKE2sp hash of the unpacked data doesn't match",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Skip the next `n` bytes of unpacked data and return the number of bytes skipped,
+    /// which is less than `n` only if the end of the data was reached.
+    ///
+    /// The data is decoded and discarded without allocating, so the hashes are still
+    /// checked when the end of the data is reached.
+    pub fn skip(&mut self, n: u64) -> io::Result<u64> {
+        io::copy(&mut io::Read::take(self, n), &mut io::sink())
+    }
+}
+
+fn extended_distances(file: &FileBlock) -> io::Result<bool> {
+    match file.compression_info.algorithm() {
+        CompressionAlgorithm::Pack5 => Ok(false),
+        CompressionAlgorithm::Pack7 => Ok(true),
+        CompressionAlgorithm::Unknown(_) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "unknown compression algorithm",
+        )),
+    }
+}
+
+fn dictionary_size(file: &FileBlock) -> io::Result<u64> {
+    file.compression_info
+        .min_dictionary_size()
+        .map_err(|_| io::Error::new(io::ErrorKind::Unsupported, "dictionary size is too large"))
+}
+
+impl<R: io::Read> Decoder<DecryptReader<R>> {
+    /// Create a decoder for the data of the encrypted `file` read from `reader`, which is
+    /// decrypted with `password`.
+    ///
+    /// Returns [`Error::IncorrectPassword`][crate::Error::IncorrectPassword] if the password doesn't match the check value
+    /// stored in the file. If the file doesn't store one, a wrong password is reported
+    /// like a checksum mismatch once all the data has been read. Returns an
+    /// [`io::ErrorKind::InvalidInput`] error if the file is not encrypted.
+    pub fn with_password(reader: R, file: &FileBlock, password: &str) -> RarResult<Self> {
+        let Some(record) = &file.encryption else {
+            return Err(
+                io::Error::new(io::ErrorKind::InvalidInput, "file is not encrypted").into(),
+            );
+        };
+
+        let reader = DecryptReader::new(reader, record, password)?;
+
+        // The checksums of encrypted files are replaced by their MACs.
+        let mac_keys = record
+            .flags
+            .uses_mac_checksum()
+            .then(|| reader.keys().clone());
+
+        let mut decoder = Self::with_reader(reader, file, &Limits::default())?;
+        decoder.mac_keys = mac_keys;
+
+        Ok(decoder)
+    }
+
+    /// Create a decoder for the data of the encrypted `file` like
+    /// [`with_password`](Self::with_password), with the password returned by `provider`
+    /// for the file.
+    ///
+    /// Returns [`Error::PasswordRequired`][crate::Error::PasswordRequired] if the provider
+    /// doesn't return a password.
+    pub fn with_credentials(
+        reader: R,
+        file: &FileBlock,
+        provider: &mut dyn CredentialProvider,
+    ) -> RarResult<Self> {
+        if file.encryption.is_none() {
+            return Err(
+                io::Error::new(io::ErrorKind::InvalidInput, "file is not encrypted").into(),
+            );
+        }
+
+        let name = match &file.name {
+            Ok(name) => Cow::Borrowed(name.as_str()),
+            Err(name) => String::from_utf8_lossy(name),
+        };
+        let password = provider
+            .password(PasswordRequest::File { name: &name })
+            .ok_or(Error::PasswordRequired)?;
+
+        Self::with_password(reader, file, &password)
+    }
+}
+
+impl<R: io::Read + io::Seek> Decoder<R> {
+    /// Skip the next `n` bytes of unpacked data like [`Self::skip`], but seek over the data
+    /// of stored files instead of reading it.
+    ///
+    /// The hashes of stored files can't be checked after seeking over some of the data.
+    /// Stored files of unknown size are read and discarded like in [`Self::skip`], since
+    /// their end can't be known without reading.
+    pub fn seek_forward(&mut self, n: u64) -> io::Result<u64> {
+        let (Inner::Store(reader), Some(remaining)) = (&mut self.inner, &mut self.remaining) else {
+            return self.skip(n);
+        };
+
+        let n = n.min(*remaining);
+        if n > 0 {
+            reader.seek(io::SeekFrom::Current(n as i64))?;
+            *remaining -= n;
+            self.crc32 = None;
+            self.blake2sp = None;
+        }
+
+        Ok(n)
+    }
+}
+
+impl<R: io::Read> io::Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let n = self.read_data(buf)?;
+
+        if n == 0 {
+            self.done = true;
+            self.verify()?;
+            return Ok(0);
+        }
+
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= n as u64;
+        }
+        if let Some((hasher, _)) = &mut self.crc32 {
+            hasher.update(&buf[..n]);
+        }
+        if let Some((hasher, _)) = &mut self.blake2sp {
+            hasher.update(&buf[..n]);
+        }
+
+        Ok(n)
+    }
+}
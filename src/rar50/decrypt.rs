@@ -0,0 +1,122 @@
+use alloc::vec::Vec;
+
+use crate::{
+    crypt::{self, CbcDecryptor, Rar50Keys},
+    error::{Error, RarResult},
+    io,
+};
+
+use super::{EncryptionVersion, FileEncryptionRecord};
+
+/// Size of the buffer of decrypted data, which must be a multiple of the AES block size.
+const BUF_SIZE: usize = 0x1000;
+
+/// Decrypts the data area of a file encrypted with a [`FileEncryptionRecord`].
+///
+/// The reader must be positioned at the start of the data area and should stop at its end,
+/// for example by wrapping it with [`io::Read::take`]. The encrypted data is padded to the
+/// AES block size, so the decrypted data may be followed by up to 15 bytes of padding.
+pub struct DecryptReader<R> {
+    reader: R,
+    decryptor: CbcDecryptor,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    keys: Rar50Keys,
+    buf: Vec<u8>,
+    buf_pos: usize,
+}
+
+impl<R: io::Read> DecryptReader<R> {
+    /// Derive the key from `password` and the salt of `record` to decrypt the data read
+    /// from `reader`.
+    ///
+    /// Returns [`Error::IncorrectPassword`] if the record contains a password check value
+    /// which doesn't match the password. Otherwise, a wrong password is only noticed when
+    /// the checksums of the decrypted data don't match.
+    pub fn new(reader: R, record: &FileEncryptionRecord, password: &str) -> RarResult<Self> {
+        if let EncryptionVersion::Unknown(_) = record.encryption_version {
+            return Err(
+                io::Error::new(io::ErrorKind::Unsupported, "unknown encryption version").into(),
+            );
+        }
+
+        let keys =
+            Rar50Keys::derive(password, &record.salt, record.kdf_count).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "KDF iteration count is too large",
+                )
+            })?;
+
+        if let Some(check_value) = &record.check_value {
+            if !keys.is_password_correct(check_value) {
+                return Err(Error::IncorrectPassword);
+            }
+        }
+
+        Ok(Self {
+            reader,
+            decryptor: CbcDecryptor::new(&keys.key, record.iv),
+            keys,
+            buf: Vec::with_capacity(BUF_SIZE),
+            buf_pos: 0,
+        })
+    }
+
+    /// Keys derived from the password.
+    #[cfg(feature = "std")]
+    pub(crate) fn keys(&self) -> &Rar50Keys {
+        &self.keys
+    }
+
+    /// Unwrap the reader of the encrypted data.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Read and decrypt the next chunk of data, which is empty at the end of the data.
+    fn fill_buf(&mut self) -> io::Result<()> {
+        self.buf.resize(BUF_SIZE, 0);
+        let mut len: usize = 0;
+
+        // Read until the end of the data or until the buffer holds whole blocks.
+        while len == 0 || !len.is_multiple_of(crypt::BLOCK_SIZE) {
+            let n = self.reader.read(&mut self.buf[len..])?;
+            if n == 0 {
+                break;
+            }
+            len += n;
+        }
+
+        if !len.is_multiple_of(crypt::BLOCK_SIZE) {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "encrypted data is not aligned to the AES block size",
+            ));
+        }
+
+        self.buf.truncate(len);
+        self.buf_pos = 0;
+        self.decryptor.decrypt(&mut self.buf);
+
+        Ok(())
+    }
+}
+
+impl<R: io::Read> io::Read for DecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.buf_pos == self.buf.len() {
+            self.fill_buf()?;
+        }
+
+        let n = buf.len().min(self.buf.len() - self.buf_pos);
+        buf[..n].copy_from_slice(&self.buf[self.buf_pos..self.buf_pos + n]);
+        self.buf_pos += n;
+        Ok(n)
+    }
+}
+
+impl<R> core::fmt::Debug for DecryptReader<R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DecryptReader").finish_non_exhaustive()
+    }
+}
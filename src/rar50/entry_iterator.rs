@@ -0,0 +1,108 @@
+use alloc::vec::Vec;
+
+use crate::{error::RarResult, io, warning::Warning};
+
+use super::{Block, BlockIterator, BlockKind, FileBlock, ServiceBlock};
+
+#[derive(Debug)]
+/// File block and the service blocks which depend on it, like its alternate data streams
+/// and NTFS permissions.
+pub struct Entry {
+    /// Block of kind [`BlockKind::File`].
+    pub block: Block,
+
+    /// Service blocks following the file block which are marked as its children.
+    pub children: Vec<Block>,
+}
+
+serialize_struct!(Entry { block, children });
+
+impl Entry {
+    /// File block of the entry.
+    pub fn file(&self) -> &FileBlock {
+        match &self.block.kind {
+            BlockKind::File(file) => file,
+            _ => unreachable!("entries are only created from file blocks"),
+        }
+    }
+
+    /// Service blocks of the children.
+    pub fn services(&self) -> impl Iterator<Item = &ServiceBlock> {
+        self.children.iter().filter_map(|block| match &block.kind {
+            BlockKind::Service(service) => Some(service),
+            _ => None,
+        })
+    }
+}
+
+/// Iterator over the file entries of an archive, which groups each file block with the
+/// child service blocks that follow it.
+///
+/// Blocks which don't belong to a file entry, like the main block and the archive comment,
+/// are skipped. Errors returned by the [`BlockIterator`] are passed through after the
+/// entry that precedes them.
+pub struct EntryIterator<R: io::Read + io::Seek> {
+    blocks: BlockIterator<R>,
+
+    /// Block read after the children of the last entry.
+    next_block: Option<RarResult<Block>>,
+}
+
+impl<R: io::Read + io::Seek> EntryIterator<R> {
+    pub fn new(blocks: BlockIterator<R>) -> Self {
+        Self {
+            blocks,
+            next_block: None,
+        }
+    }
+
+    /// Warnings about the blocks read so far.
+    pub fn warnings(&self) -> &[Warning] {
+        self.blocks.warnings()
+    }
+
+    /// Take the warnings about the blocks read so far, leaving the list empty.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        self.blocks.take_warnings()
+    }
+
+    /// Unwrap the iterator over the blocks.
+    pub fn into_inner(self) -> BlockIterator<R> {
+        self.blocks
+    }
+}
+
+impl<R: io::Read + io::Seek> Iterator for EntryIterator<R> {
+    type Item = RarResult<Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let block = match self.next_block.take().or_else(|| self.blocks.next())? {
+                Ok(block) => block,
+                Err(e) => return Some(Err(e)),
+            };
+
+            if !matches!(block.kind, BlockKind::File(_)) {
+                continue;
+            }
+
+            let mut children = vec![];
+            loop {
+                match self.blocks.next() {
+                    Some(Ok(child))
+                        if child.flags.is_child()
+                            && matches!(child.kind, BlockKind::Service(_)) =>
+                    {
+                        children.push(child)
+                    }
+                    next => {
+                        self.next_block = next;
+                        break;
+                    }
+                }
+            }
+
+            return Some(Ok(Entry { block, children }));
+        }
+    }
+}
@@ -1,6 +1,7 @@
-use std::{io, ops::Range};
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
 
-use crate::{read::*, time_conv};
+use crate::{io, read::*, time_conv};
 
 pub fn read_unix_time_nanos<R: io::Read>(
     reader: &mut R,
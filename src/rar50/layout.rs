@@ -0,0 +1,107 @@
+use alloc::vec::Vec;
+
+use crate::layout::{HeaderField, Layout};
+
+use super::{Block, FileBlock, MainBlock, ServiceBlock};
+
+/// Split the raw bytes of an unencrypted block `header` found at `offset` into its fields,
+/// like the ones returned by [`RawHeaderReader::raw_header`](crate::RawHeaderReader::raw_header).
+///
+/// The fields of the common header, of the main, file, service, encryption and end of
+/// archive blocks are listed separately, and each record of the extra area is listed as
+/// its size, its type and its data. Bytes after the known fields are listed as
+/// `"unknown"`, and the list stops at the first field which doesn't fit in the header.
+pub fn header_layout(header: &[u8], offset: u64) -> Vec<HeaderField> {
+    let mut layout = Layout::new(header, offset);
+    read_fields(&mut layout, header.len());
+    layout.into_fields()
+}
+
+fn read_fields(layout: &mut Layout, header_len: usize) -> Option<()> {
+    layout.hex_u32("header crc32")?;
+    layout.vint("header size")?;
+    let header_type = layout.vint("header type")?;
+    let flags = layout.hex_vint("flags")?;
+    let extra_area_size = if flags & 0x0001 != 0 {
+        layout.vint("extra area size")?
+    } else {
+        0
+    };
+    if flags & 0x0002 != 0 {
+        layout.vint("data size")?;
+    }
+
+    match header_type {
+        Block::MAIN => {
+            let flags = layout.hex_vint("archive flags")?;
+            if flags & 0x0002 != 0 {
+                layout.vint("volume number")?;
+            }
+        }
+        Block::FILE | Block::SERVICE => {
+            let flags = layout.hex_vint("file flags")?;
+            layout.vint("unpacked size")?;
+            layout.hex_vint("attributes")?;
+            if flags & 0x0002 != 0 {
+                layout.u32("modification time")?;
+            }
+            if flags & 0x0004 != 0 {
+                layout.hex_u32("data crc32")?;
+            }
+            layout.hex_vint("compression info")?;
+            layout.vint("host os")?;
+            let name_length = layout.vint("name length")?;
+            layout.text("name", usize::try_from(name_length).ok()?)?;
+        }
+        Block::CRYPT => {
+            layout.vint("encryption version")?;
+            let flags = layout.hex_vint("encryption flags")?;
+            layout.u8("kdf count")?;
+            layout.bytes("salt", 16)?;
+            if flags & 0x0001 != 0 {
+                layout.bytes("check value", 12)?;
+            }
+        }
+        Block::ENDARC => {
+            layout.hex_vint("end of archive flags")?;
+        }
+        _ => {}
+    }
+
+    if extra_area_size > 0 {
+        let extra_area_start = header_len.checked_sub(usize::try_from(extra_area_size).ok()?)?;
+        layout.until("body", extra_area_start);
+        read_records(layout, header_type, header_len)?;
+    }
+
+    layout.rest("unknown");
+    Some(())
+}
+
+/// Records of the extra area, which takes the rest of the header.
+fn read_records(layout: &mut Layout, header_type: u64, header_len: usize) -> Option<()> {
+    while layout.pos() < header_len {
+        let record_size = usize::try_from(layout.vint("record size")?).ok()?;
+        let start = layout.pos();
+        let record_type = layout.vint("record type")?;
+        let data_size = record_size.checked_sub(layout.pos() - start)?;
+        layout.bytes(record_name(header_type, record_type), data_size)?;
+    }
+
+    Some(())
+}
+
+fn record_name(header_type: u64, record_type: u64) -> &'static str {
+    match (header_type, record_type) {
+        (Block::MAIN, MainBlock::LOCATOR) => "locator record",
+        (Block::MAIN, MainBlock::METADATA) => "metadata record",
+        (Block::FILE | Block::SERVICE, FileBlock::CRYPT) => "encryption record",
+        (Block::FILE | Block::SERVICE, FileBlock::HASH) => "hash record",
+        (Block::FILE | Block::SERVICE, FileBlock::HTIME) => "file time record",
+        (Block::FILE | Block::SERVICE, FileBlock::VERSION) => "file version record",
+        (Block::FILE | Block::SERVICE, FileBlock::REDIR) => "redirection record",
+        (Block::FILE | Block::SERVICE, FileBlock::UOWNER) => "unix owner record",
+        (Block::SERVICE, ServiceBlock::SERVICE_DATA) => "service data record",
+        _ => "unknown record",
+    }
+}
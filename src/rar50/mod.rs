@@ -1,9 +1,32 @@
+#[cfg(feature = "async")]
+mod async_block_iterator;
 mod block_iterator;
 mod blocks;
+#[cfg(feature = "std")]
+mod decoder;
+mod decrypt;
+mod entry_iterator;
 mod helpers;
+mod layout;
+mod parser;
+mod quick_open;
 mod record_iterator;
+mod recovery_record;
+mod round_trip;
+mod stream_block_iterator;
 
+#[cfg(feature = "async")]
+pub use async_block_iterator::AsyncBlockIterator;
 pub use block_iterator::*;
 pub use blocks::*;
+#[cfg(feature = "std")]
+pub use decoder::Decoder;
+pub use decrypt::DecryptReader;
+pub use entry_iterator::*;
+pub use layout::header_layout;
+pub use parser::{Event, Parser};
+pub use quick_open::*;
+pub use recovery_record::*;
+pub use stream_block_iterator::StreamBlockIterator;
 
 const MAX_PATH_SIZE: u64 = 0x10000;
@@ -0,0 +1,136 @@
+use alloc::vec::Vec;
+
+use crate::{
+    cross_check::HeaderCursor,
+    hash::Crc32,
+    io::{self, Read as _},
+    read::{read_u32, read_vint},
+    size::BlockSize as _,
+};
+
+use super::{Block, BlockKind, CompressionMethod, ParseOptions, ServiceBlock, ServiceBlockKind};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Copy of a block header stored in a [`QuickOpenCache`].
+pub struct CachedHeader {
+    /// Flags of the cache record. No flags are currently defined.
+    pub flags: u64,
+
+    /// Offset of the block in the archive.
+    pub offset: u64,
+
+    /// Raw bytes of the block header, starting with its CRC32.
+    pub header: Vec<u8>,
+}
+
+impl CachedHeader {
+    /// Parse the cached block header as if it was read at [`Self::offset`].
+    pub fn block(&self, options: &ParseOptions) -> io::Result<Block> {
+        Block::read_with_options(&mut HeaderCursor::new(&self.header, self.offset), options)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Data area of a QuickOpen service block.
+///
+/// Archives created with the `-qo` switch store copies of the headers of the file and
+/// service blocks in the data area of a QuickOpen service block placed at the end of the
+/// archive, so that they can be listed without seeking through the whole archive.
+///
+/// The QuickOpen block can be found using the [`LocatorRecord`](super::LocatorRecord) of
+/// the main block.
+pub struct QuickOpenCache {
+    /// Cached headers, in the order they appear in the archive.
+    pub headers: Vec<CachedHeader>,
+}
+
+impl QuickOpenCache {
+    /// Maximum size of a cached header, which is the same as the one used by unrar.
+    const MAX_HEADER_SIZE: u64 = 0x20_0000;
+
+    /// Parse the data area of the QuickOpen service block at `block_offset`.
+    ///
+    /// Returns an [`io::ErrorKind::InvalidData`] error if the CRC32 of a record doesn't
+    /// match or if it references a block before the start of the archive.
+    pub fn parse(data: &[u8], block_offset: u64) -> io::Result<Self> {
+        let invalid_data = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut headers = vec![];
+        let mut data = data;
+
+        while !data.is_empty() {
+            let stored_crc32 = read_u32(&mut data)?;
+            let record_start = data;
+
+            let (size, vint_size) = read_vint(&mut data)?;
+            let size = usize::try_from(size)
+                .ok()
+                .filter(|&size| size <= data.len())
+                .ok_or_else(|| invalid_data("QuickOpen record exceeds the data area"))?;
+            let (record, rest) = data.split_at(size);
+            data = rest;
+
+            let mut crc = Crc32::new();
+            crc.update(&record_start[..vint_size as usize + size]);
+            if crc.finalize() != stored_crc32 {
+                return Err(invalid_data("QuickOpen record CRC32 doesn't match"));
+            }
+
+            let mut record = record;
+            let (flags, _) = read_vint(&mut record)?;
+            let (offset, _) = read_vint(&mut record)?;
+            let (header_size, _) = read_vint(&mut record)?;
+
+            if header_size > Self::MAX_HEADER_SIZE || header_size > record.len() as u64 {
+                return Err(invalid_data("QuickOpen header exceeds the record"));
+            }
+
+            // Offsets are relative to the start of the QuickOpen block.
+            let offset = block_offset
+                .checked_sub(offset)
+                .ok_or_else(|| invalid_data("QuickOpen header is before the archive"))?;
+
+            headers.push(CachedHeader {
+                flags,
+                offset,
+                header: record[..header_size as usize].to_vec(),
+            });
+        }
+
+        Ok(Self { headers })
+    }
+
+    /// Read the data area of the QuickOpen service `block` from `reader` and parse it.
+    ///
+    /// Returns an [`io::ErrorKind::Unsupported`] error if `block` is not a QuickOpen block,
+    /// or if its data is compressed or encrypted.
+    pub fn read<R: io::Read + io::Seek>(reader: &mut R, block: &Block) -> io::Result<Self> {
+        let unsupported = |message| io::Error::new(io::ErrorKind::Unsupported, message);
+
+        let BlockKind::Service(ServiceBlock {
+            kind: ServiceBlockKind::QuickOpen(_),
+            compression_info,
+            encryption,
+            ..
+        }) = &block.kind
+        else {
+            return Err(unsupported("not a QuickOpen block"));
+        };
+
+        if compression_info.method() != CompressionMethod::NoCompression {
+            return Err(unsupported("compressed QuickOpen data is not supported"));
+        }
+        if encryption.is_some() {
+            return Err(unsupported("encrypted QuickOpen data is not supported"));
+        }
+
+        reader.seek(io::SeekFrom::Start(block.offset() + block.header_size()))?;
+        let mut data = vec![];
+        reader.take(block.data_size()).read_to_end(&mut data)?;
+        if (data.len() as u64) < block.data_size() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Self::parse(&data, block.offset())
+    }
+}
@@ -1,6 +1,6 @@
-use std::io;
+use alloc::vec::Vec;
 
-use crate::read::*;
+use crate::{error::Error, io, limits::Limits, read::*};
 
 pub struct CommonRecord {
     pub record_type: u64,
@@ -11,10 +11,11 @@ pub struct RecordIterator<'a, R: io::Read + io::Seek> {
     reader: &'a mut R,
     end_offset: u64,
     next_record_offset: u64,
+    limits: &'a Limits,
 }
 
 impl<'r, R: io::Read + io::Seek> RecordIterator<'r, R> {
-    pub fn new(reader: &'r mut R, extra_area_size: u64) -> io::Result<Self> {
+    pub fn new(reader: &'r mut R, extra_area_size: u64, limits: &'r Limits) -> io::Result<Self> {
         let offset = reader.stream_position()?;
         let end_offset = offset + extra_area_size;
         let next_record_offset = offset;
@@ -23,6 +24,7 @@ impl<'r, R: io::Read + io::Seek> RecordIterator<'r, R> {
             reader,
             end_offset,
             next_record_offset,
+            limits,
         })
     }
 
@@ -33,9 +35,24 @@ impl<'r, R: io::Read + io::Seek> RecordIterator<'r, R> {
         let (record_size, byte_size) = read_vint(self.reader)?;
         let (record_type, type_byte_size) = read_vint(self.reader)?;
 
-        let data = read_vec(self.reader, record_size as usize - type_byte_size as usize)?;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            offset = self.next_record_offset,
+            record_type,
+            size = record_size,
+            "read record"
+        );
+
+        let data_size = self
+            .limits
+            .record_size(record_size)?
+            .checked_sub(type_byte_size as usize)
+            .ok_or(Error::CorruptHeader)?;
+        let data = read_vec(self.reader, data_size)?;
 
-        self.next_record_offset += record_size + byte_size as u64;
+        self.next_record_offset = (self.next_record_offset + byte_size as u64)
+            .checked_add(record_size)
+            .ok_or(Error::CorruptHeader)?;
 
         Ok(CommonRecord {
             record_type,
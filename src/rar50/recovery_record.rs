@@ -0,0 +1,296 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{
+    hash::Crc64,
+    io::{self, Read as _},
+    read::{read_u16, read_u32, read_u64},
+    size::BlockSize as _,
+};
+
+use super::{Block, BlockKind, CompressionMethod, ServiceBlock, ServiceBlockKind};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Data area of a recovery record service block.
+///
+/// The part of the archive before the recovery record is split in data shards of
+/// [`Self::shard_size`] bytes, and the data area is a sequence of chunks of the same size,
+/// each made of a header followed by one Reed-Solomon recovery shard. The header of every
+/// chunk starts with a `{RB}` marker and the CRC64 of the rest of the chunk, and stores
+/// the sizes of the shards along with the CRC64 of each data shard.
+///
+/// The layout is undocumented and was worked out from the recovery records written by
+/// RAR 7, so only the fields needed to find damaged data are parsed, and the archive
+/// can't be repaired from the recovery shards.
+pub struct RecoveryRecord {
+    /// Offset of the data area in the file.
+    pub data_offset: u64,
+
+    /// Size of each chunk, including its header.
+    pub chunk_size: u32,
+
+    /// Size of the header of each chunk, after which its recovery shard starts.
+    pub header_size: u32,
+
+    /// Size of the part of the archive protected by the recovery record, starting from
+    /// its signature.
+    pub protected_size: u64,
+
+    /// Size of the data shards and of the recovery shards. The last data shard ends at
+    /// [`Self::protected_size`], so it can be shorter.
+    pub shard_size: u64,
+
+    /// CRC64 of each data shard, without the inversions of the register.
+    pub shard_crcs: Vec<u64>,
+
+    /// Recovery shard of each chunk, or `None` if the CRC64 of the chunk doesn't match.
+    pub recovery_shards: Vec<Option<Vec<u8>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Damaged bytes found by [`RecoveryRecord::validate`].
+pub struct DamagedRange {
+    /// Offsets of the damaged bytes in the file.
+    ///
+    /// Damaged data shards are reported whole, and so are the damaged chunks of the
+    /// recovery record itself.
+    pub range: Range<u64>,
+
+    /// The bytes can be rebuilt from the rest of the archive and the recovery record,
+    /// because there are at least as many intact recovery shards as damaged data shards.
+    pub recoverable: bool,
+}
+
+/// Fields of the header of a chunk, which are the same in all the chunks except for the
+/// index of the chunk.
+#[derive(PartialEq, Eq)]
+struct ChunkHeader {
+    chunk_size: u32,
+    header_size: u32,
+    last_shard_size: u32,
+    protected_size: u64,
+    shard_size: u64,
+    data_shards: u16,
+    recovery_shards: u16,
+}
+
+impl RecoveryRecord {
+    /// Marker at the start of the header of each chunk.
+    const MARKER: &'static [u8; 4] = b"{RB}";
+
+    /// Size of the fields of the header of a chunk before the CRC64 of the data shards.
+    const FIXED_HEADER_SIZE: usize = 64;
+
+    /// Parse the `data` area of the recovery record service `block`.
+    ///
+    /// Returns an [`io::ErrorKind::InvalidData`] error if the first chunk doesn't start
+    /// with the marker, if none of the chunks is intact, or if the sizes stored in the
+    /// chunks don't match each other, the data area or the offset of the block.
+    pub fn parse(block: &Block, data: &[u8]) -> io::Result<Self> {
+        let invalid_data = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        if !data.starts_with(Self::MARKER) {
+            return Err(invalid_data("recovery record chunk marker not found"));
+        }
+
+        let chunk_size = read_u32(&mut data.get(12..).unwrap_or_default())? as usize;
+        if chunk_size < Self::FIXED_HEADER_SIZE || !data.len().is_multiple_of(chunk_size) {
+            return Err(invalid_data(
+                "recovery record chunks don't fill the data area",
+            ));
+        }
+
+        let mut header: Option<(ChunkHeader, &[u8])> = None;
+        let mut recovery_shards = vec![];
+        for (index, chunk) in data.chunks_exact(chunk_size).enumerate() {
+            let Some((chunk_header, shard_crcs)) = parse_chunk(chunk, index)? else {
+                recovery_shards.push(None);
+                continue;
+            };
+            recovery_shards.push(Some(chunk[chunk_header.header_size as usize..].to_vec()));
+
+            match &header {
+                Some((first, _)) if *first != chunk_header => {
+                    return Err(invalid_data("recovery record chunks don't match"));
+                }
+                Some(_) => {}
+                None => header = Some((chunk_header, shard_crcs)),
+            }
+        }
+
+        let Some((header, mut shard_crcs)) = header else {
+            return Err(invalid_data("recovery record has no intact chunks"));
+        };
+
+        let shard_size = header.shard_size;
+        let data_shards = header.data_shards as u64;
+        let protected_before_last = shard_size * (data_shards - 1);
+        if header.recovery_shards as usize != recovery_shards.len()
+            || header.chunk_size as u64 != header.header_size as u64 + shard_size
+            || header.protected_size <= protected_before_last
+            || header.protected_size - protected_before_last != header.last_shard_size as u64
+            || header.last_shard_size as u64 > shard_size
+        {
+            return Err(invalid_data("recovery record sizes don't match"));
+        }
+        if header.protected_size > block.offset() {
+            return Err(invalid_data(
+                "recovery record protects data after the block",
+            ));
+        }
+
+        let shard_crcs = (0..header.data_shards)
+            .map(|_| read_u64(&mut shard_crcs))
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self {
+            data_offset: block.offset() + block.header_size(),
+            chunk_size: header.chunk_size,
+            header_size: header.header_size,
+            protected_size: header.protected_size,
+            shard_size,
+            shard_crcs,
+            recovery_shards,
+        })
+    }
+
+    /// Read the data area of the recovery record service `block` from `reader` and parse it.
+    ///
+    /// Returns an [`io::ErrorKind::Unsupported`] error if `block` is not a recovery record
+    /// block, or if its data is compressed or encrypted.
+    pub fn read<R: io::Read + io::Seek>(reader: &mut R, block: &Block) -> io::Result<Self> {
+        let unsupported = |message| io::Error::new(io::ErrorKind::Unsupported, message);
+
+        let BlockKind::Service(ServiceBlock {
+            kind: ServiceBlockKind::RecoveryRecord(_),
+            compression_info,
+            encryption,
+            ..
+        }) = &block.kind
+        else {
+            return Err(unsupported("not a recovery record block"));
+        };
+
+        if compression_info.method() != CompressionMethod::NoCompression {
+            return Err(unsupported("compressed recovery records are not supported"));
+        }
+        if encryption.is_some() {
+            return Err(unsupported("encrypted recovery records are not supported"));
+        }
+
+        reader.seek(io::SeekFrom::Start(block.offset() + block.header_size()))?;
+        let mut data = vec![];
+        reader.take(block.data_size()).read_to_end(&mut data)?;
+        if (data.len() as u64) < block.data_size() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Self::parse(block, &data)
+    }
+
+    /// Check the data shards of the archive starting at `archive_offset`, the offset of
+    /// its signature, against the recovery record, and return the damaged ranges in order.
+    ///
+    /// Data shards whose CRC64 doesn't match are reported as damaged, and so are the
+    /// chunks of the recovery record whose CRC64 didn't match when it was parsed. The
+    /// bytes past the end of the file are missing, so the shards they're part of are
+    /// reported as damaged too.
+    pub fn validate<R: io::Read + io::Seek>(
+        &self,
+        reader: &mut R,
+        archive_offset: u64,
+    ) -> io::Result<Vec<DamagedRange>> {
+        let mut damaged_shards = vec![];
+
+        reader.seek(io::SeekFrom::Start(archive_offset))?;
+        let mut reader = reader.take(self.protected_size);
+        let mut shard = vec![0; self.shard_size as usize];
+        for (index, &stored_crc) in self.shard_crcs.iter().enumerate() {
+            let start = index as u64 * self.shard_size;
+            let size = self.shard_size.min(self.protected_size - start) as usize;
+
+            let mut filled = 0;
+            while filled < size {
+                match reader.read(&mut shard[filled..size])? {
+                    0 => break,
+                    read => filled += read,
+                }
+            }
+
+            let mut crc = Crc64::uninverted();
+            crc.update(&shard[..filled]);
+            if filled < size || crc.finalize() != stored_crc {
+                let offset = archive_offset + start;
+                damaged_shards.push(offset..offset + size as u64);
+            }
+        }
+
+        let intact_chunks = self.recovery_shards.iter().flatten().count();
+        let recoverable = damaged_shards.len() <= intact_chunks;
+
+        let chunk_size = self.chunk_size as u64;
+        let damaged_chunks = self
+            .recovery_shards
+            .iter()
+            .enumerate()
+            .filter(|(_, shard)| shard.is_none())
+            .map(|(index, _)| {
+                let offset = self.data_offset + index as u64 * chunk_size;
+                offset..offset + chunk_size
+            });
+
+        Ok(damaged_shards
+            .into_iter()
+            .chain(damaged_chunks)
+            .map(|range| DamagedRange { range, recoverable })
+            .collect())
+    }
+}
+
+/// Parse the header of the chunk at `index`, returning `None` if its marker or its CRC64
+/// don't match, and the CRC64 of the data shards along with the header otherwise.
+fn parse_chunk(chunk: &[u8], index: usize) -> io::Result<Option<(ChunkHeader, &[u8])>> {
+    let invalid_data = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    let (marker, mut fields) = chunk.split_at(RecoveryRecord::MARKER.len());
+    let stored_crc = read_u64(&mut fields)?;
+    let mut crc = Crc64::new();
+    crc.update(fields);
+    if marker != RecoveryRecord::MARKER || crc.finalize() != stored_crc {
+        return Ok(None);
+    }
+
+    let chunk_size = read_u32(&mut fields)?;
+    let header_size = read_u32(&mut fields)?;
+    let _version = read_u16(&mut fields)?;
+    let _unknown = read_u64(&mut fields)?;
+    let last_shard_size = read_u32(&mut fields)?;
+    let protected_size = read_u64(&mut fields)?;
+    let shard_size = read_u64(&mut fields)?;
+    let _chunk_size = read_u64(&mut fields)?;
+    let data_shards = read_u16(&mut fields)?;
+    let recovery_shards = read_u16(&mut fields)?;
+    let chunk_index = read_u16(&mut fields)?;
+
+    // The header ends with 8 more bytes after the CRC64 of the data shards.
+    let shard_crcs_size = data_shards as usize * 8;
+    if chunk_size as usize != chunk.len()
+        || data_shards == 0
+        || (header_size as usize) < RecoveryRecord::FIXED_HEADER_SIZE + shard_crcs_size
+        || header_size as usize > chunk.len()
+        || chunk_index as usize != index
+    {
+        return Err(invalid_data("recovery record chunk header is invalid"));
+    }
+
+    let header = ChunkHeader {
+        chunk_size,
+        header_size,
+        last_shard_size,
+        protected_size,
+        shard_size,
+        data_shards,
+        recovery_shards,
+    };
+    Ok(Some((header, &fields[..shard_crcs_size])))
+}
@@ -0,0 +1,349 @@
+use crate::{round_trip::HeaderWriter, time_conv};
+
+use super::{
+    Block, BlockKind, CryptBlock, FileBlock, FileEncryptionRecord, FileHash, FileHashRecord,
+    FileSystemRedirectionRecord, FileTimeRecord, FileVersionRecord, LocatorRecord, MainBlock,
+    MetadataRecord, ParseOptions, ServiceBlock, ServiceBlockKind, UnixOwnerRecord, UnknownRecord,
+};
+
+type Time = Result<time::OffsetDateTime, u64>;
+
+impl Block {
+    /// Serialize the header of the block back into the fields it was parsed from, with its
+    /// records in the order of their types, followed by the unknown records.
+    ///
+    /// Returns `None` if some of the records of the block were skipped by the `options`.
+    pub(crate) fn serialize_header(&self, options: &ParseOptions) -> Option<HeaderWriter> {
+        let skipped_records = match &self.kind {
+            BlockKind::Service(service) => match service.kind {
+                ServiceBlockKind::QuickOpen(_) => options.skip_quick_open,
+                ServiceBlockKind::RecoveryRecord(_) => options.skip_recovery_record,
+                _ => false,
+            },
+            _ => false,
+        };
+        if skipped_records || options.skip_unknown_record_data && self.has_unknown_records() {
+            return None;
+        }
+
+        let mut w = HeaderWriter::new();
+        w.u32(self.header_crc32);
+        // The size of the header doesn't include the CRC32 and the size itself.
+        w.sized(|w| {
+            let header_type = match &self.kind {
+                BlockKind::Main(_) => Self::MAIN,
+                BlockKind::File(_) => Self::FILE,
+                BlockKind::Service(_) => Self::SERVICE,
+                BlockKind::Crypt(_) => Self::CRYPT,
+                BlockKind::EndArchive(_) => Self::ENDARC,
+                BlockKind::Unknown(unknown) => unknown.tag,
+            };
+            w.vint(header_type);
+            w.vint(self.flags.bits() as u64);
+            if let Some(extra_area_size) = self.extra_area_size {
+                w.vint(extra_area_size);
+            }
+            if let Some(data_size) = self.data_size {
+                w.vint(data_size);
+            }
+
+            match &self.kind {
+                BlockKind::Main(main) => write_main(w, main),
+                BlockKind::File(file) => write_file(w, file),
+                BlockKind::Service(service) => write_service(w, service),
+                BlockKind::Crypt(crypt) => write_crypt(w, crypt),
+                BlockKind::EndArchive(end) => w.vint(end.flags.bits() as u64),
+                BlockKind::Unknown(unknown) => {
+                    w.bytes(&unknown.fields);
+                    write_unknown_records(w, &unknown.records);
+                }
+            }
+        });
+
+        Some(w)
+    }
+
+    fn has_unknown_records(&self) -> bool {
+        match &self.kind {
+            BlockKind::Main(main) => !main.unknown_records.is_empty(),
+            BlockKind::File(file) => !file.unknown_records.is_empty(),
+            BlockKind::Service(service) => !service.unknown_records.is_empty(),
+            BlockKind::Unknown(unknown) => !unknown.records.is_empty(),
+            BlockKind::Crypt(_) | BlockKind::EndArchive(_) => false,
+        }
+    }
+}
+
+/// Write a record of the extra area, whose size includes its type.
+fn write_record(w: &mut HeaderWriter, record_type: u64, data: &[u8]) {
+    w.sized(|w| {
+        w.vint(record_type);
+        w.bytes(data);
+    });
+}
+
+/// Write the records which were stored in a field of the block.
+fn write_known_record<T>(
+    w: &mut HeaderWriter,
+    record_type: u64,
+    record: &Option<T>,
+    write: fn(&mut HeaderWriter, &T),
+) {
+    if let Some(record) = record {
+        w.sized(|w| {
+            w.vint(record_type);
+            write(w, record);
+        });
+    }
+}
+
+fn write_unknown_records(w: &mut HeaderWriter, records: &[UnknownRecord]) {
+    for record in records {
+        write_record(w, record.tag, &record.data);
+    }
+}
+
+fn write_main(w: &mut HeaderWriter, main: &MainBlock) {
+    w.vint(main.flags.bits() as u64);
+    if let Some(volume_number) = main.volume_number {
+        w.vint(volume_number);
+    }
+    write_known_record(w, MainBlock::LOCATOR, &main.locator, write_locator);
+    write_known_record(w, MainBlock::METADATA, &main.metadata, write_metadata);
+    write_unknown_records(w, &main.unknown_records);
+}
+
+fn write_locator(w: &mut HeaderWriter, locator: &LocatorRecord) {
+    let flags = locator.flags;
+    w.vint(flags.bits() as u64);
+    // Offsets of 0 are read as missing.
+    if flags.has_quick_open_record_offset() {
+        w.vint(locator.quick_open_record_offset.unwrap_or(0));
+    }
+    if flags.has_recovery_record_offset() {
+        w.vint(locator.recovery_record_offset.unwrap_or(0));
+    }
+}
+
+fn write_metadata(w: &mut HeaderWriter, metadata: &MetadataRecord) {
+    let flags = metadata.flags;
+    w.vint(flags.bits() as u64);
+    if flags.has_archive_name() {
+        let name = metadata.name.as_deref().unwrap_or_default();
+        w.vint(name.len() as u64);
+        w.bytes(name.as_bytes());
+    }
+    if let Some(time) = metadata.creation_time {
+        if !flags.uses_unix_time() {
+            w.u64(windows_time(time));
+        } else if flags.is_unix_time_nanoseconds() {
+            w.u64(time.map_or_else(|raw| raw, |t| t.unix_timestamp_nanos() as u64));
+        } else {
+            w.u32(unix_time(time) as u32);
+        }
+    }
+}
+
+fn write_file(w: &mut HeaderWriter, file: &FileBlock) {
+    w.vint(file.flags.bits() as u64);
+    w.vint(file.unpacked_size.unwrap_or(0));
+    w.vint(file.attributes);
+    if let Some(time) = file.modification_time {
+        w.u32(time.map_or_else(|raw| raw, |t| t.unix_timestamp() as u32));
+    }
+    if let Some(crc32) = file.unpacked_data_crc32 {
+        w.u32(crc32);
+    }
+    w.vint(file.compression_info.bits());
+    w.vint(u8::from(file.host_os) as u64);
+    let name = match &file.name {
+        Ok(name) => name.as_bytes(),
+        Err(name) => name,
+    };
+    w.vint(name.len() as u64);
+    w.bytes(name);
+
+    write_known_record(w, FileBlock::CRYPT, &file.encryption, write_encryption);
+    write_known_record(w, FileBlock::HASH, &file.hash, write_hash);
+    write_known_record(w, FileBlock::HTIME, &file.extended_time, write_file_time);
+    write_known_record(w, FileBlock::VERSION, &file.version, write_version);
+    write_known_record(
+        w,
+        FileBlock::REDIR,
+        &file.filesystem_redirection,
+        write_redirection,
+    );
+    write_known_record(w, FileBlock::UOWNER, &file.unix_owner, write_unix_owner);
+    write_unknown_records(w, &file.unknown_records);
+}
+
+fn write_service(w: &mut HeaderWriter, service: &ServiceBlock) {
+    let (name, service_data): (&[u8], _) = match &service.kind {
+        ServiceBlockKind::Comment(_) => (b"CMT", None),
+        ServiceBlockKind::QuickOpen(_) => (b"QO", None),
+        ServiceBlockKind::NtfsFilePermissions => (b"ACL", None),
+        ServiceBlockKind::NtfsAlternateDataStream(stream) => (b"STM", stream.stream_name.clone()),
+        ServiceBlockKind::RecoveryRecord(recovery_record) => (
+            b"RR",
+            recovery_record.info.as_ref().map(|info| {
+                let mut data = vec![info.percentage];
+                data.extend_from_slice(&info.unknown);
+                data
+            }),
+        ),
+        ServiceBlockKind::Unknown(name) => (name, None),
+    };
+
+    w.vint(service.flags.bits() as u64);
+    w.vint(service.unpacked_size.unwrap_or(0));
+    // The attributes of service blocks are not kept, and are always 0.
+    w.vint(0);
+    if let Some(time) = service.modification_time {
+        w.u32(time.map_or_else(|raw| raw, |t| t.unix_timestamp() as u32));
+    }
+    if let Some(crc32) = service.data_crc32 {
+        w.u32(crc32);
+    }
+    w.vint(service.compression_info.bits());
+    w.vint(u8::from(service.host_os) as u64);
+    w.vint(name.len() as u64);
+    w.bytes(name);
+
+    write_known_record(
+        w,
+        ServiceBlock::CRYPT,
+        &service.encryption,
+        write_encryption,
+    );
+    write_known_record(w, ServiceBlock::HASH, &service.hash, write_hash);
+    write_known_record(
+        w,
+        ServiceBlock::HTIME,
+        &service.extended_time,
+        write_file_time,
+    );
+    write_known_record(w, ServiceBlock::VERSION, &service.version, write_version);
+    write_known_record(
+        w,
+        ServiceBlock::REDIR,
+        &service.filesystem_redirection,
+        write_redirection,
+    );
+    write_known_record(
+        w,
+        ServiceBlock::UOWNER,
+        &service.unix_owner,
+        write_unix_owner,
+    );
+    if let Some(data) = service_data {
+        write_record(w, ServiceBlock::SERVICE_DATA, &data);
+    }
+    write_unknown_records(w, &service.unknown_records);
+}
+
+fn write_crypt(w: &mut HeaderWriter, crypt: &CryptBlock) {
+    w.vint(u8::from(crypt.encryption_version) as u64);
+    w.vint(crypt.check_value.is_some() as u64);
+    w.u8(crypt.kdf_count);
+    w.bytes(&crypt.salt);
+    if let Some(check_value) = crypt.check_value {
+        w.bytes(&check_value);
+    }
+}
+
+fn write_encryption(w: &mut HeaderWriter, encryption: &FileEncryptionRecord) {
+    w.vint(u8::from(encryption.encryption_version) as u64);
+    w.vint(encryption.flags.bits() as u64);
+    w.u8(encryption.kdf_count);
+    w.bytes(&encryption.salt);
+    w.bytes(&encryption.iv);
+    if let Some(check_value) = encryption.check_value {
+        w.bytes(&check_value);
+    }
+}
+
+fn write_hash(w: &mut HeaderWriter, hash: &FileHashRecord) {
+    match &hash.hash {
+        FileHash::Blake2Sp(hash) => {
+            w.vint(FileHash::BLAKE2SP);
+            w.bytes(hash);
+        }
+        FileHash::Unknown(hash_type) => w.vint(*hash_type),
+    }
+}
+
+fn write_file_time(w: &mut HeaderWriter, record: &FileTimeRecord) {
+    let flags = record.flags;
+    w.vint(flags.bits() as u64);
+
+    let times = [
+        record.modification_time,
+        record.creation_time,
+        record.access_time,
+    ];
+    if !flags.uses_unix_time() {
+        for time in times.into_iter().flatten() {
+            w.u64(windows_time(time));
+        }
+        return;
+    }
+
+    for time in times.into_iter().flatten() {
+        w.u32(unix_time(time) as u32);
+    }
+    if flags.has_unix_time_nanoseconds() {
+        for time in times.into_iter().flatten() {
+            w.u32(time.map_or(0, |t| t.nanosecond()));
+        }
+    }
+}
+
+fn write_version(w: &mut HeaderWriter, version: &FileVersionRecord) {
+    // The flags are not used.
+    w.vint(0);
+    w.vint(version.version_number);
+}
+
+fn write_redirection(w: &mut HeaderWriter, redirection: &FileSystemRedirectionRecord) {
+    w.vint(u16::from(redirection.redirection_type) as u64);
+    w.vint(redirection.flags.bits() as u64);
+    w.vint(redirection.name.len() as u64);
+    w.bytes(redirection.name.as_bytes());
+}
+
+fn write_unix_owner(w: &mut HeaderWriter, owner: &UnixOwnerRecord) {
+    let names = [&owner.user_name, &owner.group_name];
+    let ids = [owner.user_id, owner.group_id];
+
+    let mut flags = 0;
+    for (i, present) in names
+        .iter()
+        .map(|name| name.is_some())
+        .chain(ids.iter().map(|id| id.is_some()))
+        .enumerate()
+    {
+        flags |= (present as u64) << i;
+    }
+    w.vint(flags);
+
+    for name in names.into_iter().flatten() {
+        let name = match name {
+            Ok(name) => name.as_bytes(),
+            Err(name) => name,
+        };
+        w.vint(name.len() as u64);
+        w.bytes(name);
+    }
+    for id in ids.into_iter().flatten() {
+        w.vint(id);
+    }
+}
+
+fn windows_time(time: Time) -> u64 {
+    time.map_or_else(|raw| raw, time_conv::to_windows_filetime)
+}
+
+/// Seconds of a Unix timestamp, which are stored in 32 bits.
+fn unix_time(time: Time) -> u64 {
+    time.map_or_else(|raw| raw, |t| t.unix_timestamp() as u64)
+}
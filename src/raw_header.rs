@@ -0,0 +1,58 @@
+//! Raw bytes of the headers of the blocks yielded by a block iterator.
+
+use alloc::vec::Vec;
+
+use crate::{error::RarResult, size::BlockSize};
+
+/// Block iterator which can read back the raw bytes of the headers of the blocks it
+/// yielded.
+///
+/// The bytes are read again from the archive at the offset of the block, and span its
+/// [`BlockSize::header_size`]. For encrypted headers they are the encrypted bytes, starting
+/// with the salt or IV which precedes the header.
+pub trait RawHeaderReader: Iterator<Item = RarResult<Self::Block>> {
+    /// Type of the blocks yielded by the iterator.
+    type Block: BlockSize;
+
+    /// Read the raw bytes of the header of `block`, which must have been yielded by this
+    /// iterator.
+    fn raw_header(&mut self, block: &Self::Block) -> RarResult<Vec<u8>>;
+
+    /// Yield the raw bytes of the header of each block along with the block.
+    fn with_raw_headers(self) -> RawHeaders<Self>
+    where
+        Self: Sized,
+    {
+        RawHeaders(self)
+    }
+}
+
+#[derive(Debug)]
+/// Iterator over the blocks of an archive and the raw bytes of their headers, returned by
+/// [`RawHeaderReader::with_raw_headers`].
+pub struct RawHeaders<I>(I);
+
+impl<I> RawHeaders<I> {
+    /// Get a reference to the underlying block iterator.
+    pub fn get_ref(&self) -> &I {
+        &self.0
+    }
+
+    /// Unwrap the underlying block iterator.
+    pub fn into_inner(self) -> I {
+        self.0
+    }
+}
+
+impl<I: RawHeaderReader> Iterator for RawHeaders<I> {
+    type Item = RarResult<(I::Block, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let block = match self.0.next()? {
+            Ok(block) => block,
+            Err(e) => return Some(Err(e)),
+        };
+
+        Some(self.0.raw_header(&block).map(|header| (block, header)))
+    }
+}
@@ -1,4 +1,6 @@
-use std::io;
+use alloc::{string::String, vec::Vec};
+
+use crate::io::{self, Read as _};
 
 pub fn read_u8<R: io::Read>(r: &mut R) -> io::Result<u8> {
     let mut buf = [0; 1];
@@ -51,9 +53,14 @@ pub fn read_const_bytes<const N: usize, R: io::Read>(r: &mut R) -> io::Result<[u
     Ok(buf)
 }
 
+/// Read `size` bytes, growing the buffer as they're read so that a forged size can't
+/// allocate more memory than the stream holds.
 pub fn read_vec<R: io::Read>(r: &mut R, size: usize) -> io::Result<Vec<u8>> {
-    let mut buf = vec![0; size];
-    r.read_exact(&mut buf)?;
+    let mut buf = Vec::with_capacity(size.min(0x10000));
+    r.take(size as u64).read_to_end(&mut buf)?;
+    if buf.len() != size {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
     Ok(buf)
 }
 
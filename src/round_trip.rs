@@ -0,0 +1,157 @@
+//! Serialize parsed headers back into their fields and compare them with the raw bytes they
+//! were parsed from, to check that the parser consumed every byte of the header the way it
+//! was written.
+//!
+//! Only the headers are serialized, from the values kept by the parsed blocks, so this
+//! can't be used to write archives.
+
+use alloc::vec::Vec;
+
+use crate::{
+    read::read_vint,
+    warning::{Warning, WarningKind},
+};
+
+#[derive(Debug)]
+enum Field {
+    Bytes(Vec<u8>),
+    /// Variable-size integer, which matches any encoding of the same value because RAR
+    /// writes some of them padded to be filled in later.
+    Vint(u64),
+    /// Fields preceded by their size in a variable-size integer.
+    Sized(Vec<Field>),
+}
+
+/// Fields of a header, in the same encoding they are read with.
+#[derive(Debug, Default)]
+pub(crate) struct HeaderWriter {
+    fields: Vec<Field>,
+}
+
+impl HeaderWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.bytes(&[value]);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn vint(&mut self, value: u64) {
+        self.fields.push(Field::Vint(value));
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        if let Some(Field::Bytes(last)) = self.fields.last_mut() {
+            last.extend_from_slice(bytes);
+        } else {
+            self.fields.push(Field::Bytes(bytes.to_vec()));
+        }
+    }
+
+    /// Write the fields written by `write`, preceded by their size.
+    pub fn sized(&mut self, write: impl FnOnce(&mut HeaderWriter)) {
+        let mut inner = HeaderWriter::new();
+        write(&mut inner);
+        self.fields.push(Field::Sized(inner.fields));
+    }
+}
+
+/// Compare the `serialized` header of the block at `offset` with the `original` bytes it
+/// was parsed from, pushing a warning at the first byte that differs.
+pub(crate) fn compare(
+    offset: u64,
+    original: &[u8],
+    serialized: &HeaderWriter,
+    warnings: &mut Vec<Warning>,
+) {
+    let position = match match_fields(original, 0, &serialized.fields) {
+        Ok(end) if end == original.len() => return,
+        Ok(end) => end,
+        Err(position) => position,
+    };
+
+    warnings.push(Warning {
+        offset,
+        kind: WarningKind::RoundTripMismatch {
+            position: offset + position as u64,
+        },
+    });
+}
+
+/// Match the `fields` against the `original` bytes starting at `pos`, returning the end of
+/// the matched bytes or the position of the first byte that differs.
+fn match_fields(original: &[u8], mut pos: usize, fields: &[Field]) -> Result<usize, usize> {
+    for field in fields {
+        match field {
+            Field::Bytes(bytes) => {
+                for (i, byte) in bytes.iter().enumerate() {
+                    if original.get(pos + i) != Some(byte) {
+                        return Err(pos + i);
+                    }
+                }
+                pos += bytes.len();
+            }
+            Field::Vint(value) => {
+                let (original_value, size) = read_original_vint(original, pos)?;
+                if original_value != *value {
+                    return Err(pos);
+                }
+                pos += size;
+            }
+            Field::Sized(fields) => {
+                let (size, vint_size) = read_original_vint(original, pos)?;
+                let start = pos + vint_size;
+                let end = usize::try_from(size)
+                    .ok()
+                    .and_then(|size| start.checked_add(size))
+                    .filter(|&end| end <= original.len())
+                    .ok_or(pos)?;
+                let matched_end = match_fields(&original[..end], start, fields)?;
+                if matched_end != end {
+                    return Err(matched_end);
+                }
+                pos = end;
+            }
+        }
+    }
+
+    Ok(pos)
+}
+
+fn read_original_vint(original: &[u8], pos: usize) -> Result<(u64, usize), usize> {
+    let mut bytes = original.get(pos..).ok_or(pos)?;
+    read_vint(&mut bytes)
+        .map(|(value, size)| (value, size as usize))
+        .map_err(|_| pos)
+}
+
+#[test]
+fn test_match_fields() {
+    let mut writer = HeaderWriter::new();
+    writer.u8(0xff);
+    writer.sized(|w| {
+        w.vint(0x80);
+        w.bytes(b"ab");
+    });
+
+    let matches = |original: &[u8]| match_fields(original, 0, &writer.fields);
+    assert_eq!(matches(&[0xff, 4, 0x80, 1, b'a', b'b']), Ok(6));
+    // Padded variable-size integers match.
+    assert_eq!(matches(&[0xff, 5, 0x80, 0x81, 0, b'a', b'b']), Ok(7));
+    assert_eq!(matches(&[0xff, 5, 0x80, 1, b'a', b'b', b'c']), Err(6));
+    assert_eq!(matches(&[0xff, 4, 0x81, 1, b'a', b'b']), Err(2));
+    assert_eq!(matches(&[0xfe]), Err(0));
+}
@@ -0,0 +1,77 @@
+//! Scanning for the next block header after a corrupt one.
+
+use crate::io;
+
+/// Size of the chunks of the archive read while scanning.
+const CHUNK_SIZE: u64 = 0x10000;
+
+/// Find the first offset in `start..end` at which a block header accepted by `is_header`
+/// starts.
+///
+/// At each offset, `header_size` is passed the next `prefix_size` bytes and returns the
+/// full size of the header starting there, or `None` if they can't be the start of a
+/// header. Headers ending before `end` are then passed to `is_header` with their offset.
+pub(crate) fn find_header<R: io::Read + io::Seek>(
+    reader: &mut R,
+    start: u64,
+    end: u64,
+    prefix_size: usize,
+    mut header_size: impl FnMut(&[u8]) -> Option<u64>,
+    mut is_header: impl FnMut(&[u8], u64) -> bool,
+) -> io::Result<Option<u64>> {
+    let mut chunk = vec![];
+    let mut chunk_offset = start;
+    let mut offset = start;
+    let mut long_header = vec![];
+
+    while offset.saturating_add(prefix_size as u64) <= end {
+        let pos = (offset - chunk_offset) as usize;
+        if pos + prefix_size > chunk.len() {
+            chunk_offset = offset;
+            chunk.resize((end - offset).min(CHUNK_SIZE) as usize, 0);
+            reader.seek(io::SeekFrom::Start(offset))?;
+            reader.read_exact(&mut chunk)?;
+            continue;
+        }
+
+        let size = header_size(&chunk[pos..pos + prefix_size])
+            .filter(|&size| size >= prefix_size as u64 && offset.saturating_add(size) <= end);
+
+        if let Some(size) = size {
+            let header = if pos as u64 + size <= chunk.len() as u64 {
+                &chunk[pos..pos + size as usize]
+            } else {
+                long_header.resize(size as usize, 0);
+                reader.seek(io::SeekFrom::Start(offset))?;
+                reader.read_exact(&mut long_header)?;
+                &long_header[..]
+            };
+
+            if is_header(header, offset) {
+                return Ok(Some(offset));
+            }
+        }
+
+        offset += 1;
+    }
+
+    Ok(None)
+}
+
+#[test]
+fn test_find_header_across_chunks() {
+    let mut data = vec![0xff; CHUNK_SIZE as usize + 8];
+    let offset = CHUNK_SIZE as usize - 2;
+    data[offset..offset + 4].copy_from_slice(&[4, b'h', b'd', b'r']);
+
+    let found = find_header(
+        &mut io::Cursor::new(&data),
+        1,
+        data.len() as u64,
+        1,
+        |prefix| Some(prefix[0] as u64),
+        |header, _| header == [4, b'h', b'd', b'r'],
+    )
+    .unwrap();
+    assert_eq!(found, Some(offset as u64));
+}
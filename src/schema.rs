@@ -0,0 +1,214 @@
+//! Machine-readable description of the flags and enums known to the parser.
+//!
+//! Every flags type and integer enum of the format modules exposes its description as a
+//! `SCHEMA` associated constant, and [`MODULES`] lists all of them by module, so that
+//! other tools can stay in sync with the parser without duplicating its knowledge of the
+//! formats. [`to_json`] exports the same data as JSON.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write as _;
+
+use crate::{attributes, rar14, rar15, rar50};
+
+#[derive(Debug, Clone, Copy)]
+/// Description of a flags type and the meaning of its bits.
+pub struct FlagsSchema {
+    pub name: &'static str,
+
+    /// Size of the flags in bytes.
+    pub size: usize,
+
+    /// Lines of the documentation of the type.
+    pub doc: &'static [&'static str],
+
+    pub flags: &'static [FlagSchema],
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Description of a single flag of a [`FlagsSchema`].
+pub struct FlagSchema {
+    pub name: &'static str,
+
+    /// Bits which are set when the flag is set.
+    pub mask: u64,
+
+    /// Lines of the documentation of the flag.
+    pub doc: &'static [&'static str],
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Description of an integer enum and its variants.
+///
+/// Values which don't match any variant are parsed as an `Unknown` variant.
+pub struct EnumSchema {
+    pub name: &'static str,
+
+    /// Size of the integer in bytes.
+    pub size: usize,
+
+    /// Lines of the documentation of the type.
+    pub doc: &'static [&'static str],
+
+    pub variants: &'static [VariantSchema],
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Description of a variant of an [`EnumSchema`].
+pub struct VariantSchema {
+    pub name: &'static str,
+    pub value: u64,
+
+    /// Lines of the documentation of the variant.
+    pub doc: &'static [&'static str],
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Flags and enums defined in a module of the crate.
+pub struct ModuleSchema {
+    /// Path of the module the types can be imported from, like `rawrxd::rar50`.
+    pub name: &'static str,
+
+    pub flags: &'static [FlagsSchema],
+    pub enums: &'static [EnumSchema],
+}
+
+/// Flags and enums of every module of the crate.
+///
+/// The file attributes are listed under the crate root, which they are exported from.
+pub const MODULES: &[ModuleSchema] = &[
+    rar14::SCHEMA,
+    rar15::SCHEMA,
+    rar50::SCHEMA,
+    #[cfg(feature = "std")]
+    crate::compat::SCHEMA,
+    attributes::SCHEMA,
+];
+
+/// Export [`MODULES`] as a JSON array.
+///
+/// Each module is an object with its `name` and its `flags` and `enums` arrays. Each flags
+/// type has a `name`, a `size` in bytes, a `doc` string and a `flags` array of objects
+/// with a `name`, a `mask` and a `doc`; each enum has a `name`, a `size`, a `doc` and a
+/// `variants` array of objects with a `name`, a `value` and a `doc`.
+pub fn to_json() -> String {
+    let mut json = String::from("[");
+
+    for (i, module) in MODULES.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write!(json, r#"{{"name":{},"flags":["#, string(module.name)).unwrap();
+
+        for (i, flags) in module.flags.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                r#"{{"name":{},"size":{},"doc":{},"flags":["#,
+                string(flags.name),
+                flags.size,
+                doc(flags.doc)
+            )
+            .unwrap();
+
+            for (i, flag) in flags.flags.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                write!(
+                    json,
+                    r#"{{"name":{},"mask":{},"doc":{}}}"#,
+                    string(flag.name),
+                    flag.mask,
+                    doc(flag.doc)
+                )
+                .unwrap();
+            }
+            json.push_str("]}");
+        }
+        json.push_str(r#"],"enums":["#);
+
+        for (i, e) in module.enums.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                r#"{{"name":{},"size":{},"doc":{},"variants":["#,
+                string(e.name),
+                e.size,
+                doc(e.doc)
+            )
+            .unwrap();
+
+            for (i, variant) in e.variants.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                write!(
+                    json,
+                    r#"{{"name":{},"value":{},"doc":{}}}"#,
+                    string(variant.name),
+                    variant.value,
+                    doc(variant.doc)
+                )
+                .unwrap();
+            }
+            json.push_str("]}");
+        }
+        json.push_str("]}");
+    }
+
+    json.push(']');
+    json
+}
+
+/// Join the lines of a doc comment, removing the space after the `///`.
+fn doc(lines: &[&str]) -> String {
+    let doc = lines
+        .iter()
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+    string(&doc)
+}
+
+/// Quote and escape a JSON string.
+pub(crate) fn string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c < ' ' => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[test]
+fn test_schema() {
+    let rar50 = MODULES.iter().find(|m| m.name == "rawrxd::rar50").unwrap();
+    let flags = rar50
+        .flags
+        .iter()
+        .find(|f| f.name == "FileBlockFlags")
+        .unwrap();
+    assert_eq!(flags.size, 2);
+    assert!(flags
+        .flags
+        .iter()
+        .any(|f| f.name == "is_directory" && f.mask == 0x0001));
+
+    let host_os = rar50.enums.iter().find(|e| e.name == "HostOs").unwrap();
+    assert_eq!(host_os.variants[1].name, "Unix");
+    assert_eq!(host_os.variants[1].value, 1);
+
+    assert_eq!(string("a\"b\\\n\t"), r#""a\"b\\\n\u0009""#);
+    assert!(to_json().starts_with(r#"[{"name":"rawrxd::rar14","flags":[{"name":"MainBlockFlags""#));
+}
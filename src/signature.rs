@@ -1,6 +1,7 @@
-use std::io;
-
 use aho_corasick::AhoCorasick;
+use alloc::vec::Vec;
+
+use crate::io;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// File signatures or "magic numbers" of the RAR family of file formats.
@@ -65,7 +66,7 @@ impl Signature {
     ///
     /// ```no_run
     /// # use rawrxd::Signature;
-    /// # use std::io;
+    /// # use rawrxd::io;
     /// # fn main() -> io::Result<()> {
     /// # let mut file = io::Cursor::new(Vec::new());
     /// let (format, offset) = Signature::search_stream(&mut file)?
@@ -83,33 +84,429 @@ impl Signature {
     /// archive itself. This binary may have a size up to [`Signature::MAX_SFX_SIZE`] minus the
     /// size of the signature.
     ///
+    /// The first signature found is returned, but SFX modules may contain other RAR
+    /// signatures before the one of the archive, like the one of an older format they can
+    /// also extract. Use [`Signature::detect`] to find all the candidates.
+    ///
     /// Uses [`aho_corasick`](https://docs.rs/aho-corasick/latest/aho_corasick/) under the hood
     /// to search for the signatures efficiently.
     pub fn search_stream<R: io::Read>(reader: R) -> Result<Option<(Self, u64)>, io::Error> {
+        Self::search_stream_with(reader, SearchOptions::default())
+    }
+
+    /// Search for a RAR signature in the stream like [`Signature::search_stream`], within
+    /// the limits of `options`.
+    pub fn search_stream_with<R: io::Read>(
+        mut reader: R,
+        options: SearchOptions,
+    ) -> io::Result<Option<(Self, u64)>> {
+        if options.require_at_start {
+            let mut bytes = [0; Self::RAR50.len()];
+            let mut filled = 0;
+            while filled < bytes.len() {
+                match reader.read(&mut bytes[filled..])? {
+                    0 => break,
+                    n => filled += n,
+                }
+            }
+
+            return Ok(Self::from_bytes(&bytes[..filled]).map(|format| (format, 0)));
+        }
+
+        let ac = Self::automaton();
+
+        // Avoid reading the whole file in case we don't find the signature within the limit.
+        let bounded_reader = &mut reader.take(options.max_sfx_size);
+
+        Ok(find_in_stream(&ac, bounded_reader)?.map(Self::from_match))
+    }
+
+    /// Search for all the RAR signatures in the stream up to [`Signature::MAX_SFX_SIZE`] and
+    /// pick one of them according to `pick`.
+    ///
+    /// Unlike [`Signature::search_stream`], this always reads the stream up to
+    /// [`Signature::MAX_SFX_SIZE`], to report all the candidate signatures.
+    pub fn detect<R: io::Read>(reader: R, pick: SignatureMatch) -> io::Result<Option<Detection>> {
+        let ac = Self::automaton();
+        let bounded_reader = &mut reader.take(Self::MAX_SFX_SIZE);
+
+        let candidates: Vec<_> = find_all_in_stream(&ac, bounded_reader)?
+            .into_iter()
+            .map(Self::from_match)
+            .collect();
+
+        let picked = match pick {
+            SignatureMatch::First => candidates.first(),
+            SignatureMatch::Last => candidates.last(),
+        };
+        let Some(&(format, offset)) = picked else {
+            return Ok(None);
+        };
+
+        Ok(Some(Detection {
+            format,
+            offset,
+            candidates,
+        }))
+    }
+
+    /// Asynchronous version of [`Signature::search_stream`].
+    #[cfg(feature = "async")]
+    pub async fn search_stream_async<R: futures_io::AsyncRead + Unpin>(
+        mut reader: R,
+    ) -> Result<Option<(Self, u64)>, io::Error> {
+        let ac = Self::automaton();
+        let mut search = ChunkedSearch::new(&ac);
+        let mut chunk = [0; 0x1000];
+
+        while search.searched() < Self::MAX_SFX_SIZE {
+            let max = chunk
+                .len()
+                .min((Self::MAX_SFX_SIZE - search.searched()) as usize);
+            let n = match core::future::poll_fn(|cx| {
+                core::pin::Pin::new(&mut reader).poll_read(cx, &mut chunk[..max])
+            })
+            .await
+            {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            if let Some(m) = search.push(&chunk[..n]) {
+                return Ok(Some(Self::from_match(m)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn automaton() -> AhoCorasick {
         let patterns = [&Self::RAR14[..], &Self::RAR15[..], &Self::RAR50[..]];
 
         let Ok(ac) = AhoCorasick::new(patterns) else {
             unreachable!("Aho-Corasick pattern not constructed correctly")
         };
 
-        // Avoid reading the whole file in case we don't find the signature within MAX_SFX_SIZE.
-        let bounded_reader = &mut reader.take(Self::MAX_SFX_SIZE);
+        ac
+    }
 
-        match ac.stream_find_iter(bounded_reader).next() {
-            None => Ok(None),
-            Some(Err(e)) => Err(e),
-            Some(Ok(m)) => {
-                let start = m.start();
+    fn from_match(m: aho_corasick::Match) -> (Self, u64) {
+        let format = match m.pattern().as_i32() {
+            0 => Self::Rar14,
+            1 => Self::Rar15,
+            2 => Self::Rar50,
+            i => unreachable!("invalid Aho-Corasick pattern ID: {i}"),
+        };
 
-                let format = match m.pattern().as_i32() {
-                    0 => Self::Rar14,
-                    1 => Self::Rar15,
-                    2 => Self::Rar50,
-                    i => unreachable!("invalid Aho-Corasick pattern ID: {i}"),
-                };
+        (format, m.start() as u64)
+    }
+}
 
-                Ok(Some((format, start as u64)))
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Limits of the search of [`Signature::search_stream_with`].
+pub struct SearchOptions {
+    /// Maximum size of the SFX binary embedded before the archive signature, including the
+    /// signature size, which is [`Signature::MAX_SFX_SIZE`] by default.
+    ///
+    /// The SFX modules of some installers are larger than the ones of RAR.
+    pub max_sfx_size: u64,
+
+    /// Only look for the signature at the start of the stream, which reads no more than
+    /// the size of the signature. Self-extracting archives are not found.
+    pub require_at_start: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            max_sfx_size: Signature::MAX_SFX_SIZE,
+            require_at_start: false,
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Which of the signatures found by [`Signature::detect`] is picked.
+pub enum SignatureMatch {
+    /// The first signature in the stream, like [`Signature::search_stream`].
+    #[default]
+    First,
+
+    /// The last signature in the stream, which skips the signatures contained in the SFX
+    /// module but may be one contained in the archive itself, like the one of a nested
+    /// archive stored uncompressed.
+    Last,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// RAR signature found by [`Signature::detect`].
+pub struct Detection {
+    /// Format of the picked signature.
+    pub format: Signature,
+
+    /// Offset of the picked signature in the stream.
+    pub offset: u64,
+
+    /// Format and offset of all the signatures found, in the order of their offsets,
+    /// including the picked one.
+    pub candidates: Vec<(Signature, u64)>,
+}
+
+impl Detection {
+    /// The picked signature is preceded by some bytes, which are the SFX module of a
+    /// self-extracting archive.
+    pub fn has_sfx_stub(&self) -> bool {
+        self.offset > 0
+    }
+
+    /// More than one signature was found, so the picked one may not be the right one.
+    pub fn has_multiple_candidates(&self) -> bool {
+        self.candidates.len() > 1
+    }
+}
+
+#[cfg(feature = "std")]
+fn find_in_stream<R: io::Read>(
+    ac: &AhoCorasick,
+    reader: R,
+) -> io::Result<Option<aho_corasick::Match>> {
+    ac.stream_find_iter(reader).next().transpose()
+}
+
+#[cfg(not(feature = "std"))]
+fn find_in_stream<R: io::Read>(
+    ac: &AhoCorasick,
+    mut reader: R,
+) -> io::Result<Option<aho_corasick::Match>> {
+    let mut search = ChunkedSearch::new(ac);
+    let mut chunk = [0; 0x1000];
+
+    loop {
+        let n = reader.read(&mut chunk)?;
+        if n == 0 {
+            return Ok(None);
+        }
+
+        if let Some(m) = search.push(&chunk[..n]) {
+            return Ok(Some(m));
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn find_all_in_stream<R: io::Read>(
+    ac: &AhoCorasick,
+    reader: R,
+) -> io::Result<Vec<aho_corasick::Match>> {
+    ac.stream_find_iter(reader).collect()
+}
+
+#[cfg(not(feature = "std"))]
+fn find_all_in_stream<R: io::Read>(
+    ac: &AhoCorasick,
+    mut reader: R,
+) -> io::Result<Vec<aho_corasick::Match>> {
+    let mut search = ChunkedSearch::new(ac);
+    let mut chunk = [0; 0x1000];
+    let mut matches = Vec::new();
+
+    loop {
+        let n = reader.read(&mut chunk)?;
+        if n == 0 {
+            return Ok(matches);
+        }
+
+        search.push_all(&chunk[..n], &mut matches);
+    }
+}
+
+/// Search of a stream read in chunks, for when `AhoCorasick::stream_find_iter` can't be
+/// used because `std` is disabled or the stream is asynchronous.
+///
+/// The last bytes of each chunk are kept in the buffer so that signatures split across
+/// two reads are found too.
+#[cfg(any(not(feature = "std"), feature = "async"))]
+struct ChunkedSearch<'a> {
+    ac: &'a AhoCorasick,
+    buf: alloc::vec::Vec<u8>,
+    buf_offset: usize,
+}
+
+#[cfg(any(not(feature = "std"), feature = "async"))]
+impl<'a> ChunkedSearch<'a> {
+    fn new(ac: &'a AhoCorasick) -> Self {
+        Self {
+            ac,
+            buf: alloc::vec::Vec::new(),
+            buf_offset: 0,
+        }
+    }
+
+    /// Number of bytes searched so far.
+    #[cfg(feature = "async")]
+    fn searched(&self) -> u64 {
+        (self.buf_offset + self.buf.len()) as u64
+    }
+
+    /// Search the next chunk of the stream.
+    fn push(&mut self, chunk: &[u8]) -> Option<aho_corasick::Match> {
+        self.buf.extend_from_slice(chunk);
+
+        if let Some(m) = self.ac.find(self.buf.as_slice()) {
+            return Some(m.offset(self.buf_offset));
+        }
+
+        self.consume(0);
+        None
+    }
+
+    /// Search the next chunk of the stream, collecting all the signatures in `matches`.
+    #[cfg(not(feature = "std"))]
+    fn push_all(&mut self, chunk: &[u8], matches: &mut Vec<aho_corasick::Match>) {
+        self.buf.extend_from_slice(chunk);
+
+        let mut end = 0;
+        for m in self.ac.find_iter(self.buf.as_slice()) {
+            end = m.end();
+            matches.push(m.offset(self.buf_offset));
+        }
+
+        // The bytes of the matches are dropped so that they are not found again.
+        self.consume(end);
+    }
+
+    /// Drop the bytes of the buffer which can't be part of a signature split across reads,
+    /// and at least the first `min` bytes.
+    fn consume(&mut self, min: usize) {
+        let consumed = self
+            .buf
+            .len()
+            .saturating_sub(self.ac.max_pattern_len() - 1)
+            .max(min);
+        self.buf.drain(..consumed);
+        self.buf_offset += consumed;
+    }
+}
+
+#[test]
+fn test_search_stream_across_reads() {
+    let mut data = vec![0; 0x1000 - 3];
+    data.extend_from_slice(Signature::RAR50);
+
+    assert_eq!(
+        Signature::search_stream(io::Cursor::new(data)).unwrap(),
+        Some((Signature::Rar50, 0x1000 - 3))
+    );
+}
+
+#[test]
+fn test_detect_candidates() {
+    let mut data = vec![0; 0x100];
+    data[0x10..0x17].copy_from_slice(Signature::RAR15);
+    data.extend_from_slice(Signature::RAR50);
+
+    let first = Signature::detect(io::Cursor::new(&data), SignatureMatch::First)
+        .unwrap()
+        .unwrap();
+    assert_eq!((first.format, first.offset), (Signature::Rar15, 0x10));
+    assert!(first.has_sfx_stub());
+    assert!(first.has_multiple_candidates());
+    assert_eq!(
+        first.candidates,
+        [(Signature::Rar15, 0x10), (Signature::Rar50, 0x100)]
+    );
+
+    let last = Signature::detect(io::Cursor::new(&data), SignatureMatch::Last)
+        .unwrap()
+        .unwrap();
+    assert_eq!((last.format, last.offset), (Signature::Rar50, 0x100));
+    assert_eq!(last.candidates, first.candidates);
+}
+
+#[test]
+fn test_detect_single_signature() {
+    let detection = Signature::detect(io::Cursor::new(Signature::RAR50), SignatureMatch::Last)
+        .unwrap()
+        .unwrap();
+    assert!(!detection.has_sfx_stub());
+    assert!(!detection.has_multiple_candidates());
+
+    assert_eq!(
+        Signature::detect(io::Cursor::new([0; 0x100]), SignatureMatch::First).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_detect_across_reads() {
+    let mut data = vec![0; 0x1000 - 3];
+    data.extend_from_slice(Signature::RAR14);
+    data.extend_from_slice(&[0; 0x1000]);
+    data.extend_from_slice(Signature::RAR14);
+
+    let detection = Signature::detect(io::Cursor::new(data), SignatureMatch::First)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        detection.candidates,
+        [(Signature::Rar14, 0x1000 - 3), (Signature::Rar14, 0x2001)]
+    );
+}
+
+#[test]
+fn test_search_stream_with_max_sfx_size() {
+    let mut data = vec![0; Signature::MAX_SFX_SIZE as usize];
+    data.extend_from_slice(Signature::RAR15);
+
+    assert_eq!(
+        Signature::search_stream(io::Cursor::new(&data)).unwrap(),
+        None
+    );
+
+    let options = SearchOptions {
+        max_sfx_size: Signature::MAX_SFX_SIZE * 2,
+        ..Default::default()
+    };
+    assert_eq!(
+        Signature::search_stream_with(io::Cursor::new(&data), options).unwrap(),
+        Some((Signature::Rar15, Signature::MAX_SFX_SIZE))
+    );
+
+    // The limit includes the size of the signature.
+    let options = SearchOptions {
+        max_sfx_size: 0x107,
+        ..Default::default()
+    };
+    let mut data = vec![0; 0x100];
+    data.extend_from_slice(Signature::RAR50);
+    assert_eq!(
+        Signature::search_stream_with(io::Cursor::new(&data), options).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_search_stream_with_require_at_start() {
+    let options = SearchOptions {
+        require_at_start: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        Signature::search_stream_with(io::Cursor::new(Signature::RAR50), options).unwrap(),
+        Some((Signature::Rar50, 0))
+    );
+    assert_eq!(
+        Signature::search_stream_with(io::Cursor::new(Signature::RAR14), options).unwrap(),
+        Some((Signature::Rar14, 0))
+    );
+
+    let mut data = vec![0; 0x10];
+    data.extend_from_slice(Signature::RAR50);
+    assert_eq!(
+        Signature::search_stream_with(io::Cursor::new(&data), options).unwrap(),
+        None
+    );
+}
@@ -1,3 +1,7 @@
+use core::ops::Range;
+
+use crate::io;
+
 /// Offset and size of the block in the file.
 pub trait BlockSize {
     /// Offset of the block from the start of the file.
@@ -11,6 +15,23 @@ pub trait BlockSize {
 
     /// Full size of the block from [`Self::offset`].
     fn size(&self) -> u64 {
-        self.header_size() + self.data_size()
+        self.header_size().saturating_add(self.data_size())
+    }
+
+    /// Absolute offsets of the data area of the block in the file, from the end of the
+    /// header to the end of the block.
+    fn data_range(&self) -> Range<u64> {
+        let start = self.offset().saturating_add(self.header_size());
+        start..start.saturating_add(self.data_size())
+    }
+
+    /// Seek `reader` to the start of the data area of the block and return a reader that
+    /// stops at its end.
+    fn data_reader<'a, R: io::Read + io::Seek>(
+        &self,
+        reader: &'a mut R,
+    ) -> io::Result<io::Take<&'a mut R>> {
+        reader.seek(io::SeekFrom::Start(self.data_range().start))?;
+        Ok(io::Read::take(reader, self.data_size()))
     }
 }
@@ -0,0 +1,162 @@
+use std::{
+    io, thread,
+    time::{Duration, Instant},
+};
+
+/// Hook called by the data pipeline every time bytes are read from an archive.
+///
+/// Implementations may block the calling thread to limit the throughput of an extraction,
+/// so that services extracting untrusted archives don't saturate the disk or the network.
+///
+/// Any `FnMut(usize)` closure can be used as a throttle.
+pub trait Throttle {
+    /// Called after `bytes` bytes have been read.
+    fn consume(&mut self, bytes: usize);
+}
+
+impl<F: FnMut(usize)> Throttle for F {
+    fn consume(&mut self, bytes: usize) {
+        self(bytes)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// [Token bucket](https://en.wikipedia.org/wiki/Token_bucket) limiting the throughput to
+/// a fixed number of bytes per second.
+///
+/// The bucket starts full, so up to `burst` bytes can be read before the limiter starts
+/// sleeping.
+pub struct RateLimiter {
+    bytes_per_second: u64,
+    burst: u64,
+    tokens: u64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Create a [`RateLimiter`] allowing `bytes_per_second` bytes per second, with a burst
+    /// size of one second worth of bytes.
+    pub fn new(bytes_per_second: u64) -> Self {
+        Self::with_burst(bytes_per_second, bytes_per_second)
+    }
+
+    /// Create a [`RateLimiter`] allowing `bytes_per_second` bytes per second and up to
+    /// `burst` bytes to be read at once without sleeping.
+    pub fn with_burst(bytes_per_second: u64, burst: u64) -> Self {
+        let bytes_per_second = bytes_per_second.max(1);
+        let burst = burst.max(1);
+
+        Self {
+            bytes_per_second,
+            burst,
+            tokens: burst,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        let new_tokens = elapsed.as_nanos() * self.bytes_per_second as u128 / 1_000_000_000;
+
+        if new_tokens > 0 {
+            self.tokens = (self.tokens as u128 + new_tokens).min(self.burst as u128) as u64;
+            self.last_refill = now;
+        }
+    }
+
+    /// Take `bytes` tokens from the bucket and return how long the caller must wait before
+    /// the tokens it went into debt for are refilled.
+    fn take(&mut self, bytes: u64, now: Instant) -> Duration {
+        self.refill(now);
+
+        if bytes <= self.tokens {
+            self.tokens -= bytes;
+            return Duration::ZERO;
+        }
+
+        let missing = bytes - self.tokens;
+        self.tokens = 0;
+
+        let nanos = missing as u128 * 1_000_000_000 / self.bytes_per_second as u128;
+        let wait = Duration::from_nanos(nanos.min(u64::MAX as u128) as u64);
+
+        // The tokens we're waiting for are spent as soon as they're refilled.
+        self.last_refill = now + wait;
+
+        wait
+    }
+}
+
+impl Throttle for RateLimiter {
+    fn consume(&mut self, bytes: usize) {
+        let wait = self.take(bytes as u64, Instant::now());
+
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Wraps an [`io::Read`] and calls a [`Throttle`] hook after every read.
+pub struct ThrottledReader<R, T> {
+    reader: R,
+    throttle: T,
+}
+
+impl<R: io::Read, T: Throttle> ThrottledReader<R, T> {
+    /// Throttle reads from `reader` with `throttle`.
+    pub fn new(reader: R, throttle: T) -> Self {
+        Self { reader, throttle }
+    }
+
+    /// Unwrap the inner reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: io::Read, T: Throttle> io::Read for ThrottledReader<R, T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+
+        if n > 0 {
+            self.throttle.consume(n);
+        }
+
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_throttled_reader_reports_bytes() {
+    use std::io::Read;
+
+    let mut consumed = 0;
+    let mut buf = vec![];
+    ThrottledReader::new(&b"0123456789"[..], |n| consumed += n)
+        .read_to_end(&mut buf)
+        .unwrap();
+
+    assert_eq!(buf, b"0123456789");
+    assert_eq!(consumed, 10);
+}
+
+#[test]
+fn test_rate_limiter_waits_for_missing_tokens() {
+    let mut limiter = RateLimiter::with_burst(1000, 100);
+    let now = limiter.last_refill;
+
+    assert_eq!(limiter.take(100, now), Duration::ZERO);
+    assert_eq!(limiter.take(500, now), Duration::from_millis(500));
+
+    // The debt is paid off only after the wait has elapsed.
+    assert_eq!(
+        limiter.take(10, now + Duration::from_millis(500)),
+        Duration::from_millis(10)
+    );
+    assert_eq!(
+        limiter.take(10, now + Duration::from_millis(530)),
+        Duration::ZERO
+    );
+}
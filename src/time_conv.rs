@@ -1,4 +1,4 @@
-use std::time::Duration;
+use core::time::Duration;
 
 /// Parse an MS-DOS datetime value.
 ///
@@ -20,6 +20,18 @@ pub fn parse_dos_datetime(
     Ok(time::PrimitiveDateTime::new(date, time))
 }
 
+/// Convert a datetime back into an MS-DOS datetime value, rounding the seconds down to
+/// an even number. The year must be between 1980 and 2107.
+pub fn to_dos_datetime(datetime: time::PrimitiveDateTime) -> u32 {
+    let year = (datetime.year() - 1980) as u32;
+    (year << 25)
+        | ((datetime.month() as u32) << 21)
+        | ((datetime.day() as u32) << 16)
+        | ((datetime.hour() as u32) << 11)
+        | ((datetime.minute() as u32) << 5)
+        | (datetime.second() as u32 / 2)
+}
+
 const ONE_SECOND_NS: i128 = Duration::from_secs(1).as_nanos() as _;
 
 // Values from this StackOverflow answer
@@ -37,6 +49,11 @@ pub fn parse_windows_filetime(
     time::OffsetDateTime::from_unix_timestamp_nanos(unix_timestamp_ns)
 }
 
+/// Convert a datetime back into a Windows FILETIME structure.
+pub fn to_windows_filetime(datetime: time::OffsetDateTime) -> u64 {
+    ((datetime.unix_timestamp_nanos() + WINDOWS_EPOCH_DIFFERENCE) / WINDOWS_TICK_NS) as u64
+}
+
 #[test]
 fn test_parse_windows_filetime() {
     assert_eq!(
@@ -60,3 +77,11 @@ pub fn parse_unix_timestamp_ns(
 ) -> Result<time::OffsetDateTime, time::error::ComponentRange> {
     time::OffsetDateTime::from_unix_timestamp_nanos(nanoseconds.into())
 }
+
+#[test]
+fn test_to_dos_datetime() {
+    for dos_time in [0x0021_0000, 0x5a3c_8b4e] {
+        let datetime = parse_dos_datetime(dos_time).unwrap();
+        assert_eq!(to_dos_datetime(datetime), dos_time);
+    }
+}
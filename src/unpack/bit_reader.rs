@@ -0,0 +1,155 @@
+use std::io;
+
+/// Number of bytes requested from the underlying reader at once.
+const READ_SIZE: usize = 0x8000;
+
+/// Reads a stream of bits, most significant bit first.
+///
+/// Peeking past the end of the stream returns zero bits, so the decoders only need to
+/// check for the end of the stream once per symbol with [`Self::is_past_end`].
+pub struct BitReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+
+    /// Offset in the stream of the first byte of `buf`.
+    base: u64,
+
+    /// Position in bits from the start of `buf`.
+    bit: usize,
+
+    eof: bool,
+}
+
+impl<R: io::Read> BitReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::with_capacity(READ_SIZE),
+            base: 0,
+            bit: 0,
+            eof: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Discard the buffered data and start reading a new stream from the current position
+    /// of the reader.
+    pub fn reset(&mut self) {
+        self.buf.clear();
+        self.base = 0;
+        self.bit = 0;
+        self.eof = false;
+    }
+
+    /// Make sure that at least `bytes` bytes following the current position are buffered,
+    /// unless the stream ends earlier.
+    pub fn fill(&mut self, bytes: usize) -> io::Result<()> {
+        let pos = self.bit / 8;
+        if self.eof || self.buf.len() >= pos + bytes {
+            return Ok(());
+        }
+
+        self.buf.drain(..pos.min(self.buf.len()));
+        self.base += pos as u64;
+        self.bit -= pos * 8;
+
+        let target = bytes.max(READ_SIZE);
+        while self.buf.len() < target {
+            let len = self.buf.len();
+            self.buf.resize(target, 0);
+            match self.reader.read(&mut self.buf[len..]) {
+                Ok(0) => {
+                    self.buf.truncate(len);
+                    self.eof = true;
+                    break;
+                }
+                Ok(n) => self.buf.truncate(len + n),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => self.buf.truncate(len),
+                Err(e) => {
+                    self.buf.truncate(len);
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Position in bits from the start of the stream.
+    pub fn position(&self) -> u64 {
+        self.base * 8 + self.bit as u64
+    }
+
+    /// Number of bytes buffered from the byte of the current position, which are all the
+    /// bytes left in the stream if fewer than were requested with [`Self::fill`].
+    pub fn buffered(&self) -> usize {
+        self.buf.len().saturating_sub(self.bit / 8)
+    }
+
+    /// The position is past the end of the stream.
+    pub fn is_past_end(&self) -> bool {
+        self.eof && self.bit > self.buf.len() * 8
+    }
+
+    /// Return the next `n` bits without consuming them. `n` must be at most 57.
+    pub fn peek(&self, n: u32) -> u64 {
+        debug_assert!(n <= 57);
+
+        let i = self.bit / 8;
+        let word = match self.buf.get(i..i + 8) {
+            Some(bytes) => u64::from_be_bytes(bytes.try_into().unwrap()),
+            None => (0..8).fold(0, |word, k| {
+                word << 8 | self.buf.get(i + k).copied().unwrap_or(0) as u64
+            }),
+        };
+
+        if n == 0 {
+            0
+        } else {
+            (word << (self.bit % 8)) >> (64 - n)
+        }
+    }
+
+    /// Return the next 16 bits without consuming them.
+    pub fn peek16(&self) -> u32 {
+        self.peek(16) as u32
+    }
+
+    pub fn skip(&mut self, n: u32) {
+        self.bit += n as usize;
+    }
+
+    pub fn read(&mut self, n: u32) -> u64 {
+        let bits = self.peek(n);
+        self.skip(n);
+        bits
+    }
+
+    /// Skip to the start of the next byte.
+    pub fn align(&mut self) {
+        self.bit = self.bit.next_multiple_of(8);
+    }
+}
+
+#[test]
+fn test_bit_reader() {
+    let mut reader = BitReader::new(&[0b1010_0101, 0xff, 0x00, 0x81][..]);
+    reader.fill(4).unwrap();
+
+    assert_eq!(reader.read(3), 0b101);
+    assert_eq!(reader.peek16(), 0b0010_1111_1111_1000);
+    assert_eq!(reader.read(13), 0b0_0101_1111_1111);
+    reader.align();
+    assert_eq!(reader.position(), 16);
+    assert_eq!(reader.read(9), 1);
+    assert!(!reader.is_past_end());
+    assert_eq!(reader.read(16), 0b0000_0010_0000_0000);
+    assert!(reader.is_past_end());
+}
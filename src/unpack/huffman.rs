@@ -0,0 +1,123 @@
+use std::io;
+
+use super::bit_reader::BitReader;
+
+/// Maximum length of a code.
+const MAX_BITS: usize = 15;
+
+/// Number of bits looked up at once in the quick table.
+const QUICK_BITS: u32 = 10;
+
+/// Canonical Huffman decoding table, built from the lengths of the codes of each symbol
+/// the same way as UnRAR's `MakeDecodeTables`.
+pub struct HuffmanTable {
+    /// Upper limit of the left-aligned codes of each length.
+    decode_len: [u32; MAX_BITS + 1],
+
+    /// Index in `symbols` of the first code of each length.
+    decode_pos: [u32; MAX_BITS + 1],
+
+    /// Symbols sorted by code.
+    symbols: Vec<u16>,
+
+    quick_len: Vec<u8>,
+    quick_symbols: Vec<u16>,
+}
+
+impl HuffmanTable {
+    pub fn new(lengths: &[u8]) -> Self {
+        let mut length_count = [0u32; MAX_BITS + 1];
+        for &len in lengths {
+            length_count[(len & 0x0f) as usize] += 1;
+        }
+        length_count[0] = 0;
+
+        let mut decode_len = [0; MAX_BITS + 1];
+        let mut decode_pos = [0; MAX_BITS + 1];
+        let mut upper_limit = 0u32;
+        for i in 1..=MAX_BITS {
+            upper_limit += length_count[i];
+            decode_len[i] = upper_limit << (16 - i);
+            upper_limit *= 2;
+            decode_pos[i] = decode_pos[i - 1] + length_count[i - 1];
+        }
+
+        let mut symbols = vec![0; lengths.len()];
+        let mut next_pos = decode_pos;
+        for (symbol, &len) in lengths.iter().enumerate() {
+            let len = (len & 0x0f) as usize;
+            if len != 0 {
+                symbols[next_pos[len] as usize] = symbol as u16;
+                next_pos[len] += 1;
+            }
+        }
+
+        let mut table = Self {
+            decode_len,
+            decode_pos,
+            symbols,
+            quick_len: vec![0; 1 << QUICK_BITS],
+            quick_symbols: vec![0; 1 << QUICK_BITS],
+        };
+
+        let mut len = 1;
+        for code in 0..1 << QUICK_BITS {
+            let bit_field = code << (16 - QUICK_BITS);
+            while len < MAX_BITS + 1 && bit_field >= table.decode_len[len] {
+                len += 1;
+            }
+
+            table.quick_len[code as usize] = len as u8;
+            if len <= MAX_BITS {
+                table.quick_symbols[code as usize] =
+                    table.symbol(bit_field, len).unwrap_or_default();
+            }
+        }
+
+        table
+    }
+
+    /// Symbol of the code `bit_field` of length `len`, or `None` if the code isn't
+    /// assigned to a symbol.
+    fn symbol(&self, bit_field: u32, len: usize) -> Option<u16> {
+        let dist = (bit_field - self.decode_len[len - 1]) >> (16 - len);
+        let pos = (self.decode_pos[len] + dist) as usize;
+
+        self.symbols.get(pos).copied()
+    }
+
+    pub fn decode<R: io::Read>(&self, reader: &mut BitReader<R>) -> u16 {
+        let bit_field = reader.peek16() & 0xfffe;
+
+        if bit_field < self.decode_len[QUICK_BITS as usize] {
+            let code = (bit_field >> (16 - QUICK_BITS)) as usize;
+            reader.skip(self.quick_len[code] as u32);
+            return self.quick_symbols[code];
+        }
+
+        let len = (QUICK_BITS as usize + 1..MAX_BITS)
+            .find(|&len| bit_field < self.decode_len[len])
+            .unwrap_or(MAX_BITS);
+
+        reader.skip(len as u32);
+
+        // UnRAR falls back to the first symbol in this case, but to symbol 0 in the
+        // quick table.
+        self.symbol(bit_field, len)
+            .unwrap_or_else(|| self.symbols.first().copied().unwrap_or(0))
+    }
+}
+
+#[test]
+fn test_huffman_table() {
+    // Codes: 0 -> 'b', 10 -> 'a', 110 -> 'c', 111 -> 'd'.
+    let table = HuffmanTable::new(&[2, 1, 3, 3]);
+    let mut reader = BitReader::new(&[0b0101_1011, 0b1000_0000][..]);
+    reader.fill(2).unwrap();
+
+    let decoded = (0..5)
+        .map(|_| table.decode(&mut reader))
+        .collect::<Vec<_>>();
+    assert_eq!(decoded, [1, 0, 2, 3, 1]);
+    assert_eq!(reader.position(), 10);
+}
@@ -1 +1,15 @@
+mod bit_reader;
+mod huffman;
+mod ppmd;
+mod self_test;
 mod unpack15;
+mod unpack20;
+mod unpack29;
+mod unpack50;
+mod window;
+
+pub use self_test::{self_test, SelfTestError};
+pub(crate) use unpack15::Unpack15;
+pub(crate) use unpack20::Unpack20;
+pub(crate) use unpack29::Unpack29;
+pub(crate) use unpack50::Unpack50;
@@ -0,0 +1,1367 @@
+//! PPMd variant H, which RAR 2.9 uses as an alternative to LZ for text-like data. Based on
+//! UnRAR's `model.cpp` and `suballoc.cpp`.
+//!
+//! The model has to evolve exactly like the one in the compressor, including when it runs
+//! out of memory and restarts, so the contexts and states are stored in a heap managed by
+//! a port of the original sub-allocator, at the same offsets and with the same layout.
+
+use std::io;
+
+use super::bit_reader::BitReader;
+
+const MAX_O: usize = 64;
+const INT_BITS: u32 = 7;
+const PERIOD_BITS: u32 = 7;
+const TOT_BITS: u32 = INT_BITS + PERIOD_BITS;
+const INTERVAL: u32 = 1 << INT_BITS;
+const BIN_SCALE: u32 = 1 << TOT_BITS;
+const MAX_FREQ: u32 = 124;
+
+const EXP_ESCAPE: [u8; 16] = [25, 14, 9, 7, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2];
+const INIT_BIN_ESC: [u32; 8] = [
+    0x3cdd, 0x1f3f, 0x59bf, 0x48f3, 0x64a1, 0x5abc, 0x6632, 0x6051,
+];
+
+/// Size of a context, and of the allocation units of the heap.
+const UNIT_SIZE: u32 = 12;
+
+/// Size of a [`State`] in the heap.
+const STATE_SIZE: u32 = 6;
+
+const N1: usize = 4;
+const N2: usize = 4;
+const N3: usize = 4;
+const N4: usize = (128 + 3 - N1 - 2 * N2 - 3 * N3) / 4;
+const N_INDEXES: usize = N1 + N2 + N3 + N4;
+
+const TOP: u32 = 1 << 24;
+const BOT: u32 = 1 << 15;
+
+fn corrupt(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Carryless range decoder used by RAR, which differs from the one used by 7-Zip.
+#[derive(Default)]
+struct RangeDecoder {
+    low: u32,
+    code: u32,
+    range: u32,
+
+    low_count: u32,
+    high_count: u32,
+    scale: u32,
+}
+
+impl RangeDecoder {
+    fn init<R: io::Read>(&mut self, bits: &mut BitReader<R>) {
+        self.low = 0;
+        self.code = 0;
+        self.range = u32::MAX;
+        for _ in 0..4 {
+            self.code = self.code << 8 | bits.read(8) as u32;
+        }
+    }
+
+    fn current_count(&mut self) -> u32 {
+        self.range /= self.scale.max(1);
+        self.code
+            .wrapping_sub(self.low)
+            .checked_div(self.range)
+            .unwrap_or(u32::MAX)
+    }
+
+    fn current_shift_count(&mut self, shift: u32) -> u32 {
+        self.range >>= shift;
+        self.code
+            .wrapping_sub(self.low)
+            .checked_div(self.range)
+            .unwrap_or(u32::MAX)
+    }
+
+    fn decode(&mut self) {
+        self.low = self
+            .low
+            .wrapping_add(self.range.wrapping_mul(self.low_count));
+        self.range = self
+            .range
+            .wrapping_mul(self.high_count.wrapping_sub(self.low_count));
+    }
+
+    fn normalize<R: io::Read>(&mut self, bits: &mut BitReader<R>) {
+        loop {
+            if self.low ^ self.low.wrapping_add(self.range) >= TOP {
+                if self.range >= BOT {
+                    break;
+                }
+                self.range = self.low.wrapping_neg() & (BOT - 1);
+            }
+
+            self.code = self.code << 8 | bits.read(8) as u32;
+            self.range <<= 8;
+            self.low <<= 8;
+        }
+    }
+}
+
+/// Secondary escape estimation context.
+#[derive(Clone, Copy, Default)]
+struct See2Context {
+    summ: u16,
+    shift: u8,
+    count: u8,
+}
+
+impl See2Context {
+    fn init(&mut self, init_value: u32) {
+        self.shift = (PERIOD_BITS - 4) as u8;
+        self.summ = (init_value << self.shift) as u16;
+        self.count = 4;
+    }
+
+    fn mean(&mut self) -> u32 {
+        let mean = self.summ >> self.shift;
+        self.summ = self.summ.wrapping_sub(mean);
+        mean as u32 + (mean == 0) as u32
+    }
+
+    fn update(&mut self) {
+        if (self.shift as u32) < PERIOD_BITS {
+            self.count = self.count.wrapping_sub(1);
+            if self.count == 0 {
+                self.summ = self.summ.wrapping_add(self.summ);
+                self.count = (3 << self.shift) as u8;
+                self.shift += 1;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct State {
+    symbol: u8,
+    freq: u8,
+    successor: u32,
+}
+
+/// Memory allocator of the model, which hands out offsets in its heap in multiples
+/// of [`UNIT_SIZE`]. Offset 0 is used as the null pointer.
+///
+/// Text is written upwards from the start of the heap, while contexts and states are
+/// allocated in the rest of it.
+struct SubAllocator {
+    heap: Vec<u8>,
+
+    /// Size of the heap requested by the compressor.
+    size: u32,
+    heap_end: u32,
+
+    free_list: [u32; N_INDEXES],
+    index_to_units: [u8; N_INDEXES],
+    units_to_index: [u8; 128],
+    glue_count: u32,
+
+    text: u32,
+    units_start: u32,
+    fake_units_start: u32,
+    low_unit: u32,
+    high_unit: u32,
+}
+
+impl SubAllocator {
+    fn new() -> Self {
+        // The block sizes grow by 1, 2, 3 and then 4 units.
+        let mut index_to_units = [0; N_INDEXES];
+        let mut sizes = index_to_units.iter_mut();
+        let mut units = 0;
+        for (count, step) in [(N1, 1), (N2, 2), (N3, 3), (N4, 4)] {
+            for entry in sizes.by_ref().take(count) {
+                units += step;
+                *entry = units;
+            }
+        }
+
+        let mut units_to_index = [0; 128];
+        let mut i = 0;
+        for (k, entry) in units_to_index.iter_mut().enumerate() {
+            if (index_to_units[i] as usize) < k + 1 {
+                i += 1;
+            }
+            *entry = i as u8;
+        }
+
+        Self {
+            heap: vec![],
+            size: 0,
+            heap_end: 0,
+            free_list: [0; N_INDEXES],
+            index_to_units,
+            units_to_index,
+            glue_count: 0,
+            text: 0,
+            units_start: 0,
+            fake_units_start: 0,
+            low_unit: 0,
+            high_unit: 0,
+        }
+    }
+
+    fn start(&mut self, size_mb: u32) {
+        let size = size_mb << 20;
+        if self.size == size {
+            return;
+        }
+
+        // One spare unit past the end of the heap is used by the checks for overflows,
+        // and one more holds the head of the list of free blocks used while gluing them.
+        let alloc_size = size / UNIT_SIZE * UNIT_SIZE + 2 * UNIT_SIZE;
+        self.heap = vec![0; (alloc_size + UNIT_SIZE) as usize];
+        self.heap_end = alloc_size - UNIT_SIZE;
+        self.size = size;
+    }
+
+    fn stop(&mut self) {
+        self.heap = vec![];
+        self.size = 0;
+    }
+
+    fn init(&mut self) {
+        self.free_list = [0; N_INDEXES];
+        self.text = 0;
+
+        let size2 = UNIT_SIZE * (self.size / 8 / UNIT_SIZE * 7);
+        let size1 = self.size - size2;
+        let real_size1 = size1 / UNIT_SIZE * UNIT_SIZE + UNIT_SIZE;
+
+        self.low_unit = real_size1;
+        self.units_start = real_size1;
+        self.fake_units_start = size1;
+        self.high_unit = self.low_unit + size2;
+        self.glue_count = 0;
+    }
+
+    fn u8(&self, at: u32) -> u8 {
+        self.heap[at as usize]
+    }
+
+    fn set_u8(&mut self, at: u32, value: u8) {
+        self.heap[at as usize] = value;
+    }
+
+    fn u16(&self, at: u32) -> u16 {
+        let at = at as usize;
+        u16::from_le_bytes([self.heap[at], self.heap[at + 1]])
+    }
+
+    fn set_u16(&mut self, at: u32, value: u16) {
+        let at = at as usize;
+        self.heap[at..at + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&self, at: u32) -> u32 {
+        let at = at as usize;
+        u32::from_le_bytes(self.heap[at..at + 4].try_into().unwrap())
+    }
+
+    fn set_u32(&mut self, at: u32, value: u32) {
+        let at = at as usize;
+        self.heap[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn units(&self, index: usize) -> u32 {
+        self.index_to_units[index] as u32
+    }
+
+    fn index(&self, units: u32) -> usize {
+        self.units_to_index[units as usize - 1] as usize
+    }
+
+    fn insert_node(&mut self, node: u32, index: usize) {
+        self.set_u32(node, self.free_list[index]);
+        self.free_list[index] = node;
+    }
+
+    fn remove_node(&mut self, index: usize) -> u32 {
+        let node = self.free_list[index];
+        self.free_list[index] = self.u32(node);
+        node
+    }
+
+    fn split_block(&mut self, block: u32, old_index: usize, new_index: usize) {
+        let mut diff = self.units(old_index) - self.units(new_index);
+        let mut block = block + self.units(new_index) * UNIT_SIZE;
+
+        let mut index = self.index(diff);
+        if self.units(index) != diff {
+            index -= 1;
+            self.insert_node(block, index);
+            block += self.units(index) * UNIT_SIZE;
+            diff -= self.units(index);
+        }
+
+        self.insert_node(block, self.index(diff));
+    }
+
+    // Free blocks are linked by the glue step in a circular list made of a stamp,
+    // the number of units, and the offsets of the next and previous blocks.
+
+    fn block_next(&self, block: u32) -> u32 {
+        self.u32(block + 4)
+    }
+
+    fn block_prev(&self, block: u32) -> u32 {
+        self.u32(block + 8)
+    }
+
+    fn block_insert_after(&mut self, block: u32, head: u32) {
+        let next = self.block_next(head);
+        self.set_u32(block + 8, head);
+        self.set_u32(block + 4, next);
+        self.set_u32(next + 8, block);
+        self.set_u32(head + 4, block);
+    }
+
+    fn block_remove(&mut self, block: u32) {
+        let (next, prev) = (self.block_next(block), self.block_prev(block));
+        self.set_u32(prev + 4, next);
+        self.set_u32(next + 8, prev);
+    }
+
+    /// Merge adjacent free blocks.
+    fn glue_free_blocks(&mut self) {
+        let head = self.heap_end + UNIT_SIZE;
+
+        if self.low_unit != self.high_unit {
+            self.set_u8(self.low_unit, 0);
+        }
+
+        self.set_u32(head + 4, head);
+        self.set_u32(head + 8, head);
+        for index in 0..N_INDEXES {
+            while self.free_list[index] != 0 {
+                let block = self.remove_node(index);
+                self.block_insert_after(block, head);
+                self.set_u16(block, 0xffff);
+                self.set_u16(block + 2, self.units(index) as u16);
+            }
+        }
+
+        let mut block = self.block_next(head);
+        while block != head {
+            loop {
+                let units = self.u16(block + 2) as u32;
+                let next = block + units * UNIT_SIZE;
+                let next_units = self.u16(next + 2) as u32;
+                if self.u16(next) != 0xffff || units + next_units >= 0x10000 {
+                    break;
+                }
+                self.block_remove(next);
+                self.set_u16(block + 2, (units + next_units) as u16);
+            }
+            block = self.block_next(block);
+        }
+
+        loop {
+            let mut block = self.block_next(head);
+            if block == head {
+                break;
+            }
+
+            self.block_remove(block);
+            let mut units = self.u16(block + 2) as u32;
+            while units > 128 {
+                self.insert_node(block, N_INDEXES - 1);
+                units -= 128;
+                block += 128 * UNIT_SIZE;
+            }
+
+            let mut index = self.index(units);
+            if self.units(index) != units {
+                index -= 1;
+                let rest = units - self.units(index);
+                self.insert_node(block + (units - rest) * UNIT_SIZE, rest as usize - 1);
+            }
+            self.insert_node(block, index);
+        }
+    }
+
+    fn alloc_units_rare(&mut self, index: usize) -> u32 {
+        if self.glue_count == 0 {
+            self.glue_count = 255;
+            self.glue_free_blocks();
+            if self.free_list[index] != 0 {
+                return self.remove_node(index);
+            }
+        }
+
+        let mut i = index;
+        loop {
+            i += 1;
+            if i == N_INDEXES {
+                self.glue_count -= 1;
+                let size = self.units(index) * UNIT_SIZE;
+                if self.fake_units_start as i64 - self.text as i64 > size as i64 {
+                    self.fake_units_start -= size;
+                    self.units_start -= size;
+                    return self.units_start;
+                }
+                return 0;
+            }
+
+            if self.free_list[i] != 0 {
+                break;
+            }
+        }
+
+        let block = self.remove_node(i);
+        self.split_block(block, i, index);
+        block
+    }
+
+    fn alloc_units(&mut self, units: u32) -> u32 {
+        let index = self.index(units);
+        if self.free_list[index] != 0 {
+            return self.remove_node(index);
+        }
+
+        let block = self.low_unit;
+        let size = self.units(index) * UNIT_SIZE;
+        if self.low_unit + size <= self.high_unit {
+            self.low_unit += size;
+            return block;
+        }
+
+        self.alloc_units_rare(index)
+    }
+
+    fn alloc_context(&mut self) -> u32 {
+        if self.high_unit != self.low_unit {
+            self.high_unit -= UNIT_SIZE;
+            return self.high_unit;
+        }
+
+        if self.free_list[0] != 0 {
+            return self.remove_node(0);
+        }
+
+        self.alloc_units_rare(0)
+    }
+
+    fn copy_units(&mut self, from: u32, to: u32, units: u32) {
+        let (from, to) = (from as usize, to as usize);
+        self.heap
+            .copy_within(from..from + (units * UNIT_SIZE) as usize, to);
+    }
+
+    fn expand_units(&mut self, block: u32, old_units: u32) -> u32 {
+        let old_index = self.index(old_units);
+        if old_index == self.index(old_units + 1) {
+            return block;
+        }
+
+        let new_block = self.alloc_units(old_units + 1);
+        if new_block != 0 {
+            self.copy_units(block, new_block, old_units);
+            self.insert_node(block, old_index);
+        }
+
+        new_block
+    }
+
+    fn shrink_units(&mut self, block: u32, old_units: u32, new_units: u32) -> u32 {
+        let old_index = self.index(old_units);
+        let new_index = self.index(new_units);
+        if old_index == new_index {
+            return block;
+        }
+
+        if self.free_list[new_index] != 0 {
+            let new_block = self.remove_node(new_index);
+            self.copy_units(block, new_block, new_units);
+            self.insert_node(block, old_index);
+            new_block
+        } else {
+            self.split_block(block, old_index, new_index);
+            block
+        }
+    }
+
+    fn free_units(&mut self, block: u32, units: u32) {
+        self.insert_node(block, self.index(units));
+    }
+}
+
+/// PPMd model along with its range decoder.
+///
+/// Contexts are 12 bytes long: the number of states, followed either by the total frequency
+/// and the offset of the states, or by the only state of binary contexts, and the offset of
+/// the suffix context. States are 6 bytes long: the symbol, its frequency and the offset
+/// of the successor context.
+pub struct Model {
+    alloc: SubAllocator,
+    coder: RangeDecoder,
+
+    see2: [[See2Context; 16]; 25],
+    dummy_see2: See2Context,
+
+    min_context: u32,
+    max_context: u32,
+    found_state: u32,
+
+    num_masked: u32,
+    init_esc: u32,
+    order_fall: u32,
+    max_order: u32,
+    run_length: i32,
+    init_rl: i32,
+    esc_count: u8,
+    prev_success: u32,
+    hi_bits_flag: u32,
+
+    ns_to_index: [u8; 256],
+    ns_to_bs_index: [u8; 256],
+    hb_to_flag: [u8; 256],
+    bin_summ: [[u16; 64]; 128],
+    char_mask: [u8; 256],
+}
+
+impl Model {
+    pub fn new() -> Self {
+        Self {
+            alloc: SubAllocator::new(),
+            coder: RangeDecoder::default(),
+            see2: [[See2Context::default(); 16]; 25],
+            dummy_see2: See2Context::default(),
+            min_context: 0,
+            max_context: 0,
+            found_state: 0,
+            num_masked: 0,
+            init_esc: 0,
+            order_fall: 0,
+            max_order: 0,
+            run_length: 0,
+            init_rl: 0,
+            esc_count: 0,
+            prev_success: 0,
+            hi_bits_flag: 0,
+            ns_to_index: [0; 256],
+            ns_to_bs_index: [0; 256],
+            hb_to_flag: [0; 256],
+            bin_summ: [[0; 64]; 128],
+            char_mask: [0; 256],
+        }
+    }
+
+    /// Read the parameters of a PPMd block and reset the model if requested.
+    ///
+    /// `escape` is updated if the block sets a new escape character.
+    pub fn init<R: io::Read>(
+        &mut self,
+        bits: &mut BitReader<R>,
+        escape: &mut u8,
+    ) -> io::Result<()> {
+        let flags = bits.read(8) as u32;
+        let reset = flags & 0x20 != 0;
+
+        let max_mb = if reset {
+            bits.read(8) as u32
+        } else if self.alloc.size == 0 {
+            return Err(corrupt("PPMd block continues a missing model"));
+        } else {
+            0
+        };
+
+        if flags & 0x40 != 0 {
+            *escape = bits.read(8) as u8;
+        }
+
+        self.coder.init(bits);
+
+        if reset {
+            let mut max_order = (flags & 0x1f) + 1;
+            if max_order > 16 {
+                max_order = 16 + (max_order - 16) * 3;
+            }
+
+            if max_order == 1 {
+                self.alloc.stop();
+                return Err(corrupt("invalid PPMd model order"));
+            }
+
+            self.alloc.start(max_mb + 1);
+            self.start_model(max_order);
+        }
+
+        if self.min_context == 0 {
+            return Err(corrupt("invalid PPMd model"));
+        }
+
+        Ok(())
+    }
+
+    // Context fields.
+
+    fn num_stats(&self, context: u32) -> u32 {
+        self.alloc.u16(context) as u32
+    }
+
+    fn set_num_stats(&mut self, context: u32, value: u32) {
+        self.alloc.set_u16(context, value as u16);
+    }
+
+    fn summ_freq(&self, context: u32) -> u32 {
+        self.alloc.u16(context + 2) as u32
+    }
+
+    fn set_summ_freq(&mut self, context: u32, value: u32) {
+        self.alloc.set_u16(context + 2, value as u16);
+    }
+
+    fn stats(&self, context: u32) -> u32 {
+        self.alloc.u32(context + 4)
+    }
+
+    fn set_stats(&mut self, context: u32, value: u32) {
+        self.alloc.set_u32(context + 4, value);
+    }
+
+    fn suffix(&self, context: u32) -> u32 {
+        self.alloc.u32(context + 8)
+    }
+
+    fn set_suffix(&mut self, context: u32, value: u32) {
+        self.alloc.set_u32(context + 8, value);
+    }
+
+    fn one_state(context: u32) -> u32 {
+        context + 2
+    }
+
+    // State fields.
+
+    fn symbol(&self, state: u32) -> u8 {
+        self.alloc.u8(state)
+    }
+
+    fn freq(&self, state: u32) -> u32 {
+        self.alloc.u8(state + 1) as u32
+    }
+
+    fn set_freq(&mut self, state: u32, value: u32) {
+        self.alloc.set_u8(state + 1, value as u8);
+    }
+
+    fn successor(&self, state: u32) -> u32 {
+        self.alloc.u32(state + 2)
+    }
+
+    fn set_successor(&mut self, state: u32, value: u32) {
+        self.alloc.set_u32(state + 2, value);
+    }
+
+    fn state(&self, state: u32) -> State {
+        State {
+            symbol: self.symbol(state),
+            freq: self.freq(state) as u8,
+            successor: self.successor(state),
+        }
+    }
+
+    fn set_state(&mut self, at: u32, state: State) {
+        self.alloc.set_u8(at, state.symbol);
+        self.alloc.set_u8(at + 1, state.freq);
+        self.set_successor(at, state.successor);
+    }
+
+    fn swap_states(&mut self, a: u32, b: u32) {
+        let (state_a, state_b) = (self.state(a), self.state(b));
+        self.set_state(a, state_b);
+        self.set_state(b, state_a);
+    }
+
+    fn restart_model(&mut self) {
+        self.char_mask = [0; 256];
+        self.alloc.init();
+
+        self.init_rl = -(self.max_order.min(12) as i32) - 1;
+
+        let context = self.alloc.alloc_context();
+        self.min_context = context;
+        self.max_context = context;
+        self.set_suffix(context, 0);
+        self.order_fall = self.max_order;
+        self.set_num_stats(context, 256);
+        self.set_summ_freq(context, 257);
+
+        let stats = self.alloc.alloc_units(256 / 2);
+        self.found_state = stats;
+        self.set_stats(context, stats);
+
+        self.run_length = self.init_rl;
+        self.prev_success = 0;
+
+        for symbol in 0..256 {
+            let state = State {
+                symbol: symbol as u8,
+                freq: 1,
+                successor: 0,
+            };
+            self.set_state(stats + symbol * STATE_SIZE, state);
+        }
+
+        for (i, row) in self.bin_summ.iter_mut().enumerate() {
+            for (k, &escape) in INIT_BIN_ESC.iter().enumerate() {
+                for m in (0..64).step_by(8) {
+                    row[k + m] = (BIN_SCALE - escape / (i as u32 + 2)) as u16;
+                }
+            }
+        }
+
+        for (i, row) in self.see2.iter_mut().enumerate() {
+            for context in row {
+                context.init(5 * i as u32 + 10);
+            }
+        }
+    }
+
+    fn start_model(&mut self, max_order: u32) {
+        self.esc_count = 1;
+        self.max_order = max_order;
+        self.restart_model();
+
+        self.ns_to_bs_index[0] = 0;
+        self.ns_to_bs_index[1] = 2;
+        self.ns_to_bs_index[2..11].fill(4);
+        self.ns_to_bs_index[11..].fill(6);
+
+        let (mut m, mut k, mut step) = (3, 1, 1);
+        for i in 0..256 {
+            if i < 3 {
+                self.ns_to_index[i] = i as u8;
+                continue;
+            }
+
+            self.ns_to_index[i] = m;
+            k -= 1;
+            if k == 0 {
+                step += 1;
+                k = step;
+                m += 1;
+            }
+        }
+
+        self.hb_to_flag[..0x40].fill(0);
+        self.hb_to_flag[0x40..].fill(8);
+        self.dummy_see2.shift = PERIOD_BITS as u8;
+    }
+
+    fn create_child(&mut self, context: u32, state: u32, first_state: State) -> u32 {
+        let child = self.alloc.alloc_context();
+        if child != 0 {
+            self.set_num_stats(child, 1);
+            self.set_state(Self::one_state(child), first_state);
+            self.set_suffix(child, context);
+            self.set_successor(state, child);
+        }
+        child
+    }
+
+    fn create_successors(&mut self, skip: bool, first: u32) -> u32 {
+        let mut context = self.min_context;
+        let up_branch = self.successor(self.found_state);
+        let found_symbol = self.symbol(self.found_state);
+
+        let mut states = [0; MAX_O];
+        let mut count = 0;
+
+        let mut no_loop = false;
+        if !skip {
+            states[count] = self.found_state;
+            count += 1;
+            no_loop = self.suffix(context) == 0;
+        }
+
+        if !no_loop {
+            let mut first = (first != 0).then_some(first);
+            loop {
+                context = self.suffix(context);
+
+                let state = match first.take() {
+                    Some(state) => state,
+                    None if self.num_stats(context) != 1 => {
+                        let mut state = self.stats(context);
+                        while self.symbol(state) != found_symbol {
+                            state += STATE_SIZE;
+                        }
+                        state
+                    }
+                    None => Self::one_state(context),
+                };
+
+                if self.successor(state) != up_branch {
+                    context = self.successor(state);
+                    break;
+                }
+
+                if count == MAX_O {
+                    return 0;
+                }
+                states[count] = state;
+                count += 1;
+
+                if self.suffix(context) == 0 {
+                    break;
+                }
+            }
+        }
+
+        if count == 0 {
+            return context;
+        }
+
+        let up_symbol = self.alloc.u8(up_branch);
+        let up_freq = if self.num_stats(context) != 1 {
+            if context <= self.alloc.text {
+                return 0;
+            }
+
+            let mut state = self.stats(context);
+            while self.symbol(state) != up_symbol {
+                state += STATE_SIZE;
+            }
+
+            let cf = self.freq(state) - 1;
+            let s0 = self
+                .summ_freq(context)
+                .wrapping_sub(self.num_stats(context))
+                .wrapping_sub(cf);
+
+            1 + if 2 * cf <= s0 {
+                (5 * cf > s0) as u32
+            } else if s0 == 0 {
+                return 0;
+            } else {
+                (2 * cf + 3 * s0 - 1) / (2 * s0)
+            }
+        } else {
+            self.freq(Self::one_state(context))
+        };
+
+        let up_state = State {
+            symbol: up_symbol,
+            freq: up_freq as u8,
+            successor: up_branch + 1,
+        };
+
+        while count > 0 {
+            count -= 1;
+            context = self.create_child(context, states[count], up_state);
+            if context == 0 {
+                return 0;
+            }
+        }
+
+        context
+    }
+
+    fn update_model(&mut self) {
+        if self.try_update_model().is_none() {
+            self.restart_model();
+            self.esc_count = 0;
+        }
+    }
+
+    /// Returns [`None`] if the model ran out of memory and has to be restarted.
+    fn try_update_model(&mut self) -> Option<()> {
+        let fs = self.state(self.found_state);
+        let fs_freq = fs.freq as u32;
+        let mut state = 0;
+
+        let suffix = self.suffix(self.min_context);
+        if fs_freq < MAX_FREQ / 4 && suffix != 0 {
+            if self.num_stats(suffix) != 1 {
+                state = self.stats(suffix);
+                if self.symbol(state) != fs.symbol {
+                    loop {
+                        state += STATE_SIZE;
+                        if self.symbol(state) == fs.symbol {
+                            break;
+                        }
+                    }
+
+                    if self.freq(state) >= self.freq(state - STATE_SIZE) {
+                        self.swap_states(state, state - STATE_SIZE);
+                        state -= STATE_SIZE;
+                    }
+                }
+
+                if self.freq(state) < MAX_FREQ - 9 {
+                    self.set_freq(state, self.freq(state) + 2);
+                    self.set_summ_freq(suffix, self.summ_freq(suffix) + 2);
+                }
+            } else {
+                state = Self::one_state(suffix);
+                if self.freq(state) < 32 {
+                    self.set_freq(state, self.freq(state) + 1);
+                }
+            }
+        }
+
+        if self.order_fall == 0 {
+            let context = self.create_successors(true, state);
+            self.min_context = context;
+            self.max_context = context;
+            self.set_successor(self.found_state, context);
+            return (context != 0).then_some(());
+        }
+
+        self.alloc.set_u8(self.alloc.text, fs.symbol);
+        self.alloc.text += 1;
+        let mut successor = self.alloc.text;
+        if self.alloc.text >= self.alloc.fake_units_start {
+            return None;
+        }
+
+        let mut fs_successor = fs.successor;
+        if fs_successor != 0 {
+            if fs_successor <= self.alloc.text {
+                fs_successor = self.create_successors(false, state);
+                if fs_successor == 0 {
+                    return None;
+                }
+            }
+
+            self.order_fall -= 1;
+            if self.order_fall == 0 {
+                successor = fs_successor;
+                if self.max_context != self.min_context {
+                    self.alloc.text -= 1;
+                }
+            }
+        } else {
+            self.set_successor(self.found_state, successor);
+            fs_successor = self.min_context;
+        }
+
+        let ns = self.num_stats(self.min_context);
+        let s0 = self
+            .summ_freq(self.min_context)
+            .wrapping_sub(ns)
+            .wrapping_sub(fs_freq - 1);
+
+        let mut context = self.max_context;
+        while context != self.min_context {
+            let ns1 = self.num_stats(context);
+            if ns1 != 1 {
+                if ns1 & 1 == 0 {
+                    let stats = self.alloc.expand_units(self.stats(context), ns1 >> 1);
+                    if stats == 0 {
+                        return None;
+                    }
+                    self.set_stats(context, stats);
+                }
+
+                let summ = self.summ_freq(context);
+                let bonus =
+                    (2 * ns1 < ns) as u32 + 2 * ((4 * ns1 <= ns) as u32 & (summ <= 8 * ns1) as u32);
+                self.set_summ_freq(context, summ + bonus);
+            } else {
+                let stats = self.alloc.alloc_units(1);
+                if stats == 0 {
+                    return None;
+                }
+
+                let one_state = self.state(Self::one_state(context));
+                self.set_state(stats, one_state);
+                self.set_stats(context, stats);
+
+                let freq = self.freq(stats);
+                let freq = if freq < MAX_FREQ / 4 - 1 {
+                    freq * 2
+                } else {
+                    MAX_FREQ - 4
+                };
+                self.set_freq(stats, freq);
+                self.set_summ_freq(context, freq + self.init_esc + (ns > 3) as u32);
+            }
+
+            let summ = self.summ_freq(context);
+            let mut cf = 2 * fs_freq * (summ + 6);
+            let sf = s0.wrapping_add(summ);
+            if cf < 6 * sf {
+                cf = 1 + (cf > sf) as u32 + (cf >= 4 * sf) as u32;
+                self.set_summ_freq(context, summ + 3);
+            } else {
+                cf = 4 + (cf >= 9 * sf) as u32 + (cf >= 12 * sf) as u32 + (cf >= 15 * sf) as u32;
+                self.set_summ_freq(context, summ + cf);
+            }
+
+            let state = self.stats(context) + ns1 * STATE_SIZE;
+            self.set_state(
+                state,
+                State {
+                    symbol: fs.symbol,
+                    freq: cf as u8,
+                    successor,
+                },
+            );
+            self.set_num_stats(context, ns1 + 1);
+
+            context = self.suffix(context);
+        }
+
+        self.max_context = fs_successor;
+        self.min_context = fs_successor;
+        Some(())
+    }
+
+    fn rescale(&mut self, context: u32) {
+        let old_ns = self.num_stats(context);
+        let stats = self.stats(context);
+
+        // Move the found state to the front.
+        let mut state = self.found_state;
+        while state != stats {
+            self.swap_states(state, state - STATE_SIZE);
+            state -= STATE_SIZE;
+        }
+
+        self.set_freq(stats, self.freq(stats) + 4);
+        self.set_summ_freq(context, self.summ_freq(context) + 4);
+
+        let mut esc_freq = self.summ_freq(context) as i32 - self.freq(state) as i32;
+        let adder = (self.order_fall != 0) as u32;
+
+        let freq = (self.freq(state) + adder) >> 1;
+        self.set_freq(state, freq);
+        let mut summ = freq;
+
+        for _ in 1..old_ns {
+            state += STATE_SIZE;
+            esc_freq -= self.freq(state) as i32;
+
+            let freq = (self.freq(state) + adder) >> 1;
+            self.set_freq(state, freq);
+            summ += freq;
+
+            if freq > self.freq(state - STATE_SIZE) {
+                let moved = self.state(state);
+                let mut at = state;
+                loop {
+                    self.set_state(at, self.state(at - STATE_SIZE));
+                    at -= STATE_SIZE;
+                    if at == stats || moved.freq as u32 <= self.freq(at - STATE_SIZE) {
+                        break;
+                    }
+                }
+                self.set_state(at, moved);
+            }
+        }
+
+        if self.freq(state) == 0 {
+            let mut zeros = 0;
+            loop {
+                zeros += 1;
+                state -= STATE_SIZE;
+                if self.freq(state) != 0 {
+                    break;
+                }
+            }
+
+            esc_freq += zeros as i32;
+            let ns = old_ns - zeros;
+            self.set_num_stats(context, ns);
+
+            if ns == 1 {
+                let mut first = self.state(stats);
+                loop {
+                    first.freq -= first.freq >> 1;
+                    esc_freq >>= 1;
+                    if esc_freq <= 1 {
+                        break;
+                    }
+                }
+
+                self.alloc.free_units(stats, (old_ns + 1) >> 1);
+                self.found_state = Self::one_state(context);
+                self.set_state(self.found_state, first);
+                return;
+            }
+        }
+
+        esc_freq -= esc_freq >> 1;
+        self.set_summ_freq(context, summ.wrapping_add(esc_freq as u32));
+
+        let old_units = (old_ns + 1) >> 1;
+        let new_units = (self.num_stats(context) + 1) >> 1;
+        if old_units != new_units {
+            let stats = self.alloc.shrink_units(stats, old_units, new_units);
+            self.set_stats(context, stats);
+        }
+
+        self.found_state = self.stats(context);
+    }
+
+    fn decode_bin_symbol(&mut self, context: u32) {
+        let state = Self::one_state(context);
+        let freq = self.freq(state);
+
+        self.hi_bits_flag = self.hb_to_flag[self.symbol(self.found_state) as usize] as u32;
+        let suffix_ns = self.num_stats(self.suffix(context));
+        let i = (freq as usize).saturating_sub(1).min(127);
+        let j = self.prev_success
+            + self.ns_to_bs_index[(suffix_ns as usize).saturating_sub(1).min(255)] as u32
+            + self.hi_bits_flag
+            + 2 * self.hb_to_flag[self.symbol(state) as usize] as u32
+            + ((self.run_length >> 26) & 0x20) as u32;
+        let bs = self.bin_summ[i][j as usize] as u32;
+        let mean = (bs + (1 << (PERIOD_BITS - 2))) >> PERIOD_BITS;
+
+        if self.coder.current_shift_count(TOT_BITS) < bs {
+            self.found_state = state;
+            self.set_freq(state, freq + (freq < 128) as u32);
+            self.coder.low_count = 0;
+            self.coder.high_count = bs;
+            self.bin_summ[i][j as usize] = (bs + INTERVAL - mean) as u16;
+            self.prev_success = 1;
+            self.run_length += 1;
+        } else {
+            self.coder.low_count = bs;
+            let bs = (bs - mean) as u16;
+            self.bin_summ[i][j as usize] = bs;
+            self.coder.high_count = BIN_SCALE;
+            self.init_esc = EXP_ESCAPE[(bs >> 10).min(15) as usize] as u32;
+            self.num_masked = 1;
+            self.char_mask[self.symbol(state) as usize] = self.esc_count;
+            self.prev_success = 0;
+            self.found_state = 0;
+        }
+    }
+
+    fn update1(&mut self, context: u32, mut state: u32) {
+        self.found_state = state;
+        self.set_freq(state, self.freq(state) + 4);
+        self.set_summ_freq(context, self.summ_freq(context) + 4);
+
+        if self.freq(state) > self.freq(state - STATE_SIZE) {
+            self.swap_states(state, state - STATE_SIZE);
+            state -= STATE_SIZE;
+            self.found_state = state;
+            if self.freq(state) > MAX_FREQ {
+                self.rescale(context);
+            }
+        }
+    }
+
+    fn decode_symbol1(&mut self, context: u32) -> bool {
+        let mut state = self.stats(context);
+        self.coder.scale = self.summ_freq(context);
+
+        let count = self.coder.current_count();
+        if count >= self.coder.scale {
+            return false;
+        }
+
+        let mut hi_cnt = self.freq(state);
+        if count < hi_cnt {
+            self.coder.high_count = hi_cnt;
+            self.prev_success = (2 * hi_cnt > self.coder.scale) as u32;
+            self.run_length += self.prev_success as i32;
+
+            hi_cnt += 4;
+            self.found_state = state;
+            self.set_freq(state, hi_cnt);
+            self.set_summ_freq(context, self.summ_freq(context) + 4);
+            if hi_cnt > MAX_FREQ {
+                self.rescale(context);
+            }
+
+            self.coder.low_count = 0;
+            return true;
+        } else if self.found_state == 0 {
+            return false;
+        }
+
+        self.prev_success = 0;
+
+        let num_stats = self.num_stats(context);
+        for _ in 1..num_stats {
+            state += STATE_SIZE;
+            hi_cnt += self.freq(state);
+
+            if hi_cnt > count {
+                self.coder.high_count = hi_cnt;
+                self.coder.low_count = hi_cnt - self.freq(state);
+                self.update1(context, state);
+                return true;
+            }
+        }
+
+        // Escape: mask all the symbols of this context.
+        self.hi_bits_flag = self.hb_to_flag[self.symbol(self.found_state) as usize] as u32;
+        self.coder.low_count = hi_cnt;
+        self.coder.high_count = self.coder.scale;
+        self.num_masked = num_stats;
+        self.found_state = 0;
+
+        for i in 0..num_stats {
+            let symbol = self.symbol(state - i * STATE_SIZE);
+            self.char_mask[symbol as usize] = self.esc_count;
+        }
+
+        true
+    }
+
+    fn update2(&mut self, context: u32, state: u32) {
+        self.found_state = state;
+        self.set_freq(state, self.freq(state) + 4);
+        self.set_summ_freq(context, self.summ_freq(context) + 4);
+        if self.freq(state) > MAX_FREQ {
+            self.rescale(context);
+        }
+
+        self.esc_count = self.esc_count.wrapping_add(1);
+        self.run_length = self.init_rl;
+    }
+
+    /// Select the SEE context used to estimate the escape frequency, or [`None`] for
+    /// the dummy context.
+    fn make_esc_freq2(&mut self, context: u32, diff: u32) -> Option<(usize, usize)> {
+        let num_stats = self.num_stats(context);
+
+        if num_stats != 256 {
+            let suffix_ns = self.num_stats(self.suffix(context));
+            let row = self.ns_to_index[(diff as usize).saturating_sub(1).min(255)] as usize;
+            let column = ((diff as i32) < suffix_ns as i32 - num_stats as i32) as usize
+                + 2 * (self.summ_freq(context) < 11 * num_stats) as usize
+                + 4 * (self.num_masked > diff) as usize
+                + self.hi_bits_flag as usize;
+
+            self.coder.scale = self.see2[row][column].mean();
+            Some((row, column))
+        } else {
+            self.coder.scale = 1;
+            None
+        }
+    }
+
+    fn decode_symbol2(&mut self, context: u32) -> bool {
+        let unmasked = self.num_stats(context).wrapping_sub(self.num_masked);
+        if unmasked == 0 || unmasked > 256 {
+            return false;
+        }
+
+        let see = self.make_esc_freq2(context, unmasked);
+
+        let mut states = Vec::with_capacity(unmasked as usize);
+        let mut state = self.stats(context);
+        let mut hi_cnt = 0;
+        while states.len() < unmasked as usize {
+            while self.char_mask[self.symbol(state) as usize] == self.esc_count {
+                state += STATE_SIZE;
+            }
+            hi_cnt += self.freq(state);
+            states.push(state);
+            state += STATE_SIZE;
+        }
+
+        self.coder.scale += hi_cnt;
+        let count = self.coder.current_count();
+        if count >= self.coder.scale {
+            return false;
+        }
+
+        if count < hi_cnt {
+            let mut hi_cnt = 0;
+            for &state in &states {
+                hi_cnt += self.freq(state);
+                if hi_cnt > count {
+                    self.coder.high_count = hi_cnt;
+                    self.coder.low_count = hi_cnt - self.freq(state);
+                    if let Some((row, column)) = see {
+                        self.see2[row][column].update();
+                    }
+                    self.update2(context, state);
+                    return true;
+                }
+            }
+            false
+        } else {
+            self.coder.low_count = hi_cnt;
+            self.coder.high_count = self.coder.scale;
+
+            for &state in &states {
+                self.char_mask[self.symbol(state) as usize] = self.esc_count;
+            }
+
+            let see2 = match see {
+                Some((row, column)) => &mut self.see2[row][column],
+                None => &mut self.dummy_see2,
+            };
+            see2.summ = see2.summ.wrapping_add(self.coder.scale as u16);
+            self.num_masked = self.num_stats(context);
+            true
+        }
+    }
+
+    fn is_valid_context(&self, context: u32) -> bool {
+        context > self.alloc.text && context <= self.alloc.heap_end
+    }
+
+    /// Decode the next byte, or return [`None`] if the data is invalid.
+    pub fn decode_char<R: io::Read>(&mut self, bits: &mut BitReader<R>) -> Option<u8> {
+        if !self.is_valid_context(self.min_context) {
+            return None;
+        }
+
+        if self.num_stats(self.min_context) != 1 {
+            if !self.is_valid_context(self.stats(self.min_context)) {
+                return None;
+            }
+            if !self.decode_symbol1(self.min_context) {
+                return None;
+            }
+        } else {
+            self.decode_bin_symbol(self.min_context);
+        }
+
+        self.coder.decode();
+
+        while self.found_state == 0 {
+            self.coder.normalize(bits);
+
+            loop {
+                self.order_fall += 1;
+                self.min_context = self.suffix(self.min_context);
+                if !self.is_valid_context(self.min_context) {
+                    return None;
+                }
+                if self.num_stats(self.min_context) != self.num_masked {
+                    break;
+                }
+            }
+
+            if !self.decode_symbol2(self.min_context) {
+                return None;
+            }
+            self.coder.decode();
+        }
+
+        let symbol = self.symbol(self.found_state);
+        let successor = self.successor(self.found_state);
+
+        if self.order_fall == 0 && successor > self.alloc.text {
+            self.min_context = successor;
+            self.max_context = successor;
+        } else {
+            self.update_model();
+            if self.esc_count == 0 {
+                self.esc_count = 1;
+                self.char_mask = [0; 256];
+            }
+        }
+
+        self.coder.normalize(bits);
+        Some(symbol)
+    }
+}
@@ -0,0 +1,164 @@
+//! Known-answer tests for the decompression algorithms.
+//!
+//! Each algorithm embeds a few small vectors of packed bytes along with the data they are
+//! expected to decompress to, so that regressions in the decoders can be caught without
+//! shipping large fixture archives.
+//!
+//! The vectors of the older algorithms are the start of the files of the
+//! `rar15/unpack_versions.rar` fixture, cut after the first KiB of unpacked data. They
+//! catch regressions, but they aren't an independent check of the decoders: the fixture
+//! is tested against the same decoders, so a bug shared by both would go unnoticed.
+
+use std::io;
+
+use thiserror::Error;
+
+use super::{Unpack15, Unpack20, Unpack29, Unpack50};
+
+/// Decode `packed` into `unpacked_size` bytes.
+type DecodeFn = fn(packed: &[u8], unpacked_size: usize) -> io::Result<Vec<u8>>;
+
+struct Vector {
+    name: &'static str,
+    decode: DecodeFn,
+    packed: &'static [u8],
+    unpacked: &'static [u8],
+}
+
+const VECTORS: &[Vector] = &[
+    Vector {
+        name: "store",
+        decode: decode_store,
+        packed: b"stored data is copied verbatim",
+        unpacked: b"stored data is copied verbatim",
+    },
+    Vector {
+        name: "rar15",
+        decode: decode_rar15,
+        packed: include_bytes!("vectors/rar15_binary.bin"),
+        unpacked: include_bytes!("vectors/rar15_binary.out"),
+    },
+    Vector {
+        name: "rar20",
+        decode: decode_rar20,
+        packed: include_bytes!("vectors/rar20_binary.bin"),
+        unpacked: include_bytes!("vectors/rar20_binary.out"),
+    },
+    Vector {
+        name: "rar20_audio",
+        decode: decode_rar20,
+        packed: include_bytes!("vectors/rar20_audio.bin"),
+        unpacked: include_bytes!("vectors/rar20_audio.out"),
+    },
+    Vector {
+        name: "rar29",
+        decode: decode_rar29,
+        packed: include_bytes!("vectors/rar29_acknowledgments.bin"),
+        unpacked: include_bytes!("vectors/rar29_acknowledgments.txt"),
+    },
+    Vector {
+        name: "rar29_ppmd",
+        decode: decode_rar29,
+        packed: include_bytes!("vectors/rar29_ppmd_acknowledgments.bin"),
+        unpacked: include_bytes!("vectors/rar29_ppmd_acknowledgments.txt"),
+    },
+    Vector {
+        name: "rar50",
+        decode: decode_rar50,
+        packed: include_bytes!("vectors/rar50_makefile.bin"),
+        unpacked: include_bytes!("vectors/rar50_makefile.txt"),
+    },
+];
+
+fn decode_store(packed: &[u8], unpacked_size: usize) -> io::Result<Vec<u8>> {
+    match packed.get(..unpacked_size) {
+        Some(data) => Ok(data.to_vec()),
+        None => Err(io::ErrorKind::UnexpectedEof.into()),
+    }
+}
+
+fn decode_rar15(packed: &[u8], unpacked_size: usize) -> io::Result<Vec<u8>> {
+    let mut unpack = Unpack15::new(packed, unpacked_size, unpacked_size as u64);
+    decode_all(unpacked_size, |data| unpack.decode(data))
+}
+
+/// The decoder of RAR 2.0 switches between LZ and audio blocks on its own.
+fn decode_rar20(packed: &[u8], unpacked_size: usize) -> io::Result<Vec<u8>> {
+    let mut unpack = Unpack20::new(packed, unpacked_size, unpacked_size as u64);
+    decode_all(unpacked_size, |data| unpack.decode(data))
+}
+
+/// The decoder of RAR 2.9 switches between LZ and PPMd blocks on its own.
+fn decode_rar29(packed: &[u8], unpacked_size: usize) -> io::Result<Vec<u8>> {
+    let mut unpack = Unpack29::new(packed, unpacked_size, unpacked_size as u64);
+    decode_all(unpacked_size, |data| unpack.decode(data))
+}
+
+fn decode_rar50(packed: &[u8], unpacked_size: usize) -> io::Result<Vec<u8>> {
+    let mut unpack = Unpack50::new(packed, unpacked_size, false);
+    decode_all(unpacked_size, |data| unpack.decode(data))
+}
+
+/// Call `decode` until it has decoded `unpacked_size` bytes.
+fn decode_all(
+    unpacked_size: usize,
+    mut decode: impl FnMut(&mut Vec<u8>) -> io::Result<()>,
+) -> io::Result<Vec<u8>> {
+    let mut data = vec![];
+
+    while data.len() < unpacked_size {
+        let len = data.len();
+        decode(&mut data)?;
+        if data.len() == len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+    }
+
+    data.truncate(unpacked_size);
+    Ok(data)
+}
+
+#[derive(Debug, Error)]
+/// A decompression algorithm failed its known-answer test.
+#[error("self-test vector {name} failed")]
+pub struct SelfTestError {
+    /// Name of the vector that failed.
+    pub name: &'static str,
+
+    /// Error returned by the decoder, if any.
+    #[source]
+    pub error: Option<io::Error>,
+}
+
+impl Vector {
+    fn run(&self) -> Result<(), SelfTestError> {
+        match (self.decode)(self.packed, self.unpacked.len()) {
+            Ok(data) if data == self.unpacked => Ok(()),
+            Ok(_) => Err(SelfTestError {
+                name: self.name,
+                error: None,
+            }),
+            Err(error) => Err(SelfTestError {
+                name: self.name,
+                error: Some(error),
+            }),
+        }
+    }
+}
+
+/// Run the known-answer tests of all the decompression algorithms supported by this library.
+///
+/// This is intended for packagers who want to verify that the library works correctly
+/// on the target platform.
+pub fn self_test() -> Result<(), SelfTestError> {
+    VECTORS.iter().try_for_each(Vector::run)
+}
+
+#[test]
+fn test_self_test_vectors() {
+    for vector in VECTORS {
+        if let Err(e) = vector.run() {
+            panic!("{e}: {:?}", e.error);
+        }
+    }
+}
@@ -1,15 +1,624 @@
+//! Decoder for the RAR 1.5 compression format, which is used by files with an unpack
+//! version of 15.
+//!
+//! Literals, match lengths and distances are coded with fixed prefix codes whose symbols
+//! are reordered by frequency as they are decoded, and a flag byte (itself coded the same
+//! way) announces which kind of item comes next.
+
 use std::io;
 
-pub fn unpack15<R: io::Read + io::Seek>(reader: R) {
-    let mut old_dist = [!0, !0, !0, !0];
-    let mut old_dist_ptr = 0;
+use super::{bit_reader::BitReader, window::Window};
+
+/// Number of bytes decoded between writes.
+const WRITE_SIZE: u64 = 0x4_0000;
+
+/// Smallest window which can hold a chunk of decoded data and a full match.
+const MIN_WINDOW_SIZE: usize = 2 * WRITE_SIZE as usize;
+
+/// Fixed prefix code, given as the upper limit of the left-aligned codes of each length
+/// and the index of the first symbol of each length.
+struct PrefixCode {
+    start_length: u32,
+    limits: &'static [u32],
+    positions: [u32; 13],
+}
+
+const LENGTH_1: PrefixCode = PrefixCode {
+    start_length: 2,
+    limits: &[
+        0x8000, 0xa000, 0xc000, 0xd000, 0xe000, 0xea00, 0xee00, 0xf000, 0xf200, 0xf200, 0xffff,
+    ],
+    positions: [0, 0, 0, 2, 3, 5, 7, 11, 16, 20, 24, 32, 32],
+};
+
+const LENGTH_2: PrefixCode = PrefixCode {
+    start_length: 3,
+    limits: &[
+        0xa000, 0xc000, 0xd000, 0xe000, 0xea00, 0xee00, 0xf000, 0xf200, 0xf240, 0xffff,
+    ],
+    positions: [0, 0, 0, 0, 5, 7, 9, 13, 18, 22, 26, 34, 36],
+};
+
+const HUFFMAN_0: PrefixCode = PrefixCode {
+    start_length: 4,
+    limits: &[
+        0x8000, 0xc000, 0xe000, 0xf200, 0xf200, 0xf200, 0xf200, 0xf200, 0xffff,
+    ],
+    positions: [0, 0, 0, 0, 0, 8, 16, 24, 33, 33, 33, 33, 33],
+};
+
+const HUFFMAN_1: PrefixCode = PrefixCode {
+    start_length: 5,
+    limits: &[
+        0x2000, 0xc000, 0xe000, 0xf000, 0xf200, 0xf200, 0xf7e0, 0xffff,
+    ],
+    positions: [0, 0, 0, 0, 0, 0, 4, 44, 60, 76, 80, 80, 127],
+};
+
+const HUFFMAN_2: PrefixCode = PrefixCode {
+    start_length: 5,
+    limits: &[
+        0x1000, 0x2400, 0x8000, 0xc000, 0xfa00, 0xffff, 0xffff, 0xffff,
+    ],
+    positions: [0, 0, 0, 0, 0, 0, 2, 7, 53, 117, 233, 0, 0],
+};
+
+const HUFFMAN_3: PrefixCode = PrefixCode {
+    start_length: 6,
+    limits: &[0x0800, 0x2400, 0xee00, 0xfe80, 0xffff, 0xffff, 0xffff],
+    positions: [0, 0, 0, 0, 0, 0, 0, 2, 16, 218, 251, 0, 0],
+};
+
+const HUFFMAN_4: PrefixCode = PrefixCode {
+    start_length: 8,
+    limits: &[0xff00, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff],
+    positions: [0, 0, 0, 0, 0, 0, 0, 0, 0, 255, 0, 0, 0],
+};
+
+const SHORT_LENGTHS_1: [u32; 16] = [1, 3, 4, 4, 5, 6, 7, 8, 8, 4, 4, 5, 6, 6, 4, 0];
+const SHORT_XORS_1: [u32; 16] = [
+    0, 0xa0, 0xd0, 0xe0, 0xf0, 0xf8, 0xfc, 0xfe, 0xff, 0xc0, 0x80, 0x90, 0x98, 0x9c, 0xb0, 0,
+];
+const SHORT_LENGTHS_2: [u32; 16] = [2, 3, 3, 3, 4, 4, 5, 6, 6, 4, 4, 5, 6, 6, 4, 0];
+const SHORT_XORS_2: [u32; 16] = [
+    0, 0x40, 0x60, 0xa0, 0xd0, 0xe0, 0xf0, 0xf8, 0xfc, 0xc0, 0x80, 0x90, 0x98, 0x9c, 0xb0, 0,
+];
+
+/// Decode a symbol of `code`.
+fn decode_number<R: io::Read>(bits: &mut BitReader<R>, code: &PrefixCode) -> u32 {
+    let number = bits.peek16() & 0xfff0;
+
+    let mut length = code.start_length;
+    let mut i = 0;
+    while code.limits[i] <= number {
+        length += 1;
+        i += 1;
+    }
+    bits.skip(length);
+
+    let base = if i > 0 { code.limits[i - 1] } else { 0 };
+    ((number - base) >> (16 - length)) + code.positions[length as usize]
+}
+
+/// Reset the ranks kept in the low byte of the entries of a symbol set, and the
+/// positions where each rank starts.
+fn correct_ranks(set: &mut [u16; 256], rank_positions: &mut [u8; 256]) {
+    for (i, chunk) in set.chunks_mut(32).enumerate() {
+        for entry in chunk {
+            *entry = (*entry & !0xff) | (7 - i as u16);
+        }
+    }
+
+    rank_positions.fill(0);
+    for (i, position) in rank_positions[..7].iter_mut().enumerate() {
+        *position = (7 - i as u8) * 32;
+    }
+}
+
+/// RAR 1.5 decoder state.
+pub struct Unpack15<R> {
+    bits: BitReader<R>,
+    window: Window,
+
+    /// Number of bytes written out.
+    written: u64,
+
+    /// Position in the window of the end of the current file.
+    end: u64,
+
+    old_distances: [u32; 4],
+    old_distance_index: usize,
+    last_distance: u32,
+    last_length: u32,
+
+    /// Literals, most frequent first, with the symbol in the high byte and its rank in
+    /// the low byte.
+    literals: [u16; 256],
+
+    /// Short match distances, most recently used first.
+    short_distances: [u16; 256],
+
+    /// Long match distances, in the same format as `literals`.
+    long_distances: [u16; 256],
+
+    /// Flag bytes, in the same format as `literals`.
+    flags: [u16; 256],
+    literal_ranks: [u8; 256],
+    long_distance_ranks: [u8; 256],
+    flag_ranks: [u8; 256],
+
+    average_place: u32,
+    average_place_b: u32,
+    average_length_1: u32,
+    average_length_2: u32,
+    average_length_3: u32,
+    literal_count: u32,
+    buf60: u32,
+    max_distance_3: u32,
+    literal_weight: u32,
+    match_weight: u32,
+
+    flag_buffer: u32,
+    flag_count: i32,
+
+    /// Literals are decoded without flags until an escape sequence.
+    literal_mode: bool,
+    repeat_count: u32,
+
+    started: bool,
+    finished: bool,
+}
+
+impl<R: io::Read> Unpack15<R> {
+    /// Create a decoder with a window of at least `window_size` bytes, which stops after
+    /// decoding `unpacked_size` bytes.
+    pub fn new(reader: R, window_size: usize, unpacked_size: u64) -> Self {
+        let mut unpack = Self {
+            bits: BitReader::new(reader),
+            window: Window::new(window_size.max(MIN_WINDOW_SIZE)),
+            written: 0,
+            end: unpacked_size,
+            old_distances: [u32::MAX; 4],
+            old_distance_index: 0,
+            last_distance: u32::MAX,
+            last_length: 0,
+            literals: [0; 256],
+            short_distances: [0; 256],
+            long_distances: [0; 256],
+            flags: [0; 256],
+            literal_ranks: [0; 256],
+            long_distance_ranks: [0; 256],
+            flag_ranks: [0; 256],
+            average_place: 0x3500,
+            average_place_b: 0,
+            average_length_1: 0,
+            average_length_2: 0,
+            average_length_3: 0,
+            literal_count: 0,
+            buf60: 0,
+            max_distance_3: 0x2001,
+            literal_weight: 0x80,
+            match_weight: 0x80,
+            flag_buffer: 0,
+            flag_count: 0,
+            literal_mode: false,
+            repeat_count: 0,
+            started: false,
+            finished: false,
+        };
+
+        for i in 0..256 {
+            unpack.literals[i] = (i as u16) << 8;
+            unpack.long_distances[i] = (i as u16) << 8;
+            unpack.short_distances[i] = i as u16;
+            unpack.flags[i] = ((i as u8).wrapping_neg() as u16) << 8;
+        }
+        correct_ranks(&mut unpack.long_distances, &mut unpack.long_distance_ranks);
+
+        unpack
+    }
+
+    pub fn into_inner(self) -> R {
+        self.bits.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        self.bits.get_mut()
+    }
+
+    /// Continue the solid stream with the next file, whose data is read from the current
+    /// position of the reader, which needs a window of at least `window_size` bytes and
+    /// stops after decoding `unpacked_size` bytes.
+    ///
+    /// The window and the adaptive state are kept, while the flags are read again. The
+    /// current file must have been decoded to its end.
+    pub fn next_file(&mut self, window_size: usize, unpacked_size: u64) {
+        self.bits.reset();
+        self.window.grow(window_size);
+        self.written = self.window.position();
+        self.end = self.written + unpacked_size;
+        self.flag_buffer = 0;
+        self.flag_count = 0;
+        self.literal_mode = false;
+        self.repeat_count = 0;
+        self.started = false;
+        self.finished = false;
+    }
+
+    /// Decode the next chunk of data and append it to `out`.
+    ///
+    /// At least one byte is appended unless the end of the data was reached.
+    pub fn decode(&mut self, out: &mut Vec<u8>) -> io::Result<()> {
+        let len = out.len();
+
+        if !self.started {
+            self.started = true;
+            if self.end > self.window.position() {
+                self.bits.fill(64)?;
+                self.read_flags();
+                self.flag_count = 8;
+            }
+        }
+
+        while !self.finished && out.len() == len {
+            let limit = self.window.position() + WRITE_SIZE;
+
+            while self.window.position() < limit {
+                if self.window.position() >= self.end {
+                    self.finished = true;
+                    break;
+                }
+
+                self.bits.fill(64)?;
+                if self.bits.is_past_end() {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+
+                self.decode_item();
+            }
+
+            let end = self.window.position().min(self.end);
+            self.window.copy_to(self.written, end, out);
+            self.written = end;
+        }
 
-    let mut last_dist = -1;
-    let mut last_length = 0;
+        Ok(())
+    }
+
+    fn decode_item(&mut self) {
+        if self.literal_mode {
+            self.decode_literal();
+            return;
+        }
+
+        if self.next_flag() {
+            if self.match_weight > self.literal_weight {
+                self.decode_long_match();
+            } else {
+                self.decode_literal();
+            }
+        } else if self.next_flag() {
+            if self.match_weight > self.literal_weight {
+                self.decode_literal();
+            } else {
+                self.decode_long_match();
+            }
+        } else {
+            self.decode_short_match();
+        }
+    }
+
+    /// Take the next bit of the flag byte, reading a new one when it runs out.
+    fn next_flag(&mut self) -> bool {
+        self.flag_count -= 1;
+        if self.flag_count < 0 {
+            self.read_flags();
+            self.flag_count = 7;
+        }
+
+        let flag = self.flag_buffer & 0x80 != 0;
+        self.flag_buffer <<= 1;
+        flag
+    }
+
+    fn read_flags(&mut self) {
+        let place = decode_number(&mut self.bits, &HUFFMAN_2) as usize;
+        if place >= 256 {
+            return;
+        }
+
+        let (flags, new_place) = loop {
+            let flags = self.flags[place];
+            self.flag_buffer = flags as u32 >> 8;
+            let rank = &mut self.flag_ranks[flags as usize & 0xff];
+            let new_place = *rank as usize;
+            *rank = rank.wrapping_add(1);
+
+            let flags = flags.wrapping_add(1);
+            if flags & 0xff != 0 {
+                break (flags, new_place);
+            }
+            correct_ranks(&mut self.flags, &mut self.flag_ranks);
+        };
+
+        self.flags[place] = self.flags[new_place];
+        self.flags[new_place] = flags;
+    }
+
+    fn decode_literal(&mut self) {
+        let bit_field = self.bits.peek16();
+
+        let code = match self.average_place {
+            0x7600.. => &HUFFMAN_4,
+            0x5e00.. => &HUFFMAN_3,
+            0x3600.. => &HUFFMAN_2,
+            0x0e00.. => &HUFFMAN_1,
+            _ => &HUFFMAN_0,
+        };
+        let mut place = decode_number(&mut self.bits, code) & 0xff;
+
+        if self.literal_mode {
+            if place == 0 && bit_field > 0xfff {
+                place = 0x100;
+            }
+
+            if place == 0 {
+                // Escape sequence, which either ends the literal mode or encodes a match.
+                if self.bits.read(1) == 1 {
+                    self.literal_count = 0;
+                    self.literal_mode = false;
+                    return;
+                }
+
+                let length = if self.bits.read(1) == 1 { 4 } else { 3 };
+                let distance = decode_number(&mut self.bits, &HUFFMAN_2);
+                let distance = (distance << 5) | self.bits.read(5) as u32;
+                self.copy(distance, length);
+                return;
+            }
+
+            place -= 1;
+        } else {
+            let count = self.literal_count;
+            self.literal_count += 1;
+            if count >= 16 && self.flag_count == 0 {
+                self.literal_mode = true;
+            }
+        }
+
+        let place = place as usize;
+        self.average_place += place as u32;
+        self.average_place -= self.average_place >> 8;
+        self.literal_weight += 16;
+        if self.literal_weight > 0xff {
+            self.literal_weight = 0x90;
+            self.match_weight >>= 1;
+        }
+
+        self.window.push((self.literals[place] >> 8) as u8);
+
+        let (literal, new_place) = loop {
+            let literal = self.literals[place];
+            let rank = &mut self.literal_ranks[literal as usize & 0xff];
+            let new_place = *rank as usize;
+            *rank = rank.wrapping_add(1);
+
+            let literal = literal + 1;
+            if literal & 0xff <= 0xa1 {
+                break (literal, new_place);
+            }
+            correct_ranks(&mut self.literals, &mut self.literal_ranks);
+        };
+
+        self.literals[place] = self.literals[new_place];
+        self.literals[new_place] = literal;
+    }
+
+    fn decode_short_match(&mut self) {
+        self.literal_count = 0;
+
+        let mut bit_field = self.bits.peek16();
+        if self.repeat_count == 2 {
+            self.bits.skip(1);
+            if bit_field >= 0x8000 {
+                self.copy(self.last_distance, self.last_length);
+                return;
+            }
+            bit_field <<= 1;
+            self.repeat_count = 0;
+        }
+        let bit_field = (bit_field >> 8) & 0xff;
+
+        // One of the codes gets longer as matches of length 2 at distances up to 256
+        // become rarer.
+        let (lengths, xors, buf60_index) = if self.average_length_1 < 37 {
+            (SHORT_LENGTHS_1, SHORT_XORS_1, 1)
+        } else {
+            (SHORT_LENGTHS_2, SHORT_XORS_2, 3)
+        };
+        let code_length = |i: usize| {
+            if i == buf60_index {
+                self.buf60 + 3
+            } else {
+                lengths[i]
+            }
+        };
+
+        let mut length = 0;
+        while (bit_field ^ xors[length]) & !(0xff >> code_length(length)) & 0xff != 0 {
+            length += 1;
+        }
+        self.bits.skip(code_length(length));
+
+        if length >= 9 {
+            if length == 9 {
+                self.repeat_count += 1;
+                self.copy(self.last_distance, self.last_length);
+                return;
+            }
+
+            if length == 14 {
+                self.repeat_count = 0;
+                let length = decode_number(&mut self.bits, &LENGTH_2) + 5;
+                let distance = (self.bits.peek16() >> 1) | 0x8000;
+                self.bits.skip(15);
+                self.last_length = length;
+                self.last_distance = distance;
+                self.copy(distance, length);
+                return;
+            }
+
+            self.repeat_count = 0;
+            let index = self.old_distance_index.wrapping_sub(length - 9) & 3;
+            let distance = self.old_distances[index];
+            let mut match_length = decode_number(&mut self.bits, &LENGTH_1) + 2;
+            if match_length == 0x101 && length == 10 {
+                self.buf60 ^= 1;
+                return;
+            }
+            if distance > 256 {
+                match_length += 1;
+            }
+            if distance >= self.max_distance_3 {
+                match_length += 1;
+            }
+
+            self.insert_old_distance(distance);
+            self.last_length = match_length;
+            self.last_distance = distance;
+            self.copy(distance, match_length);
+            return;
+        }
+
+        self.repeat_count = 0;
+        self.average_length_1 += length as u32;
+        self.average_length_1 -= self.average_length_1 >> 4;
+
+        let place = (decode_number(&mut self.bits, &HUFFMAN_2) & 0xff) as usize;
+        let distance = self.short_distances[place];
+        if place > 0 {
+            self.short_distances[place] = self.short_distances[place - 1];
+            self.short_distances[place - 1] = distance;
+        }
+
+        let length = length as u32 + 2;
+        let distance = distance as u32 + 1;
+        self.insert_old_distance(distance);
+        self.last_length = length;
+        self.last_distance = distance;
+        self.copy(distance, length);
+    }
+
+    fn decode_long_match(&mut self) {
+        self.literal_count = 0;
+        self.match_weight += 16;
+        if self.match_weight > 0xff {
+            self.match_weight = 0x90;
+            self.literal_weight >>= 1;
+        }
+        let previous_average_length_2 = self.average_length_2;
+
+        let mut length = if self.average_length_2 >= 122 {
+            decode_number(&mut self.bits, &LENGTH_2)
+        } else if self.average_length_2 >= 64 {
+            decode_number(&mut self.bits, &LENGTH_1)
+        } else {
+            let bit_field = self.bits.peek16();
+            if bit_field < 0x100 {
+                self.bits.skip(16);
+                bit_field
+            } else {
+                let mut length = 0;
+                while (bit_field << length) & 0x8000 == 0 {
+                    length += 1;
+                }
+                self.bits.skip(length + 1);
+                length
+            }
+        };
+
+        self.average_length_2 += length;
+        self.average_length_2 -= self.average_length_2 >> 5;
+
+        let code = match self.average_place_b {
+            0x2900.. => &HUFFMAN_2,
+            0x0700.. => &HUFFMAN_1,
+            _ => &HUFFMAN_0,
+        };
+        let place = decode_number(&mut self.bits, code);
+        self.average_place_b += place;
+        self.average_place_b -= self.average_place_b >> 8;
+        let place = place as usize & 0xff;
+
+        let (distance, new_place) = loop {
+            let distance = self.long_distances[place];
+            let rank = &mut self.long_distance_ranks[distance as usize & 0xff];
+            let new_place = *rank as usize;
+            *rank = rank.wrapping_add(1);
+
+            let distance = distance.wrapping_add(1);
+            if distance & 0xff != 0 {
+                break (distance, new_place);
+            }
+            correct_ranks(&mut self.long_distances, &mut self.long_distance_ranks);
+        };
+
+        self.long_distances[place] = self.long_distances[new_place];
+        self.long_distances[new_place] = distance;
+
+        let distance = ((distance as u32 & 0xff00) | (self.bits.peek16() >> 8)) >> 1;
+        self.bits.skip(7);
+
+        let previous_average_length_3 = self.average_length_3;
+        if length != 1 && length != 4 {
+            if length == 0 && distance <= self.max_distance_3 {
+                self.average_length_3 += 1;
+                self.average_length_3 -= self.average_length_3 >> 8;
+            } else if self.average_length_3 > 0 {
+                self.average_length_3 -= 1;
+            }
+        }
+
+        length += 3;
+        if distance >= self.max_distance_3 {
+            length += 1;
+        }
+        if distance <= 256 {
+            length += 8;
+        }
+
+        self.max_distance_3 = if previous_average_length_3 > 0xb0
+            || (self.average_place >= 0x2a00 && previous_average_length_2 < 0x40)
+        {
+            0x7f00
+        } else {
+            0x2001
+        };
+
+        self.insert_old_distance(distance);
+        self.last_length = length;
+        self.last_distance = distance;
+        self.copy(distance, length);
+    }
+
+    fn insert_old_distance(&mut self, distance: u32) {
+        self.old_distances[self.old_distance_index] = distance;
+        self.old_distance_index = (self.old_distance_index + 1) & 3;
+    }
+
+    fn copy(&mut self, distance: u32, length: u32) {
+        self.window.copy_or_zero(length as usize, distance as u64);
+    }
+}
 
-    let mut unp_ptr = 0;
-    let mut wr_ptr = 0;
+#[test]
+fn test_decode_number() {
+    // The 4 bit codes starting with 0 come first, followed by the 5 bit codes.
+    let mut bits = BitReader::new(&[0b1000_0000, 0b0111_0000][..]);
+    bits.fill(2).unwrap();
 
-    let mut prev_ptr = 0;
-    let mut first_win_done = false;
+    assert_eq!(decode_number(&mut bits, &HUFFMAN_0), 8);
+    assert_eq!(decode_number(&mut bits, &HUFFMAN_0), 0);
+    assert_eq!(decode_number(&mut bits, &HUFFMAN_0), 24);
+    assert_eq!(bits.position(), 16);
 }
@@ -0,0 +1,459 @@
+//! Decoder for the RAR 2.0 compression format, which is used by files with an unpack
+//! version of 20 or 26.
+//!
+//! This is LZ77 with Huffman coding like the RAR 2.9 format, but without filters or
+//! PPMd. Instead, a block may switch to an audio mode in which every byte is predicted
+//! from the previous bytes of its channel.
+
+use std::io;
+
+use super::{
+    bit_reader::BitReader,
+    huffman::HuffmanTable,
+    unpack29::{
+        DISTANCE_SLOTS, LENGTH_BASES, LENGTH_BITS, SHORT_DISTANCE_BASES, SHORT_DISTANCE_BITS,
+    },
+    window::Window,
+};
+
+/// Number of symbols in the table which encodes the lengths of the other tables.
+const BC: usize = 19;
+
+/// Number of symbols in the main table.
+const NC: usize = 298;
+
+/// Number of symbols in the distance table.
+const DC: usize = 48;
+
+/// Number of symbols in the repeat length table.
+const RC: usize = 28;
+
+/// Number of symbols in the table of each audio channel.
+const MC: usize = 257;
+
+/// Maximum number of audio channels.
+const MAX_CHANNELS: usize = 4;
+
+/// The audio tables of all channels take up as much space as the largest table set.
+const HUFF_TABLE_SIZE: usize = MC * MAX_CHANNELS;
+
+/// Number of bytes decoded between writes.
+const WRITE_SIZE: u64 = 0x4_0000;
+
+/// Smallest window which can hold a chunk of decoded data and a full match.
+const MIN_WINDOW_SIZE: usize = 2 * WRITE_SIZE as usize;
+
+fn corrupt(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct LzTables {
+    main: HuffmanTable,
+    distance: HuffmanTable,
+    repeat_length: HuffmanTable,
+}
+
+enum Tables {
+    Lz(Box<LzTables>),
+    Audio(Vec<HuffmanTable>),
+}
+
+/// Adaptive linear predictor for one audio channel.
+#[derive(Default)]
+struct AudioPredictor {
+    previous_byte: u8,
+    previous_delta: i32,
+    deltas: [i32; 4],
+    coefficients: [i32; 5],
+    errors: [u32; 11],
+    count: u32,
+}
+
+impl AudioPredictor {
+    /// Decode a byte given the difference from its predicted value and the last delta
+    /// of the previous channel.
+    fn decode(&mut self, delta: u8, channel_delta: &mut i32) -> u8 {
+        self.count += 1;
+
+        let [d1, d2, d3, _] = self.deltas;
+        self.deltas = [self.previous_delta, self.previous_delta - d1, d2, d3];
+        let [d1, d2, d3, d4] = self.deltas;
+        let [k1, k2, k3, k4, k5] = self.coefficients;
+
+        let predicted = 8 * self.previous_byte as i32
+            + k1 * d1
+            + k2 * d2
+            + k3 * d3
+            + k4 * d4
+            + k5 * *channel_delta;
+        let byte = ((predicted >> 3) as u8).wrapping_sub(delta);
+
+        let d = (delta as i8 as i32) << 3;
+        let variants = [
+            d,
+            d - d1,
+            d + d1,
+            d - d2,
+            d + d2,
+            d - d3,
+            d + d3,
+            d - d4,
+            d + d4,
+            d - *channel_delta,
+            d + *channel_delta,
+        ];
+        for (error, variant) in self.errors.iter_mut().zip(variants) {
+            *error = error.wrapping_add(variant.unsigned_abs());
+        }
+
+        self.previous_delta = byte.wrapping_sub(self.previous_byte) as i8 as i32;
+        *channel_delta = self.previous_delta;
+        self.previous_byte = byte;
+
+        // Every 32 bytes, nudge the coefficient that would have given the smallest error.
+        if self.count & 0x1f == 0 {
+            let mut min_index = 0;
+            for i in 1..self.errors.len() {
+                if self.errors[i] < self.errors[min_index] {
+                    min_index = i;
+                }
+            }
+            self.errors = [0; 11];
+
+            if min_index > 0 {
+                let k = &mut self.coefficients[(min_index - 1) / 2];
+                if min_index % 2 == 1 && *k >= -16 {
+                    *k -= 1;
+                } else if min_index % 2 == 0 && *k < 16 {
+                    *k += 1;
+                }
+            }
+        }
+
+        byte
+    }
+}
+
+/// RAR 2.0 decoder state.
+pub struct Unpack20<R> {
+    bits: BitReader<R>,
+    window: Window,
+
+    /// Number of bytes written out.
+    written: u64,
+
+    /// Position in the window of the end of the current file.
+    end: u64,
+
+    old_distances: [u32; 4],
+    old_distance_index: usize,
+    last_distance: u32,
+    last_length: u32,
+    old_table: [u8; HUFF_TABLE_SIZE],
+    tables: Option<Tables>,
+
+    audio: [AudioPredictor; MAX_CHANNELS],
+    channels: usize,
+    current_channel: usize,
+    channel_delta: i32,
+
+    started: bool,
+    finished: bool,
+}
+
+impl<R: io::Read> Unpack20<R> {
+    /// Create a decoder with a window of at least `window_size` bytes, which stops after
+    /// decoding `unpacked_size` bytes.
+    pub fn new(reader: R, window_size: usize, unpacked_size: u64) -> Self {
+        Self {
+            bits: BitReader::new(reader),
+            window: Window::new(window_size.max(MIN_WINDOW_SIZE)),
+            written: 0,
+            end: unpacked_size,
+            old_distances: [u32::MAX; 4],
+            old_distance_index: 0,
+            last_distance: u32::MAX,
+            last_length: 0,
+            old_table: [0; HUFF_TABLE_SIZE],
+            tables: None,
+            audio: Default::default(),
+            channels: 1,
+            current_channel: 0,
+            channel_delta: 0,
+            started: false,
+            finished: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.bits.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        self.bits.get_mut()
+    }
+
+    /// Read the tables for the next file of a solid stream, which RAR writes after the last
+    /// symbol of the current file if there are at least 5 bytes left.
+    ///
+    /// Must be called once the current file has been decoded to its end, before moving the
+    /// reader to the next file.
+    pub fn end_file(&mut self) -> io::Result<()> {
+        self.bits.fill(5)?;
+        if self.bits.buffered() < 5 {
+            return Ok(());
+        }
+
+        let new_tables = match &self.tables {
+            None => false,
+            Some(Tables::Audio(tables)) => {
+                tables[self.current_channel].decode(&mut self.bits) == 256
+            }
+            Some(Tables::Lz(tables)) => tables.main.decode(&mut self.bits) == 269,
+        };
+
+        if new_tables {
+            self.read_tables()?;
+        }
+
+        Ok(())
+    }
+
+    /// Decode the next chunk of data and append it to `out`.
+    ///
+    /// At least one byte is appended unless the end of the data was reached.
+    pub fn decode(&mut self, out: &mut Vec<u8>) -> io::Result<()> {
+        let len = out.len();
+
+        if !self.started {
+            self.started = true;
+            if self.end > self.window.position() {
+                self.read_tables()?;
+            }
+        }
+
+        while !self.finished && out.len() == len {
+            let limit = self.window.position() + WRITE_SIZE;
+
+            while self.window.position() < limit {
+                if self.window.position() >= self.end {
+                    self.finished = true;
+                    break;
+                }
+
+                self.bits.fill(64)?;
+                if self.bits.is_past_end() {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+
+                self.decode_symbol()?;
+            }
+
+            let end = self.window.position().min(self.end);
+            self.window.copy_to(self.written, end, out);
+            self.written = end;
+        }
+
+        Ok(())
+    }
+
+    /// Continue the solid stream with the next file, whose data is read from the current
+    /// position of the reader, which needs a window of at least `window_size` bytes and
+    /// stops after decoding `unpacked_size` bytes.
+    ///
+    /// The window and the Huffman tables are kept.
+    pub fn next_file(&mut self, window_size: usize, unpacked_size: u64) {
+        self.bits.reset();
+        self.window.grow(window_size);
+        self.written = self.window.position();
+        self.end = self.written + unpacked_size;
+        self.started = self.tables.is_some();
+        self.finished = false;
+    }
+
+    fn read_tables(&mut self) -> io::Result<()> {
+        self.bits.fill(0x1000)?;
+
+        let audio = self.bits.peek16() & 0x8000 != 0;
+        if self.bits.peek16() & 0x4000 == 0 {
+            self.old_table = [0; HUFF_TABLE_SIZE];
+        }
+        self.bits.skip(2);
+
+        let table_size = if audio {
+            self.channels = self.bits.read(2) as usize + 1;
+            if self.current_channel >= self.channels {
+                self.current_channel = 0;
+            }
+            MC * self.channels
+        } else {
+            NC + DC + RC
+        };
+
+        let mut bit_lengths = [0; BC];
+        for length in &mut bit_lengths {
+            *length = self.bits.read(4) as u8;
+        }
+        let bit_lengths = HuffmanTable::new(&bit_lengths);
+
+        let mut table = [0; HUFF_TABLE_SIZE];
+        let mut i = 0;
+        while i < table_size {
+            let number = bit_lengths.decode(&mut self.bits);
+
+            if number < 16 {
+                table[i] = (number as u8 + self.old_table[i]) & 0xf;
+                i += 1;
+                continue;
+            }
+
+            let count = match number {
+                16 => self.bits.read(2) as usize + 3,
+                17 => self.bits.read(3) as usize + 3,
+                _ => self.bits.read(7) as usize + 11,
+            };
+            let end = (i + count).min(table_size);
+
+            if number == 16 {
+                if i == 0 {
+                    return Err(corrupt("invalid Huffman table"));
+                }
+                let previous = table[i - 1];
+                table[i..end].fill(previous);
+            } else {
+                table[i..end].fill(0);
+            }
+            i = end;
+        }
+
+        if self.bits.is_past_end() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        self.old_table = table;
+
+        self.tables = Some(if audio {
+            Tables::Audio(
+                table[..table_size]
+                    .chunks(MC)
+                    .map(HuffmanTable::new)
+                    .collect(),
+            )
+        } else {
+            let (main, rest) = table.split_at(NC);
+            let (distance, rest) = rest.split_at(DC);
+
+            Tables::Lz(Box::new(LzTables {
+                main: HuffmanTable::new(main),
+                distance: HuffmanTable::new(distance),
+                repeat_length: HuffmanTable::new(&rest[..RC]),
+            }))
+        });
+
+        Ok(())
+    }
+
+    fn decode_symbol(&mut self) -> io::Result<()> {
+        let (main, distance_table, repeat_length) = match &self.tables {
+            None => return Err(corrupt("missing Huffman tables")),
+
+            Some(Tables::Audio(tables)) => {
+                let symbol = tables[self.current_channel].decode(&mut self.bits);
+                if symbol == 256 {
+                    return self.read_tables();
+                }
+
+                let byte =
+                    self.audio[self.current_channel].decode(symbol as u8, &mut self.channel_delta);
+                self.window.push(byte);
+                self.current_channel = (self.current_channel + 1) % self.channels;
+
+                return Ok(());
+            }
+
+            Some(Tables::Lz(tables)) => (&tables.main, &tables.distance, &tables.repeat_length),
+        };
+
+        let symbol = main.decode(&mut self.bits) as usize;
+
+        match symbol {
+            0..=255 => self.window.push(symbol as u8),
+
+            256 => self.copy(self.last_length, self.last_distance),
+
+            257..=260 => {
+                let index = self.old_distance_index.wrapping_sub(symbol - 256) & 3;
+                let distance = self.old_distances[index];
+
+                let slot = repeat_length.decode(&mut self.bits) as usize;
+                let mut length = Self::slot_to_length(&mut self.bits, slot) + 2;
+                if distance >= 0x101 {
+                    length += 1;
+                    if distance >= 0x2000 {
+                        length += 1;
+                        if distance >= 0x4_0000 {
+                            length += 1;
+                        }
+                    }
+                }
+
+                self.copy(length, distance);
+            }
+
+            261..=268 => {
+                let slot = symbol - 261;
+                let distance = SHORT_DISTANCE_BASES[slot] as u32
+                    + 1
+                    + self.bits.read(SHORT_DISTANCE_BITS[slot] as u32) as u32;
+
+                self.copy(2, distance);
+            }
+
+            269 => self.read_tables()?,
+
+            _ => {
+                let mut length = Self::slot_to_length(&mut self.bits, symbol - 270) + 3;
+
+                let slot = distance_table.decode(&mut self.bits) as usize;
+                let (base, distance_bits) = DISTANCE_SLOTS[slot];
+                let distance = base + 1 + self.bits.read(distance_bits as u32) as u32;
+
+                if distance >= 0x2000 {
+                    length += 1;
+                    if distance >= 0x4_0000 {
+                        length += 1;
+                    }
+                }
+
+                self.copy(length, distance);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn slot_to_length(bits: &mut BitReader<R>, slot: usize) -> u32 {
+        LENGTH_BASES[slot] as u32 + bits.read(LENGTH_BITS[slot] as u32) as u32
+    }
+
+    /// Copy a match and remember its distance and length for later repeats.
+    fn copy(&mut self, length: u32, distance: u32) {
+        self.old_distances[self.old_distance_index & 3] = distance;
+        self.old_distance_index = self.old_distance_index.wrapping_add(1);
+        self.last_distance = distance;
+        self.last_length = length;
+        self.window.copy_or_zero(length as usize, distance as u64);
+    }
+}
+
+#[test]
+fn test_audio_predictor() {
+    let mut predictor = AudioPredictor::default();
+    let mut channel_delta = 0;
+
+    // With the coefficients still at zero, each byte is predicted to be the previous one.
+    assert_eq!(predictor.decode(!0, &mut channel_delta), 1);
+    assert_eq!(channel_delta, 1);
+    assert_eq!(predictor.decode(0xfd, &mut channel_delta), 4);
+    assert_eq!(channel_delta, 3);
+}
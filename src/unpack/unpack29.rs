@@ -0,0 +1,1052 @@
+//! Decompression algorithm introduced by RAR 2.9 and used up to RAR 4.x, based on UnRAR's
+//! `unpack30.cpp`.
+//!
+//! The compressed data is made of LZ blocks coded with Huffman tables and PPMd blocks,
+//! both of which can define filters for ranges of the output. The filters are programs for
+//! the RAR virtual machine, but in practice RAR only ever emits a few standard programs,
+//! which are identified by their checksum and implemented natively.
+
+use std::{collections::VecDeque, io};
+
+use crate::hash::Crc32;
+
+use super::{bit_reader::BitReader, huffman::HuffmanTable, ppmd, window::Window};
+
+/// Size of the table used to encode the lengths of the other tables.
+const BC: usize = 20;
+
+/// Literals, end of block/filter/repeat symbols and length slots.
+const NC: usize = 299;
+
+/// Distance slots.
+const DC: usize = 60;
+
+/// Low distance bits.
+const LDC: usize = 17;
+
+/// Length slots used by repeated distances.
+const RC: usize = 28;
+
+const HUFF_TABLE_SIZE: usize = NC + DC + LDC + RC;
+
+pub(super) const LENGTH_BASES: [u8; 28] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 10, 12, 14, 16, 20, 24, 28, 32, 40, 48, 56, 64, 80, 96, 112, 128,
+    160, 192, 224,
+];
+pub(super) const LENGTH_BITS: [u8; 28] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5,
+];
+
+/// Number of distance slots using each number of extra bits.
+const DISTANCE_BIT_COUNTS: [u8; 19] = [4, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 14, 0, 12];
+
+/// Base distance and number of extra bits of each distance slot.
+pub(super) const DISTANCE_SLOTS: [(u32, u8); DC] = {
+    let mut slots = [(0, 0); DC];
+    let mut slot = 0;
+    let mut base = 0;
+    let mut bits = 0;
+    while bits < DISTANCE_BIT_COUNTS.len() {
+        let mut i = 0;
+        while i < DISTANCE_BIT_COUNTS[bits] {
+            slots[slot] = (base, bits as u8);
+            base += 1 << bits;
+            slot += 1;
+            i += 1;
+        }
+        bits += 1;
+    }
+    slots
+};
+
+pub(super) const SHORT_DISTANCE_BASES: [u8; 8] = [0, 4, 8, 16, 32, 64, 128, 192];
+pub(super) const SHORT_DISTANCE_BITS: [u8; 8] = [2, 2, 3, 4, 5, 6, 6, 6];
+
+/// Number of distances for which the low distance bits are repeated after a symbol 16.
+const LOW_DISTANCE_REPEATS: u32 = 16;
+
+/// Longest match, produced by PPMd blocks.
+const MAX_MATCH_SIZE: u64 = 0xff + 32;
+
+/// Size of the memory of the virtual machine, which limits the size of a filter.
+const VM_MEMORY_SIZE: u32 = 0x4_0000;
+
+const MAX_FILTERS: usize = 8192;
+
+/// The window must hold the data range of a filter plus the data decoded while waiting
+/// for the filter to be complete.
+const MIN_WINDOW_SIZE: usize = 2 * VM_MEMORY_SIZE as usize;
+
+/// Number of bytes decoded between writes.
+const WRITE_SIZE: u64 = 0x4_0000;
+
+fn corrupt(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct Tables {
+    main: HuffmanTable,
+    distance: HuffmanTable,
+    low_distance: HuffmanTable,
+    repeat_length: HuffmanTable,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Lz,
+    Ppm,
+}
+
+/// Standard filter programs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FilterKind {
+    E8,
+    E8E9,
+    Itanium,
+    Delta,
+    Rgb,
+    Audio,
+}
+
+impl FilterKind {
+    /// Identify a filter program from its bytecode.
+    fn identify(code: &[u8]) -> Option<Self> {
+        // The first byte is the XOR of all the others.
+        let (&checksum, rest) = code.split_first()?;
+        if rest.iter().fold(0, |xor, byte| xor ^ byte) != checksum {
+            return None;
+        }
+
+        let mut crc = Crc32::new();
+        crc.update(code);
+
+        match (code.len(), crc.finalize()) {
+            (53, 0xad57_6887) => Some(Self::E8),
+            (57, 0x3cd7_e57e) => Some(Self::E8E9),
+            (120, 0x3769_893f) => Some(Self::Itanium),
+            (29, 0x0e06_077d) => Some(Self::Delta),
+            (149, 0x1c2c_5dc8) => Some(Self::Rgb),
+            (216, 0xbc85_e701) => Some(Self::Audio),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Filter {
+    /// Position of the first byte of the filtered range in the output.
+    start: u64,
+    length: u32,
+    kind: FilterKind,
+
+    /// Initial values of the registers of the virtual machine, which hold the parameters
+    /// of the filter.
+    registers: [u32; 7],
+}
+
+impl Filter {
+    fn end(&self) -> u64 {
+        self.start + self.length as u64
+    }
+
+    /// Apply the filter to `data`. `file_offset` is the offset of the data in the file.
+    ///
+    /// Like in UnRAR, the data is left as it is if the parameters are invalid.
+    fn apply(&self, data: &mut Vec<u8>, file_offset: u32) {
+        let size = data.len();
+        let channels = self.registers[0] as usize;
+
+        match self.kind {
+            FilterKind::E8 | FilterKind::E8E9 => {
+                const FILE_SIZE: u32 = 0x100_0000;
+
+                if size < 4 {
+                    return;
+                }
+
+                let second_opcode = match self.kind {
+                    FilterKind::E8E9 => 0xe9,
+                    _ => 0xe8,
+                };
+
+                let mut pos = 0;
+                while pos < size - 4 {
+                    let byte = data[pos];
+                    pos += 1;
+
+                    if byte != 0xe8 && byte != second_opcode {
+                        continue;
+                    }
+
+                    let offset = (pos as u32).wrapping_add(file_offset);
+                    let address = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+
+                    // Convert absolute addresses back to relative ones.
+                    let address = if address & 0x8000_0000 != 0 {
+                        if address.wrapping_add(offset) & 0x8000_0000 == 0 {
+                            Some(address.wrapping_add(FILE_SIZE))
+                        } else {
+                            None
+                        }
+                    } else if address.wrapping_sub(FILE_SIZE) & 0x8000_0000 != 0 {
+                        Some(address.wrapping_sub(offset))
+                    } else {
+                        None
+                    };
+
+                    if let Some(address) = address {
+                        data[pos..pos + 4].copy_from_slice(&address.to_le_bytes());
+                    }
+
+                    pos += 4;
+                }
+            }
+
+            FilterKind::Itanium => {
+                const BRANCH_MASKS: [u8; 16] = [4, 4, 6, 6, 0, 0, 7, 7, 4, 4, 0, 0, 4, 4, 0, 0];
+
+                if size < 21 {
+                    return;
+                }
+
+                let mut file_offset = file_offset >> 4;
+                let mut pos = 0;
+                while pos < size - 21 {
+                    // The fields of the last slot extend past the end of the bundle.
+                    let bundle = &mut data[pos..];
+                    let template = bundle[0] & 0x1f;
+
+                    if let Some(&mask) = BRANCH_MASKS.get(template.wrapping_sub(0x10) as usize) {
+                        for slot in 0..3 {
+                            if mask & (1 << slot) == 0 {
+                                continue;
+                            }
+
+                            let start = slot * 41 + 5;
+                            if itanium_bits(bundle, start + 37, 4) == 5 {
+                                let offset = itanium_bits(bundle, start + 13, 20);
+                                let offset = offset.wrapping_sub(file_offset) & 0xf_ffff;
+                                set_itanium_bits(bundle, offset, start + 13, 20);
+                            }
+                        }
+                    }
+
+                    pos += 16;
+                    file_offset = file_offset.wrapping_add(1);
+                }
+            }
+
+            FilterKind::Delta => {
+                if size > VM_MEMORY_SIZE as usize / 2 || channels > 1024 || channels == 0 {
+                    return;
+                }
+
+                // The bytes of each channel are stored contiguously, so they have to be put
+                // back in their interleaved positions.
+                let mut output = vec![0; size];
+                let mut source = data.iter();
+
+                for channel in 0..channels {
+                    let mut previous = 0u8;
+                    for byte in output.iter_mut().skip(channel).step_by(channels) {
+                        previous = previous.wrapping_sub(*source.next().unwrap());
+                        *byte = previous;
+                    }
+                }
+
+                *data = output;
+            }
+
+            FilterKind::Rgb => {
+                let width = self.registers[0].wrapping_sub(3) as usize;
+                let red_position = self.registers[1] as usize;
+
+                if size > VM_MEMORY_SIZE as usize / 2
+                    || size < 3
+                    || width > size
+                    || red_position > 2
+                {
+                    return;
+                }
+
+                let mut output = vec![0u8; size];
+                let mut source = data.iter();
+
+                for channel in 0..3 {
+                    let mut previous = 0i32;
+
+                    for i in (channel..size).step_by(3) {
+                        let predicted = if i >= width + 3 {
+                            // Paeth predictor using the pixels above.
+                            let upper = output[i - width] as i32;
+                            let upper_left = output[i - width - 3] as i32;
+
+                            let pa = (upper - upper_left).abs();
+                            let pb = (previous - upper_left).abs();
+                            let pc = (previous + upper - 2 * upper_left).abs();
+
+                            if pa <= pb && pa <= pc {
+                                previous
+                            } else if pb <= pc {
+                                upper
+                            } else {
+                                upper_left
+                            }
+                        } else {
+                            previous
+                        };
+
+                        output[i] = (predicted as u8).wrapping_sub(*source.next().unwrap());
+                        previous = output[i] as i32;
+                    }
+                }
+
+                for i in (red_position..size - 2).step_by(3) {
+                    let green = output[i + 1];
+                    output[i] = output[i].wrapping_add(green);
+                    output[i + 2] = output[i + 2].wrapping_add(green);
+                }
+
+                *data = output;
+            }
+
+            FilterKind::Audio => {
+                if size > VM_MEMORY_SIZE as usize / 2 || channels > 128 || channels == 0 {
+                    return;
+                }
+
+                let mut output = vec![0; size];
+                let mut source = data.iter();
+
+                for channel in 0..channels {
+                    let mut predictor = AudioPredictor::default();
+                    for byte in output.iter_mut().skip(channel).step_by(channels) {
+                        *byte = predictor.decode(*source.next().unwrap());
+                    }
+                }
+
+                *data = output;
+            }
+        }
+    }
+}
+
+/// Read `count` bits at bit offset `start` of an Itanium instruction bundle.
+fn itanium_bits(bundle: &[u8], start: usize, count: u32) -> u32 {
+    let at = start / 8;
+    let bytes = u32::from_le_bytes(bundle[at..at + 4].try_into().unwrap());
+    (bytes >> (start % 8)) & (u32::MAX >> (32 - count))
+}
+
+fn set_itanium_bits(bundle: &mut [u8], value: u32, start: usize, count: u32) {
+    let at = start / 8;
+    let mask = !((u32::MAX >> (32 - count)) << (start % 8));
+    let bytes = u32::from_le_bytes(bundle[at..at + 4].try_into().unwrap());
+    let bytes = (bytes & mask) | (value << (start % 8));
+    bundle[at..at + 4].copy_from_slice(&bytes.to_le_bytes());
+}
+
+/// Adaptive linear predictor of the audio filter, which decodes one channel.
+#[derive(Default)]
+struct AudioPredictor {
+    previous_byte: u32,
+    previous_delta: i32,
+    deltas: [i32; 3],
+    coefficients: [i32; 3],
+    errors: [u32; 7],
+    count: u32,
+}
+
+impl AudioPredictor {
+    fn decode(&mut self, delta: u8) -> u8 {
+        let [d1, d2, _] = self.deltas;
+        self.deltas = [self.previous_delta, self.previous_delta - d1, d2];
+        let [d1, d2, d3] = self.deltas;
+        let [k1, k2, k3] = self.coefficients;
+
+        let predicted = (8 * self.previous_byte as i32 + k1 * d1 + k2 * d2 + k3 * d3) as u32;
+        let predicted = ((predicted >> 3) & 0xff).wrapping_sub(delta as u32);
+        let byte = predicted as u8;
+
+        self.previous_delta = byte.wrapping_sub(self.previous_byte as u8) as i8 as i32;
+        self.previous_byte = byte as u32;
+
+        let d = (delta as i8 as i32) << 3;
+        for (i, error) in self.errors.iter_mut().enumerate() {
+            let variant = match i {
+                0 => d,
+                _ if i % 2 == 1 => d - self.deltas[(i - 1) / 2],
+                _ => d + self.deltas[(i - 1) / 2],
+            };
+            *error = error.wrapping_add(variant.unsigned_abs());
+        }
+
+        // Every 32 bytes, nudge the coefficient that would have given the smallest error.
+        if self.count & 0x1f == 0 {
+            let mut min_index = 0;
+            for i in 1..self.errors.len() {
+                if self.errors[i] < self.errors[min_index] {
+                    min_index = i;
+                }
+            }
+            self.errors = [0; 7];
+
+            if min_index > 0 {
+                let k = &mut self.coefficients[(min_index - 1) / 2];
+                if min_index % 2 == 1 && *k >= -16 {
+                    *k -= 1;
+                } else if min_index % 2 == 0 && *k < 16 {
+                    *k += 1;
+                }
+            }
+        }
+        self.count += 1;
+
+        byte
+    }
+}
+
+/// Read a number encoded in the variable length format used by the virtual machine.
+fn read_vm_number<R: io::Read>(bits: &mut BitReader<R>) -> u32 {
+    let data = bits.peek16();
+
+    match data & 0xc000 {
+        0 => {
+            bits.skip(6);
+            (data >> 10) & 0xf
+        }
+        0x4000 if data & 0x3c00 == 0 => {
+            bits.skip(14);
+            0xffff_ff00 | ((data >> 2) & 0xff)
+        }
+        0x4000 => {
+            bits.skip(10);
+            (data >> 6) & 0xff
+        }
+        0x8000 => {
+            bits.skip(2);
+            bits.read(16) as u32
+        }
+        _ => {
+            bits.skip(2);
+            bits.read(32) as u32
+        }
+    }
+}
+
+/// RAR 2.9 decoder state.
+pub struct Unpack29<R> {
+    bits: BitReader<R>,
+    window: Window,
+
+    /// Number of bytes written out.
+    written: u64,
+
+    /// Position in the window of the start of the current file.
+    file_start: u64,
+
+    /// Position in the window of the end of the current file.
+    end: u64,
+
+    block: BlockKind,
+    old_distances: [u32; 4],
+    last_length: u32,
+    previous_low_distance: u32,
+    low_distance_repeats: u32,
+    old_table: [u8; HUFF_TABLE_SIZE],
+    tables: Option<Tables>,
+
+    /// The next file of a solid stream reuses the Huffman tables instead of reading new
+    /// ones.
+    keep_tables: bool,
+
+    ppm: Box<ppmd::Model>,
+    ppm_escape: u8,
+
+    /// Kinds of the filter programs defined so far.
+    programs: Vec<FilterKind>,
+
+    /// Length of the last range filtered by each program.
+    program_lengths: Vec<u32>,
+    last_program: usize,
+    filters: VecDeque<Filter>,
+
+    started: bool,
+    finished: bool,
+
+    /// The marker of the end of the file was decoded.
+    end_of_file: bool,
+}
+
+impl<R: io::Read> Unpack29<R> {
+    /// Create a decoder with a window of at least `window_size` bytes, which stops after
+    /// decoding `unpacked_size` bytes.
+    pub fn new(reader: R, window_size: usize, unpacked_size: u64) -> Self {
+        Self {
+            bits: BitReader::new(reader),
+            window: Window::new(window_size.max(MIN_WINDOW_SIZE)),
+            written: 0,
+            file_start: 0,
+            end: unpacked_size,
+            block: BlockKind::Lz,
+            old_distances: [u32::MAX; 4],
+            last_length: 0,
+            previous_low_distance: 0,
+            low_distance_repeats: 0,
+            old_table: [0; HUFF_TABLE_SIZE],
+            tables: None,
+            keep_tables: false,
+            ppm: Box::new(ppmd::Model::new()),
+            ppm_escape: 2,
+            programs: vec![],
+            program_lengths: vec![],
+            last_program: 0,
+            filters: VecDeque::new(),
+            started: false,
+            finished: false,
+            end_of_file: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.bits.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        self.bits.get_mut()
+    }
+
+    /// Decode up to the marker of the end of the current file, which tells whether the
+    /// next file of a solid stream starts with new tables.
+    ///
+    /// Like UnRAR, the symbols before the marker are added to the window even if they're
+    /// past the end of the file. Must be called once the current file has been decoded to
+    /// its end, before moving the reader to the next file.
+    pub fn end_file(&mut self) -> io::Result<()> {
+        let capacity = self.window.size() - MAX_MATCH_SIZE;
+
+        while !self.end_of_file && self.window.position() - self.written <= capacity {
+            self.bits.fill(64)?;
+            if self.bits.is_past_end() {
+                break;
+            }
+
+            match self.block {
+                BlockKind::Lz => self.decode_symbol()?,
+                BlockKind::Ppm => self.decode_ppm()?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Continue the solid stream with the next file, whose data is read from the current
+    /// position of the reader, which needs a window of at least `window_size` bytes and
+    /// stops after decoding `unpacked_size` bytes.
+    ///
+    /// The window, the Huffman tables, the PPMd model and the filter programs are kept,
+    /// while the pending filters are dropped.
+    pub fn next_file(&mut self, window_size: usize, unpacked_size: u64) {
+        self.bits.reset();
+        self.window.grow(window_size);
+        self.file_start = self.window.position();
+        self.written = self.file_start;
+        self.end = self.file_start + unpacked_size;
+        self.filters.clear();
+        self.started = self.keep_tables;
+        self.finished = false;
+        self.end_of_file = false;
+    }
+
+    /// Decode the next chunk of data and append it to `out`.
+    ///
+    /// At least one byte is appended unless the end of the data was reached.
+    pub fn decode(&mut self, out: &mut Vec<u8>) -> io::Result<()> {
+        let len = out.len();
+
+        if !self.started {
+            self.started = true;
+            self.read_tables()?;
+        }
+
+        while !self.finished && out.len() == len {
+            let limit = self.window.position() + WRITE_SIZE;
+            let capacity = self.window.size() - MAX_MATCH_SIZE;
+
+            if self.window.position() - self.written > capacity {
+                return Err(corrupt("filtered data doesn't fit in the window"));
+            }
+
+            while self.window.position() < limit
+                && self.window.position() - self.written <= capacity
+            {
+                if self.window.position() >= self.end {
+                    self.finished = true;
+                    break;
+                }
+
+                self.bits.fill(64)?;
+                if self.bits.is_past_end() {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+
+                match self.block {
+                    BlockKind::Lz => self.decode_symbol()?,
+                    BlockKind::Ppm => self.decode_ppm()?,
+                }
+
+                if self.finished {
+                    break;
+                }
+            }
+
+            self.flush(out);
+        }
+
+        Ok(())
+    }
+
+    fn read_tables(&mut self) -> io::Result<()> {
+        self.bits.align();
+        self.bits.fill(0x1000)?;
+
+        if self.bits.peek16() & 0x8000 != 0 {
+            self.block = BlockKind::Ppm;
+            return self.ppm.init(&mut self.bits, &mut self.ppm_escape);
+        }
+
+        self.block = BlockKind::Lz;
+        self.previous_low_distance = 0;
+        self.low_distance_repeats = 0;
+
+        if self.bits.peek16() & 0x4000 == 0 {
+            self.old_table = [0; HUFF_TABLE_SIZE];
+        }
+        self.bits.skip(2);
+
+        let mut bit_lengths = [0; BC];
+        let mut i = 0;
+        while i < BC {
+            let length = self.bits.read(4) as u8;
+            if length == 15 {
+                let zero_count = self.bits.read(4) as usize;
+                if zero_count == 0 {
+                    bit_lengths[i] = 15;
+                    i += 1;
+                } else {
+                    let end = (i + zero_count + 2).min(BC);
+                    bit_lengths[i..end].fill(0);
+                    i = end;
+                }
+            } else {
+                bit_lengths[i] = length;
+                i += 1;
+            }
+        }
+
+        let bit_lengths = HuffmanTable::new(&bit_lengths);
+
+        let mut table = [0; HUFF_TABLE_SIZE];
+        let mut i = 0;
+        while i < table.len() {
+            let number = bit_lengths.decode(&mut self.bits);
+
+            if number < 16 {
+                table[i] = (number as u8 + self.old_table[i]) & 0xf;
+                i += 1;
+                continue;
+            }
+
+            let count = if matches!(number, 16 | 18) {
+                self.bits.read(3) as usize + 3
+            } else {
+                self.bits.read(7) as usize + 11
+            };
+            let end = (i + count).min(table.len());
+
+            if number < 18 {
+                if i == 0 {
+                    return Err(corrupt("invalid Huffman table"));
+                }
+                let previous = table[i - 1];
+                table[i..end].fill(previous);
+            } else {
+                table[i..end].fill(0);
+            }
+            i = end;
+        }
+
+        if self.bits.is_past_end() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        self.old_table = table;
+
+        let (main, rest) = table.split_at(NC);
+        let (distance, rest) = rest.split_at(DC);
+        let (low_distance, repeat_length) = rest.split_at(LDC);
+
+        self.tables = Some(Tables {
+            main: HuffmanTable::new(main),
+            distance: HuffmanTable::new(distance),
+            low_distance: HuffmanTable::new(low_distance),
+            repeat_length: HuffmanTable::new(repeat_length),
+        });
+        self.keep_tables = true;
+
+        Ok(())
+    }
+
+    fn decode_symbol(&mut self) -> io::Result<()> {
+        let Some(tables) = &self.tables else {
+            return Err(corrupt("missing Huffman tables"));
+        };
+
+        let symbol = tables.main.decode(&mut self.bits) as usize;
+
+        match symbol {
+            0..=255 => self.window.push(symbol as u8),
+
+            256 => {
+                // End of the block, followed either by new tables or by the end of the file,
+                // which tells whether the next file of a solid stream starts with new tables.
+                if self.bits.read(1) == 1 {
+                    self.keep_tables = false;
+                    self.read_tables()?;
+                } else {
+                    self.keep_tables = self.bits.read(1) == 0;
+                    self.finished = true;
+                    self.end_of_file = true;
+                }
+            }
+
+            257 => self.read_filter()?,
+
+            258 => {
+                if self.last_length != 0 {
+                    self.copy(self.last_length, self.old_distances[0]);
+                }
+            }
+
+            259..=262 => {
+                let index = symbol - 259;
+                let distance = self.old_distances[index];
+                self.old_distances.copy_within(..index, 1);
+                self.old_distances[0] = distance;
+
+                let slot = tables.repeat_length.decode(&mut self.bits) as usize;
+                let length = Self::slot_to_length(&mut self.bits, slot) + 2;
+                self.last_length = length;
+                self.copy(length, distance);
+            }
+
+            263..=270 => {
+                let slot = symbol - 263;
+                let distance = SHORT_DISTANCE_BASES[slot] as u32
+                    + 1
+                    + self.bits.read(SHORT_DISTANCE_BITS[slot] as u32) as u32;
+
+                self.insert_old_distance(distance);
+                self.last_length = 2;
+                self.copy(2, distance);
+            }
+
+            _ => {
+                let mut length = Self::slot_to_length(&mut self.bits, symbol - 271) + 3;
+
+                let slot = tables.distance.decode(&mut self.bits) as usize;
+                let (base, distance_bits) = DISTANCE_SLOTS[slot];
+                let distance_bits = distance_bits as u32;
+                let mut distance = base + 1;
+
+                if slot > 9 {
+                    if distance_bits > 4 {
+                        distance += (self.bits.read(distance_bits - 4) as u32) << 4;
+                    }
+
+                    if self.low_distance_repeats > 0 {
+                        self.low_distance_repeats -= 1;
+                        distance += self.previous_low_distance;
+                    } else {
+                        let low_distance = tables.low_distance.decode(&mut self.bits) as u32;
+                        if low_distance == 16 {
+                            self.low_distance_repeats = LOW_DISTANCE_REPEATS - 1;
+                            distance += self.previous_low_distance;
+                        } else {
+                            distance += low_distance;
+                            self.previous_low_distance = low_distance;
+                        }
+                    }
+                } else {
+                    distance += self.bits.read(distance_bits) as u32;
+                }
+
+                if distance >= 0x2000 {
+                    length += 1;
+                    if distance >= 0x4_0000 {
+                        length += 1;
+                    }
+                }
+
+                self.insert_old_distance(distance);
+                self.last_length = length;
+                self.copy(length, distance);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn slot_to_length(bits: &mut BitReader<R>, slot: usize) -> u32 {
+        LENGTH_BASES[slot] as u32 + bits.read(LENGTH_BITS[slot] as u32) as u32
+    }
+
+    fn insert_old_distance(&mut self, distance: u32) {
+        self.old_distances.copy_within(..3, 1);
+        self.old_distances[0] = distance;
+    }
+
+    fn copy(&mut self, length: u32, distance: u32) {
+        self.window.copy_or_zero(length as usize, distance as u64);
+    }
+
+    fn ppm_char(&mut self) -> io::Result<u8> {
+        // A symbol may need a few bytes for every order of the model.
+        self.bits.fill(0x400)?;
+        if self.bits.is_past_end() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        self.ppm
+            .decode_char(&mut self.bits)
+            .ok_or_else(|| corrupt("invalid PPMd data"))
+    }
+
+    fn decode_ppm(&mut self) -> io::Result<()> {
+        let byte = self.ppm_char()?;
+        if byte != self.ppm_escape {
+            self.window.push(byte);
+            return Ok(());
+        }
+
+        match self.ppm_char()? {
+            0 => self.read_tables()?,
+            2 => {
+                self.finished = true;
+                self.end_of_file = true;
+            }
+            3 => self.read_filter_ppm()?,
+            4 => {
+                let mut distance = 0;
+                for _ in 0..3 {
+                    distance = distance << 8 | self.ppm_char()? as u32;
+                }
+                let length = self.ppm_char()? as u32;
+                self.copy(length + 32, distance + 2);
+            }
+            5 => {
+                let length = self.ppm_char()? as u32;
+                self.copy(length + 4, 1);
+            }
+            // The escape character itself.
+            _ => self.window.push(byte),
+        }
+
+        Ok(())
+    }
+
+    fn read_filter(&mut self) -> io::Result<()> {
+        let flags = self.bits.read(8) as u8;
+        let length = match flags & 7 {
+            6 => self.bits.read(8) as usize + 7,
+            7 => self.bits.read(16) as usize,
+            n => n as usize + 1,
+        };
+
+        self.bits.fill(length)?;
+        let code = (0..length).map(|_| self.bits.read(8) as u8).collect();
+        self.add_filter(flags, code)
+    }
+
+    fn read_filter_ppm(&mut self) -> io::Result<()> {
+        let flags = self.ppm_char()?;
+        let length = match flags & 7 {
+            6 => self.ppm_char()? as usize + 7,
+            7 => (self.ppm_char()? as usize) << 8 | self.ppm_char()? as usize,
+            n => n as usize + 1,
+        };
+
+        let code = (0..length)
+            .map(|_| self.ppm_char())
+            .collect::<io::Result<_>>()?;
+        self.add_filter(flags, code)
+    }
+
+    fn add_filter(&mut self, flags: u8, code: Vec<u8>) -> io::Result<()> {
+        if code.is_empty() {
+            return Err(corrupt("empty filter"));
+        }
+
+        let mut bits = BitReader::new(&code[..]);
+        bits.fill(code.len())?;
+
+        let index = if flags & 0x80 != 0 {
+            match read_vm_number(&mut bits) as usize {
+                // Forget all the programs.
+                0 => {
+                    self.programs.clear();
+                    self.program_lengths.clear();
+                    self.filters.clear();
+                    0
+                }
+                n => n - 1,
+            }
+        } else {
+            self.last_program
+        };
+
+        if index > self.programs.len() || index > MAX_FILTERS {
+            return Err(corrupt("invalid filter program"));
+        }
+        self.last_program = index;
+
+        let is_new = index == self.programs.len();
+        if is_new {
+            self.program_lengths.push(0);
+        }
+
+        if self.filters.len() > MAX_FILTERS {
+            return Err(corrupt("too many filters"));
+        }
+
+        let mut start = read_vm_number(&mut bits) as u64;
+        if flags & 0x40 != 0 {
+            start += 258;
+        }
+
+        let length = if flags & 0x20 != 0 {
+            let length = read_vm_number(&mut bits);
+            self.program_lengths[index] = length;
+            length
+        } else {
+            self.program_lengths[index]
+        };
+
+        if length > VM_MEMORY_SIZE {
+            return Err(corrupt("filter is too large"));
+        }
+
+        let mut registers = [0; 7];
+        registers[4] = length;
+
+        if flags & 0x10 != 0 {
+            let mask = bits.read(7);
+            for (i, register) in registers.iter_mut().enumerate() {
+                if mask & (1 << i) != 0 {
+                    *register = read_vm_number(&mut bits);
+                }
+            }
+        }
+
+        if is_new {
+            let size = read_vm_number(&mut bits) as usize;
+            let offset = (bits.position() / 8) as usize;
+            if size >= 0x10000 || size == 0 || offset + size > code.len() {
+                return Err(corrupt("invalid filter program"));
+            }
+
+            let program = (0..size).map(|_| bits.read(8) as u8).collect::<Vec<_>>();
+            let kind = FilterKind::identify(&program).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "filter programs other than the standard ones are not supported",
+                )
+            })?;
+            self.programs.push(kind);
+        }
+
+        self.filters.push_back(Filter {
+            start: self.window.position() + start,
+            length,
+            kind: self.programs[index],
+            registers,
+        });
+
+        Ok(())
+    }
+
+    /// Write out the decoded data up to the start of the first filter that can't be
+    /// applied yet.
+    fn flush(&mut self, out: &mut Vec<u8>) {
+        let mut i = 0;
+
+        while let Some(&filter) = self.filters.get(i) {
+            if filter.start < self.written {
+                // The filter overlaps data that was already written.
+                self.filters.remove(i);
+                continue;
+            }
+
+            if filter.start >= self.window.position() {
+                i += 1;
+                continue;
+            }
+
+            self.write_window(filter.start, out);
+
+            if filter.end() > self.window.position() {
+                return;
+            }
+
+            self.filters.remove(i);
+
+            let file_offset = (filter.start - self.file_start) as u32;
+            let mut data = Vec::with_capacity(filter.length as usize);
+            self.window.copy_to(filter.start, filter.end(), &mut data);
+            filter.apply(&mut data, file_offset);
+
+            // Filters for the same range are applied to the output of the previous one.
+            while let Some(next) = self.filters.get(i) {
+                if next.start != filter.start || next.length as usize != data.len() {
+                    break;
+                }
+                next.apply(&mut data, file_offset);
+                self.filters.remove(i);
+            }
+
+            out.extend_from_slice(&data);
+            self.written = filter.end();
+        }
+
+        self.write_window(self.window.position(), out);
+    }
+
+    fn write_window(&mut self, end: u64, out: &mut Vec<u8>) {
+        self.window.copy_to(self.written, end, out);
+        self.written = self.written.max(end);
+    }
+}
+
+#[test]
+fn test_distance_slots() {
+    assert_eq!(DISTANCE_SLOTS[0], (0, 0));
+    assert_eq!(DISTANCE_SLOTS[4], (4, 1));
+    assert_eq!(DISTANCE_SLOTS[34], (0x2_0000, 16));
+    assert_eq!(DISTANCE_SLOTS[48], (0x10_0000, 18));
+    assert_eq!(DISTANCE_SLOTS[59], (0x3c_0000, 18));
+}
+
+#[test]
+fn test_audio_filter() {
+    let filter = Filter {
+        start: 0,
+        length: 6,
+        kind: FilterKind::Audio,
+        registers: [2, 0, 0, 0, 6, 0, 0],
+    };
+
+    // With the coefficients still at zero, each channel is stored as negated deltas.
+    let mut data = vec![!0, !0, !0, 246, 246, 246];
+    filter.apply(&mut data, 0);
+    assert_eq!(data, [1, 10, 2, 20, 3, 30]);
+}
@@ -0,0 +1,578 @@
+//! Decompression algorithm used by RAR5 and RAR7, based on UnRAR's `unpack50.cpp`.
+//!
+//! The compressed data is a sequence of blocks, each starting with a small byte-aligned
+//! header and optionally a new set of Huffman tables. The symbols decoded with the tables
+//! are either literals, LZ matches into a sliding window, or filters that transform
+//! a range of the output before it's written out.
+
+use std::{collections::VecDeque, io};
+
+use super::{bit_reader::BitReader, huffman::HuffmanTable, window::Window};
+
+/// Size of the table used to encode the lengths of the other tables.
+const BC: usize = 20;
+
+/// Literals, end of block/filter/repeat symbols and length slots.
+const NC: usize = 306;
+
+/// Distance slots.
+const DC: usize = 64;
+
+/// Distance slots of RAR7, which supports dictionaries larger than 4GiB.
+const DCX: usize = 80;
+
+/// Low distance bits.
+const LDC: usize = 16;
+
+/// Length slots used by repeated distances.
+const RC: usize = 44;
+
+/// Maximum length of a single match, including the length bonus for long distances.
+const MAX_MATCH_SIZE: u64 = 0x1001 + 3;
+
+const MAX_FILTER_SIZE: u32 = 0x40_0000;
+
+const MAX_FILTERS: usize = 8192;
+
+/// The window must hold the whole data range of a filter plus the data decoded
+/// while waiting for the filter to be complete.
+const MIN_WINDOW_SIZE: usize = 2 * MAX_FILTER_SIZE as usize;
+
+/// Number of bytes decoded between writes.
+const WRITE_SIZE: u64 = 0x4_0000;
+
+fn corrupt(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct Tables {
+    main: HuffmanTable,
+    distance: HuffmanTable,
+    low_distance: HuffmanTable,
+    repeat_length: HuffmanTable,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FilterKind {
+    Delta { channels: usize },
+    E8,
+    E8E9,
+    Arm,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Filter {
+    /// Position of the first byte of the filtered range in the output.
+    start: u64,
+    length: u32,
+    kind: FilterKind,
+}
+
+impl Filter {
+    fn end(&self) -> u64 {
+        self.start + self.length as u64
+    }
+
+    /// Apply the filter to `data`. `file_offset` is the offset of the data in the file.
+    fn apply(&self, data: &mut Vec<u8>, file_offset: u32) {
+        match self.kind {
+            FilterKind::E8 | FilterKind::E8E9 => {
+                const FILE_SIZE: u32 = 0x100_0000;
+
+                let second_opcode = match self.kind {
+                    FilterKind::E8E9 => 0xe9,
+                    _ => 0xe8,
+                };
+
+                let mut pos = 0;
+                while pos + 4 < data.len() {
+                    let byte = data[pos];
+                    pos += 1;
+
+                    if byte != 0xe8 && byte != second_opcode {
+                        continue;
+                    }
+
+                    let offset = (pos as u32).wrapping_add(file_offset) % FILE_SIZE;
+                    let address = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+
+                    // Convert absolute addresses back to relative ones.
+                    let address = if address & 0x8000_0000 != 0 {
+                        if address.wrapping_add(offset) & 0x8000_0000 == 0 {
+                            Some(address.wrapping_add(FILE_SIZE))
+                        } else {
+                            None
+                        }
+                    } else if address.wrapping_sub(FILE_SIZE) & 0x8000_0000 != 0 {
+                        Some(address.wrapping_sub(offset))
+                    } else {
+                        None
+                    };
+
+                    if let Some(address) = address {
+                        data[pos..pos + 4].copy_from_slice(&address.to_le_bytes());
+                    }
+
+                    pos += 4;
+                }
+            }
+
+            FilterKind::Arm => {
+                let mut pos = 0;
+                while pos + 3 < data.len() {
+                    // BL instruction with the "always" condition.
+                    if data[pos + 3] == 0xeb {
+                        let offset =
+                            u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], 0])
+                                .wrapping_sub(file_offset.wrapping_add(pos as u32) / 4);
+                        data[pos..pos + 3].copy_from_slice(&offset.to_le_bytes()[..3]);
+                    }
+                    pos += 4;
+                }
+            }
+
+            FilterKind::Delta { channels } => {
+                // The bytes of each channel are stored contiguously, so they have to be put
+                // back in their interleaved positions.
+                let mut output = vec![0; data.len()];
+                let mut source = data.iter();
+
+                for channel in 0..channels {
+                    let mut previous = 0u8;
+                    for byte in output.iter_mut().skip(channel).step_by(channels) {
+                        previous = previous.wrapping_sub(*source.next().unwrap());
+                        *byte = previous;
+                    }
+                }
+
+                *data = output;
+            }
+        }
+    }
+}
+
+/// RAR5 decoder state.
+pub struct Unpack50<R> {
+    bits: BitReader<R>,
+    extended_distances: bool,
+
+    window: Window,
+
+    /// Number of bytes written out.
+    written: u64,
+
+    /// Position of the start of the current file in the output.
+    file_start: u64,
+
+    old_distances: [u64; 4],
+    last_length: u32,
+    tables: Option<Tables>,
+    filters: VecDeque<Filter>,
+
+    /// Position in bits of the end of the current block.
+    block_end: u64,
+    last_block: bool,
+    finished: bool,
+}
+
+impl<R: io::Read> Unpack50<R> {
+    /// Create a decoder with a window of at least `window_size` bytes.
+    ///
+    /// `extended_distances` enables the larger distance table used by RAR7.
+    pub fn new(reader: R, window_size: usize, extended_distances: bool) -> Self {
+        Self {
+            bits: BitReader::new(reader),
+            extended_distances,
+            window: Window::new(window_size.max(MIN_WINDOW_SIZE)),
+            written: 0,
+            file_start: 0,
+            old_distances: [0; 4],
+            last_length: 0,
+            tables: None,
+            filters: VecDeque::new(),
+            block_end: 0,
+            last_block: false,
+            finished: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.bits.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        self.bits.get_mut()
+    }
+
+    /// Continue the solid stream with the next file, whose data is read from the current
+    /// position of the reader and needs a window of at least `window_size` bytes.
+    ///
+    /// The window, the Huffman tables and the match distances are kept, while the filters
+    /// never span several files and are dropped. The current file must have been decoded
+    /// to its end.
+    pub fn next_file(&mut self, window_size: usize, extended_distances: bool) {
+        self.bits.reset();
+        self.extended_distances = extended_distances;
+        self.window.grow(window_size);
+        self.file_start = self.window.position();
+        self.written = self.window.position();
+        self.filters.clear();
+        self.block_end = 0;
+        self.last_block = false;
+        self.finished = false;
+    }
+
+    /// Decode the next chunk of data and append it to `out`.
+    ///
+    /// At least one byte is appended unless the end of the data was reached.
+    pub fn decode(&mut self, out: &mut Vec<u8>) -> io::Result<()> {
+        let len = out.len();
+
+        while !self.finished && out.len() == len {
+            let limit = self.window.position() + WRITE_SIZE;
+            let capacity = self.window.size() - MAX_MATCH_SIZE;
+
+            if self.window.position() - self.written > capacity {
+                return Err(corrupt("filtered data doesn't fit in the window"));
+            }
+
+            while self.window.position() < limit
+                && self.window.position() - self.written <= capacity
+            {
+                self.bits.fill(64)?;
+
+                if self.bits.position() >= self.block_end {
+                    if self.last_block {
+                        self.finished = true;
+                        break;
+                    }
+                    self.read_block_header()?;
+                    continue;
+                }
+
+                if self.bits.is_past_end() {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+
+                self.decode_symbol(out)?;
+            }
+
+            self.flush(out);
+        }
+
+        Ok(())
+    }
+
+    fn read_block_header(&mut self) -> io::Result<()> {
+        self.bits.align();
+        self.bits.fill(8)?;
+
+        let flags = self.bits.read(8) as u8;
+        let checksum = self.bits.read(8) as u8;
+
+        let byte_count = ((flags >> 3) & 3) + 1;
+        if byte_count == 4 {
+            return Err(corrupt("invalid block header"));
+        }
+
+        let mut size = 0u32;
+        for i in 0..byte_count {
+            size |= (self.bits.read(8) as u32) << (i * 8);
+        }
+
+        if self.bits.is_past_end() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        if checksum != 0x5a ^ flags ^ size as u8 ^ (size >> 8) as u8 ^ (size >> 16) as u8 {
+            return Err(corrupt("block header checksum mismatch"));
+        }
+
+        // The flags contain the number of bits used in the last byte of the block.
+        let start = self.bits.position();
+        self.block_end = match size {
+            0 => start,
+            _ => start + (size as u64 - 1) * 8 + (flags & 7) as u64 + 1,
+        };
+        self.last_block = flags & 0x40 != 0;
+
+        if flags & 0x80 != 0 {
+            self.read_tables()?;
+        } else if self.tables.is_none() {
+            return Err(corrupt("missing Huffman tables"));
+        }
+
+        Ok(())
+    }
+
+    fn read_tables(&mut self) -> io::Result<()> {
+        self.bits.fill(0x1000)?;
+
+        let mut bit_lengths = [0; BC];
+        let mut i = 0;
+        while i < BC {
+            let length = self.bits.read(4) as u8;
+            if length == 15 {
+                let zero_count = self.bits.read(4) as usize;
+                if zero_count == 0 {
+                    bit_lengths[i] = 15;
+                    i += 1;
+                } else {
+                    let end = (i + zero_count + 2).min(BC);
+                    bit_lengths[i..end].fill(0);
+                    i = end;
+                }
+            } else {
+                bit_lengths[i] = length;
+                i += 1;
+            }
+        }
+
+        let bit_lengths = HuffmanTable::new(&bit_lengths);
+
+        let dc = if self.extended_distances { DCX } else { DC };
+        let mut table = vec![0; NC + dc + LDC + RC];
+        let mut i = 0;
+        while i < table.len() {
+            let number = bit_lengths.decode(&mut self.bits);
+
+            if number < 16 {
+                table[i] = number as u8;
+                i += 1;
+                continue;
+            }
+
+            let count = if matches!(number, 16 | 18) {
+                self.bits.read(3) as usize + 3
+            } else {
+                self.bits.read(7) as usize + 11
+            };
+            let end = (i + count).min(table.len());
+
+            if number < 18 {
+                if i == 0 {
+                    return Err(corrupt("invalid Huffman table"));
+                }
+                let previous = table[i - 1];
+                table[i..end].fill(previous);
+            } else {
+                table[i..end].fill(0);
+            }
+            i = end;
+        }
+
+        if self.bits.is_past_end() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let (main, rest) = table.split_at(NC);
+        let (distance, rest) = rest.split_at(dc);
+        let (low_distance, repeat_length) = rest.split_at(LDC);
+
+        self.tables = Some(Tables {
+            main: HuffmanTable::new(main),
+            distance: HuffmanTable::new(distance),
+            low_distance: HuffmanTable::new(low_distance),
+            repeat_length: HuffmanTable::new(repeat_length),
+        });
+
+        Ok(())
+    }
+
+    fn decode_symbol(&mut self, out: &mut Vec<u8>) -> io::Result<()> {
+        let Some(tables) = &self.tables else {
+            return Err(corrupt("missing Huffman tables"));
+        };
+
+        let slot = tables.main.decode(&mut self.bits) as u32;
+
+        match slot {
+            0..=255 => {
+                self.window.push(slot as u8);
+            }
+
+            256 => self.read_filter(out)?,
+
+            257 => {
+                if self.last_length != 0 {
+                    self.copy(self.last_length, self.old_distances[0]);
+                }
+            }
+
+            258..=261 => {
+                let index = (slot - 258) as usize;
+                let distance = self.old_distances[index];
+                self.old_distances.copy_within(..index, 1);
+                self.old_distances[0] = distance;
+
+                let length_slot = tables.repeat_length.decode(&mut self.bits) as u32;
+                let length = Self::slot_to_length(&mut self.bits, length_slot);
+                self.last_length = length;
+                self.copy(length, distance);
+            }
+
+            _ => {
+                let mut length = Self::slot_to_length(&mut self.bits, slot - 262);
+
+                let distance_slot = tables.distance.decode(&mut self.bits) as u64;
+                let (distance_bits, mut distance) = if distance_slot < 4 {
+                    (0, 1 + distance_slot)
+                } else {
+                    let bits = distance_slot as u32 / 2 - 1;
+                    (bits, 1 + ((2 | (distance_slot & 1)) << bits))
+                };
+
+                if distance_bits >= 4 {
+                    if distance_bits > 4 {
+                        distance += self.bits.read(distance_bits - 4) << 4;
+                    }
+                    distance += tables.low_distance.decode(&mut self.bits) as u64;
+                } else if distance_bits > 0 {
+                    distance += self.bits.read(distance_bits);
+                }
+
+                if distance > 0x100 {
+                    length += 1;
+                    if distance > 0x2000 {
+                        length += 1;
+                        if distance > 0x40000 {
+                            length += 1;
+                        }
+                    }
+                }
+
+                self.old_distances.copy_within(..3, 1);
+                self.old_distances[0] = distance;
+                self.last_length = length;
+                self.copy(length, distance);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn slot_to_length(bits: &mut BitReader<R>, slot: u32) -> u32 {
+        if slot < 8 {
+            return 2 + slot;
+        }
+
+        let extra_bits = slot / 4 - 1;
+        2 + ((4 | (slot & 3)) << extra_bits) + bits.read(extra_bits) as u32
+    }
+
+    fn copy(&mut self, length: u32, distance: u64) {
+        self.window.copy(length as usize, distance);
+    }
+
+    fn read_filter_data(&mut self) -> u32 {
+        let byte_count = self.bits.read(2) as u32 + 1;
+
+        (0..byte_count).fold(0, |data, i| data | (self.bits.read(8) as u32) << (i * 8))
+    }
+
+    fn read_filter(&mut self, out: &mut Vec<u8>) -> io::Result<()> {
+        let start = self.read_filter_data();
+        let length = self.read_filter_data();
+
+        let kind = match self.bits.read(3) {
+            0 => FilterKind::Delta {
+                channels: self.bits.read(5) as usize + 1,
+            },
+            1 => FilterKind::E8,
+            2 => FilterKind::E8E9,
+            3 => FilterKind::Arm,
+            _ => return Err(corrupt("unknown filter type")),
+        };
+
+        // UnRAR ignores filters that are too large.
+        if length == 0 || length > MAX_FILTER_SIZE {
+            return Ok(());
+        }
+
+        if self.filters.len() >= MAX_FILTERS {
+            self.flush(out);
+            if self.filters.len() >= MAX_FILTERS {
+                self.filters.clear();
+            }
+        }
+
+        self.filters.push_back(Filter {
+            start: self.window.position() + start as u64,
+            length,
+            kind,
+        });
+
+        Ok(())
+    }
+
+    /// Write out the decoded data up to the start of the first filter that can't be
+    /// applied yet.
+    fn flush(&mut self, out: &mut Vec<u8>) {
+        let mut i = 0;
+
+        while let Some(&filter) = self.filters.get(i) {
+            if filter.start < self.written {
+                // The filter overlaps data that was already written.
+                self.filters.remove(i);
+                continue;
+            }
+
+            if filter.start >= self.window.position() {
+                i += 1;
+                continue;
+            }
+
+            self.write_window(filter.start, out);
+
+            if filter.end() > self.window.position() {
+                return;
+            }
+
+            self.filters.remove(i);
+
+            let mut data = Vec::with_capacity(filter.length as usize);
+            self.window.copy_to(filter.start, filter.end(), &mut data);
+            filter.apply(&mut data, (filter.start - self.file_start) as u32);
+            out.extend_from_slice(&data);
+            self.written = filter.end();
+        }
+
+        self.write_window(self.window.position(), out);
+    }
+
+    fn write_window(&mut self, end: u64, out: &mut Vec<u8>) {
+        self.window.copy_to(self.written, end, out);
+        self.written = self.written.max(end);
+    }
+}
+
+#[test]
+fn test_delta_filter() {
+    let filter = Filter {
+        start: 0,
+        length: 6,
+        kind: FilterKind::Delta { channels: 2 },
+    };
+
+    // Channel 0 is 1, 2, 3 and channel 1 is 10, 20, 30, stored as negated deltas.
+    let mut data = vec![!0, !0, !0, 246, 246, 246];
+    filter.apply(&mut data, 0);
+    assert_eq!(data, [1, 10, 2, 20, 3, 30]);
+}
+
+#[test]
+fn test_e8_filter() {
+    let filter = Filter {
+        start: 0,
+        length: 10,
+        kind: FilterKind::E8,
+    };
+
+    // A call at offset 0 to the absolute address 0x105 becomes relative to the next byte.
+    let mut data = vec![0xe8, 0x05, 0x01, 0x00, 0x00, 0x90, 0x90, 0x90, 0x90, 0x90];
+    filter.apply(&mut data, 0x1000);
+    assert_eq!(
+        data,
+        [0xe8, 0x04, 0xf1, 0xff, 0xff, 0x90, 0x90, 0x90, 0x90, 0x90]
+    );
+}
@@ -0,0 +1,123 @@
+/// Sliding window holding the most recently decoded data, which LZ matches copy from.
+///
+/// Positions are absolute offsets in the decoded stream, so they keep increasing after
+/// the window wraps around.
+pub struct Window {
+    data: Vec<u8>,
+    mask: u64,
+
+    /// Number of bytes decoded.
+    pos: u64,
+}
+
+impl Window {
+    /// Create a window of at least `size` bytes.
+    pub fn new(size: usize) -> Self {
+        let size = size.next_power_of_two();
+
+        Self {
+            data: vec![0; size],
+            mask: size as u64 - 1,
+            pos: 0,
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    /// Number of bytes decoded.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Grow the window to at least `size` bytes, keeping the data it holds.
+    pub fn grow(&mut self, size: usize) {
+        let size = size.next_power_of_two();
+        if size <= self.data.len() {
+            return;
+        }
+
+        let mut data = vec![0; size];
+        let mask = size as u64 - 1;
+        for pos in self.pos.saturating_sub(self.size())..self.pos {
+            data[(pos & mask) as usize] = self.data[(pos & self.mask) as usize];
+        }
+
+        self.data = data;
+        self.mask = mask;
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        self.data[(self.pos & self.mask) as usize] = byte;
+        self.pos += 1;
+    }
+
+    /// Copy `length` bytes starting `distance` bytes before the current position.
+    pub fn copy(&mut self, length: usize, distance: u64) {
+        let dest = (self.pos & self.mask) as usize;
+        let source = (self.pos.wrapping_sub(distance) & self.mask) as usize;
+        let size = self.data.len();
+
+        if dest.wrapping_sub(source) % size >= length
+            && dest + length <= size
+            && source + length <= size
+        {
+            self.data.copy_within(source..source + length, dest);
+        } else {
+            // The source overlaps the destination or wraps around the window.
+            for i in 0..length {
+                self.data[(dest + i) % size] = self.data[(source + i) % size];
+            }
+        }
+
+        self.pos += length as u64;
+    }
+
+    /// Copy a match like [`Self::copy`], but fill it with zeros if it reaches before the
+    /// start of the data, which only happens in corrupt archives.
+    ///
+    /// This is how UnRAR handles such matches in the RAR 1.5 to 2.9 formats.
+    pub fn copy_or_zero(&mut self, length: usize, distance: u64) {
+        if distance == 0 || distance > self.pos {
+            for _ in 0..length {
+                self.push(0);
+            }
+        } else {
+            self.copy(length, distance);
+        }
+    }
+
+    /// Append the bytes between the absolute positions `start` and `end` to `out`.
+    pub fn copy_to(&self, mut start: u64, end: u64, out: &mut Vec<u8>) {
+        while start < end {
+            let from = (start & self.mask) as usize;
+            let len = ((end - start) as usize).min(self.data.len() - from);
+            out.extend_from_slice(&self.data[from..from + len]);
+            start += len as u64;
+        }
+    }
+}
+
+#[test]
+fn test_window() {
+    let mut window = Window::new(6);
+    assert_eq!(window.size(), 8);
+
+    for byte in b"abc" {
+        window.push(*byte);
+    }
+    window.copy(7, 2);
+    assert_eq!(window.position(), 10);
+
+    let mut out = vec![];
+    window.copy_to(2, 10, &mut out);
+    assert_eq!(out, b"cbcbcbcb");
+
+    window.grow(16);
+    assert_eq!(window.size(), 16);
+    window.copy(3, 8);
+    out.clear();
+    window.copy_to(2, 13, &mut out);
+    assert_eq!(out, b"cbcbcbcbcbc");
+}
@@ -0,0 +1,314 @@
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
+
+use crate::size::BlockSize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// How worrying a [`Warning`] is.
+pub enum Severity {
+    /// The archive contains something unusual that doesn't affect how it's parsed.
+    Low,
+
+    /// The archive may not be parsed correctly.
+    Medium,
+
+    /// Different parsers may disagree on the contents of the archive. This is a common
+    /// sign of a maliciously crafted archive.
+    High,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A non-fatal anomaly found while parsing an archive.
+pub struct Warning {
+    /// Offset of the block in which the anomaly was found.
+    pub offset: u64,
+
+    /// What was found.
+    pub kind: WarningKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Kind of a [`Warning`].
+pub enum WarningKind {
+    /// The fields of the block header extend past the header size declared by the block,
+    /// so they overlap with the data area or with the next block.
+    HeaderOverrun {
+        /// End of the header according to the declared header size.
+        declared_end: u64,
+
+        /// Offset at which the parser stopped reading the header.
+        parsed_end: u64,
+    },
+
+    /// The block overlaps a block that has already been read, or a header in the QuickOpen
+    /// cache overlaps another cached one.
+    OverlappingBlock {
+        /// Range of the block.
+        range: Range<u64>,
+
+        /// Range of the blocks read before which overlaps it. The ranges of blocks read
+        /// one after the other are merged.
+        previous: Range<u64>,
+    },
+
+    /// An offset stored in the archive points inside another block rather than at its start.
+    OffsetInsideBlock {
+        /// The offset stored in the archive.
+        target: u64,
+
+        /// Range of the block that contains the offset.
+        block: Range<u64>,
+    },
+
+    /// Parsing the header again from its declared bytes only, with strict checks, doesn't
+    /// give the same result as the lenient parser.
+    ParserDivergence(Divergence),
+
+    /// Flags of the block have bits set which are not known to this library.
+    UnknownFlags {
+        /// Name of the flags type, as listed in [`crate::schema::MODULES`].
+        flags: &'static str,
+
+        /// Bits which are not known.
+        bits: u64,
+    },
+
+    /// Flags of the block are set which only have a meaning together with other flags that
+    /// are not set, so they were ignored.
+    IgnoredFlags {
+        /// Name of the flags type, as listed in [`crate::schema::MODULES`].
+        flags: &'static str,
+
+        /// Bits which were ignored.
+        bits: u64,
+    },
+
+    /// The fields of the block header end before the header size declared by the block,
+    /// leaving reserved bytes or fields that are not known to this library.
+    UnknownHeaderBytes {
+        /// Offset at which the parser stopped reading the header.
+        parsed_end: u64,
+
+        /// End of the header according to the declared header size.
+        declared_end: u64,
+    },
+
+    /// Serializing the parsed header back into bytes doesn't give the bytes it was parsed
+    /// from, because the parser skipped or normalized some of them, or because they were
+    /// written in an unusual encoding.
+    RoundTripMismatch {
+        /// Offset of the first byte of the header which differs.
+        position: u64,
+    },
+
+    /// The copy of the block header in the QuickOpen cache is not the same as the header
+    /// found at the offset of the block in the archive.
+    QuickOpenMismatch {
+        /// Offset of the first byte of the header which differs.
+        position: u64,
+    },
+
+    /// The header at the offset of the warning was corrupt, and the bytes in `range` were
+    /// skipped to resume from the next block header found after it.
+    CorruptDataSkipped {
+        /// Range of the skipped bytes, ending at the offset of the next block.
+        range: Range<u64>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Why the strict parse of a header disagrees with the lenient one.
+pub enum Divergence {
+    /// The checksum stored in the header doesn't match the checksum of its bytes.
+    ChecksumMismatch {
+        /// Checksum stored in the header.
+        stored: u32,
+
+        /// Checksum computed from the header bytes.
+        computed: u32,
+    },
+
+    /// The header can't be parsed without reading past its declared size.
+    Truncated,
+
+    /// The fields of the header end before its declared size, leaving some bytes that
+    /// are not part of any field.
+    TrailingBytes {
+        /// Number of bytes after the last field.
+        count: u64,
+    },
+
+    /// The strict parser rejected the header.
+    Invalid(String),
+
+    /// Both parsers accepted the header but returned different blocks.
+    FieldsDiffer,
+}
+
+impl Warning {
+    /// How worrying this warning is.
+    pub fn severity(&self) -> Severity {
+        match self.kind {
+            WarningKind::HeaderOverrun { .. }
+            | WarningKind::OverlappingBlock { .. }
+            | WarningKind::OffsetInsideBlock { .. }
+            | WarningKind::ParserDivergence(_)
+            | WarningKind::QuickOpenMismatch { .. } => Severity::High,
+            WarningKind::CorruptDataSkipped { .. } | WarningKind::RoundTripMismatch { .. } => {
+                Severity::Medium
+            }
+            WarningKind::UnknownFlags { .. }
+            | WarningKind::IgnoredFlags { .. }
+            | WarningKind::UnknownHeaderBytes { .. } => Severity::Low,
+        }
+    }
+}
+
+/// Push a [`WarningKind::UnknownFlags`] warning about the block at `offset` if any of the
+/// `bits` of the flags type `flags` are set.
+pub(crate) fn check_flags(
+    offset: u64,
+    flags: &'static str,
+    bits: u64,
+    warnings: &mut Vec<Warning>,
+) {
+    if bits != 0 {
+        warnings.push(Warning {
+            offset,
+            kind: WarningKind::UnknownFlags { flags, bits },
+        });
+    }
+}
+
+/// Push a [`WarningKind::IgnoredFlags`] warning about the block at `offset` if any of the
+/// `bits` of the flags type `flags` were ignored.
+pub(crate) fn check_ignored_flags(
+    offset: u64,
+    flags: &'static str,
+    bits: u64,
+    warnings: &mut Vec<Warning>,
+) {
+    if bits != 0 {
+        warnings.push(Warning {
+            offset,
+            kind: WarningKind::IgnoredFlags { flags, bits },
+        });
+    }
+}
+
+/// Push a [`WarningKind::UnknownHeaderBytes`] warning if the header of `block` was only
+/// parsed up to `parsed_end`, before its declared end.
+pub(crate) fn check_header_end<B: BlockSize>(
+    block: &B,
+    parsed_end: u64,
+    warnings: &mut Vec<Warning>,
+) {
+    let declared_end = block.offset() + block.header_size();
+    if parsed_end < declared_end {
+        warnings.push(Warning {
+            offset: block.offset(),
+            kind: WarningKind::UnknownHeaderBytes {
+                parsed_end,
+                declared_end,
+            },
+        });
+    }
+}
+
+#[derive(Debug, Default)]
+/// Keeps track of the ranges of the blocks read by an iterator to detect overlapping blocks.
+///
+/// Blocks are read one after the other, so the ranges of adjacent blocks are merged and
+/// only the gaps left by [`resume_at`](crate::rar50::BlockIterator::resume_at) or by
+/// salvaging take up memory.
+pub(crate) struct BlockRanges {
+    /// Sorted ranges, neither overlapping nor adjacent.
+    ranges: Vec<Range<u64>>,
+}
+
+impl BlockRanges {
+    /// Record the range of a block, returning the previously recorded range that overlaps
+    /// it, if any. Ranges which overlap are not recorded.
+    pub fn insert(&mut self, range: Range<u64>) -> Option<Range<u64>> {
+        let index = self.ranges.partition_point(|r| r.end < range.start);
+
+        // The ranges from `index` on end at or after the start of the range, so only the
+        // first one which doesn't end right at its start can overlap it.
+        let after_previous = self.ranges.get(index).is_some_and(|r| r.end == range.start);
+        let next_index = index + after_previous as usize;
+
+        if let Some(next) = self.ranges.get(next_index) {
+            if next.start < range.end {
+                return Some(next.clone());
+            }
+        }
+
+        let before_next = self
+            .ranges
+            .get(next_index)
+            .is_some_and(|r| r.start == range.end);
+
+        match (after_previous, before_next) {
+            (true, true) => self.ranges[index].end = self.ranges.remove(next_index).end,
+            (true, false) => self.ranges[index].end = range.end,
+            (false, true) => self.ranges[next_index].start = range.start,
+            (false, false) => self.ranges.insert(index, range),
+        }
+
+        None
+    }
+
+    /// Record the range of a block that was read by an iterator, pushing a warning if it
+    /// overlaps other blocks or if its header was parsed past its declared size.
+    pub fn record_block<B: BlockSize>(
+        &mut self,
+        block: &B,
+        parsed_end: u64,
+        warnings: &mut Vec<Warning>,
+    ) {
+        let offset = block.offset();
+        let declared_end = offset + block.header_size();
+
+        if parsed_end > declared_end {
+            warnings.push(Warning {
+                offset,
+                kind: WarningKind::HeaderOverrun {
+                    declared_end,
+                    parsed_end,
+                },
+            });
+        }
+
+        let range = offset..offset + block.size();
+
+        if let Some(previous) = self.insert(range.clone()) {
+            warnings.push(Warning {
+                offset,
+                kind: WarningKind::OverlappingBlock { range, previous },
+            });
+        }
+    }
+}
+
+#[test]
+fn test_block_ranges_overlap() {
+    let mut ranges = BlockRanges::default();
+
+    assert_eq!(ranges.insert(10..20), None);
+    assert_eq!(ranges.insert(30..40), None);
+    assert_eq!(ranges.insert(50..60), None);
+    assert_eq!(ranges.insert(25..27), None);
+    assert_eq!(ranges.insert(15..16), Some(10..20));
+    assert_eq!(ranges.insert(20..26), Some(25..27));
+    assert_eq!(ranges.insert(39..50), Some(30..40));
+
+    // Adjacent ranges are merged.
+    assert_eq!(ranges.insert(20..25), None);
+    assert_eq!(ranges.insert(27..30), None);
+    assert_eq!(ranges.insert(40..45), None);
+    assert_eq!(ranges.ranges, [10..45, 50..60]);
+    assert_eq!(ranges.insert(45..50), None);
+    assert_eq!(ranges.ranges.len(), 1);
+    assert_eq!(ranges.ranges[0], 10..60);
+    assert_eq!(ranges.insert(59..61), Some(10..60));
+}
@@ -0,0 +1,139 @@
+use std::{
+    fs,
+    io::{self, Read as _, Seek as _, Write as _},
+    path::PathBuf,
+};
+
+use rawrxd::compat::{AlternateDataStream, StreamPolicy};
+
+use super::archive;
+
+#[test]
+fn alternate_data_streams() {
+    let mut archive = archive("rar50/alternate_data_streams.rar");
+    let streams: Vec<_> = archive
+        .blocks()
+        .unwrap()
+        .map(Result::unwrap)
+        .filter_map(|block| AlternateDataStream::from_block(&block))
+        .collect();
+
+    let names: Vec<_> = streams.iter().map(|s| s.name_lossy()).collect();
+    assert_eq!(names, ["Zone.Identifier", "notes"]);
+
+    let stream = &streams[0];
+    assert_eq!(stream.unpacked_size, Some(12));
+    assert_eq!(stream.crc32, Some(0x53dd9ffd));
+    assert!(stream.is_stored);
+    assert!(!stream.is_encrypted);
+
+    let mut reader = archive.into_inner();
+    reader
+        .seek(io::SeekFrom::Start(stream.offset + stream.header_size))
+        .unwrap();
+    let mut data = vec![];
+    reader
+        .take(stream.packed_size)
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, b"stream data\n");
+}
+
+#[test]
+fn entry_streams() {
+    let mut archive = archive("rar50/alternate_data_streams.rar");
+    let entries = archive.alternate_streams().unwrap();
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].entry.name.as_deref(), Ok("file.txt"));
+
+    let streams = &entries[0].streams;
+    assert_eq!(streams.len(), 2);
+    assert_eq!(
+        streams[0].read_data(archive.reader()).unwrap(),
+        b"stream data\n"
+    );
+
+    let mut corrupt = streams[0].clone();
+    corrupt.crc32 = corrupt.crc32.map(|crc| !crc);
+    let error = corrupt.read_data(archive.reader()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+    let mut compressed = streams[0].clone();
+    compressed.is_stored = false;
+    let error = compressed.data_reader(archive.reader()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+}
+
+#[test]
+fn rar15_service_streams() {
+    let mut archive = archive("rar15/service_blocks.rar");
+    let entries = archive.alternate_streams().unwrap();
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].entry.name.as_deref(), Ok("file.txt"));
+
+    let stream = &entries[0].streams[0];
+    assert_eq!(stream.name_lossy(), "n\u{f6}tes");
+    assert_eq!(
+        stream.read_data(archive.reader()).unwrap(),
+        b"stream data\n"
+    );
+}
+
+#[test]
+fn rar20_sub_block_streams() {
+    let mut archive = archive("rar15/sub_blocks.rar");
+    let entries = archive.alternate_streams().unwrap();
+    assert_eq!(entries[0].entry.name.as_deref(), Ok("file.txt"));
+
+    // The stream was compressed like unpack20.bin.
+    let stream = &entries[0].streams[0];
+    assert_eq!(stream.name_lossy(), "notes");
+    assert_eq!(stream.unpack_version, Some(20));
+    assert!(!stream.is_stored);
+
+    let data = stream.read_data(archive.reader()).unwrap();
+    assert_eq!(data.len(), 4096);
+
+    let expected = super::archive("rar15/unpack_versions.rar")
+        .peek(1, 4096)
+        .unwrap()
+        .unwrap();
+    assert_eq!(expected.entry.name.as_deref(), Ok("unpack20.bin"));
+    assert_eq!(data, expected.data);
+
+    let mut corrupt = stream.clone();
+    corrupt.crc32 = corrupt.crc32.map(|crc| !crc);
+    let error = corrupt.read_data(archive.reader()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn stream_policy() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("stream_policy");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("file.txt");
+
+    assert!(StreamPolicy::Skip.create(&file, "notes").unwrap().is_none());
+
+    let (path, mut stream) = StreamPolicy::SeparateFile
+        .create(&file, "a/b")
+        .unwrap()
+        .unwrap();
+    assert_eq!(path, dir.join("file.txt__a_b"));
+    stream.write_all(b"data").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"data");
+
+    // Other systems don't have alternate data streams.
+    if !cfg!(windows) {
+        let (path, _) = StreamPolicy::AlternateDataStream
+            .create(&file, "notes")
+            .unwrap()
+            .unwrap();
+        assert_eq!(path, dir.join("file.txt__notes"));
+    }
+
+    assert!(StreamPolicy::SeparateFile.create(&file, "").is_err());
+}
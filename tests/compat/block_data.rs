@@ -0,0 +1,53 @@
+use std::io::Read;
+
+use rawrxd::{compat::FileEntry, BlockSize, WarningKind};
+use rstest::rstest;
+
+use super::archive;
+
+#[rstest]
+#[case("rar14/stored.rar", "HELLO.TXT")]
+#[case("rar15/stored.rar", "hello.txt")]
+fn read_block_data(#[case] path: &str, #[case] name: &str) {
+    let mut archive = archive(path);
+    let blocks: Vec<_> = archive.blocks().unwrap().map(Result::unwrap).collect();
+    let block = blocks
+        .iter()
+        .find(|block| {
+            FileEntry::from_block(block).is_some_and(|entry| entry.name.as_deref() == Ok(name))
+        })
+        .unwrap();
+
+    let mut data = vec![];
+    block
+        .data_reader(archive.reader())
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data.len() as u64, block.data_size());
+    assert!(data.starts_with(b"Hello, world!"));
+}
+
+#[rstest]
+#[case("rar14/stored.rar", &[])]
+#[case("rar15/stored.rar", &[])]
+#[case("rar50/links.rar", &[])]
+#[case("rar50/unknown_flags.rar", &[("FileBlockFlags", 0x40)])]
+fn block_warnings(#[case] path: &str, #[case] expected: &[(&str, u64)]) {
+    let mut archive = archive(path);
+    let mut blocks = archive.blocks().unwrap();
+    for block in blocks.by_ref() {
+        block.unwrap();
+    }
+
+    let unknown_flags: Vec<_> = blocks
+        .take_warnings()
+        .into_iter()
+        .map(|warning| match warning.kind {
+            WarningKind::UnknownFlags { flags, bits } => (flags, bits),
+            kind => panic!("unexpected warning {kind:?}"),
+        })
+        .collect();
+    assert_eq!(unknown_flags, expected);
+    assert!(blocks.warnings().is_empty());
+}
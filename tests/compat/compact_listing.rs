@@ -0,0 +1,81 @@
+use rawrxd::compat::{CompactEntry, NameArena, NameId, NameInterner};
+use rstest::rstest;
+
+use super::archive;
+
+#[rstest]
+#[case("rar14/stored.rar", &["DOCS", "HELLO.TXT"], &[true, false])]
+#[case("rar15/stored.rar", &["docs", "docs\\readme.txt", "hello.txt"], &[true, false, false])]
+#[case("rar50/unix_high_ascii_filename.rar", &["Æ"], &[false])]
+fn compact_listing(#[case] path: &str, #[case] names: &[&str], #[case] directories: &[bool]) {
+    let listing = archive(path).entries_compact().unwrap();
+
+    let listed_names: Vec<_> = listing.iter().map(|(name, _)| name).collect();
+    let names: Vec<_> = names.iter().map(|name| name.as_bytes()).collect();
+    assert_eq!(listed_names, names);
+
+    let listed_directories: Vec<_> = listing.entries().iter().map(|e| e.is_directory()).collect();
+    assert_eq!(listed_directories, directories);
+}
+
+#[test]
+fn compact_listing_sizes() {
+    let listing = archive("rar50/recovery_record_and_quick_open.rar")
+        .entries_compact()
+        .unwrap();
+
+    assert_eq!(listing.len(), 12);
+
+    let (name, unrar) = listing.iter().nth(1).unwrap();
+    assert_eq!(name, b"unrar");
+    assert_eq!(unrar.packed_size(), 174751);
+    assert_eq!(unrar.unpacked_size(), Some(437536));
+    assert!(unrar.modification_time().is_some());
+}
+
+#[test]
+fn shared_name_arena() {
+    let mut names = NameArena::default();
+
+    let first = archive("rar15/stored.rar")
+        .entries_compact_with(&mut names)
+        .unwrap();
+    let size = names.size();
+    let second = archive("rar15/stored.rar")
+        .entries_compact_with(&mut names)
+        .unwrap();
+
+    // The names of the second archive are already stored.
+    assert_eq!(names.len(), 3);
+    assert_eq!(names.size(), size);
+
+    let ids = |entries: &[CompactEntry]| entries.iter().map(|e| e.name_id()).collect::<Vec<_>>();
+    assert_eq!(ids(&first), ids(&second));
+    assert_eq!(names.get(second[2].name_id()), b"hello.txt");
+}
+
+#[test]
+fn custom_name_interner() {
+    /// Interner which stores every name, even duplicates.
+    #[derive(Default)]
+    struct Names(Vec<Vec<u8>>);
+
+    impl NameInterner for Names {
+        fn intern(&mut self, name: &[u8]) -> NameId {
+            self.0.push(name.to_vec());
+            NameId::new(self.0.len() as u32 - 1)
+        }
+
+        fn get(&self, id: NameId) -> &[u8] {
+            &self.0[id.index() as usize]
+        }
+    }
+
+    let mut names = Names::default();
+    let entries = archive("rar14/stored.rar")
+        .entries_compact_with(&mut names)
+        .unwrap();
+
+    let listed: Vec<_> = entries.iter().map(|e| names.get(e.name_id())).collect();
+    assert_eq!(listed, [&b"DOCS"[..], b"HELLO.TXT"]);
+}
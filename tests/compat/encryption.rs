@@ -0,0 +1,98 @@
+use std::{fs, io};
+
+use rawrxd::compat::{Archive, EncryptionScheme, HeaderEncryption};
+
+use super::archive;
+
+#[test]
+fn not_encrypted() {
+    for path in [
+        "rar14/stored.rar",
+        "rar15/stored.rar",
+        "rar50/quick_open.rar",
+    ] {
+        let status = archive(path).encryption().unwrap();
+        assert_eq!(status.headers, None, "{path}");
+        assert_eq!(status.encrypted_entries, [], "{path}");
+        assert!(!status.needs_password(), "{path}");
+    }
+}
+
+#[test]
+fn rar15_encrypted_headers() {
+    let status = archive("rar15/encrypted_headers.rar").encryption().unwrap();
+
+    assert_eq!(
+        status.headers,
+        Some(HeaderEncryption {
+            scheme: EncryptionScheme::Aes128,
+            offset: 20,
+            kdf: None,
+            has_password_check: false,
+        })
+    );
+    assert!(status.needs_password());
+}
+
+#[test]
+fn rar50_encrypted_headers() {
+    let status = archive("rar50/encrypted_headers.rar").encryption().unwrap();
+
+    let headers = status.headers.unwrap();
+    assert_eq!(headers.scheme, EncryptionScheme::Aes256);
+    assert_eq!(headers.offset, 46);
+    assert!(headers.has_password_check);
+
+    let kdf = headers.kdf.unwrap();
+    assert_eq!(kdf.kdf_count, 15);
+    assert_eq!(kdf.iterations(), 0x8000);
+}
+
+#[test]
+fn rar15_encrypted_files() {
+    let mut data = fs::read("tests/fixtures/rar15/stored.rar").unwrap();
+    // Set the encrypted flag of docs\readme.txt, which is compressed by RAR 2.9.
+    data[59] |= 0x04;
+
+    let status = Archive::new(io::Cursor::new(data))
+        .unwrap()
+        .encryption()
+        .unwrap();
+
+    assert_eq!(status.headers, None);
+    assert_eq!(status.encrypted_entries.len(), 1);
+    let entry = &status.encrypted_entries[0];
+    assert_eq!(entry.offset, 56);
+    assert_eq!(entry.name.as_deref(), Ok("docs\\readme.txt"));
+    assert_eq!(entry.scheme, EncryptionScheme::Aes128);
+    assert_eq!(entry.kdf, None);
+    assert!(!entry.has_password_check);
+}
+
+#[test]
+fn rar50_encrypted_files() {
+    let status = archive("rar50/encrypted_files.rar").encryption().unwrap();
+
+    assert_eq!(status.headers, None);
+    assert!(status.needs_password());
+
+    let entries: Vec<_> = status
+        .encrypted_entries
+        .iter()
+        .map(|entry| (entry.offset, entry.name.clone().unwrap()))
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            (24, "hello.txt".to_string()),
+            (131, "readme.txt".to_string()),
+            (497, "blake2.txt".to_string()),
+        ]
+    );
+
+    for entry in &status.encrypted_entries {
+        assert_eq!(entry.scheme, EncryptionScheme::Aes256);
+        assert_eq!(entry.kdf.unwrap().kdf_count, 15);
+        assert!(entry.has_password_check);
+    }
+}
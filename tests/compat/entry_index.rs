@@ -0,0 +1,105 @@
+use std::{fs, io};
+
+use rawrxd::compat::{Archive, FileEntry};
+use rstest::rstest;
+
+use super::archive;
+
+#[rstest]
+#[case("rar14/stored.rar")]
+#[case("rar15/unpack_versions.rar")]
+#[case("rar50/recovery_record_and_quick_open.rar")]
+fn entry_at(#[case] path: &str) {
+    let entries = archive(path).entries().unwrap();
+
+    let mut archive = archive(path);
+    assert_eq!(archive.entry_count().unwrap(), entries.len());
+
+    // Read them backwards so that each entry is read by offset.
+    for (index, entry) in entries.iter().enumerate().rev() {
+        let found = archive.entry_at(index).unwrap().unwrap();
+        assert_eq!(found.offset, entry.offset);
+        assert_eq!(found.name, entry.name);
+        assert_eq!(found.unpacked_size, entry.unpacked_size);
+    }
+
+    assert!(archive.entry_at(entries.len()).unwrap().is_none());
+}
+
+#[test]
+fn entries_range() {
+    let entries = archive("rar50/recovery_record_and_quick_open.rar")
+        .entries()
+        .unwrap();
+    let mut archive = archive("rar50/recovery_record_and_quick_open.rar");
+
+    let names = |entries: &[FileEntry]| entries.iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+
+    let page = archive.entries_range(2..5).unwrap();
+    assert_eq!(names(&page), names(&entries[2..5]));
+
+    // Ranges are clamped to the number of entries.
+    let page = archive.entries_range(10..20).unwrap();
+    assert_eq!(names(&page), names(&entries[10..]));
+    assert!(archive.entries_range(20..30).unwrap().is_empty());
+}
+
+#[rstest]
+#[case("rar14/stored.rar")]
+#[case("rar15/unpack_versions.rar")]
+#[case("rar50/quick_open.rar")]
+#[case("rar50/recovery_record_and_quick_open.rar")]
+fn find_entry(#[case] path: &str) {
+    let entries = archive(path).entries().unwrap();
+
+    let mut archive = archive(path);
+    for entry in &entries {
+        let name = entry.name.as_deref().unwrap();
+        let found = archive.find_entry(name).unwrap().unwrap();
+        assert_eq!(found.offset, entry.offset);
+        assert_eq!(found.unpacked_size, entry.unpacked_size);
+    }
+
+    assert!(archive.find_entry("missing").unwrap().is_none());
+}
+
+#[test]
+fn find_entry_from_quick_open() {
+    let mut data = fs::read("tests/fixtures/rar50/quick_open.rar").unwrap();
+
+    // Only change the name in the header of the file, not in its cached copy.
+    let offset = data
+        .windows(9)
+        .position(|window| window == b"hello.txt")
+        .unwrap();
+    data[offset] = b'j';
+
+    let mut archive = Archive::new(io::Cursor::new(data)).unwrap();
+    let cache = archive.seek_to_quick_open().unwrap().unwrap();
+    assert_eq!(cache.headers.len(), 4);
+
+    let found = archive.find_entry("hello.txt").unwrap().unwrap();
+    assert_eq!(found.name.as_deref(), Ok("hello.txt"));
+
+    // Names which are not in the cache are found by scanning the archive.
+    let found = archive.find_entry("jello.txt").unwrap().unwrap();
+    assert_eq!(found.offset, cache.headers[0].offset);
+}
+
+#[rstest]
+#[case("rar15/unpack_versions.rar", false, false)]
+#[case("rar50/quick_open.rar", true, false)]
+#[case("rar50/recovery_record.rar", false, true)]
+#[case("rar50/recovery_record_and_quick_open.rar", true, false)]
+fn seek_to_locator_targets(
+    #[case] path: &str,
+    #[case] quick_open: bool,
+    #[case] recovery_record: bool,
+) {
+    let mut archive = archive(path);
+    assert_eq!(archive.seek_to_quick_open().unwrap().is_some(), quick_open);
+    assert_eq!(
+        archive.seek_to_recovery_record().unwrap().is_some(),
+        recovery_record
+    );
+}
@@ -0,0 +1,51 @@
+use super::archive;
+
+#[test]
+fn lazy_entries() {
+    for path in [
+        "rar14/stored.rar",
+        "rar15/service_blocks.rar",
+        "rar50/alternate_data_streams.rar",
+    ] {
+        let expected: Vec<_> = archive(path)
+            .entries()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.offset)
+            .collect();
+
+        let mut archive = archive(path);
+        let offsets: Vec<_> = archive
+            .entry_iter()
+            .unwrap()
+            .map(|entry| entry.unwrap().offset)
+            .collect();
+        assert_eq!(offsets, expected, "{path}");
+
+        let offsets: Vec<_> = archive
+            .into_entry_iter()
+            .unwrap()
+            .map(|entry| entry.unwrap().offset)
+            .collect();
+        assert_eq!(offsets, expected, "{path}");
+    }
+}
+
+#[test]
+fn skips_other_blocks() {
+    // The stream and owner service blocks are not listed.
+    let names: Vec<_> = archive("rar15/service_blocks.rar")
+        .into_entry_iter()
+        .unwrap()
+        .map(|entry| entry.unwrap().name_lossy().into_owned())
+        .collect();
+    assert_eq!(names, ["file.txt", "other.txt"]);
+}
+
+#[test]
+fn passes_errors_through() {
+    let mut entries = archive("rar50/encrypted_headers.rar")
+        .into_entry_iter()
+        .unwrap();
+    assert!(entries.next().unwrap().is_err());
+}
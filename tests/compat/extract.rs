@@ -0,0 +1,420 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Instant, SystemTime},
+};
+
+use rawrxd::{
+    compat::{Archive, ExtractOptions, ExtractStatus, Extractor},
+    Progress,
+};
+
+use super::archive;
+
+fn destination(name: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&path);
+    path
+}
+
+#[test]
+fn test_extract_all() {
+    let destination = destination("extract_all");
+    let mut archive = archive("rar15/stored.rar");
+
+    let report = Extractor::new(&mut archive, &destination)
+        .extract_all()
+        .unwrap();
+    assert!(report.is_ok());
+    assert_eq!(report.entries.len(), 3);
+    assert!(report
+        .entries
+        .iter()
+        .all(|e| matches!(e.status, ExtractStatus::Extracted) && e.metadata_errors.is_empty()));
+
+    assert!(destination.join("docs").is_dir());
+    assert_eq!(
+        fs::read_to_string(destination.join("hello.txt")).unwrap(),
+        "Hello, world!\n"
+    );
+    assert_eq!(
+        fs::read_to_string(destination.join("docs/readme.txt")).unwrap(),
+        "Read me first.\n"
+    );
+
+    let modified = fs::metadata(destination.join("hello.txt"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    assert_eq!(
+        modified,
+        SystemTime::from(time::macros::datetime!(2010-06-15 12:30:10 UTC))
+    );
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        let metadata = fs::metadata(destination.join("docs/readme.txt")).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o644);
+    }
+
+    let report = Extractor::new(&mut archive, &destination)
+        .extract_all()
+        .unwrap();
+    assert!(matches!(
+        report.entries[2].status,
+        ExtractStatus::AlreadyExists
+    ));
+}
+
+/// Reader recording the unpacked bytes counted by a [`Progress`] every time it's read.
+struct ProgressRecorder<R> {
+    reader: R,
+    progress: Arc<Progress>,
+    seen: Arc<Mutex<Vec<u64>>>,
+}
+
+impl<R: io::Read> io::Read for ProgressRecorder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.seen
+            .lock()
+            .unwrap()
+            .push(self.progress.unpacked_bytes());
+        self.reader.read(buf)
+    }
+}
+
+impl<R: io::Seek> io::Seek for ProgressRecorder<R> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
+
+#[test]
+fn test_extract_progress() {
+    let destination = destination("extract_progress");
+    let total = Progress::new();
+    let seen = Arc::new(Mutex::new(vec![]));
+    let reader = ProgressRecorder {
+        reader: fs::File::open("tests/fixtures/rar15/unpack_versions.rar").unwrap(),
+        progress: total.clone(),
+        seen: seen.clone(),
+    };
+    let mut archive = Archive::new(reader).unwrap();
+
+    let options = ExtractOptions {
+        progress: Some(total.clone()),
+        ..Default::default()
+    };
+    let report = Extractor::with_options(&mut archive, &destination, options)
+        .extract_all()
+        .unwrap();
+    assert!(report.is_ok());
+
+    for extracted in &report.entries {
+        assert_eq!(
+            Some(extracted.progress.unpacked_bytes()),
+            extracted.entry.unpacked_size
+        );
+        assert_eq!(
+            extracted.progress.packed_bytes(),
+            extracted.entry.packed_size
+        );
+    }
+
+    let unpacked: u64 = report
+        .entries
+        .iter()
+        .map(|e| e.progress.unpacked_bytes())
+        .sum();
+    let packed: u64 = report.entries.iter().map(|e| e.entry.packed_size).sum();
+    assert_eq!(total.unpacked_bytes(), unpacked);
+    assert_eq!(total.packed_bytes(), packed);
+
+    // The totals advance while the archive is read.
+    let seen = seen.lock().unwrap();
+    assert!(seen.windows(2).all(|w| w[0] <= w[1]));
+    assert!(seen.iter().any(|&n| 0 < n && n < unpacked));
+}
+
+#[test]
+fn test_extract_throttle() {
+    let destination = destination("extract_throttle");
+    let mut archive = archive("rar15/unpack_versions.rar");
+
+    let mut throttled = 0;
+    let report = Extractor::new(&mut archive, &destination)
+        .with_throttle(|bytes| throttled += bytes as u64)
+        .extract_all()
+        .unwrap();
+    assert!(report.is_ok());
+
+    let packed: u64 = report.entries.iter().map(|e| e.entry.packed_size).sum();
+    assert_eq!(throttled, packed);
+}
+
+#[test]
+fn test_extract_max_bytes_per_second() {
+    let destination = destination("extract_max_bytes_per_second");
+    let mut archive = archive("rar15/stored.rar");
+    let packed: u64 = archive
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.packed_size)
+        .sum();
+
+    // The first half is read at once, and the second one takes a second.
+    let options = ExtractOptions {
+        max_bytes_per_second: Some(packed / 2),
+        ..Default::default()
+    };
+    let start = Instant::now();
+    let report = Extractor::with_options(&mut archive, &destination, options)
+        .extract_all()
+        .unwrap();
+    assert!(report.is_ok());
+    assert!(start.elapsed().as_millis() >= 500);
+}
+
+#[test]
+fn test_extract_local_offset() {
+    let destination = destination("extract_local_offset");
+    let mut archive = archive("rar15/stored.rar");
+
+    let options = ExtractOptions {
+        local_offset: Some(time::macros::offset!(+2)),
+        ..Default::default()
+    };
+    let report = Extractor::with_options(&mut archive, &destination, options)
+        .extract_all()
+        .unwrap();
+    assert!(report.is_ok());
+
+    let modified = fs::metadata(destination.join("hello.txt"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    assert_eq!(
+        modified,
+        SystemTime::from(time::macros::datetime!(2010-06-15 10:30:10 UTC))
+    );
+}
+
+#[test]
+fn test_extract_compressed() {
+    let destination = destination("extract_compressed");
+    let mut archive = archive("rar15/unpack_versions.rar");
+    let entries = archive.entries().unwrap();
+
+    let report = Extractor::new(&mut archive, &destination)
+        .extract_all()
+        .unwrap();
+    assert!(report.is_ok());
+
+    for entry in entries {
+        let metadata = fs::metadata(destination.join(entry.sanitized_path())).unwrap();
+        assert_eq!(Some(metadata.len()), entry.unpacked_size);
+    }
+}
+
+#[test]
+fn test_extract_not_extractable() {
+    let destination = destination("extract_not_extractable");
+    let mut archive = archive("rar50/encrypted_files.rar");
+
+    let report = Extractor::new(&mut archive, &destination)
+        .extract_all()
+        .unwrap();
+    assert!(report.is_ok());
+    for extracted in report.entries.iter().filter(|e| e.entry.is_encrypted) {
+        assert!(matches!(extracted.status, ExtractStatus::NotExtractable));
+        assert!(!extracted.path.exists());
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_links() {
+    use std::os::unix::fs::{MetadataExt as _, PermissionsExt as _};
+
+    use rawrxd::compat::{LinkAction, LinkPolicy};
+
+    let destination = destination("extract_links");
+    let mut archive = archive("rar50/links.rar");
+
+    let report = Extractor::new(&mut archive, &destination)
+        .extract_all()
+        .unwrap();
+    assert!(report.is_ok());
+
+    let statuses: Vec<_> = report
+        .entries
+        .iter()
+        .map(|e| (e.entry.name.as_deref().unwrap(), &e.status))
+        .collect();
+    assert!(matches!(
+        statuses[..],
+        [
+            ("dir/file.txt", ExtractStatus::Extracted),
+            ("link", ExtractStatus::Extracted),
+            ("evil", ExtractStatus::LinkRejected),
+            ("hard.txt", ExtractStatus::Extracted),
+            ("dir", ExtractStatus::Extracted),
+        ]
+    ));
+
+    let file = fs::metadata(destination.join("dir/file.txt")).unwrap();
+    assert_eq!(file.permissions().mode() & 0o777, 0o640);
+    assert_eq!(
+        fs::read_link(destination.join("link")).unwrap(),
+        PathBuf::from("dir/file.txt")
+    );
+    assert!(fs::symlink_metadata(destination.join("evil")).is_err());
+    assert_eq!(
+        fs::metadata(destination.join("hard.txt")).unwrap().ino(),
+        file.ino()
+    );
+
+    let destination = self::destination("extract_links_skip");
+    let options = ExtractOptions {
+        links: LinkPolicy {
+            symbolic_links: LinkAction::Skip,
+            hard_links: LinkAction::Skip,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let report = Extractor::with_options(&mut archive, &destination, options)
+        .extract_all()
+        .unwrap();
+    assert!(matches!(
+        report.entries[1].status,
+        ExtractStatus::LinkRejected
+    ));
+    assert!(matches!(
+        report.entries[3].status,
+        ExtractStatus::LinkRejected
+    ));
+    assert!(!destination.join("link").exists());
+
+    let destination = self::destination("extract_links_copy");
+    let options = ExtractOptions {
+        links: LinkPolicy {
+            symbolic_links: LinkAction::Copy,
+            hard_links: LinkAction::Copy,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let report = Extractor::with_options(&mut archive, &destination, options)
+        .extract_all()
+        .unwrap();
+    assert!(report.is_ok());
+    assert!(matches!(
+        report.entries[2].status,
+        ExtractStatus::LinkRejected
+    ));
+    for name in ["link", "hard.txt"] {
+        let copy = fs::symlink_metadata(destination.join(name)).unwrap();
+        assert!(copy.is_file());
+        assert_eq!(copy.nlink(), 1);
+        assert_eq!(
+            fs::read_to_string(destination.join(name)).unwrap(),
+            "hello\n"
+        );
+    }
+
+    let destination = self::destination("extract_links_outside");
+    let options = ExtractOptions {
+        links: LinkPolicy {
+            allow_outside_destination: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    Extractor::with_options(&mut archive, &destination, options)
+        .extract_all()
+        .unwrap();
+    assert_eq!(
+        fs::read_link(destination.join("evil")).unwrap(),
+        PathBuf::from("../../etc/passwd")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_chained_links() {
+    let destination = destination("extract_chained_links");
+    let mut archive = archive("rar50/chained_links.rar");
+
+    let report = Extractor::new(&mut archive, &destination)
+        .extract_all()
+        .unwrap();
+
+    let statuses: Vec<_> = report
+        .entries
+        .iter()
+        .map(|e| (e.entry.name.as_deref().unwrap(), &e.status))
+        .collect();
+    assert!(matches!(
+        statuses[..],
+        [
+            ("d", ExtractStatus::Extracted),
+            ("e", ExtractStatus::LinkRejected),
+            ("f", ExtractStatus::LinkRejected),
+            ("g", ExtractStatus::LinkRejected),
+            ("h", ExtractStatus::Extracted),
+            ("fine", ExtractStatus::Extracted),
+        ]
+    ));
+    for name in ["e", "f", "g"] {
+        assert!(fs::symlink_metadata(destination.join(name)).is_err());
+    }
+    assert_eq!(
+        fs::canonicalize(destination.join("fine")).unwrap(),
+        fs::canonicalize(&destination).unwrap()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_through_symlink() {
+    let destination = destination("extract_through_symlink");
+    let outside = self::destination("extract_through_symlink_outside");
+    fs::create_dir_all(&destination).unwrap();
+    fs::create_dir_all(&outside).unwrap();
+    std::os::unix::fs::symlink(&outside, destination.join("dir")).unwrap();
+
+    let mut archive = archive("rar50/links.rar");
+    let report = Extractor::new(&mut archive, &destination)
+        .extract_all()
+        .unwrap();
+
+    assert!(matches!(report.entries[0].status, ExtractStatus::Failed(_)));
+    assert!(fs::read_dir(&outside).unwrap().next().is_none());
+}
+
+#[test]
+fn test_extract_alternate_data_streams() {
+    let destination = destination("extract_alternate_data_streams");
+    let mut archive = archive("rar50/alternate_data_streams.rar");
+
+    let report = Extractor::new(&mut archive, &destination)
+        .extract_all()
+        .unwrap();
+    assert!(report.is_ok());
+    assert!(report.entries.iter().all(|e| e.metadata_errors.is_empty()));
+
+    // The streams are only written as files on systems without alternate data streams.
+    let separator = if cfg!(windows) { ":" } else { "__" };
+    let stream = |name: &str| {
+        let mut path = destination.join("file.txt").into_os_string();
+        path.push(format!("{separator}{name}"));
+        fs::read(path).unwrap()
+    };
+    assert_eq!(stream("Zone.Identifier"), b"stream data\n");
+    assert_eq!(stream("notes"), b"second\n");
+    assert_eq!(destination.join("file.txt__notes").exists(), !cfg!(windows));
+}
@@ -0,0 +1,266 @@
+use std::{io::Read, path::Path};
+
+use rawrxd::compat::{FileEntry, HashKind, HostOs, PermissionPolicy, Timestamp};
+use rawrxd::{BlockSize, FileAttributes, UnixFileType};
+use time::macros::{datetime, offset};
+
+use super::archive;
+
+fn entries(path: &str) -> Vec<FileEntry> {
+    archive(path).entries().unwrap()
+}
+
+#[test]
+fn rar14_file_entry() {
+    let entries = entries("rar14/stored.rar");
+
+    assert_eq!(entries[0].name, Ok("DOCS".to_string()));
+    assert!(entries[0].is_directory);
+
+    let hello = &entries[1];
+    assert_eq!(hello.name, Ok("HELLO.TXT".to_string()));
+    assert!(!hello.is_directory);
+    assert_eq!(hello.unpacked_size, Some(15));
+    assert_eq!(hello.host_os, HostOs::MsDos);
+    assert_eq!(
+        hello.modification_time,
+        Some(Timestamp::Local(datetime!(1995-06-01 12:00:00)))
+    );
+}
+
+#[test]
+fn rar15_extended_time() {
+    let entries = entries("rar15/extended_time.rar");
+
+    let times = &entries[0];
+    assert_eq!(
+        times.modification_time,
+        Some(Timestamp::Local(datetime!(2024-05-17 12:30:10.1193046)))
+    );
+    assert_eq!(
+        times.creation_time,
+        Some(Timestamp::Local(datetime!(2023-01-02 03:04:07.119296)))
+    );
+    assert_eq!(
+        times.access_time,
+        Some(Timestamp::Local(datetime!(2024-06-01 08:00:00)))
+    );
+    assert_eq!(times.archive_time, None);
+
+    // The extended times of the other entries are truncated.
+    assert_eq!(entries[1].creation_time, None);
+    assert_eq!(entries[2].creation_time, None);
+}
+
+#[test]
+fn rar15_file_entry() {
+    let entries = entries("rar15/stored.rar");
+
+    assert!(entries[0].is_directory);
+
+    let hello = &entries[2];
+    assert_eq!(hello.name, Ok("hello.txt".to_string()));
+    assert_eq!(hello.unpacked_size, Some(14));
+    assert_eq!(hello.packed_size, 14);
+    assert_eq!(hello.host_os, HostOs::Windows);
+    assert_eq!(hello.checksum, Some(HashKind::Crc32(0x7b55a718)));
+    assert_eq!(
+        hello.modification_time,
+        Some(Timestamp::Local(datetime!(2010-06-15 12:30:10)))
+    );
+}
+
+#[test]
+fn rar50_file_entry() {
+    let entries = entries("rar50/unix_high_ascii_filename.rar");
+
+    let file = &entries[0];
+    assert_eq!(file.name, Ok("Æ".to_string()));
+    assert_eq!(file.unpacked_size, Some(4));
+    assert_eq!(file.host_os, HostOs::Unix);
+    assert_eq!(file.attributes, 0o100644);
+    assert_eq!(file.checksum, Some(HashKind::Crc32(3632233996)));
+    assert!(matches!(file.modification_time, Some(Timestamp::Utc(_))));
+}
+
+#[test]
+fn normalized_modification_time() {
+    // The extended time of RAR15 is more precise than the MS-DOS timestamp.
+    let rar15 = entries("rar15/extended_time.rar");
+    assert_eq!(
+        rar15[0].modification_time(),
+        Some(datetime!(2024-05-17 12:30:10.1193046 UTC))
+    );
+    assert_eq!(
+        rar15[1].modification_time(),
+        Some(datetime!(2024-05-17 12:30:10 UTC))
+    );
+
+    let rar50 = entries("rar50/unix_high_ascii_filename.rar");
+    let Some(Timestamp::Utc(time)) = rar50[0].modification_time else {
+        panic!("not a UTC timestamp");
+    };
+    assert_eq!(rar50[0].modification_time(), Some(time));
+}
+
+#[test]
+fn timestamp_offset() {
+    let local = Timestamp::Local(datetime!(2024-05-17 12:30:10));
+    assert_eq!(
+        local.assume_offset(offset!(-5)),
+        datetime!(2024-05-17 17:30:10 UTC)
+    );
+    assert_eq!(local.assume_utc(), datetime!(2024-05-17 12:30:10 UTC));
+
+    let utc = Timestamp::Utc(datetime!(2024-05-17 12:30:10 UTC));
+    assert_eq!(utc.assume_offset(offset!(+9)), utc.assume_utc());
+}
+
+#[test]
+fn read_stored_entries() {
+    for path in ["rar14/stored.rar", "rar15/stored.rar"] {
+        let mut archive = archive(path);
+        let entries = archive.entries().unwrap();
+        let hello = entries
+            .iter()
+            .find(|entry| entry.name_lossy().eq_ignore_ascii_case("hello.txt"))
+            .unwrap();
+        assert!(hello.is_stored);
+
+        let mut data = String::new();
+        hello
+            .data_reader(archive.reader())
+            .unwrap()
+            .read_to_string(&mut data)
+            .unwrap();
+        assert!(data.starts_with("Hello, world!"), "{path}: {data:?}");
+    }
+}
+
+#[test]
+fn data_range_of_entries() {
+    for path in [
+        "rar14/stored.rar",
+        "rar15/stored.rar",
+        "rar50/unix_owner.rar",
+    ] {
+        let file = std::fs::read(format!("tests/fixtures/{path}")).unwrap();
+        let mut archive = archive(path);
+
+        let blocks = archive
+            .blocks()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        for block in &blocks {
+            let Some(entry) = FileEntry::from_block(block) else {
+                continue;
+            };
+            let range = entry.data_range();
+            assert_eq!(range, block.data_range(), "{path}");
+            assert_eq!(range.start, entry.offset + entry.header_size);
+            assert_eq!(range.end - range.start, entry.packed_size);
+            assert!(range.end <= file.len() as u64);
+        }
+    }
+}
+
+#[test]
+fn owned_block_entries_match_borrowed_ones() {
+    for path in [
+        "rar14/stored.rar",
+        "rar15/stored.rar",
+        "rar15/versions.rar",
+        "rar50/versions.rar",
+        "rar50/unix_owner.rar",
+    ] {
+        let mut archive = archive(path);
+        for block in archive.blocks().unwrap() {
+            let block = block.unwrap();
+            let borrowed = FileEntry::from_block(&block);
+            let owned = FileEntry::from_owned_block(block);
+            assert_eq!(format!("{owned:?}"), format!("{borrowed:?}"), "{path}");
+        }
+    }
+}
+
+#[test]
+fn compressed_entry_has_no_data_reader() {
+    let mut archive = archive("rar15/unpack_versions.rar");
+    let entries = archive.entries().unwrap();
+    assert!(!entries[0].is_stored);
+
+    let error = entries[0].data_reader(archive.reader()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::Unsupported);
+}
+
+#[test]
+fn synthesized_permissions() {
+    let policy = PermissionPolicy::default();
+
+    let entries = entries("rar15/stored.rar");
+    assert_eq!(entries[0].host_os, HostOs::Windows);
+    assert_eq!(policy.mode(&entries[0]), 0o755);
+    assert_eq!(policy.mode(&entries[2]), 0o644);
+
+    let strict = PermissionPolicy {
+        umask: 0o077,
+        ..Default::default()
+    };
+    assert_eq!(strict.mode(&entries[0]), 0o700);
+    assert_eq!(strict.mode(&entries[2]), 0o600);
+}
+
+#[test]
+fn preserved_unix_permissions() {
+    let entries = entries("rar50/unix_high_ascii_filename.rar");
+    let policy = PermissionPolicy {
+        umask: 0,
+        file_mode: 0o600,
+        ..Default::default()
+    };
+    assert_eq!(policy.mode(&entries[0]), 0o644);
+
+    let policy = PermissionPolicy {
+        preserve_unix_mode: false,
+        ..policy
+    };
+    assert_eq!(policy.mode(&entries[0]), 0o600);
+}
+
+#[test]
+fn file_entry_attributes() {
+    let rar14 = entries("rar14/stored.rar");
+    let FileAttributes::Dos(attributes) = rar14[0].attributes() else {
+        panic!("expected DOS attributes");
+    };
+    assert!(attributes.is_directory());
+
+    let rar15 = entries("rar15/stored.rar");
+    let FileAttributes::Windows(attributes) = rar15[2].attributes() else {
+        panic!("expected Windows attributes");
+    };
+    assert!(!attributes.is_directory());
+
+    let rar50 = entries("rar50/unix_owner.rar");
+    let FileAttributes::Unix(mode) = rar50[0].attributes() else {
+        panic!("expected a Unix mode");
+    };
+    assert_eq!(mode.file_type(), Some(UnixFileType::Regular));
+    assert!(mode.is_setuid());
+    assert_eq!(mode.permissions(), 0o4750);
+}
+
+#[test]
+fn file_entry_sanitized_path() {
+    let entries = entries("rar15/stored.rar");
+    assert_eq!(entries[1].name, Ok("docs\\readme.txt".to_string()));
+    assert_eq!(
+        entries[1].sanitized_path(),
+        Path::new("docs").join("readme.txt")
+    );
+
+    let mut entry = entries[2].clone();
+    entry.name = Ok("../../etc/passwd".to_string());
+    assert_eq!(entry.sanitized_path(), Path::new("etc").join("passwd"));
+}
@@ -0,0 +1,116 @@
+use rawrxd::{
+    compat::{EncryptionScheme, FeatureChange, HashType},
+    Signature,
+};
+
+use super::archive;
+
+#[test]
+fn header_encryption() {
+    let features = archive("rar15/encrypted_headers.rar")
+        .format_features()
+        .unwrap();
+    assert_eq!(features.header_encryption, Some(EncryptionScheme::Aes128));
+
+    assert_eq!(features.changes_for(Signature::Rar15), []);
+    assert_eq!(
+        features.changes_for(Signature::Rar50),
+        [FeatureChange::HeaderEncryption {
+            from: EncryptionScheme::Aes128,
+            to: Some(EncryptionScheme::Aes256),
+        }]
+    );
+    assert_eq!(
+        features.changes_for(Signature::Rar14),
+        [FeatureChange::HeaderEncryption {
+            from: EncryptionScheme::Aes128,
+            to: None,
+        }]
+    );
+
+    let features = archive("rar50/encrypted_headers.rar")
+        .format_features()
+        .unwrap();
+    assert_eq!(features.header_encryption, Some(EncryptionScheme::Aes256));
+}
+
+#[test]
+fn file_encryption_and_hashes() {
+    let features = archive("rar50/encrypted_files.rar")
+        .format_features()
+        .unwrap();
+    assert_eq!(features.format, Signature::Rar50);
+    assert!(features
+        .file_encryption
+        .iter()
+        .eq([&EncryptionScheme::Aes256]));
+    assert!(features
+        .hashes
+        .iter()
+        .eq([&HashType::Crc32, &HashType::Blake2sp]));
+
+    assert_eq!(features.changes_for(Signature::Rar50), []);
+    assert_eq!(
+        features.changes_for(Signature::Rar15),
+        [
+            FeatureChange::FileEncryption {
+                from: EncryptionScheme::Aes256,
+                to: EncryptionScheme::Aes128,
+            },
+            FeatureChange::Hash {
+                from: HashType::Blake2sp,
+                to: HashType::Crc32,
+            },
+        ]
+    );
+}
+
+#[test]
+fn dictionary_size() {
+    let features = archive("rar50/rar7_dictionary_sizes.rar")
+        .format_features()
+        .unwrap();
+    assert_eq!(features.max_dictionary_size, Some(128 << 30));
+
+    assert!(features
+        .changes_for(Signature::Rar15)
+        .contains(&FeatureChange::DictionarySize {
+            from: 128 << 30,
+            to: 4 << 20,
+        }));
+
+    let features = archive("rar15/unpack_versions.rar")
+        .format_features()
+        .unwrap();
+    assert_eq!(features.max_dictionary_size, Some(1 << 20));
+    assert_eq!(
+        features.changes_for(Signature::Rar14),
+        [
+            FeatureChange::DictionarySize {
+                from: 1 << 20,
+                to: 64 << 10,
+            },
+            FeatureChange::Hash {
+                from: HashType::Crc32,
+                to: HashType::Crc16,
+            },
+        ]
+    );
+}
+
+#[test]
+fn recovery_record() {
+    let features = archive("rar50/recovery_record.rar")
+        .format_features()
+        .unwrap();
+    assert!(features.recovery_record);
+
+    assert_eq!(features.changes_for(Signature::Rar50), []);
+    assert_eq!(
+        features.changes_for(Signature::Rar15),
+        [FeatureChange::RecoveryRecord { lost: false }]
+    );
+    assert!(features
+        .changes_for(Signature::Rar14)
+        .contains(&FeatureChange::RecoveryRecord { lost: true }));
+}
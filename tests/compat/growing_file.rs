@@ -0,0 +1,80 @@
+use std::{
+    cell::RefCell,
+    fs,
+    io::{self, Read, Seek},
+    rc::Rc,
+};
+
+use rawrxd::{compat::BlockIterator, BlockSize, Signature};
+
+/// Reader of a file which is still being written to.
+struct GrowingFile {
+    data: Rc<RefCell<Vec<u8>>>,
+    position: u64,
+}
+
+impl Read for GrowingFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = self.data.borrow();
+        let mut remaining = data.get(self.position as usize..).unwrap_or_default();
+        let n = remaining.read(buf)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for GrowingFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.position = match pos {
+            io::SeekFrom::Start(offset) => offset,
+            io::SeekFrom::End(offset) => (self.data.borrow().len() as i64 + offset)
+                .try_into()
+                .unwrap(),
+            io::SeekFrom::Current(offset) => (self.position as i64 + offset).try_into().unwrap(),
+        };
+        Ok(self.position)
+    }
+}
+
+/// Offsets of all the blocks of the archive at `path`.
+fn block_offsets(path: &str, format: Signature) -> Vec<u64> {
+    let reader = io::Cursor::new(fs::read(path).unwrap());
+    BlockIterator::new(reader, format, format.size())
+        .unwrap()
+        .map(|block| block.unwrap().offset())
+        .collect()
+}
+
+#[rstest::rstest]
+#[case("tests/fixtures/rar15/stored.rar", Signature::Rar15)]
+#[case("tests/fixtures/rar50/quick_open.rar", Signature::Rar50)]
+fn resume_after_truncated_block(#[case] path: &str, #[case] format: Signature) {
+    let full = fs::read(path).unwrap();
+    let expected = block_offsets(path, format);
+
+    for size in [expected[2], expected[2] + 3, full.len() as u64 - 1] {
+        let data = Rc::new(RefCell::new(full[..size as usize].to_vec()));
+        let reader = GrowingFile {
+            data: data.clone(),
+            position: 0,
+        };
+        let mut blocks = BlockIterator::new(reader, format, format.size()).unwrap();
+
+        let mut offsets = vec![];
+        for block in blocks.by_ref() {
+            match block {
+                Ok(block) => offsets.push(block.offset()),
+                Err(_) => break,
+            }
+        }
+        assert_eq!(blocks.next_offset(), expected[offsets.len()], "{size}");
+
+        *data.borrow_mut() = full.clone();
+        blocks.resume_at(blocks.next_offset(), full.len() as u64);
+        offsets.extend(blocks.by_ref().map(|block| block.unwrap().offset()));
+
+        assert_eq!(offsets, expected, "{size}");
+        assert_eq!(blocks.warnings(), &[], "{size}");
+        assert_eq!(blocks.has_next_volume(), Some(false));
+    }
+}
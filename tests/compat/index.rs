@@ -0,0 +1,74 @@
+use std::io;
+
+use rawrxd::compat::{ArchiveIndex, MultiVolumeReader};
+
+use super::archive;
+
+#[test]
+fn lookup() {
+    let entries = archive("rar15/stored.rar").entries().unwrap();
+    let index = archive("rar15/stored.rar").index().unwrap();
+
+    assert_eq!(index.len(), entries.len());
+    for (indexed, entry) in index.entries().iter().zip(&entries) {
+        assert_eq!(indexed.volume, 0);
+        assert_eq!(indexed.offset, entry.offset);
+        assert_eq!(indexed.packed_size, entry.packed_size);
+        assert_eq!(indexed.checksum, entry.checksum);
+    }
+
+    // Both separators can be used to look up RAR15 paths stored with `\`.
+    let readme = index.get("docs/readme.txt").unwrap();
+    assert_eq!(readme.name, "docs\\readme.txt");
+    assert_eq!(index.get("docs\\readme.txt"), Some(readme));
+
+    assert_eq!(index.get("DOCS/README.TXT"), None);
+    assert_eq!(index.get_ignore_case("DOCS/README.TXT"), Some(readme));
+
+    let names = |pattern| {
+        index
+            .glob(pattern)
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names("*.txt"), ["hello.txt"]);
+    assert_eq!(names("**.txt"), ["docs\\readme.txt", "hello.txt"]);
+    assert_eq!(names("docs/*"), ["docs\\readme.txt"]);
+}
+
+#[test]
+fn multi_volume_index() {
+    let index = MultiVolumeReader::open("tests/fixtures/rar50/multivolume.part1.rar")
+        .unwrap()
+        .index();
+
+    let volumes: Vec<_> = index
+        .entries()
+        .iter()
+        .map(|entry| (entry.name.as_str(), entry.volume))
+        .collect();
+    assert_eq!(volumes, [("hello.txt", 0), ("data.bin", 0), ("zzz.txt", 2)]);
+}
+
+#[test]
+fn serialize() {
+    for path in [
+        "rar14/stored.rar",
+        "rar15/stored.rar",
+        "rar50/blake2sp_hash.rar",
+        "rar50/recovery_record_and_quick_open.rar",
+    ] {
+        let index = archive(path).index().unwrap();
+
+        let mut data = vec![];
+        index.write_to(&mut data).unwrap();
+        let read = ArchiveIndex::read_from(data.as_slice()).unwrap();
+
+        assert_eq!(read.entries(), index.entries(), "{path}");
+        let first = &index.entries()[0];
+        assert_eq!(read.get(&first.name), Some(first));
+    }
+
+    let err = ArchiveIndex::read_from(&b"Rar!\x1a\x07\x00"[..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,33 @@
+use rawrxd::compat::{FileEntry, HostOs, MacFileInfo};
+
+use super::archive;
+
+#[test]
+fn mac_file_info() {
+    let mut archive = archive("rar15/mac_info.rar");
+    let blocks: Vec<_> = archive.blocks().unwrap().map(Result::unwrap).collect();
+
+    let entry = blocks.iter().find_map(FileEntry::from_block).unwrap();
+    assert_eq!(entry.host_os, HostOs::MacOs);
+
+    let infos: Vec<_> = blocks.iter().filter_map(MacFileInfo::from_block).collect();
+    assert_eq!(
+        infos,
+        [MacFileInfo {
+            file_type: *b"TEXT",
+            creator: *b"ttxt",
+        }]
+    );
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "freebsd", target_os = "netbsd")))]
+#[test]
+fn extended_attributes_unsupported() {
+    use std::{io, path::Path};
+
+    use rawrxd::compat::set_extended_attribute;
+
+    let err =
+        set_extended_attribute(Path::new("file"), MacFileInfo::FINDER_INFO, &[0; 32]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+}
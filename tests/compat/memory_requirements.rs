@@ -0,0 +1,42 @@
+use super::archive;
+
+#[test]
+fn rar7_dictionary_sizes() {
+    let requirements = archive("rar50/rar7_dictionary_sizes.rar")
+        .memory_requirements(1024)
+        .unwrap();
+
+    // Sizes over the 64 GiB supported by RAR 7 are still reported.
+    assert_eq!(
+        requirements.max_dictionary_size,
+        Some(128 * 1024 * 1024 * 1024)
+    );
+
+    // The entries are smaller than their dictionaries.
+    assert_eq!(requirements.max_window_size, Some(970));
+    assert!(!requirements.exceeds_budget());
+
+    let requirements = archive("rar50/rar7_dictionary_sizes.rar")
+        .memory_requirements(512)
+        .unwrap();
+    assert!(requirements.exceeds_budget());
+}
+
+#[test]
+fn rar15_dictionary_sizes() {
+    let requirements = archive("rar15/unpack_versions.rar")
+        .memory_requirements(0)
+        .unwrap();
+    assert_eq!(requirements.max_window_size, Some(12000));
+    assert!(requirements.exceeds_budget());
+}
+
+#[test]
+fn stored_entries() {
+    for path in ["rar14/stored.rar", "rar15/stored.rar"] {
+        let requirements = archive(path).memory_requirements(0).unwrap();
+        assert_eq!(requirements.max_dictionary_size, None);
+        assert_eq!(requirements.max_window_size, None);
+        assert!(!requirements.exceeds_budget());
+    }
+}
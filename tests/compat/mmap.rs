@@ -0,0 +1,36 @@
+use rawrxd::{compat::Archive, Error};
+
+use super::archive;
+
+#[test]
+fn same_blocks_as_buffered_reader() {
+    for path in [
+        "rar14/stored.rar",
+        "rar15/unpack_versions.rar",
+        "rar50/quick_open.rar",
+        "rar50/encrypted_files.rar",
+    ] {
+        let mut buffered = archive(path);
+        let expected: Vec<_> = buffered
+            .blocks()
+            .unwrap()
+            .map(|block| format!("{block:?}"))
+            .collect();
+
+        let mut mapped = unsafe { Archive::open_mmap(format!("tests/fixtures/{path}")) }.unwrap();
+        assert_eq!(mapped.format(), buffered.format());
+        let blocks: Vec<_> = mapped
+            .blocks()
+            .unwrap()
+            .map(|block| format!("{block:?}"))
+            .collect();
+
+        assert_eq!(blocks, expected, "{path}");
+    }
+}
+
+#[test]
+fn missing_file() {
+    let result = unsafe { Archive::open_mmap("tests/fixtures/missing.rar") };
+    assert!(matches!(result, Err(Error::Io(_))));
+}
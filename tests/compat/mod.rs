@@ -0,0 +1,40 @@
+use std::{fs, io};
+
+use rawrxd::compat::Archive;
+
+mod alternate_streams;
+mod block_data;
+mod compact_listing;
+mod encryption;
+mod entry_index;
+mod entry_iterator;
+mod extract;
+mod file_entry;
+mod format_features;
+mod growing_file;
+mod index;
+mod mac_info;
+mod memory_requirements;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod multi_volume;
+mod nested;
+mod ntfs_acl;
+mod owner;
+mod peek;
+mod rar13;
+mod raw_headers;
+mod segment_cache;
+mod sfx;
+mod solid_plan;
+mod summary;
+mod trailing_data;
+mod unpacked_entry_reader;
+mod verify;
+mod versions;
+mod volume_set;
+
+fn archive(path: &str) -> Archive<io::BufReader<fs::File>> {
+    let reader = io::BufReader::new(fs::File::open(format!("tests/fixtures/{path}")).unwrap());
+    Archive::new(reader).unwrap()
+}
@@ -0,0 +1,295 @@
+use std::{
+    cell::Cell,
+    fs,
+    io::{self, Read, Seek},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use rawrxd::{
+    compat::{find_volumes, Block, EntryReader, MultiVolumeReader, VolumeNaming},
+    rar50, Error,
+};
+
+use super::archive;
+
+/// Decompress and check the entry read by `reader`.
+fn decompress<R: Read + Seek>(reader: EntryReader<'_, R>) -> Vec<u8> {
+    let Block::Rar50(block) = &reader.entry().block else {
+        panic!("not a RAR50 block");
+    };
+    let rar50::BlockKind::File(file) = &block.kind else {
+        panic!("not a file block");
+    };
+
+    let mut data = vec![];
+    rar50::Decoder::new(reader, file)
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    data
+}
+
+fn spans<R: Read + Seek>(reader: &MultiVolumeReader<R>) -> Vec<(String, usize, usize)> {
+    reader
+        .entries()
+        .iter()
+        .map(|spanned| {
+            (
+                spanned.entry.name_lossy().into_owned(),
+                *spanned.volumes.start(),
+                *spanned.volumes.end(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn new_naming() {
+    let mut reader = MultiVolumeReader::open("tests/fixtures/rar50/multivolume.part1.rar").unwrap();
+
+    assert_eq!(reader.volume_count(), 3);
+    assert_eq!(
+        spans(&reader),
+        [
+            ("hello.txt".to_string(), 0, 0),
+            ("data.bin".to_string(), 0, 2),
+            ("zzz.txt".to_string(), 2, 2),
+        ]
+    );
+
+    let entry = &reader.entries()[1].entry;
+    assert!(!entry.split_before && !entry.split_after);
+    assert_eq!(entry.packed_size, 10000);
+
+    assert_eq!(decompress(reader.entry_reader(1).unwrap()).len(), 10000);
+    assert_eq!(
+        decompress(reader.entry_reader(0).unwrap()),
+        b"Hello, world!\n"
+    );
+    assert!(reader.entry_reader(3).is_none());
+}
+
+#[test]
+fn old_naming() {
+    let mut reader = MultiVolumeReader::open("tests/fixtures/rar50/oldnaming.rar").unwrap();
+
+    assert_eq!(reader.volume_count(), 3);
+    assert_eq!(spans(&reader), [("lines.txt".to_string(), 0, 2)]);
+
+    let data = decompress(reader.entry_reader(0).unwrap());
+    assert_eq!(data.len(), 20694);
+    assert!(data.starts_with(b"0: "));
+}
+
+#[test]
+fn seek_across_volumes() {
+    let mut reader = MultiVolumeReader::open("tests/fixtures/rar50/multivolume.part1.rar").unwrap();
+
+    let mut data = vec![];
+    let mut entry_reader = reader.entry_reader(1).unwrap();
+    entry_reader.read_to_end(&mut data).unwrap();
+
+    // Read ranges crossing the boundaries between the volumes.
+    for (start, len) in [(100, 9800), (5000, 4000), (0, 10000)] {
+        let mut buf = vec![0; len];
+        entry_reader
+            .seek(io::SeekFrom::Start(start as u64))
+            .unwrap();
+        entry_reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[start..start + len]);
+    }
+
+    let mut tail = vec![];
+    entry_reader.seek(io::SeekFrom::End(-10)).unwrap();
+    entry_reader.read_to_end(&mut tail).unwrap();
+    assert_eq!(tail, data[data.len() - 10..]);
+}
+
+/// Reader counting the reads of all the volumes.
+struct CountingReader {
+    reader: fs::File,
+    reads: Rc<Cell<usize>>,
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reads.set(self.reads.get() + 1);
+        self.reader.read(buf)
+    }
+}
+
+impl Seek for CountingReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
+
+#[test]
+fn segment_cache() {
+    let reads = Rc::new(Cell::new(0));
+    let volumes = (1..=3).map(|n| CountingReader {
+        reader: fs::File::open(format!("tests/fixtures/rar50/multivolume.part{n}.rar")).unwrap(),
+        reads: reads.clone(),
+    });
+    let mut reader = MultiVolumeReader::new(volumes).unwrap();
+    reader.set_cache_capacity(0x10_0000);
+
+    let data = decompress(reader.entry_reader(1).unwrap());
+    let count = reads.get();
+
+    // The entry spans the three volumes, which are not read again.
+    assert_eq!(decompress(reader.entry_reader(1).unwrap()), data);
+    assert_eq!(reads.get(), count);
+
+    let mut entry_reader = reader.entry_reader(1).unwrap();
+    let mut buf = vec![0; 4000];
+    entry_reader.seek(io::SeekFrom::Start(5000)).unwrap();
+    entry_reader.read_exact(&mut buf).unwrap();
+    assert_eq!(reads.get(), count);
+
+    let mut packed = vec![];
+    entry_reader.seek(io::SeekFrom::Start(0)).unwrap();
+    entry_reader.read_to_end(&mut packed).unwrap();
+    assert_eq!(buf, packed[5000..9000]);
+
+    // The segments are read from the volumes once they don't fit anymore.
+    reader.set_cache_capacity(0);
+    assert_eq!(decompress(reader.entry_reader(1).unwrap()), data);
+    assert!(reads.get() > count);
+}
+
+#[test]
+fn volume_list() {
+    let volumes = (1..=3).map(|n| {
+        io::BufReader::new(
+            fs::File::open(format!("tests/fixtures/rar50/multivolume.part{n}.rar")).unwrap(),
+        )
+    });
+    let reader = MultiVolumeReader::new(volumes).unwrap();
+    assert_eq!(reader.entries().len(), 3);
+}
+
+#[test]
+fn missing_volume() {
+    let result = MultiVolumeReader::with_opener(|index| {
+        if index == 2 {
+            return Ok(None);
+        }
+        let path = format!("tests/fixtures/rar50/multivolume.part{}.rar", index + 1);
+        Ok(Some(io::BufReader::new(fs::File::open(path)?)))
+    });
+
+    match result {
+        Err(Error::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::NotFound),
+        other => panic!("expected a missing volume error, got {other:?}"),
+    }
+}
+
+#[test]
+fn volume_naming() {
+    let old = Path::new("dir/archive.rar");
+    assert_eq!(VolumeNaming::detect(old), VolumeNaming::Old);
+    assert_eq!(
+        VolumeNaming::Old.volume_path(old, 1).unwrap(),
+        Path::new("dir/archive.r00")
+    );
+    assert_eq!(
+        VolumeNaming::Old.volume_path(old, 101).unwrap(),
+        Path::new("dir/archive.s00")
+    );
+    assert_eq!(
+        VolumeNaming::Old
+            .volume_path(Path::new("ARCHIVE.RAR"), 2)
+            .unwrap(),
+        Path::new("ARCHIVE.R01")
+    );
+    assert_eq!(VolumeNaming::Old.volume_path(old, 901), None);
+
+    let new = Path::new("dir/archive.part01.rar");
+    let naming = VolumeNaming::detect(new);
+    assert_eq!(naming, VolumeNaming::New { digits: 2 });
+    assert_eq!(
+        naming.volume_path(new, 0).unwrap(),
+        Path::new("dir/archive.part01.rar")
+    );
+    assert_eq!(
+        naming.volume_path(new, 9).unwrap(),
+        Path::new("dir/archive.part10.rar")
+    );
+    assert_eq!(
+        naming.volume_path(new, 99).unwrap(),
+        Path::new("dir/archive.part100.rar")
+    );
+}
+
+#[test]
+fn find_volume_paths() {
+    let fixture = |name: &str| PathBuf::from(format!("tests/fixtures/rar50/{name}"));
+
+    assert_eq!(
+        find_volumes(fixture("multivolume.part1.rar")).unwrap(),
+        [
+            fixture("multivolume.part1.rar"),
+            fixture("multivolume.part2.rar"),
+            fixture("multivolume.part3.rar"),
+        ]
+    );
+
+    assert_eq!(
+        find_volumes(fixture("oldnaming.rar")).unwrap(),
+        [
+            fixture("oldnaming.rar"),
+            fixture("oldnaming.r00"),
+            fixture("oldnaming.r01"),
+        ]
+    );
+
+    assert_eq!(
+        find_volumes(fixture("unix_owner.rar")).unwrap(),
+        [fixture("unix_owner.rar")]
+    );
+}
+
+#[test]
+fn find_volume_paths_missing() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("find_volumes_missing");
+    fs::create_dir_all(&dir).unwrap();
+    for n in [1, 2] {
+        fs::copy(
+            format!("tests/fixtures/rar50/multivolume.part{n}.rar"),
+            dir.join(format!("multivolume.part{n}.rar")),
+        )
+        .unwrap();
+    }
+
+    let first = dir.join("multivolume.part1.rar");
+    assert_eq!(
+        find_volumes(&first).unwrap(),
+        [first.clone(), dir.join("multivolume.part2.rar")]
+    );
+
+    match MultiVolumeReader::open(&first) {
+        Err(Error::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::NotFound),
+        other => panic!("expected a missing volume error, got {other:?}"),
+    }
+}
+
+#[test]
+fn block_iterator_has_next_volume() {
+    for (n, has_next_volume) in [(1, true), (2, true), (3, false)] {
+        let mut archive = archive(&format!("rar50/multivolume.part{n}.rar"));
+        let mut blocks = archive.blocks().unwrap();
+        assert_eq!(blocks.has_next_volume(), None);
+
+        let last = blocks.by_ref().map(Result::unwrap).last().unwrap();
+        let Block::Rar50(last) = last else {
+            panic!("not a RAR50 block");
+        };
+        let rar50::BlockKind::EndArchive(end) = &last.kind else {
+            panic!("not an end of archive block");
+        };
+        assert_eq!(end.is_last_volume(), !has_next_volume);
+        assert_eq!(blocks.has_next_volume(), Some(has_next_volume));
+    }
+}
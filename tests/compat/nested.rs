@@ -0,0 +1,32 @@
+use rawrxd::{compat::FileEntry, Error};
+
+use super::archive;
+
+fn names(entries: &[FileEntry]) -> Vec<&str> {
+    entries
+        .iter()
+        .map(|entry| entry.name.as_deref().unwrap())
+        .collect()
+}
+
+#[test]
+fn open_nested_archive() {
+    let mut outer = archive("rar15/nested.rar");
+    let expected = archive("rar15/stored.rar").entries().unwrap();
+
+    let mut nested = outer.open_nested(0).unwrap().unwrap();
+    assert_eq!(nested.reader().entry().name.as_deref(), Ok("inner.rar"));
+    let entries = nested.entries().unwrap();
+    assert_eq!(names(&entries), names(&expected));
+
+    let peek = nested.peek(2, 64).unwrap().unwrap();
+    assert_eq!(peek.data, b"Hello, world!\n");
+
+    assert!(outer.open_nested(1).unwrap().is_none());
+}
+
+#[test]
+fn entry_is_not_an_archive() {
+    let err = archive("rar15/stored.rar").open_nested(2).err().unwrap();
+    assert!(matches!(err, Error::SignatureNotFound));
+}
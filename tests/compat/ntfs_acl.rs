@@ -0,0 +1,43 @@
+use std::io;
+
+use rstest::rstest;
+
+use super::archive;
+
+#[rstest]
+#[case::rar15("rar15/ntfs_acl.rar")]
+#[case::rar20("rar15/sub_blocks.rar")]
+#[case::rar50("rar50/ntfs_acl.rar")]
+fn ntfs_acls(#[case] path: &str) {
+    let mut archive = archive(path);
+    let acls = archive.ntfs_acls().unwrap();
+
+    // The RAR50 service block after other.txt is not marked as its child.
+    assert_eq!(acls.len(), 1);
+    assert_eq!(acls[0].entry.name.as_deref(), Ok("file.txt"));
+
+    let acl = &acls[0].acl;
+    assert_eq!(acl.unpacked_size, Some(100));
+    assert_eq!(acl.crc32, Some(0x2322757a));
+    assert!(acl.is_stored);
+    assert!(!acl.is_encrypted);
+
+    let data = acl.read_data(archive.reader()).unwrap();
+    assert_eq!(data.len(), 100);
+
+    let descriptor = acl.read_descriptor(archive.reader()).unwrap();
+    assert_eq!(
+        descriptor.to_sddl(),
+        "O:BAG:SYD:P(A;OICIID;FA;;;SY)(A;ID;0x1200a9;;;BU)"
+    );
+
+    let mut corrupt = acl.clone();
+    corrupt.crc32 = corrupt.crc32.map(|crc| !crc);
+    let error = corrupt.read_data(archive.reader()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+    let mut compressed = acl.clone();
+    compressed.is_stored = false;
+    let error = compressed.data_reader(archive.reader()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+}
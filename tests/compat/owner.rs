@@ -0,0 +1,74 @@
+use rawrxd::compat::{FileEntry, UnixOwner};
+
+use super::archive;
+
+#[test]
+fn unix_owner() {
+    let mut archive = archive("rar50/unix_owner.rar");
+    let owners: Vec<_> = archive
+        .blocks()
+        .unwrap()
+        .map(Result::unwrap)
+        .filter_map(|block| {
+            let entry = FileEntry::from_block(&block)?;
+            Some((
+                entry.name_lossy().into_owned(),
+                UnixOwner::from_block(&block)?,
+            ))
+        })
+        .collect();
+
+    assert_eq!(owners.len(), 2);
+
+    let (name, owner) = &owners[0];
+    assert_eq!(name, "hello.txt");
+    assert_eq!(owner.mode, Some(0o4750));
+    assert_eq!(owner.user_name, Some(Ok("root".to_string())));
+    assert_eq!(owner.group_name, Some(Ok("root".to_string())));
+
+    let (name, owner) = &owners[1];
+    assert_eq!(name, "dir");
+    assert_eq!(owner.mode, Some(0o711));
+}
+
+#[test]
+fn rar15_unix_owner() {
+    let mut archive = archive("rar15/service_blocks.rar");
+    let mut owners: Vec<UnixOwner> = vec![];
+    for block in archive.blocks().unwrap() {
+        let block = block.unwrap();
+        let Some(owner) = UnixOwner::from_block(&block) else {
+            continue;
+        };
+
+        // The owner is stored in a service block after the file block.
+        if FileEntry::from_block(&block).is_some() {
+            owners.push(owner);
+        } else {
+            owners.last_mut().unwrap().merge(owner);
+        }
+    }
+
+    assert_eq!(owners.len(), 2);
+    assert_eq!(owners[0].mode, Some(0o644));
+    assert_eq!(owners[0].user_name, Some(Ok("user".to_string())));
+    assert_eq!(owners[0].group_name, Some(Ok("staff".to_string())));
+    assert_eq!(owners[1].user_name, Some(Ok("root".to_string())));
+    assert_eq!(owners[1].group_name, Some(Ok("wheel".to_string())));
+}
+
+#[test]
+fn merge_owner() {
+    let mut owner = UnixOwner {
+        mode: Some(0o644),
+        ..UnixOwner::default()
+    };
+    owner.merge(UnixOwner {
+        user_name: Some(Ok("user".to_string())),
+        mode: Some(0o755),
+        ..UnixOwner::default()
+    });
+
+    assert_eq!(owner.mode, Some(0o644));
+    assert_eq!(owner.user_name, Some(Ok("user".to_string())));
+}
@@ -0,0 +1,77 @@
+use std::{fs, io};
+
+use rawrxd::compat::{Archive, PeekStatus};
+
+use super::archive;
+
+#[test]
+fn peek_prefix() {
+    let mut archive = archive("rar15/unpack_versions.rar");
+
+    let peek = archive.peek(3, 16).unwrap().unwrap();
+    assert_eq!(peek.entry.name.as_deref(), Ok("unpack29.bin"));
+    assert_eq!(peek.data.len(), 16);
+    assert_eq!(peek.status, PeekStatus::Unverified);
+
+    // The whole entry fits, so its CRC32 is checked.
+    let peek = archive.peek(0, 8192).unwrap().unwrap();
+    assert_eq!(peek.entry.name.as_deref(), Ok("unpack15.bin"));
+    assert_eq!(peek.data.len(), 4097);
+    assert_eq!(peek.status, PeekStatus::Verified);
+
+    let peek = archive.peek(0, 4097).unwrap().unwrap();
+    assert_eq!(peek.data.len(), 4097);
+    assert_eq!(peek.status, PeekStatus::Verified);
+
+    assert!(archive.peek(5, 16).unwrap().is_none());
+}
+
+#[test]
+fn peek_stored() {
+    let peek = archive("rar15/stored.rar").peek(2, 64).unwrap().unwrap();
+    assert_eq!(peek.entry.name.as_deref(), Ok("hello.txt"));
+    assert_eq!(peek.data, b"Hello, world!\n");
+    assert_eq!(peek.status, PeekStatus::Verified);
+
+    // RAR14 data is read, but can't be checked.
+    let peek = archive("rar14/stored.rar").peek(1, 5).unwrap().unwrap();
+    assert_eq!(peek.data, b"Hello");
+    assert_eq!(peek.status, PeekStatus::Unverified);
+}
+
+#[test]
+fn peek_corrupt() {
+    let mut data = fs::read("tests/fixtures/rar15/stored.rar").unwrap();
+    let offset = data
+        .windows(13)
+        .position(|window| window == b"Hello, world!")
+        .unwrap();
+    data[offset] = b'J';
+
+    let mut archive = Archive::new(io::Cursor::new(data)).unwrap();
+
+    let peek = archive.peek(2, 5).unwrap().unwrap();
+    assert_eq!(peek.data, b"Jello");
+    assert_eq!(peek.status, PeekStatus::Unverified);
+
+    let peek = archive.peek(2, 64).unwrap().unwrap();
+    assert_eq!(peek.status, PeekStatus::Corrupt);
+}
+
+#[test]
+fn peek_not_extractable() {
+    let peek = archive("rar50/encrypted_files.rar")
+        .peek(0, 16)
+        .unwrap()
+        .unwrap();
+    assert!(peek.entry.is_encrypted);
+    assert!(peek.data.is_empty());
+    assert_eq!(peek.status, PeekStatus::NotExtractable);
+
+    let peek = archive("rar50/multivolume.part1.rar")
+        .peek(1, 16)
+        .unwrap()
+        .unwrap();
+    assert!(peek.entry.split_after);
+    assert_eq!(peek.status, PeekStatus::NotExtractable);
+}
@@ -0,0 +1,39 @@
+use std::{fs, io, io::Read as _};
+
+use rawrxd::{rar14, Signature};
+
+use super::archive;
+
+/// Hand-built archive with the same stored file twice, with the unpack version byte of
+/// RAR 1.3 and with an older one. `unrar` lists them as v13 and v10 and tests them fine.
+const FIXTURE: &str = "rar14/unpack_versions.rar";
+
+#[test]
+fn rar13_unpack_versions() {
+    let reader = io::BufReader::new(fs::File::open(format!("tests/fixtures/{FIXTURE}")).unwrap());
+    let versions: Vec<_> = rar14::BlockIterator::new(reader, Signature::Rar14.size())
+        .unwrap()
+        .filter_map(|block| match block.unwrap() {
+            rar14::Block::File(file) => Some(file.unpack_version),
+            rar14::Block::Main(_) => None,
+        })
+        .collect();
+    assert_eq!(versions, [13, 10]);
+}
+
+#[test]
+fn list_rar13_entries() {
+    let mut archive = archive(FIXTURE);
+    let entries = archive.entries().unwrap();
+
+    let names: Vec<_> = entries.iter().map(|entry| entry.name_lossy()).collect();
+    assert_eq!(names, ["HELLO.TXT", "OLDER.TXT"]);
+    assert!(entries.iter().all(|entry| entry.is_stored));
+
+    for index in 0..entries.len() {
+        let mut data = vec![];
+        let mut reader = archive.open_entry(index).unwrap().unwrap();
+        reader.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"Hello, world!\r\n");
+    }
+}
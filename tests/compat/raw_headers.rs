@@ -0,0 +1,35 @@
+use std::fs;
+
+use rawrxd::{BlockSize, RawHeaderReader};
+use rstest::rstest;
+
+use super::archive;
+
+#[rstest]
+#[case("rar14/stored.rar")]
+#[case("rar15/stored.rar")]
+#[case("rar50/unix_owner.rar")]
+#[case("rar50/quick_open.rar")]
+fn raw_headers_match_archive_bytes(#[case] path: &str) {
+    let file = fs::read(format!("tests/fixtures/{path}")).unwrap();
+    let mut archive = archive(path);
+
+    for result in archive.blocks().unwrap().with_raw_headers() {
+        let (block, header) = result.unwrap();
+        let start = block.offset() as usize;
+        assert_eq!(header, &file[start..start + block.header_size() as usize]);
+    }
+}
+
+#[test]
+fn raw_header_of_block_from_another_archive() {
+    let block = archive("rar15/stored.rar")
+        .blocks()
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+
+    let mut archive = archive("rar50/unix_owner.rar");
+    assert!(archive.blocks().unwrap().raw_header(&block).is_err());
+}
@@ -0,0 +1,104 @@
+use std::{
+    fs,
+    io::{self, Read, Seek},
+};
+
+use rawrxd::compat::{Segment, SegmentCache};
+
+use super::archive;
+
+/// Reader counting the reads of the volume.
+struct CountingReader {
+    reader: fs::File,
+    reads: usize,
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reads += 1;
+        self.reader.read(buf)
+    }
+}
+
+impl Seek for CountingReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
+
+fn segments(path: &str) -> Vec<Segment> {
+    archive(path)
+        .entries()
+        .unwrap()
+        .into_iter()
+        .map(|entry| Segment {
+            volume: 0,
+            offset: entry.offset + entry.header_size,
+            size: entry.packed_size,
+        })
+        .collect()
+}
+
+#[test]
+fn segments_are_read_once() {
+    let segments = segments("rar15/unpack_versions.rar");
+    let mut reader = CountingReader {
+        reader: fs::File::open("tests/fixtures/rar15/unpack_versions.rar").unwrap(),
+        reads: 0,
+    };
+    let mut cache = SegmentCache::new(0x10_0000);
+
+    let data: Vec<_> = segments
+        .iter()
+        .map(|segment| cache.data(&mut reader, segment).unwrap().unwrap().to_vec())
+        .collect();
+    let reads = reader.reads;
+    assert_eq!(
+        cache.size(),
+        segments.iter().map(|segment| segment.size).sum::<u64>()
+    );
+
+    for (segment, data) in segments.iter().zip(&data) {
+        let mut expected = vec![0; segment.size as usize];
+        reader
+            .reader
+            .seek(io::SeekFrom::Start(segment.offset))
+            .unwrap();
+        reader.reader.read_exact(&mut expected).unwrap();
+        assert_eq!(*data, expected);
+
+        assert_eq!(cache.data(&mut reader, segment).unwrap().unwrap(), data);
+    }
+    assert_eq!(reader.reads, reads);
+}
+
+#[test]
+fn least_recently_used_segments_are_evicted() {
+    let segments = segments("rar15/unpack_versions.rar");
+    let mut reader = CountingReader {
+        reader: fs::File::open("tests/fixtures/rar15/unpack_versions.rar").unwrap(),
+        reads: 0,
+    };
+    let mut cache = SegmentCache::new(segments[0].size + segments[1].size);
+
+    cache.data(&mut reader, &segments[0]).unwrap().unwrap();
+    cache.data(&mut reader, &segments[1]).unwrap().unwrap();
+    cache.data(&mut reader, &segments[0]).unwrap().unwrap();
+    let reads = reader.reads;
+
+    // The second segment is the least recently used one.
+    cache.data(&mut reader, &segments[2]).unwrap().unwrap();
+    cache.data(&mut reader, &segments[0]).unwrap().unwrap();
+    assert!(reader.reads > reads);
+
+    let reads = reader.reads;
+    cache.data(&mut reader, &segments[1]).unwrap().unwrap();
+    assert!(reader.reads > reads);
+
+    // Segments larger than the cache are not read.
+    cache.set_capacity(segments[3].size - 1);
+    assert!(cache.size() <= cache.capacity());
+    let reads = reader.reads;
+    assert!(cache.data(&mut reader, &segments[3]).unwrap().is_none());
+    assert_eq!(reader.reads, reads);
+}
@@ -0,0 +1,96 @@
+use std::{fs, io};
+
+use rawrxd::{
+    compat::{Archive, SfxExecutableKind},
+    Error, SearchOptions,
+};
+
+use super::archive;
+
+/// A minimal PE stub, with the PE header right after the MS-DOS header.
+fn pe_stub() -> Vec<u8> {
+    let mut stub = vec![0; 0x200];
+    stub[..2].copy_from_slice(b"MZ");
+    stub[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+    stub[0x40..0x44].copy_from_slice(b"PE\0\0");
+    stub
+}
+
+fn sfx_archive(stub: &[u8]) -> Archive<io::Cursor<Vec<u8>>> {
+    let mut data = stub.to_vec();
+    data.extend(fs::read("tests/fixtures/rar50/quick_open.rar").unwrap());
+    Archive::new(io::Cursor::new(data)).unwrap()
+}
+
+#[test]
+fn not_sfx() {
+    let mut archive = archive("rar50/quick_open.rar");
+    assert_eq!(archive.sfx_module().unwrap(), None);
+
+    let mut stub = vec![];
+    assert_eq!(archive.copy_sfx_module(&mut stub).unwrap(), 0);
+    assert!(stub.is_empty());
+}
+
+#[test]
+fn pe_module() {
+    let pe = pe_stub();
+    let mut archive = sfx_archive(&pe);
+
+    let module = archive.sfx_module().unwrap().unwrap();
+    assert_eq!(module.range, 0..pe.len() as u64);
+    assert_eq!(module.size(), pe.len() as u64);
+    assert_eq!(module.kind, SfxExecutableKind::Pe);
+
+    let mut stub = vec![];
+    assert_eq!(archive.copy_sfx_module(&mut stub).unwrap(), pe.len() as u64);
+    assert_eq!(stub, pe);
+
+    // The archive can still be read after the module.
+    assert!(!archive.entries().unwrap().is_empty());
+}
+
+#[test]
+fn sfx_module_is_not_searched_at_start_only() {
+    let mut data = pe_stub();
+    data.extend(fs::read("tests/fixtures/rar50/quick_open.rar").unwrap());
+    let options = SearchOptions {
+        require_at_start: true,
+        ..Default::default()
+    };
+
+    let result = Archive::with_search_options(io::Cursor::new(data), options);
+    assert!(matches!(result, Err(Error::SignatureNotFound)));
+}
+
+#[test]
+fn elf_module() {
+    let mut elf = b"\x7fELF\x02\x01\x01".to_vec();
+    elf.resize(0x100, 0);
+
+    let module = sfx_archive(&elf).sfx_module().unwrap().unwrap();
+    assert_eq!(module.kind, SfxExecutableKind::Elf);
+}
+
+#[test]
+fn unknown_module() {
+    assert_eq!(
+        SfxExecutableKind::from_bytes(b"#!/bin/sh\n"),
+        SfxExecutableKind::Unknown
+    );
+
+    // An MS-DOS header without a PE header.
+    let mut mz = pe_stub();
+    mz[0x40..0x44].fill(0);
+    assert_eq!(
+        SfxExecutableKind::from_bytes(&mz),
+        SfxExecutableKind::Unknown
+    );
+
+    // The PE header offset points past the end of the bytes.
+    mz[0x3c..0x40].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(
+        SfxExecutableKind::from_bytes(&mz),
+        SfxExecutableKind::Unknown
+    );
+}
@@ -0,0 +1,69 @@
+use std::{
+    fs,
+    io::{self, Read as _, Seek as _},
+};
+
+use rawrxd::compat::Archive;
+use rstest::rstest;
+
+use super::archive;
+
+/// `unpack_versions.rar` with the solid flag set on the entries at `solid`.
+fn solid_archive(solid: &[usize]) -> Archive<io::Cursor<Vec<u8>>> {
+    let mut data = fs::read("tests/fixtures/rar15/unpack_versions.rar").unwrap();
+    let entries = archive("rar15/unpack_versions.rar").entries().unwrap();
+
+    for &i in solid {
+        // The flags are after the CRC16 and the type of the block.
+        data[entries[i].offset as usize + 3] |= 0x10;
+    }
+
+    Archive::new(io::Cursor::new(data)).unwrap()
+}
+
+#[test]
+fn independent_entries() {
+    let mut archive = archive("rar15/unpack_versions.rar");
+    let count = archive.entry_count().unwrap();
+
+    for i in 0..count {
+        let plan = archive.solid_plan(i).unwrap().unwrap();
+        assert!(plan.is_independent());
+        assert_eq!(plan.target(), i);
+    }
+    assert!(archive.solid_plan(count).unwrap().is_none());
+}
+
+#[rstest]
+#[case(&[1, 2, 3], 3, &[0, 1, 2, 3])]
+#[case(&[1, 3], 3, &[2, 3])]
+#[case(&[2, 3], 4, &[4])]
+#[case(&[2, 3], 2, &[1, 2])]
+fn solid_entries(#[case] solid: &[usize], #[case] target: usize, #[case] expected: &[usize]) {
+    let plan = solid_archive(solid).solid_plan(target).unwrap().unwrap();
+    assert_eq!(plan.entries(), expected);
+    assert_eq!(plan.prerequisites(), &expected[..expected.len() - 1]);
+}
+
+#[test]
+fn open_solid_entry() {
+    let mut archive = archive("rar50/solid.rar");
+    let plan = archive.solid_plan(2).unwrap().unwrap();
+    assert_eq!(plan.entries(), [0, 1, 2]);
+
+    let mut first = vec![];
+    let mut reader = archive.open_entry(0).unwrap().unwrap();
+    reader.read_to_end(&mut first).unwrap();
+
+    let mut reader = archive.open_entry(2).unwrap().unwrap();
+    assert_eq!(reader.entry().name.as_deref(), Ok("third.txt"));
+    let mut third = String::new();
+    reader.read_to_string(&mut third).unwrap();
+    assert!(third.contains("this line only appears in the third file"));
+
+    // Seeking backward decodes the whole stream again.
+    reader.seek(io::SeekFrom::Start(0)).unwrap();
+    let mut start = vec![0; 100];
+    reader.read_exact(&mut start).unwrap();
+    assert_eq!(start, first[..100]);
+}
@@ -0,0 +1,75 @@
+use std::{fs, io};
+
+use rawrxd::compat::{Archive, HostOs};
+use time::macros::datetime;
+
+use super::archive;
+
+#[test]
+fn rar15_summary() {
+    let summary = archive("rar15/stored.rar").summary().unwrap();
+
+    assert_eq!(summary.entries, 3);
+    assert_eq!(summary.directories, 1);
+    assert_eq!(summary.packed_size, 29);
+    assert_eq!(summary.unpacked_size, 29);
+    assert_eq!(summary.compression_ratio(), Some(1.0));
+    assert_eq!(summary.host_os, vec![(HostOs::Windows, 3)]);
+    assert_eq!(
+        summary.earliest_modification_time,
+        Some(datetime!(2001-01-01 05:00 UTC))
+    );
+    assert_eq!(
+        summary.latest_modification_time,
+        Some(datetime!(2010-06-15 12:30:10 UTC))
+    );
+    assert!(!summary.is_encrypted);
+    assert!(!summary.is_solid);
+    assert!(!summary.is_locked);
+    assert!(!summary.has_recovery_record);
+}
+
+#[test]
+fn compression_ratio() {
+    let summary = archive("rar15/unpack_versions.rar").summary().unwrap();
+    assert_eq!(summary.packed_size, 16519);
+    assert_eq!(summary.unpacked_size, 32993);
+    assert_eq!(summary.compression_ratio(), Some(16519.0 / 32993.0));
+
+    let summary = archive("rar50/links.rar").summary().unwrap();
+    assert_eq!(summary.earliest_modification_time, None);
+    assert_eq!(summary.latest_modification_time, None);
+}
+
+#[test]
+fn archive_flags() {
+    let mut data = fs::read("tests/fixtures/rar15/unpack_versions.rar").unwrap();
+    // Solid and locked flags of the main block, which follows the signature.
+    data[7 + 3] |= 0x08 | 0x04;
+
+    let summary = Archive::new(io::Cursor::new(data))
+        .unwrap()
+        .summary()
+        .unwrap();
+    assert!(summary.is_solid);
+    assert!(summary.is_locked);
+
+    let summary = archive("rar50/recovery_record.rar").summary().unwrap();
+    assert!(summary.has_recovery_record);
+}
+
+#[test]
+fn encrypted() {
+    let summary = archive("rar50/encrypted_files.rar").summary().unwrap();
+    assert_eq!(summary.entries, 3);
+    assert!(summary.is_encrypted);
+    assert!(!summary.has_encrypted_headers);
+
+    for path in ["rar15/encrypted_headers.rar", "rar50/encrypted_headers.rar"] {
+        let summary = archive(path).summary().unwrap();
+        assert_eq!(summary.entries, 0);
+        assert!(summary.is_encrypted);
+        assert!(summary.has_encrypted_headers);
+        assert_eq!(summary.compression_ratio(), None);
+    }
+}
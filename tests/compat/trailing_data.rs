@@ -0,0 +1,84 @@
+use std::{fs, io};
+
+use rawrxd::{compat::Archive, rar15, rar50, Signature};
+
+use super::archive;
+
+fn with_trailing_data(path: &str, payload: &[u8]) -> (Vec<u8>, u64) {
+    let mut data = fs::read(format!("tests/fixtures/{path}")).unwrap();
+    let archive_size = data.len() as u64;
+    data.extend_from_slice(payload);
+    (data, archive_size)
+}
+
+#[test]
+fn no_trailing_data() {
+    for path in ["rar15/stored.rar", "rar50/quick_open.rar"] {
+        let mut archive = archive(path);
+        assert_eq!(archive.trailing_data().unwrap(), None, "{path}");
+
+        let mut payload = vec![];
+        assert_eq!(archive.copy_trailing_data(&mut payload).unwrap(), 0);
+        assert!(payload.is_empty());
+    }
+}
+
+#[test]
+fn archive_trailing_data() {
+    for path in ["rar15/stored.rar", "rar50/quick_open.rar"] {
+        let (data, archive_size) = with_trailing_data(path, b"MZ appended payload");
+        let mut archive = Archive::new(io::Cursor::new(data)).unwrap();
+
+        assert_eq!(
+            archive.trailing_data().unwrap(),
+            Some(archive_size..archive_size + 19),
+            "{path}"
+        );
+
+        let mut payload = vec![];
+        assert_eq!(archive.copy_trailing_data(&mut payload).unwrap(), 19);
+        assert_eq!(payload, b"MZ appended payload");
+
+        // The archive can still be read before the trailing data.
+        assert!(!archive.entries().unwrap().is_empty());
+    }
+}
+
+#[test]
+fn block_iterator_trailing_data() {
+    let (data, archive_size) = with_trailing_data("rar15/stored.rar", &[0; 100]);
+    let mut blocks =
+        rar15::BlockIterator::new(io::Cursor::new(data), Signature::Rar15.size()).unwrap();
+    assert_eq!(blocks.trailing_data(), None);
+
+    for block in blocks.by_ref() {
+        block.unwrap();
+    }
+    assert_eq!(
+        blocks.trailing_data(),
+        Some(archive_size..archive_size + 100)
+    );
+
+    let reader = io::BufReader::new(fs::File::open("tests/fixtures/rar50/quick_open.rar").unwrap());
+    let mut blocks = rar50::BlockIterator::new(reader, Signature::Rar50.size()).unwrap();
+    for block in blocks.by_ref() {
+        block.unwrap();
+    }
+    assert!(blocks.trailing_data().unwrap().is_empty());
+}
+
+#[test]
+fn truncated_archive() {
+    let mut data = fs::read("tests/fixtures/rar15/stored.rar").unwrap();
+    // Remove the end of archive block.
+    data.truncate(data.len() - 7);
+
+    let mut archive = Archive::new(io::Cursor::new(data)).unwrap();
+    assert_eq!(archive.trailing_data().unwrap(), None);
+
+    let mut blocks = archive.blocks().unwrap();
+    for block in blocks.by_ref() {
+        block.unwrap();
+    }
+    assert_eq!(blocks.trailing_data(), None);
+}
@@ -0,0 +1,63 @@
+use std::io::{self, Read, Seek};
+
+use rstest::rstest;
+
+use super::archive;
+
+fn read_n(reader: &mut impl Read, n: usize) -> Vec<u8> {
+    let mut data = vec![];
+    reader.take(n as u64).read_to_end(&mut data).unwrap();
+    data
+}
+
+#[rstest]
+#[case::rar14_stored("rar14/stored.rar", 1)]
+#[case::rar15_stored("rar15/stored.rar", 2)]
+#[case::rar15_compressed("rar15/unpack_versions.rar", 3)]
+#[case::rar50_compressed("rar50/recovery_record_and_quick_open.rar", 5)]
+fn seek_in_entry(#[case] path: &str, #[case] index: usize) {
+    let mut archive = archive(path);
+    let mut reader = archive.open_entry(index).unwrap().unwrap();
+
+    let mut data = vec![];
+    reader.read_to_end(&mut data).unwrap();
+    assert_eq!(Some(data.len() as u64), reader.unpacked_size());
+    assert!(data.len() > 12);
+    let middle = data.len() / 2;
+
+    // Backward.
+    assert_eq!(reader.seek(io::SeekFrom::Start(2)).unwrap(), 2);
+    assert_eq!(read_n(&mut reader, 4), data[2..6]);
+
+    // Forward.
+    reader
+        .seek(io::SeekFrom::Current(middle as i64 - 6))
+        .unwrap();
+    assert_eq!(reader.stream_position().unwrap(), middle as u64);
+    assert_eq!(read_n(&mut reader, 4), data[middle..middle + 4]);
+
+    reader.seek(io::SeekFrom::End(-5)).unwrap();
+    assert_eq!(read_n(&mut reader, 16), data[data.len() - 5..]);
+
+    // Past the end.
+    reader.seek(io::SeekFrom::End(10)).unwrap();
+    assert_eq!(read_n(&mut reader, 16), []);
+    assert!(reader.seek(io::SeekFrom::Current(-100_000_000)).is_err());
+}
+
+#[test]
+fn open_missing_entry() {
+    assert!(archive("rar15/stored.rar")
+        .open_entry(100)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn open_unsupported_entry() {
+    let err = archive("rar50/encrypted_files.rar")
+        .open_entry(0)
+        .err()
+        .unwrap();
+    assert!(matches!(err, rawrxd::Error::Io(e) if e.kind() == io::ErrorKind::Unsupported));
+}
@@ -0,0 +1,94 @@
+use std::{fs, io};
+
+use rawrxd::{
+    compat::{Archive, HashType, VerifyStatus},
+    Error, LimitKind, Progress,
+};
+
+use super::archive;
+
+#[test]
+fn test_archive() {
+    let report = archive("rar15/unpack_versions.rar").test().unwrap();
+    assert!(report.is_ok());
+
+    let statuses: Vec<_> = report.entries.iter().map(|r| &r.status).collect();
+    assert_eq!(statuses, [&VerifyStatus::Ok; 5]);
+    assert_eq!(report.entries[0].unpacked, 4097);
+
+    let report = archive("rar50/blake2sp_hash.rar").test().unwrap();
+    assert!(report.is_ok());
+    assert!(report.entries.iter().all(|r| r.status == VerifyStatus::Ok));
+}
+
+#[test]
+fn test_archive_progress() {
+    let total = Progress::new();
+    let report = archive("rar15/unpack_versions.rar")
+        .test_with_progress(&total)
+        .unwrap();
+    assert!(report.is_ok());
+
+    for entry in &report.entries {
+        assert_eq!(entry.progress.unpacked_bytes(), entry.unpacked);
+        assert_eq!(entry.progress.packed_bytes(), entry.entry.packed_size);
+    }
+
+    let unpacked: u64 = report.entries.iter().map(|r| r.unpacked).sum();
+    let packed: u64 = report.entries.iter().map(|r| r.entry.packed_size).sum();
+    assert_eq!(total.unpacked_bytes(), unpacked);
+    assert_eq!(total.packed_bytes(), packed);
+}
+
+#[test]
+fn test_archive_not_extractable() {
+    let report = archive("rar14/stored.rar").test().unwrap();
+    assert!(report.is_ok());
+    assert_eq!(report.entries[1].status, VerifyStatus::Unverified);
+
+    let report = archive("rar50/encrypted_files.rar").test().unwrap();
+    assert!(report.is_ok());
+    assert!(report
+        .entries
+        .iter()
+        .filter(|r| r.entry.is_encrypted)
+        .all(|r| r.status == VerifyStatus::NotExtractable));
+
+    // The window of the dictionary of 64 GiB is larger than the default limit.
+    let mut archive = archive("rar50/huge_dictionary.rar");
+    let report = archive.test().unwrap();
+    assert_eq!(report.entries[0].status, VerifyStatus::NotExtractable);
+    assert!(matches!(
+        archive.open_entry(0).map(drop),
+        Err(Error::LimitExceeded {
+            kind: LimitKind::WindowSize,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn verify_hash_mismatch() {
+    let mut data = fs::read("tests/fixtures/rar15/stored.rar").unwrap();
+    let offset = data
+        .windows(13)
+        .position(|window| window == b"Hello, world!")
+        .unwrap();
+    data[offset] = b'J';
+
+    let mut archive = Archive::new(io::Cursor::new(data)).unwrap();
+
+    let report = archive.verify(2).unwrap().unwrap();
+    assert_eq!(report.entry.name.as_deref(), Ok("hello.txt"));
+    assert_eq!(report.unpacked, 14);
+    assert!(matches!(
+        report.status,
+        VerifyStatus::HashMismatch {
+            hash: HashType::Crc32,
+            ..
+        }
+    ));
+
+    assert!(archive.verify(3).unwrap().is_none());
+    assert!(!archive.test().unwrap().is_ok());
+}
@@ -0,0 +1,84 @@
+use rawrxd::compat::VersionSelection;
+use rstest::rstest;
+
+use super::archive;
+
+#[rstest]
+#[case("rar15/versions.rar")]
+#[case("rar50/versions.rar")]
+fn entry_version(#[case] path: &str) {
+    let entries = archive(path).entries().unwrap();
+
+    let names: Vec<_> = entries
+        .iter()
+        .map(|entry| (entry.name_lossy().into_owned(), entry.version))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            ("notes.txt".to_string(), Some(1)),
+            ("other.txt".to_string(), None),
+            ("notes.txt".to_string(), Some(2)),
+            ("notes.txt".to_string(), None),
+        ]
+    );
+
+    assert_eq!(entries[0].sanitized_path().to_str(), Some("notes.txt;1"));
+    assert_eq!(entries[3].sanitized_path().to_str(), Some("notes.txt"));
+}
+
+#[rstest]
+#[case("rar15/versions.rar")]
+#[case("rar50/versions.rar")]
+fn all_versions(#[case] path: &str) {
+    let files = archive(path).entry_versions(VersionSelection::All).unwrap();
+
+    let versions: Vec<_> = files
+        .iter()
+        .map(|file| {
+            let versions: Vec<_> = file.versions.iter().map(|entry| entry.version).collect();
+            (file.name.clone().unwrap(), versions)
+        })
+        .collect();
+    assert_eq!(
+        versions,
+        [
+            ("notes.txt".to_string(), vec![None, Some(2), Some(1)]),
+            ("other.txt".to_string(), vec![None]),
+        ]
+    );
+    assert_eq!(files[0].latest().unpacked_size, Some(6));
+}
+
+#[rstest]
+#[case("rar15/versions.rar")]
+#[case("rar50/versions.rar")]
+fn latest_versions(#[case] path: &str) {
+    let mut archive = archive(path);
+    let files = archive.entry_versions(VersionSelection::Latest).unwrap();
+
+    assert_eq!(files.len(), 2);
+    for file in &files {
+        assert_eq!(file.versions.len(), 1);
+        assert_eq!(file.latest().version, None);
+    }
+
+    let mut data = String::new();
+    std::io::Read::read_to_string(
+        &mut files[0].latest().data_reader(archive.reader()).unwrap(),
+        &mut data,
+    )
+    .unwrap();
+    assert_eq!(data, "third\n");
+}
+
+#[test]
+fn unversioned_archive() {
+    let files = archive("rar50/quick_open.rar")
+        .entry_versions(VersionSelection::Latest)
+        .unwrap();
+    let entries = archive("rar50/quick_open.rar").entries().unwrap();
+
+    assert_eq!(files.len(), entries.len());
+    assert!(entries.iter().all(|entry| entry.version.is_none()));
+}
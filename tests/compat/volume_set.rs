@@ -0,0 +1,125 @@
+use std::{fs, path::PathBuf};
+
+use rawrxd::compat::VolumeSet;
+
+fn volume(n: usize) -> PathBuf {
+    PathBuf::from(format!("tests/fixtures/rar50/multivolume.part{n}.rar"))
+}
+
+/// Copy volume `n` to a temporary file and modify its contents with `f`.
+fn modified_volume(n: usize, name: &str, f: impl FnOnce(&mut Vec<u8>)) -> PathBuf {
+    let mut data = fs::read(volume(n)).unwrap();
+    f(&mut data);
+
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&path, data).unwrap();
+    path
+}
+
+fn spans(set: &VolumeSet) -> Vec<(&str, usize, usize)> {
+    set.entries()
+        .iter()
+        .map(|entry| {
+            (
+                entry.name.as_str(),
+                *entry.volumes.start(),
+                *entry.volumes.end(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn intact_volumes() {
+    let set = VolumeSet::check([volume(1), volume(2), volume(3)]);
+
+    assert!(set.is_ok());
+    for status in set.volumes() {
+        assert!(status.present && status.signature_ok && status.header_chain_ok);
+        assert_eq!(status.crc_sampled_ok, Some(true));
+    }
+
+    assert_eq!(
+        spans(&set),
+        [("hello.txt", 0, 0), ("data.bin", 0, 2), ("zzz.txt", 2, 2)]
+    );
+    assert!(set
+        .volumes_to_reacquire(["hello.txt", "data.bin", "zzz.txt"])
+        .is_empty());
+}
+
+#[test]
+fn missing_volume() {
+    let set = VolumeSet::check([volume(1), volume(4), volume(3)]);
+
+    assert!(!set.is_ok());
+    assert!(!set.volumes()[1].present);
+    assert_eq!(
+        spans(&set),
+        [("hello.txt", 0, 0), ("data.bin", 0, 2), ("zzz.txt", 2, 2)]
+    );
+
+    assert_eq!(set.volumes_to_reacquire(["data.bin"]), [1]);
+    assert!(set
+        .volumes_to_reacquire(["hello.txt", "zzz.txt"])
+        .is_empty());
+}
+
+#[test]
+fn missing_first_volume() {
+    let set = VolumeSet::check([volume(4), volume(2), volume(3)]);
+
+    // The entry continues from the missing volume.
+    assert_eq!(spans(&set), [("data.bin", 0, 2), ("zzz.txt", 2, 2)]);
+    assert_eq!(set.volumes_to_reacquire(["data.bin", "zzz.txt"]), [0]);
+    assert_eq!(set.volumes_needed("hello.txt"), None);
+}
+
+#[test]
+fn corrupt_data() {
+    let corrupt = modified_volume(2, "multivolume_corrupt_data.part2.rar", |data| {
+        data[1000] ^= 0xff;
+    });
+    let set = VolumeSet::check([volume(1), corrupt, volume(3)]);
+
+    let status = &set.volumes()[1];
+    assert!(status.header_chain_ok);
+    assert_eq!(status.crc_sampled_ok, Some(false));
+
+    assert_eq!(set.volumes_to_reacquire(["data.bin", "zzz.txt"]), [1]);
+}
+
+#[test]
+fn truncated_volume() {
+    let truncated = modified_volume(3, "multivolume_truncated.part3.rar", |data| {
+        data.truncate(1000);
+    });
+    let set = VolumeSet::check([volume(1), volume(2), truncated]);
+
+    let status = &set.volumes()[2];
+    assert!(status.signature_ok);
+    assert!(!status.header_chain_ok);
+
+    // The last entry is lost with the truncated volume.
+    assert_eq!(spans(&set), [("hello.txt", 0, 0), ("data.bin", 0, 2)]);
+    assert_eq!(set.volumes_to_reacquire(["hello.txt", "data.bin"]), [2]);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn check_parallel() {
+    use std::num::NonZeroUsize;
+
+    let corrupt = modified_volume(2, "multivolume_parallel.part2.rar", |data| {
+        let len = data.len();
+        data[len / 2] ^= 0xff;
+    });
+    let paths = [volume(1), corrupt, volume(4), volume(3)];
+    let serial = VolumeSet::check(paths.clone());
+
+    for threads in [1, 2, 8] {
+        let set = VolumeSet::check_parallel(paths.clone(), NonZeroUsize::new(threads).unwrap());
+        assert_eq!(set.volumes(), serial.volumes());
+        assert_eq!(set.entries(), serial.entries());
+    }
+}
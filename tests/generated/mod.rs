@@ -0,0 +1,117 @@
+//! Checks the archives generated by `cargo xtask fixtures` against their manifest.
+//!
+//! The fixtures in the repository were generated by RAR 7.01. Regenerating them needs a
+//! locally-installed `rar` binary, and the tests fail if they are missing instead of
+//! passing without checking anything.
+
+use std::{collections::HashMap, fs, io};
+
+use rawrxd::compat::{Archive, HashKind, VerifyStatus};
+
+const FIXTURES: &str = "tests/fixtures/generated";
+
+struct Fixture {
+    path: String,
+    feature: String,
+    entries: HashMap<String, (u64, u32)>,
+}
+
+fn manifest() -> Vec<Fixture> {
+    let manifest = fs::read_to_string(format!("{FIXTURES}/manifest.txt"))
+        .unwrap_or_else(|e| panic!("{FIXTURES}/manifest.txt: {e}, run `cargo xtask fixtures`"));
+
+    let mut fixtures: Vec<Fixture> = vec![];
+    for line in manifest.lines().filter(|line| !line.starts_with('#')) {
+        let fields: Vec<_> = line.split('\t').collect();
+        match fields[..] {
+            ["archive", path, _format, feature, _password] => fixtures.push(Fixture {
+                path: path.to_string(),
+                feature: feature.to_string(),
+                entries: HashMap::new(),
+            }),
+            ["entry", name, size, crc] => {
+                let entry = (size.parse().unwrap(), u32::from_str_radix(crc, 16).unwrap());
+                let fixture = fixtures.last_mut().expect("entry before any archive");
+                fixture.entries.insert(name.to_string(), entry);
+            }
+            _ => panic!("invalid manifest line {line:?}"),
+        }
+    }
+
+    fixtures
+}
+
+#[test]
+fn generated_fixtures_match_manifest() {
+    for fixture in manifest() {
+        // The names of the entries are encrypted too.
+        if fixture.feature == "encrypted_headers" {
+            continue;
+        }
+
+        let path = format!("{FIXTURES}/{}", fixture.path);
+        let reader = io::BufReader::new(fs::File::open(&path).unwrap());
+        let entries = Archive::new(reader).unwrap().entries().unwrap();
+
+        let mut files = 0;
+        for entry in entries.iter().filter(|entry| !entry.is_directory) {
+            files += 1;
+
+            let name = entry.name_lossy().replace('\\', "/");
+            let &(size, crc) = fixture
+                .entries
+                .get(&name)
+                .unwrap_or_else(|| panic!("{path}: unexpected entry {name:?}"));
+
+            assert_eq!(entry.is_encrypted, fixture.feature == "encrypted", "{path}");
+            if entry.split_before || entry.split_after {
+                continue;
+            }
+
+            assert_eq!(entry.unpacked_size, Some(size), "{path}: {name}");
+            if !entry.is_encrypted {
+                assert_eq!(entry.checksum, Some(HashKind::Crc32(crc)), "{path}: {name}");
+            }
+        }
+
+        // Only the first volume of multi-volume archives is listed.
+        if fixture.feature != "multivolume" {
+            assert_eq!(files, fixture.entries.len(), "{path}");
+        }
+    }
+}
+
+#[test]
+fn generated_fixtures_decode() {
+    for fixture in manifest() {
+        if !matches!(
+            fixture.feature.as_str(),
+            "plain" | "unicode_names" | "large_dictionary"
+        ) {
+            continue;
+        }
+
+        let path = format!("{FIXTURES}/{}", fixture.path);
+        let reader = io::BufReader::new(fs::File::open(&path).unwrap());
+        let report = Archive::new(reader).unwrap().test().unwrap();
+
+        for entry in report.entries.iter().filter(|r| !r.entry.is_directory) {
+            assert_eq!(entry.status, VerifyStatus::Ok, "{path}: {:?}", entry.entry);
+        }
+    }
+}
+
+#[test]
+fn large_dictionary_is_reduced() {
+    let fixture = manifest()
+        .into_iter()
+        .find(|fixture| fixture.feature == "large_dictionary")
+        .expect("large_dictionary fixture");
+
+    // RAR 7 reduces the 64 GiB dictionary to fit the files, which are small enough for
+    // the RAR 5 compression algorithm and its smallest dictionary.
+    let path = format!("{FIXTURES}/{}", fixture.path);
+    let reader = io::BufReader::new(fs::File::open(&path).unwrap());
+    let features = Archive::new(reader).unwrap().format_features().unwrap();
+    assert_eq!(features.max_dictionary_size, Some(128 << 10));
+}
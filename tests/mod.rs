@@ -1,3 +1,5 @@
 mod common;
+mod compat;
+mod generated;
 mod rar15;
 mod rar50;
@@ -0,0 +1,29 @@
+use std::fs;
+
+use futures::{executor::block_on, io::AllowStdIo};
+use rawrxd::{rar15, Signature};
+
+use super::block_iterator;
+
+#[test]
+fn same_blocks_as_sync_iterator() {
+    for file_name in ["file_comment.rar", "mac_info.rar", "unpack_versions.rar"] {
+        let expected: Vec<_> = block_iterator(file_name)
+            .map(|block| format!("{:?}", block.unwrap()))
+            .collect();
+
+        let reader =
+            AllowStdIo::new(fs::File::open(format!("tests/fixtures/rar15/{file_name}")).unwrap());
+        let mut iter = block_on(rar15::AsyncBlockIterator::new(
+            reader,
+            Signature::Rar15.size(),
+        ))
+        .unwrap();
+        let mut blocks = vec![];
+        while let Some(block) = block_on(iter.next()) {
+            blocks.push(format!("{:?}", block.unwrap()));
+        }
+
+        assert_eq!(blocks, expected, "{file_name}");
+    }
+}
@@ -0,0 +1,52 @@
+use rawrxd::{
+    rar15::{self, BlockKind},
+    RawHeaderReader,
+};
+use time::macros::datetime;
+
+use super::block_iterator;
+
+fn blocks() -> Vec<rar15::Block> {
+    block_iterator("authenticity.rar")
+        .map(Result::unwrap)
+        .collect()
+}
+
+#[test]
+fn av_block() {
+    let blocks = blocks();
+    let BlockKind::Av(av) = &blocks[1].kind else {
+        panic!("{:?}", blocks[1]);
+    };
+
+    assert_eq!(av.unpack_version, 20);
+    assert_eq!(av.av_info, (0x40..0x60).collect::<Vec<u8>>());
+}
+
+#[test]
+fn sign_block() {
+    let blocks = blocks();
+    let BlockKind::Sign(sign) = &blocks[2].kind else {
+        panic!("{:?}", blocks[2]);
+    };
+
+    assert_eq!(sign.creation_time, Ok(datetime!(2001-02-03 04:05:06)));
+    assert_eq!(sign.archive_name.as_deref(), Some(&b"provenance.rar"[..]));
+    assert_eq!(sign.user_name.as_deref(), Some(&b"Example User"[..]));
+}
+
+#[test]
+fn headers_are_read_completely() {
+    for result in block_iterator("authenticity.rar").with_raw_headers() {
+        let (block, header) = result.unwrap();
+        let fields = rar15::header_layout(&header, block.offset);
+        assert!(
+            fields.iter().all(|field| field.name != "unknown"),
+            "{fields:?}"
+        );
+    }
+
+    let mut iter = block_iterator("authenticity.rar");
+    assert_eq!(iter.by_ref().count(), 4);
+    assert_eq!(iter.warnings(), []);
+}
@@ -1,6 +1,8 @@
+#![allow(clippy::manual_ok_err)]
+
 use crate::rar15::block_iterator;
 
-use rawrxd::Error;
+use rawrxd::{BlockSize, Error, Signature};
 use rstest::rstest;
 
 /// Archive whose header_size is set to 0, or whose offset + header_size or offset + size
@@ -21,5 +23,30 @@ fn rar15_corrupt_header(#[case] name: &str) {
         Err(e) => Some(e),
     });
 
-    assert!(matches!(err, Some(Error::CorruptHeader)));
+    assert!(matches!(
+        err.as_ref().map(Error::root),
+        Some(Error::CorruptHeader)
+    ));
+}
+
+#[test]
+fn corrupt_header_location() {
+    let mut iter = block_iterator("corrupt_header_1.rar");
+
+    let mut next_offset = Signature::Rar15.size();
+    let err = loop {
+        match iter.next().unwrap() {
+            Ok(block) => next_offset = block.offset() + block.size(),
+            Err(e) => break e,
+        }
+    };
+
+    assert_eq!(err.offset(), Some(next_offset));
+    let Error::Block { block_type, .. } = &err else {
+        panic!("expected the location of the error, got {err:?}");
+    };
+    assert!(block_type.is_some());
+    assert!(err
+        .to_string()
+        .starts_with(&format!("block at offset {next_offset} of type")));
 }
@@ -0,0 +1,55 @@
+use std::{fs, io};
+
+use rawrxd::{
+    rar15::{self, ParseOptions},
+    Divergence, Signature, Warning, WarningKind,
+};
+
+fn cross_check(file_name: &str) -> Vec<Warning> {
+    let reader =
+        io::BufReader::new(fs::File::open(format!("tests/fixtures/rar15/{file_name}")).unwrap());
+    let options = ParseOptions {
+        cross_check: true,
+        ..Default::default()
+    };
+    let mut iter =
+        rar15::BlockIterator::with_options(reader, Signature::Rar15.size(), options).unwrap();
+    while let Some(Ok(_)) = iter.next() {}
+
+    iter.take_warnings()
+}
+
+#[test]
+fn well_formed_archive_agrees() {
+    assert_eq!(cross_check("stored.rar"), []);
+    assert_eq!(cross_check("file_comment.rar"), []);
+}
+
+#[test]
+fn checksum_mismatch() {
+    let warnings = cross_check("corrupt_header_4.rar");
+
+    // The corrupt header size also leaves bytes after the fields of the header.
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(
+        warnings[0].kind,
+        WarningKind::UnknownHeaderBytes {
+            parsed_end: 20,
+            declared_end: 56
+        }
+    );
+    assert_eq!(warnings[1].offset, 7);
+    assert!(matches!(
+        warnings[1].kind,
+        WarningKind::ParserDivergence(Divergence::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn header_overrun_is_truncated() {
+    let warnings = cross_check("corrupt_header_2.rar");
+
+    assert!(warnings
+        .iter()
+        .any(|warning| warning.kind == WarningKind::ParserDivergence(Divergence::Truncated)));
+}
@@ -0,0 +1,250 @@
+use std::{
+    fs,
+    io::{self, Read, Seek},
+};
+
+use rawrxd::{
+    rar15::{BlockKind, Decoder, FileBlock, SubBlock, SubBlockKind},
+    BlockSize,
+};
+
+use super::block_iterator;
+
+fn files(file_name: &str) -> Vec<(u64, u64, FileBlock)> {
+    block_iterator(file_name)
+        .filter_map(|block| {
+            let block = block.unwrap();
+            let data_offset = block.offset() + block.header_size();
+            let data_size = block.data_size();
+            match block.kind {
+                BlockKind::File(file) => Some((data_offset, data_size, file)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn decompress(
+    file_name: &str,
+    data_offset: u64,
+    data_size: u64,
+    file: &FileBlock,
+) -> io::Result<Vec<u8>> {
+    let mut reader = fs::File::open(format!("tests/fixtures/rar15/{file_name}")).unwrap();
+    reader.seek(io::SeekFrom::Start(data_offset)).unwrap();
+
+    let mut data = vec![];
+    Decoder::new(io::BufReader::new(reader).take(data_size), file)?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+#[test]
+fn decompress_all_versions() {
+    let files = files("unpack_versions.rar");
+    let versions: Vec<_> = files
+        .iter()
+        .map(|(_, _, file)| file.unpack_version)
+        .collect();
+    assert_eq!(versions, [15, 20, 26, 29, 29]);
+
+    for (data_offset, data_size, file) in &files {
+        let data = decompress("unpack_versions.rar", *data_offset, *data_size, file)
+            .unwrap_or_else(|e| panic!("{:?}: {e}", file.file_name));
+        assert_eq!(
+            data.len() as u64,
+            file.unpacked_data_size,
+            "{:?}",
+            file.file_name
+        );
+    }
+}
+
+#[test]
+fn decompress_stored() {
+    let (data_offset, data_size, file) = files("stored.rar").into_iter().next().unwrap();
+    let data = decompress("stored.rar", data_offset, data_size, &file).unwrap();
+    assert_eq!(data.len() as u64, file.unpacked_data_size);
+}
+
+#[test]
+fn crc32_mismatch() {
+    let (data_offset, data_size, mut file) = files("unpack_versions.rar")
+        .into_iter()
+        .find(|(_, _, file)| file.file_name.as_bytes() == b"unpack29_ppmd.txt")
+        .unwrap();
+
+    file.file_crc32 = !file.file_crc32;
+    let error = decompress("unpack_versions.rar", data_offset, data_size, &file).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn truncated_data() {
+    let (data_offset, data_size, file) = files("unpack_versions.rar")
+        .into_iter()
+        .find(|(_, _, file)| file.file_name.as_bytes() == b"unpack29.bin")
+        .unwrap();
+
+    let error = decompress("unpack_versions.rar", data_offset, data_size / 2, &file).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn whole_stream_is_consumed() {
+    // The packed streams have no padding, so every byte is needed to decompress them.
+    // Without the last one the decoder either runs out of data or reads zero bits in its
+    // place, which changes the data and fails the CRC32 check.
+    for (data_offset, data_size, file) in files("unpack_versions.rar") {
+        let error = decompress("unpack_versions.rar", data_offset, data_size - 1, &file)
+            .expect_err(&format!("{:?}", file.file_name));
+        assert!(
+            matches!(
+                error.kind(),
+                io::ErrorKind::UnexpectedEof | io::ErrorKind::InvalidData
+            ),
+            "{:?}: {error}",
+            file.file_name
+        );
+    }
+}
+
+#[test]
+fn skip_keeps_crc32_check() {
+    for (data_offset, data_size, mut file) in files("unpack_versions.rar") {
+        let data = decompress("unpack_versions.rar", data_offset, data_size, &file).unwrap();
+
+        let mut reader = fs::File::open("tests/fixtures/rar15/unpack_versions.rar").unwrap();
+        reader.seek(io::SeekFrom::Start(data_offset)).unwrap();
+        let mut decoder = Decoder::new(io::BufReader::new(reader).take(data_size), &file).unwrap();
+
+        assert_eq!(decoder.skip(1000).unwrap(), 1000);
+        let mut rest = vec![];
+        decoder.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, data[1000..]);
+
+        file.file_crc32 = !file.file_crc32;
+        let mut reader = fs::File::open("tests/fixtures/rar15/unpack_versions.rar").unwrap();
+        reader.seek(io::SeekFrom::Start(data_offset)).unwrap();
+        let mut decoder = Decoder::new(io::BufReader::new(reader).take(data_size), &file).unwrap();
+
+        assert_eq!(
+            decoder.skip(u64::MAX).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
+
+#[test]
+fn seek_over_stored_data() {
+    let (data_offset, _, mut file) = files("stored.rar")
+        .into_iter()
+        .find(|(_, _, file)| file.file_name.as_bytes() == b"hello.txt")
+        .unwrap();
+
+    // The CRC32 can't be checked after seeking.
+    file.file_crc32 = !file.file_crc32;
+
+    let mut reader = fs::File::open("tests/fixtures/rar15/stored.rar").unwrap();
+    reader.seek(io::SeekFrom::Start(data_offset)).unwrap();
+    let mut decoder = Decoder::new(reader, &file).unwrap();
+
+    assert_eq!(decoder.seek_forward(7).unwrap(), 7);
+    let mut rest = String::new();
+    decoder.read_to_string(&mut rest).unwrap();
+    assert_eq!(rest, "world!\n");
+
+    assert_eq!(decoder.seek_forward(10).unwrap(), 0);
+}
+
+#[test]
+fn file_comments() {
+    let commented = files("file_comment.rar");
+    let comments: Vec<_> = commented
+        .iter()
+        .map(|(_, _, file)| file.comment.as_ref().unwrap())
+        .collect();
+
+    assert_eq!(comments[0].unpack().unwrap(), b"Stored file comment\r\n");
+
+    // The second comment was compressed like unpack20.bin.
+    let (data_offset, data_size, file) = files("unpack_versions.rar")
+        .into_iter()
+        .find(|(_, _, file)| file.file_name.as_bytes() == b"unpack20.bin")
+        .unwrap();
+    let data = decompress("unpack_versions.rar", data_offset, data_size, &file).unwrap();
+    assert_eq!(comments[1].header.unpack_version, 20);
+    assert_eq!(comments[1].unpack().unwrap(), data);
+
+    let (_, _, mut file) = commented.into_iter().nth(1).unwrap();
+    let comment = file.comment.as_mut().unwrap();
+    comment.header.crc16 = !comment.header.crc16;
+    assert_eq!(
+        comment.unpack().unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+}
+
+#[test]
+fn sub_block_data() {
+    let mut sub_blocks = vec![];
+    for block in block_iterator("sub_blocks.rar") {
+        let block = block.unwrap();
+        let data_offset = block.offset() + block.header_size();
+        if let BlockKind::Sub(sub_block) = block.kind {
+            sub_blocks.push((data_offset, sub_block));
+        }
+    }
+
+    let unpack = |data_offset: u64, sub_block: &SubBlock| -> io::Result<Vec<u8>> {
+        let mut reader = fs::File::open("tests/fixtures/rar15/sub_blocks.rar").unwrap();
+        reader.seek(io::SeekFrom::Start(data_offset)).unwrap();
+
+        let mut data = vec![];
+        Decoder::for_sub_block(reader.take(sub_block.data_size as u64), sub_block)?
+            .read_to_end(&mut data)?;
+        Ok(data)
+    };
+
+    // The stream was compressed like unpack20.bin.
+    let (data_offset, data_size, file) = files("unpack_versions.rar")
+        .into_iter()
+        .find(|(_, _, file)| file.file_name.as_bytes() == b"unpack20.bin")
+        .unwrap();
+    let expected = decompress("unpack_versions.rar", data_offset, data_size, &file).unwrap();
+    let (data_offset, stream) = &sub_blocks[0];
+    assert_eq!(unpack(*data_offset, stream).unwrap(), expected);
+
+    let (data_offset, acl) = &sub_blocks[1];
+    assert_eq!(unpack(*data_offset, acl).unwrap().len(), 100);
+
+    let (data_offset, owner) = &sub_blocks[2];
+    assert_eq!(
+        unpack(*data_offset, owner).unwrap_err().kind(),
+        io::ErrorKind::Unsupported
+    );
+
+    let (data_offset, mut stream) = sub_blocks.into_iter().next().unwrap();
+    if let SubBlockKind::NtfsStream(stream) = &mut stream.kind {
+        stream.stream_crc32 = !stream.stream_crc32;
+    }
+    assert_eq!(
+        unpack(data_offset, &stream).unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+}
+
+#[test]
+fn continue_solid_needs_solid_file() {
+    let files = files("unpack_versions.rar");
+    let (data_offset, data_size, first) = &files[0];
+
+    let mut reader = fs::File::open("tests/fixtures/rar15/unpack_versions.rar").unwrap();
+    reader.seek(io::SeekFrom::Start(*data_offset)).unwrap();
+    let mut decoder = Decoder::new(io::BufReader::new(reader).take(*data_size), first).unwrap();
+
+    // The rest of the current file is still decoded and checked.
+    let (_, _, second) = &files[1];
+    let error = decoder.continue_solid(second, |_| Ok(())).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(decoder.read(&mut [0; 16]).unwrap(), 0);
+}
@@ -0,0 +1,101 @@
+use std::{
+    fs, io,
+    sync::{Arc, Mutex},
+};
+
+use rawrxd::{
+    rar15::{self, BlockKind},
+    BlockSize, Error, PasswordRequest, Signature,
+};
+
+use super::block_iterator;
+
+fn with_password(password: &str) -> rar15::BlockIterator<io::BufReader<fs::File>> {
+    let reader =
+        io::BufReader::new(fs::File::open("tests/fixtures/rar15/encrypted_headers.rar").unwrap());
+    rar15::BlockIterator::with_password(reader, Signature::Rar15.size(), password).unwrap()
+}
+
+#[test]
+fn list_encrypted_headers() {
+    let mut iter = with_password("password");
+    let blocks: Vec<_> = iter.by_ref().map(Result::unwrap).collect();
+
+    assert!(matches!(&blocks[0].kind, BlockKind::Main(main) if main.has_password()));
+    assert!(matches!(
+        blocks.last().unwrap().kind,
+        BlockKind::EndArchive(_)
+    ));
+
+    let files: Vec<_> = blocks
+        .iter()
+        .filter_map(|block| match &block.kind {
+            BlockKind::File(file) => Some((
+                String::from_utf8_lossy(file.file_name.as_bytes()).into_owned(),
+                file.unpacked_data_size,
+                block.data_size(),
+            )),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        files,
+        [
+            ("hello.txt".to_string(), 14, 16),
+            ("docs\\readme.txt".to_string(), 970, 976),
+        ]
+    );
+
+    // The header size includes the salt and the padding.
+    let hello = &blocks[1];
+    assert!(hello.header_salt.is_some());
+    assert_eq!(hello.header_size(), 8 + 64);
+    assert_eq!(hello.offset() + hello.size(), blocks[2].offset());
+}
+
+#[test]
+fn incorrect_password() {
+    let mut iter = with_password("wrong");
+
+    assert!(matches!(iter.next(), Some(Ok(_))));
+    assert!(matches!(iter.next(), Some(Err(Error::IncorrectPassword))));
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn password_required() {
+    let mut iter = block_iterator("encrypted_headers.rar");
+
+    assert!(matches!(
+        iter.next(),
+        Some(Ok(rar15::Block {
+            kind: BlockKind::Main(_),
+            ..
+        }))
+    ));
+    assert!(matches!(iter.next(), Some(Err(Error::PasswordRequired))));
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn credentials_asked_once() {
+    let requests = Arc::new(Mutex::new(vec![]));
+    let provider = {
+        let requests = requests.clone();
+        move |request: PasswordRequest| {
+            if let PasswordRequest::Headers { offset } = request {
+                requests.lock().unwrap().push(offset);
+            }
+            Some("password".to_string())
+        }
+    };
+    let reader =
+        io::BufReader::new(fs::File::open("tests/fixtures/rar15/encrypted_headers.rar").unwrap());
+    let iter =
+        rar15::BlockIterator::with_credentials(reader, Signature::Rar15.size(), provider).unwrap();
+    let blocks: Vec<_> = iter.map(Result::unwrap).collect();
+
+    // Each header has its own salt, but the password is only asked for the first one.
+    assert!(blocks[2].header_salt.is_some());
+    assert_eq!(*requests.lock().unwrap(), [blocks[1].offset()]);
+}
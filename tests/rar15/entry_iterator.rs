@@ -0,0 +1,66 @@
+use rawrxd::rar15::{BlockKind, EntryIterator, ServiceBlockKind, SubBlockKind};
+
+use super::block_iterator;
+
+#[test]
+fn child_service_blocks() {
+    let entries: Vec<_> = EntryIterator::new(block_iterator("ntfs_acl.rar"))
+        .map(Result::unwrap)
+        .collect();
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].file().file_name.as_bytes(), b"file.txt");
+    assert_eq!(entries[1].file().file_name.as_bytes(), b"other.txt");
+
+    let services: Vec<_> = entries[0].services().collect();
+    assert_eq!(services.len(), 1);
+    assert!(matches!(
+        services[0].kind,
+        ServiceBlockKind::NtfsFilePermissions
+    ));
+    assert!(entries[1].children.is_empty());
+}
+
+#[test]
+fn child_sub_blocks() {
+    let entries: Vec<_> = EntryIterator::new(block_iterator("mac_info.rar"))
+        .map(Result::unwrap)
+        .collect();
+
+    let children = &entries[0].children;
+    assert_eq!(children.len(), 1);
+    assert!(matches!(
+        &children[0].kind,
+        BlockKind::Sub(sub) if matches!(sub.kind, SubBlockKind::MacOsInfo(_))
+    ));
+}
+
+#[test]
+fn service_sub_data() {
+    let entries: Vec<_> = EntryIterator::new(block_iterator("service_blocks.rar"))
+        .map(Result::unwrap)
+        .collect();
+
+    let services: Vec<_> = entries[0].services().collect();
+    assert_eq!(services.len(), 2);
+
+    let ServiceBlockKind::UnixOwner(owner) = &services[0].kind else {
+        panic!("expected a Unix owner block, got {:?}", services[0].kind);
+    };
+    assert_eq!(owner.user, b"user");
+    assert_eq!(owner.group, b"staff");
+
+    let ServiceBlockKind::NtfsAlternateDataStream(stream) = &services[1].kind else {
+        panic!("expected a stream block, got {:?}", services[1].kind);
+    };
+    let name: Vec<u16> = ":n\u{f6}tes".encode_utf16().collect();
+    assert_eq!(stream.stream_name, name);
+
+    // The group of the second owner is not terminated by a NUL.
+    let services: Vec<_> = entries[1].services().collect();
+    let ServiceBlockKind::UnixOwner(owner) = &services[0].kind else {
+        panic!("expected a Unix owner block, got {:?}", services[0].kind);
+    };
+    assert_eq!(owner.user, b"root");
+    assert_eq!(owner.group, b"wheel");
+}
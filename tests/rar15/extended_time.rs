@@ -0,0 +1,52 @@
+use rawrxd::rar15::{BlockKind, FileBlock};
+use time::macros::datetime;
+
+use super::block_iterator;
+
+fn files() -> Vec<FileBlock> {
+    let mut iter = block_iterator("extended_time.rar");
+    let files = iter
+        .by_ref()
+        .filter_map(|block| match block.unwrap().kind {
+            BlockKind::File(file) => Some(file),
+            _ => None,
+        })
+        .collect();
+
+    // The extended time never extends past the end of the header.
+    assert_eq!(iter.warnings(), []);
+    files
+}
+
+#[test]
+fn extended_time() {
+    let files = files();
+    let times = &files[0];
+
+    assert_eq!(
+        times.modification_time,
+        Ok(datetime!(2024-05-17 12:30:10.1193046))
+    );
+    assert_eq!(
+        times.creation_time,
+        Some(Ok(datetime!(2023-01-02 03:04:07.119296)))
+    );
+    assert_eq!(times.access_time, Some(Ok(datetime!(2024-06-01 08:00:00))));
+    assert_eq!(times.archive_time, None);
+}
+
+#[test]
+fn truncated_extended_time() {
+    // The flags of the first file declare a creation time but its header ends right after
+    // them, and the header of the second file ends before the flags.
+    for file in &files()[1..] {
+        assert_eq!(
+            file.modification_time,
+            Ok(datetime!(2024-05-17 12:30:10)),
+            "{:?}",
+            file.file_name
+        );
+        assert_eq!(file.creation_time, None);
+        assert_eq!(file.access_time, None);
+    }
+}
@@ -0,0 +1,42 @@
+use rawrxd::rar15::{BlockKind, FileBlock};
+
+use super::block_iterator;
+
+fn files(file_name: &str) -> Vec<FileBlock> {
+    block_iterator(file_name)
+        .filter_map(|block| match block.unwrap().kind {
+            BlockKind::File(file) => Some(file),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Volume in the middle of an archive, starting with the end of a file and ending with the
+/// start of another one.
+#[test]
+fn split_and_comment_flags() {
+    let files = files("file_flags.rar");
+    let flags: Vec<_> = files
+        .iter()
+        .map(|file| {
+            (
+                file.flags.split_before(),
+                file.flags.split_after(),
+                file.flags.has_comment(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        flags,
+        [
+            (true, false, false),
+            (false, false, true),
+            (false, true, false)
+        ]
+    );
+
+    assert!(files
+        .iter()
+        .all(|file| !file.flags.is_encrypted() && !file.flags.is_solid()));
+}
@@ -0,0 +1,89 @@
+use rawrxd::{hash::Crc16, rar15, BlockSize, RawHeaderReader};
+use rstest::rstest;
+
+use super::block_iterator;
+
+#[rstest]
+#[case("stored.rar")]
+#[case("extended_time.rar")]
+#[case("file_comment.rar")]
+#[case("service_blocks.rar")]
+#[case("sub_blocks.rar")]
+fn fields_cover_header(#[case] file_name: &str) {
+    for result in block_iterator(file_name).with_raw_headers() {
+        let (block, header) = result.unwrap();
+        let fields = rar15::header_layout(&header, block.offset());
+
+        let mut offset = block.offset();
+        for field in &fields {
+            assert_eq!(field.offset, offset, "{field:?}");
+            assert_ne!(field.name, "unknown", "{field:?}");
+            offset += field.size;
+        }
+        assert_eq!(offset, block.offset() + block.header_size());
+    }
+}
+
+#[test]
+fn file_block_fields() {
+    let (block, header) = block_iterator("stored.rar")
+        .with_raw_headers()
+        .map(Result::unwrap)
+        .find(|(block, _)| matches!(block.kind, rar15::BlockKind::File(_)))
+        .unwrap();
+
+    let fields = rar15::header_layout(&header, block.offset());
+    let names: Vec<_> = fields.iter().map(|field| field.name).collect();
+    assert_eq!(
+        names,
+        [
+            "header crc16",
+            "header type",
+            "flags",
+            "header size",
+            "low packed data size",
+            "low unpacked data size",
+            "host os",
+            "data crc32",
+            "modification time",
+            "unpack version",
+            "method",
+            "name size",
+            "attributes",
+            "name",
+        ]
+    );
+
+    let name = fields.last().unwrap();
+    assert_eq!(name.value, "docs");
+    assert_eq!(
+        name.offset + name.size,
+        block.offset() + block.header_size()
+    );
+    assert_eq!(fields[1].value, "0x74");
+}
+
+#[test]
+fn truncated_header() {
+    let (block, header) = block_iterator("stored.rar")
+        .with_raw_headers()
+        .map(Result::unwrap)
+        .find(|(block, _)| matches!(block.kind, rar15::BlockKind::File(_)))
+        .unwrap();
+
+    let fields = rar15::header_layout(&header[..11], block.offset());
+    assert_eq!(fields.len(), 5);
+    assert_eq!(fields[4].name, "low packed data size");
+    assert_eq!(fields[4].offset, block.offset() + 7);
+}
+
+#[test]
+fn raw_header_checksum() {
+    for result in block_iterator("stored.rar").with_raw_headers() {
+        let (block, header) = result.unwrap();
+
+        let mut crc = Crc16::new();
+        crc.update(&header[2..]);
+        assert_eq!(crc.finalize(), block.header_crc16);
+    }
+}
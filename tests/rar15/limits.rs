@@ -0,0 +1,56 @@
+use std::{fs, io};
+
+use rawrxd::{
+    rar15::{self, ParseOptions},
+    Error, LimitKind, Limits, Signature,
+};
+
+fn limited(file_name: &str, limits: Limits) -> Option<Error> {
+    let reader =
+        io::BufReader::new(fs::File::open(format!("tests/fixtures/rar15/{file_name}")).unwrap());
+    let options = ParseOptions {
+        limits,
+        ..Default::default()
+    };
+    rar15::BlockIterator::with_options(reader, Signature::Rar15.size(), options)
+        .unwrap()
+        .find_map(|result| result.err().map(Error::into_root))
+}
+
+#[test]
+fn default_limits_accept_archive() {
+    assert!(limited("stored.rar", Limits::default()).is_none());
+    assert!(limited("file_comment.rar", Limits::default()).is_none());
+}
+
+#[test]
+fn name_size_exceeded() {
+    let limits = Limits {
+        max_name_size: 4,
+        ..Default::default()
+    };
+    assert!(matches!(
+        limited("stored.rar", limits),
+        Some(Error::LimitExceeded {
+            kind: LimitKind::NameSize,
+            limit: 4,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn header_size_exceeded() {
+    let limits = Limits {
+        max_header_size: 8,
+        ..Default::default()
+    };
+    assert!(matches!(
+        limited("stored.rar", limits),
+        Some(Error::LimitExceeded {
+            kind: LimitKind::HeaderSize,
+            limit: 8,
+            ..
+        })
+    ));
+}
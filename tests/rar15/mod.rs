@@ -2,7 +2,26 @@ use std::{fs, io};
 
 use rawrxd::{rar15, Signature};
 
+#[cfg(feature = "async")]
+mod async_block_iterator;
+mod authenticity;
 mod corrupt_header;
+mod cross_check;
+mod decompress;
+mod encrypted_headers;
+mod entry_iterator;
+mod extended_time;
+mod file_flags;
+mod layout;
+mod limits;
+#[cfg(feature = "encodings")]
+mod oem_filename;
+mod parser;
+#[cfg(feature = "experimental-protect-record")]
+mod protect;
+mod round_trip;
+mod salvage;
+mod stream_block_iterator;
 
 fn block_iterator(file_name: &str) -> rar15::BlockIterator<io::BufReader<fs::File>> {
     let reader =
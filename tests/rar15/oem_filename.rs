@@ -0,0 +1,26 @@
+use rawrxd::{rar14::OemString, rar15::Filename, CodePage};
+
+#[test]
+fn decode_oem_filename() {
+    let name = Filename::Oem(b"\x8f\xe0\xa8\xa2\xa5\xe2.txt".to_vec());
+    assert_eq!(name.decode(CodePage::Cp866), "Привет.txt");
+    assert_eq!(name.guess_decode(), "Привет.txt");
+
+    let name = Filename::Ascii("hello.txt".to_string());
+    assert_eq!(name.decode(CodePage::Cp932), "hello.txt");
+}
+
+#[test]
+fn decode_oem_part_of_unicode_filename() {
+    // The encoded Unicode filename after the 0 byte is truncated.
+    let name = Filename::Unicode(Err(b"\x83\x65\x83\x58\x83\x67\0\xff".to_vec()));
+    assert_eq!(name.decode(CodePage::Cp932), "テスト");
+    assert_eq!(name.guess_decode(), "テスト");
+}
+
+#[test]
+fn decode_rar14_oem_string() {
+    let name = OemString::Oem(b"Caf\x82.txt".to_vec());
+    assert_eq!(name.decode(CodePage::Cp850), "Café.txt");
+    assert_eq!(name.guess_decode(), "Café.txt");
+}
@@ -0,0 +1,38 @@
+use std::fs;
+
+use rawrxd::{
+    rar15::{self, Event},
+    Signature,
+};
+
+use super::block_iterator;
+
+#[test]
+fn same_blocks_as_block_iterator() {
+    for file_name in ["file_comment.rar", "mac_info.rar", "encrypted_headers.rar"] {
+        let expected: Vec<_> = block_iterator(file_name)
+            .map(|block| format!("{block:?}"))
+            .collect();
+        let data = fs::read(format!("tests/fixtures/rar15/{file_name}")).unwrap();
+
+        for chunk_size in [1, 5, 4096] {
+            let mut parser = rar15::Parser::new(Signature::Rar15.size());
+            let mut events = vec![];
+            for chunk in data[Signature::Rar15.size() as usize..].chunks(chunk_size) {
+                events.extend(parser.feed(chunk));
+            }
+            events.extend(parser.finish());
+
+            let mut blocks = vec![];
+            for event in events {
+                match event {
+                    Event::Block(block) => blocks.push(format!("{:?}", Ok::<_, ()>(block))),
+                    Event::Error(e) => blocks.push(format!("{:?}", Err::<(), _>(e))),
+                    Event::End => break,
+                }
+            }
+
+            assert_eq!(blocks, expected, "{file_name} in chunks of {chunk_size}");
+        }
+    }
+}
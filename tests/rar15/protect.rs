@@ -0,0 +1,150 @@
+use std::{fs, io};
+
+use rawrxd::rar15::{self, BlockKind, DamagedRange, ProtectRecord};
+
+use super::block_iterator;
+
+const FIXTURE: &str = "tests/fixtures/rar15/protect.rar";
+
+fn protect_block() -> rar15::Block {
+    block_iterator("protect.rar")
+        .map(Result::unwrap)
+        .find(|block| matches!(block.kind, BlockKind::Protect(_)))
+        .unwrap()
+}
+
+fn protect_record() -> ProtectRecord {
+    let mut reader = fs::File::open(FIXTURE).unwrap();
+    ProtectRecord::read(&mut reader, &protect_block()).unwrap()
+}
+
+/// Validate the archive with the bytes at `offsets` flipped, reading the recovery record
+/// from the damaged archive.
+fn validate_damaged(offsets: &[usize]) -> Vec<DamagedRange> {
+    let mut archive = fs::read(FIXTURE).unwrap();
+    for &offset in offsets {
+        archive[offset] ^= 0xff;
+    }
+
+    let mut reader = io::Cursor::new(archive);
+    let record = ProtectRecord::read(&mut reader, &protect_block()).unwrap();
+    record.validate(&mut reader, 0).unwrap()
+}
+
+#[test]
+fn read_protect_record() {
+    let record = protect_record();
+
+    assert_eq!(record.block_offset, 1560);
+    assert_eq!(record.data_offset, 1586);
+    assert_eq!(record.sector_crcs.len(), 4);
+    assert_eq!(record.parity_sectors.len(), 2);
+}
+
+#[test]
+fn read_other_block() {
+    let block = block_iterator("protect.rar").next().unwrap().unwrap();
+    let mut reader = fs::File::open(FIXTURE).unwrap();
+
+    let err = ProtectRecord::read(&mut reader, &block).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+}
+
+#[test]
+fn parse_mismatched_size() {
+    let err = ProtectRecord::parse(&protect_block(), &[0; 1030]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn intact_archive() {
+    assert_eq!(validate_damaged(&[]), vec![]);
+}
+
+#[test]
+fn damaged_sector() {
+    assert_eq!(
+        validate_damaged(&[600, 700]),
+        vec![DamagedRange {
+            range: 512..1024,
+            recoverable: true,
+        }]
+    );
+}
+
+#[test]
+fn damaged_sectors_in_different_groups() {
+    assert_eq!(
+        validate_damaged(&[10, 600]),
+        vec![
+            DamagedRange {
+                range: 0..512,
+                recoverable: true,
+            },
+            DamagedRange {
+                range: 512..1024,
+                recoverable: true,
+            },
+        ]
+    );
+}
+
+#[test]
+fn damaged_sectors_in_the_same_group() {
+    assert_eq!(
+        validate_damaged(&[600, 1540]),
+        vec![
+            DamagedRange {
+                range: 512..1024,
+                recoverable: false,
+            },
+            DamagedRange {
+                range: 1536..2048,
+                recoverable: false,
+            },
+        ]
+    );
+}
+
+#[test]
+fn damaged_sector_crc() {
+    assert_eq!(
+        validate_damaged(&[1588]),
+        vec![DamagedRange {
+            range: 1588..1590,
+            recoverable: true,
+        }]
+    );
+}
+
+#[test]
+fn damaged_parity_sector() {
+    assert_eq!(
+        validate_damaged(&[2200]),
+        vec![DamagedRange {
+            range: 2106..2618,
+            recoverable: true,
+        }]
+    );
+}
+
+#[test]
+fn damaged_protect_block_is_not_a_damaged_sector() {
+    // The last sector overlaps the header of the protect block, which isn't protected.
+    assert_eq!(validate_damaged(&[1565]), vec![]);
+}
+
+#[test]
+fn protect_block_fields() {
+    let blocks = block_iterator("protect.rar")
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+    let BlockKind::Protect(protect) = &blocks[2].kind else {
+        panic!("{:?}", blocks[2]);
+    };
+
+    assert_eq!(protect.recovery_sectors, 2);
+    assert_eq!(protect.total_blocks, 4);
+    assert_eq!(protect.data_size, 1032);
+    assert!(matches!(blocks[3].kind, rar15::BlockKind::EndArchive(_)));
+}
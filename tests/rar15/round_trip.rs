@@ -0,0 +1,53 @@
+use std::{fs, io};
+
+use rawrxd::{
+    rar15::{self, ParseOptions},
+    Signature, Warning, WarningKind,
+};
+use rstest::rstest;
+
+fn round_trip_warnings(file_name: &str) -> Vec<Warning> {
+    let reader =
+        io::BufReader::new(fs::File::open(format!("tests/fixtures/rar15/{file_name}")).unwrap());
+    let options = ParseOptions {
+        round_trip: true,
+        ..Default::default()
+    };
+    let mut iter =
+        rar15::BlockIterator::with_options(reader, Signature::Rar15.size(), options).unwrap();
+    while let Some(Ok(_)) = iter.next() {}
+
+    iter.take_warnings()
+        .into_iter()
+        .filter(|warning| matches!(warning.kind, WarningKind::RoundTripMismatch { .. }))
+        .collect()
+}
+
+#[rstest]
+#[case("stored.rar")]
+#[case("mac_info.rar")]
+#[case("service_blocks.rar")]
+#[case("sub_blocks.rar")]
+#[case("ntfs_acl.rar")]
+#[case("unpack_versions.rar")]
+#[case("file_comment.rar")]
+fn headers_round_trip(#[case] file_name: &str) {
+    assert_eq!(round_trip_warnings(file_name), vec![]);
+}
+
+#[test]
+fn truncated_extended_time_mismatch() {
+    assert_eq!(
+        round_trip_warnings("extended_time.rar"),
+        vec![
+            Warning {
+                offset: 82,
+                kind: WarningKind::RoundTripMismatch { position: 128 },
+            },
+            Warning {
+                offset: 139,
+                kind: WarningKind::RoundTripMismatch { position: 183 },
+            },
+        ]
+    );
+}
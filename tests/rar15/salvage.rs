@@ -0,0 +1,57 @@
+use std::{fs, io};
+
+use rawrxd::{
+    rar15::{self, ParseOptions},
+    BlockSize, Error, Signature, WarningKind,
+};
+
+fn salvage(data: &[u8]) -> rar15::BlockIterator<io::Cursor<&[u8]>> {
+    let options = ParseOptions {
+        salvage: true,
+        ..Default::default()
+    };
+    rar15::BlockIterator::with_options(io::Cursor::new(data), Signature::Rar15.size(), options)
+        .unwrap()
+}
+
+fn offsets(data: &[u8]) -> Vec<u64> {
+    rar15::BlockIterator::new(io::Cursor::new(data), Signature::Rar15.size())
+        .unwrap()
+        .map(|block| block.unwrap().offset())
+        .collect()
+}
+
+#[test]
+fn skip_block_with_corrupt_checksum() {
+    let mut data = fs::read("tests/fixtures/rar15/unpack_versions.rar").unwrap();
+    let offsets = offsets(&data);
+    let corrupt = offsets[1];
+    data[corrupt as usize] ^= 0xff;
+
+    let mut iter = salvage(&data);
+    let salvaged = iter
+        .by_ref()
+        .map(|block| block.unwrap().offset())
+        .collect::<Vec<_>>();
+
+    assert_eq!(salvaged, [&offsets[..1], &offsets[2..]].concat());
+    assert_eq!(
+        iter.warnings()[0].kind,
+        WarningKind::CorruptDataSkipped {
+            range: corrupt..offsets[2]
+        }
+    );
+}
+
+#[test]
+fn corrupt_header_fixtures() {
+    for name in ["corrupt_header_1", "corrupt_header_2", "corrupt_header_3"] {
+        let data = fs::read(format!("tests/fixtures/rar15/{name}.rar")).unwrap();
+        for result in salvage(&data) {
+            assert!(matches!(
+                result.as_ref().map_err(Error::root),
+                Ok(_) | Err(Error::CorruptHeader)
+            ));
+        }
+    }
+}
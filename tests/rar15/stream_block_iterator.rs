@@ -0,0 +1,23 @@
+use std::fs;
+
+use rawrxd::{rar15, Signature};
+
+use super::block_iterator;
+
+#[test]
+fn same_blocks_as_block_iterator() {
+    for file_name in ["file_comment.rar", "ntfs_acl.rar", "unpack_versions.rar"] {
+        let expected: Vec<_> = block_iterator(file_name)
+            .map(|block| format!("{block:?}"))
+            .collect();
+        let data = fs::read(format!("tests/fixtures/rar15/{file_name}")).unwrap();
+
+        let iter = rar15::StreamBlockIterator::new(
+            &data[Signature::Rar15.size() as usize..],
+            Signature::Rar15.size(),
+        );
+        let blocks: Vec<_> = iter.map(|block| format!("{block:?}")).collect();
+
+        assert_eq!(blocks, expected, "{file_name}");
+    }
+}
@@ -0,0 +1,75 @@
+use std::fs;
+
+use futures::{executor::block_on, io::AllowStdIo};
+use rawrxd::{
+    rar50::{self, BlockKind, ParseOptions},
+    Error, Signature,
+};
+
+use super::block_iterator;
+
+fn open(file_name: &str) -> AllowStdIo<fs::File> {
+    AllowStdIo::new(fs::File::open(format!("tests/fixtures/rar50/{file_name}")).unwrap())
+}
+
+fn collect<R>(mut iter: rar50::AsyncBlockIterator<R>) -> Vec<String>
+where
+    R: futures::AsyncRead + futures::AsyncSeek + Unpin,
+{
+    let mut blocks = vec![];
+    while let Some(block) = block_on(iter.next()) {
+        blocks.push(format!("{:?}", block.unwrap()));
+    }
+    blocks
+}
+
+#[test]
+fn same_blocks_as_sync_iterator() {
+    for file_name in ["quick_open.rar", "recovery_record.rar", "ntfs_acl.rar"] {
+        let expected: Vec<_> = block_iterator(file_name)
+            .map(|block| format!("{:?}", block.unwrap()))
+            .collect();
+
+        let options = ParseOptions {
+            cross_check: true,
+            ..Default::default()
+        };
+        let iter = block_on(rar50::AsyncBlockIterator::with_options(
+            open(file_name),
+            Signature::Rar50.size(),
+            options,
+        ))
+        .unwrap();
+        assert_eq!(collect(iter), expected, "{file_name}");
+    }
+}
+
+#[test]
+fn encrypted_headers() {
+    let iter = block_on(rar50::AsyncBlockIterator::with_password(
+        open("encrypted_headers.rar"),
+        Signature::Rar50.size(),
+        "password",
+    ))
+    .unwrap();
+    assert_eq!(collect(iter).len(), 5);
+
+    let mut iter = block_on(rar50::AsyncBlockIterator::new(
+        open("encrypted_headers.rar"),
+        Signature::Rar50.size(),
+    ))
+    .unwrap();
+    let crypt = block_on(iter.next()).unwrap().unwrap();
+    assert!(matches!(crypt.kind, BlockKind::Crypt(_)));
+    assert!(matches!(
+        block_on(iter.next()),
+        Some(Err(Error::PasswordRequired))
+    ));
+    assert!(block_on(iter.next()).is_none());
+}
+
+#[test]
+fn search_signature() {
+    let found = block_on(Signature::search_stream_async(open("quick_open.rar"))).unwrap();
+    assert_eq!(found, Some((Signature::Rar50, 0)));
+}
@@ -0,0 +1,81 @@
+use std::{fs, io};
+
+use rawrxd::{
+    rar50::{self, ParseOptions},
+    Divergence, Signature, Warning, WarningKind,
+};
+
+fn cross_check(file_name: &str) -> Vec<Warning> {
+    let reader =
+        io::BufReader::new(fs::File::open(format!("tests/fixtures/rar50/{file_name}")).unwrap());
+    let options = ParseOptions {
+        cross_check: true,
+        ..Default::default()
+    };
+    let mut iter =
+        rar50::BlockIterator::with_options(reader, Signature::Rar50.size(), options).unwrap();
+    for block in iter.by_ref() {
+        block.unwrap();
+    }
+
+    iter.take_warnings()
+}
+
+fn divergences(warnings: &[Warning]) -> Vec<(u64, &Divergence)> {
+    warnings
+        .iter()
+        .filter_map(|warning| match &warning.kind {
+            WarningKind::ParserDivergence(divergence) => Some((warning.offset, divergence)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn trailing_bytes_and_checksum_mismatch() {
+    let warnings = cross_check("ambiguous_headers.rar");
+    let divergences = divergences(&warnings);
+
+    assert_eq!(divergences.len(), 2);
+    assert_eq!(
+        divergences[0],
+        (16, &Divergence::TrailingBytes { count: 4 })
+    );
+    assert!(matches!(
+        divergences[1],
+        (52, Divergence::ChecksumMismatch { stored, computed }) if stored != computed
+    ));
+}
+
+#[test]
+fn header_overrun_is_truncated() {
+    let warnings = cross_check("header_overrun.rar");
+
+    assert_eq!(divergences(&warnings), [(16, &Divergence::Truncated)]);
+}
+
+#[test]
+fn well_formed_archives_agree() {
+    for file_name in [
+        "recovery_record.rar",
+        "recovery_record_and_quick_open.rar",
+        "unix_high_ascii_filename.rar",
+        "rar7_dictionary_sizes.rar",
+        "blake2sp_hash.rar",
+    ] {
+        assert_eq!(cross_check(file_name), [], "{file_name}");
+    }
+}
+
+#[test]
+fn disabled_by_default() {
+    let mut iter = super::block_iterator("ambiguous_headers.rar");
+    for block in iter.by_ref() {
+        block.unwrap();
+    }
+
+    assert!(!iter
+        .warnings()
+        .iter()
+        .any(|warning| matches!(warning.kind, WarningKind::ParserDivergence(_))));
+}
@@ -0,0 +1,238 @@
+use std::{
+    fs,
+    io::{self, Read, Seek},
+};
+
+use rawrxd::{
+    rar50::{BlockKind, CompressionInfo, CompressionMethod, Decoder, FileBlock, FileHash},
+    BlockSize, Error, LimitKind, Limits,
+};
+
+use super::block_iterator;
+
+fn files(file_name: &str) -> Vec<(u64, u64, FileBlock)> {
+    block_iterator(file_name)
+        .filter_map(|block| {
+            let block = block.unwrap();
+            let data_offset = block.offset() + block.header_size();
+            let data_size = block.data_size();
+            match block.kind {
+                BlockKind::File(file) => Some((data_offset, data_size, file)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn decompress(
+    file_name: &str,
+    data_offset: u64,
+    data_size: u64,
+    file: &FileBlock,
+) -> io::Result<Vec<u8>> {
+    let mut reader = fs::File::open(format!("tests/fixtures/rar50/{file_name}")).unwrap();
+    reader.seek(io::SeekFrom::Start(data_offset)).unwrap();
+
+    let mut data = vec![];
+    Decoder::new(io::BufReader::new(reader).take(data_size), file)?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+#[test]
+fn decompress_all_methods() {
+    let files = files("recovery_record_and_quick_open.rar");
+    assert_eq!(files.len(), 12);
+
+    for (data_offset, data_size, file) in &files {
+        let data = decompress(
+            "recovery_record_and_quick_open.rar",
+            *data_offset,
+            *data_size,
+            file,
+        )
+        .unwrap_or_else(|e| panic!("{:?}: {e}", file.name));
+        assert_eq!(
+            Some(data.len() as u64),
+            file.unpacked_size,
+            "{:?}",
+            file.name
+        );
+    }
+}
+
+#[test]
+fn verify_blake2sp() {
+    let files = files("blake2sp_hash.rar");
+    assert_eq!(files.len(), 2);
+
+    for (data_offset, data_size, file) in &files {
+        assert!(file.unpacked_data_crc32.is_none());
+        let data = decompress("blake2sp_hash.rar", *data_offset, *data_size, file).unwrap();
+
+        let record = file.hash.as_ref().unwrap();
+        assert!(record.verify(&data));
+        assert!(!record.verify(&data[1..]));
+    }
+
+    let (data_offset, data_size, mut file) = files.into_iter().next().unwrap();
+    if let Some(record) = &mut file.hash {
+        record.hash = FileHash::Blake2Sp([0; 32]);
+    }
+    let error = decompress("blake2sp_hash.rar", data_offset, data_size, &file).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn crc32_mismatch() {
+    let (data_offset, data_size, mut file) = files("recovery_record_and_quick_open.rar")
+        .into_iter()
+        .find(|(_, _, file)| file.name.as_deref() == Ok("readme.txt"))
+        .unwrap();
+
+    file.unpacked_data_crc32 = file.unpacked_data_crc32.map(|crc| !crc);
+    let error = decompress(
+        "recovery_record_and_quick_open.rar",
+        data_offset,
+        data_size,
+        &file,
+    )
+    .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn truncated_data() {
+    let (data_offset, data_size, file) = files("recovery_record_and_quick_open.rar")
+        .into_iter()
+        .find(|(_, _, file)| file.name.as_deref() == Ok("readme.txt"))
+        .unwrap();
+
+    let error = decompress(
+        "recovery_record_and_quick_open.rar",
+        data_offset,
+        data_size / 2,
+        &file,
+    )
+    .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn skip_compressed_data() {
+    for (data_offset, data_size, file) in files("recovery_record_and_quick_open.rar") {
+        let data = decompress(
+            "recovery_record_and_quick_open.rar",
+            data_offset,
+            data_size,
+            &file,
+        )
+        .unwrap();
+
+        let mut reader =
+            fs::File::open("tests/fixtures/rar50/recovery_record_and_quick_open.rar").unwrap();
+        reader.seek(io::SeekFrom::Start(data_offset)).unwrap();
+        let mut decoder = Decoder::new(io::BufReader::new(reader).take(data_size), &file).unwrap();
+
+        let skip = data.len() as u64 / 2;
+        assert_eq!(decoder.skip(skip).unwrap(), skip);
+        let mut rest = vec![];
+        decoder.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, data[skip as usize..], "{:?}", file.name);
+    }
+}
+
+#[test]
+fn window_size_limit() {
+    let mut files = files("recovery_record_and_quick_open.rar");
+    let index = files
+        .iter()
+        .position(|(_, _, file)| file.compression_info.method() != CompressionMethod::NoCompression)
+        .unwrap();
+    let (data_offset, data_size, mut file) = files.swap_remove(index);
+    let open = |file: &FileBlock, limits: &Limits| {
+        let mut reader =
+            fs::File::open("tests/fixtures/rar50/recovery_record_and_quick_open.rar").unwrap();
+        reader.seek(io::SeekFrom::Start(data_offset)).unwrap();
+        Decoder::with_limits(reader.take(data_size), file, limits)
+            .map(drop)
+            .map_err(Error::from)
+    };
+
+    let limits = Limits {
+        max_window_size: 0x10000,
+        ..Default::default()
+    };
+    assert!(matches!(
+        open(&file, &limits),
+        Err(Error::LimitExceeded {
+            kind: LimitKind::WindowSize,
+            limit: 0x10000,
+            ..
+        })
+    ));
+
+    // A RAR 7 dictionary of 64 GiB, which isn't bounded by the size of the file.
+    file.unpacked_size = None;
+    file.compression_info =
+        CompressionInfo::new((file.compression_info.bits() & 0x0380) | 0x01 | 19 << 10);
+    assert_eq!(file.compression_info.min_dictionary_size(), Ok(1 << 36));
+    assert!(matches!(
+        open(&file, &Limits::default()),
+        Err(Error::LimitExceeded {
+            kind: LimitKind::WindowSize,
+            size: 0x1000000000,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn solid_stream() {
+    let files = files("solid.rar");
+    let solid: Vec<_> = files
+        .iter()
+        .map(|(_, _, file)| file.compression_info.is_solid())
+        .collect();
+    assert_eq!(solid, [false, true, true]);
+
+    // The third file only differs from the first by one line, so it's mostly matches.
+    let (data_offset, data_size, third) = &files[2];
+    assert!(*data_size < 100);
+    let error = decompress("solid.rar", *data_offset, *data_size, third).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+
+    let mut reader = fs::File::open("tests/fixtures/rar50/solid.rar").unwrap();
+    let (data_offset, data_size, first) = &files[0];
+    reader.seek(io::SeekFrom::Start(*data_offset)).unwrap();
+    let mut decoder = Decoder::new(io::BufReader::new(reader).take(*data_size), first).unwrap();
+
+    let mut first_data = vec![];
+    decoder.read_to_end(&mut first_data).unwrap();
+
+    // The second file is skipped, but it still has to be decoded.
+    for (data_offset, data_size, file) in &files[1..] {
+        decoder
+            .continue_solid(file, |packed| {
+                packed.get_mut().seek(io::SeekFrom::Start(*data_offset))?;
+                packed.set_limit(*data_size);
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    let mut third_data = vec![];
+    decoder.read_to_end(&mut third_data).unwrap();
+    assert_eq!(third_data.len() as u64, third.unpacked_size.unwrap());
+    assert_ne!(third_data, first_data);
+    assert_eq!(
+        String::from_utf8(third_data)
+            .unwrap()
+            .matches("this line only appears in the third file")
+            .count(),
+        1
+    );
+
+    // A file which starts a new stream can't continue it.
+    let error = decoder.continue_solid(first, |_| Ok(())).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,146 @@
+use std::{
+    fs,
+    io::{self, Read, Seek},
+};
+
+use rawrxd::{
+    rar50::{BlockKind, Decoder, DecryptReader, FileBlock, FileHash},
+    BlockSize, Error, PasswordRequest,
+};
+
+use super::block_iterator;
+
+fn files() -> Vec<(u64, u64, FileBlock)> {
+    block_iterator("encrypted_files.rar")
+        .filter_map(|block| {
+            let block = block.unwrap();
+            let data_offset = block.offset() + block.header_size();
+            let data_size = block.data_size();
+            match block.kind {
+                BlockKind::File(file) => Some((data_offset, data_size, file)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn data_reader(data_offset: u64, data_size: u64) -> io::Take<io::BufReader<fs::File>> {
+    let mut reader = fs::File::open("tests/fixtures/rar50/encrypted_files.rar").unwrap();
+    reader.seek(io::SeekFrom::Start(data_offset)).unwrap();
+    io::BufReader::new(reader).take(data_size)
+}
+
+fn readme() -> String {
+    (0..200)
+        .map(|i| format!("{i}: The quick brown fox jumps over the lazy dog.\n"))
+        .collect()
+}
+
+#[test]
+fn decrypt_files() {
+    let files = files();
+    let names: Vec<_> = files
+        .iter()
+        .map(|(_, _, file)| file.name.clone().unwrap())
+        .collect();
+    assert_eq!(names, ["hello.txt", "readme.txt", "blake2.txt"]);
+
+    // The last file is hashed with BLAKE2sp instead of CRC32.
+    assert!(files[2].2.unpacked_data_crc32.is_none());
+    assert!(matches!(
+        files[2].2.hash.as_ref().unwrap().hash,
+        FileHash::Blake2Sp(_)
+    ));
+
+    for (data_offset, data_size, file) in &files {
+        let encryption = file.encryption.as_ref().unwrap();
+        assert!(encryption.flags.uses_mac_checksum());
+
+        let mut data = String::new();
+        Decoder::with_password(data_reader(*data_offset, *data_size), file, "password")
+            .unwrap()
+            .read_to_string(&mut data)
+            .unwrap();
+
+        let expected = match file.name.as_deref().unwrap() {
+            "hello.txt" => "Hello, world!\n".to_string(),
+            _ => readme(),
+        };
+        assert_eq!(data, expected);
+    }
+}
+
+#[test]
+fn decrypt_with_credentials() {
+    let mut requested = vec![];
+    let mut provider = |request: PasswordRequest| {
+        let PasswordRequest::File { name } = request else {
+            panic!("{request:?}");
+        };
+        requested.push(name.to_string());
+        (name == "hello.txt").then(|| "password".to_string())
+    };
+
+    let files = files();
+    let (data_offset, data_size, file) = &files[0];
+    let mut data = String::new();
+    Decoder::with_credentials(data_reader(*data_offset, *data_size), file, &mut provider)
+        .unwrap()
+        .read_to_string(&mut data)
+        .unwrap();
+    assert_eq!(data, "Hello, world!\n");
+
+    let (data_offset, data_size, file) = &files[1];
+    assert!(matches!(
+        Decoder::with_credentials(data_reader(*data_offset, *data_size), file, &mut provider),
+        Err(Error::PasswordRequired)
+    ));
+
+    assert_eq!(requested, ["hello.txt", "readme.txt"]);
+}
+
+#[test]
+fn verify_password() {
+    for (_, _, file) in files() {
+        let encryption = file.encryption.unwrap();
+        assert!(encryption.verify_password("password"));
+        assert!(!encryption.verify_password("wrong"));
+    }
+}
+
+#[test]
+fn decrypt_reader() {
+    let (data_offset, data_size, file) = files().remove(0);
+    assert_eq!(data_size, 16);
+
+    let mut reader = DecryptReader::new(
+        data_reader(data_offset, data_size),
+        file.encryption.as_ref().unwrap(),
+        "password",
+    )
+    .unwrap();
+
+    // The data is padded to the AES block size.
+    let mut data = vec![];
+    reader.read_to_end(&mut data).unwrap();
+    assert_eq!(data.len(), 16);
+    assert_eq!(&data[..14], b"Hello, world!\n");
+}
+
+#[test]
+fn incorrect_password() {
+    for (data_offset, data_size, file) in files() {
+        let result = Decoder::with_password(data_reader(data_offset, data_size), &file, "wrong");
+        assert!(matches!(result, Err(Error::IncorrectPassword)));
+    }
+}
+
+#[test]
+fn password_required() {
+    for (data_offset, data_size, file) in files() {
+        let error = Decoder::new(data_reader(data_offset, data_size), &file)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    fs,
+    io::{self, Read},
+    sync::{Arc, Mutex},
+};
+
+use rawrxd::{
+    rar50::{self, BlockKind},
+    BlockSize, Error, PasswordRequest, Signature,
+};
+
+use super::block_iterator;
+
+fn with_password(password: &str) -> rar50::BlockIterator<io::BufReader<fs::File>> {
+    let reader =
+        io::BufReader::new(fs::File::open("tests/fixtures/rar50/encrypted_headers.rar").unwrap());
+    rar50::BlockIterator::with_password(reader, Signature::Rar50.size(), password).unwrap()
+}
+
+#[test]
+fn list_encrypted_headers() {
+    let mut iter = with_password("password");
+    let blocks: Vec<_> = iter.by_ref().map(Result::unwrap).collect();
+
+    assert!(matches!(blocks[0].kind, BlockKind::Crypt(_)));
+    assert!(matches!(blocks[1].kind, BlockKind::Main(_)));
+    assert!(matches!(
+        blocks.last().unwrap().kind,
+        BlockKind::EndArchive(_)
+    ));
+
+    let names: Vec<_> = blocks
+        .iter()
+        .filter_map(|block| match &block.kind {
+            BlockKind::File(file) => file.name.clone().ok(),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(names, ["hello.txt", "docs/readme.txt"]);
+
+    // The data area follows the padded header.
+    let hello = &blocks[2];
+    let mut reader =
+        io::BufReader::new(fs::File::open("tests/fixtures/rar50/encrypted_headers.rar").unwrap());
+    let mut data = String::new();
+    hello
+        .data_reader(&mut reader)
+        .unwrap()
+        .read_to_string(&mut data)
+        .unwrap();
+    assert_eq!(data, "Hello, world!\n");
+}
+
+#[test]
+fn incorrect_password() {
+    let mut iter = with_password("wrong");
+
+    assert!(matches!(iter.next(), Some(Ok(_))));
+    assert!(matches!(iter.next(), Some(Err(Error::IncorrectPassword))));
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn password_required() {
+    let mut iter = block_iterator("encrypted_headers.rar");
+
+    assert!(matches!(
+        iter.next(),
+        Some(Ok(rar50::Block {
+            kind: BlockKind::Crypt(_),
+            ..
+        }))
+    ));
+    assert!(matches!(iter.next(), Some(Err(Error::PasswordRequired))));
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn credentials_asked_once() {
+    let requests = Arc::new(Mutex::new(vec![]));
+    let provider = {
+        let requests = requests.clone();
+        move |request: PasswordRequest| {
+            if let PasswordRequest::Headers { offset } = request {
+                requests.lock().unwrap().push(offset);
+            }
+            Some("password".to_string())
+        }
+    };
+    let reader =
+        io::BufReader::new(fs::File::open("tests/fixtures/rar50/encrypted_headers.rar").unwrap());
+    let mut iter =
+        rar50::BlockIterator::with_credentials(reader, Signature::Rar50.size(), provider).unwrap();
+
+    // The password isn't needed before the first encrypted block.
+    let crypt = iter.next().unwrap().unwrap();
+    assert!(requests.lock().unwrap().is_empty());
+
+    assert_eq!(iter.map(Result::unwrap).count(), 4);
+    assert_eq!(*requests.lock().unwrap(), [crypt.offset() + crypt.size()]);
+}
+
+#[test]
+fn credentials_without_password() {
+    let reader =
+        io::BufReader::new(fs::File::open("tests/fixtures/rar50/encrypted_headers.rar").unwrap());
+    let mut iter = rar50::BlockIterator::with_credentials(
+        reader,
+        Signature::Rar50.size(),
+        |_: PasswordRequest| None,
+    )
+    .unwrap();
+
+    assert!(matches!(iter.next(), Some(Ok(_))));
+    assert!(matches!(iter.next(), Some(Err(Error::PasswordRequired))));
+}
+
+#[test]
+fn verify_password() {
+    let block = block_iterator("encrypted_headers.rar")
+        .next()
+        .unwrap()
+        .unwrap();
+    let BlockKind::Crypt(crypt) = block.kind else {
+        panic!("{block:?}");
+    };
+
+    assert!(crypt.verify_password("password"));
+    assert!(!crypt.verify_password("wrong"));
+}
@@ -0,0 +1,39 @@
+use rawrxd::rar50::{BlockKind, EntryIterator, ServiceBlockKind};
+
+use super::block_iterator;
+
+#[test]
+fn child_service_blocks() {
+    let entries: Vec<_> = EntryIterator::new(block_iterator("ntfs_acl.rar"))
+        .map(Result::unwrap)
+        .collect();
+
+    let names: Vec<_> = entries
+        .iter()
+        .map(|entry| entry.file().name.as_deref().unwrap())
+        .collect();
+    assert_eq!(names, ["file.txt", "other.txt"]);
+
+    let services: Vec<_> = entries[0].services().collect();
+    assert_eq!(services.len(), 1);
+    assert!(matches!(
+        services[0].kind,
+        ServiceBlockKind::NtfsFilePermissions
+    ));
+
+    // The service block after other.txt is not marked as its child.
+    assert!(entries[1].children.is_empty());
+}
+
+#[test]
+fn no_children() {
+    let mut iter = EntryIterator::new(block_iterator("links.rar"));
+    let entries: Vec<_> = iter.by_ref().map(Result::unwrap).collect();
+
+    assert_eq!(entries.len(), 5);
+    assert!(entries.iter().all(|entry| entry.children.is_empty()));
+    assert!(entries
+        .iter()
+        .all(|entry| matches!(entry.block.kind, BlockKind::File(_))));
+    assert!(iter.warnings().is_empty());
+}
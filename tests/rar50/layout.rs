@@ -0,0 +1,47 @@
+use rawrxd::{rar50, BlockSize, RawHeaderReader};
+use rstest::rstest;
+
+use super::block_iterator;
+
+#[rstest]
+#[case("unix_owner.rar")]
+#[case("quick_open.rar")]
+#[case("links.rar")]
+#[case("blake2sp_hash.rar")]
+#[case("encrypted_files.rar")]
+fn fields_cover_header(#[case] file_name: &str) {
+    for result in block_iterator(file_name).with_raw_headers() {
+        let (block, header) = result.unwrap();
+        let fields = rar50::header_layout(&header, block.offset());
+
+        let mut offset = block.offset();
+        for field in &fields {
+            assert_eq!(field.offset, offset, "{field:?}");
+            assert_ne!(field.name, "unknown", "{field:?}");
+            assert_ne!(field.name, "unknown record", "{field:?}");
+            offset += field.size;
+        }
+        assert_eq!(offset, block.offset() + block.header_size());
+    }
+}
+
+#[test]
+fn extra_area_records() {
+    let (block, header) = block_iterator("unix_owner.rar")
+        .with_raw_headers()
+        .map(Result::unwrap)
+        .find(|(block, _)| matches!(block.kind, rar50::BlockKind::File(_)))
+        .unwrap();
+
+    let fields = rar50::header_layout(&header, block.offset());
+
+    let name = fields.iter().find(|field| field.name == "name").unwrap();
+    assert_eq!(name.value, "hello.txt");
+
+    let records: Vec<_> = fields
+        .iter()
+        .filter(|field| field.name.ends_with(" record"))
+        .map(|field| field.name)
+        .collect();
+    assert!(records.contains(&"unix owner record"), "{records:?}");
+}
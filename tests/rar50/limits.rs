@@ -0,0 +1,102 @@
+use std::{fs, io};
+
+use rawrxd::{
+    rar50::{self, ParseOptions},
+    Error, LimitKind, Limits, Signature,
+};
+
+fn limited(file_name: &str, limits: Limits) -> Option<Error> {
+    let reader =
+        io::BufReader::new(fs::File::open(format!("tests/fixtures/rar50/{file_name}")).unwrap());
+    let options = ParseOptions {
+        limits,
+        ..Default::default()
+    };
+    rar50::BlockIterator::with_options(reader, Signature::Rar50.size(), options)
+        .unwrap()
+        .find_map(|result| result.err().map(Error::into_root))
+}
+
+#[test]
+fn default_limits_accept_archive() {
+    assert!(limited("unix_owner.rar", Limits::default()).is_none());
+    assert!(limited("quick_open.rar", Limits::default()).is_none());
+}
+
+#[test]
+fn name_size_exceeded() {
+    let limits = Limits {
+        max_name_size: 4,
+        ..Default::default()
+    };
+    assert!(matches!(
+        limited("unix_owner.rar", limits),
+        Some(Error::LimitExceeded {
+            kind: LimitKind::NameSize,
+            limit: 4,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn header_size_exceeded() {
+    let limits = Limits {
+        max_header_size: 8,
+        ..Default::default()
+    };
+    assert!(matches!(
+        limited("unix_owner.rar", limits),
+        Some(Error::LimitExceeded {
+            kind: LimitKind::HeaderSize,
+            limit: 8,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn record_size_exceeded() {
+    let limits = Limits {
+        max_record_size: 4,
+        ..Default::default()
+    };
+    assert!(matches!(
+        limited("unix_owner.rar", limits),
+        Some(Error::LimitExceeded {
+            kind: LimitKind::RecordSize,
+            limit: 4,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn forged_name_size_is_not_allocated() {
+    // Service block whose name is 2^40 bytes long.
+    let fields = [
+        0x03, // Block type
+        0x00, // Block flags
+        0x00, // Service flags
+        0x00, // Unpacked size
+        0x00, // Attributes
+        0x00, // Compression info
+        0x00, // Host OS
+        0x80, 0x80, 0x80, 0x80, 0x80, 0x20, // Name size
+    ];
+    let mut archive = Signature::Rar50.signature().to_vec();
+    archive.extend_from_slice(&[0; 4]);
+    archive.push(fields.len() as u8);
+    archive.extend_from_slice(&fields);
+
+    let mut iter =
+        rar50::BlockIterator::new(io::Cursor::new(archive), Signature::Rar50.size()).unwrap();
+    assert!(matches!(
+        iter.next().unwrap().map_err(Error::into_root),
+        Err(Error::LimitExceeded {
+            kind: LimitKind::NameSize,
+            size: 0x10000000000,
+            ..
+        })
+    ));
+}
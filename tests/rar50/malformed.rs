@@ -0,0 +1,93 @@
+use std::io;
+
+use rawrxd::{rar50, Error, Signature};
+
+/// Archive made of a single block with the given header fields after the header size.
+fn archive(fields: &[u8]) -> io::Cursor<Vec<u8>> {
+    let mut archive = Signature::Rar50.signature().to_vec();
+    archive.extend_from_slice(&[0; 4]);
+    archive.push(fields.len() as u8);
+    archive.extend_from_slice(fields);
+    io::Cursor::new(archive)
+}
+
+fn first_block(fields: &[u8]) -> rawrxd::RarResult<rar50::Block> {
+    rar50::BlockIterator::new(archive(fields), Signature::Rar50.size())
+        .unwrap()
+        .next()
+        .unwrap()
+        .map_err(Error::into_root)
+}
+
+#[test]
+fn host_os_out_of_range() {
+    let fields = [
+        0x02, // Block type
+        0x00, // Block flags
+        0x00, // File flags
+        0x00, // Unpacked size
+        0x00, // Attributes
+        0x00, // Compression info
+        0xac, 0x02, // Host OS
+        0x01, b'a', // Name
+    ];
+    assert!(matches!(
+        first_block(&fields),
+        Err(Error::InvalidEnumValue {
+            name: "HostOs",
+            value: 300,
+        })
+    ));
+}
+
+#[test]
+fn redirection_target_is_not_utf8() {
+    let fields = [
+        0x02, // Block type
+        0x01, // Block flags
+        0x06, // Extra area size
+        0x00, // File flags
+        0x00, // Unpacked size
+        0x00, // Attributes
+        0x00, // Compression info
+        0x00, // Host OS
+        0x01, b'a', // Name
+        0x05, 0x05, // Record size and type
+        0x01, // Redirection type
+        0x00, // Redirection flags
+        0x01, 0xff, // Target
+    ];
+    assert!(matches!(
+        first_block(&fields),
+        Err(Error::InvalidUtf8 {
+            field: "redirection target",
+        })
+    ));
+}
+
+#[test]
+fn record_smaller_than_its_type() {
+    let fields = [
+        0x02, // Block type
+        0x01, // Block flags
+        0x03, // Extra area size
+        0x00, // File flags
+        0x00, // Unpacked size
+        0x00, // Attributes
+        0x00, // Compression info
+        0x00, // Host OS
+        0x01, b'a', // Name
+        0x00, 0x85, 0x01, // Record size and type
+    ];
+    assert!(matches!(first_block(&fields), Err(Error::CorruptHeader)));
+}
+
+#[test]
+fn truncated_header() {
+    let fields = [
+        0x02, // Block type
+        0x00, // Block flags
+        0x00, // File flags
+    ];
+    assert!(matches!(first_block(&fields), Err(Error::UnexpectedEof)));
+}
@@ -2,7 +2,29 @@ use std::{fs, io};
 
 use rawrxd::{rar50, Signature};
 
+#[cfg(feature = "async")]
+mod async_block_iterator;
+mod cross_check;
+mod decompress;
+mod encrypted_files;
+mod encrypted_headers;
+mod entry_iterator;
+mod layout;
+mod limits;
+mod malformed;
+mod parser;
+mod quick_open;
+mod rar7_dictionary_sizes;
+mod recovery_record;
+mod round_trip;
+mod salvage;
+mod skip_service_blocks;
+mod stream_block_iterator;
+mod tamper_detection;
+#[cfg(feature = "tracing")]
+mod tracing;
 mod unicode_filename;
+mod unknown_block;
 
 fn block_iterator(file_name: &str) -> rar50::BlockIterator<io::BufReader<fs::File>> {
     let reader =
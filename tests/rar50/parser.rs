@@ -0,0 +1,103 @@
+use std::fs;
+
+use rawrxd::{
+    rar50::{self, Block, Event, ParseOptions},
+    Error, Signature,
+};
+
+use super::block_iterator;
+
+fn feed(parser: &mut rar50::Parser, data: &[u8], chunk_size: usize) -> Vec<Event> {
+    let mut events = vec![];
+    for chunk in data.chunks(chunk_size) {
+        events.extend(parser.feed(chunk));
+    }
+    events.extend(parser.finish());
+    events
+}
+
+fn blocks(events: Vec<Event>) -> Vec<Block> {
+    let mut blocks = vec![];
+    for event in events {
+        match event {
+            Event::Block(block) => blocks.push(block),
+            Event::Error(e) => panic!("{e}"),
+            Event::End => break,
+        }
+    }
+    blocks
+}
+
+#[test]
+fn same_blocks_as_block_iterator() {
+    for file_name in ["quick_open.rar", "recovery_record.rar", "links.rar"] {
+        let expected: Vec<_> = block_iterator(file_name)
+            .map(|block| format!("{:?}", block.unwrap()))
+            .collect();
+        let data = fs::read(format!("tests/fixtures/rar50/{file_name}")).unwrap();
+
+        for chunk_size in [1, 13, 4096] {
+            let options = ParseOptions {
+                cross_check: true,
+                ..Default::default()
+            };
+            let mut parser = rar50::Parser::with_options(Signature::Rar50.size(), options);
+            let events = feed(
+                &mut parser,
+                &data[Signature::Rar50.size() as usize..],
+                chunk_size,
+            );
+            let blocks: Vec<_> = blocks(events)
+                .iter()
+                .map(|block| format!("{block:?}"))
+                .collect();
+
+            assert_eq!(blocks, expected, "{file_name} in chunks of {chunk_size}");
+            assert!(parser.warnings().is_empty());
+        }
+    }
+}
+
+#[test]
+fn blocks_are_returned_as_soon_as_the_header_is_complete() {
+    let data = fs::read("tests/fixtures/rar50/links.rar").unwrap();
+    let first = block_iterator("links.rar").next().unwrap().unwrap();
+    let header_end = (first.offset + first.header_size) as usize;
+
+    let mut parser = rar50::Parser::new(Signature::Rar50.size());
+    assert!(parser.feed(&data[8..header_end - 1]).is_empty());
+    assert!(matches!(
+        parser.feed(&data[header_end - 1..header_end]).as_slice(),
+        [Event::Block(_)]
+    ));
+    assert_eq!(parser.position(), header_end as u64);
+}
+
+#[test]
+fn encrypted_headers() {
+    let data = fs::read("tests/fixtures/rar50/encrypted_headers.rar").unwrap();
+
+    let mut parser = rar50::Parser::with_password(Signature::Rar50.size(), "password");
+    let events = feed(&mut parser, &data[8..], 100);
+    assert!(matches!(events.last(), Some(Event::End)));
+
+    let mut parser = rar50::Parser::new(Signature::Rar50.size());
+    let events = feed(&mut parser, &data[8..], 100);
+    assert!(matches!(
+        events.as_slice(),
+        [Event::Block(_), Event::Error(Error::PasswordRequired)]
+    ));
+}
+
+#[test]
+fn truncated_stream() {
+    let data = fs::read("tests/fixtures/rar50/links.rar").unwrap();
+
+    let mut parser = rar50::Parser::new(Signature::Rar50.size());
+    let events = feed(&mut parser, &data[8..data.len() - 4], 64);
+    let Some(Event::Error(error)) = events.last() else {
+        panic!("expected an error, got {:?}", events.last());
+    };
+    assert!(matches!(error.root(), Error::UnexpectedEof));
+    assert!(parser.feed(&data[data.len() - 4..]).is_empty());
+}
@@ -0,0 +1,159 @@
+use std::{fs, io};
+
+use rawrxd::{
+    rar50::{self, Block, BlockKind, ParseOptions, QuickOpenCache, ServiceBlockKind},
+    BlockSize, Signature, Warning, WarningKind,
+};
+
+use super::block_iterator;
+
+fn blocks<R: io::Read + io::Seek>(reader: R, quick_open: bool) -> Vec<Block> {
+    let options = ParseOptions {
+        quick_open,
+        ..Default::default()
+    };
+    rar50::BlockIterator::with_options(reader, Signature::Rar50.size(), options)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+/// Offset, sizes and name of each block.
+fn listing(blocks: &[Block]) -> Vec<(u64, u64, u64, Option<String>)> {
+    blocks
+        .iter()
+        .map(|block| {
+            let name = match &block.kind {
+                BlockKind::File(file) => Some(file.name.clone().unwrap()),
+                _ => None,
+            };
+            (block.offset(), block.header_size(), block.data_size(), name)
+        })
+        .collect()
+}
+
+#[test]
+fn parse_cache() {
+    let path = "tests/fixtures/rar50/quick_open.rar";
+    let blocks = blocks(io::BufReader::new(fs::File::open(path).unwrap()), false);
+    let quick_open = blocks
+        .iter()
+        .find(|block| {
+            matches!(
+                &block.kind,
+                BlockKind::Service(rar50::ServiceBlock {
+                    kind: ServiceBlockKind::QuickOpen(_),
+                    ..
+                })
+            )
+        })
+        .unwrap();
+
+    let mut reader = io::BufReader::new(fs::File::open(path).unwrap());
+    let cache = QuickOpenCache::read(&mut reader, quick_open).unwrap();
+
+    let offsets: Vec<_> = cache.headers.iter().map(|cached| cached.offset).collect();
+    assert_eq!(offsets, [24, 82, 295, 342]);
+
+    let cached: Vec<_> = cache
+        .headers
+        .iter()
+        .map(|cached| cached.block(&ParseOptions::default()).unwrap())
+        .collect();
+    assert_eq!(listing(&cached), listing(&blocks[1..5]));
+}
+
+#[test]
+fn corrupt_cache() {
+    let mut data = fs::read("tests/fixtures/rar50/quick_open.rar").unwrap();
+    let blocks = blocks(io::Cursor::new(&data), false);
+    let quick_open = &blocks[5];
+
+    // Flip a byte of the first cached header.
+    let start = (quick_open.offset() + quick_open.header_size()) as usize;
+    data[start + 10] ^= 0xff;
+
+    let err = QuickOpenCache::read(&mut io::Cursor::new(&data), quick_open).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // The archive is read as usual when the cache is corrupt.
+    let listed = listing(&self::blocks(io::Cursor::new(&data), true));
+    assert_eq!(listed, listing(&blocks));
+}
+
+#[test]
+fn list_from_cache() {
+    for file_name in ["quick_open.rar", "recovery_record_and_quick_open.rar"] {
+        let expected: Vec<_> = block_iterator(file_name).map(Result::unwrap).collect();
+
+        let reader = io::BufReader::new(
+            fs::File::open(format!("tests/fixtures/rar50/{file_name}")).unwrap(),
+        );
+        assert_eq!(listing(&blocks(reader, true)), listing(&expected));
+    }
+}
+
+#[test]
+fn headers_are_read_from_cache() {
+    let mut data = fs::read("tests/fixtures/rar50/quick_open.rar").unwrap();
+
+    // Only change the name in the header of the file, not in its cached copy.
+    let offset = data
+        .windows(9)
+        .position(|window| window == b"hello.txt")
+        .unwrap();
+    data[offset] = b'j';
+
+    let name = |blocks: Vec<Block>| match &blocks[1].kind {
+        BlockKind::File(file) => file.name.clone().unwrap(),
+        _ => panic!("not a file block"),
+    };
+
+    assert_eq!(name(blocks(io::Cursor::new(&data), false)), "jello.txt");
+    assert_eq!(name(blocks(io::Cursor::new(&data), true)), "hello.txt");
+}
+
+#[test]
+fn cached_headers_are_compared_when_checking() {
+    let mut data = fs::read("tests/fixtures/rar50/quick_open.rar").unwrap();
+
+    let offset = data
+        .windows(9)
+        .position(|window| window == b"hello.txt")
+        .unwrap();
+    data[offset] = b'j';
+
+    let options = ParseOptions {
+        quick_open: true,
+        round_trip: true,
+        ..Default::default()
+    };
+    let mut iter = rar50::BlockIterator::with_options(
+        io::Cursor::new(&data),
+        Signature::Rar50.size(),
+        options,
+    )
+    .unwrap();
+    let blocks: Vec<_> = iter.by_ref().map(Result::unwrap).collect();
+
+    // The header in the archive is the one returned.
+    match &blocks[1].kind {
+        BlockKind::File(file) => assert_eq!(file.name.as_deref(), Ok("jello.txt")),
+        _ => panic!("not a file block"),
+    }
+
+    let mismatches: Vec<_> = iter
+        .take_warnings()
+        .into_iter()
+        .filter(|warning| matches!(warning.kind, WarningKind::QuickOpenMismatch { .. }))
+        .collect();
+    assert_eq!(
+        mismatches,
+        [Warning {
+            offset: blocks[1].offset(),
+            kind: WarningKind::QuickOpenMismatch {
+                position: offset as u64,
+            },
+        }]
+    );
+}
@@ -0,0 +1,78 @@
+use std::{fs, io};
+
+use rawrxd::{
+    compat::{Archive, VerifyStatus},
+    rar50::{Block, BlockKind, CompressionAlgorithm, CompressionMethod, FileBlock},
+};
+
+use super::block_iterator;
+
+fn files() -> Vec<FileBlock> {
+    block_iterator("rar7_dictionary_sizes.rar")
+        .filter_map(|block| match block.unwrap() {
+            Block {
+                kind: BlockKind::File(file),
+                ..
+            } => Some(file),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Synthetic archive with the compression info of files compressed by RAR 7, whose data
+/// is coded as literals in a single block.
+///
+/// RAR 7 only uses the Pack7 algorithm for dictionaries larger than 4 GiB, and reduces
+/// the dictionary to fit files smaller than half of it, so a real archive would need
+/// gigabytes of input and of memory to create. The `large_dictionary` archive generated
+/// by RAR 7 in `tests/fixtures/generated` shows the reduction.
+#[test]
+fn rar7_dictionary_sizes() {
+    let files = files();
+
+    let dict_64g = &files[0].compression_info;
+    assert_eq!(dict_64g.version(), CompressionAlgorithm::Pack7);
+    assert_eq!(dict_64g.algorithm(), CompressionAlgorithm::Pack7);
+    assert_eq!(dict_64g.method(), CompressionMethod::Method3);
+    assert_eq!(dict_64g.min_dictionary_size(), Ok(64 * 1024 * 1024 * 1024));
+
+    let dict_fraction = &files[1].compression_info;
+    assert_eq!(dict_fraction.dictionary_size_fraction(), 16);
+    assert_eq!(dict_fraction.min_dictionary_size(), Ok(6 * 1024 * 1024));
+
+    let rar5_compatible = &files[2].compression_info;
+    assert!(rar5_compatible.is_rar5_compatible());
+    assert_eq!(rar5_compatible.version(), CompressionAlgorithm::Pack7);
+    assert_eq!(rar5_compatible.algorithm(), CompressionAlgorithm::Pack5);
+    assert_eq!(rar5_compatible.min_dictionary_size(), Ok(512 * 1024));
+
+    let too_large = &files[3].compression_info;
+    assert_eq!(
+        too_large.min_dictionary_size(),
+        Err(128 * 1024 * 1024 * 1024)
+    );
+
+    assert!(files.iter().all(|f| f.compression_info.unknown_bits() == 0));
+}
+
+#[test]
+fn rar7_dictionary_sizes_decode() {
+    let reader = io::BufReader::new(
+        fs::File::open("tests/fixtures/rar50/rar7_dictionary_sizes.rar").unwrap(),
+    );
+    let report = Archive::new(reader).unwrap().test().unwrap();
+    assert!(report.is_ok());
+
+    let statuses: Vec<_> = report.entries.iter().map(|r| &r.status).collect();
+    assert_eq!(
+        statuses,
+        [
+            &VerifyStatus::Ok,
+            &VerifyStatus::Ok,
+            &VerifyStatus::Ok,
+            // The dictionary is larger than the largest one supported by RAR 7.
+            &VerifyStatus::NotExtractable,
+        ]
+    );
+    assert!(report.entries[..3].iter().all(|r| r.unpacked == 970));
+}
@@ -0,0 +1,147 @@
+use std::{fs, io};
+
+use rawrxd::{
+    rar50::{Block, BlockKind, DamagedRange, RecoveryRecord, ServiceBlockKind},
+    BlockSize,
+};
+
+use super::block_iterator;
+
+fn recovery_record_block(file_name: &str) -> Block {
+    block_iterator(file_name)
+        .map(Result::unwrap)
+        .find(|block| {
+            matches!(
+                &block.kind,
+                BlockKind::Service(service) if matches!(service.kind, ServiceBlockKind::RecoveryRecord(_))
+            )
+        })
+        .unwrap()
+}
+
+#[test]
+fn parse_recovery_record() {
+    let block = recovery_record_block("recovery_record.rar");
+    let mut reader = fs::File::open("tests/fixtures/rar50/recovery_record.rar").unwrap();
+    let rr = RecoveryRecord::read(&mut reader, &block).unwrap();
+
+    assert_eq!(rr.chunk_size, 160);
+    assert_eq!(rr.header_size, 80);
+    assert_eq!(rr.protected_size, block.offset());
+    assert_eq!(rr.shard_size, 80);
+    assert_eq!(rr.shard_crcs.len(), 1);
+
+    // With a single data shard the recovery shard is a copy of it.
+    let archive = fs::read("tests/fixtures/rar50/recovery_record.rar").unwrap();
+    let [Some(recovery_shard)] = &rr.recovery_shards[..] else {
+        panic!("{:?}", rr.recovery_shards);
+    };
+    assert_eq!(recovery_shard[..79], archive[..79]);
+}
+
+fn validate_damaged(offsets: &[usize]) -> Vec<DamagedRange> {
+    let block = recovery_record_block("recovery_shards.rar");
+    let mut archive = fs::read("tests/fixtures/rar50/recovery_shards.rar").unwrap();
+    for &offset in offsets {
+        archive[offset] ^= 0x40;
+    }
+
+    let mut reader = io::Cursor::new(archive);
+    RecoveryRecord::read(&mut reader, &block)
+        .unwrap()
+        .validate(&mut reader, 0)
+        .unwrap()
+}
+
+#[test]
+fn validate_intact_archive() {
+    let block = recovery_record_block("recovery_shards.rar");
+    let mut reader = fs::File::open("tests/fixtures/rar50/recovery_shards.rar").unwrap();
+    let rr = RecoveryRecord::read(&mut reader, &block).unwrap();
+
+    assert_eq!(rr.protected_size, block.offset());
+    assert_eq!(rr.shard_size, 1020);
+    assert_eq!(rr.shard_crcs.len(), 8);
+    assert_eq!(rr.recovery_shards.len(), 2);
+    assert!(rr.recovery_shards.iter().all(Option::is_some));
+
+    assert_eq!(rr.validate(&mut reader, 0).unwrap(), []);
+}
+
+#[test]
+fn validate_damaged_shards() {
+    // `rar r` reports the same ranges and recovers both of them.
+    assert_eq!(
+        validate_damaged(&[100, 3000]),
+        [
+            DamagedRange {
+                range: 0..1020,
+                recoverable: true,
+            },
+            DamagedRange {
+                range: 2040..3060,
+                recoverable: true,
+            },
+        ]
+    );
+
+    // There are only two recovery shards.
+    let damaged = validate_damaged(&[100, 3000, 5000]);
+    assert_eq!(damaged.len(), 3);
+    assert!(damaged.iter().all(|damaged| !damaged.recoverable));
+}
+
+#[test]
+fn validate_damaged_last_shard() {
+    let block = recovery_record_block("recovery_shards.rar");
+    let offset = block.offset() as usize;
+    assert_eq!(
+        validate_damaged(&[offset - 1]),
+        [DamagedRange {
+            range: 7140..offset as u64,
+            recoverable: true,
+        }]
+    );
+}
+
+#[test]
+fn validate_damaged_chunk() {
+    let block = recovery_record_block("recovery_shards.rar");
+    let data_offset = block.offset() + block.header_size();
+    let damaged = validate_damaged(&[data_offset as usize + 1156 + 500, 100]);
+    assert_eq!(
+        damaged,
+        [
+            DamagedRange {
+                range: 0..1020,
+                recoverable: true,
+            },
+            DamagedRange {
+                range: data_offset + 1156..data_offset + 2 * 1156,
+                recoverable: true,
+            },
+        ]
+    );
+
+    // The damaged chunk leaves a single recovery shard.
+    let damaged = validate_damaged(&[data_offset as usize + 1156 + 500, 100, 3000]);
+    assert!(damaged.iter().all(|damaged| !damaged.recoverable));
+}
+
+#[test]
+fn reject_missing_marker() {
+    let block = recovery_record_block("recovery_record.rar");
+    let err = RecoveryRecord::parse(&block, &[0; 160]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn reject_non_recovery_record_block() {
+    let block = block_iterator("recovery_record.rar")
+        .next()
+        .unwrap()
+        .unwrap();
+    let mut reader = fs::File::open("tests/fixtures/rar50/recovery_record.rar").unwrap();
+    let err = RecoveryRecord::read(&mut reader, &block).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+}
@@ -0,0 +1,71 @@
+use std::{fs, io};
+
+use rawrxd::{
+    rar50::{self, ParseOptions},
+    Signature, Warning, WarningKind,
+};
+use rstest::rstest;
+
+fn round_trip_warnings(file_name: &str) -> Vec<Warning> {
+    let reader =
+        io::BufReader::new(fs::File::open(format!("tests/fixtures/rar50/{file_name}")).unwrap());
+    let options = ParseOptions {
+        round_trip: true,
+        ..Default::default()
+    };
+    let mut iter =
+        rar50::BlockIterator::with_options(reader, Signature::Rar50.size(), options).unwrap();
+    while let Some(Ok(_)) = iter.next() {}
+
+    iter.take_warnings()
+        .into_iter()
+        .filter(|warning| matches!(warning.kind, WarningKind::RoundTripMismatch { .. }))
+        .collect()
+}
+
+#[rstest]
+#[case("unix_owner.rar")]
+#[case("quick_open.rar")]
+#[case("recovery_record_and_quick_open.rar")]
+#[case("links.rar")]
+#[case("blake2sp_hash.rar")]
+#[case("encrypted_files.rar")]
+#[case("alternate_data_streams.rar")]
+#[case("ntfs_acl.rar")]
+#[case("multivolume.part1.rar")]
+#[case("unknown_flags.rar")]
+fn headers_round_trip(#[case] file_name: &str) {
+    assert_eq!(round_trip_warnings(file_name), vec![]);
+}
+
+#[test]
+fn trailing_header_bytes_mismatch() {
+    assert_eq!(
+        round_trip_warnings("header_overrun.rar"),
+        vec![Warning {
+            offset: 16,
+            kind: WarningKind::RoundTripMismatch { position: 41 },
+        }]
+    );
+}
+
+#[test]
+fn skipped_records_are_not_checked() {
+    let reader = io::BufReader::new(
+        fs::File::open("tests/fixtures/rar50/recovery_record_and_quick_open.rar").unwrap(),
+    );
+    let options = ParseOptions {
+        round_trip: true,
+        skip_quick_open: true,
+        skip_recovery_record: true,
+        ..Default::default()
+    };
+    let mut iter =
+        rar50::BlockIterator::with_options(reader, Signature::Rar50.size(), options).unwrap();
+    while let Some(Ok(_)) = iter.next() {}
+
+    assert!(!iter
+        .take_warnings()
+        .iter()
+        .any(|warning| matches!(warning.kind, WarningKind::RoundTripMismatch { .. })));
+}
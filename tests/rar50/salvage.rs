@@ -0,0 +1,102 @@
+use std::{fs, io};
+
+use rawrxd::{
+    rar50::{self, BlockKind, ParseOptions},
+    BlockSize, Error, Signature, WarningKind,
+};
+
+fn salvage(data: &[u8]) -> rar50::BlockIterator<io::Cursor<&[u8]>> {
+    let options = ParseOptions {
+        salvage: true,
+        ..Default::default()
+    };
+    rar50::BlockIterator::with_options(io::Cursor::new(data), Signature::Rar50.size(), options)
+        .unwrap()
+}
+
+fn offsets(data: &[u8]) -> Vec<u64> {
+    rar50::BlockIterator::new(io::Cursor::new(data), Signature::Rar50.size())
+        .unwrap()
+        .map(|block| block.unwrap().offset())
+        .collect()
+}
+
+#[test]
+fn skip_block_with_corrupt_checksum() {
+    let mut data = fs::read("tests/fixtures/rar50/links.rar").unwrap();
+    let offsets = offsets(&data);
+    let corrupt = offsets[2];
+    data[corrupt as usize] ^= 0xff;
+
+    let mut iter = salvage(&data);
+    let salvaged = iter
+        .by_ref()
+        .map(|block| block.unwrap().offset())
+        .collect::<Vec<_>>();
+
+    let expected = offsets
+        .iter()
+        .copied()
+        .filter(|&offset| offset != corrupt)
+        .collect::<Vec<_>>();
+    assert_eq!(salvaged, expected);
+    assert_eq!(
+        iter.warnings()[0].kind,
+        WarningKind::CorruptDataSkipped {
+            range: corrupt..offsets[3]
+        }
+    );
+}
+
+#[test]
+fn resync_after_unparsable_header() {
+    let mut data = fs::read("tests/fixtures/rar50/links.rar").unwrap();
+    let offsets = offsets(&data);
+    let corrupt = offsets[1] as usize;
+    // Make the header size larger than the limit.
+    data[corrupt + 4..corrupt + 7].copy_from_slice(&[0xff, 0xff, 0x7f]);
+
+    let blocks = salvage(&data).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(blocks[0].offset(), offsets[0]);
+    assert_eq!(blocks[1].offset(), offsets[2]);
+    assert!(matches!(
+        blocks.last().unwrap().kind,
+        BlockKind::EndArchive(_)
+    ));
+}
+
+#[test]
+fn error_without_salvage() {
+    let mut data = fs::read("tests/fixtures/rar50/links.rar").unwrap();
+    let corrupt = offsets(&data)[1] as usize;
+    data[corrupt + 4..corrupt + 7].copy_from_slice(&[0xff, 0xff, 0x7f]);
+
+    let err = rar50::BlockIterator::new(io::Cursor::new(&data[..]), Signature::Rar50.size())
+        .unwrap()
+        .find_map(Result::err);
+    assert!(matches!(
+        err.as_ref().map(Error::root),
+        Some(Error::LimitExceeded { .. })
+    ));
+}
+
+#[test]
+fn no_header_after_corruption() {
+    let mut data = fs::read("tests/fixtures/rar50/links.rar").unwrap();
+    let offsets = offsets(&data);
+    let corrupt = *offsets.last().unwrap() as usize;
+    data[corrupt] ^= 0xff;
+
+    let mut iter = salvage(&data);
+    let results = iter.by_ref().collect::<Vec<_>>();
+    assert_eq!(results.len(), offsets.len());
+    assert!(matches!(
+        results.last().unwrap().as_ref().map_err(Error::root),
+        Err(Error::CorruptHeader)
+    ));
+    assert_eq!(
+        results.last().unwrap().as_ref().unwrap_err().offset(),
+        Some(corrupt as u64)
+    );
+    assert!(iter.next().is_none());
+}
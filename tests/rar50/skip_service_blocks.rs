@@ -0,0 +1,78 @@
+use std::{fs, io};
+
+use rawrxd::{
+    rar50::{self, Block, BlockKind, ParseOptions, ServiceBlockKind},
+    BlockSize, Signature,
+};
+
+use super::block_iterator;
+
+fn recovery_record(
+    mut iter: impl Iterator<Item = rawrxd::RarResult<Block>>,
+) -> (u64, rar50::RecoveryRecordServiceBlock) {
+    iter.find_map(|block| match block {
+        Ok(Block {
+            offset,
+            kind:
+                BlockKind::Service(rar50::ServiceBlock {
+                    kind: ServiceBlockKind::RecoveryRecord(rr),
+                    ..
+                }),
+            ..
+        }) => Some((offset, rr)),
+        _ => None,
+    })
+    .unwrap()
+}
+
+#[test]
+fn skip_recovery_record() {
+    let (offset, rr) = recovery_record(block_iterator("recovery_record.rar"));
+    assert!(rr.info.is_some());
+
+    let reader =
+        io::BufReader::new(fs::File::open("tests/fixtures/rar50/recovery_record.rar").unwrap());
+    let options = ParseOptions {
+        skip_recovery_record: true,
+        ..Default::default()
+    };
+    let iter =
+        rar50::BlockIterator::with_options(reader, Signature::Rar50.size(), options).unwrap();
+    let (skipped_offset, skipped_rr) = recovery_record(iter);
+
+    assert_eq!(skipped_offset, offset);
+    assert!(skipped_rr.info.is_none());
+}
+
+fn quick_open_block(options: ParseOptions) -> Block {
+    let reader = io::BufReader::new(
+        fs::File::open("tests/fixtures/rar50/recovery_record_and_quick_open.rar").unwrap(),
+    );
+    rar50::BlockIterator::with_options(reader, Signature::Rar50.size(), options)
+        .unwrap()
+        .map(Result::unwrap)
+        .find(|block| {
+            matches!(
+                &block.kind,
+                BlockKind::Service(rar50::ServiceBlock {
+                    kind: ServiceBlockKind::QuickOpen(_),
+                    ..
+                })
+            )
+        })
+        .unwrap()
+}
+
+#[test]
+fn skip_quick_open() {
+    let block = quick_open_block(ParseOptions::default());
+    let skipped = quick_open_block(ParseOptions {
+        skip_quick_open: true,
+        ..Default::default()
+    });
+
+    assert_eq!(skipped.offset(), block.offset());
+    assert_eq!(skipped.header_size, block.header_size);
+    assert_eq!(skipped.extra_area_size, block.extra_area_size);
+    assert_eq!(skipped.data_size, block.data_size);
+}
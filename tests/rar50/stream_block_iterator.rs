@@ -0,0 +1,75 @@
+use std::{fs, io};
+
+use rawrxd::{
+    rar50::{self, ParseOptions},
+    Error, Signature,
+};
+
+use super::block_iterator;
+
+/// Reader which returns at most `chunk_size` bytes per read and can't be seeked.
+struct Pipe<'a> {
+    data: &'a [u8],
+    chunk_size: usize,
+}
+
+impl io::Read for Pipe<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.chunk_size).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn same_blocks_as_block_iterator() {
+    for file_name in ["quick_open.rar", "recovery_record.rar", "unix_owner.rar"] {
+        let expected: Vec<_> = block_iterator(file_name)
+            .map(|block| format!("{block:?}"))
+            .collect();
+        let data = fs::read(format!("tests/fixtures/rar50/{file_name}")).unwrap();
+
+        for chunk_size in [1, 100, 0x10000] {
+            let pipe = Pipe {
+                data: &data[Signature::Rar50.size() as usize..],
+                chunk_size,
+            };
+            let options = ParseOptions {
+                cross_check: true,
+                ..Default::default()
+            };
+            let mut iter =
+                rar50::StreamBlockIterator::with_options(pipe, Signature::Rar50.size(), options);
+            let blocks: Vec<_> = iter.by_ref().map(|block| format!("{block:?}")).collect();
+
+            assert_eq!(blocks, expected, "{file_name} in chunks of {chunk_size}");
+            assert!(iter.warnings().is_empty());
+        }
+    }
+}
+
+#[test]
+fn encrypted_headers() {
+    let data = fs::read("tests/fixtures/rar50/encrypted_headers.rar").unwrap();
+
+    let iter = rar50::StreamBlockIterator::with_password(&data[8..], 8, "password");
+    assert_eq!(iter.map(Result::unwrap).count(), 5);
+
+    let mut iter = rar50::StreamBlockIterator::new(&data[8..], 8);
+    assert!(iter.next().unwrap().is_ok());
+    assert!(matches!(iter.next(), Some(Err(Error::PasswordRequired))));
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn truncated_stream() {
+    let data = fs::read("tests/fixtures/rar50/unix_owner.rar").unwrap();
+
+    let mut iter = rar50::StreamBlockIterator::new(&data[8..data.len() - 3], 8);
+    assert!(matches!(
+        iter.by_ref().last().unwrap().map_err(Error::into_root),
+        Err(Error::UnexpectedEof)
+    ));
+    assert!(iter.next().is_none());
+}
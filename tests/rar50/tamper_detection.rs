@@ -0,0 +1,233 @@
+use std::{fs, io};
+
+use rawrxd::{rar50, Severity, Signature, Warning, WarningKind};
+
+use super::block_iterator;
+
+#[test]
+fn header_overrun() {
+    let mut iter = block_iterator("header_overrun.rar");
+    for block in iter.by_ref() {
+        block.unwrap();
+    }
+
+    let warnings = iter.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity(), Severity::High);
+    assert!(matches!(
+        warnings[0].kind,
+        WarningKind::HeaderOverrun { declared_end, parsed_end } if parsed_end == declared_end + 8
+    ));
+    assert!(iter.warnings().is_empty());
+}
+
+#[test]
+fn locator_inside_block() {
+    let mut iter = block_iterator("locator_inside_block.rar");
+    for block in iter.by_ref() {
+        block.unwrap();
+    }
+
+    assert_eq!(
+        iter.warnings()
+            .iter()
+            .map(|warning| &warning.kind)
+            .collect::<Vec<_>>(),
+        vec![&WarningKind::OffsetInsideBlock {
+            target: 8 + 43,
+            block: 21..62
+        }]
+    );
+}
+
+#[test]
+fn well_formed_archives_have_no_warnings() {
+    for file_name in [
+        "recovery_record.rar",
+        "recovery_record_and_quick_open.rar",
+        "unix_high_ascii_filename.rar",
+        "rar7_dictionary_sizes.rar",
+        "links.rar",
+    ] {
+        let mut iter = block_iterator(file_name);
+        for block in iter.by_ref() {
+            block.unwrap();
+        }
+        assert_eq!(iter.warnings(), &[], "{file_name}");
+    }
+}
+
+#[test]
+fn unknown_flags() {
+    let mut iter = block_iterator("unknown_flags.rar");
+    for block in iter.by_ref() {
+        block.unwrap();
+    }
+
+    let warnings = iter.take_warnings();
+    assert_eq!(
+        warnings,
+        [Warning {
+            offset: 23,
+            kind: WarningKind::UnknownFlags {
+                flags: "FileBlockFlags",
+                bits: 0x40
+            }
+        }]
+    );
+    assert_eq!(warnings[0].severity(), Severity::Low);
+}
+
+#[test]
+fn unknown_header_bytes() {
+    let mut iter = block_iterator("ambiguous_headers.rar");
+    for block in iter.by_ref() {
+        block.unwrap();
+    }
+
+    assert_eq!(
+        iter.warnings(),
+        [Warning {
+            offset: 16,
+            kind: WarningKind::UnknownHeaderBytes {
+                parsed_end: 41,
+                declared_end: 45
+            }
+        }]
+    );
+}
+
+/// Warnings about an archive made of a single block with the given header fields after
+/// the header size.
+fn single_block_warnings(fields: &[u8]) -> Vec<Warning> {
+    let mut archive = Signature::Rar50.signature().to_vec();
+    archive.extend_from_slice(&[0; 4]);
+    archive.push(fields.len() as u8);
+    archive.extend_from_slice(fields);
+
+    let mut iter =
+        rar50::BlockIterator::new(io::Cursor::new(archive), Signature::Rar50.size()).unwrap();
+    for block in iter.by_ref() {
+        block.unwrap();
+    }
+    iter.take_warnings()
+}
+
+#[test]
+fn metadata_time_flags_without_creation_time() {
+    let fields = [
+        0x01, // Block type
+        0x01, // Block flags
+        0x03, // Extra area size
+        0x00, // Archive flags
+        0x02, 0x02, 0x0c, // Metadata record with Unix time in nanoseconds
+    ];
+    let warnings = single_block_warnings(&fields);
+    assert_eq!(
+        warnings,
+        [Warning {
+            offset: 8,
+            kind: WarningKind::IgnoredFlags {
+                flags: "MetadataRecordFlags",
+                bits: 0x0c
+            }
+        }]
+    );
+    assert_eq!(warnings[0].severity(), Severity::Low);
+}
+
+#[test]
+fn file_time_nanoseconds_without_unix_time() {
+    let fields = [
+        0x02, // Block type
+        0x01, // Block flags
+        0x03, // Extra area size
+        0x00, // File flags
+        0x00, // Unpacked size
+        0x00, // Attributes
+        0x00, // Compression info
+        0x00, // Host OS
+        0x01, b'a', // Name
+        0x02, 0x03, 0x10, // File time record with nanoseconds
+    ];
+    assert_eq!(
+        single_block_warnings(&fields),
+        [Warning {
+            offset: 8,
+            kind: WarningKind::IgnoredFlags {
+                flags: "FileTimeRecordFlags",
+                bits: 0x10
+            }
+        }]
+    );
+}
+
+/// Warnings about the archive in the fixture `file_name` read with the QuickOpen cache.
+fn quick_open_warnings(file_name: &str) -> Vec<Warning> {
+    let reader =
+        io::BufReader::new(fs::File::open(format!("tests/fixtures/rar50/{file_name}")).unwrap());
+    let options = rar50::ParseOptions {
+        quick_open: true,
+        ..Default::default()
+    };
+    let mut iter =
+        rar50::BlockIterator::with_options(reader, Signature::Rar50.size(), options).unwrap();
+    for block in iter.by_ref() {
+        block.unwrap();
+    }
+    iter.take_warnings()
+}
+
+#[test]
+fn header_smuggled_in_quick_open_cache() {
+    for file_name in ["quick_open.rar", "recovery_record_and_quick_open.rar"] {
+        assert_eq!(quick_open_warnings(file_name), [], "{file_name}");
+    }
+
+    // The cache lists a header stored in the data of the first file.
+    let warnings = quick_open_warnings("smuggled_header.rar");
+    assert_eq!(
+        warnings,
+        [
+            Warning {
+                offset: 56,
+                kind: WarningKind::OverlappingBlock {
+                    range: 56..92,
+                    previous: 21..106
+                }
+            },
+            Warning {
+                offset: 21,
+                kind: WarningKind::OffsetInsideBlock {
+                    target: 56,
+                    block: 21..106
+                }
+            },
+        ]
+    );
+    assert!(warnings.iter().all(|w| w.severity() == Severity::High));
+}
+
+#[test]
+fn block_read_again_after_resuming() {
+    let mut iter = block_iterator("smuggled_header.rar");
+    for block in iter.by_ref() {
+        block.unwrap();
+    }
+    assert_eq!(iter.warnings(), &[]);
+
+    // Resume at the header stored in the data of the first file.
+    iter.resume_at(56, 204);
+    iter.next().unwrap().unwrap();
+
+    assert_eq!(
+        iter.take_warnings(),
+        [Warning {
+            offset: 56,
+            kind: WarningKind::OverlappingBlock {
+                range: 56..92,
+                previous: 8..204
+            }
+        }]
+    );
+}
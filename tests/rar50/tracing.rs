@@ -0,0 +1,73 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+use super::block_iterator;
+
+/// Subscriber which records the fields of every event as `name=value` strings.
+#[derive(Default, Clone)]
+struct Recorder {
+    events: Arc<Mutex<Vec<Vec<String>>>>,
+}
+
+struct FieldVisitor<'a>(&'a mut Vec<String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.push(format!("{}={value:?}", field.name()));
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = vec![];
+        event.record(&mut FieldVisitor(&mut fields));
+        self.events.lock().unwrap().push(fields);
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+#[test]
+fn block_events() {
+    let recorder = Recorder::default();
+    let blocks = tracing::subscriber::with_default(recorder.clone(), || {
+        block_iterator("unix_owner.rar")
+            .map(|block| block.unwrap().offset)
+            .collect::<Vec<_>>()
+    });
+
+    let events = recorder.events.lock().unwrap();
+    let read_blocks: Vec<_> = events
+        .iter()
+        .filter(|fields| fields[0] == r#"message=read block"#)
+        .collect();
+    assert_eq!(read_blocks.len(), blocks.len());
+    for (fields, offset) in read_blocks.iter().zip(&blocks) {
+        assert_eq!(fields[1], format!("offset={offset}"));
+    }
+    assert_eq!(read_blocks[0][2], r#"kind="Main""#);
+    assert!(events
+        .iter()
+        .any(|fields| fields[0] == "message=read record"));
+}
@@ -0,0 +1,51 @@
+use std::io;
+
+use rawrxd::rar50::{Block, BlockKind, ParseOptions};
+
+#[test]
+fn unknown_block_keeps_fields_and_records() {
+    // CRC32, header size, type, flags with the extra area flag, extra area size, two bytes
+    // of fields and a record of type 7.
+    let header = b"\0\0\0\0\x09\x10\x01\x04\xaa\xbb\x03\x07\x01\x02";
+
+    let block = Block::read(&mut io::Cursor::new(header)).unwrap();
+    let BlockKind::Unknown(unknown) = block.kind else {
+        panic!("expected an unknown block, got {:?}", block.kind);
+    };
+
+    assert_eq!(unknown.tag, 0x10);
+    assert_eq!(unknown.fields, [0xaa, 0xbb]);
+    assert_eq!(unknown.records.len(), 1);
+    assert_eq!(unknown.records[0].tag, 0x07);
+    assert_eq!(unknown.records[0].data, [0x01, 0x02]);
+}
+
+#[test]
+fn unknown_block_without_extra_area() {
+    let header = b"\0\0\0\0\x04\x10\x00\xaa\xbb";
+
+    let block = Block::read(&mut io::Cursor::new(header)).unwrap();
+    let BlockKind::Unknown(unknown) = block.kind else {
+        panic!("expected an unknown block, got {:?}", block.kind);
+    };
+
+    assert_eq!(unknown.fields, [0xaa, 0xbb]);
+    assert!(unknown.records.is_empty());
+}
+
+#[test]
+fn skip_unknown_record_data() {
+    let header = b"\0\0\0\0\x09\x10\x01\x04\xaa\xbb\x03\x07\x01\x02";
+    let options = ParseOptions {
+        skip_unknown_record_data: true,
+        ..Default::default()
+    };
+
+    let block = Block::read_with_options(&mut io::Cursor::new(header), &options).unwrap();
+    let BlockKind::Unknown(unknown) = block.kind else {
+        panic!("expected an unknown block, got {:?}", block.kind);
+    };
+
+    assert_eq!(unknown.records[0].tag, 0x07);
+    assert!(unknown.records[0].data.is_empty());
+}
@@ -0,0 +1,302 @@
+//! Development tasks for rawrxd, run with `cargo xtask <task>`.
+//!
+//! The `fixtures` task generates a matrix of test archives by driving a locally-installed
+//! `rar` binary, and writes a manifest of their contents which is checked by the tests in
+//! `tests/generated`. RARLAB doesn't allow redistributing `rar`, so it must be installed
+//! separately. RAR 7 can't create RAR 1.5 archives anymore, so those are only generated
+//! when the binary supports the `-ma4` switch, and archives with dictionaries larger than
+//! 4 GiB are only generated by RAR 7. The names of the `unicode_names` fixture are
+//! converted from the locale by `rar`, so it must run in a UTF-8 locale.
+
+use std::{
+    env, fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+/// Password used for the encrypted fixtures.
+const PASSWORD: &str = "rawrxd";
+
+/// Size of each volume of the multi-volume fixtures.
+const VOLUME_SIZE: &str = "20k";
+
+const USAGE: &str = "\
+Usage: cargo xtask fixtures [--rar <path>] [--out <dir>]
+
+Generate the fixture matrix by running a locally-installed rar binary.
+
+Options:
+  --rar <path>  rar binary to run (default: rar)
+  --out <dir>   output directory (default: tests/fixtures/generated)";
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Rar15,
+    Rar50,
+}
+
+impl Format {
+    const ALL: [Format; 2] = [Format::Rar15, Format::Rar50];
+
+    fn switch(self) -> &'static str {
+        match self {
+            Format::Rar15 => "-ma4",
+            Format::Rar50 => "-ma5",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Rar15 => "rar15",
+            Format::Rar50 => "rar50",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Feature {
+    Plain,
+    Solid,
+    Encrypted,
+    EncryptedHeaders,
+    Multivolume,
+    UnicodeNames,
+    LargeDictionary,
+}
+
+impl Feature {
+    const ALL: [Feature; 7] = [
+        Feature::Plain,
+        Feature::Solid,
+        Feature::Encrypted,
+        Feature::EncryptedHeaders,
+        Feature::Multivolume,
+        Feature::UnicodeNames,
+        Feature::LargeDictionary,
+    ];
+
+    fn switches(self) -> Vec<String> {
+        match self {
+            Feature::Plain | Feature::UnicodeNames => vec![],
+            Feature::Solid => vec!["-s".into()],
+            Feature::Encrypted => vec![format!("-p{PASSWORD}")],
+            Feature::EncryptedHeaders => vec![format!("-hp{PASSWORD}")],
+            Feature::Multivolume => vec![format!("-v{VOLUME_SIZE}")],
+            Feature::LargeDictionary => vec!["-md64g".into()],
+        }
+    }
+
+    fn password(self) -> Option<&'static str> {
+        match self {
+            Feature::Encrypted | Feature::EncryptedHeaders => Some(PASSWORD),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Feature::Plain => "plain",
+            Feature::Solid => "solid",
+            Feature::Encrypted => "encrypted",
+            Feature::EncryptedHeaders => "encrypted_headers",
+            Feature::Multivolume => "multivolume",
+            Feature::UnicodeNames => "unicode_names",
+            Feature::LargeDictionary => "large_dictionary",
+        })
+    }
+}
+
+/// A file added to the fixtures.
+struct Input {
+    /// Path relative to the input directory, with `/` separators.
+    name: &'static str,
+    data: Vec<u8>,
+}
+
+fn inputs(feature: Feature) -> Vec<Input> {
+    let mut inputs = vec![
+        Input {
+            name: "hello.txt",
+            data: b"Hello, world!\r\n".to_vec(),
+        },
+        Input {
+            name: "docs/readme.txt",
+            data: (0..500)
+                .flat_map(|i| {
+                    format!("{i}: The quick brown fox jumps over the lazy dog.\n").into_bytes()
+                })
+                .collect(),
+        },
+        Input {
+            name: "data.bin",
+            data: pseudo_random(0x1_0000),
+        },
+    ];
+
+    if feature == Feature::UnicodeNames {
+        inputs.push(Input {
+            name: "Æble/smørrebrød.txt",
+            data: "Æ ø å\n".repeat(100).into_bytes(),
+        });
+        inputs.push(Input {
+            name: "日本語.txt",
+            data: "こんにちは\n".repeat(100).into_bytes(),
+        });
+    }
+
+    inputs
+}
+
+/// Incompressible but reproducible data.
+fn pseudo_random(len: usize) -> Vec<u8> {
+    let mut state: u32 = 0x12345678;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as u8
+        })
+        .collect()
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn run(command: &mut Command) -> io::Result<bool> {
+    let status = command.status().map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("couldn't run {:?}: {e}", command.get_program()),
+        )
+    })?;
+    Ok(status.success())
+}
+
+/// Create the archive for `format` and `feature` in `out` and return the path of its
+/// first volume relative to `out`, or `None` if `rar` failed.
+fn generate(
+    rar: &Path,
+    out: &Path,
+    format: Format,
+    feature: Feature,
+) -> io::Result<Option<String>> {
+    let input_dir = out.join("input").join(feature.to_string());
+    let inputs = inputs(feature);
+    for input in &inputs {
+        let path = input_dir.join(input.name);
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, &input.data)?;
+    }
+
+    let archive_dir = out.join(format.to_string());
+    fs::create_dir_all(&archive_dir)?;
+    let archive = archive_dir.canonicalize()?.join(format!("{feature}.rar"));
+
+    let mut command = Command::new(rar);
+    command
+        .current_dir(&input_dir)
+        .args(["a", "-cfg-", "-idq", "-r", "-m3", format.switch()])
+        .args(feature.switches())
+        .arg(&archive)
+        .args(inputs.iter().map(|input| input.name));
+
+    if !run(&mut command)? {
+        return Ok(None);
+    }
+
+    // Multi-volume archives are named `<name>.part1.rar`, `<name>.part2.rar`...
+    let first_volume = format!("{feature}.part1.rar");
+    if archive_dir.join(&first_volume).exists() {
+        Ok(Some(format!("{format}/{first_volume}")))
+    } else {
+        Ok(Some(format!("{format}/{feature}.rar")))
+    }
+}
+
+fn fixtures(rar: &Path, out: &Path) -> io::Result<()> {
+    if out.exists() {
+        fs::remove_dir_all(out)?;
+    }
+    fs::create_dir_all(out)?;
+
+    let mut manifest = io::BufWriter::new(fs::File::create(out.join("manifest.txt"))?);
+    writeln!(
+        manifest,
+        "# Generated by `cargo xtask fixtures`, do not edit."
+    )?;
+
+    for format in Format::ALL {
+        for feature in Feature::ALL {
+            let Some(path) = generate(rar, out, format, feature)? else {
+                eprintln!("skipping {format}/{feature}: rar failed");
+                continue;
+            };
+
+            let password = feature.password().unwrap_or("-");
+            writeln!(manifest, "archive\t{path}\t{format}\t{feature}\t{password}")?;
+
+            for input in inputs(feature) {
+                let size = input.data.len();
+                let crc = crc32(&input.data);
+                writeln!(manifest, "entry\t{}\t{size}\t{crc:08x}", input.name)?;
+            }
+
+            eprintln!("generated {path}");
+        }
+    }
+
+    fs::remove_dir_all(out.join("input"))?;
+    manifest.flush()
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut rar = PathBuf::from("rar");
+    let mut out = PathBuf::from("tests/fixtures/generated");
+
+    match args.next().as_deref() {
+        Some("fixtures") => {}
+        _ => {
+            eprintln!("{USAGE}");
+            process::exit(2);
+        }
+    }
+
+    while let Some(arg) = args.next() {
+        let value = args.next();
+        match (arg.as_str(), value) {
+            ("--rar", Some(path)) => rar = path.into(),
+            ("--out", Some(dir)) => out = dir.into(),
+            _ => {
+                eprintln!("{USAGE}");
+                process::exit(2);
+            }
+        }
+    }
+
+    if let Err(e) = fixtures(&rar, &out) {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+}
+
+#[test]
+fn test_crc32() {
+    assert_eq!(crc32(b"Hello, world!\r\n"), 0x6e815da5);
+}